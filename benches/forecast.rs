@@ -0,0 +1,111 @@
+//! Forecast hot path: parsing a full One Call response (48 hourly and 8 daily
+//! entries), turning it into the client response, and serializing that.
+//!
+//! cargo bench --bench forecast
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde_json::{json, Value};
+
+use weathrs::forecast::models::{ForecastResponse, GeoLocation, OneCallResponse};
+
+const START: i64 = 1_700_000_000;
+
+fn condition(hour: i64) -> Value {
+    let (id, main, description) = match hour % 4 {
+        0 => (800, "Clear", "clear sky"),
+        1 => (802, "Clouds", "scattered clouds"),
+        2 => (500, "Rain", "light rain"),
+        _ => (601, "Snow", "snow"),
+    };
+    let icon = if (6..18).contains(&(hour % 24)) {
+        "01d"
+    } else {
+        "01n"
+    };
+    json!([{"id": id, "main": main, "description": description, "icon": icon}])
+}
+
+/// A One Call body the size OWM sends for a full forecast
+fn one_call_body() -> String {
+    let hourly: Vec<Value> = (0..48)
+        .map(|h| {
+            json!({
+                "dt": START + h * 3600, "temp": 12.5, "feels_like": 11.0,
+                "pressure": 1013, "humidity": 70, "dew_point": 7.2, "uvi": 1.5,
+                "clouds": 40, "visibility": 10000, "wind_speed": 4.2, "wind_deg": 210,
+                "wind_gust": 7.9, "pop": 0.35, "rain": {"1h": 0.4},
+                "weather": condition(h)
+            })
+        })
+        .collect();
+    let daily: Vec<Value> = (0..8)
+        .map(|d| {
+            json!({
+                "dt": START + d * 86400, "sunrise": START + d * 86400 - 20000,
+                "sunset": START + d * 86400 + 20000, "moonrise": START + d * 86400,
+                "moonset": 0, "moon_phase": 0.25,
+                "summary": "Expect a day of partly cloudy with rain",
+                "temp": {"day": 14.0, "min": 6.0, "max": 16.0, "night": 8.0, "eve": 12.0, "morn": 7.0},
+                "feels_like": {"day": 13.0, "night": 7.0, "eve": 11.0, "morn": 6.0},
+                "pressure": 1015, "humidity": 65, "dew_point": 6.5, "wind_speed": 5.1,
+                "wind_deg": 200, "wind_gust": 9.0, "clouds": 55, "pop": 0.6, "rain": 2.3,
+                "uvi": 3.1, "weather": condition(d)
+            })
+        })
+        .collect();
+    json!({
+        "lat": 41.88, "lon": -87.63, "timezone": "America/Chicago",
+        "timezone_offset": -21600,
+        "current": {
+            "dt": START, "sunrise": START - 20000, "sunset": START + 20000,
+            "temp": 12.0, "feels_like": 10.8, "pressure": 1013, "humidity": 72,
+            "dew_point": 7.0, "uvi": 1.2, "clouds": 40, "visibility": 10000,
+            "wind_speed": 4.0, "wind_deg": 200, "weather": condition(0)
+        },
+        "hourly": hourly,
+        "daily": daily
+    })
+    .to_string()
+}
+
+fn location() -> GeoLocation {
+    GeoLocation {
+        name: "Chicago".to_string(),
+        lat: 41.88,
+        lon: -87.63,
+        country: "US".to_string(),
+        state: Some("Illinois".to_string()),
+    }
+}
+
+fn forecast_benches(c: &mut Criterion) {
+    let body = one_call_body();
+    let parsed = || serde_json::from_str::<OneCallResponse>(&body).unwrap();
+
+    c.bench_function("one_call/deserialize", |b| {
+        b.iter(|| serde_json::from_str::<OneCallResponse>(black_box(&body)).unwrap())
+    });
+    c.bench_function("one_call/transform", |b| {
+        b.iter_batched(
+            || (parsed(), location()),
+            |(data, location)| ForecastResponse::from_one_call(data, location),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let response = ForecastResponse::from_one_call(parsed(), location());
+    c.bench_function("forecast/serialize", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&response)).unwrap())
+    });
+    c.bench_function("forecast/end_to_end", |b| {
+        b.iter(|| {
+            let data = serde_json::from_str::<OneCallResponse>(black_box(&body)).unwrap();
+            serde_json::to_vec(&ForecastResponse::from_one_call(data, location())).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, forecast_benches);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the bundled protoc unless PROTOC points at another, so building
+    // needs no system protobuf compiler
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_protos(&["proto/weathrs.proto"], &["proto"])?;
+    emit_build_info();
+    Ok(())
+}
+
+/// Commit and build time reported by GET /api/v1/status. `WEATHRS_GIT_COMMIT`
+/// stands in for git where there's no checkout (Docker builds), and
+/// `SOURCE_DATE_EPOCH` for the clock in reproducible builds.
+fn emit_build_info() {
+    let commit = std::env::var("WEATHRS_GIT_COMMIT")
+        .ok()
+        .filter(|c| !c.trim().is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()
+                .filter(|out| out.status.success())
+                .and_then(|out| String::from_utf8(out.stdout).ok())
+        })
+        .map(|c| c.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+
+    println!("cargo:rustc-env=WEATHRS_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=WEATHRS_BUILT_AT={}", built_at);
+    println!("cargo:rerun-if-env-changed=WEATHRS_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
@@ -0,0 +1,7 @@
+-- Treat the last registration/settings update as the most recent sign of life
+-- for devices that existed before heartbeats were tracked
+UPDATE devices
+SET last_seen = updated_at
+WHERE last_seen IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_devices_last_seen ON devices(last_seen);
@@ -0,0 +1,17 @@
+-- Per-device record of every push we sent, queued, or failed to send
+CREATE TABLE IF NOT EXISTS notification_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    device_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    priority TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_log_device_ts
+    ON notification_log(device_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_notification_log_created_at
+    ON notification_log(created_at);
@@ -0,0 +1,18 @@
+-- Every weather alert observed for a location, one row per issued alert
+CREATE TABLE IF NOT EXISTS alert_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    location_key TEXT NOT NULL,
+    city TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    event TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    start_ts INTEGER NOT NULL,
+    end_ts INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL,
+    UNIQUE(location_key, sender, event, start_ts)
+);
+
+CREATE INDEX IF NOT EXISTS idx_alert_history_location_start
+    ON alert_history(location_key, start_ts);
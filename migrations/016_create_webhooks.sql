@@ -0,0 +1,13 @@
+-- Incoming webhooks: each triggers a saved job or an ad-hoc forecast push
+CREATE TABLE IF NOT EXISTS webhooks (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    secret_hash TEXT NOT NULL,
+    job_id TEXT,
+    city TEXT,
+    units TEXT,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    owner TEXT,
+    created_at INTEGER NOT NULL,
+    last_triggered INTEGER
+);
@@ -0,0 +1,15 @@
+-- Who created, changed, or deleted jobs, devices, and webhooks
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    actor TEXT,
+    api_key TEXT,
+    ip TEXT,
+    changes TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_resource
+    ON audit_log(resource, resource_id);
@@ -0,0 +1,12 @@
+-- Named saved locations, referenced by jobs and devices as "loc:<id>"
+CREATE TABLE IF NOT EXISTS saved_locations (
+    id TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    city TEXT,
+    lat REAL,
+    lon REAL,
+    units TEXT,
+    owner TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
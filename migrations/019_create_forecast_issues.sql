@@ -0,0 +1,13 @@
+-- Daily forecasts as issued by scheduled jobs, kept in metric so they can be
+-- scored against weather_history. One row per target day and lead time; a
+-- later run with the same lead replaces the earlier one.
+CREATE TABLE IF NOT EXISTS forecast_issues (
+    location_key TEXT NOT NULL,
+    target_date TEXT NOT NULL,
+    lead_days INTEGER NOT NULL,
+    issued_at INTEGER NOT NULL,
+    temp_min REAL NOT NULL,
+    temp_max REAL NOT NULL,
+    precipitation REAL NOT NULL,
+    PRIMARY KEY (location_key, target_date, lead_days)
+);
@@ -0,0 +1,15 @@
+-- Every forecast fetched from OWM, as gzip-compressed ForecastResponse JSON in
+-- the units it was fetched in. Pruned to forecast_archive.retention_days.
+CREATE TABLE IF NOT EXISTS forecast_archive (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    location_key TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    units TEXT NOT NULL,
+    issued_at INTEGER NOT NULL,
+    snapshot BLOB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_forecast_archive_location
+    ON forecast_archive(location_key, issued_at);
+CREATE INDEX IF NOT EXISTS idx_forecast_archive_issued_at
+    ON forecast_archive(issued_at);
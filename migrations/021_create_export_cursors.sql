@@ -0,0 +1,7 @@
+-- How far each export job has got, as the last exported row ID of its source
+-- table, so every run only sends rows added since the previous one.
+CREATE TABLE IF NOT EXISTS export_cursors (
+    name TEXT PRIMARY KEY,
+    last_id INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
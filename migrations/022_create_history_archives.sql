@@ -0,0 +1,11 @@
+-- Hourly weather_history rows older than the retention window, compacted into
+-- one gzip-compressed CSV per location and calendar month (UTC)
+CREATE TABLE IF NOT EXISTS history_archives (
+    location_key TEXT NOT NULL,
+    month TEXT NOT NULL,
+    city TEXT NOT NULL,
+    row_count INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (location_key, month)
+);
@@ -0,0 +1,33 @@
+-- History is stored in metric only; responses are converted on read.
+-- Drop imperial/standard rows already covered by a metric row for the same hour
+DELETE FROM weather_history
+WHERE units != 'metric'
+  AND EXISTS (
+    SELECT 1 FROM weather_history m
+    WHERE m.units = 'metric'
+      AND m.location_key = weather_history.location_key
+      AND m.timestamp = weather_history.timestamp
+  );
+
+-- Of the rest, keep one row per hour
+DELETE FROM weather_history
+WHERE units != 'metric'
+  AND id NOT IN (
+    SELECT MIN(id)
+    FROM weather_history
+    WHERE units != 'metric'
+    GROUP BY location_key, timestamp
+  );
+
+-- Convert what remains to metric: °F and K to °C, mph to m/s
+UPDATE OR IGNORE weather_history
+SET temperature = CASE units WHEN 'imperial' THEN (temperature - 32) * 5.0 / 9.0 ELSE temperature - 273.15 END,
+    feels_like = CASE units WHEN 'imperial' THEN (feels_like - 32) * 5.0 / 9.0 ELSE feels_like - 273.15 END,
+    dew_point = CASE units WHEN 'imperial' THEN (dew_point - 32) * 5.0 / 9.0 ELSE dew_point - 273.15 END,
+    wind_speed = CASE units WHEN 'imperial' THEN wind_speed * 0.44704 ELSE wind_speed END,
+    wind_gust = CASE units WHEN 'imperial' THEN wind_gust * 0.44704 ELSE wind_gust END,
+    units = 'metric'
+WHERE units IN ('imperial', 'standard');
+
+-- Anything left could not be converted without a conflict
+DELETE FROM weather_history WHERE units != 'metric';
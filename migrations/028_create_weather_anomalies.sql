@@ -0,0 +1,19 @@
+-- Upstream values rejected as physically implausible, kept out of
+-- weather_history and notifications
+CREATE TABLE IF NOT EXISTS weather_anomalies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    location_key TEXT NOT NULL,
+    city TEXT NOT NULL,
+    -- Where the value came from: timemachine, observation, or forecast
+    source TEXT NOT NULL,
+    -- Time of the reading, and when it was rejected
+    observed_at INTEGER NOT NULL,
+    detected_at INTEGER NOT NULL,
+    -- Checks that failed, as a JSON array of strings
+    reasons TEXT NOT NULL,
+    -- The rejected reading as JSON
+    payload TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_weather_anomalies_location
+    ON weather_anomalies (location_key, observed_at);
@@ -0,0 +1,20 @@
+-- Weather alerts that have been notified per location, so scheduled runs only
+-- notify new or materially changed alerts, and acknowledged ones not at all
+CREATE TABLE IF NOT EXISTS alerts (
+    -- Fingerprint of location, sender, event, start and end
+    id TEXT PRIMARY KEY,
+    location_key TEXT NOT NULL,
+    city TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    event TEXT NOT NULL,
+    start_ts INTEGER NOT NULL,
+    end_ts INTEGER NOT NULL,
+    -- Hash of severity, description and instruction as last notified
+    revision TEXT NOT NULL,
+    first_notified INTEGER NOT NULL,
+    last_notified INTEGER NOT NULL,
+    acknowledged_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_alerts_location_end
+    ON alerts(location_key, end_ts);
@@ -0,0 +1,10 @@
+-- Which devices each notified alert has reached, so a device that subscribes
+-- later, or missed a push (quiet hours, another job's run), still gets it
+CREATE TABLE IF NOT EXISTS alert_deliveries (
+    alert_id TEXT NOT NULL,
+    device_id TEXT NOT NULL,
+    -- Alert revision as delivered to this device
+    revision TEXT NOT NULL,
+    delivered_at INTEGER NOT NULL,
+    PRIMARY KEY (alert_id, device_id)
+);
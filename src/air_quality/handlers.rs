@@ -2,6 +2,7 @@ use axum::{extract::State, Json};
 
 use super::models::AirQualityResponse;
 use super::service::AirQualityError;
+use crate::error::ErrorResponse;
 use crate::extractors::CityParam;
 use crate::AppState;
 
@@ -10,11 +11,23 @@ use crate::AppState;
 /// Uses the OWM Air Pollution API to return current AQI and pollutant concentrations.
 ///
 /// - GET /air-quality/{city}
+#[utoipa::path(
+    get,
+    path = "/api/v1/air-quality/{city}",
+    tag = "air-quality",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code")),
+    responses(
+        (status = 200, description = "Current air quality", body = AirQualityResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
 pub async fn get_air_quality(
     State(state): State<AppState>,
     CityParam(city): CityParam,
 ) -> Result<Json<AirQualityResponse>, AirQualityError> {
-    let city = city.unwrap_or_else(|| state.config.default_city.clone());
+    let city = city.unwrap_or_else(|| state.config.default_location());
 
     let air_quality = state.air_quality_service.get_air_quality(&city).await?;
     Ok(Json(air_quality))
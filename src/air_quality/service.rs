@@ -5,10 +5,11 @@ use thiserror::Error;
 use std::sync::Arc;
 
 use super::models::*;
-use crate::api_budget::ApiCallBudget;
+use crate::api_budget::{ApiCallBudget, BudgetFeature};
+use crate::api_keys::ApiKeyPool;
 use crate::error::HttpError;
 use crate::forecast::ForecastService;
-use crate::impl_into_response;
+use crate::{impl_from_upstream, impl_into_response};
 
 const AIR_POLLUTION_API_URL: &str = "https://api.openweathermap.org/data/2.5/air_pollution";
 
@@ -25,6 +26,12 @@ pub enum AirQualityError {
 
     #[error("No air quality data available")]
     NoData,
+
+    #[error("Daily API budget exhausted, retry in {retry_after}s")]
+    BudgetExhausted { retry_after: u64 },
+
+    #[error("OpenWeatherMap is unavailable, retry in {retry_after}s")]
+    UpstreamUnavailable { retry_after: u64 },
 }
 
 impl HttpError for AirQualityError {
@@ -34,6 +41,8 @@ impl HttpError for AirQualityError {
             Self::RequestError(_) => StatusCode::BAD_GATEWAY,
             Self::ApiError(_) => StatusCode::BAD_REQUEST,
             Self::NoData => StatusCode::NOT_FOUND,
+            Self::BudgetExhausted { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::UpstreamUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 
@@ -43,15 +52,27 @@ impl HttpError for AirQualityError {
             Self::RequestError(_) => Some("REQUEST_ERROR"),
             Self::ApiError(_) => Some("API_ERROR"),
             Self::NoData => Some("NO_DATA"),
+            Self::BudgetExhausted { .. } => Some("API_BUDGET_EXHAUSTED"),
+            Self::UpstreamUnavailable { .. } => Some("UPSTREAM_UNAVAILABLE"),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::BudgetExhausted { retry_after } | Self::UpstreamUnavailable { retry_after } => {
+                Some(*retry_after)
+            }
+            _ => None,
         }
     }
 }
 
 impl_into_response!(AirQualityError);
+impl_from_upstream!(AirQualityError);
 
 pub struct AirQualityService {
     client: Client,
-    api_key: String,
+    api_keys: Arc<ApiKeyPool>,
     forecast_service: Arc<ForecastService>,
     api_budget: Arc<ApiCallBudget>,
 }
@@ -59,13 +80,13 @@ pub struct AirQualityService {
 impl AirQualityService {
     pub fn new(
         client: Client,
-        api_key: &str,
+        api_keys: Arc<ApiKeyPool>,
         forecast_service: Arc<ForecastService>,
         api_budget: Arc<ApiCallBudget>,
     ) -> Self {
         Self {
             client,
-            api_key: api_key.to_string(),
+            api_keys,
             forecast_service,
             api_budget,
         }
@@ -73,6 +94,12 @@ impl AirQualityService {
 
     /// Get air quality data for a city
     pub async fn get_air_quality(&self, city: &str) -> Result<AirQualityResponse, AirQualityError> {
+        if !self.api_budget.try_acquire(BudgetFeature::AirQuality) {
+            return Err(AirQualityError::BudgetExhausted {
+                retry_after: self.api_budget.seconds_until_reset(),
+            });
+        }
+
         // Reuse the forecast service's geocoding
         let location = self
             .forecast_service
@@ -87,17 +114,15 @@ impl AirQualityService {
             "Fetching air quality"
         );
 
-        self.api_budget.record_call();
-
         let response = self
-            .client
-            .get(AIR_POLLUTION_API_URL)
-            .query(&[
-                ("lat", location.lat.to_string()),
-                ("lon", location.lon.to_string()),
-                ("appid", self.api_key.clone()),
-            ])
-            .send()
+            .api_keys
+            .send_budgeted(
+                BudgetFeature::AirQuality,
+                self.client.get(AIR_POLLUTION_API_URL).query(&[
+                    ("lat", location.lat.to_string()),
+                    ("lon", location.lon.to_string()),
+                ]),
+            )
             .await?;
 
         if !response.status().is_success() {
@@ -1,30 +1,160 @@
 use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-/// Tracks daily API call usage with automatic reset at UTC day boundaries.
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{BudgetWarningConfig, ConditionStylesConfig};
+use crate::notifications::{AdminNotifier, ConditionGroup, NotificationMessage, Priority};
+
+/// How often usage is compared against the warning threshold
+const WARNING_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Features that draw on the shared API budget and may be given a cap of
+/// their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetFeature {
+    Weather,
+    Forecast,
+    History,
+    AirQuality,
+    Backfill,
+}
+
+impl BudgetFeature {
+    pub const ALL: [BudgetFeature; 5] = [
+        BudgetFeature::Weather,
+        BudgetFeature::Forecast,
+        BudgetFeature::History,
+        BudgetFeature::AirQuality,
+        BudgetFeature::Backfill,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Weather => "weather",
+            Self::Forecast => "forecast",
+            Self::History => "history",
+            Self::AirQuality => "air_quality",
+            Self::Backfill => "backfill",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Tracks daily API call usage, resetting at midnight in the configured
+/// timezone (UTC by default), with optional per-feature caps.
 pub struct ApiCallBudget {
     daily_limit: u32,
+    timezone: Tz,
+    /// Max calls per feature per day, if capped
+    feature_limits: [Option<u32>; BudgetFeature::ALL.len()],
     calls_today: AtomicU32,
+    feature_calls: [AtomicU32; BudgetFeature::ALL.len()],
     current_day: AtomicI64,
+    /// Day the low-budget warning was last raised
+    warned_day: AtomicI64,
 }
 
 impl ApiCallBudget {
     pub fn new(daily_limit: u32) -> Self {
         Self {
             daily_limit,
+            timezone: Tz::UTC,
+            feature_limits: [None; BudgetFeature::ALL.len()],
             calls_today: AtomicU32::new(0),
-            current_day: AtomicI64::new(Self::utc_day_now()),
+            feature_calls: Default::default(),
+            current_day: AtomicI64::new(day_number(Utc::now(), Tz::UTC)),
+            warned_day: AtomicI64::new(-1),
         }
     }
 
+    /// Reset the budget at midnight in `timezone` instead of UTC
+    pub fn with_reset_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self.current_day = AtomicI64::new(day_number(Utc::now(), timezone));
+        self
+    }
+
+    /// Let `feature` use at most `percent` of the daily limit
+    pub fn with_feature_limit(mut self, feature: BudgetFeature, percent: u8) -> Self {
+        let limit = u64::from(self.daily_limit) * u64::from(percent.min(100)) / 100;
+        self.feature_limits[feature.index()] = Some(limit as u32);
+        self
+    }
+
     pub fn daily_limit(&self) -> u32 {
         self.daily_limit
     }
 
-    /// Record an API call. Returns `true` if the call was within budget.
-    pub fn record_call(&self) -> bool {
+    pub fn reset_timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// Reserve one API call for `feature`. Returns `false`, reserving
+    /// nothing, once today's budget or the feature's share of it is spent.
+    pub fn try_acquire(&self, feature: BudgetFeature) -> bool {
         self.maybe_reset();
-        let prev = self.calls_today.fetch_add(1, Ordering::Relaxed);
-        prev < self.daily_limit
+        let feature_calls = &self.feature_calls[feature.index()];
+        let feature_limit = self.feature_limits[feature.index()].unwrap_or(u32::MAX);
+
+        let acquired = feature_calls
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                (used < feature_limit).then_some(used + 1)
+            })
+            .is_ok()
+            && {
+                let total = self
+                    .calls_today
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                        (used < self.daily_limit).then_some(used + 1)
+                    })
+                    .is_ok();
+                if !total {
+                    feature_calls.fetch_sub(1, Ordering::Relaxed);
+                }
+                total
+            };
+
+        if !acquired {
+            metrics::counter!(crate::metrics::API_BUDGET_REJECTIONS, "feature" => feature.key())
+                .increment(1);
+        }
+        acquired
+    }
+
+    /// Calls `feature` can still make today
+    pub fn remaining_for(&self, feature: BudgetFeature) -> u32 {
+        let remaining = self.remaining();
+        match self.feature_limits[feature.index()] {
+            Some(limit) => {
+                let used = self.feature_calls[feature.index()].load(Ordering::Relaxed);
+                remaining.min(limit.saturating_sub(used))
+            }
+            None => remaining,
+        }
+    }
+
+    /// Calls made by `feature` today
+    pub fn used_by(&self, feature: BudgetFeature) -> u32 {
+        self.maybe_reset();
+        self.feature_calls[feature.index()].load(Ordering::Relaxed)
+    }
+
+    /// The cap for `feature`, if it has one
+    pub fn feature_limit(&self, feature: BudgetFeature) -> Option<u32> {
+        self.feature_limits[feature.index()]
+    }
+
+    /// Seconds until the budget resets at the next midnight in the reset timezone
+    pub fn seconds_until_reset(&self) -> u64 {
+        seconds_until_midnight(Utc::now(), self.timezone)
     }
 
     /// Number of API calls remaining today.
@@ -40,9 +170,24 @@ impl ApiCallBudget {
         self.calls_today.load(Ordering::Relaxed)
     }
 
-    /// Reset counter if the UTC day has changed (compare-and-swap).
+    /// Whether usage has reached `percent` of the daily limit and no warning
+    /// has been raised yet today. Returns `true` at most once per day.
+    pub fn should_warn(&self, percent: u8) -> bool {
+        if percent == 0 {
+            return false;
+        }
+        self.maybe_reset();
+        let used = u64::from(self.calls_today.load(Ordering::Relaxed));
+        if used * 100 < u64::from(self.daily_limit) * u64::from(percent) {
+            return false;
+        }
+        let today = self.current_day.load(Ordering::Relaxed);
+        self.warned_day.swap(today, Ordering::AcqRel) != today
+    }
+
+    /// Reset counters if the day has changed (compare-and-swap).
     fn maybe_reset(&self) {
-        let today = Self::utc_day_now();
+        let today = day_number(Utc::now(), self.timezone);
         let stored = self.current_day.load(Ordering::Relaxed);
         if today != stored {
             // Attempt to swap the day; only the winner resets the counter.
@@ -52,13 +197,70 @@ impl ApiCallBudget {
                 .is_ok()
             {
                 self.calls_today.store(0, Ordering::Relaxed);
+                for calls in &self.feature_calls {
+                    calls.store(0, Ordering::Relaxed);
+                }
             }
         }
     }
+}
 
-    fn utc_day_now() -> i64 {
-        chrono::Utc::now().timestamp() / 86400
-    }
+/// Calendar day of `now` in `tz`, as days since the common era
+pub(crate) fn day_number(now: DateTime<Utc>, tz: Tz) -> i64 {
+    i64::from(now.with_timezone(&tz).date_naive().num_days_from_ce())
+}
+
+pub(crate) fn seconds_until_midnight(now: DateTime<Utc>, tz: Tz) -> u64 {
+    let tomorrow = now.with_timezone(&tz).date_naive() + chrono::Days::new(1);
+    let midnight = tz
+        .from_local_datetime(&tomorrow.and_time(chrono::NaiveTime::MIN))
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or(now + chrono::Duration::days(1));
+    (midnight - now).num_seconds().max(1) as u64
+}
+
+/// Start a background task that warns admins once a day when API usage
+/// crosses the configured threshold
+pub fn start_budget_warning_task(
+    budget: Arc<ApiCallBudget>,
+    admin: Arc<AdminNotifier>,
+    config: BudgetWarningConfig,
+    styles: ConditionStylesConfig,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(WARNING_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !budget.should_warn(config.threshold_percent) {
+                continue;
+            }
+
+            let used = budget.used_today();
+            let percent = u64::from(used) * 100 / u64::from(budget.daily_limit().max(1));
+            let body = format!(
+                "{}% of today's OWM quota used by {} ({} of {} calls)",
+                percent,
+                Utc::now()
+                    .with_timezone(&budget.reset_timezone())
+                    .format("%H:%M %Z"),
+                used,
+                budget.daily_limit()
+            );
+            tracing::warn!(used = used, limit = budget.daily_limit(), "{}", body);
+
+            let message = NotificationMessage {
+                title: "API budget running low".to_string(),
+                subtitle: None,
+                body,
+                priority: Priority::High,
+                tags: styles.tag(ConditionGroup::Alert).into_iter().collect(),
+                city: None,
+                events: vec![],
+            };
+            admin.notify(&config.admin_tokens, &message).await;
+        }
+    });
 }
 
 #[cfg(test)]
@@ -66,20 +268,73 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_record_call_within_budget() {
+    fn test_try_acquire_within_budget() {
         let budget = ApiCallBudget::new(3);
-        assert!(budget.record_call());
-        assert!(budget.record_call());
-        assert!(budget.record_call());
-        // 4th call exceeds budget
-        assert!(!budget.record_call());
+        assert!(budget.try_acquire(BudgetFeature::Forecast));
+        assert!(budget.try_acquire(BudgetFeature::Forecast));
+        assert!(budget.try_acquire(BudgetFeature::Forecast));
+        // 4th call exceeds budget and isn't counted
+        assert!(!budget.try_acquire(BudgetFeature::Forecast));
+        assert_eq!(budget.used_today(), 3);
+    }
+
+    #[test]
+    fn test_should_warn_once_per_day() {
+        let budget = ApiCallBudget::new(10);
+        for _ in 0..6 {
+            budget.try_acquire(BudgetFeature::Forecast);
+        }
+        assert!(!budget.should_warn(70));
+        budget.try_acquire(BudgetFeature::Forecast);
+        assert!(budget.should_warn(70));
+        budget.try_acquire(BudgetFeature::Forecast);
+        assert!(!budget.should_warn(70));
+        assert!(!budget.should_warn(0));
+    }
+
+    #[test]
+    fn test_seconds_until_reset() {
+        let secs = ApiCallBudget::new(1).seconds_until_reset();
+        assert!(secs > 0 && secs <= 86400);
+
+        // 23:30 CDT on May 31
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 4, 30, 0).unwrap();
+        assert_eq!(seconds_until_midnight(now, Tz::UTC), 70200);
+        assert_eq!(
+            seconds_until_midnight(now, chrono_tz::America::Chicago),
+            1800
+        );
+        assert_ne!(
+            day_number(now, Tz::UTC),
+            day_number(now, chrono_tz::America::Chicago)
+        );
+    }
+
+    #[test]
+    fn test_feature_limit() {
+        let budget = ApiCallBudget::new(10).with_feature_limit(BudgetFeature::Backfill, 40);
+        assert_eq!(budget.feature_limit(BudgetFeature::Backfill), Some(4));
+        for _ in 0..4 {
+            assert!(budget.try_acquire(BudgetFeature::Backfill));
+        }
+        assert!(!budget.try_acquire(BudgetFeature::Backfill));
+        assert_eq!(budget.remaining_for(BudgetFeature::Backfill), 0);
+        assert_eq!(budget.used_by(BudgetFeature::Backfill), 4);
+
+        // Other features still draw on the rest of the daily limit
+        assert_eq!(budget.remaining_for(BudgetFeature::Weather), 6);
+        for _ in 0..6 {
+            assert!(budget.try_acquire(BudgetFeature::Weather));
+        }
+        assert!(!budget.try_acquire(BudgetFeature::Weather));
+        assert_eq!(budget.used_by(BudgetFeature::Weather), 6);
     }
 
     #[test]
     fn test_remaining() {
         let budget = ApiCallBudget::new(10);
         assert_eq!(budget.remaining(), 10);
-        budget.record_call();
+        budget.try_acquire(BudgetFeature::Forecast);
         assert_eq!(budget.remaining(), 9);
     }
 
@@ -87,8 +342,8 @@ mod tests {
     fn test_used_today() {
         let budget = ApiCallBudget::new(100);
         assert_eq!(budget.used_today(), 0);
-        budget.record_call();
-        budget.record_call();
+        budget.try_acquire(BudgetFeature::Forecast);
+        budget.try_acquire(BudgetFeature::Forecast);
         assert_eq!(budget.used_today(), 2);
     }
 }
@@ -0,0 +1,404 @@
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use reqwest::{Client, Method, Request, RequestBuilder, Response, StatusCode};
+
+use crate::api_budget::{ApiCallBudget, BudgetFeature};
+use crate::config::{UpstreamAlertConfig, UpstreamConfig};
+use crate::upstream::{
+    api_name, is_upstream_failure, CircuitBreaker, RetryPolicy, UpstreamError, UpstreamHealth,
+};
+
+/// How long a key is skipped after OWM rejects it as invalid (401)
+const UNAUTHORIZED_QUARANTINE_SECS: i64 = 60 * 60;
+
+/// How long a key is skipped after OWM rate-limits it (429)
+const RATE_LIMITED_QUARANTINE_SECS: i64 = 10 * 60;
+
+/// Round-robin pool of OpenWeatherMap API keys.
+///
+/// Keys that come back with 401 or 429 are quarantined for a while so
+/// requests move on to the others. If every key is quarantined, the one
+/// closest to release is used rather than failing outright.
+///
+/// Every OWM request goes through [`ApiKeyPool::send`], so the retry policy
+/// and circuit breaker live here too.
+pub struct ApiKeyPool {
+    keys: Vec<PooledKey>,
+    next: AtomicUsize,
+    retry: RetryPolicy,
+    breaker: CircuitBreaker,
+    health: UpstreamHealth,
+    /// Charged for each extra attempt of a budgeted request
+    budget: Option<Arc<ApiCallBudget>>,
+}
+
+struct PooledKey {
+    key: String,
+    quarantined_until: AtomicI64,
+}
+
+impl ApiKeyPool {
+    pub fn new(keys: Vec<String>) -> Self {
+        let mut unique: Vec<String> = Vec::new();
+        for key in keys {
+            let key = key.trim().to_string();
+            if !key.is_empty() && !unique.contains(&key) {
+                unique.push(key);
+            }
+        }
+
+        Self {
+            keys: unique
+                .into_iter()
+                .map(|key| PooledKey {
+                    key,
+                    quarantined_until: AtomicI64::new(0),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            retry: RetryPolicy::default(),
+            breaker: CircuitBreaker::default(),
+            health: UpstreamHealth::new(&UpstreamAlertConfig::default()),
+            budget: None,
+        }
+    }
+
+    /// Use the configured retry policy and circuit breaker
+    pub fn with_upstream(mut self, config: &UpstreamConfig) -> Self {
+        self.retry = RetryPolicy::new(config);
+        self.breaker = CircuitBreaker::new(config);
+        self
+    }
+
+    /// Track per-API failure rates for admin alerts with these settings
+    pub fn with_upstream_alert(mut self, config: &UpstreamAlertConfig) -> Self {
+        self.health = UpstreamHealth::new(config);
+        self
+    }
+
+    /// Charge retries of budgeted requests to this budget
+    pub fn with_budget(mut self, budget: Arc<ApiCallBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Recent failure rates per OWM API
+    pub fn health(&self) -> &UpstreamHealth {
+        &self.health
+    }
+
+    /// Seconds until OWM calls resume, if the circuit breaker is open
+    pub fn circuit_open_for(&self) -> Option<u64> {
+        self.breaker.open_for(chrono::Utc::now().timestamp())
+    }
+
+    /// Number of configured keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether no keys are configured
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Number of keys not currently quarantined
+    pub fn available(&self) -> usize {
+        let now = chrono::Utc::now().timestamp();
+        self.keys
+            .iter()
+            .filter(|k| k.quarantined_until.load(Ordering::Relaxed) <= now)
+            .count()
+    }
+
+    /// Pick the next key to use
+    fn pick(&self, now: i64) -> Option<&PooledKey> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..self.keys.len())
+            .map(|i| &self.keys[(start + i) % self.keys.len()])
+            .find(|k| k.quarantined_until.load(Ordering::Relaxed) <= now)
+            .or_else(|| {
+                self.keys
+                    .iter()
+                    .min_by_key(|k| k.quarantined_until.load(Ordering::Relaxed))
+            })
+    }
+
+    /// How long a key is quarantined for after OWM answers with this status
+    fn quarantine_secs(status: StatusCode) -> Option<i64> {
+        match status {
+            StatusCode::UNAUTHORIZED => Some(UNAUTHORIZED_QUARANTINE_SECS),
+            StatusCode::TOO_MANY_REQUESTS => Some(RATE_LIMITED_QUARANTINE_SECS),
+            _ => None,
+        }
+    }
+
+    /// Quarantine a key based on the status OWM answered with
+    fn report(&self, key: &PooledKey, status: StatusCode, now: i64) {
+        let Some(secs) = Self::quarantine_secs(status) else {
+            return;
+        };
+        key.quarantined_until.store(now + secs, Ordering::Relaxed);
+        tracing::warn!(
+            key = %mask(&key.key),
+            status = %status,
+            quarantine_secs = secs,
+            "Quarantined OpenWeatherMap API key"
+        );
+    }
+
+    /// Send a request to OWM. GETs that time out, fail to connect, or get a
+    /// 5xx are retried with backoff; every attempt counts toward the circuit
+    /// breaker, and none are made while it is open. A request whose key is
+    /// quarantined (401 or 429) is sent again right away on the next healthy
+    /// key, if there is one.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, UpstreamError> {
+        self.send_inner(request, None).await
+    }
+
+    /// Like [`ApiKeyPool::send`], for a call already reserved from the API
+    /// budget under `feature`. Every attempt after the first reserves another
+    /// call, and no more are made once the budget is spent.
+    pub async fn send_budgeted(
+        &self,
+        feature: BudgetFeature,
+        request: RequestBuilder,
+    ) -> Result<Response, UpstreamError> {
+        self.send_inner(request, Some(feature)).await
+    }
+
+    async fn send_inner(
+        &self,
+        request: RequestBuilder,
+        feature: Option<BudgetFeature>,
+    ) -> Result<Response, UpstreamError> {
+        let (client, request) = request.build_split();
+        let mut request = request?;
+        request
+            .timeout_mut()
+            .get_or_insert(self.retry.attempt_timeout);
+        let idempotent = request.method() == Method::GET;
+        let api = api_name(request.url().path());
+
+        let mut attempt = 0;
+        let mut key_switches = 0;
+        loop {
+            let now = chrono::Utc::now().timestamp();
+            self.breaker
+                .check(now)
+                .map_err(|retry_after| UpstreamError::CircuitOpen { retry_after })?;
+
+            let next = request.try_clone();
+            let outcome = self.send_once(&client, request, now).await;
+            let failed = is_upstream_failure(&outcome);
+            let now = chrono::Utc::now().timestamp();
+            self.breaker.record(failed, now);
+            self.health.record(api, failed, now);
+
+            let quarantined = matches!(
+                &outcome,
+                Ok(response) if Self::quarantine_secs(response.status()).is_some()
+            );
+            let switch_key =
+                quarantined && key_switches + 1 < self.keys.len() && self.available() > 0;
+            let retry = failed && idempotent && attempt < self.retry.max_retries;
+            match next {
+                Some(next) if (switch_key || retry) && self.reserve_attempt(feature) => {
+                    if switch_key {
+                        tracing::debug!("Retrying OpenWeatherMap request with another key");
+                        key_switches += 1;
+                    } else {
+                        let delay = self.retry.backoff(attempt);
+                        metrics::counter!(crate::metrics::OWM_RETRIES).increment(1);
+                        tracing::debug!(
+                            attempt = attempt + 1,
+                            delay_ms = delay.as_millis() as u64,
+                            "Retrying OpenWeatherMap request"
+                        );
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    request = next;
+                }
+                _ => return outcome.map_err(Into::into),
+            }
+        }
+    }
+
+    /// Reserve an extra attempt of a budgeted request from the API budget
+    fn reserve_attempt(&self, feature: Option<BudgetFeature>) -> bool {
+        match (feature, &self.budget) {
+            (Some(feature), Some(budget)) => {
+                let reserved = budget.try_acquire(feature);
+                if !reserved {
+                    tracing::debug!(
+                        feature = feature.key(),
+                        "API budget spent, not retrying OpenWeatherMap request"
+                    );
+                }
+                reserved
+            }
+            _ => true,
+        }
+    }
+
+    /// Attach the next key as `appid` and send one attempt, quarantining the
+    /// key if OWM rejects or rate-limits it
+    async fn send_once(
+        &self,
+        client: &Client,
+        mut request: Request,
+        now: i64,
+    ) -> reqwest::Result<Response> {
+        let Some(key) = self.pick(now) else {
+            return client.execute(request).await;
+        };
+        request
+            .url_mut()
+            .query_pairs_mut()
+            .append_pair("appid", &key.key);
+        let response = client.execute(request).await?;
+        self.report(key, response.status(), now);
+        Ok(response)
+    }
+}
+
+/// Show only the last four characters of a key in logs
+fn mask(key: &str) -> String {
+    let tail: String = key
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("…{}", tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(pool: &ApiKeyPool, now: i64, n: usize) -> Vec<String> {
+        (0..n)
+            .map(|_| pool.pick(now).unwrap().key.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_round_robin_skips_quarantined_keys() {
+        let pool = ApiKeyPool::new(vec![
+            "aaaa".to_string(),
+            "bbbb".to_string(),
+            " aaaa ".to_string(),
+            "cccc".to_string(),
+        ]);
+        assert_eq!(pool.len(), 3);
+        assert_eq!(keys(&pool, 1000, 3), vec!["aaaa", "bbbb", "cccc"]);
+
+        pool.report(&pool.keys[1], StatusCode::TOO_MANY_REQUESTS, 1000);
+        assert_eq!(keys(&pool, 1000, 4), vec!["aaaa", "cccc", "cccc", "aaaa"]);
+
+        // Released once the quarantine has passed
+        let later = 1000 + RATE_LIMITED_QUARANTINE_SECS;
+        assert!(keys(&pool, later, 3).contains(&"bbbb".to_string()));
+    }
+
+    #[test]
+    fn test_all_quarantined_uses_soonest_released() {
+        let pool = ApiKeyPool::new(vec!["aaaa".to_string(), "bbbb".to_string()]);
+        pool.report(&pool.keys[0], StatusCode::UNAUTHORIZED, 1000);
+        pool.report(&pool.keys[1], StatusCode::TOO_MANY_REQUESTS, 1000);
+        assert_eq!(pool.pick(1001).unwrap().key, "bbbb");
+
+        pool.report(&pool.keys[0], StatusCode::OK, 1000);
+        assert_eq!(mask("abcdefgh"), "…efgh");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_key_retries_on_next_key() {
+        use axum::{extract::Query, http::StatusCode as Status, routing::get, Router};
+        use std::collections::HashMap;
+
+        let app = Router::new().route(
+            "/data/2.5/weather",
+            get(|Query(q): Query<HashMap<String, String>>| async move {
+                match q.get("appid").map(String::as_str) {
+                    Some("bbbb") => Status::OK,
+                    _ => Status::UNAUTHORIZED,
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let pool = ApiKeyPool::new(vec!["aaaa".to_string(), "bbbb".to_string()]);
+        let url = format!("http://{}/data/2.5/weather", addr);
+        let response = pool.send(Client::new().get(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(pool.available(), 1);
+
+        // Each switch to another key is charged to the budget
+        let budget = Arc::new(ApiCallBudget::new(10));
+        let pool = ApiKeyPool::new(vec!["aaaa".to_string(), "bbbb".to_string()])
+            .with_budget(Arc::clone(&budget));
+        assert!(budget.try_acquire(BudgetFeature::Weather));
+        let response = pool
+            .send_budgeted(BudgetFeature::Weather, Client::new().get(&url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(budget.used_by(BudgetFeature::Weather), 2);
+
+        // With no healthy key left, the rejection is passed on
+        let pool = ApiKeyPool::new(vec!["aaaa".to_string(), "cccc".to_string()]);
+        let response = pool.send(Client::new().get(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_when_budget_spent() {
+        use axum::{http::StatusCode as Status, routing::get, Router};
+        use std::sync::atomic::AtomicU32;
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let app = Router::new().route(
+            "/data/2.5/weather",
+            get(move || async move {
+                counter.fetch_add(1, Ordering::Relaxed);
+                Status::SERVICE_UNAVAILABLE
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let budget = Arc::new(ApiCallBudget::new(2));
+        let pool = ApiKeyPool::new(vec!["aaaa".to_string()])
+            .with_upstream(&UpstreamConfig {
+                max_retries: 3,
+                retry_base_delay_ms: 1,
+                ..UpstreamConfig::default()
+            })
+            .with_budget(Arc::clone(&budget));
+        assert!(budget.try_acquire(BudgetFeature::Weather));
+        let url = format!("http://{}/data/2.5/weather", addr);
+        let response = pool
+            .send_budgeted(BudgetFeature::Weather, Client::new().get(&url))
+            .await
+            .unwrap();
+
+        // One retry fit in the budget; the other two were never made
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert_eq!(budget.remaining(), 0);
+    }
+}
@@ -0,0 +1,187 @@
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+
+use super::models::{ApiV2Error, DeviceFilterQuery, Envelope, PageQuery};
+use super::RequestId;
+use crate::auth::Principal;
+use crate::db::DeviceFilter;
+use crate::devices::models::{DeliveryRecord, DeviceSummary};
+use crate::error::ErrorResponse;
+use crate::extractors::UnitsParam;
+use crate::history::models::{HistoryDataPoint, HistoryQuery};
+use crate::scheduler::ForecastJob;
+use crate::units::Units;
+use crate::AppState;
+
+/// List scheduled jobs the caller can see, ordered by id
+///
+/// GET /api/v2/scheduler/jobs?cursor={cursor}&limit={n}
+#[utoipa::path(
+    get,
+    path = "/api/v2/scheduler/jobs",
+    tag = "v2",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "One page of jobs", body = Envelope<ForecastJob>),
+        (status = 400, description = "Invalid cursor", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    request_id: RequestId,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Envelope<ForecastJob>>, ApiV2Error> {
+    let after = page.after()?;
+    let mut jobs = state.scheduler_service.get_jobs().await;
+    jobs.retain(|job| {
+        principal
+            .as_ref()
+            .is_none_or(|Extension(p)| p.can_access(job.owner.as_deref()))
+            && after.as_ref().is_none_or(|after| job.id > *after)
+    });
+    jobs.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(Json(Envelope::page(
+        jobs,
+        page.limit(),
+        |job| job.id.clone(),
+        request_id,
+    )))
+}
+
+/// List registered devices, newest first. Users only see their own devices.
+///
+/// GET /api/v2/devices?cursor={cursor}&limit={n}&city={city}&platform={platform}&enabled={bool}
+#[utoipa::path(
+    get,
+    path = "/api/v2/devices",
+    tag = "v2",
+    params(PageQuery, DeviceFilterQuery),
+    responses(
+        (status = 200, description = "One page of devices", body = Envelope<DeviceSummary>),
+        (status = 400, description = "Invalid cursor", body = ErrorResponse)
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_devices(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    request_id: RequestId,
+    Query(page): Query<PageQuery>,
+    Query(query): Query<DeviceFilterQuery>,
+) -> Result<Json<Envelope<DeviceSummary>>, ApiV2Error> {
+    let after = page.after_keyset()?;
+    let filter = DeviceFilter {
+        city: query.city,
+        platform: query.platform,
+        enabled: query.enabled,
+        owner: principal.and_then(|Extension(p)| p.owner_filter()),
+    };
+    let limit = page.limit();
+    let devices = state
+        .devices_service
+        .list_after(
+            &filter,
+            after.as_ref().map(|(ts, id)| (*ts, id.as_str())),
+            limit + 1,
+        )
+        .await?;
+
+    Ok(Json(Envelope::page(
+        devices,
+        limit,
+        |d| format!("{}:{}", d.registered_at, d.id),
+        request_id,
+    )))
+}
+
+/// List pushes sent to a device, newest first
+///
+/// GET /api/v2/devices/{id}/notifications?cursor={cursor}&limit={n}
+#[utoipa::path(
+    get,
+    path = "/api/v2/devices/{id}/notifications",
+    tag = "v2",
+    params(("id" = String, Path, description = "Device ID"), PageQuery),
+    responses(
+        (status = 200, description = "One page of the delivery log", body = Envelope<DeliveryRecord>),
+        (status = 400, description = "Invalid cursor", body = ErrorResponse),
+        (status = 404, description = "Device not found", body = ErrorResponse)
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_device_notifications(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    request_id: RequestId,
+    Path(device_id): Path<String>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Envelope<DeliveryRecord>>, ApiV2Error> {
+    let before = page
+        .after_keyset()?
+        .map(|(ts, id)| id.parse().map(|id| (ts, id)))
+        .transpose()
+        .map_err(|_| ApiV2Error::InvalidCursor)?;
+    let owner = principal.and_then(|Extension(p)| p.owner_filter());
+    let limit = page.limit();
+    let records = state
+        .devices_service
+        .notification_page(&device_id, owner.as_deref(), before, limit + 1)
+        .await?;
+
+    Ok(Json(
+        Envelope::page(
+            records,
+            limit,
+            |(id, record)| format!("{}:{}", record.created_at, id),
+            request_id,
+        )
+        .map(|(_, record)| record),
+    ))
+}
+
+/// List hourly history points for a city, oldest first
+///
+/// GET /api/v2/history/{city}?start={unix}&end={unix}&units={units}&cursor={cursor}&limit={n}
+#[utoipa::path(
+    get,
+    path = "/api/v2/history/{city}",
+    tag = "v2",
+    params(("city" = String, Path, description = "City name"), HistoryQuery, ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)"), PageQuery),
+    responses(
+        (status = 200, description = "One page of hourly history", body = Envelope<HistoryDataPoint>),
+        (status = 400, description = "Invalid parameters or cursor", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
+pub async fn list_history(
+    State(state): State<AppState>,
+    request_id: RequestId,
+    Path(city): Path<String>,
+    Query(query): Query<HistoryQuery>,
+    UnitsParam(units): UnitsParam,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Envelope<HistoryDataPoint>>, ApiV2Error> {
+    let units = units.unwrap_or_else(|| state.default_units(&city));
+    // Resume just after the last point already returned
+    let start = match page.after()? {
+        Some(key) => Some(key.parse::<i64>().map_err(|_| ApiV2Error::InvalidCursor)? + 1),
+        None => query.start,
+    };
+
+    let history = state
+        .history_service
+        .get_history(&city, start, query.end, units.as_str())
+        .await?;
+
+    Ok(Json(Envelope::page(
+        history.data_points,
+        page.limit(),
+        |point| point.timestamp.to_string(),
+        request_id,
+    )))
+}
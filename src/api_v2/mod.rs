@@ -0,0 +1,6 @@
+pub mod handlers;
+pub mod models;
+mod pagination;
+
+pub use models::Pagination;
+pub use pagination::RequestId;
@@ -0,0 +1,102 @@
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::devices::{DevicesError, Platform};
+use crate::error::HttpError;
+use crate::history::HistoryError;
+use crate::impl_into_response;
+
+/// Page size when none is requested
+const DEFAULT_LIMIT: u32 = 50;
+
+/// Largest page size a caller may request
+const MAX_LIMIT: u32 = 200;
+
+#[derive(Error, Debug)]
+pub enum ApiV2Error {
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+
+    #[error(transparent)]
+    History(#[from] HistoryError),
+
+    #[error(transparent)]
+    Devices(#[from] DevicesError),
+}
+
+impl HttpError for ApiV2Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidCursor => StatusCode::BAD_REQUEST,
+            Self::History(e) => e.status_code(),
+            Self::Devices(DevicesError::NotFound) => StatusCode::NOT_FOUND,
+            Self::Devices(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidCursor => Some("INVALID_CURSOR"),
+            Self::History(e) => e.error_code(),
+            Self::Devices(DevicesError::NotFound) => Some("DEVICE_NOT_FOUND"),
+            Self::Devices(_) => Some("DATABASE_ERROR"),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::History(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+}
+
+impl_into_response!(ApiV2Error);
+
+/// Response shape shared by every v2 list endpoint
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Envelope<T> {
+    pub data: Vec<T>,
+    pub pagination: Pagination,
+    /// Echoes the caller's `X-Request-Id`, or a generated id
+    pub request_id: String,
+}
+
+/// Where a page sits in the full listing
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct Pagination {
+    /// Page size used for this response
+    pub limit: u32,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// Cursor pagination parameters accepted by every v2 list endpoint
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// `next_cursor` from the previous page; omit for the first page
+    pub cursor: Option<String>,
+    /// Items per page (default 50, max 200)
+    pub limit: Option<u32>,
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
+
+/// Device listing filters for GET /api/v2/devices
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeviceFilterQuery {
+    /// Only devices subscribed to this city
+    pub city: Option<String>,
+    pub platform: Option<Platform>,
+    pub enabled: Option<bool>,
+}
@@ -0,0 +1,150 @@
+use std::convert::Infallible;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use super::models::{ApiV2Error, Envelope, PageQuery, Pagination};
+
+/// Longest client-supplied `X-Request-Id` that is echoed back
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Id reported in every v2 envelope: the caller's `X-Request-Id` when it is
+/// short printable ASCII, otherwise a fresh UUID
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let provided = parts
+            .headers
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_LEN
+                    && id.chars().all(|c| c.is_ascii_graphic())
+            });
+        Ok(Self(provided.map_or_else(
+            || uuid::Uuid::new_v4().to_string(),
+            str::to_string,
+        )))
+    }
+}
+
+impl PageQuery {
+    /// Sort key of the last item on the previous page, if a cursor was given
+    pub fn after(&self) -> Result<Option<String>, ApiV2Error> {
+        self.cursor.as_deref().map(decode_cursor).transpose()
+    }
+
+    /// Like `after`, for `"{timestamp}:{id}"` keyset cursors
+    pub fn after_keyset(&self) -> Result<Option<(i64, String)>, ApiV2Error> {
+        self.after()?
+            .map(|key| {
+                let (ts, id) = key.split_once(':').ok_or(ApiV2Error::InvalidCursor)?;
+                let ts = ts.parse().map_err(|_| ApiV2Error::InvalidCursor)?;
+                Ok((ts, id.to_string()))
+            })
+            .transpose()
+    }
+}
+
+/// Cursors are the last item's sort key, base64url-encoded so clients treat
+/// them as opaque
+fn encode_cursor(key: &str) -> String {
+    URL_SAFE_NO_PAD.encode(key)
+}
+
+fn decode_cursor(cursor: &str) -> Result<String, ApiV2Error> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(ApiV2Error::InvalidCursor)
+}
+
+impl<T> Envelope<T> {
+    /// Build a page from up to `limit + 1` items fetched after the cursor.
+    /// The extra item only signals that another page exists; `key` gives the
+    /// sort key the next cursor resumes after.
+    pub fn page(
+        mut items: Vec<T>,
+        limit: u32,
+        key: impl Fn(&T) -> String,
+        request_id: RequestId,
+    ) -> Self {
+        let has_more = items.len() > limit as usize;
+        items.truncate(limit as usize);
+        let next_cursor = items
+            .last()
+            .filter(|_| has_more)
+            .map(|last| encode_cursor(&key(last)));
+
+        Self {
+            data: items,
+            pagination: Pagination {
+                limit,
+                next_cursor,
+                has_more,
+            },
+            request_id: request_id.0,
+        }
+    }
+
+    /// Convert each item once the cursor has been taken from the page
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Envelope<U> {
+        Envelope {
+            data: self.data.into_iter().map(f).collect(),
+            pagination: self.pagination,
+            request_id: self.request_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_cursor_round_trip() {
+        let request_id = RequestId("req-1".to_string());
+        let page: Envelope<i64> = Envelope::page(
+            vec![30, 20, 10],
+            2,
+            |ts: &i64| format!("{}:dev-{}", ts, ts),
+            request_id.clone(),
+        );
+        assert_eq!(page.data, vec![30, 20]);
+        assert!(page.pagination.has_more);
+        assert_eq!(page.request_id, "req-1");
+
+        let query = PageQuery {
+            cursor: page.pagination.next_cursor,
+            limit: Some(500),
+        };
+        assert_eq!(query.limit(), 200);
+        assert_eq!(
+            query.after_keyset().unwrap(),
+            Some((20, "dev-20".to_string()))
+        );
+
+        let last: Envelope<i64> = Envelope::page(vec![10], 2, |ts| ts.to_string(), request_id);
+        assert_eq!(last.pagination.next_cursor, None);
+        assert!(!last.pagination.has_more);
+
+        let bad = PageQuery {
+            cursor: Some("not base64!".to_string()),
+            limit: None,
+        };
+        assert!(matches!(bad.after(), Err(ApiV2Error::InvalidCursor)));
+        assert!(PageQuery::default().after().unwrap().is_none());
+    }
+}
@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use super::models::{AuditListResponse, AuditQuery};
+use crate::error::ErrorResponse;
+use crate::AppState;
+
+/// Recorded changes to jobs, devices, and webhooks, newest first (admin only)
+/// GET /audit
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    tag = "audit",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Audit entries, newest first", body = AuditListResponse),
+        (status = 403, description = "Admin only", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Response {
+    match state.audit.query(&query).await {
+        Ok(entries) => Json(AuditListResponse {
+            count: entries.len(),
+            entries,
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to read audit log");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::with_code(e.to_string(), "DATABASE_ERROR")),
+            )
+                .into_response()
+        }
+    }
+}
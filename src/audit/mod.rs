@@ -0,0 +1,6 @@
+pub mod handlers;
+mod models;
+mod service;
+
+pub use models::{AuditAction, AuditEntry, AuditQuery, AuditResource};
+pub use service::{changes, snapshot, AuditActor, AuditLog};
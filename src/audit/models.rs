@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Kinds of resource whose changes are audited
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditResource {
+    Job,
+    Device,
+    Webhook,
+    Location,
+}
+
+/// One recorded change
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: i64,
+    pub action: AuditAction,
+    pub resource: AuditResource,
+    pub resource_id: String,
+    /// Signed-in user, when JWT auth is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    /// Name of the API key presented ("device" for the device API key)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    /// The resource as created or deleted, or `{field: {from, to}}` for updates
+    pub changes: serde_json::Value,
+}
+
+/// Filters for GET /audit
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub resource: Option<AuditResource>,
+    pub resource_id: Option<String>,
+    /// Only changes made by this user
+    pub actor: Option<String>,
+    /// Only entries older than this entry id, for paging back
+    pub before: Option<i64>,
+    /// Max entries (default 100, max 500)
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditListResponse {
+    pub entries: Vec<AuditEntry>,
+    pub count: usize,
+}
@@ -0,0 +1,177 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+
+use super::models::{AuditAction, AuditEntry, AuditQuery, AuditResource};
+use crate::auth::Principal;
+use crate::db::{AuditRepository, DbError, SqliteAuditRepository};
+use crate::AppState;
+
+/// Entries returned when no limit is requested
+const DEFAULT_LIMIT: u32 = 100;
+
+/// Largest limit a caller may request
+const MAX_LIMIT: u32 = 500;
+
+/// Bookkeeping fields that change on every write and would drown out the
+/// real changes
+const IGNORED_FIELDS: &[&str] = &["updated_at", "last_seen"];
+
+/// Who made a change: the signed-in user, the API key presented, and the
+/// client's IP
+#[derive(Debug, Clone, Default)]
+pub struct AuditActor {
+    pub user: Option<String>,
+    pub api_key: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl FromRequestParts<AppState> for AuditActor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let provided = parts.headers.get("X-API-Key").and_then(|v| v.to_str().ok());
+
+        Ok(Self {
+            user: parts
+                .extensions
+                .get::<Principal>()
+                .map(|p| p.username.clone()),
+            api_key: state.client_keys.key_name(provided),
+            ip: client_ip(parts),
+        })
+    }
+}
+
+/// The client's IP: the first `X-Forwarded-For` hop or `X-Real-IP` when
+/// behind a proxy, else the peer address
+fn client_ip(parts: &Parts) -> Option<String> {
+    let header = |name: &str| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    header("x-forwarded-for")
+        .or_else(|| header("x-real-ip"))
+        .or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        })
+}
+
+/// The full serialized resource, recorded for creates and deletes
+pub fn snapshot<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// What changed between two versions of a resource: `{field: {from, to}}`
+/// for each top-level field that differs
+pub fn changes<T: Serialize>(before: &T, after: &T) -> Value {
+    let (Value::Object(before), Value::Object(after)) = (snapshot(before), snapshot(after)) else {
+        return Value::Null;
+    };
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    let diff: Map<String, Value> = fields
+        .into_iter()
+        .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let from = before.get(field).unwrap_or(&Value::Null);
+            let to = after.get(field).unwrap_or(&Value::Null);
+            (from != to).then(|| (field.clone(), serde_json::json!({ "from": from, "to": to })))
+        })
+        .collect();
+    Value::Object(diff)
+}
+
+/// Record of who created, changed, or deleted jobs, devices, and webhooks
+pub struct AuditLog {
+    repo: SqliteAuditRepository,
+}
+
+impl AuditLog {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            repo: SqliteAuditRepository::new(pool),
+        }
+    }
+
+    /// Record a change. Updates that changed nothing are skipped. A failure
+    /// is logged rather than failing the change itself, which has already
+    /// been made.
+    pub async fn record(
+        &self,
+        actor: &AuditActor,
+        action: AuditAction,
+        resource: AuditResource,
+        resource_id: &str,
+        changes: Value,
+    ) {
+        if action == AuditAction::Update && changes.as_object().is_some_and(Map::is_empty) {
+            return;
+        }
+        let entry = AuditEntry {
+            id: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+            action,
+            resource,
+            resource_id: resource_id.to_string(),
+            actor: actor.user.clone(),
+            api_key: actor.api_key.clone(),
+            ip: actor.ip.clone(),
+            changes,
+        };
+        if let Err(e) = self.repo.record(&entry).await {
+            tracing::error!(
+                error = %e,
+                resource = ?resource,
+                resource_id = %resource_id,
+                "Failed to record audit entry"
+            );
+        }
+    }
+
+    /// Entries matching the query, newest first
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, DbError> {
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        self.repo.query(query, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_changes_lists_differing_fields() {
+        let before = json!({"name": "Storm alerts", "enabled": true, "updated_at": 1, "cron": "0 0 7 * * *"});
+        let after = json!({"name": "Storm alerts", "enabled": false, "updated_at": 2, "owner": "alice", "cron": "0 0 7 * * *"});
+
+        assert_eq!(
+            changes(&before, &after),
+            json!({
+                "enabled": {"from": true, "to": false},
+                "owner": {"from": null, "to": "alice"}
+            })
+        );
+        assert_eq!(changes(&before, &before), json!({}));
+    }
+}
@@ -0,0 +1,63 @@
+use axum::{extract::State, Extension, Json};
+use subtle::ConstantTimeEq;
+
+use super::models::{AuthError, LoginRequest, LoginResponse, Principal};
+use crate::error::ErrorResponse;
+use crate::AppState;
+
+/// Exchange a configured username and password for a bearer token
+/// POST /auth/login
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Bearer token", body = LoginResponse),
+        (status = 401, description = "Invalid credentials", body = ErrorResponse)
+    )
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, AuthError> {
+    let keys = state.jwt_keys.as_ref().ok_or(AuthError::Disabled)?;
+
+    let user = state
+        .config
+        .auth
+        .users
+        .iter()
+        .find(|u| u.username == request.username)
+        .filter(|u| bool::from(u.password.as_bytes().ct_eq(request.password.as_bytes())));
+    let Some(user) = user else {
+        tracing::warn!(username = %request.username, "Failed login attempt");
+        return Err(AuthError::InvalidCredentials);
+    };
+
+    let token = keys.issue(&user.username, user.role, chrono::Utc::now().timestamp());
+    tracing::info!(username = %user.username, "Issued access token");
+
+    Ok(Json(LoginResponse {
+        access_token: token,
+        token_type: "Bearer",
+        expires_in: keys.ttl_secs(),
+    }))
+}
+
+/// The caller's identity from their token
+/// GET /auth/me
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The caller's identity", body = Principal),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn me(principal: Option<Extension<Principal>>) -> Result<Json<Principal>, AuthError> {
+    let Extension(principal) = principal.ok_or(AuthError::Disabled)?;
+    Ok(Json(principal))
+}
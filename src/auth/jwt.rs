@@ -0,0 +1,133 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::models::{AuthError, Claims, Principal, Role};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// `iss` claim on every token this server issues
+const ISSUER: &str = "weathrs";
+
+/// Base64url of `{"alg":"HS256","typ":"JWT"}`
+const HEADER: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+
+/// HS256 signing key and token lifetime for issuing and checking JWTs
+pub struct JwtKeys {
+    secret: Vec<u8>,
+    ttl_secs: u64,
+}
+
+impl JwtKeys {
+    pub fn new(secret: &str, ttl_secs: u64) -> Self {
+        Self {
+            secret: secret.as_bytes().to_vec(),
+            ttl_secs,
+        }
+    }
+
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl_secs
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length")
+    }
+
+    /// Issue a signed token for `username`
+    pub fn issue(&self, username: &str, role: Role, now: i64) -> String {
+        let claims = Claims {
+            sub: username.to_string(),
+            role,
+            iss: ISSUER.to_string(),
+            iat: now,
+            exp: now + self.ttl_secs as i64,
+        };
+        let payload =
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).expect("claims serialize"));
+        let signing_input = format!("{}.{}", HEADER, payload);
+
+        let mut mac = self.mac();
+        mac.update(signing_input.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+        format!("{}.{}", signing_input, signature)
+    }
+
+    /// Check a token's signature, issuer, and expiry
+    pub fn verify(&self, token: &str, now: i64) -> Result<Principal, AuthError> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(AuthError::InvalidToken("malformed"));
+        };
+
+        let header: serde_json::Value = URL_SAFE_NO_PAD
+            .decode(header)
+            .ok()
+            .and_then(|h| serde_json::from_slice(&h).ok())
+            .ok_or(AuthError::InvalidToken("malformed header"))?;
+        if header.get("alg").and_then(|a| a.as_str()) != Some("HS256") {
+            return Err(AuthError::InvalidToken("unsupported algorithm"));
+        }
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AuthError::InvalidToken("malformed signature"))?;
+        let signed_len = token.len() - token.rsplit('.').next().map_or(0, str::len) - 1;
+        let mut mac = self.mac();
+        mac.update(&token.as_bytes()[..signed_len]);
+        mac.verify_slice(&signature)
+            .map_err(|_| AuthError::InvalidToken("bad signature"))?;
+
+        let claims: Claims = URL_SAFE_NO_PAD
+            .decode(payload)
+            .ok()
+            .and_then(|p| serde_json::from_slice(&p).ok())
+            .ok_or(AuthError::InvalidToken("malformed claims"))?;
+        if claims.iss != ISSUER {
+            return Err(AuthError::InvalidToken("unknown issuer"));
+        }
+        if claims.exp <= now {
+            return Err(AuthError::ExpiredToken);
+        }
+
+        Ok(claims.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_and_verify() {
+        let keys = JwtKeys::new("0123456789abcdef0123456789abcdef", 3600);
+        let token = keys.issue("alice", Role::Admin, 1_700_000_000);
+
+        let principal = keys.verify(&token, 1_700_000_100).unwrap();
+        assert_eq!(principal.username, "alice");
+        assert!(principal.is_admin());
+
+        assert!(matches!(
+            keys.verify(&token, 1_700_003_600),
+            Err(AuthError::ExpiredToken)
+        ));
+
+        let other = JwtKeys::new("another-secret-another-secret-xx", 3600);
+        assert!(matches!(
+            other.verify(&token, 1_700_000_100),
+            Err(AuthError::InvalidToken("bad signature"))
+        ));
+
+        // Tampered claims no longer match the signature
+        let mut parts: Vec<&str> = token.split('.').collect();
+        let forged = URL_SAFE_NO_PAD
+            .encode(r#"{"sub":"mallory","role":"admin","iss":"weathrs","iat":0,"exp":9999999999}"#);
+        parts[1] = &forged;
+        assert!(keys.verify(&parts.join("."), 1_700_000_100).is_err());
+        assert!(keys.verify("not-a-token", 0).is_err());
+    }
+}
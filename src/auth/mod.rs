@@ -0,0 +1,6 @@
+pub mod handlers;
+mod jwt;
+pub mod models;
+
+pub use jwt::JwtKeys;
+pub use models::{AuthError, Principal, Role};
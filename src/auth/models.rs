@@ -0,0 +1,149 @@
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::error::HttpError;
+use crate::impl_into_response;
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+    #[error("Authentication is not enabled")]
+    Disabled,
+
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+
+    #[error("Bearer token required. Provide an Authorization: Bearer <token> header.")]
+    MissingToken,
+
+    #[error("Invalid token: {0}")]
+    InvalidToken(&'static str),
+
+    #[error("Token has expired")]
+    ExpiredToken,
+
+    #[error("Admin role required")]
+    Forbidden,
+}
+
+impl HttpError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Disabled => StatusCode::NOT_FOUND,
+            Self::InvalidCredentials
+            | Self::MissingToken
+            | Self::InvalidToken(_)
+            | Self::ExpiredToken => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::Disabled => Some("AUTH_DISABLED"),
+            Self::InvalidCredentials => Some("INVALID_CREDENTIALS"),
+            Self::MissingToken => Some("MISSING_TOKEN"),
+            Self::InvalidToken(_) => Some("INVALID_TOKEN"),
+            Self::ExpiredToken => Some("EXPIRED_TOKEN"),
+            Self::Forbidden => Some("FORBIDDEN"),
+        }
+    }
+}
+
+impl_into_response!(AuthError);
+
+/// What a user may do. Admins can manage everything; users only their own
+/// jobs and devices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    #[default]
+    User,
+}
+
+/// JWT claims issued by `/auth/login`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// Username
+    pub sub: String,
+    pub role: Role,
+    pub iss: String,
+    /// Issued at (unix seconds)
+    pub iat: i64,
+    /// Expires at (unix seconds)
+    pub exp: i64,
+}
+
+/// The authenticated caller, attached to requests that carried a valid token
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Principal {
+    pub username: String,
+    pub role: Role,
+}
+
+impl Principal {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    /// Owner to filter listings by; `None` for admins, who see everything
+    pub fn owner_filter(&self) -> Option<String> {
+        (!self.is_admin()).then(|| self.username.clone())
+    }
+
+    /// Whether this principal may see or change something owned by `owner`.
+    /// Unowned (shared) jobs and devices are admin-only.
+    pub fn can_access(&self, owner: Option<&str>) -> bool {
+        self.is_admin() || owner == Some(self.username.as_str())
+    }
+}
+
+impl From<Claims> for Principal {
+    fn from(claims: Claims) -> Self {
+        Self {
+            username: claims.sub,
+            role: claims.role,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginResponse {
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Seconds until the token expires
+    pub expires_in: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_principal_access() {
+        let alice = Principal {
+            username: "alice".to_string(),
+            role: Role::User,
+        };
+        assert!(alice.can_access(Some("alice")));
+        assert!(!alice.can_access(Some("bob")));
+        assert!(!alice.can_access(None));
+        assert_eq!(alice.owner_filter().as_deref(), Some("alice"));
+
+        let admin = Principal {
+            username: "root".to_string(),
+            role: Role::Admin,
+        };
+        assert!(admin.can_access(Some("bob")));
+        assert!(admin.can_access(None));
+        assert_eq!(admin.owner_filter(), None);
+    }
+}
@@ -1,3 +1,3 @@
 mod runner;
 
-pub use runner::schedule_backfill_job;
+pub use runner::{build_city_list, schedule_backfill_job};
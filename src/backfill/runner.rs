@@ -3,7 +3,7 @@ use std::sync::Arc;
 use indexmap::IndexSet;
 use tokio_cron_scheduler::Job;
 
-use crate::api_budget::ApiCallBudget;
+use crate::api_budget::{ApiCallBudget, BudgetFeature};
 use crate::config::HistoryBackfillConfig;
 use crate::devices::DevicesService;
 use crate::geocode::models::make_location_key;
@@ -13,37 +13,48 @@ use crate::scheduler::{SchedulerError, SchedulerService};
 /// Build a deduplicated, priority-ordered list of cities to backfill.
 ///
 /// Priority order:
-/// 1. First city from each enabled device (= "my location")
-/// 2. Remaining device cities
-/// 3. Cities from enabled scheduler jobs
-/// 4. Fallback cities from config
-fn build_city_list(
+/// 1. Reported coordinates of each enabled device (= "my location")
+/// 2. First city from each enabled device without coordinates
+/// 3. Remaining device cities
+/// 4. Cities from enabled scheduler jobs
+/// 5. Fallback cities from config
+pub fn build_city_list(
     devices: &[crate::devices::models::Device],
     jobs: &[crate::scheduler::ForecastJob],
     fallback: &[String],
 ) -> Vec<String> {
     let mut set = IndexSet::new();
 
-    // 1) First city from each enabled device
+    // 1) Reported coordinates from each enabled device
     for device in devices.iter().filter(|d| d.enabled) {
+        if let Some(location) = device.location_query() {
+            set.insert(location);
+        }
+    }
+
+    // 2) First city from each enabled device without coordinates
+    for device in devices
+        .iter()
+        .filter(|d| d.enabled && d.coordinates().is_none())
+    {
         if let Some(first) = device.cities.first() {
             set.insert(first.clone());
         }
     }
 
-    // 2) Remaining cities from enabled devices
+    // 3) Remaining cities from enabled devices
     for device in devices.iter().filter(|d| d.enabled) {
         for city in &device.cities {
             set.insert(city.clone());
         }
     }
 
-    // 3) Cities from enabled scheduler jobs
+    // 4) Cities from enabled scheduler jobs
     for job in jobs.iter().filter(|j| j.enabled) {
         set.insert(job.city.clone());
     }
 
-    // 4) Fallback cities
+    // 5) Fallback cities
     for city in fallback {
         set.insert(city.clone());
     }
@@ -80,12 +91,14 @@ async fn run_backfill(
         "Starting history backfill"
     );
 
+    // Calls beyond the backfill budget are left for user-facing requests
+    let reserve = budget.daily_limit().saturating_sub(config.daily_budget);
+
     let mut total_inserted: usize = 0;
-    let units = "metric";
     let mut seen_locations = IndexSet::new();
 
     for city in &cities {
-        if budget.remaining() == 0 {
+        if budget.remaining() <= reserve || budget.remaining_for(BudgetFeature::Backfill) == 0 {
             tracing::info!("Backfill: daily budget exhausted");
             break;
         }
@@ -113,7 +126,7 @@ async fn run_backfill(
 
         // Get missing days
         let missing_days = match history_service
-            .get_missing_days(&location_key, start_ts, end_ts, units)
+            .get_missing_days(&location_key, start_ts, end_ts)
             .await
         {
             Ok(days) => days,
@@ -138,8 +151,12 @@ async fn run_backfill(
         let mut city_inserted: usize = 0;
 
         for day_ts in &missing_days {
+            if budget.remaining() <= reserve || budget.remaining_for(BudgetFeature::Backfill) == 0 {
+                tracing::info!(city = %city_name, "Backfill: budget exhausted mid-city");
+                break;
+            }
             match history_service
-                .fetch_day_if_budget(&city_name, &location, *day_ts, units)
+                .fetch_day_if_budget(&city_name, &location, *day_ts)
                 .await
             {
                 Ok(Some(count)) => {
@@ -174,6 +191,7 @@ async fn run_backfill(
     if total_inserted > 0 {
         metrics::counter!(crate::metrics::BACKFILL_DAYS_FETCHED).increment(total_inserted as u64);
     }
+    history_service.mark_backfill_finished(chrono::Utc::now().timestamp());
 
     tracing::info!(
         total_inserted = total_inserted,
@@ -205,6 +223,9 @@ pub async fn schedule_backfill_job(
         let budget = Arc::clone(&budget);
 
         Box::pin(async move {
+            let Some(_run) = scheduler_service.begin_run() else {
+                return;
+            };
             tracing::info!("Backfill job triggered");
             run_backfill(
                 &history_service,
@@ -223,3 +244,39 @@ pub async fn schedule_backfill_job(
     tracing::info!("History backfill job scheduled");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::models::Device;
+
+    fn create_test_device(cities: &[&str], location: Option<(f64, f64)>) -> Device {
+        let mut device: Device = serde_json::from_value(serde_json::json!({
+            "id": "device-1",
+            "token": "ExponentPushToken[test]",
+            "platform": "ios",
+            "cities": cities,
+            "registered_at": 1700000000,
+            "updated_at": 1700000000
+        }))
+        .unwrap();
+        device.lat = location.map(|l| l.0);
+        device.lon = location.map(|l| l.1);
+        device
+    }
+
+    #[test]
+    fn test_build_city_list_prefers_device_coordinates() {
+        let devices = vec![
+            create_test_device(&["Chicago", "London"], Some((41.8781, -87.6298))),
+            create_test_device(&["Paris"], None),
+        ];
+        let fallback = vec!["Tokyo".to_string()];
+
+        let cities = build_city_list(&devices, &[], &fallback);
+        assert_eq!(
+            cities,
+            vec!["41.88,-87.63", "Paris", "Chicago", "London", "Tokyo"]
+        );
+    }
+}
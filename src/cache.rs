@@ -1,57 +1,274 @@
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-
-/// A thread-safe cache with TTL (time-to-live) support
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use utoipa::ToSchema;
+
+use crate::cache_backend::SharedCache;
+use crate::config::CacheConfig;
+
+/// A thread-safe cache with TTL (time-to-live) support and an optional
+/// entry cap with least-recently-used eviction.
+///
+/// Hits, misses, evictions, and approximate memory use are counted and
+/// exported to Prometheus under the cache's name.
 pub struct TtlCache<K, V> {
+    name: &'static str,
     data: DashMap<K, CacheEntry<V>>,
     ttl: Duration,
+    /// Entry cap, 0 for unbounded
+    max_entries: usize,
+    /// Logical clock stamped on entries when they are used
+    clock: AtomicU64,
+    counters: CacheCounters,
 }
 
 struct CacheEntry<V> {
     value: V,
     expires_at: Instant,
+    /// Approximate bytes used by the key and value
+    size: usize,
+    last_used: AtomicU64,
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    bytes: AtomicUsize,
+}
+
+/// Effectiveness and size of one cache
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub ttl_secs: u64,
+    /// Entry cap, 0 for unbounded
+    pub max_entries: usize,
+    /// Entries held, including expired ones kept for stale fallback
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Share of lookups answered from the cache (0 when there were none)
+    pub hit_ratio: f64,
+    pub evictions: u64,
+    /// Approximate memory used by keys and values, measured as their JSON size
+    pub approx_bytes: usize,
+}
+
+/// Approximate in-memory size of a value: the length of its JSON encoding
+fn approx_size<T: Serialize>(value: &T) -> usize {
+    struct Counter(usize);
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
 }
 
 impl<K, V> TtlCache<K, V>
 where
-    K: std::hash::Hash + Eq + Clone,
-    V: Clone,
+    K: std::hash::Hash + Eq + Clone + Serialize,
+    V: Clone + Serialize,
 {
-    /// Create a new cache with the specified TTL
-    pub fn new(ttl: Duration) -> Self {
+    /// Create a new cache with the specified TTL. `name` labels its metrics.
+    pub fn new(name: &'static str, ttl: Duration) -> Self {
         Self {
+            name,
             data: DashMap::new(),
             ttl,
+            max_entries: 0,
+            clock: AtomicU64::new(0),
+            counters: CacheCounters::default(),
         }
     }
 
+    /// Cap the cache at `max_entries` (0 for unbounded)
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
     /// Get a value from the cache if it exists and hasn't expired
     pub fn get(&self, key: &K) -> Option<V> {
-        let entry = self.data.get(key)?;
-        if entry.expires_at > Instant::now() {
-            Some(entry.value.clone())
-        } else {
-            drop(entry);
-            self.data.remove(key);
-            None
-        }
+        let fresh = self
+            .data
+            .get(key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                entry.value.clone()
+            });
+        let (counter, metric) = match fresh {
+            Some(_) => (&self.counters.hits, crate::metrics::TTL_CACHE_HITS),
+            None => (&self.counters.misses, crate::metrics::TTL_CACHE_MISSES),
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        metrics::counter!(metric, "cache" => self.name).increment(1);
+        fresh
+    }
+
+    /// Get a value even if it has expired, for when a fresh one can't be
+    /// fetched. Expired entries are kept until overwritten or `cleanup`.
+    pub fn get_stale(&self, key: &K) -> Option<V> {
+        self.data.get(key).map(|entry| {
+            entry.last_used.store(self.tick(), Ordering::Relaxed);
+            entry.value.clone()
+        })
     }
 
     /// Insert a value into the cache
     pub fn insert(&self, key: K, value: V) {
+        let size = approx_size(&key) + approx_size(&value);
         let entry = CacheEntry {
             value,
             expires_at: Instant::now() + self.ttl,
+            size,
+            last_used: AtomicU64::new(self.tick()),
         };
-        self.data.insert(key, entry);
+        // Count the new entry before it can be replaced, so the total never
+        // dips below zero
+        self.counters.bytes.fetch_add(size, Ordering::Relaxed);
+        if let Some(old) = self.data.insert(key, entry) {
+            self.counters.bytes.fetch_sub(old.size, Ordering::Relaxed);
+        }
+        if self.max_entries > 0 && self.data.len() > self.max_entries {
+            self.evict_to_capacity();
+        }
+        self.update_gauges();
+    }
+
+    /// Trim to 90% of the cap, dropping expired entries first and then the
+    /// least recently used. Trimming below the cap means the scan runs once
+    /// per batch of inserts rather than on every one.
+    fn evict_to_capacity(&self) {
+        let target = self.max_entries - self.max_entries / 10;
+        let excess = self.data.len().saturating_sub(target);
+        if excess == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut candidates: Vec<(bool, u64, K)> = self
+            .data
+            .iter()
+            .map(|entry| {
+                (
+                    entry.expires_at > now,
+                    entry.last_used.load(Ordering::Relaxed),
+                    entry.key().clone(),
+                )
+            })
+            .collect();
+        candidates.sort_unstable_by_key(|(fresh, last_used, _)| (*fresh, *last_used));
+
+        let mut evicted = 0;
+        for (_, _, key) in candidates.into_iter().take(excess) {
+            if let Some((_, entry)) = self.data.remove(&key) {
+                self.counters.bytes.fetch_sub(entry.size, Ordering::Relaxed);
+                evicted += 1;
+            }
+        }
+        self.record_evictions(evicted, "capacity");
+    }
+
+    fn record_evictions(&self, evicted: u64, reason: &'static str) {
+        self.counters
+            .evictions
+            .fetch_add(evicted, Ordering::Relaxed);
+        metrics::counter!(
+            crate::metrics::TTL_CACHE_EVICTIONS,
+            "cache" => self.name,
+            "reason" => reason
+        )
+        .increment(evicted);
     }
 
     /// Remove expired entries from the cache
     pub fn cleanup(&self) {
         let now = Instant::now();
-        self.data.retain(|_, entry| entry.expires_at > now);
+        let mut evicted = 0;
+        let mut freed = 0;
+        self.data.retain(|_, entry| {
+            let keep = entry.expires_at > now;
+            if !keep {
+                evicted += 1;
+                freed += entry.size;
+            }
+            keep
+        });
+        if evicted > 0 {
+            self.counters.bytes.fetch_sub(freed, Ordering::Relaxed);
+            self.record_evictions(evicted, "expired");
+            self.update_gauges();
+        }
+    }
+
+    fn update_gauges(&self) {
+        metrics::gauge!(crate::metrics::TTL_CACHE_ENTRIES, "cache" => self.name)
+            .set(self.data.len() as f64);
+        metrics::gauge!(crate::metrics::TTL_CACHE_BYTES, "cache" => self.name)
+            .set(self.counters.bytes.load(Ordering::Relaxed) as f64);
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Time until the entry for `key` expires (zero once it has), or None
+    /// when there is no entry
+    pub fn expires_in(&self, key: &K) -> Option<Duration> {
+        self.data
+            .get(key)
+            .map(|entry| entry.expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Time since the entry for `key` was inserted, or None when there is
+    /// no entry
+    pub fn age(&self, key: &K) -> Option<Duration> {
+        self.data
+            .get(key)
+            .map(|entry| (Instant::now() + self.ttl).saturating_duration_since(entry.expires_at))
+    }
+
+    /// Hit, miss, eviction, and size counts since startup
+    pub fn stats(&self) -> CacheStats {
+        let hits = self.counters.hits.load(Ordering::Relaxed);
+        let misses = self.counters.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        CacheStats {
+            name: self.name,
+            ttl_secs: self.ttl.as_secs(),
+            max_entries: self.max_entries,
+            entries: self.data.len(),
+            hits,
+            misses,
+            hit_ratio: if lookups == 0 {
+                0.0
+            } else {
+                hits as f64 / lookups as f64
+            },
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            approx_bytes: self.counters.bytes.load(Ordering::Relaxed),
+        }
     }
 
     /// Get the number of entries in the cache (including expired ones)
@@ -70,7 +287,7 @@ where
 pub type GeoCache = Arc<GeoCacheWithDb>;
 
 /// Cached version of GeoLocation (needs Clone)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedGeoLocation {
     pub name: String,
     pub lat: f64,
@@ -79,27 +296,40 @@ pub struct CachedGeoLocation {
     pub state: Option<String>,
 }
 
-/// Default SQLite geocoding cache TTL: 7 days
-const GEO_CACHE_DB_TTL_SECS: i64 = 7 * 24 * 60 * 60;
-
-/// Geocoding cache backed by in-memory DashMap + SQLite persistence
+/// Geocoding cache backed by in-memory DashMap + SQLite persistence, with an
+/// optional shared layer between the two
 pub struct GeoCacheWithDb {
     memory: TtlCache<String, CachedGeoLocation>,
     pool: SqlitePool,
     db_ttl_secs: i64,
+    shared: Option<SharedCache>,
 }
 
 impl GeoCacheWithDb {
-    /// Create a new geocoding cache with in-memory TTL of 24 hours
-    pub fn new(pool: SqlitePool) -> Self {
+    /// Create a new geocoding cache with the configured in-memory and SQLite TTLs
+    pub fn new(pool: SqlitePool, config: &CacheConfig) -> Self {
         Self {
-            memory: TtlCache::new(Duration::from_secs(24 * 60 * 60)),
+            memory: TtlCache::new("geocode", Duration::from_secs(config.geocode_ttl_secs))
+                .with_max_entries(config.geocode_max_entries),
             pool,
-            db_ttl_secs: GEO_CACHE_DB_TTL_SECS,
+            db_ttl_secs: config.geocode_db_ttl_secs as i64,
+            shared: None,
         }
     }
 
-    /// Get a cached geo location: checks memory first, then SQLite
+    /// Also read and write a cache shared with other instances. Shared
+    /// entries live as long as SQLite ones.
+    pub fn with_shared(mut self, shared: SharedCache) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    fn shared_key(key: &str) -> String {
+        format!("geo:{}", key)
+    }
+
+    /// Get a cached geo location: checks memory first, then the shared
+    /// cache, then SQLite
     pub async fn get(&self, key: &str) -> Option<CachedGeoLocation> {
         // Check in-memory cache first
         if let Some(cached) = self.memory.get(&key.to_string()) {
@@ -107,6 +337,17 @@ impl GeoCacheWithDb {
             return Some(cached);
         }
 
+        if let Some(ref shared) = self.shared {
+            if let Some(location) = shared
+                .get_json::<CachedGeoLocation>(&Self::shared_key(key))
+                .await
+            {
+                self.memory.insert(key.to_string(), location.clone());
+                metrics::counter!(crate::metrics::CACHE_HITS, "layer" => "shared").increment(1);
+                return Some(location);
+            }
+        }
+
         // Check SQLite
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -135,8 +376,9 @@ impl GeoCacheWithDb {
                 country: row.country,
                 state: row.state,
             };
-            // Promote to in-memory cache
+            // Promote to in-memory cache, and share with other instances
             self.memory.insert(key.to_string(), location.clone());
+            self.set_shared(key, &location).await;
             metrics::counter!(crate::metrics::CACHE_HITS, "layer" => "sqlite").increment(1);
             tracing::debug!(key = %key, "Geocoding cache hit (SQLite)");
             Some(location)
@@ -146,10 +388,18 @@ impl GeoCacheWithDb {
         }
     }
 
-    /// Insert a geo location into both memory and SQLite caches
+    async fn set_shared(&self, key: &str, value: &CachedGeoLocation) {
+        if let Some(ref shared) = self.shared {
+            let ttl = Duration::from_secs(self.db_ttl_secs as u64);
+            shared.set_json(&Self::shared_key(key), value, ttl).await;
+        }
+    }
+
+    /// Insert a geo location into memory, shared, and SQLite caches
     pub async fn insert(&self, key: String, value: CachedGeoLocation) {
         // Insert into memory
         self.memory.insert(key.clone(), value.clone());
+        self.set_shared(&key, &value).await;
 
         // Insert into SQLite
         let now = SystemTime::now()
@@ -209,6 +459,11 @@ impl GeoCacheWithDb {
     pub fn memory_len(&self) -> usize {
         self.memory.len()
     }
+
+    /// Counters for the in-memory layer
+    pub fn stats(&self) -> CacheStats {
+        self.memory.stats()
+    }
 }
 
 #[derive(sqlx::FromRow)]
@@ -224,21 +479,51 @@ struct GeoCacheRow {
     cached_at: i64,
 }
 
-/// Normalize a location string for cache key
-/// Converts to lowercase and trims whitespace
+/// Normalize a location string for cache key, so spellings that differ only
+/// in case, accents, or spacing share an entry ("São  Paulo" and "sao paulo",
+/// "Zürich" and "ZURICH", "Straße" and "strasse")
 pub fn normalize_cache_key(location: &str) -> String {
-    location.trim().to_lowercase()
+    let folded: String = location
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .flat_map(|c| {
+            // Letters without a decomposition, folded by hand
+            let (first, second) = match c {
+                'ß' => ('s', Some('s')),
+                'æ' => ('a', Some('e')),
+                'œ' => ('o', Some('e')),
+                'þ' => ('t', Some('h')),
+                'ø' => ('o', None),
+                'ł' => ('l', None),
+                'đ' | 'ð' => ('d', None),
+                'ı' => ('i', None),
+                'ς' => ('σ', None),
+                c => (c, None),
+            };
+            std::iter::once(first).chain(second)
+        })
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 /// Create a geocoding cache backed by SQLite
-pub fn create_geo_cache(pool: SqlitePool) -> GeoCache {
-    Arc::new(GeoCacheWithDb::new(pool))
+pub fn create_geo_cache(
+    pool: SqlitePool,
+    config: &CacheConfig,
+    shared: Option<SharedCache>,
+) -> GeoCache {
+    let cache = GeoCacheWithDb::new(pool, config);
+    Arc::new(match shared {
+        Some(shared) => cache.with_shared(shared),
+        None => cache,
+    })
 }
 
-/// Start a background task that cleans up expired cache entries hourly
-pub fn start_cache_cleanup_task(cache: GeoCache) {
+/// Start a background task that cleans up expired cache entries every `period`
+pub fn start_cache_cleanup_task(cache: GeoCache, period: Duration) {
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(60 * 60)); // 1 hour
+        let mut interval = tokio::time::interval(period);
         loop {
             interval.tick().await;
             let before = cache.memory_len();
@@ -261,28 +546,36 @@ mod tests {
 
     #[test]
     fn test_cache_insert_and_get() {
-        let cache: TtlCache<String, String> = TtlCache::new(Duration::from_secs(60));
+        let cache: TtlCache<String, String> = TtlCache::new("test", Duration::from_secs(60));
         cache.insert("key".to_string(), "value".to_string());
         assert_eq!(cache.get(&"key".to_string()), Some("value".to_string()));
     }
 
     #[test]
     fn test_cache_miss() {
-        let cache: TtlCache<String, String> = TtlCache::new(Duration::from_secs(60));
+        let cache: TtlCache<String, String> = TtlCache::new("test", Duration::from_secs(60));
         assert_eq!(cache.get(&"missing".to_string()), None);
     }
 
     #[test]
     fn test_cache_ttl_expiry() {
-        let cache: TtlCache<String, String> = TtlCache::new(Duration::from_millis(1));
+        let cache: TtlCache<String, String> = TtlCache::new("test", Duration::from_millis(1));
         cache.insert("key".to_string(), "value".to_string());
         std::thread::sleep(Duration::from_millis(10));
         assert_eq!(cache.get(&"key".to_string()), None);
+        assert_eq!(
+            cache.get_stale(&"key".to_string()),
+            Some("value".to_string())
+        );
+        assert_eq!(cache.expires_in(&"key".to_string()), Some(Duration::ZERO));
+        assert_eq!(cache.expires_in(&"missing".to_string()), None);
+        assert!(cache.age(&"key".to_string()).unwrap() >= Duration::from_millis(10));
+        assert_eq!(cache.age(&"missing".to_string()), None);
     }
 
     #[test]
     fn test_cache_cleanup() {
-        let cache: TtlCache<String, String> = TtlCache::new(Duration::from_millis(1));
+        let cache: TtlCache<String, String> = TtlCache::new("test", Duration::from_millis(1));
         cache.insert("key1".to_string(), "value1".to_string());
         cache.insert("key2".to_string(), "value2".to_string());
         std::thread::sleep(Duration::from_millis(10));
@@ -290,10 +583,87 @@ mod tests {
         assert!(cache.is_empty());
     }
 
+    #[test]
+    fn test_lru_eviction_at_capacity() {
+        let cache: TtlCache<String, String> =
+            TtlCache::new("test", Duration::from_secs(60)).with_max_entries(2);
+        cache.insert("a".to_string(), "1".to_string());
+        cache.insert("b".to_string(), "2".to_string());
+        assert!(cache.get(&"a".to_string()).is_some());
+        cache.insert("c".to_string(), "3".to_string());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&"b".to_string()).is_none());
+        assert!(cache.get(&"a".to_string()).is_some());
+        assert!(cache.get(&"c".to_string()).is_some());
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().approx_bytes, 12);
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let cache: TtlCache<String, String> = TtlCache::new("test", Duration::from_millis(20));
+        cache.insert("key".to_string(), "value".to_string());
+        assert_eq!(cache.stats().approx_bytes, 12);
+        cache.insert("key".to_string(), "longer value".to_string());
+        assert_eq!(cache.stats().approx_bytes, 19);
+
+        assert!(cache.get(&"key".to_string()).is_some());
+        assert!(cache.get(&"missing".to_string()).is_none());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(&"key".to_string()).is_none());
+        cache.cleanup();
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 2, 1));
+        assert!((stats.hit_ratio - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!((stats.entries, stats.approx_bytes), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_geo_cache_shared_between_instances() {
+        let shared: SharedCache = Arc::new(crate::cache_backend::InMemoryBackend::default());
+        let pool = || async {
+            crate::db::create_pool(&crate::db::DbConfig {
+                url: "sqlite::memory:".to_string(),
+                max_connections: 1,
+            })
+            .await
+            .unwrap()
+        };
+        let config = CacheConfig::default();
+        let first = create_geo_cache(pool().await, &config, Some(shared.clone()));
+        let second = create_geo_cache(pool().await, &config, Some(shared));
+
+        let location = CachedGeoLocation {
+            name: "Chicago".to_string(),
+            lat: 41.88,
+            lon: -87.63,
+            country: "US".to_string(),
+            state: Some("Illinois".to_string()),
+        };
+        first.insert("chicago".to_string(), location).await;
+
+        let found = second.get("chicago").await.unwrap();
+        assert_eq!(found.name, "Chicago");
+        assert_eq!(second.memory_len(), 1);
+    }
+
     #[test]
     fn test_normalize_cache_key() {
         assert_eq!(normalize_cache_key("  Chicago  "), "chicago");
         assert_eq!(normalize_cache_key("NEW YORK"), "new york");
         assert_eq!(normalize_cache_key("London,GB"), "london,gb");
+        assert_eq!(normalize_cache_key("Zürich"), normalize_cache_key("ZURICH"));
+        assert_eq!(normalize_cache_key("São  Paulo, BR"), "sao paulo, br");
+        // Precomposed and combining-mark spellings of the same name
+        assert_eq!(
+            normalize_cache_key("Mal\u{e9}"),
+            normalize_cache_key("Male\u{301}")
+        );
+        assert_eq!(normalize_cache_key("Straße"), "strasse");
+        assert_eq!(normalize_cache_key("Tromsø"), "tromso");
+        assert_eq!(normalize_cache_key("ŁÓDŹ"), "lodz");
+        assert_eq!(normalize_cache_key("Αθήνα"), "αθηνα");
     }
 }
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::config::{CacheBackendKind, CacheConfig};
+
+/// Cache shared between weathrs instances, consulted after the in-memory
+/// caches miss and before calling upstream.
+///
+/// Best effort: backend failures are logged and read as misses, so an
+/// unreachable server costs upstream calls but never fails a request.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration);
+}
+
+pub type SharedCache = Arc<dyn CacheBackend>;
+
+impl dyn CacheBackend {
+    /// Read a JSON-encoded value. Undecodable entries (e.g. written by an
+    /// older version) count as misses.
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.get(key).await.and_then(|bytes| {
+            serde_json::from_slice(&bytes)
+                .inspect_err(|e| tracing::debug!(key = %key, error = %e, "Ignoring undecodable shared cache entry"))
+                .ok()
+        });
+        match value {
+            Some(_) => metrics::counter!(crate::metrics::SHARED_CACHE_HITS).increment(1),
+            None => metrics::counter!(crate::metrics::SHARED_CACHE_MISSES).increment(1),
+        }
+        value
+    }
+
+    /// Store a value as JSON for `ttl`
+    pub async fn set_json<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        match serde_json::to_vec(value) {
+            Ok(bytes) => self.set(key, bytes, ttl).await,
+            Err(e) => tracing::warn!(key = %key, error = %e, "Failed to encode shared cache entry"),
+        }
+    }
+}
+
+/// Redis-backed shared cache. Entries expire through Redis TTLs.
+pub struct RedisBackend {
+    conn: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisBackend {
+    /// Connect to `url`. The connection is re-established automatically if
+    /// it drops later.
+    pub async fn connect(url: &str, prefix: &str) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(url)?;
+        // Keep a slow Redis from stalling requests; a timeout is just a miss
+        let config = ConnectionManagerConfig::new()
+            .set_connection_timeout(Duration::from_secs(2))
+            .set_response_timeout(Duration::from_millis(500));
+        let conn = ConnectionManager::new_with_config(client, config).await?;
+        Ok(Self {
+            conn,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.prefix, key)
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisBackend {
+    async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut conn = self.conn.clone();
+        conn.get::<_, Option<Vec<u8>>>(self.key(key))
+            .await
+            .unwrap_or_else(|e| {
+                metrics::counter!(crate::metrics::SHARED_CACHE_ERRORS).increment(1);
+                tracing::warn!(error = %e, "Redis cache read failed");
+                None
+            })
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) {
+        let mut conn = self.conn.clone();
+        let secs = ttl.as_secs().max(1);
+        if let Err(e) = conn.set_ex::<_, _, ()>(self.key(key), value, secs).await {
+            metrics::counter!(crate::metrics::SHARED_CACHE_ERRORS).increment(1);
+            tracing::warn!(error = %e, "Redis cache write failed");
+        }
+    }
+}
+
+/// Connect the configured shared cache, if any
+pub async fn create_shared_cache(config: &CacheConfig) -> anyhow::Result<Option<SharedCache>> {
+    match config.backend {
+        CacheBackendKind::Memory => Ok(None),
+        CacheBackendKind::Redis => {
+            let url = config
+                .redis_url
+                .as_deref()
+                .context("cache.redis_url is required for the redis backend")?;
+            let backend = RedisBackend::connect(url, &config.redis_key_prefix)
+                .await
+                .context("Failed to connect to Redis")?;
+            tracing::info!(
+                prefix = %config.redis_key_prefix,
+                "Sharing geocode and forecast caches through Redis"
+            );
+            Ok(Some(Arc::new(backend)))
+        }
+    }
+}
+
+/// In-process stand-in for a shared backend, for tests
+#[cfg(test)]
+#[derive(Default)]
+pub struct InMemoryBackend {
+    data: dashmap::DashMap<String, Vec<u8>>,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl CacheBackend for InMemoryBackend {
+    async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.data.get(key).map(|v| v.clone())
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, _ttl: Duration) {
+        self.data.insert(key.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_json_round_trip_and_bad_entries() {
+        let backend: SharedCache = Arc::new(InMemoryBackend::default());
+        backend
+            .set_json("geo:chicago", &vec![41.88, -87.63], Duration::from_secs(60))
+            .await;
+        assert_eq!(
+            backend.get_json::<Vec<f64>>("geo:chicago").await,
+            Some(vec![41.88, -87.63])
+        );
+
+        backend
+            .set("geo:bad", b"not json".to_vec(), Duration::from_secs(60))
+            .await;
+        assert_eq!(backend.get_json::<Vec<f64>>("geo:bad").await, None);
+        assert_eq!(backend.get_json::<Vec<f64>>("geo:missing").await, None);
+    }
+}
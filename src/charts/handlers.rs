@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+};
+
+use super::models::{ChartError, ChartQuery};
+use super::render::{render_png, ChartData, ChartPoint};
+use crate::error::ErrorResponse;
+use crate::extractors::UnitsParam;
+use crate::units::Units;
+use crate::AppState;
+
+/// Render a temperature and precipitation chart for a city
+///
+/// Recorded history over `period`, followed by the 48-hour hourly forecast
+/// when OpenWeatherMap can supply it. Meant for notification attachments,
+/// e-ink displays, and READMEs.
+///
+/// GET /charts/{city}/temperature.png?period=7d&units={units}&width={px}&height={px}
+#[utoipa::path(
+    get,
+    path = "/api/v1/charts/{city}/temperature.png",
+    tag = "charts",
+    params(("city" = String, Path, description = "City name"), ChartQuery, ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)")),
+    responses(
+        (status = 200, description = "PNG chart", content_type = "image/png", body = Vec<u8>),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found or nothing to chart", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse)
+    )
+)]
+pub async fn get_temperature_chart(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+    Query(query): Query<ChartQuery>,
+    UnitsParam(units): UnitsParam,
+) -> Result<(HeaderMap, Vec<u8>), ChartError> {
+    let days = query.period_days()?;
+    let (width, height) = query.size();
+    let units = units.unwrap_or_else(|| state.default_units(&city));
+    let now = chrono::Utc::now().timestamp();
+
+    let history = state
+        .history_service
+        .get_history(&city, Some(now - days * 86400), Some(now), units.as_str())
+        .await?;
+    // History alone still makes a chart
+    let forecast = state
+        .forecast_service
+        .get_hourly_forecast(&city, units.as_str())
+        .await
+        .inspect_err(|e| tracing::warn!(city = %city, error = %e, "Charting without forecast"))
+        .ok();
+
+    let recorded: Vec<ChartPoint> = history
+        .data_points
+        .iter()
+        .map(|p| ChartPoint {
+            timestamp: p.timestamp,
+            temperature: p.temperature,
+            precipitation: p.rain_1h.unwrap_or(0.0) + p.snow_1h.unwrap_or(0.0),
+        })
+        .collect();
+    let last_recorded = recorded.last().map_or(i64::MIN, |p| p.timestamp);
+    let data = ChartData {
+        title: format!("{}, past {}d and forecast", history.city, days),
+        temperature_unit: match units {
+            Units::Metric => "°C",
+            Units::Imperial => "°F",
+            Units::Standard => "K",
+        },
+        timezone: forecast
+            .as_ref()
+            .and_then(|f| f.timezone.parse().ok())
+            .unwrap_or(chrono_tz::UTC),
+        now,
+        recorded,
+        forecast: forecast
+            .iter()
+            .flat_map(|f| &f.hourly)
+            .filter(|h| h.timestamp > last_recorded)
+            .map(|h| ChartPoint {
+                timestamp: h.timestamp,
+                temperature: h.temperature,
+                precipitation: h.rain_volume.unwrap_or(0.0) + h.snow_volume.unwrap_or(0.0),
+            })
+            .collect(),
+    };
+
+    let png_bytes = tokio::task::spawn_blocking(move || render_png(&data, width, height))
+        .await
+        .map_err(|e| ChartError::Render(e.to_string()))??;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/png"));
+    Ok((headers, png_bytes))
+}
@@ -0,0 +1,121 @@
+use axum::http::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+
+use crate::error::HttpError;
+use crate::history::HistoryError;
+use crate::impl_into_response;
+
+/// Days of history charted when no period is given
+pub const DEFAULT_PERIOD_DAYS: i64 = 7;
+
+/// Longest period a chart covers
+const MAX_PERIOD_DAYS: i64 = 30;
+
+#[derive(Error, Debug)]
+pub enum ChartError {
+    #[error("Invalid period: {0}")]
+    InvalidPeriod(String),
+
+    #[error("No history or forecast data to chart for {0}")]
+    NoData(String),
+
+    #[error("Failed to render chart: {0}")]
+    Render(String),
+
+    #[error(transparent)]
+    History(#[from] HistoryError),
+}
+
+impl HttpError for ChartError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidPeriod(_) => StatusCode::BAD_REQUEST,
+            Self::NoData(_) => StatusCode::NOT_FOUND,
+            Self::Render(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::History(e) => e.status_code(),
+        }
+    }
+
+    fn error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidPeriod(_) => Some("INVALID_PERIOD"),
+            Self::NoData(_) => Some("NO_CHART_DATA"),
+            Self::Render(_) => Some("CHART_RENDER_FAILED"),
+            Self::History(e) => e.error_code(),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::History(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+}
+
+impl_into_response!(ChartError);
+
+/// Query parameters for chart images
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ChartQuery {
+    /// History to chart before the forecast, 1d to 30d (default 7d)
+    pub period: Option<String>,
+    /// Image width in pixels (default 800, 200 to 2000)
+    pub width: Option<u32>,
+    /// Image height in pixels (default 400, 150 to 1200)
+    pub height: Option<u32>,
+}
+
+impl ChartQuery {
+    /// Days of history requested
+    pub fn period_days(&self) -> Result<i64, ChartError> {
+        let Some(period) = self.period.as_deref() else {
+            return Ok(DEFAULT_PERIOD_DAYS);
+        };
+        period
+            .trim()
+            .strip_suffix('d')
+            .and_then(|days| days.parse::<i64>().ok())
+            .filter(|days| (1..=MAX_PERIOD_DAYS).contains(days))
+            .ok_or_else(|| {
+                ChartError::InvalidPeriod(format!(
+                    "period must be 1d to {}d (got {:?})",
+                    MAX_PERIOD_DAYS, period
+                ))
+            })
+    }
+
+    /// Requested image size, clamped to what the endpoint renders
+    pub fn size(&self) -> (u32, u32) {
+        (
+            self.width.unwrap_or(800).clamp(200, 2000),
+            self.height.unwrap_or(400).clamp(150, 1200),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(period: Option<&str>) -> ChartQuery {
+        ChartQuery {
+            period: period.map(str::to_string),
+            width: Some(5000),
+            height: None,
+        }
+    }
+
+    #[test]
+    fn test_period_and_size() {
+        assert_eq!(query(None).period_days().unwrap(), 7);
+        assert_eq!(query(Some("14d")).period_days().unwrap(), 14);
+        assert!(query(Some("0d")).period_days().is_err());
+        assert!(query(Some("31d")).period_days().is_err());
+        assert!(query(Some("1w")).period_days().is_err());
+        assert_eq!(query(None).size(), (2000, 400));
+    }
+}
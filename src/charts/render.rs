@@ -0,0 +1,240 @@
+use std::sync::Once;
+
+use chrono_tz::Tz;
+use plotters::prelude::*;
+use plotters::style::FontStyle;
+
+use super::models::ChartError;
+
+/// Bundled so charts render the same without system fonts
+static FONT: &[u8] = include_bytes!("../../static/fonts/DejaVuSansMono.ttf");
+
+const RECORDED_COLOR: RGBColor = RGBColor(33, 102, 172);
+const FORECAST_COLOR: RGBColor = RGBColor(214, 96, 77);
+const PRECIPITATION_COLOR: RGBColor = RGBColor(67, 147, 195);
+
+/// One hour on a chart
+#[derive(Debug, Clone, Copy)]
+pub struct ChartPoint {
+    pub timestamp: i64,
+    pub temperature: f64,
+    /// Rain plus snow over the hour, mm
+    pub precipitation: f64,
+}
+
+/// Everything drawn on a temperature chart
+pub struct ChartData {
+    pub title: String,
+    /// "°C", "°F", or "K"
+    pub temperature_unit: &'static str,
+    /// Axis labels are in the location's local time
+    pub timezone: Tz,
+    /// Marked with a vertical line between recorded and forecast data
+    pub now: i64,
+    pub recorded: Vec<ChartPoint>,
+    pub forecast: Vec<ChartPoint>,
+}
+
+fn register_font() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        if plotters::style::register_font("sans-serif", FontStyle::Normal, FONT).is_err() {
+            tracing::error!("Bundled chart font failed to load");
+        }
+    });
+}
+
+fn render_err(e: impl std::fmt::Display) -> ChartError {
+    ChartError::Render(e.to_string())
+}
+
+/// Draw temperature (top two thirds) and hourly precipitation (bottom third)
+/// as a `width` x `height` PNG
+pub fn render_png(data: &ChartData, width: u32, height: u32) -> Result<Vec<u8>, ChartError> {
+    register_font();
+    let points: Vec<&ChartPoint> = data.recorded.iter().chain(&data.forecast).collect();
+    let (Some(first), Some(last)) = (
+        points.iter().map(|p| p.timestamp).min(),
+        points.iter().map(|p| p.timestamp).max(),
+    ) else {
+        return Err(ChartError::NoData(data.title.clone()));
+    };
+    // f64 seconds: plotters' integer coordinates overflow on Unix timestamps
+    let x_range = first as f64..(last + 3600) as f64;
+    let (low, high) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| {
+        (lo.min(p.temperature), hi.max(p.temperature))
+    });
+    let temp_range = (low - 2.0).floor()..(high + 2.0).ceil();
+    let max_precipitation = points.iter().map(|p| p.precipitation).fold(1.0, f64::max);
+
+    let day_labels = last - first > 2 * 86400;
+    let tz = data.timezone;
+    let x_label = |ts: &f64| {
+        chrono::DateTime::from_timestamp(*ts as i64, 0)
+            .map(|t| {
+                let local = t.with_timezone(&tz);
+                if day_labels {
+                    local.format("%a %-d").to_string()
+                } else {
+                    local.format("%a %H:%M").to_string()
+                }
+            })
+            .unwrap_or_default()
+    };
+
+    let mut pixels = vec![0u8; (width * height * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+        root.fill(&WHITE).map_err(render_err)?;
+        let area = root
+            .titled(&data.title, ("sans-serif", 16))
+            .map_err(render_err)?;
+        let split = area.dim_in_pixel().1 * 2 / 3;
+        let (upper, lower) = area.split_vertically(split);
+
+        let mut temperature = ChartBuilder::on(&upper)
+            .margin(8)
+            .y_label_area_size(52)
+            .build_cartesian_2d(x_range.clone(), temp_range.clone())
+            .map_err(render_err)?;
+        temperature
+            .configure_mesh()
+            .disable_x_mesh()
+            .light_line_style(TRANSPARENT)
+            .x_labels(0)
+            .y_labels(5)
+            .y_desc(data.temperature_unit)
+            .label_style(("sans-serif", 12))
+            .draw()
+            .map_err(render_err)?;
+        temperature
+            .draw_series(LineSeries::new(
+                data.recorded
+                    .iter()
+                    .map(|p| (p.timestamp as f64, p.temperature)),
+                RECORDED_COLOR.stroke_width(2),
+            ))
+            .map_err(render_err)?
+            .label("Recorded")
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 16, y)], RECORDED_COLOR));
+        temperature
+            .draw_series(LineSeries::new(
+                data.forecast
+                    .iter()
+                    .map(|p| (p.timestamp as f64, p.temperature)),
+                FORECAST_COLOR.stroke_width(2),
+            ))
+            .map_err(render_err)?
+            .label("Forecast")
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 16, y)], FORECAST_COLOR));
+        let now = data.now as f64;
+        if x_range.contains(&now) {
+            temperature
+                .draw_series(std::iter::once(PathElement::new(
+                    vec![(now, temp_range.start), (now, temp_range.end)],
+                    BLACK.mix(0.4),
+                )))
+                .map_err(render_err)?;
+        }
+        temperature
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK.mix(0.3))
+            .label_font(("sans-serif", 12))
+            .draw()
+            .map_err(render_err)?;
+
+        let mut precipitation = ChartBuilder::on(&lower)
+            .margin(8)
+            .x_label_area_size(24)
+            .y_label_area_size(52)
+            .build_cartesian_2d(x_range, 0.0..max_precipitation * 1.1)
+            .map_err(render_err)?;
+        precipitation
+            .configure_mesh()
+            .disable_x_mesh()
+            .light_line_style(TRANSPARENT)
+            .x_labels(6)
+            .y_labels(3)
+            .x_label_formatter(&x_label)
+            .y_desc("mm")
+            .label_style(("sans-serif", 12))
+            .draw()
+            .map_err(render_err)?;
+        precipitation
+            .draw_series(points.iter().filter(|p| p.precipitation > 0.0).map(|p| {
+                Rectangle::new(
+                    [
+                        (p.timestamp as f64, 0.0),
+                        ((p.timestamp + 3600) as f64, p.precipitation),
+                    ],
+                    PRECIPITATION_COLOR.filled(),
+                )
+            }))
+            .map_err(render_err)?;
+
+        root.present().map_err(render_err)?;
+    }
+
+    encode_png(&pixels, width, height)
+}
+
+/// PNG-encode an RGB buffer
+fn encode_png(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, ChartError> {
+    let mut png_bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(render_err)?;
+    writer.write_image_data(pixels).map_err(render_err)?;
+    writer.finish().map_err(render_err)?;
+    Ok(png_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(recorded: Vec<ChartPoint>) -> ChartData {
+        ChartData {
+            title: "Chicago".to_string(),
+            temperature_unit: "°C",
+            timezone: chrono_tz::America::Chicago,
+            now: 1_700_000_000,
+            recorded,
+            forecast: (1..=24)
+                .map(|h| ChartPoint {
+                    timestamp: 1_700_000_000 + h * 3600,
+                    temperature: 10.0 + h as f64 / 4.0,
+                    precipitation: if h % 5 == 0 { 1.2 } else { 0.0 },
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_render_png() {
+        let recorded = (0..72)
+            .map(|h| ChartPoint {
+                timestamp: 1_700_000_000 - h * 3600,
+                temperature: 8.0 + (h % 24) as f64 / 3.0,
+                precipitation: 0.0,
+            })
+            .collect();
+        let png_bytes = render_png(&chart(recorded), 640, 320).unwrap();
+        assert_eq!(&png_bytes[..8], b"\x89PNG\r\n\x1a\n");
+        // IHDR width and height
+        assert_eq!(&png_bytes[16..24], &[0, 0, 2, 128, 0, 0, 1, 64]);
+    }
+
+    #[test]
+    fn test_render_requires_data() {
+        let mut data = chart(vec![]);
+        data.forecast.clear();
+        assert!(matches!(
+            render_png(&data, 640, 320),
+            Err(ChartError::NoData(_))
+        ));
+    }
+}
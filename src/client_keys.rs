@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::api_budget::{day_number, seconds_until_midnight};
+use crate::config::ClientKeysConfig;
+use crate::error::HttpError;
+use crate::impl_into_response;
+
+#[derive(Error, Debug)]
+pub enum ClientKeyError {
+    #[error("API key required. Provide X-API-Key header.")]
+    Missing,
+
+    #[error("Invalid API key")]
+    Invalid,
+
+    #[error("Daily request quota for API key {name:?} exceeded")]
+    QuotaExceeded { name: String, retry_after: u64 },
+}
+
+impl HttpError for ClientKeyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Missing | Self::Invalid => StatusCode::UNAUTHORIZED,
+            Self::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    fn error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::Missing => Some("MISSING_API_KEY"),
+            Self::Invalid => Some("INVALID_API_KEY"),
+            Self::QuotaExceeded { .. } => Some("CLIENT_QUOTA_EXCEEDED"),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::QuotaExceeded { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
+}
+
+impl_into_response!(ClientKeyError);
+
+/// Today's usage of one client key
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientKeyUsage {
+    pub name: String,
+    pub used_today: u32,
+    pub rejected_today: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_today: Option<u32>,
+}
+
+/// Routes listed per key in usage statistics
+const TOP_ROUTES: usize = 5;
+
+/// Requests made with one key since startup
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyTraffic {
+    /// Client key name, "device" for the device API key, or "anonymous" for
+    /// requests without a recognized key
+    pub name: String,
+    pub requests: u64,
+    pub client_errors: u64,
+    pub server_errors: u64,
+    /// Share of requests answered with a 4xx or 5xx status
+    pub error_rate: f64,
+    /// Busiest routes, most requests first
+    pub top_routes: Vec<RouteCount>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteCount {
+    pub route: String,
+    pub requests: u64,
+}
+
+#[derive(Default)]
+struct TrafficCounts {
+    requests: u64,
+    client_errors: u64,
+    server_errors: u64,
+    routes: HashMap<String, u64>,
+}
+
+/// API keys handed out to other clients, each with its own daily request
+/// quota. Counts reset at midnight in the API budget's reset timezone.
+///
+/// The device API key belongs to the instance owner and is never metered.
+pub struct ClientKeys {
+    keys: Vec<ClientKey>,
+    owner_key: Option<String>,
+    required: bool,
+    timezone: Tz,
+    /// Requests and responses per key name since startup
+    traffic: Mutex<HashMap<String, TrafficCounts>>,
+}
+
+struct ClientKey {
+    name: String,
+    key: String,
+    daily_limit: Option<u32>,
+    usage: Mutex<DailyUsage>,
+}
+
+#[derive(Default)]
+struct DailyUsage {
+    day: i64,
+    used: u32,
+    rejected: u32,
+}
+
+impl DailyUsage {
+    fn roll_over(&mut self, today: i64) {
+        if self.day != today {
+            *self = Self {
+                day: today,
+                ..Default::default()
+            };
+        }
+    }
+}
+
+/// Who a request's `X-API-Key` header identifies
+enum KeyMatch {
+    /// Configured client key, by index
+    Client(usize),
+    /// The device API key
+    Owner,
+    /// No header, or a key that isn't configured
+    Unknown,
+}
+
+impl ClientKeys {
+    pub fn new(config: &ClientKeysConfig, owner_key: Option<String>, timezone: Tz) -> Self {
+        Self {
+            keys: config
+                .keys
+                .iter()
+                .map(|k| ClientKey {
+                    name: k.name.clone(),
+                    key: k.key.clone(),
+                    daily_limit: k.daily_limit,
+                    usage: Mutex::new(DailyUsage::default()),
+                })
+                .collect(),
+            owner_key,
+            required: config.required,
+            timezone,
+            traffic: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Identify the key a request presented
+    fn identify(&self, provided: Option<&str>) -> KeyMatch {
+        let Some(provided) = provided else {
+            return KeyMatch::Unknown;
+        };
+        let matches = |key: &str| bool::from(key.as_bytes().ct_eq(provided.as_bytes()));
+
+        if self.owner_key.as_deref().is_some_and(matches) {
+            return KeyMatch::Owner;
+        }
+        self.keys
+            .iter()
+            .position(|k| matches(&k.key))
+            .map_or(KeyMatch::Unknown, KeyMatch::Client)
+    }
+
+    /// Check a request's key and count it against that key's quota. A key
+    /// that matches none is rejected even when keys are optional, so a wrong
+    /// or revoked key can't slip past its quota; only requests with no key
+    /// may be anonymous.
+    pub fn admit(&self, provided: Option<&str>, now: DateTime<Utc>) -> Result<(), ClientKeyError> {
+        match self.identify(provided) {
+            KeyMatch::Client(index) => self.record(index, now),
+            KeyMatch::Owner => Ok(()),
+            KeyMatch::Unknown if provided.is_some() => Err(ClientKeyError::Invalid),
+            KeyMatch::Unknown if self.required => Err(ClientKeyError::Missing),
+            KeyMatch::Unknown => Ok(()),
+        }
+    }
+
+    fn record(&self, index: usize, now: DateTime<Utc>) -> Result<(), ClientKeyError> {
+        let key = &self.keys[index];
+        let mut usage = key.usage.lock().unwrap();
+        usage.roll_over(day_number(now, self.timezone));
+
+        if key.daily_limit.is_some_and(|limit| usage.used >= limit) {
+            usage.rejected += 1;
+            metrics::counter!(crate::metrics::CLIENT_KEY_REJECTIONS, "key" => key.name.clone())
+                .increment(1);
+            return Err(ClientKeyError::QuotaExceeded {
+                name: key.name.clone(),
+                retry_after: seconds_until_midnight(now, self.timezone),
+            });
+        }
+
+        usage.used += 1;
+        metrics::counter!(crate::metrics::CLIENT_KEY_REQUESTS, "key" => key.name.clone())
+            .increment(1);
+        Ok(())
+    }
+
+    /// Today's usage for every configured key
+    pub fn usage(&self, now: DateTime<Utc>) -> Vec<ClientKeyUsage> {
+        let today = day_number(now, self.timezone);
+        self.keys
+            .iter()
+            .map(|key| {
+                let mut usage = key.usage.lock().unwrap();
+                usage.roll_over(today);
+                ClientKeyUsage {
+                    name: key.name.clone(),
+                    used_today: usage.used,
+                    rejected_today: usage.rejected,
+                    daily_limit: key.daily_limit,
+                    remaining_today: key.daily_limit.map(|l| l.saturating_sub(usage.used)),
+                }
+            })
+            .collect()
+    }
+
+    /// Name of the key a request presented, for the audit log: a client
+    /// key's name, or "device" for the device API key
+    pub fn key_name(&self, provided: Option<&str>) -> Option<String> {
+        match self.identify(provided) {
+            KeyMatch::Client(index) => Some(self.keys[index].name.clone()),
+            KeyMatch::Owner => Some("device".to_string()),
+            KeyMatch::Unknown => None,
+        }
+    }
+
+    /// Count a finished request against the key it presented. `route` is the
+    /// matched route template, so path parameters don't split the counts.
+    pub fn record_response(&self, provided: Option<&str>, route: &str, status: StatusCode) {
+        let name = self
+            .key_name(provided)
+            .unwrap_or_else(|| "anonymous".to_string());
+        let mut traffic = self.traffic.lock().unwrap();
+        let counts = traffic.entry(name).or_default();
+        counts.requests += 1;
+        if status.is_client_error() {
+            counts.client_errors += 1;
+        } else if status.is_server_error() {
+            counts.server_errors += 1;
+        }
+        *counts.routes.entry(route.to_string()).or_default() += 1;
+    }
+
+    /// Requests per key since startup, busiest key first
+    pub fn traffic(&self) -> Vec<KeyTraffic> {
+        let traffic = self.traffic.lock().unwrap();
+        let mut keys: Vec<KeyTraffic> = traffic
+            .iter()
+            .map(|(name, counts)| {
+                let mut top_routes: Vec<RouteCount> = counts
+                    .routes
+                    .iter()
+                    .map(|(route, &requests)| RouteCount {
+                        route: route.clone(),
+                        requests,
+                    })
+                    .collect();
+                top_routes.sort_by(|a, b| b.requests.cmp(&a.requests).then(a.route.cmp(&b.route)));
+                top_routes.truncate(TOP_ROUTES);
+
+                let errors = counts.client_errors + counts.server_errors;
+                KeyTraffic {
+                    name: name.clone(),
+                    requests: counts.requests,
+                    client_errors: counts.client_errors,
+                    server_errors: counts.server_errors,
+                    error_rate: if counts.requests == 0 {
+                        0.0
+                    } else {
+                        errors as f64 / counts.requests as f64
+                    },
+                    top_routes,
+                }
+            })
+            .collect();
+        keys.sort_by(|a, b| b.requests.cmp(&a.requests).then(a.name.cmp(&b.name)));
+        keys
+    }
+
+    /// Whether requests without a valid key are rejected
+    pub fn required(&self) -> bool {
+        self.required
+    }
+
+    pub fn reset_timezone(&self) -> Tz {
+        self.timezone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClientKeyConfig;
+    use chrono::TimeZone;
+
+    fn client_keys(required: bool) -> ClientKeys {
+        let config = ClientKeysConfig {
+            required,
+            keys: vec![
+                ClientKeyConfig {
+                    name: "friend".to_string(),
+                    key: "friend-key".to_string(),
+                    daily_limit: Some(2),
+                },
+                ClientKeyConfig {
+                    name: "kiosk".to_string(),
+                    key: "kiosk-key".to_string(),
+                    daily_limit: None,
+                },
+            ],
+        };
+        ClientKeys::new(&config, Some("owner-key".to_string()), Tz::UTC)
+    }
+
+    #[test]
+    fn test_quota_resets_daily() {
+        let keys = client_keys(false);
+        let day1 = Utc.with_ymd_and_hms(2024, 6, 1, 23, 0, 0).unwrap();
+
+        assert!(keys.admit(Some("friend-key"), day1).is_ok());
+        assert!(keys.admit(Some("friend-key"), day1).is_ok());
+        match keys.admit(Some("friend-key"), day1) {
+            Err(ClientKeyError::QuotaExceeded { retry_after, .. }) => {
+                assert_eq!(retry_after, 3600)
+            }
+            other => panic!("expected quota error, got {:?}", other),
+        }
+        // Unlimited and owner keys are never rejected
+        for _ in 0..5 {
+            assert!(keys.admit(Some("kiosk-key"), day1).is_ok());
+            assert!(keys.admit(Some("owner-key"), day1).is_ok());
+        }
+
+        let usage = keys.usage(day1);
+        assert_eq!(usage[0].used_today, 2);
+        assert_eq!(usage[0].rejected_today, 1);
+        assert_eq!(usage[0].remaining_today, Some(0));
+        assert_eq!(usage[1].used_today, 5);
+
+        let day2 = day1 + chrono::Duration::hours(2);
+        assert!(keys.admit(Some("friend-key"), day2).is_ok());
+        assert_eq!(keys.usage(day2)[0].used_today, 1);
+    }
+
+    #[test]
+    fn test_required_rejects_unknown_keys() {
+        let now = Utc::now();
+        assert!(client_keys(false).admit(None, now).is_ok());
+        assert!(matches!(
+            client_keys(false).admit(Some("nope"), now),
+            Err(ClientKeyError::Invalid)
+        ));
+
+        let keys = client_keys(true);
+        assert!(matches!(
+            keys.admit(None, now),
+            Err(ClientKeyError::Missing)
+        ));
+        assert!(matches!(
+            keys.admit(Some("nope"), now),
+            Err(ClientKeyError::Invalid)
+        ));
+        assert!(keys.admit(Some("owner-key"), now).is_ok());
+
+        assert_eq!(keys.key_name(Some("kiosk-key")).as_deref(), Some("kiosk"));
+        assert_eq!(keys.key_name(Some("owner-key")).as_deref(), Some("device"));
+        assert_eq!(keys.key_name(Some("nope")), None);
+    }
+
+    #[test]
+    fn test_traffic_per_key() {
+        let keys = client_keys(false);
+        let forecast = "/api/v1/forecast/{city}";
+        keys.record_response(Some("kiosk-key"), forecast, StatusCode::OK);
+        keys.record_response(Some("kiosk-key"), forecast, StatusCode::OK);
+        keys.record_response(
+            Some("kiosk-key"),
+            "/api/v1/weather/{city}",
+            StatusCode::NOT_FOUND,
+        );
+        keys.record_response(
+            Some("kiosk-key"),
+            forecast,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+        keys.record_response(Some("owner-key"), "/api/v1/devices", StatusCode::OK);
+        keys.record_response(Some("nope"), forecast, StatusCode::OK);
+
+        let traffic = keys.traffic();
+        let names: Vec<&str> = traffic.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, ["kiosk", "anonymous", "device"]);
+
+        let kiosk = &traffic[0];
+        assert_eq!(kiosk.requests, 4);
+        assert_eq!(kiosk.client_errors, 1);
+        assert_eq!(kiosk.server_errors, 1);
+        assert_eq!(kiosk.error_rate, 0.5);
+        assert_eq!(kiosk.top_routes[0].route, forecast);
+        assert_eq!(kiosk.top_routes[0].requests, 3);
+        assert_eq!(kiosk.top_routes.len(), 2);
+    }
+}
@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use rust_embed::Embed;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::devices::models::DeliveryRecord;
+use crate::stats::{api_budget_stats, ApiBudgetStats};
+use crate::weather::service::WeatherResponse;
+use crate::AppState;
+
+/// Notifications shown in the dashboard's recent list
+const RECENT_NOTIFICATIONS: u32 = 20;
+
+/// Dashboard page, script, and styles, embedded in the binary
+#[derive(Embed)]
+#[folder = "static/dashboard/"]
+struct Assets;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardResponse {
+    pub generated_at: i64,
+    pub weather: Vec<CityWeather>,
+    pub upcoming_runs: Vec<UpcomingRun>,
+    pub recent_notifications: Vec<DeliveryRecord>,
+    pub api_budget: ApiBudgetStats,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CityWeather {
+    pub city: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weather: Option<WeatherResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpcomingRun {
+    pub job_id: String,
+    pub name: String,
+    pub city: String,
+    pub next_run: i64,
+}
+
+fn serve_asset(path: &str) -> Response {
+    match Assets::get(path) {
+        Some(file) => (
+            [(header::CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data,
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Dashboard page
+/// GET /ui
+pub async fn index() -> Response {
+    serve_asset("index.html")
+}
+
+/// Dashboard script and styles
+/// GET /ui/{*path}
+pub async fn asset(Path(path): Path<String>) -> Response {
+    serve_asset(&path)
+}
+
+/// Everything the dashboard shows, in one request
+/// GET /api/v1/dashboard
+#[utoipa::path(
+    get,
+    path = "/api/v1/dashboard",
+    tag = "stats",
+    responses((status = 200, description = "Dashboard data", body = DashboardResponse)),
+    security(("bearer" = []))
+)]
+pub async fn get_dashboard(State(state): State<AppState>) -> Json<DashboardResponse> {
+    let features = &state.config.features;
+    let jobs = if features.scheduler {
+        state.scheduler_service.get_jobs().await
+    } else {
+        Vec::new()
+    };
+
+    let mut cities = state.config.dashboard.cities.clone();
+    if cities.is_empty() {
+        for job in jobs.iter().filter(|j| j.enabled) {
+            if !cities.iter().any(|c| c.eq_ignore_ascii_case(&job.city)) {
+                cities.push(job.city.clone());
+            }
+        }
+    }
+
+    let mut weather = Vec::with_capacity(cities.len());
+    for city in cities {
+        let result = state
+            .weather_service
+            .get_weather(&city, &state.config.units)
+            .await;
+        weather.push(match result {
+            Ok(current) => CityWeather {
+                city,
+                weather: Some(current),
+                error: None,
+            },
+            Err(e) => CityWeather {
+                city,
+                weather: None,
+                error: Some(e.to_string()),
+            },
+        });
+    }
+
+    let upcoming_runs = if features.scheduler {
+        state.scheduler_service.upcoming_runs().await
+    } else {
+        Vec::new()
+    };
+    let upcoming_runs = upcoming_runs
+        .into_iter()
+        .map(|(job, next_run)| UpcomingRun {
+            job_id: job.id,
+            name: job.name,
+            city: job.city,
+            next_run,
+        })
+        .collect();
+
+    Json(DashboardResponse {
+        generated_at: chrono::Utc::now().timestamp(),
+        weather,
+        upcoming_runs,
+        recent_notifications: if features.devices {
+            state
+                .devices_service
+                .recent_notifications(RECENT_NOTIFICATIONS)
+                .await
+        } else {
+            Vec::new()
+        },
+        api_budget: api_budget_stats(&state),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets_are_embedded() {
+        for path in ["index.html", "app.js", "style.css"] {
+            assert!(Assets::get(path).is_some(), "missing {}", path);
+        }
+        assert_eq!(serve_asset("missing.js").status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::forecast::models::{AlertResponse, AlertSeverity};
+
+use super::DbError;
+
+/// A weather alert as stored in the alert history
+#[derive(Debug, Clone)]
+pub struct AlertHistoryRecord {
+    pub sender: String,
+    pub event: String,
+    pub severity: AlertSeverity,
+    pub start: i64,
+    pub end: i64,
+    pub description: String,
+    pub first_seen: i64,
+}
+
+/// Repository trait for observed weather alerts
+#[async_trait]
+pub trait AlertHistoryRepository: Send + Sync {
+    /// Store alerts seen for a location. Alerts already stored (same sender,
+    /// event and start) get their end time, description and last-seen updated.
+    async fn record(
+        &self,
+        location_key: &str,
+        city: &str,
+        alerts: &[AlertResponse],
+        seen_at: i64,
+    ) -> Result<(), DbError>;
+
+    /// Alerts for a location that were in effect at any point in the range, oldest first
+    async fn get_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<AlertHistoryRecord>, DbError>;
+}
+
+/// SQLite implementation of AlertHistoryRepository
+pub struct SqliteAlertHistoryRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAlertHistoryRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Internal row structure for SQLite queries
+#[derive(sqlx::FromRow)]
+struct AlertHistoryRow {
+    sender: String,
+    event: String,
+    severity: String,
+    start_ts: i64,
+    end_ts: i64,
+    description: String,
+    first_seen: i64,
+}
+
+#[async_trait]
+impl AlertHistoryRepository for SqliteAlertHistoryRepository {
+    async fn record(
+        &self,
+        location_key: &str,
+        city: &str,
+        alerts: &[AlertResponse],
+        seen_at: i64,
+    ) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+        for alert in alerts {
+            let severity = serde_json::to_value(alert.severity)?;
+            sqlx::query(
+                "INSERT INTO alert_history (location_key, city, sender, event, severity, start_ts, end_ts, description, first_seen, last_seen)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(location_key, sender, event, start_ts) DO UPDATE SET
+                    end_ts = excluded.end_ts,
+                    severity = excluded.severity,
+                    description = excluded.description,
+                    last_seen = excluded.last_seen",
+            )
+            .bind(location_key)
+            .bind(city)
+            .bind(&alert.sender)
+            .bind(&alert.event)
+            .bind(severity.as_str().unwrap_or_default())
+            .bind(alert.start)
+            .bind(alert.end)
+            .bind(&alert.description)
+            .bind(seen_at)
+            .bind(seen_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<AlertHistoryRecord>, DbError> {
+        let rows: Vec<AlertHistoryRow> = sqlx::query_as(
+            "SELECT sender, event, severity, start_ts, end_ts, description, first_seen
+             FROM alert_history
+             WHERE location_key = ? AND start_ts <= ? AND (end_ts >= ? OR end_ts = 0)
+             ORDER BY start_ts ASC, id ASC",
+        )
+        .bind(location_key)
+        .bind(end_ts)
+        .bind(start_ts)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(AlertHistoryRecord {
+                    severity: serde_json::from_value(serde_json::Value::String(row.severity))?,
+                    sender: row.sender,
+                    event: row.event,
+                    start: row.start_ts,
+                    end: row.end_ts,
+                    description: row.description,
+                    first_seen: row.first_seen,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+
+    async fn setup_test_db() -> SqlitePool {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        pool
+    }
+
+    fn alert(event: &str, start: i64, end: i64) -> AlertResponse {
+        AlertResponse::new(
+            "NWS Chicago".to_string(),
+            event.to_string(),
+            start,
+            end,
+            "Details".to_string(),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_record_dedupes_and_extends() {
+        let pool = setup_test_db().await;
+        let repo = SqliteAlertHistoryRepository::new(pool);
+
+        repo.record(
+            "41.88,-87.63",
+            "Chicago",
+            &[alert("Wind Advisory", 1000, 2000)],
+            1000,
+        )
+        .await
+        .unwrap();
+        // Same alert seen again with a later end time
+        repo.record(
+            "41.88,-87.63",
+            "Chicago",
+            &[alert("Wind Advisory", 1000, 3000)],
+            1500,
+        )
+        .await
+        .unwrap();
+        repo.record(
+            "41.88,-87.63",
+            "Chicago",
+            &[alert("Wind Advisory", 5000, 6000)],
+            5000,
+        )
+        .await
+        .unwrap();
+
+        let all = repo.get_range("41.88,-87.63", 0, 10000).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].end, 3000);
+        assert_eq!(all[0].first_seen, 1000);
+        assert_eq!(all[0].severity, AlertSeverity::Moderate);
+
+        // Overlap: the first alert ran until 3000
+        let overlapping = repo.get_range("41.88,-87.63", 2500, 4000).await.unwrap();
+        assert_eq!(overlapping.len(), 1);
+        assert_eq!(overlapping[0].start, 1000);
+
+        assert!(repo
+            .get_range("51.51,-0.13", 0, 10000)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use crate::forecast::models::AlertResponse;
+
+use super::DbError;
+
+/// Hex characters of the SHA-256 digest kept for alert ids
+const ID_LEN: usize = 16;
+
+fn short_hash(input: &str) -> String {
+    Sha256::digest(input.as_bytes())
+        .iter()
+        .take(ID_LEN / 2)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Stable id of an alert at a location: a hash of the location key, sender,
+/// event, start and end. A re-issued alert with a new end time gets a new id.
+pub fn alert_fingerprint(
+    location_key: &str,
+    sender: &str,
+    event: &str,
+    start: i64,
+    end: i64,
+) -> String {
+    short_hash(&format!(
+        "{}|{}|{}|{}|{}",
+        location_key, sender, event, start, end
+    ))
+}
+
+/// Hash of the parts of an alert whose change is worth notifying again
+fn alert_revision(alert: &AlertResponse) -> String {
+    short_hash(&format!(
+        "{:?}|{}|{}",
+        alert.severity,
+        alert.description.trim(),
+        alert.instruction.as_deref().unwrap_or_default().trim()
+    ))
+}
+
+/// A weather alert as last notified for a location
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct NotifiedAlert {
+    pub id: String,
+    pub location_key: String,
+    pub city: String,
+    pub sender: String,
+    pub event: String,
+    #[sqlx(rename = "start_ts")]
+    pub start: i64,
+    #[sqlx(rename = "end_ts")]
+    pub end: i64,
+    pub revision: String,
+    pub first_notified: i64,
+    pub last_notified: i64,
+    pub acknowledged_at: Option<i64>,
+}
+
+/// One device's notification of an alert
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct AlertDelivery {
+    pub alert_id: String,
+    pub device_id: String,
+    /// Alert revision the device was sent
+    pub revision: String,
+    pub delivered_at: i64,
+}
+
+/// Which devices a location's active alerts have reached, and which of those
+/// alerts are acknowledged
+#[derive(Debug, Clone, Default)]
+pub struct AlertLedger {
+    location_key: String,
+    acknowledged: HashSet<String>,
+    /// Revision delivered, by (alert id, device id)
+    delivered: HashMap<(String, String), String>,
+}
+
+impl AlertLedger {
+    pub fn new(
+        location_key: &str,
+        notified: &[NotifiedAlert],
+        deliveries: Vec<AlertDelivery>,
+    ) -> Self {
+        Self {
+            location_key: location_key.to_string(),
+            acknowledged: notified
+                .iter()
+                .filter(|n| n.acknowledged_at.is_some())
+                .map(|n| n.id.clone())
+                .collect(),
+            delivered: deliveries
+                .into_iter()
+                .map(|d| ((d.alert_id, d.device_id), d.revision))
+                .collect(),
+        }
+    }
+
+    /// A ledger with nothing delivered or acknowledged yet
+    pub fn empty(location_key: &str) -> Self {
+        Self::new(location_key, &[], Vec::new())
+    }
+
+    pub fn location_key(&self) -> &str {
+        &self.location_key
+    }
+
+    /// The alert's id at this ledger's location
+    pub fn id_of(&self, alert: &AlertResponse) -> String {
+        alert_fingerprint(
+            &self.location_key,
+            &alert.sender,
+            &alert.event,
+            alert.start,
+            alert.end,
+        )
+    }
+
+    /// Whether an alert still needs notifying to a device: it hasn't been
+    /// acknowledged, and the device hasn't been sent it, or was sent it
+    /// before a material change
+    pub fn needs_notifying(&self, alert: &AlertResponse, device_id: &str) -> bool {
+        let id = self.id_of(alert);
+        if self.acknowledged.contains(&id) {
+            return false;
+        }
+        self.delivered
+            .get(&(id, device_id.to_string()))
+            .is_none_or(|revision| *revision != alert_revision(alert))
+    }
+}
+
+/// Repository trait for notified weather alerts
+#[async_trait]
+pub trait AlertRepository: Send + Sync {
+    /// Alerts notified for a location that hadn't ended as of `now`
+    async fn get_active(&self, location_key: &str, now: i64)
+        -> Result<Vec<NotifiedAlert>, DbError>;
+
+    /// Deliveries of the alerts `get_active` returns
+    async fn get_deliveries(
+        &self,
+        location_key: &str,
+        now: i64,
+    ) -> Result<Vec<AlertDelivery>, DbError>;
+
+    /// Active alerts at a location and the devices they have reached
+    async fn get_ledger(&self, location_key: &str, now: i64) -> Result<AlertLedger, DbError> {
+        let notified = self.get_active(location_key, now).await?;
+        let deliveries = self.get_deliveries(location_key, now).await?;
+        Ok(AlertLedger::new(location_key, &notified, deliveries))
+    }
+
+    /// Record an alert at a location as delivered to `device_ids` at `now`,
+    /// keeping its first notification time and any acknowledgement
+    async fn record_delivered(
+        &self,
+        location_key: &str,
+        city: &str,
+        alert: &AlertResponse,
+        device_ids: &[String],
+        now: i64,
+    ) -> Result<(), DbError>;
+
+    /// Devices sent any issue of an alert at a location: the same sender and
+    /// event, notified for a span reaching `alert`'s start
+    async fn get_recipients(
+        &self,
+        location_key: &str,
+        alert: &AlertResponse,
+    ) -> Result<Vec<String>, DbError>;
+
+    /// Silence an alert for everyone. Returns false if there is no such alert.
+    async fn acknowledge(&self, id: &str, at: i64) -> Result<bool, DbError>;
+}
+
+/// SQLite implementation of AlertRepository
+pub struct SqliteAlertRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAlertRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+const ALERT_COLUMNS: &str = "id, location_key, city, sender, event, start_ts, end_ts, revision, \
+    first_notified, last_notified, acknowledged_at";
+
+#[async_trait]
+impl AlertRepository for SqliteAlertRepository {
+    async fn get_active(
+        &self,
+        location_key: &str,
+        now: i64,
+    ) -> Result<Vec<NotifiedAlert>, DbError> {
+        let alerts = sqlx::query_as(&format!(
+            "SELECT {} FROM alerts WHERE location_key = ? AND (end_ts > ? OR end_ts = 0)",
+            ALERT_COLUMNS
+        ))
+        .bind(location_key)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(alerts)
+    }
+
+    async fn get_deliveries(
+        &self,
+        location_key: &str,
+        now: i64,
+    ) -> Result<Vec<AlertDelivery>, DbError> {
+        let deliveries = sqlx::query_as(
+            "SELECT d.alert_id, d.device_id, d.revision, d.delivered_at
+             FROM alert_deliveries d JOIN alerts a ON a.id = d.alert_id
+             WHERE a.location_key = ? AND (a.end_ts > ? OR a.end_ts = 0)",
+        )
+        .bind(location_key)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(deliveries)
+    }
+
+    async fn record_delivered(
+        &self,
+        location_key: &str,
+        city: &str,
+        alert: &AlertResponse,
+        device_ids: &[String],
+        now: i64,
+    ) -> Result<(), DbError> {
+        if device_ids.is_empty() {
+            return Ok(());
+        }
+        let id = alert_fingerprint(
+            location_key,
+            &alert.sender,
+            &alert.event,
+            alert.start,
+            alert.end,
+        );
+        let revision = alert_revision(alert);
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO alerts (id, location_key, city, sender, event, start_ts, end_ts, revision, first_notified, last_notified)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                revision = excluded.revision,
+                last_notified = excluded.last_notified",
+        )
+        .bind(&id)
+        .bind(location_key)
+        .bind(city)
+        .bind(&alert.sender)
+        .bind(&alert.event)
+        .bind(alert.start)
+        .bind(alert.end)
+        .bind(&revision)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        for device_id in device_ids {
+            sqlx::query(
+                "INSERT INTO alert_deliveries (alert_id, device_id, revision, delivered_at)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT(alert_id, device_id) DO UPDATE SET
+                    revision = excluded.revision,
+                    delivered_at = excluded.delivered_at",
+            )
+            .bind(&id)
+            .bind(device_id)
+            .bind(&revision)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_recipients(
+        &self,
+        location_key: &str,
+        alert: &AlertResponse,
+    ) -> Result<Vec<String>, DbError> {
+        let device_ids = sqlx::query_scalar(
+            "SELECT DISTINCT d.device_id
+             FROM alert_deliveries d JOIN alerts a ON a.id = d.alert_id
+             WHERE a.location_key = ? AND a.sender = ? AND a.event = ?
+                AND (a.end_ts >= ? OR a.end_ts = 0)
+             ORDER BY d.device_id",
+        )
+        .bind(location_key)
+        .bind(&alert.sender)
+        .bind(&alert.event)
+        .bind(alert.start)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(device_ids)
+    }
+
+    async fn acknowledge(&self, id: &str, at: i64) -> Result<bool, DbError> {
+        let result = sqlx::query(
+            "UPDATE alerts SET acknowledged_at = COALESCE(acknowledged_at, ?) WHERE id = ?",
+        )
+        .bind(at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+    use crate::forecast::models::AlertSeverity;
+
+    #[tokio::test]
+    async fn test_alerts_notify_each_device_once_until_changed_or_acknowledged() {
+        let pool = create_pool(&DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        let repo = SqliteAlertRepository::new(pool);
+
+        let key = "41.88,-87.63";
+        let mut warning = AlertResponse::new(
+            "NWS Chicago".to_string(),
+            "Winter Storm Warning".to_string(),
+            1_700_000_000,
+            1_700_086_400,
+            "Heavy snow expected".to_string(),
+            None,
+        );
+        let ledger = repo.get_ledger(key, 1_700_000_050).await.unwrap();
+        assert!(ledger.needs_notifying(&warning, "device-1"));
+
+        repo.record_delivered(
+            key,
+            "Chicago",
+            &warning,
+            &["device-1".to_string()],
+            1_700_000_100,
+        )
+        .await
+        .unwrap();
+        let ledger = repo.get_ledger(key, 1_700_000_200).await.unwrap();
+        assert!(!ledger.needs_notifying(&warning, "device-1"));
+        // Devices the alert hasn't reached still get it
+        assert!(ledger.needs_notifying(&warning, "device-2"));
+        assert!(repo
+            .get_active(key, 1_700_086_400)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Upgraded severity is a material change; a new end time is a new alert
+        warning.severity = AlertSeverity::Extreme;
+        assert!(ledger.needs_notifying(&warning, "device-1"));
+        let mut extended = warning.clone();
+        extended.end += 3600;
+        assert!(ledger.needs_notifying(&extended, "device-1"));
+
+        let id = ledger.id_of(&warning);
+        assert!(repo.acknowledge(&id, 1_700_000_300).await.unwrap());
+        assert!(!repo
+            .acknowledge("0000000000000000", 1_700_000_300)
+            .await
+            .unwrap());
+        let notified = repo.get_active(key, 1_700_000_400).await.unwrap();
+        assert_eq!(notified[0].acknowledged_at, Some(1_700_000_300));
+        assert_eq!(notified[0].first_notified, 1_700_000_100);
+        let ledger = repo.get_ledger(key, 1_700_000_400).await.unwrap();
+        assert!(!ledger.needs_notifying(&warning, "device-1"));
+        assert!(!ledger.needs_notifying(&warning, "device-2"));
+    }
+
+    #[tokio::test]
+    async fn test_alert_recipients_cover_reissues_only() {
+        let pool = create_pool(&DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        let repo = SqliteAlertRepository::new(pool);
+
+        let key = "41.88,-87.63";
+        let storm = |start: i64, end: i64| {
+            AlertResponse::new(
+                "NWS Chicago".to_string(),
+                "Winter Storm Warning".to_string(),
+                start,
+                end,
+                "Heavy snow expected".to_string(),
+                None,
+            )
+        };
+        // Last week's storm, then this one, re-issued with a later end
+        let old = storm(1_699_000_000, 1_699_086_400);
+        let first = storm(1_700_000_000, 1_700_086_400);
+        let reissued = storm(1_700_000_000, 1_700_172_800);
+        repo.record_delivered(
+            key,
+            "Chicago",
+            &old,
+            &["device-0".to_string()],
+            1_699_000_100,
+        )
+        .await
+        .unwrap();
+        repo.record_delivered(
+            key,
+            "Chicago",
+            &first,
+            &["device-1".to_string()],
+            1_700_000_100,
+        )
+        .await
+        .unwrap();
+        repo.record_delivered(
+            key,
+            "Chicago",
+            &reissued,
+            &["device-2".to_string()],
+            1_700_050_000,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            repo.get_recipients(key, &reissued).await.unwrap(),
+            vec!["device-1".to_string(), "device-2".to_string()]
+        );
+        assert!(repo
+            .get_recipients("43.04,-87.91", &reissued)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use super::DbError;
+
+/// An upstream reading rejected by the sanity checks
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherAnomaly {
+    pub location_key: String,
+    pub city: String,
+    /// `timemachine`, `observation`, or `forecast`
+    pub source: String,
+    /// Time of the reading
+    pub observed_at: i64,
+    /// When it was rejected
+    pub detected_at: i64,
+    /// The checks that failed, e.g. "humidity 140% above 100%"
+    pub reasons: Vec<String>,
+    /// The rejected reading as it arrived
+    pub payload: serde_json::Value,
+}
+
+/// Repository trait for quarantined weather readings
+#[async_trait]
+pub trait AnomalyRepository: Send + Sync {
+    /// Quarantine rejected readings
+    async fn record(&self, anomalies: &[WeatherAnomaly]) -> Result<(), DbError>;
+
+    /// Readings for a location taken within a time range, oldest first
+    async fn get_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<WeatherAnomaly>, DbError>;
+}
+
+/// SQLite implementation of AnomalyRepository
+pub struct SqliteAnomalyRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAnomalyRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Internal row structure for SQLite queries
+#[derive(sqlx::FromRow)]
+struct AnomalyRow {
+    location_key: String,
+    city: String,
+    source: String,
+    observed_at: i64,
+    detected_at: i64,
+    reasons: String,
+    payload: String,
+}
+
+impl TryFrom<AnomalyRow> for WeatherAnomaly {
+    type Error = DbError;
+
+    fn try_from(row: AnomalyRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            location_key: row.location_key,
+            city: row.city,
+            source: row.source,
+            observed_at: row.observed_at,
+            detected_at: row.detected_at,
+            reasons: serde_json::from_str(&row.reasons)?,
+            payload: serde_json::from_str(&row.payload)?,
+        })
+    }
+}
+
+#[async_trait]
+impl AnomalyRepository for SqliteAnomalyRepository {
+    async fn record(&self, anomalies: &[WeatherAnomaly]) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+        for anomaly in anomalies {
+            sqlx::query(
+                "INSERT INTO weather_anomalies (location_key, city, source, observed_at, detected_at, reasons, payload)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&anomaly.location_key)
+            .bind(&anomaly.city)
+            .bind(&anomaly.source)
+            .bind(anomaly.observed_at)
+            .bind(anomaly.detected_at)
+            .bind(serde_json::to_string(&anomaly.reasons)?)
+            .bind(anomaly.payload.to_string())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<WeatherAnomaly>, DbError> {
+        let rows: Vec<AnomalyRow> = sqlx::query_as(
+            "SELECT location_key, city, source, observed_at, detected_at, reasons, payload
+             FROM weather_anomalies
+             WHERE location_key = ? AND observed_at >= ? AND observed_at <= ?
+             ORDER BY observed_at ASC, id ASC",
+        )
+        .bind(location_key)
+        .bind(start_ts)
+        .bind(end_ts)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(WeatherAnomaly::try_from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+
+    #[tokio::test]
+    async fn test_record_and_get_range() {
+        let pool = create_pool(&DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        let repo = SqliteAnomalyRepository::new(pool);
+
+        let anomaly = |observed_at| WeatherAnomaly {
+            location_key: "41.88,-87.63".to_string(),
+            city: "Chicago".to_string(),
+            source: "observation".to_string(),
+            observed_at,
+            detected_at: 1_700_010_000,
+            reasons: vec!["pressure 0 hPa outside 870-1085 hPa".to_string()],
+            payload: serde_json::json!({"pressure": 0}),
+        };
+        repo.record(&[anomaly(1_700_003_600), anomaly(1_700_000_000)])
+            .await
+            .unwrap();
+
+        let stored = repo
+            .get_range("41.88,-87.63", 1_700_000_000, 1_700_003_600)
+            .await
+            .unwrap();
+        assert_eq!(stored, vec![anomaly(1_700_000_000), anomaly(1_700_003_600)]);
+        assert!(repo
+            .get_range("0.00,0.00", 0, i64::MAX)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::audit::{AuditAction, AuditEntry, AuditQuery, AuditResource};
+
+use super::DbError;
+
+/// Repository trait for the audit log
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /// Append an entry; its `id` is ignored
+    async fn record(&self, entry: &AuditEntry) -> Result<(), DbError>;
+
+    /// Entries matching the query, newest first
+    async fn query(&self, query: &AuditQuery, limit: u32) -> Result<Vec<AuditEntry>, DbError>;
+}
+
+/// SQLite implementation of AuditRepository
+pub struct SqliteAuditRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAuditRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn row_to_entry(row: AuditRow) -> Result<AuditEntry, DbError> {
+        let action: AuditAction = serde_json::from_value(serde_json::Value::String(row.action))?;
+        let resource: AuditResource =
+            serde_json::from_value(serde_json::Value::String(row.resource))?;
+
+        Ok(AuditEntry {
+            id: row.id,
+            timestamp: row.timestamp,
+            action,
+            resource,
+            resource_id: row.resource_id,
+            actor: row.actor,
+            api_key: row.api_key,
+            ip: row.ip,
+            changes: serde_json::from_str(&row.changes)?,
+        })
+    }
+
+    /// Serialize a unit enum to its serde string form (e.g. `AuditAction::Update` -> "update")
+    fn enum_str<T: serde::Serialize>(value: &T) -> Result<String, DbError> {
+        let value = serde_json::to_value(value)?;
+        Ok(value.as_str().unwrap_or_default().to_string())
+    }
+}
+
+/// Internal row structure for SQLite queries
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: i64,
+    timestamp: i64,
+    action: String,
+    resource: String,
+    resource_id: String,
+    actor: Option<String>,
+    api_key: Option<String>,
+    ip: Option<String>,
+    changes: String,
+}
+
+#[async_trait]
+impl AuditRepository for SqliteAuditRepository {
+    async fn record(&self, entry: &AuditEntry) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO audit_log (timestamp, action, resource, resource_id, actor, api_key, ip, changes)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(entry.timestamp)
+        .bind(Self::enum_str(&entry.action)?)
+        .bind(Self::enum_str(&entry.resource)?)
+        .bind(&entry.resource_id)
+        .bind(&entry.actor)
+        .bind(&entry.api_key)
+        .bind(&entry.ip)
+        .bind(serde_json::to_string(&entry.changes)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn query(&self, query: &AuditQuery, limit: u32) -> Result<Vec<AuditEntry>, DbError> {
+        let resource = query.resource.as_ref().map(Self::enum_str).transpose()?;
+        let rows: Vec<AuditRow> = sqlx::query_as(
+            "SELECT id, timestamp, action, resource, resource_id, actor, api_key, ip, changes
+             FROM audit_log
+             WHERE (?1 IS NULL OR resource = ?1)
+               AND (?2 IS NULL OR resource_id = ?2)
+               AND (?3 IS NULL OR actor = ?3)
+               AND (?4 IS NULL OR id < ?4)
+             ORDER BY id DESC LIMIT ?5",
+        )
+        .bind(resource)
+        .bind(&query.resource_id)
+        .bind(&query.actor)
+        .bind(query.before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_entry).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+
+    async fn setup_test_db() -> SqlitePool {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        pool
+    }
+
+    fn create_test_entry(resource: AuditResource, resource_id: &str, actor: &str) -> AuditEntry {
+        AuditEntry {
+            id: 0,
+            timestamp: 1700000000,
+            action: AuditAction::Update,
+            resource,
+            resource_id: resource_id.to_string(),
+            actor: Some(actor.to_string()),
+            api_key: None,
+            ip: Some("203.0.113.7".to_string()),
+            changes: serde_json::json!({"enabled": {"from": true, "to": false}}),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_query() {
+        let pool = setup_test_db().await;
+        let repo = SqliteAuditRepository::new(pool);
+
+        repo.record(&create_test_entry(
+            AuditResource::Job,
+            "storm-alerts",
+            "alice",
+        ))
+        .await
+        .unwrap();
+        repo.record(&create_test_entry(AuditResource::Device, "device-1", "bob"))
+            .await
+            .unwrap();
+        repo.record(&create_test_entry(AuditResource::Job, "morning", "bob"))
+            .await
+            .unwrap();
+
+        let all = repo.query(&AuditQuery::default(), 10).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].resource_id, "morning");
+
+        let query = AuditQuery {
+            resource: Some(AuditResource::Job),
+            actor: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let entries = repo.query(&query, 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].resource_id, "storm-alerts");
+        assert_eq!(entries[0].changes["enabled"]["to"], false);
+
+        let query = AuditQuery {
+            before: Some(all[0].id),
+            ..Default::default()
+        };
+        assert_eq!(repo.query(&query, 10).await.unwrap().len(), 2);
+    }
+}
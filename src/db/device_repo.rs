@@ -1,10 +1,14 @@
 use async_trait::async_trait;
 use sqlx::SqlitePool;
 
+use crate::devices::models::default_subscriptions;
 use crate::devices::{Device, Platform};
 
 use super::DbError;
 
+/// Columns selected for every device query, in `DeviceRow` order
+const DEVICE_COLUMNS: &str = "id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at, last_seen, lat, lon, quiet_hours, language, alert_preferences, metadata, cold_threshold, heat_threshold, subscriptions, owner, timezone, min_priority";
+
 /// Repository trait for device operations
 #[allow(dead_code)]
 #[async_trait]
@@ -27,13 +31,51 @@ pub trait DeviceRepository: Send + Sync {
     /// Insert or update a device
     async fn upsert(&self, device: &Device) -> Result<(), DbError>;
 
+    /// Insert a new device unless its token is already registered. Returns
+    /// false, writing nothing, if it is.
+    async fn insert(&self, device: &Device) -> Result<bool, DbError>;
+
     /// Remove a device by token
     async fn remove(&self, token: &str) -> Result<bool, DbError>;
 
     /// Get the total count of devices
     async fn count(&self) -> Result<usize, DbError>;
+
+    /// Get one page of devices matching a filter, plus the total number of matches
+    async fn list(
+        &self,
+        filter: &DeviceFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<Device>, usize), DbError>;
+
+    /// Get devices matching a filter, newest first, that sort after the
+    /// `(registered_at, id)` of the last device on the previous page
+    async fn list_after(
+        &self,
+        filter: &DeviceFilter,
+        after: Option<(i64, &str)>,
+        limit: u32,
+    ) -> Result<Vec<Device>, DbError>;
 }
 
+/// Optional criteria for listing devices; `None` fields match everything
+#[derive(Debug, Default)]
+pub struct DeviceFilter {
+    /// Subscribed city (case-insensitive exact match)
+    pub city: Option<String>,
+    pub platform: Option<Platform>,
+    pub enabled: Option<bool>,
+    /// Owning user (exact match)
+    pub owner: Option<String>,
+}
+
+/// Shared WHERE clause for `list`; binds are ?1 platform, ?2 enabled, ?3 city, ?4 owner
+const DEVICE_FILTER_SQL: &str = "(?1 IS NULL OR platform = ?1)
+    AND (?2 IS NULL OR enabled = ?2)
+    AND (?3 IS NULL OR EXISTS (SELECT 1 FROM json_each(devices.cities) WHERE LOWER(json_each.value) = LOWER(?3)))
+    AND (?4 IS NULL OR owner = ?4)";
+
 /// SQLite implementation of DeviceRepository
 pub struct SqliteDeviceRepository {
     pool: SqlitePool,
@@ -44,6 +86,63 @@ impl SqliteDeviceRepository {
         Self { pool }
     }
 
+    /// Insert a device, resolving a clash with an existing row per `on_conflict`.
+    /// Returns whether a row was written.
+    async fn write(&self, device: &Device, on_conflict: &str) -> Result<bool, DbError> {
+        let cities_json = serde_json::to_string(&device.cities)?;
+        let quiet_hours_json = device
+            .quiet_hours
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let alert_preferences_json = serde_json::to_string(&device.alert_preferences)?;
+        let subscriptions_json = serde_json::to_string(&device.subscriptions)?;
+        let metadata_json = device
+            .metadata
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let min_priority = device
+            .min_priority
+            .map(serde_json::to_value)
+            .transpose()?
+            .and_then(|p| p.as_str().map(str::to_string));
+
+        let result = sqlx::query(&format!(
+            "INSERT INTO devices (id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at, last_seen, lat, lon, quiet_hours, language, alert_preferences, metadata, cold_threshold, heat_threshold, subscriptions, owner, timezone, min_priority)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             {}",
+            on_conflict
+        ))
+        .bind(&device.id)
+        .bind(&device.token)
+        .bind(Self::platform_to_str(&device.platform))
+        .bind(&device.device_name)
+        .bind(&device.app_version)
+        .bind(&cities_json)
+        .bind(&device.units)
+        .bind(if device.enabled { 1 } else { 0 })
+        .bind(device.registered_at)
+        .bind(device.updated_at)
+        .bind(device.last_seen)
+        .bind(device.lat)
+        .bind(device.lon)
+        .bind(&quiet_hours_json)
+        .bind(&device.language)
+        .bind(&alert_preferences_json)
+        .bind(&metadata_json)
+        .bind(device.cold_threshold)
+        .bind(device.heat_threshold)
+        .bind(&subscriptions_json)
+        .bind(&device.owner)
+        .bind(&device.timezone)
+        .bind(&min_priority)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     fn platform_to_str(platform: &Platform) -> &'static str {
         match platform {
             Platform::Ios => "ios",
@@ -63,6 +162,32 @@ impl SqliteDeviceRepository {
 
     fn row_to_device(row: DeviceRow) -> Result<Device, DbError> {
         let cities: Vec<String> = serde_json::from_str(&row.cities)?;
+        let quiet_hours = row
+            .quiet_hours
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?;
+        let alert_preferences = row
+            .alert_preferences
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?
+            .unwrap_or_default();
+        let metadata = row
+            .metadata
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?;
+        let subscriptions = row
+            .subscriptions
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?
+            .unwrap_or_else(default_subscriptions);
+        let min_priority = row
+            .min_priority
+            .map(|p| serde_json::from_value(serde_json::Value::String(p)))
+            .transpose()?;
 
         Ok(Device {
             id: row.id,
@@ -75,6 +200,19 @@ impl SqliteDeviceRepository {
             enabled: row.enabled != 0,
             registered_at: row.registered_at,
             updated_at: row.updated_at,
+            last_seen: row.last_seen,
+            lat: row.lat,
+            lon: row.lon,
+            quiet_hours,
+            timezone: row.timezone,
+            language: row.language,
+            alert_preferences,
+            subscriptions,
+            metadata,
+            cold_threshold: row.cold_threshold,
+            heat_threshold: row.heat_threshold,
+            owner: row.owner,
+            min_priority,
         })
     }
 }
@@ -92,15 +230,27 @@ struct DeviceRow {
     enabled: i32,
     registered_at: i64,
     updated_at: i64,
+    last_seen: Option<i64>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    quiet_hours: Option<String>,
+    language: String,
+    alert_preferences: Option<String>,
+    metadata: Option<String>,
+    cold_threshold: Option<f64>,
+    heat_threshold: Option<f64>,
+    subscriptions: Option<String>,
+    owner: Option<String>,
+    timezone: Option<String>,
+    min_priority: Option<String>,
 }
 
 #[async_trait]
 impl DeviceRepository for SqliteDeviceRepository {
     async fn get_by_token(&self, token: &str) -> Result<Option<Device>, DbError> {
-        let row: Option<DeviceRow> = sqlx::query_as(
-            "SELECT id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at
-             FROM devices WHERE token = ?"
-        )
+        let row: Option<DeviceRow> = sqlx::query_as(&format!(
+            "SELECT {DEVICE_COLUMNS} FROM devices WHERE token = ?"
+        ))
         .bind(token)
         .fetch_optional(&self.pool)
         .await?;
@@ -109,10 +259,9 @@ impl DeviceRepository for SqliteDeviceRepository {
     }
 
     async fn get_by_id(&self, id: &str) -> Result<Option<Device>, DbError> {
-        let row: Option<DeviceRow> = sqlx::query_as(
-            "SELECT id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at
-             FROM devices WHERE id = ?"
-        )
+        let row: Option<DeviceRow> = sqlx::query_as(&format!(
+            "SELECT {DEVICE_COLUMNS} FROM devices WHERE id = ?"
+        ))
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
@@ -121,10 +270,9 @@ impl DeviceRepository for SqliteDeviceRepository {
     }
 
     async fn get_all(&self) -> Result<Vec<Device>, DbError> {
-        let rows: Vec<DeviceRow> = sqlx::query_as(
-            "SELECT id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at
-             FROM devices ORDER BY registered_at DESC"
-        )
+        let rows: Vec<DeviceRow> = sqlx::query_as(&format!(
+            "SELECT {DEVICE_COLUMNS} FROM devices ORDER BY registered_at DESC"
+        ))
         .fetch_all(&self.pool)
         .await?;
 
@@ -132,10 +280,9 @@ impl DeviceRepository for SqliteDeviceRepository {
     }
 
     async fn get_enabled(&self) -> Result<Vec<Device>, DbError> {
-        let rows: Vec<DeviceRow> = sqlx::query_as(
-            "SELECT id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at
-             FROM devices WHERE enabled = 1 ORDER BY registered_at DESC"
-        )
+        let rows: Vec<DeviceRow> = sqlx::query_as(&format!(
+            "SELECT {DEVICE_COLUMNS} FROM devices WHERE enabled = 1 ORDER BY registered_at DESC"
+        ))
         .fetch_all(&self.pool)
         .await?;
 
@@ -144,13 +291,13 @@ impl DeviceRepository for SqliteDeviceRepository {
 
     async fn get_by_city(&self, city: &str) -> Result<Vec<Device>, DbError> {
         // SQLite JSON contains check - cities is stored as JSON array
-        let rows: Vec<DeviceRow> = sqlx::query_as(
-            r#"SELECT id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at
+        let rows: Vec<DeviceRow> = sqlx::query_as(&format!(
+            r#"SELECT {DEVICE_COLUMNS}
                FROM devices
                WHERE enabled = 1
                AND (cities LIKE '%"' || ? || '"%' OR cities LIKE '%' || LOWER(?) || '%')
                ORDER BY registered_at DESC"#
-        )
+        ))
         .bind(city)
         .bind(city)
         .fetch_all(&self.pool)
@@ -169,12 +316,9 @@ impl DeviceRepository for SqliteDeviceRepository {
     }
 
     async fn upsert(&self, device: &Device) -> Result<(), DbError> {
-        let cities_json = serde_json::to_string(&device.cities)?;
-
-        sqlx::query(
-            "INSERT INTO devices (id, token, platform, device_name, app_version, cities, units, enabled, registered_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(id) DO UPDATE SET
+        self.write(
+            device,
+            "ON CONFLICT(id) DO UPDATE SET
                 token = excluded.token,
                 platform = excluded.platform,
                 device_name = excluded.device_name,
@@ -182,24 +326,29 @@ impl DeviceRepository for SqliteDeviceRepository {
                 cities = excluded.cities,
                 units = excluded.units,
                 enabled = excluded.enabled,
-                updated_at = excluded.updated_at"
+                updated_at = excluded.updated_at,
+                last_seen = excluded.last_seen,
+                lat = excluded.lat,
+                lon = excluded.lon,
+                quiet_hours = excluded.quiet_hours,
+                language = excluded.language,
+                alert_preferences = excluded.alert_preferences,
+                metadata = excluded.metadata,
+                cold_threshold = excluded.cold_threshold,
+                heat_threshold = excluded.heat_threshold,
+                subscriptions = excluded.subscriptions,
+                owner = excluded.owner,
+                timezone = excluded.timezone,
+                min_priority = excluded.min_priority",
         )
-        .bind(&device.id)
-        .bind(&device.token)
-        .bind(Self::platform_to_str(&device.platform))
-        .bind(&device.device_name)
-        .bind(&device.app_version)
-        .bind(&cities_json)
-        .bind(&device.units)
-        .bind(if device.enabled { 1 } else { 0 })
-        .bind(device.registered_at)
-        .bind(device.updated_at)
-        .execute(&self.pool)
         .await?;
-
         Ok(())
     }
 
+    async fn insert(&self, device: &Device) -> Result<bool, DbError> {
+        self.write(device, "ON CONFLICT(token) DO NOTHING").await
+    }
+
     async fn remove(&self, token: &str) -> Result<bool, DbError> {
         let result = sqlx::query("DELETE FROM devices WHERE token = ?")
             .bind(token)
@@ -216,12 +365,79 @@ impl DeviceRepository for SqliteDeviceRepository {
 
         Ok(row.0 as usize)
     }
+
+    async fn list(
+        &self,
+        filter: &DeviceFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<Device>, usize), DbError> {
+        let platform = filter.platform.as_ref().map(Self::platform_to_str);
+        let enabled = filter.enabled.map(i32::from);
+
+        let total: (i64,) = sqlx::query_as(&format!(
+            "SELECT COUNT(*) FROM devices WHERE {DEVICE_FILTER_SQL}"
+        ))
+        .bind(platform)
+        .bind(enabled)
+        .bind(&filter.city)
+        .bind(&filter.owner)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let rows: Vec<DeviceRow> = sqlx::query_as(&format!(
+            "SELECT {DEVICE_COLUMNS} FROM devices WHERE {DEVICE_FILTER_SQL}
+             ORDER BY registered_at DESC LIMIT ?5 OFFSET ?6"
+        ))
+        .bind(platform)
+        .bind(enabled)
+        .bind(&filter.city)
+        .bind(&filter.owner)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let devices = rows
+            .into_iter()
+            .map(Self::row_to_device)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((devices, total.0 as usize))
+    }
+
+    async fn list_after(
+        &self,
+        filter: &DeviceFilter,
+        after: Option<(i64, &str)>,
+        limit: u32,
+    ) -> Result<Vec<Device>, DbError> {
+        let platform = filter.platform.as_ref().map(Self::platform_to_str);
+        let enabled = filter.enabled.map(i32::from);
+
+        let rows: Vec<DeviceRow> = sqlx::query_as(&format!(
+            "SELECT {DEVICE_COLUMNS} FROM devices WHERE {DEVICE_FILTER_SQL}
+               AND (?5 IS NULL OR registered_at < ?5 OR (registered_at = ?5 AND id < ?6))
+             ORDER BY registered_at DESC, id DESC LIMIT ?7"
+        ))
+        .bind(platform)
+        .bind(enabled)
+        .bind(&filter.city)
+        .bind(&filter.owner)
+        .bind(after.map(|(registered_at, _)| registered_at))
+        .bind(after.map(|(_, id)| id))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_device).collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db::{create_pool, run_migrations, DbConfig};
+    use crate::devices::models::AlertPreferences;
 
     async fn setup_test_db() -> SqlitePool {
         let config = DbConfig {
@@ -229,7 +445,7 @@ mod tests {
             max_connections: 1,
         };
         let pool = create_pool(&config).await.unwrap();
-        run_migrations(&pool).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
         pool
     }
 
@@ -245,6 +461,19 @@ mod tests {
             enabled: true,
             registered_at: 1700000000,
             updated_at: 1700000000,
+            last_seen: None,
+            lat: None,
+            lon: None,
+            quiet_hours: None,
+            timezone: None,
+            language: "en".to_string(),
+            alert_preferences: AlertPreferences::default(),
+            subscriptions: default_subscriptions(),
+            metadata: None,
+            cold_threshold: None,
+            heat_threshold: None,
+            owner: None,
+            min_priority: None,
         }
     }
 
@@ -301,6 +530,113 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[tokio::test]
+    async fn test_heartbeat_fields_round_trip() {
+        let pool = setup_test_db().await;
+        let repo = SqliteDeviceRepository::new(pool);
+
+        let mut device = create_test_device("token_heartbeat");
+        repo.upsert(&device).await.unwrap();
+        assert!(repo
+            .get_by_token("token_heartbeat")
+            .await
+            .unwrap()
+            .unwrap()
+            .last_seen
+            .is_none());
+
+        device.last_seen = Some(1700050000);
+        device.metadata = Some(serde_json::json!({"installId": "abc", "theme": "dark"}));
+        device.lat = Some(41.88);
+        device.lon = Some(-87.63);
+        repo.upsert(&device).await.unwrap();
+
+        let retrieved = repo.get_by_token("token_heartbeat").await.unwrap().unwrap();
+        assert_eq!(retrieved.last_seen, Some(1700050000));
+        assert_eq!(retrieved.lat, Some(41.88));
+        assert_eq!(retrieved.lon, Some(-87.63));
+        assert_eq!(retrieved.metadata, device.metadata);
+    }
+
+    #[tokio::test]
+    async fn test_list_filters_and_pages() {
+        let pool = setup_test_db().await;
+        let repo = SqliteDeviceRepository::new(pool);
+
+        for i in 0..5 {
+            let mut device = create_test_device(&format!("token{}", i));
+            device.registered_at += i;
+            device.enabled = i % 2 == 0;
+            if i == 4 {
+                device.platform = Platform::Ios;
+                device.cities = vec!["Paris".to_string()];
+                device.owner = Some("alice".to_string());
+            }
+            repo.upsert(&device).await.unwrap();
+        }
+
+        let (page, total) = repo.list(&DeviceFilter::default(), 2, 0).await.unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].token, "token4");
+
+        let (page, total) = repo.list(&DeviceFilter::default(), 2, 4).await.unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(page.len(), 1);
+
+        let filter = DeviceFilter {
+            city: Some("chicago".to_string()),
+            enabled: Some(true),
+            ..Default::default()
+        };
+        let (page, total) = repo.list(&filter, 10, 0).await.unwrap();
+        assert_eq!(total, 2);
+        assert!(page.iter().all(|d| d.enabled && d.token != "token4"));
+
+        let filter = DeviceFilter {
+            platform: Some(Platform::Ios),
+            ..Default::default()
+        };
+        let (page, total) = repo.list(&filter, 10, 0).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(page[0].token, "token4");
+
+        let filter = DeviceFilter {
+            owner: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let (page, total) = repo.list(&filter, 10, 0).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(page[0].owner.as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_list_after_walks_keyset() {
+        let pool = setup_test_db().await;
+        let repo = SqliteDeviceRepository::new(pool);
+
+        // Two devices share a registration time, so the id breaks the tie
+        for (i, registered_at) in [1700000000, 1700000100, 1700000100].iter().enumerate() {
+            let mut device = create_test_device(&format!("token{}", i));
+            device.id = format!("id-{}", i);
+            device.registered_at = *registered_at;
+            repo.upsert(&device).await.unwrap();
+        }
+
+        let filter = DeviceFilter::default();
+        let page = repo.list_after(&filter, None, 2).await.unwrap();
+        let ids: Vec<_> = page.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["id-2", "id-1"]);
+
+        let last = page.last().unwrap();
+        let page = repo
+            .list_after(&filter, Some((last.registered_at, &last.id)), 2)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, "id-0");
+    }
+
     #[tokio::test]
     async fn test_count() {
         let pool = setup_test_db().await;
@@ -313,4 +649,23 @@ mod tests {
 
         assert_eq!(repo.count().await.unwrap(), 2);
     }
+
+    #[tokio::test]
+    async fn test_insert_keeps_first_registration_of_token() {
+        let pool = setup_test_db().await;
+        let repo = SqliteDeviceRepository::new(pool);
+
+        let first = create_test_device("raced_token");
+        let mut second = create_test_device("raced_token");
+        second.id = "other-id".to_string();
+        second.device_name = Some("Second".to_string());
+
+        assert!(repo.insert(&first).await.unwrap());
+        assert!(!repo.insert(&second).await.unwrap());
+
+        let stored = repo.get_by_token("raced_token").await.unwrap().unwrap();
+        assert_eq!(stored.id, first.id);
+        assert_eq!(stored.device_name, first.device_name);
+        assert_eq!(repo.count().await.unwrap(), 1);
+    }
 }
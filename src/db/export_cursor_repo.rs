@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use super::DbError;
+
+/// Repository trait for export job progress
+#[async_trait]
+pub trait ExportCursorRepository: Send + Sync {
+    /// Last row ID exported by the named export (0 before its first run)
+    async fn get(&self, name: &str) -> Result<i64, DbError>;
+
+    /// Record that the named export has sent every row up to `last_id`
+    async fn set(&self, name: &str, last_id: i64, updated_at: i64) -> Result<(), DbError>;
+}
+
+/// SQLite implementation of ExportCursorRepository
+pub struct SqliteExportCursorRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteExportCursorRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ExportCursorRepository for SqliteExportCursorRepository {
+    async fn get(&self, name: &str) -> Result<i64, DbError> {
+        let last_id: Option<i64> =
+            sqlx::query_scalar("SELECT last_id FROM export_cursors WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(last_id.unwrap_or(0))
+    }
+
+    async fn set(&self, name: &str, last_id: i64, updated_at: i64) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO export_cursors (name, last_id, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(name) DO UPDATE SET
+                last_id = excluded.last_id,
+                updated_at = excluded.updated_at",
+        )
+        .bind(name)
+        .bind(last_id)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
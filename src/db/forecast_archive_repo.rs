@@ -0,0 +1,180 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::compression::{gunzip, gzip};
+use crate::forecast::models::ForecastResponse;
+
+use super::DbError;
+
+/// A forecast as fetched from OWM at `issued_at`
+#[derive(Debug, Clone)]
+pub struct ArchivedForecast {
+    /// Which fetch produced it: full, daily, or hourly
+    pub kind: String,
+    pub units: String,
+    pub issued_at: i64,
+    pub forecast: ForecastResponse,
+}
+
+/// Repository trait for archived forecast snapshots
+#[async_trait]
+pub trait ForecastArchiveRepository: Send + Sync {
+    /// Store a fetched forecast
+    async fn record(&self, location_key: &str, archived: &ArchivedForecast) -> Result<(), DbError>;
+
+    /// The latest forecast for a location issued at or before `at`
+    async fn get_at(
+        &self,
+        location_key: &str,
+        at: i64,
+    ) -> Result<Option<ArchivedForecast>, DbError>;
+
+    /// Delete snapshots issued before `cutoff`, returning how many
+    async fn prune_before(&self, cutoff: i64) -> Result<u64, DbError>;
+}
+
+/// SQLite implementation of ForecastArchiveRepository
+pub struct SqliteForecastArchiveRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteForecastArchiveRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Internal row structure for SQLite queries
+#[derive(sqlx::FromRow)]
+struct ArchiveRow {
+    kind: String,
+    units: String,
+    issued_at: i64,
+    snapshot: Vec<u8>,
+}
+
+/// Gzip a forecast's JSON
+fn compress(forecast: &ForecastResponse) -> Result<Vec<u8>, DbError> {
+    Ok(gzip(&serde_json::to_vec(forecast)?)?)
+}
+
+fn decompress(snapshot: &[u8]) -> Result<ForecastResponse, DbError> {
+    Ok(serde_json::from_slice(&gunzip(snapshot)?)?)
+}
+
+#[async_trait]
+impl ForecastArchiveRepository for SqliteForecastArchiveRepository {
+    async fn record(&self, location_key: &str, archived: &ArchivedForecast) -> Result<(), DbError> {
+        let snapshot = compress(&archived.forecast)?;
+        sqlx::query(
+            "INSERT INTO forecast_archive (location_key, kind, units, issued_at, snapshot)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(location_key)
+        .bind(&archived.kind)
+        .bind(&archived.units)
+        .bind(archived.issued_at)
+        .bind(snapshot)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_at(
+        &self,
+        location_key: &str,
+        at: i64,
+    ) -> Result<Option<ArchivedForecast>, DbError> {
+        let row: Option<ArchiveRow> = sqlx::query_as(
+            "SELECT kind, units, issued_at, snapshot FROM forecast_archive
+             WHERE location_key = ? AND issued_at <= ?
+             ORDER BY issued_at DESC, id DESC
+             LIMIT 1",
+        )
+        .bind(location_key)
+        .bind(at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| {
+            Ok(ArchivedForecast {
+                forecast: decompress(&r.snapshot)?,
+                kind: r.kind,
+                units: r.units,
+                issued_at: r.issued_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn prune_before(&self, cutoff: i64) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM forecast_archive WHERE issued_at < ?")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+    use crate::forecast::models::LocationInfo;
+
+    async fn setup_test_db() -> SqlitePool {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        pool
+    }
+
+    fn archived(timezone: &str, issued_at: i64) -> ArchivedForecast {
+        ArchivedForecast {
+            kind: "daily".to_string(),
+            units: "metric".to_string(),
+            issued_at,
+            forecast: ForecastResponse {
+                location: LocationInfo {
+                    city: "Chicago".to_string(),
+                    country: "US".to_string(),
+                    state: None,
+                    lat: 41.88,
+                    lon: -87.63,
+                },
+                timezone: timezone.to_string(),
+                current: None,
+                hourly: vec![],
+                daily: vec![],
+                alerts: vec![],
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_at_and_prune() {
+        let pool = setup_test_db().await;
+        let repo = SqliteForecastArchiveRepository::new(pool);
+
+        repo.record("41.88,-87.63", &archived("first", 1000))
+            .await
+            .unwrap();
+        repo.record("41.88,-87.63", &archived("second", 2000))
+            .await
+            .unwrap();
+
+        let found = repo.get_at("41.88,-87.63", 1500).await.unwrap().unwrap();
+        assert_eq!(found.issued_at, 1000);
+        assert_eq!(found.forecast.timezone, "first");
+        assert_eq!(found.forecast.location.city, "Chicago");
+        assert!(repo.get_at("41.88,-87.63", 999).await.unwrap().is_none());
+        assert!(repo.get_at("0.00,0.00", 5000).await.unwrap().is_none());
+
+        assert_eq!(repo.prune_before(1500).await.unwrap(), 1);
+        let latest = repo.get_at("41.88,-87.63", 5000).await.unwrap().unwrap();
+        assert_eq!(latest.forecast.timezone, "second");
+    }
+}
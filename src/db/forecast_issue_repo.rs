@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use super::DbError;
+
+/// A day's forecast as issued `lead_days` ahead, in metric
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ForecastIssue {
+    /// UTC date the forecast is for, YYYY-MM-DD (as in daily history)
+    pub target_date: String,
+    pub lead_days: i64,
+    pub issued_at: i64,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    /// Expected rain and snow, mm
+    pub precipitation: f64,
+}
+
+/// Repository trait for issued forecasts
+#[async_trait]
+pub trait ForecastIssueRepository: Send + Sync {
+    /// Store a forecast's days, replacing earlier issues with the same lead
+    async fn record(&self, location_key: &str, issues: &[ForecastIssue]) -> Result<(), DbError>;
+
+    /// Issues for target dates from `start_date` to `end_date` inclusive
+    async fn get_range(
+        &self,
+        location_key: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<ForecastIssue>, DbError>;
+}
+
+/// SQLite implementation of ForecastIssueRepository
+pub struct SqliteForecastIssueRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteForecastIssueRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ForecastIssueRepository for SqliteForecastIssueRepository {
+    async fn record(&self, location_key: &str, issues: &[ForecastIssue]) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+        for issue in issues {
+            sqlx::query(
+                "INSERT INTO forecast_issues (location_key, target_date, lead_days, issued_at, temp_min, temp_max, precipitation)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(location_key, target_date, lead_days) DO UPDATE SET
+                    issued_at = excluded.issued_at,
+                    temp_min = excluded.temp_min,
+                    temp_max = excluded.temp_max,
+                    precipitation = excluded.precipitation",
+            )
+            .bind(location_key)
+            .bind(&issue.target_date)
+            .bind(issue.lead_days)
+            .bind(issue.issued_at)
+            .bind(issue.temp_min)
+            .bind(issue.temp_max)
+            .bind(issue.precipitation)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_range(
+        &self,
+        location_key: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<ForecastIssue>, DbError> {
+        let rows = sqlx::query_as(
+            "SELECT target_date, lead_days, issued_at, temp_min, temp_max, precipitation
+             FROM forecast_issues
+             WHERE location_key = ? AND target_date >= ? AND target_date <= ?
+             ORDER BY target_date ASC, lead_days ASC",
+        )
+        .bind(location_key)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+
+    async fn setup_test_db() -> SqlitePool {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        pool
+    }
+
+    fn issue(target_date: &str, lead_days: i64, issued_at: i64, temp_max: f64) -> ForecastIssue {
+        ForecastIssue {
+            target_date: target_date.to_string(),
+            lead_days,
+            issued_at,
+            temp_min: temp_max - 10.0,
+            temp_max,
+            precipitation: 0.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_replaces_same_lead() {
+        let pool = setup_test_db().await;
+        let repo = SqliteForecastIssueRepository::new(pool);
+
+        repo.record(
+            "41.88,-87.63",
+            &[
+                issue("2024-07-02", 1, 1000, 28.0),
+                issue("2024-07-04", 3, 1000, 30.0),
+            ],
+        )
+        .await
+        .unwrap();
+        repo.record("41.88,-87.63", &[issue("2024-07-02", 1, 2000, 27.0)])
+            .await
+            .unwrap();
+
+        let stored = repo
+            .get_range("41.88,-87.63", "2024-07-01", "2024-07-03")
+            .await
+            .unwrap();
+        assert_eq!(stored, vec![issue("2024-07-02", 1, 2000, 27.0)]);
+    }
+}
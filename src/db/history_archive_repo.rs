@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use super::DbError;
+
+/// A month of hourly history for one location, as gzip-compressed CSV
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct HistoryArchive {
+    pub location_key: String,
+    /// UTC calendar month, YYYY-MM
+    pub month: String,
+    pub city: String,
+    pub row_count: i64,
+    pub created_at: i64,
+    pub data: Vec<u8>,
+}
+
+/// An archived month without its data
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct HistoryArchiveInfo {
+    pub month: String,
+    pub row_count: i64,
+    pub size_bytes: i64,
+    pub created_at: i64,
+}
+
+/// Repository trait for archived monthly history
+#[async_trait]
+pub trait HistoryArchiveRepository: Send + Sync {
+    /// Store a month, replacing any earlier archive of it
+    async fn store(&self, archive: &HistoryArchive) -> Result<(), DbError>;
+
+    /// One archived month for a location
+    async fn get(&self, location_key: &str, month: &str)
+        -> Result<Option<HistoryArchive>, DbError>;
+
+    /// Months archived for a location, oldest first
+    async fn list(&self, location_key: &str) -> Result<Vec<HistoryArchiveInfo>, DbError>;
+}
+
+/// SQLite implementation of HistoryArchiveRepository
+pub struct SqliteHistoryArchiveRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteHistoryArchiveRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HistoryArchiveRepository for SqliteHistoryArchiveRepository {
+    async fn store(&self, archive: &HistoryArchive) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO history_archives (location_key, month, city, row_count, created_at, data)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(location_key, month) DO UPDATE SET
+                city = excluded.city,
+                row_count = excluded.row_count,
+                created_at = excluded.created_at,
+                data = excluded.data",
+        )
+        .bind(&archive.location_key)
+        .bind(&archive.month)
+        .bind(&archive.city)
+        .bind(archive.row_count)
+        .bind(archive.created_at)
+        .bind(&archive.data)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        location_key: &str,
+        month: &str,
+    ) -> Result<Option<HistoryArchive>, DbError> {
+        let archive = sqlx::query_as(
+            "SELECT location_key, month, city, row_count, created_at, data
+             FROM history_archives WHERE location_key = ? AND month = ?",
+        )
+        .bind(location_key)
+        .bind(month)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(archive)
+    }
+
+    async fn list(&self, location_key: &str) -> Result<Vec<HistoryArchiveInfo>, DbError> {
+        let archives = sqlx::query_as(
+            "SELECT month, row_count, length(data) AS size_bytes, created_at
+             FROM history_archives WHERE location_key = ?
+             ORDER BY month ASC",
+        )
+        .bind(location_key)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(archives)
+    }
+}
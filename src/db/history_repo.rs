@@ -4,8 +4,14 @@ use sqlx::SqlitePool;
 use super::DbError;
 use crate::geocode::models::make_location_key;
 
+/// Rows fetched from the One Call Timemachine API
+pub const SOURCE_TIMEMACHINE: &str = "timemachine";
+
+/// Rows sampled from current conditions by the observation recorder
+pub const SOURCE_OBSERVATION: &str = "observation";
+
 /// A single weather history record stored in SQLite
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct HistoryRecord {
     pub city: String,
     #[allow(dead_code)]
@@ -27,6 +33,14 @@ pub struct HistoryRecord {
     pub snow_1h: Option<f64>,
     pub units: String,
     pub fetched_at: i64,
+    pub wind_gust: Option<f64>,
+    pub dew_point: Option<f64>,
+    /// UV index, when OWM reports one for the hour
+    pub uvi: Option<f64>,
+    /// OWM weather condition code (e.g. 501 for moderate rain)
+    pub condition_id: Option<i32>,
+    /// `SOURCE_TIMEMACHINE` or `SOURCE_OBSERVATION`
+    pub source: String,
 }
 
 /// Aggregated daily summary from history data
@@ -39,7 +53,11 @@ pub struct DailySummaryRow {
     pub humidity_avg: f64,
     pub wind_speed_avg: f64,
     pub precipitation_total: f64,
+    /// Part of `precipitation_total` that fell as snow
+    pub snow_total: f64,
     pub dominant_condition: Option<String>,
+    /// Number of hourly records behind the aggregates
+    pub readings: i64,
 }
 
 /// Repository trait for weather history operations
@@ -64,6 +82,48 @@ pub trait HistoryRepository: Send + Sync {
         units: &str,
     ) -> Result<Vec<DailySummaryRow>, DbError>;
 
+    /// Up to `limit` records stored after row `after_id`, oldest first, with
+    /// their row IDs
+    async fn get_after_id(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, HistoryRecord)>, DbError>;
+
+    /// Up to `limit` of a location's records in `units` after `after_ts` and
+    /// up to `end_ts`, oldest first, with their row IDs. Pass the last
+    /// timestamp of one page as `after_ts` to get the next.
+    async fn get_page_after(
+        &self,
+        location_key: &str,
+        after_ts: i64,
+        end_ts: i64,
+        units: &str,
+        limit: i64,
+    ) -> Result<Vec<(i64, HistoryRecord)>, DbError>;
+
+    /// Locations and UTC months (YYYY-MM) with records before `before_ts`
+    async fn months_before(&self, before_ts: i64) -> Result<Vec<(String, String)>, DbError>;
+
+    /// Records for a location from `start_ts` up to but excluding `end_ts`,
+    /// in every unit system, with their row IDs
+    async fn get_numbered_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<(i64, HistoryRecord)>, DbError>;
+
+    /// Delete a location's records from `start_ts` up to but excluding
+    /// `end_ts` whose row ID is at most `max_id`
+    async fn delete_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+        max_id: i64,
+    ) -> Result<u64, DbError>;
+
     /// Insert a batch of records, ignoring duplicates
     async fn insert_batch(&self, records: &[HistoryRecord]) -> Result<usize, DbError>;
 
@@ -114,7 +174,7 @@ pub struct HistoryStats {
     pub cities: Vec<CityHistoryStats>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CityHistoryStats {
     pub city: String,
@@ -126,6 +186,7 @@ pub struct CityHistoryStats {
 }
 
 /// SQLite implementation of HistoryRepository
+#[derive(Clone)]
 pub struct SqliteHistoryRepository {
     pool: SqlitePool,
 }
@@ -158,6 +219,19 @@ struct HistoryRow {
     snow_1h: Option<f64>,
     units: String,
     fetched_at: i64,
+    wind_gust: Option<f64>,
+    dew_point: Option<f64>,
+    uvi: Option<f64>,
+    condition_id: Option<i32>,
+    source: String,
+}
+
+/// A history row with its row ID, for exports
+#[derive(sqlx::FromRow)]
+struct NumberedHistoryRow {
+    id: i64,
+    #[sqlx(flatten)]
+    row: HistoryRow,
 }
 
 impl From<HistoryRow> for HistoryRecord {
@@ -182,6 +256,11 @@ impl From<HistoryRow> for HistoryRecord {
             snow_1h: row.snow_1h,
             units: row.units,
             fetched_at: row.fetched_at,
+            wind_gust: row.wind_gust,
+            dew_point: row.dew_point,
+            uvi: row.uvi,
+            condition_id: row.condition_id,
+            source: row.source,
         }
     }
 }
@@ -195,7 +274,9 @@ struct DailySummaryDbRow {
     humidity_avg: f64,
     wind_speed_avg: f64,
     precipitation_total: f64,
+    snow_total: f64,
     dominant_condition: Option<String>,
+    readings: i64,
 }
 
 #[allow(dead_code)]
@@ -221,7 +302,8 @@ impl HistoryRepository for SqliteHistoryRepository {
         let rows: Vec<HistoryRow> = sqlx::query_as(
             "SELECT city, location_key, lat, lon, timestamp, temperature, feels_like, humidity, pressure,
                     wind_speed, wind_direction, clouds, visibility, description, icon,
-                    rain_1h, snow_1h, units, fetched_at
+                    rain_1h, snow_1h, units, fetched_at, wind_gust, dew_point, uvi, condition_id,
+                    source
              FROM weather_history
              WHERE location_key = ? AND timestamp >= ? AND timestamp <= ? AND units = ?
              ORDER BY timestamp ASC",
@@ -252,13 +334,15 @@ impl HistoryRepository for SqliteHistoryRepository {
                 AVG(humidity) as humidity_avg,
                 AVG(wind_speed) as wind_speed_avg,
                 COALESCE(SUM(COALESCE(rain_1h, 0.0) + COALESCE(snow_1h, 0.0)), 0.0) as precipitation_total,
+                COALESCE(SUM(snow_1h), 0.0) as snow_total,
                 (SELECT h2.description FROM weather_history h2
                  WHERE h2.location_key = weather_history.location_key
                    AND date(h2.timestamp, 'unixepoch') = date(weather_history.timestamp, 'unixepoch')
                    AND h2.units = weather_history.units
                  GROUP BY h2.description
                  ORDER BY COUNT(*) DESC
-                 LIMIT 1) as dominant_condition
+                 LIMIT 1) as dominant_condition,
+                COUNT(*) as readings
              FROM weather_history
              WHERE location_key = ? AND timestamp >= ? AND timestamp <= ? AND units = ?
              GROUP BY date(timestamp, 'unixepoch')
@@ -281,11 +365,122 @@ impl HistoryRepository for SqliteHistoryRepository {
                 humidity_avg: r.humidity_avg,
                 wind_speed_avg: r.wind_speed_avg,
                 precipitation_total: r.precipitation_total,
+                snow_total: r.snow_total,
                 dominant_condition: r.dominant_condition,
+                readings: r.readings,
             })
             .collect())
     }
 
+    async fn get_after_id(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, HistoryRecord)>, DbError> {
+        let rows: Vec<NumberedHistoryRow> = sqlx::query_as(
+            "SELECT id, city, location_key, lat, lon, timestamp, temperature, feels_like, humidity,
+                    pressure, wind_speed, wind_direction, clouds, visibility, description, icon,
+                    rain_1h, snow_1h, units, fetched_at, wind_gust, dew_point, uvi, condition_id,
+                    source
+             FROM weather_history
+             WHERE id > ?
+             ORDER BY id ASC
+             LIMIT ?",
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.id, r.row.into())).collect())
+    }
+
+    async fn get_page_after(
+        &self,
+        location_key: &str,
+        after_ts: i64,
+        end_ts: i64,
+        units: &str,
+        limit: i64,
+    ) -> Result<Vec<(i64, HistoryRecord)>, DbError> {
+        let rows: Vec<NumberedHistoryRow> = sqlx::query_as(
+            "SELECT id, city, location_key, lat, lon, timestamp, temperature, feels_like, humidity,
+                    pressure, wind_speed, wind_direction, clouds, visibility, description, icon,
+                    rain_1h, snow_1h, units, fetched_at, wind_gust, dew_point, uvi, condition_id,
+                    source
+             FROM weather_history
+             WHERE location_key = ? AND timestamp > ? AND timestamp <= ? AND units = ?
+             ORDER BY timestamp ASC
+             LIMIT ?",
+        )
+        .bind(location_key)
+        .bind(after_ts)
+        .bind(end_ts)
+        .bind(units)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.id, r.row.into())).collect())
+    }
+
+    async fn months_before(&self, before_ts: i64) -> Result<Vec<(String, String)>, DbError> {
+        let months = sqlx::query_as(
+            "SELECT DISTINCT location_key, strftime('%Y-%m', timestamp, 'unixepoch') AS month
+             FROM weather_history
+             WHERE timestamp < ?
+             ORDER BY location_key, month",
+        )
+        .bind(before_ts)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(months)
+    }
+
+    async fn get_numbered_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<(i64, HistoryRecord)>, DbError> {
+        let rows: Vec<NumberedHistoryRow> = sqlx::query_as(
+            "SELECT id, city, location_key, lat, lon, timestamp, temperature, feels_like, humidity,
+                    pressure, wind_speed, wind_direction, clouds, visibility, description, icon,
+                    rain_1h, snow_1h, units, fetched_at, wind_gust, dew_point, uvi, condition_id,
+                    source
+             FROM weather_history
+             WHERE location_key = ? AND timestamp >= ? AND timestamp < ?
+             ORDER BY timestamp ASC, id ASC",
+        )
+        .bind(location_key)
+        .bind(start_ts)
+        .bind(end_ts)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.id, r.row.into())).collect())
+    }
+
+    async fn delete_range(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+        max_id: i64,
+    ) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "DELETE FROM weather_history
+             WHERE location_key = ? AND timestamp >= ? AND timestamp < ? AND id <= ?",
+        )
+        .bind(location_key)
+        .bind(start_ts)
+        .bind(end_ts)
+        .bind(max_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     async fn insert_batch(&self, records: &[HistoryRecord]) -> Result<usize, DbError> {
         let mut inserted = 0;
         for record in records {
@@ -294,8 +489,9 @@ impl HistoryRepository for SqliteHistoryRepository {
                 "INSERT OR IGNORE INTO weather_history
                  (city, location_key, lat, lon, timestamp, temperature, feels_like, humidity, pressure,
                   wind_speed, wind_direction, clouds, visibility, description, icon,
-                  rain_1h, snow_1h, units, fetched_at)
-                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                  rain_1h, snow_1h, units, fetched_at, wind_gust, dew_point, uvi, condition_id,
+                  source)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(&record.city)
             .bind(&location_key)
@@ -316,6 +512,11 @@ impl HistoryRepository for SqliteHistoryRepository {
             .bind(record.snow_1h)
             .bind(&record.units)
             .bind(record.fetched_at)
+            .bind(record.wind_gust)
+            .bind(record.dew_point)
+            .bind(record.uvi)
+            .bind(record.condition_id)
+            .bind(&record.source)
             .execute(&self.pool)
             .await?;
 
@@ -535,7 +736,7 @@ mod tests {
             max_connections: 1,
         };
         let pool = create_pool(&config).await.unwrap();
-        run_migrations(&pool).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
         pool
     }
 
@@ -560,6 +761,11 @@ mod tests {
             snow_1h: None,
             units: "metric".to_string(),
             fetched_at: 1700000000,
+            wind_gust: Some(9.2),
+            dew_point: Some(13.8),
+            uvi: None,
+            condition_id: Some(800),
+            source: SOURCE_TIMEMACHINE.to_string(),
         }
     }
 
@@ -587,6 +793,93 @@ mod tests {
         assert_eq!(result.len(), 3);
         assert_eq!(result[0].timestamp, 1700000000);
         assert_eq!(result[2].timestamp, 1700007200);
+        assert_eq!(result[0].wind_gust, Some(9.2));
+        assert_eq!(result[0].dew_point, Some(13.8));
+        assert_eq!(result[0].uvi, None);
+        assert_eq!(result[0].condition_id, Some(800));
+    }
+
+    #[tokio::test]
+    async fn test_get_after_id() {
+        let pool = setup_test_db().await;
+        let repo = SqliteHistoryRepository::new(pool);
+
+        let records: Vec<HistoryRecord> = (0..3)
+            .map(|i| create_test_record("Chicago", 1700000000 + i * 3600))
+            .collect();
+        repo.insert_batch(&records).await.unwrap();
+
+        let first = repo.get_after_id(0, 2).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].1.timestamp, 1700000000);
+
+        let rest = repo.get_after_id(first[1].0, 2).await.unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].1.timestamp, 1700007200);
+        assert!(repo.get_after_id(rest[0].0, 2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_page_after() {
+        let pool = setup_test_db().await;
+        let repo = SqliteHistoryRepository::new(pool);
+
+        let records: Vec<HistoryRecord> = (0..5)
+            .map(|i| create_test_record("Chicago", 1700000000 + i * 3600))
+            .collect();
+        repo.insert_batch(&records).await.unwrap();
+
+        let end = 1700000000 + 3 * 3600;
+        let first = repo
+            .get_page_after(TEST_LOCATION_KEY, 1699999999, end, "metric", 3)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].1.timestamp, 1700000000);
+
+        let rest = repo
+            .get_page_after(TEST_LOCATION_KEY, first[2].1.timestamp, end, "metric", 3)
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].1.timestamp, end);
+        assert!(repo
+            .get_page_after(TEST_LOCATION_KEY, 1699999999, end, "imperial", 3)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_month_ranges_for_archival() {
+        let pool = setup_test_db().await;
+        let repo = SqliteHistoryRepository::new(pool);
+
+        // Two hours in November 2023, one on 2023-12-01
+        let records = vec![
+            create_test_record("Chicago", 1700000000),
+            create_test_record("Chicago", 1700003600),
+            create_test_record("Chicago", 1701388800),
+        ];
+        repo.insert_batch(&records).await.unwrap();
+        let key = records[0].location_key.clone();
+
+        let months = repo.months_before(1701388800).await.unwrap();
+        assert_eq!(months, vec![(key.clone(), "2023-11".to_string())]);
+
+        let november = repo
+            .get_numbered_range(&key, 1698796800, 1701388800)
+            .await
+            .unwrap();
+        assert_eq!(november.len(), 2);
+
+        // Rows added after the range was read survive the delete
+        let deleted = repo
+            .delete_range(&key, 1698796800, 1701388800, november[0].0)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(repo.get_after_id(0, 10).await.unwrap().len(), 2);
     }
 
     #[tokio::test]
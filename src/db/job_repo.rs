@@ -54,6 +54,11 @@ impl SqliteJobRepository {
             include_hourly: row.include_hourly != 0,
             enabled: row.enabled != 0,
             notify,
+            owner: row.owner,
+            device_local_time: row.device_local_time != 0,
+            title_template: row.title_template,
+            body_prefix: row.body_prefix,
+            body_suffix: row.body_suffix,
         })
     }
 }
@@ -71,13 +76,18 @@ struct JobRow {
     include_hourly: i32,
     enabled: i32,
     notify_config: String,
+    owner: Option<String>,
+    device_local_time: i32,
+    title_template: Option<String>,
+    body_prefix: Option<String>,
+    body_suffix: Option<String>,
 }
 
 #[async_trait]
 impl JobRepository for SqliteJobRepository {
     async fn get(&self, id: &str) -> Result<Option<ForecastJob>, DbError> {
         let row: Option<JobRow> = sqlx::query_as(
-            "SELECT id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config
+            "SELECT id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config, owner, device_local_time, title_template, body_prefix, body_suffix
              FROM scheduler_jobs WHERE id = ?"
         )
         .bind(id)
@@ -89,7 +99,7 @@ impl JobRepository for SqliteJobRepository {
 
     async fn get_all(&self) -> Result<Vec<ForecastJob>, DbError> {
         let rows: Vec<JobRow> = sqlx::query_as(
-            "SELECT id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config
+            "SELECT id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config, owner, device_local_time, title_template, body_prefix, body_suffix
              FROM scheduler_jobs ORDER BY name"
         )
         .fetch_all(&self.pool)
@@ -100,7 +110,7 @@ impl JobRepository for SqliteJobRepository {
 
     async fn get_enabled(&self) -> Result<Vec<ForecastJob>, DbError> {
         let rows: Vec<JobRow> = sqlx::query_as(
-            "SELECT id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config
+            "SELECT id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config, owner, device_local_time, title_template, body_prefix, body_suffix
              FROM scheduler_jobs WHERE enabled = 1 ORDER BY name"
         )
         .fetch_all(&self.pool)
@@ -122,8 +132,8 @@ impl JobRepository for SqliteJobRepository {
         let notify_json = serde_json::to_string(&job.notify)?;
 
         sqlx::query(
-            "INSERT INTO scheduler_jobs (id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "INSERT INTO scheduler_jobs (id, name, city, units, cron, timezone, include_daily, include_hourly, enabled, notify_config, owner, device_local_time, title_template, body_prefix, body_suffix)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(id) DO UPDATE SET
                 name = excluded.name,
                 city = excluded.city,
@@ -133,7 +143,12 @@ impl JobRepository for SqliteJobRepository {
                 include_daily = excluded.include_daily,
                 include_hourly = excluded.include_hourly,
                 enabled = excluded.enabled,
-                notify_config = excluded.notify_config"
+                notify_config = excluded.notify_config,
+                owner = excluded.owner,
+                device_local_time = excluded.device_local_time,
+                title_template = excluded.title_template,
+                body_prefix = excluded.body_prefix,
+                body_suffix = excluded.body_suffix"
         )
         .bind(&job.id)
         .bind(&job.name)
@@ -145,6 +160,11 @@ impl JobRepository for SqliteJobRepository {
         .bind(if job.include_hourly { 1 } else { 0 })
         .bind(if job.enabled { 1 } else { 0 })
         .bind(&notify_json)
+        .bind(&job.owner)
+        .bind(if job.device_local_time { 1 } else { 0 })
+        .bind(&job.title_template)
+        .bind(&job.body_prefix)
+        .bind(&job.body_suffix)
         .execute(&self.pool)
         .await?;
 
@@ -180,7 +200,7 @@ mod tests {
             max_connections: 1,
         };
         let pool = create_pool(&config).await.unwrap();
-        run_migrations(&pool).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
         pool
     }
 
@@ -201,7 +221,21 @@ mod tests {
                 on_precipitation: false,
                 cold_threshold: Some(0.0),
                 heat_threshold: Some(35.0),
+                wind_threshold: None,
+                gust_threshold: None,
+                snow_threshold_mm: None,
+                snow_window_hours: None,
+                condition: None,
+                min_severity: None,
+                compare_yesterday: false,
+                include_clothing: false,
+                moon_events: false,
             },
+            owner: None,
+            device_local_time: false,
+            title_template: None,
+            body_prefix: None,
+            body_suffix: None,
         }
     }
 
@@ -210,7 +244,10 @@ mod tests {
         let pool = setup_test_db().await;
         let repo = SqliteJobRepository::new(pool);
 
-        let job = create_test_job("test-job-1");
+        let mut job = create_test_job("test-job-1");
+        job.owner = Some("alice".to_string());
+        job.title_template = Some("🏫 {name}".to_string());
+        job.body_suffix = Some("Pack a lunch".to_string());
         repo.upsert(&job).await.unwrap();
 
         let retrieved = repo.get("test-job-1").await.unwrap();
@@ -221,6 +258,10 @@ mod tests {
         assert_eq!(retrieved.name, job.name);
         assert_eq!(retrieved.city, job.city);
         assert_eq!(retrieved.notify.cold_threshold, Some(0.0));
+        assert_eq!(retrieved.owner.as_deref(), Some("alice"));
+        assert_eq!(retrieved.title_template.as_deref(), Some("🏫 {name}"));
+        assert_eq!(retrieved.body_prefix, None);
+        assert_eq!(retrieved.body_suffix.as_deref(), Some("Pack a lunch"));
     }
 
     #[tokio::test]
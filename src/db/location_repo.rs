@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::locations::SavedLocation;
+
+use super::DbError;
+
+/// Repository trait for saved locations
+#[async_trait]
+pub trait LocationRepository: Send + Sync {
+    /// Get all saved locations
+    async fn get_all(&self) -> Result<Vec<SavedLocation>, DbError>;
+
+    /// Insert or update a saved location
+    async fn upsert(&self, location: &SavedLocation) -> Result<(), DbError>;
+
+    /// Remove a saved location by ID
+    async fn remove(&self, id: &str) -> Result<bool, DbError>;
+}
+
+/// SQLite implementation of LocationRepository
+pub struct SqliteLocationRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteLocationRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Internal row structure for SQLite queries
+#[derive(sqlx::FromRow)]
+struct LocationRow {
+    id: String,
+    label: String,
+    city: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    units: Option<String>,
+    owner: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl From<LocationRow> for SavedLocation {
+    fn from(row: LocationRow) -> Self {
+        Self {
+            id: row.id,
+            label: row.label,
+            city: row.city,
+            lat: row.lat,
+            lon: row.lon,
+            units: row.units,
+            owner: row.owner,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[async_trait]
+impl LocationRepository for SqliteLocationRepository {
+    async fn get_all(&self) -> Result<Vec<SavedLocation>, DbError> {
+        let rows: Vec<LocationRow> = sqlx::query_as(
+            "SELECT id, label, city, lat, lon, units, owner, created_at, updated_at
+             FROM saved_locations ORDER BY label",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn upsert(&self, location: &SavedLocation) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO saved_locations (id, label, city, lat, lon, units, owner, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                label = excluded.label,
+                city = excluded.city,
+                lat = excluded.lat,
+                lon = excluded.lon,
+                units = excluded.units,
+                owner = excluded.owner,
+                updated_at = excluded.updated_at",
+        )
+        .bind(&location.id)
+        .bind(&location.label)
+        .bind(&location.city)
+        .bind(location.lat)
+        .bind(location.lon)
+        .bind(&location.units)
+        .bind(&location.owner)
+        .bind(location.created_at)
+        .bind(location.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM saved_locations WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+
+    async fn setup_test_db() -> SqlitePool {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_remove() {
+        let pool = setup_test_db().await;
+        let repo = SqliteLocationRepository::new(pool);
+
+        let mut location = SavedLocation {
+            id: "loc-1".to_string(),
+            label: "Home".to_string(),
+            city: None,
+            lat: Some(41.88),
+            lon: Some(-87.63),
+            units: Some("imperial".to_string()),
+            owner: Some("alice".to_string()),
+            created_at: 1700000000,
+            updated_at: 1700000000,
+        };
+        repo.upsert(&location).await.unwrap();
+
+        location.label = "Old home".to_string();
+        location.updated_at = 1700000100;
+        repo.upsert(&location).await.unwrap();
+
+        let stored = repo.get_all().await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].label, "Old home");
+        assert_eq!(stored[0].lat, Some(41.88));
+        assert_eq!(stored[0].created_at, 1700000000);
+
+        assert!(repo.remove("loc-1").await.unwrap());
+        assert!(!repo.remove("loc-1").await.unwrap());
+    }
+}
@@ -1,14 +1,44 @@
+mod alert_history_repo;
+mod alert_repo;
+mod anomaly_repo;
+mod audit_repo;
 mod device_repo;
+mod export_cursor_repo;
+mod forecast_archive_repo;
+mod forecast_issue_repo;
+mod history_archive_repo;
 pub mod history_repo;
 mod job_repo;
-
-pub use device_repo::{DeviceRepository, SqliteDeviceRepository};
+mod location_repo;
+mod notification_log_repo;
+mod webhook_repo;
+
+pub use alert_history_repo::{AlertHistoryRepository, SqliteAlertHistoryRepository};
+pub use alert_repo::{alert_fingerprint, AlertLedger, AlertRepository, SqliteAlertRepository};
+pub use anomaly_repo::{AnomalyRepository, SqliteAnomalyRepository, WeatherAnomaly};
+pub use audit_repo::{AuditRepository, SqliteAuditRepository};
+pub use device_repo::{DeviceFilter, DeviceRepository, SqliteDeviceRepository};
+pub use export_cursor_repo::{ExportCursorRepository, SqliteExportCursorRepository};
+pub use forecast_archive_repo::{
+    ArchivedForecast, ForecastArchiveRepository, SqliteForecastArchiveRepository,
+};
+pub use forecast_issue_repo::{
+    ForecastIssue, ForecastIssueRepository, SqliteForecastIssueRepository,
+};
+pub use history_archive_repo::{
+    HistoryArchive, HistoryArchiveRepository, SqliteHistoryArchiveRepository,
+};
 pub use job_repo::{JobRepository, SqliteJobRepository};
+pub use location_repo::{LocationRepository, SqliteLocationRepository};
+pub use notification_log_repo::{NotificationLogRepository, SqliteNotificationLogRepository};
+pub use webhook_repo::{SqliteWebhookRepository, WebhookRepository};
 
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use std::path::Path;
 use thiserror::Error;
 
+use crate::config::FeaturesConfig;
+
 #[derive(Error, Debug)]
 pub enum DbError {
     #[error("Database error: {0}")]
@@ -19,6 +49,9 @@ pub enum DbError {
 
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+
+    #[error("Compression error: {0}")]
+    Compression(#[from] std::io::Error),
 }
 
 /// Database configuration
@@ -57,40 +90,308 @@ pub async fn create_pool(config: &DbConfig) -> Result<SqlitePool, DbError> {
     Ok(pool)
 }
 
-/// Run database migrations
-pub async fn run_migrations(pool: &SqlitePool) -> Result<(), DbError> {
+/// Devices and jobs share migration 001, so its tables exist when either
+/// subsystem is enabled
+fn device_tables(features: &FeaturesConfig) -> bool {
+    features.devices || features.scheduler
+}
+
+/// Run database migrations, skipping tables of disabled subsystems
+pub async fn run_migrations(pool: &SqlitePool, features: &FeaturesConfig) -> Result<(), DbError> {
     // Run migrations in order
-    let migration_001 = include_str!("../../migrations/001_create_tables.sql");
-    sqlx::raw_sql(migration_001).execute(pool).await?;
+    if device_tables(features) {
+        let migration_001 = include_str!("../../migrations/001_create_tables.sql");
+        sqlx::raw_sql(migration_001).execute(pool).await?;
+    }
 
-    let migration_002 = include_str!("../../migrations/002_create_history_table.sql");
-    sqlx::raw_sql(migration_002).execute(pool).await?;
+    if features.history {
+        let migration_002 = include_str!("../../migrations/002_create_history_table.sql");
+        sqlx::raw_sql(migration_002).execute(pool).await?;
+    }
 
     let migration_003 = include_str!("../../migrations/003_create_geocoding_cache.sql");
     sqlx::raw_sql(migration_003).execute(pool).await?;
 
-    // Migration 004: Add location_key column with backfill and dedup.
-    // The ALTER TABLE ADD COLUMN is not idempotent in SQLite, so we attempt it
-    // separately and ignore "duplicate column" errors on subsequent runs.
-    let _ = sqlx::raw_sql("ALTER TABLE weather_history ADD COLUMN location_key TEXT;")
+    if features.history {
+        // Migration 004: Add location_key column with backfill and dedup.
+        // The ALTER TABLE ADD COLUMN is not idempotent in SQLite, so we attempt it
+        // separately and ignore "duplicate column" errors on subsequent runs.
+        let _ = sqlx::raw_sql("ALTER TABLE weather_history ADD COLUMN location_key TEXT;")
+            .execute(pool)
+            .await;
+
+        let migration_004 = include_str!("../../migrations/004_add_location_key.sql");
+        sqlx::raw_sql(migration_004)
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(format!("Migration 004 failed: {}", e)))?;
+
+        // Migration 024: wind gust, dew point, UV index, and condition code on
+        // history rows. Older rows keep NULLs.
+        for column in [
+            "wind_gust REAL",
+            "dew_point REAL",
+            "uvi REAL",
+            "condition_id INTEGER",
+        ] {
+            let _ = sqlx::raw_sql(&format!(
+                "ALTER TABLE weather_history ADD COLUMN {};",
+                column
+            ))
+            .execute(pool)
+            .await;
+        }
+
+        // Migration 025: history in metric only, converting imperial and
+        // standard rows. Idempotent once every row is metric.
+        sqlx::raw_sql(include_str!(
+            "../../migrations/025_canonical_history_units.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 025 failed: {}", e)))?;
+
+        // Migration 026: where each history row came from. Rows before it
+        // all came from Timemachine.
+        let _ = sqlx::raw_sql(
+            "ALTER TABLE weather_history ADD COLUMN source TEXT NOT NULL DEFAULT 'timemachine';",
+        )
         .execute(pool)
         .await;
 
-    let migration_004 = include_str!("../../migrations/004_add_location_key.sql");
-    sqlx::raw_sql(migration_004)
+        sqlx::raw_sql(include_str!(
+            "../../migrations/019_create_forecast_issues.sql"
+        ))
         .execute(pool)
         .await
-        .map_err(|e| DbError::Migration(format!("Migration 004 failed: {}", e)))?;
+        .map_err(|e| DbError::Migration(format!("Migration 019 failed: {}", e)))?;
+    }
 
     sqlx::raw_sql(include_str!("../../migrations/005_create_tile_usage.sql"))
         .execute(pool)
         .await
         .map_err(|e| DbError::Migration(format!("Migration 005 failed: {}", e)))?;
 
+    if device_tables(features) {
+        run_device_migrations(pool).await?;
+    }
+
+    // Alerts and quarantined readings are recorded by scheduled jobs and
+    // history fetches, and read back through history
+    if features.scheduler || features.history {
+        sqlx::raw_sql(include_str!(
+            "../../migrations/014_create_alert_history.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 014 failed: {}", e)))?;
+
+        sqlx::raw_sql(include_str!(
+            "../../migrations/028_create_weather_anomalies.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 028 failed: {}", e)))?;
+    }
+
+    if features.devices {
+        sqlx::raw_sql(include_str!(
+            "../../migrations/010_create_notification_log.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 010 failed: {}", e)))?;
+    }
+
+    if features.scheduler {
+        sqlx::raw_sql(include_str!("../../migrations/016_create_webhooks.sql"))
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(format!("Migration 016 failed: {}", e)))?;
+
+        sqlx::raw_sql(include_str!("../../migrations/030_create_alerts.sql"))
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(format!("Migration 030 failed: {}", e)))?;
+
+        sqlx::raw_sql(include_str!(
+            "../../migrations/031_create_alert_deliveries.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 031 failed: {}", e)))?;
+    }
+
+    sqlx::raw_sql(include_str!("../../migrations/017_create_audit_log.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 017 failed: {}", e)))?;
+
+    sqlx::raw_sql(include_str!(
+        "../../migrations/018_create_saved_locations.sql"
+    ))
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(format!("Migration 018 failed: {}", e)))?;
+
+    if features.history {
+        sqlx::raw_sql(include_str!(
+            "../../migrations/020_create_forecast_archive.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 020 failed: {}", e)))?;
+
+        sqlx::raw_sql(include_str!(
+            "../../migrations/021_create_export_cursors.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 021 failed: {}", e)))?;
+
+        sqlx::raw_sql(include_str!(
+            "../../migrations/022_create_history_archives.sql"
+        ))
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(format!("Migration 022 failed: {}", e)))?;
+    }
+
     tracing::info!("Database migrations completed");
     Ok(())
 }
 
+/// Columns added to the devices and scheduler_jobs tables after 001
+async fn run_device_migrations(pool: &SqlitePool) -> Result<(), DbError> {
+    // Migration 006: device heartbeat columns. As with 004, the ADD COLUMN
+    // statements fail harmlessly once the columns exist.
+    for statement in [
+        "ALTER TABLE devices ADD COLUMN last_seen INTEGER;",
+        "ALTER TABLE devices ADD COLUMN lat REAL;",
+        "ALTER TABLE devices ADD COLUMN lon REAL;",
+    ] {
+        let _ = sqlx::raw_sql(statement).execute(pool).await;
+    }
+
+    // Migration 013: per-device event subscriptions, stored as JSON (NULL = everything)
+    let _ = sqlx::raw_sql("ALTER TABLE devices ADD COLUMN subscriptions TEXT;")
+        .execute(pool)
+        .await;
+
+    sqlx::raw_sql(include_str!(
+        "../../migrations/006_add_device_last_seen.sql"
+    ))
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(format!("Migration 006 failed: {}", e)))?;
+
+    // Migration 007: per-device quiet hours, stored as JSON
+    let _ = sqlx::raw_sql("ALTER TABLE devices ADD COLUMN quiet_hours TEXT;")
+        .execute(pool)
+        .await;
+
+    // Migration 008: per-device notification language
+    let _ = sqlx::raw_sql("ALTER TABLE devices ADD COLUMN language TEXT NOT NULL DEFAULT 'en';")
+        .execute(pool)
+        .await;
+
+    // Migration 009: per-device alert filter, stored as JSON (NULL = deliver everything)
+    let _ = sqlx::raw_sql("ALTER TABLE devices ADD COLUMN alert_preferences TEXT;")
+        .execute(pool)
+        .await;
+
+    // Migration 011: opaque client metadata on devices, stored as JSON
+    let _ = sqlx::raw_sql("ALTER TABLE devices ADD COLUMN metadata TEXT;")
+        .execute(pool)
+        .await;
+
+    // Migration 012: per-device temperature thresholds (in the device's units)
+    for statement in [
+        "ALTER TABLE devices ADD COLUMN cold_threshold REAL;",
+        "ALTER TABLE devices ADD COLUMN heat_threshold REAL;",
+    ] {
+        let _ = sqlx::raw_sql(statement).execute(pool).await;
+    }
+
+    // Migration 015: owning user for jobs and devices (NULL = shared, admin-managed)
+    for statement in [
+        "ALTER TABLE scheduler_jobs ADD COLUMN owner TEXT;",
+        "ALTER TABLE devices ADD COLUMN owner TEXT;",
+    ] {
+        let _ = sqlx::raw_sql(statement).execute(pool).await;
+    }
+
+    // Migration 023: device timezones, and jobs delivered at each device's local time
+    for statement in [
+        "ALTER TABLE devices ADD COLUMN timezone TEXT;",
+        "ALTER TABLE scheduler_jobs ADD COLUMN device_local_time INTEGER NOT NULL DEFAULT 0;",
+    ] {
+        let _ = sqlx::raw_sql(statement).execute(pool).await;
+    }
+
+    // Migration 027: per-job notification title and body wording
+    for statement in [
+        "ALTER TABLE scheduler_jobs ADD COLUMN title_template TEXT;",
+        "ALTER TABLE scheduler_jobs ADD COLUMN body_prefix TEXT;",
+        "ALTER TABLE scheduler_jobs ADD COLUMN body_suffix TEXT;",
+    ] {
+        let _ = sqlx::raw_sql(statement).execute(pool).await;
+    }
+
+    // Migration 029: per-device minimum notification priority
+    let _ = sqlx::raw_sql("ALTER TABLE devices ADD COLUMN min_priority TEXT;")
+        .execute(pool)
+        .await;
+
+    Ok(())
+}
+
+/// Tables created by `run_migrations` for the enabled subsystems
+fn schema_tables(features: &FeaturesConfig) -> Vec<&'static str> {
+    let mut tables = vec![
+        "geocoding_cache",
+        "tile_usage",
+        "audit_log",
+        "saved_locations",
+    ];
+    if device_tables(features) {
+        tables.extend(["devices", "scheduler_jobs"]);
+    }
+    if features.history {
+        tables.extend([
+            "weather_history",
+            "forecast_issues",
+            "forecast_archive",
+            "export_cursors",
+            "history_archives",
+        ]);
+    }
+    if features.scheduler || features.history {
+        tables.extend(["alert_history", "weather_anomalies"]);
+    }
+    if features.devices {
+        tables.push("notification_log");
+    }
+    if features.scheduler {
+        tables.extend(["webhooks", "alerts", "alert_deliveries"]);
+    }
+    tables
+}
+
+/// Whether every table the migrations create for the enabled subsystems exists
+pub async fn migrations_applied(
+    pool: &SqlitePool,
+    features: &FeaturesConfig,
+) -> Result<bool, DbError> {
+    let existing: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .fetch_all(pool)
+            .await?;
+    Ok(schema_tables(features)
+        .iter()
+        .all(|table| existing.iter().any(|name| name == table)))
+}
+
 /// Import devices from JSON file to SQLite (one-time migration helper)
 #[allow(dead_code)]
 pub async fn import_devices_from_json(
@@ -186,8 +487,77 @@ mod tests {
             max_connections: 1,
         };
         let pool = create_pool(&config).await.expect("Failed to create pool");
-        run_migrations(&pool)
+        run_migrations(&pool, &FeaturesConfig::default())
             .await
             .expect("Failed to run migrations");
+        assert!(migrations_applied(&pool, &FeaturesConfig::default())
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_features_skip_tables() {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        let features = FeaturesConfig {
+            history: false,
+            devices: false,
+            scheduler: false,
+            swagger_ui: false,
+        };
+        run_migrations(&pool, &features).await.unwrap();
+        assert!(migrations_applied(&pool, &features).await.unwrap());
+        assert!(!migrations_applied(&pool, &FeaturesConfig::default())
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_history_converted_to_metric() {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        let features = FeaturesConfig::default();
+        run_migrations(&pool, &features).await.unwrap();
+
+        for (timestamp, temperature, wind_speed, units) in [
+            (3600, 20.0, 5.0, "metric"),
+            (3600, 68.0, 11.2, "imperial"),
+            (7200, 50.0, 10.0, "imperial"),
+            (7200, 283.15, 4.47, "standard"),
+        ] {
+            sqlx::query(
+                "INSERT INTO weather_history (city, location_key, lat, lon, timestamp, temperature,
+                     feels_like, humidity, pressure, wind_speed, units, fetched_at)
+                 VALUES ('Chicago', '41.88,-87.63', 41.88, -87.63, ?, ?, ?, 50, 1013, ?, ?, 0)",
+            )
+            .bind(timestamp)
+            .bind(temperature)
+            .bind(temperature)
+            .bind(wind_speed)
+            .bind(units)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        run_migrations(&pool, &features).await.unwrap();
+
+        let rows: Vec<(i64, f64, f64, String)> = sqlx::query_as(
+            "SELECT timestamp, temperature, wind_speed, units FROM weather_history ORDER BY timestamp",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], (3600, 20.0, 5.0, "metric".to_string()));
+        assert_eq!(rows[1].0, 7200);
+        assert!((rows[1].1 - 10.0).abs() < 1e-9);
+        assert!((rows[1].2 - 4.4704).abs() < 1e-9);
+        assert_eq!(rows[1].3, "metric");
     }
 }
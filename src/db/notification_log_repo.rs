@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::devices::models::{DeliveryRecord, DeliveryStatus};
+use crate::notifications::Priority;
+
+use super::DbError;
+
+/// Repository trait for the per-device notification delivery log
+#[async_trait]
+pub trait NotificationLogRepository: Send + Sync {
+    /// Append delivery records
+    async fn record(&self, records: &[DeliveryRecord]) -> Result<(), DbError>;
+
+    /// Most recent records for a device, newest first
+    async fn recent_for_device(
+        &self,
+        device_id: &str,
+        limit: u32,
+    ) -> Result<Vec<DeliveryRecord>, DbError>;
+
+    /// Records for a device, newest first, that sort after the
+    /// `(created_at, id)` of the last record on the previous page. Each
+    /// record comes with its row id.
+    async fn page_for_device(
+        &self,
+        device_id: &str,
+        before: Option<(i64, i64)>,
+        limit: u32,
+    ) -> Result<Vec<(i64, DeliveryRecord)>, DbError>;
+
+    /// Most recent records across all devices, newest first
+    async fn recent(&self, limit: u32) -> Result<Vec<DeliveryRecord>, DbError>;
+
+    /// Delete records created before the given timestamp
+    async fn prune_before(&self, timestamp: i64) -> Result<u64, DbError>;
+
+    /// Per-device count of non-urgent pushes sent since the given timestamp. A
+    /// digest is one push; the messages queued for it aren't counted separately.
+    async fn non_urgent_counts_since(&self, since: i64) -> Result<HashMap<String, u32>, DbError>;
+}
+
+/// SQLite implementation of NotificationLogRepository
+pub struct SqliteNotificationLogRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteNotificationLogRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn row_to_record(row: DeliveryRow) -> Result<DeliveryRecord, DbError> {
+        let priority: Priority = serde_json::from_value(serde_json::Value::String(row.priority))?;
+        let status: DeliveryStatus = serde_json::from_value(serde_json::Value::String(row.status))?;
+
+        Ok(DeliveryRecord {
+            device_id: row.device_id,
+            title: row.title,
+            body: row.body,
+            priority,
+            status,
+            error: row.error,
+            created_at: row.created_at,
+        })
+    }
+
+    /// Serialize a unit enum to its serde string form (e.g. `Priority::High` -> "high")
+    fn enum_str<T: serde::Serialize>(value: &T) -> Result<String, DbError> {
+        let value = serde_json::to_value(value)?;
+        Ok(value.as_str().unwrap_or_default().to_string())
+    }
+}
+
+/// Internal row structure for SQLite queries
+#[derive(sqlx::FromRow)]
+struct DeliveryRow {
+    device_id: String,
+    title: String,
+    body: String,
+    priority: String,
+    status: String,
+    error: Option<String>,
+    created_at: i64,
+}
+
+/// Delivery row plus its id, for keyset pagination
+#[derive(sqlx::FromRow)]
+struct DeliveryPageRow {
+    id: i64,
+    #[sqlx(flatten)]
+    row: DeliveryRow,
+}
+
+#[async_trait]
+impl NotificationLogRepository for SqliteNotificationLogRepository {
+    async fn record(&self, records: &[DeliveryRecord]) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+        for record in records {
+            sqlx::query(
+                "INSERT INTO notification_log (device_id, title, body, priority, status, error, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&record.device_id)
+            .bind(&record.title)
+            .bind(&record.body)
+            .bind(Self::enum_str(&record.priority)?)
+            .bind(Self::enum_str(&record.status)?)
+            .bind(&record.error)
+            .bind(record.created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn recent_for_device(
+        &self,
+        device_id: &str,
+        limit: u32,
+    ) -> Result<Vec<DeliveryRecord>, DbError> {
+        let rows: Vec<DeliveryRow> = sqlx::query_as(
+            "SELECT device_id, title, body, priority, status, error, created_at
+             FROM notification_log WHERE device_id = ?
+             ORDER BY created_at DESC, id DESC LIMIT ?",
+        )
+        .bind(device_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    async fn page_for_device(
+        &self,
+        device_id: &str,
+        before: Option<(i64, i64)>,
+        limit: u32,
+    ) -> Result<Vec<(i64, DeliveryRecord)>, DbError> {
+        let rows: Vec<DeliveryPageRow> = sqlx::query_as(
+            "SELECT id, device_id, title, body, priority, status, error, created_at
+             FROM notification_log
+             WHERE device_id = ?1
+               AND (?2 IS NULL OR created_at < ?2 OR (created_at = ?2 AND id < ?3))
+             ORDER BY created_at DESC, id DESC LIMIT ?4",
+        )
+        .bind(device_id)
+        .bind(before.map(|(created_at, _)| created_at))
+        .bind(before.map(|(_, id)| id))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|r| Ok((r.id, Self::row_to_record(r.row)?)))
+            .collect()
+    }
+
+    async fn recent(&self, limit: u32) -> Result<Vec<DeliveryRecord>, DbError> {
+        let rows: Vec<DeliveryRow> = sqlx::query_as(
+            "SELECT device_id, title, body, priority, status, error, created_at
+             FROM notification_log ORDER BY created_at DESC, id DESC LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    async fn prune_before(&self, timestamp: i64) -> Result<u64, DbError> {
+        let result = sqlx::query("DELETE FROM notification_log WHERE created_at < ?")
+            .bind(timestamp)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn non_urgent_counts_since(&self, since: i64) -> Result<HashMap<String, u32>, DbError> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT device_id, COUNT(*) FROM notification_log
+             WHERE created_at >= ? AND status = 'sent' AND priority != 'urgent'
+             GROUP BY device_id",
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(device_id, count)| (device_id, count as u32))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+
+    async fn setup_test_db() -> SqlitePool {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        pool
+    }
+
+    fn create_test_record(device_id: &str, created_at: i64) -> DeliveryRecord {
+        DeliveryRecord {
+            device_id: device_id.to_string(),
+            title: "Chicago, US".to_string(),
+            body: "Sunny".to_string(),
+            priority: Priority::Urgent,
+            status: DeliveryStatus::Sent,
+            error: None,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_recent_for_device() {
+        let pool = setup_test_db().await;
+        let repo = SqliteNotificationLogRepository::new(pool);
+
+        let mut failed = create_test_record("device-1", 1700000100);
+        failed.status = DeliveryStatus::Failed;
+        failed.error = Some("timeout".to_string());
+        repo.record(&[
+            create_test_record("device-1", 1700000000),
+            failed,
+            create_test_record("device-2", 1700000200),
+        ])
+        .await
+        .unwrap();
+
+        let records = repo.recent_for_device("device-1", 10).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].status, DeliveryStatus::Failed);
+        assert_eq!(records[0].error.as_deref(), Some("timeout"));
+        assert_eq!(records[1].priority, Priority::Urgent);
+
+        let records = repo.recent_for_device("device-1", 1).await.unwrap();
+        assert_eq!(records.len(), 1);
+
+        let records = repo.recent(2).await.unwrap();
+        assert_eq!(records[0].device_id, "device-2");
+        assert_eq!(records[1].status, DeliveryStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_page_for_device() {
+        let pool = setup_test_db().await;
+        let repo = SqliteNotificationLogRepository::new(pool);
+
+        repo.record(&[
+            create_test_record("device-1", 1700000000),
+            create_test_record("device-1", 1700000100),
+            create_test_record("device-1", 1700000100),
+            create_test_record("device-2", 1700000200),
+        ])
+        .await
+        .unwrap();
+
+        let page = repo.page_for_device("device-1", None, 2).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert!(page.iter().all(|(_, r)| r.created_at == 1700000100));
+        assert!(page[0].0 > page[1].0);
+
+        let (id, last) = &page[1];
+        let page = repo
+            .page_for_device("device-1", Some((last.created_at, *id)), 2)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].1.created_at, 1700000000);
+    }
+
+    #[tokio::test]
+    async fn test_non_urgent_counts_since() {
+        let pool = setup_test_db().await;
+        let repo = SqliteNotificationLogRepository::new(pool);
+
+        let mut normal = create_test_record("device-1", 1700000100);
+        normal.priority = Priority::Default;
+        let mut failed = normal.clone();
+        failed.status = DeliveryStatus::Failed;
+        let mut queued = normal.clone();
+        queued.status = DeliveryStatus::Queued;
+        let mut old = normal.clone();
+        old.created_at = 1600000000;
+        repo.record(&[
+            normal.clone(),
+            normal,
+            failed,
+            queued,
+            old,
+            create_test_record("device-1", 1700000200),
+        ])
+        .await
+        .unwrap();
+
+        let counts = repo.non_urgent_counts_since(1700000000).await.unwrap();
+        assert_eq!(counts.get("device-1"), Some(&2));
+        assert_eq!(counts.get("device-2"), None);
+    }
+
+    #[tokio::test]
+    async fn test_prune_before() {
+        let pool = setup_test_db().await;
+        let repo = SqliteNotificationLogRepository::new(pool);
+
+        repo.record(&[
+            create_test_record("device-1", 1700000000),
+            create_test_record("device-1", 1800000000),
+        ])
+        .await
+        .unwrap();
+
+        assert_eq!(repo.prune_before(1750000000).await.unwrap(), 1);
+        assert_eq!(
+            repo.recent_for_device("device-1", 10).await.unwrap().len(),
+            1
+        );
+    }
+}
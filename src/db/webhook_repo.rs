@@ -0,0 +1,189 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::webhooks::Webhook;
+
+use super::DbError;
+
+/// Repository trait for incoming webhooks
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    /// Get a webhook by its ID
+    async fn get(&self, id: &str) -> Result<Option<Webhook>, DbError>;
+
+    /// Get all webhooks
+    async fn get_all(&self) -> Result<Vec<Webhook>, DbError>;
+
+    /// Insert or update a webhook
+    async fn upsert(&self, webhook: &Webhook) -> Result<(), DbError>;
+
+    /// Remove a webhook by ID
+    async fn remove(&self, id: &str) -> Result<bool, DbError>;
+
+    /// Record when a webhook last fired
+    async fn set_last_triggered(&self, id: &str, timestamp: i64) -> Result<(), DbError>;
+}
+
+/// SQLite implementation of WebhookRepository
+pub struct SqliteWebhookRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteWebhookRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Internal row structure for SQLite queries
+#[derive(sqlx::FromRow)]
+struct WebhookRow {
+    id: String,
+    name: String,
+    secret_hash: String,
+    job_id: Option<String>,
+    city: Option<String>,
+    units: Option<String>,
+    enabled: i32,
+    owner: Option<String>,
+    created_at: i64,
+    last_triggered: Option<i64>,
+}
+
+impl From<WebhookRow> for Webhook {
+    fn from(row: WebhookRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            secret_hash: row.secret_hash,
+            job_id: row.job_id,
+            city: row.city,
+            units: row.units,
+            enabled: row.enabled != 0,
+            owner: row.owner,
+            created_at: row.created_at,
+            last_triggered: row.last_triggered,
+        }
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for SqliteWebhookRepository {
+    async fn get(&self, id: &str) -> Result<Option<Webhook>, DbError> {
+        let row: Option<WebhookRow> = sqlx::query_as(
+            "SELECT id, name, secret_hash, job_id, city, units, enabled, owner, created_at, last_triggered
+             FROM webhooks WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn get_all(&self) -> Result<Vec<Webhook>, DbError> {
+        let rows: Vec<WebhookRow> = sqlx::query_as(
+            "SELECT id, name, secret_hash, job_id, city, units, enabled, owner, created_at, last_triggered
+             FROM webhooks ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn upsert(&self, webhook: &Webhook) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO webhooks (id, name, secret_hash, job_id, city, units, enabled, owner, created_at, last_triggered)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                secret_hash = excluded.secret_hash,
+                job_id = excluded.job_id,
+                city = excluded.city,
+                units = excluded.units,
+                enabled = excluded.enabled,
+                owner = excluded.owner,
+                last_triggered = excluded.last_triggered",
+        )
+        .bind(&webhook.id)
+        .bind(&webhook.name)
+        .bind(&webhook.secret_hash)
+        .bind(&webhook.job_id)
+        .bind(&webhook.city)
+        .bind(&webhook.units)
+        .bind(i32::from(webhook.enabled))
+        .bind(&webhook.owner)
+        .bind(webhook.created_at)
+        .bind(webhook.last_triggered)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_last_triggered(&self, id: &str, timestamp: i64) -> Result<(), DbError> {
+        sqlx::query("UPDATE webhooks SET last_triggered = ? WHERE id = ?")
+            .bind(timestamp)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+
+    async fn setup_test_db() -> SqlitePool {
+        let config = DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        };
+        let pool = create_pool(&config).await.unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_upsert_trigger_and_remove() {
+        let pool = setup_test_db().await;
+        let repo = SqliteWebhookRepository::new(pool);
+
+        let webhook = Webhook {
+            id: "hook-1".to_string(),
+            name: "Doorbell".to_string(),
+            secret_hash: "abc".to_string(),
+            job_id: None,
+            city: Some("Chicago".to_string()),
+            units: None,
+            enabled: true,
+            owner: Some("alice".to_string()),
+            created_at: 1700000000,
+            last_triggered: None,
+        };
+        repo.upsert(&webhook).await.unwrap();
+        repo.set_last_triggered("hook-1", 1700000100).await.unwrap();
+
+        let stored = repo.get("hook-1").await.unwrap().unwrap();
+        assert_eq!(stored.city.as_deref(), Some("Chicago"));
+        assert_eq!(stored.owner.as_deref(), Some("alice"));
+        assert_eq!(stored.last_triggered, Some(1700000100));
+        assert_eq!(repo.get_all().await.unwrap().len(), 1);
+
+        assert!(repo.remove("hook-1").await.unwrap());
+        assert!(repo.get("hook-1").await.unwrap().is_none());
+    }
+}
@@ -1,22 +1,129 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
 use serde_json::json;
 
+use crate::audit::{self, AuditAction, AuditActor, AuditResource};
+use crate::auth::Principal;
+use crate::error::ErrorResponse;
 use crate::AppState;
 
 use super::models::{
-    DeviceRegistrationRequest, DeviceResponse, DeviceSettingsRequest, DeviceUnregisterRequest,
+    DeviceBroadcastRequest, DeviceHeartbeatRequest, DeviceListQuery, DeviceListResponse,
+    DeviceLocationRequest, DeviceRegistrationRequest, DeviceResponse, DeviceSettingsRequest,
+    DeviceSummary, DeviceTokenQuery, DeviceUnregisterRequest, NotificationHistoryQuery,
     TestNotificationRequest,
 };
+use super::service::DevicesError;
+use super::Device;
+
+/// Audit a device change. `before` is the device as it was, `after` as it
+/// is now; `None` on either side means it didn't or no longer exists.
+async fn audit_device(
+    state: &AppState,
+    actor: &AuditActor,
+    before: Option<Device>,
+    after: Option<Device>,
+) {
+    let before = before.map(DeviceSummary::from);
+    let after = after.map(DeviceSummary::from);
+    let (action, id, changes) = match (before, after) {
+        (None, Some(after)) => (
+            AuditAction::Create,
+            after.id.clone(),
+            audit::snapshot(&after),
+        ),
+        (Some(before), Some(after)) => (
+            AuditAction::Update,
+            after.id.clone(),
+            audit::changes(&before, &after),
+        ),
+        (Some(before), None) => (
+            AuditAction::Delete,
+            before.id.clone(),
+            audit::snapshot(&before),
+        ),
+        (None, None) => return,
+    };
+    state
+        .audit
+        .record(actor, action, AuditResource::Device, &id, changes)
+        .await;
+}
 
 /// POST /devices/register - Register a device for push notifications
+///
+/// With a bearer token, the device is registered to the signed-in user.
+/// Registering an already registered token, even concurrently, updates and
+/// returns its existing record. With an `Idempotency-Key` header, a retry of
+/// the same request replays the first response.
+#[utoipa::path(
+    post,
+    path = "/api/v1/devices/register",
+    tag = "devices",
+    request_body = DeviceRegistrationRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key that makes retries safe, up to 255 characters")),
+    responses(
+        (status = 200, description = "Device registered", body = DeviceResponse),
+        (status = 400, description = "Invalid registration or Idempotency-Key", body = DeviceResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "Device is registered to another user", body = DeviceResponse),
+        (status = 422, description = "Idempotency-Key reused for a different request", body = DeviceResponse)
+    ),
+    security(("api_key" = []), ("bearer" = []))
+)]
 pub async fn register_device(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
+    headers: HeaderMap,
     Json(request): Json<DeviceRegistrationRequest>,
 ) -> impl IntoResponse {
-    match state.devices_service.register(request).await {
-        Ok(device) => (
-            StatusCode::OK,
-            Json(DeviceResponse::success(Some(device.id))),
+    let owner = principal.map(|Extension(p)| p.username);
+    let idempotency_key = headers
+        .get("Idempotency-Key")
+        .map(|v| v.to_str().unwrap_or_default().to_string());
+    let before = state.devices_service.get_by_token(&request.token).await;
+    let result = match idempotency_key {
+        Some(key) => {
+            state
+                .devices_service
+                .register_idempotent(&key, request, owner)
+                .await
+        }
+        None => state
+            .devices_service
+            .register(request, owner)
+            .await
+            .map(|device| (device, false)),
+    };
+    match result {
+        Ok((device, replayed)) => {
+            if !replayed {
+                audit_device(&state, &actor, before, Some(device.clone())).await;
+            }
+            (StatusCode::OK, Json(DeviceResponse::registered(device)))
+        }
+        Err(
+            e @ (DevicesError::InvalidQuietHours(_)
+            | DevicesError::InvalidLanguage(_)
+            | DevicesError::InvalidTimezone(_)
+            | DevicesError::InvalidMetadata(_)
+            | DevicesError::InvalidIdempotencyKey(_)),
+        ) => (
+            StatusCode::BAD_REQUEST,
+            Json(DeviceResponse::error(e.to_string())),
+        ),
+        Err(e @ DevicesError::OwnedByAnother) => (
+            StatusCode::FORBIDDEN,
+            Json(DeviceResponse::error(e.to_string())),
+        ),
+        Err(e @ DevicesError::IdempotencyKeyReused) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(DeviceResponse::error(e.to_string())),
         ),
         Err(e) => {
             tracing::error!(error = %e, "Failed to register device");
@@ -29,13 +136,28 @@ pub async fn register_device(
 }
 
 /// POST /devices/unregister - Unregister a device
+#[utoipa::path(
+    post,
+    path = "/api/v1/devices/unregister",
+    tag = "devices",
+    request_body = DeviceUnregisterRequest,
+    responses(
+        (status = 200, description = "Device removed", body = DeviceResponse),
+        (status = 404, description = "Device not found", body = DeviceResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse)
+    ),
+    security(("api_key" = []), ("bearer" = []))
+)]
 pub async fn unregister_device(
     State(state): State<AppState>,
+    actor: AuditActor,
     Json(request): Json<DeviceUnregisterRequest>,
 ) -> impl IntoResponse {
+    let before = state.devices_service.get_by_token(&request.token).await;
     match state.devices_service.unregister(&request.token).await {
         Ok(removed) => {
             if removed {
+                audit_device(&state, &actor, before, None).await;
                 (StatusCode::OK, Json(DeviceResponse::success(None)))
             } else {
                 (
@@ -55,21 +177,173 @@ pub async fn unregister_device(
 }
 
 /// PUT /devices/settings - Update device settings
+#[utoipa::path(
+    put,
+    path = "/api/v1/devices/settings",
+    tag = "devices",
+    request_body = DeviceSettingsRequest,
+    responses(
+        (status = 200, description = "Settings updated", body = DeviceResponse),
+        (status = 404, description = "Device not found", body = DeviceResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse)
+    ),
+    security(("api_key" = []), ("bearer" = []))
+)]
 pub async fn update_device_settings(
     State(state): State<AppState>,
+    actor: AuditActor,
     Json(request): Json<DeviceSettingsRequest>,
 ) -> impl IntoResponse {
+    let before = state.devices_service.get_by_token(&request.token).await;
     match state.devices_service.update_settings(request).await {
+        Ok(device) => {
+            let id = device.id.clone();
+            audit_device(&state, &actor, before, Some(device)).await;
+            (StatusCode::OK, Json(DeviceResponse::success(Some(id))))
+        }
+        Err(DevicesError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(DeviceResponse::error("Device not found")),
+        ),
+        Err(
+            e @ (DevicesError::InvalidQuietHours(_)
+            | DevicesError::InvalidLanguage(_)
+            | DevicesError::InvalidTimezone(_)
+            | DevicesError::InvalidMetadata(_)),
+        ) => (
+            StatusCode::BAD_REQUEST,
+            Json(DeviceResponse::error(e.to_string())),
+        ),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to update device settings");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DeviceResponse::error(e.to_string())),
+            )
+        }
+    }
+}
+
+/// POST /devices/heartbeat - Record that an installed app is still active
+#[utoipa::path(
+    post,
+    path = "/api/v1/devices/heartbeat",
+    tag = "devices",
+    request_body = DeviceHeartbeatRequest,
+    responses(
+        (status = 200, description = "Heartbeat recorded", body = DeviceResponse),
+        (status = 400, description = "Out-of-range or unpaired coordinates, or an unknown timezone", body = DeviceResponse),
+        (status = 404, description = "Device not found", body = DeviceResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse)
+    ),
+    security(("api_key" = []), ("bearer" = []))
+)]
+pub async fn device_heartbeat(
+    State(state): State<AppState>,
+    Json(request): Json<DeviceHeartbeatRequest>,
+) -> impl IntoResponse {
+    match state.devices_service.heartbeat(request).await {
         Ok(device) => (
             StatusCode::OK,
             Json(DeviceResponse::success(Some(device.id))),
         ),
-        Err(super::service::DevicesError::NotFound) => (
+        Err(DevicesError::NotFound) => (
             StatusCode::NOT_FOUND,
             Json(DeviceResponse::error("Device not found")),
         ),
+        Err(e @ (DevicesError::InvalidLocation(_) | DevicesError::InvalidTimezone(_))) => (
+            StatusCode::BAD_REQUEST,
+            Json(DeviceResponse::error(e.to_string())),
+        ),
         Err(e) => {
-            tracing::error!(error = %e, "Failed to update device settings");
+            tracing::error!(error = %e, "Failed to record device heartbeat");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DeviceResponse::error(e.to_string())),
+            )
+        }
+    }
+}
+
+/// GET /devices/me - Current server-side settings for the calling device
+///
+/// The push token is taken from the `token` query parameter or the
+/// `X-Device-Token` header.
+#[utoipa::path(
+    get,
+    path = "/api/v1/devices/me",
+    tag = "devices",
+    params(DeviceTokenQuery, ("X-Device-Token" = Option<String>, Header, description = "Push token, instead of the query parameter")),
+    responses(
+        (status = 200, description = "The calling device", body = DeviceSummary),
+        (status = 400, description = "No push token given", body = DeviceResponse),
+        (status = 404, description = "Device not found", body = DeviceResponse)
+    ),
+    security(("api_key" = []), ("bearer" = []))
+)]
+pub async fn get_my_device(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DeviceTokenQuery>,
+) -> impl IntoResponse {
+    let token = query.token.or_else(|| {
+        headers
+            .get("X-Device-Token")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    });
+
+    let Some(token) = token else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!(DeviceResponse::error(
+                "Provide a token query parameter or X-Device-Token header"
+            ))),
+        );
+    };
+
+    match state.devices_service.get_by_token(&token).await {
+        Some(device) => (StatusCode::OK, Json(json!(DeviceSummary::from(device)))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!(DeviceResponse::error("Device not found"))),
+        ),
+    }
+}
+
+/// PUT /devices/location - Update a device's current coordinates
+#[utoipa::path(
+    put,
+    path = "/api/v1/devices/location",
+    tag = "devices",
+    request_body = DeviceLocationRequest,
+    responses(
+        (status = 200, description = "Location updated", body = DeviceResponse),
+        (status = 400, description = "Coordinates out of range", body = DeviceResponse),
+        (status = 404, description = "Device not found", body = DeviceResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse)
+    ),
+    security(("api_key" = []), ("bearer" = []))
+)]
+pub async fn update_device_location(
+    State(state): State<AppState>,
+    Json(request): Json<DeviceLocationRequest>,
+) -> impl IntoResponse {
+    match state.devices_service.update_location(request).await {
+        Ok(device) => (
+            StatusCode::OK,
+            Json(DeviceResponse::success(Some(device.id))),
+        ),
+        Err(DevicesError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(DeviceResponse::error("Device not found")),
+        ),
+        Err(e @ DevicesError::InvalidLocation(_)) => (
+            StatusCode::BAD_REQUEST,
+            Json(DeviceResponse::error(e.to_string())),
+        ),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to update device location");
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(DeviceResponse::error(e.to_string())),
@@ -79,6 +353,18 @@ pub async fn update_device_settings(
 }
 
 /// POST /devices/test - Send a test notification
+#[utoipa::path(
+    post,
+    path = "/api/v1/devices/test",
+    tag = "devices",
+    request_body = TestNotificationRequest,
+    responses(
+        (status = 200, description = "Test notification sent", body = Object),
+        (status = 404, description = "Device not found", body = DeviceResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse)
+    ),
+    security(("api_key" = []), ("bearer" = []))
+)]
 pub async fn send_test_notification(
     State(state): State<AppState>,
     Json(request): Json<TestNotificationRequest>,
@@ -104,34 +390,182 @@ pub async fn send_test_notification(
     }
 }
 
-/// GET /devices/count - Get registered device count
-pub async fn get_device_count(State(state): State<AppState>) -> impl IntoResponse {
-    let count = state.devices_service.count().await;
+/// POST /devices/broadcast - Send a notification to every enabled device
+#[utoipa::path(
+    post,
+    path = "/api/v1/devices/broadcast",
+    tag = "devices",
+    request_body = DeviceBroadcastRequest,
+    responses(
+        (status = 200, description = "Recipients and number sent", body = Object),
+        (status = 400, description = "Title or body missing", body = Object),
+        (status = 403, description = "Admin only", body = ErrorResponse)
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn broadcast_notification(
+    State(state): State<AppState>,
+    Json(request): Json<DeviceBroadcastRequest>,
+) -> impl IntoResponse {
+    let message = request.message;
+    if message.title.trim().is_empty() || message.body.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": "title and body are required"
+            })),
+        );
+    }
+
+    let recipients = match state
+        .devices_service
+        .broadcast_recipient_count(&message)
+        .await
+    {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to count broadcast recipients");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                })),
+            );
+        }
+    };
+
+    if request.dry_run {
+        return (
+            StatusCode::OK,
+            Json(json!({
+                "status": "success",
+                "dry_run": true,
+                "recipients": recipients
+            })),
+        );
+    }
+
+    match state.devices_service.broadcast(&message).await {
+        Ok(sent) => {
+            tracing::info!(recipients, sent, title = %message.title, "Admin broadcast sent");
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "dry_run": false,
+                    "recipients": recipients,
+                    "sent": sent
+                })),
+            )
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to broadcast notification");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                })),
+            )
+        }
+    }
+}
+
+/// GET /devices/count - Get registered device count (the caller's own, for non-admins)
+#[utoipa::path(
+    get,
+    path = "/api/v1/devices/count",
+    tag = "devices",
+    responses((status = 200, description = "Registered device count", body = Object)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_device_count(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> impl IntoResponse {
+    let count = match principal.and_then(|Extension(p)| p.owner_filter()) {
+        Some(owner) => state.devices_service.count_owned(&owner).await,
+        None => state.devices_service.count().await,
+    };
     Json(json!({
         "count": count
     }))
 }
 
-/// GET /devices/debug - List all devices (for debugging)
-pub async fn list_devices(State(state): State<AppState>) -> impl IntoResponse {
-    let devices = state.devices_service.get_all().await;
-    let debug_info: Vec<serde_json::Value> = devices
-        .iter()
-        .map(|d| {
-            json!({
-                "id": d.id,
-                "platform": d.platform,
-                "device_name": d.device_name,
-                "enabled": d.enabled,
-                "cities": d.cities,
-                "token_preview": format!("{}...{}", &d.token[..20.min(d.token.len())], &d.token[d.token.len().saturating_sub(10)..]),
-                "registered_at": d.registered_at,
-                "updated_at": d.updated_at,
-            })
-        })
-        .collect();
-    Json(json!({
-        "count": devices.len(),
-        "devices": debug_info
-    }))
+/// GET /devices - List devices with optional filters and pagination
+///
+/// Non-admin users only see devices registered to them.
+#[utoipa::path(
+    get,
+    path = "/api/v1/devices",
+    tag = "devices",
+    params(DeviceListQuery),
+    responses((status = 200, description = "A page of devices", body = DeviceListResponse)),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn list_devices(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Query(query): Query<DeviceListQuery>,
+) -> impl IntoResponse {
+    let owner = principal.and_then(|Extension(p)| p.owner_filter());
+    match state.devices_service.list(query, owner).await {
+        Ok(response) => (StatusCode::OK, Json(json!(response))),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to list devices");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!(DeviceResponse::error(e.to_string()))),
+            )
+        }
+    }
+}
+
+/// GET /devices/{id}/notifications - Recent pushes sent to a device
+#[utoipa::path(
+    get,
+    path = "/api/v1/devices/{id}/notifications",
+    tag = "devices",
+    params(("id" = String, Path, description = "Device ID"), NotificationHistoryQuery),
+    responses(
+        (status = 200, description = "device_id, count, and notifications (DeliveryRecord, newest first)", body = Object),
+        (status = 404, description = "Device not found", body = DeviceResponse)
+    ),
+    security(("bearer" = []), ("api_key" = []))
+)]
+pub async fn get_device_notifications(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(device_id): Path<String>,
+    Query(query): Query<NotificationHistoryQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let owner = principal.and_then(|Extension(p)| p.owner_filter());
+    match state
+        .devices_service
+        .notification_history(&device_id, owner.as_deref(), limit)
+        .await
+    {
+        Ok(notifications) => (
+            StatusCode::OK,
+            Json(json!({
+                "device_id": device_id,
+                "count": notifications.len(),
+                "notifications": notifications
+            })),
+        ),
+        Err(DevicesError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(json!(DeviceResponse::error("Device not found"))),
+        ),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to get device notification history");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!(DeviceResponse::error(e.to_string()))),
+            )
+        }
+    }
 }
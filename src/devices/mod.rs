@@ -3,4 +3,7 @@ pub mod models;
 mod service;
 
 pub use models::{Device, Platform};
-pub use service::DevicesService;
+pub use service::{
+    start_delivery_log_prune_task, start_digest_flush_task, start_receipt_check_task, DevicesError,
+    DevicesService,
+};
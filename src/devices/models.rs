@@ -1,7 +1,14 @@
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::forecast::models::{AlertLevel, AlertResponse, AlertSeverity};
+use crate::notifications::{EventType, NotificationMessage, Priority};
 
 /// Platform type for the device
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Platform {
     Ios,
@@ -44,18 +51,226 @@ pub struct Device {
 
     /// Last updated timestamp
     pub updated_at: i64,
+
+    /// Last time the app checked in (registration or heartbeat)
+    #[serde(default)]
+    pub last_seen: Option<i64>,
+
+    /// Last reported latitude
+    #[serde(default)]
+    pub lat: Option<f64>,
+
+    /// Last reported longitude
+    #[serde(default)]
+    pub lon: Option<f64>,
+
+    /// Daily window during which notifications are held back
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+
+    /// Lowest priority to deliver (default: everything); urgent messages
+    /// always get through
+    #[serde(default)]
+    pub min_priority: Option<Priority>,
+
+    /// IANA timezone the device is in, for jobs that deliver at local time
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// Notification language (OWM language code, e.g. "en", "es", "pt_br")
+    #[serde(default = "default_language")]
+    pub language: String,
+
+    /// Which weather alerts this device wants to hear about
+    #[serde(default)]
+    pub alert_preferences: AlertPreferences,
+
+    /// Event classes this device wants to receive (default: all of them)
+    #[serde(default = "default_subscriptions")]
+    pub subscriptions: BTreeSet<EventType>,
+
+    /// Notify when the current temperature drops below this (in the device's units)
+    #[serde(default)]
+    pub cold_threshold: Option<f64>,
+
+    /// Notify when the current temperature rises above this (in the device's units)
+    #[serde(default)]
+    pub heat_threshold: Option<f64>,
+
+    /// Opaque client data (install IDs, theme, experiment flags); never interpreted by the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+
+    /// User the device was registered by, when registered with a bearer token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+/// Per-device filter applied to weather alerts before sending
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertPreferences {
+    /// Lowest alert level to deliver (default: everything)
+    #[serde(default)]
+    pub min_level: AlertLevel,
+
+    /// Lowest normalized severity to deliver; unknown severities always pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<AlertSeverity>,
+
+    /// Alert event names to never deliver (case-insensitive, e.g. "Special Weather Statement")
+    #[serde(default)]
+    pub muted_events: Vec<String>,
+}
+
+impl AlertPreferences {
+    /// Whether an alert passes this device's filter
+    pub fn accepts(&self, alert: &AlertResponse) -> bool {
+        alert.level() >= self.min_level
+            && self
+                .min_severity
+                .is_none_or(|min| alert.severity.meets(min))
+            && !self
+                .muted_events
+                .iter()
+                .any(|muted| muted.eq_ignore_ascii_case(alert.event.trim()))
+    }
+}
+
+impl Device {
+    /// Last reported coordinates, if the app has shared a location
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.lat.zip(self.lon)
+    }
+
+    /// Whether an alert should reach this device: it passes the alert
+    /// preferences, and when both the alert's area and the device's location
+    /// are known, the device is inside that area
+    pub fn receives_alert(&self, alert: &AlertResponse) -> bool {
+        self.alert_preferences.accepts(alert)
+            && self
+                .coordinates()
+                .and_then(|(lat, lon)| alert.covers(lat, lon))
+                .unwrap_or(true)
+    }
+
+    /// Coordinates as a "lat,lon" location string accepted by the geocoding
+    /// services, rounded to the same precision as history location keys
+    pub fn location_query(&self) -> Option<String> {
+        self.coordinates()
+            .map(|(lat, lon)| format!("{:.2},{:.2}", lat, lon))
+    }
+
+    /// Whether a message tagged with these event classes should reach this device.
+    /// Untagged messages (tests, broadcasts) always do.
+    pub fn is_subscribed_to(&self, events: &[EventType]) -> bool {
+        events.is_empty() || events.iter().any(|e| self.subscriptions.contains(e))
+    }
+
+    /// Whether a notification of the given urgency should be suppressed right now
+    pub fn is_quiet_at(&self, now: DateTime<Utc>, urgent: bool) -> bool {
+        self.quiet_hours.as_ref().is_some_and(|q| {
+            !(urgent && q.allow_urgent) && q.contains(now, self.timezone.as_deref())
+        })
+    }
+
+    /// Whether a message of this priority clears the device's threshold.
+    /// Urgent messages always do.
+    pub fn receives_priority(&self, priority: Priority) -> bool {
+        priority == Priority::Urgent || self.min_priority.is_none_or(|min| priority >= min)
+    }
+}
+
+/// Great-circle distance between two coordinates in kilometres
+pub fn haversine_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+/// Daily do-not-disturb window in the device owner's local time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    /// Start of the window, "HH:MM" local time
+    pub start: String,
+
+    /// End of the window, "HH:MM" local time (may be earlier than start to span midnight)
+    pub end: String,
+
+    /// IANA timezone name, e.g. "America/Chicago"; empty uses the device's
+    /// timezone, or UTC when it has none
+    #[serde(default)]
+    pub timezone: String,
+
+    /// Still deliver urgent notifications (severe weather alerts) during quiet
+    /// hours (default: true)
+    #[serde(default = "default_true")]
+    pub allow_urgent: bool,
+}
+
+impl QuietHours {
+    /// Check that the times and timezone parse
+    pub fn validate(&self) -> Result<(), String> {
+        self.parse(None).map(|_| ())
+    }
+
+    fn parse(
+        &self,
+        device_timezone: Option<&str>,
+    ) -> Result<(NaiveTime, NaiveTime, chrono_tz::Tz), String> {
+        let parse_time = |s: &str| {
+            NaiveTime::parse_from_str(s, "%H:%M")
+                .map_err(|_| format!("invalid time '{}', expected HH:MM", s))
+        };
+        let start = parse_time(&self.start)?;
+        let end = parse_time(&self.end)?;
+        let timezone = match self.timezone.trim() {
+            "" => device_timezone.unwrap_or("UTC"),
+            timezone => timezone,
+        };
+        let tz = timezone
+            .parse::<chrono_tz::Tz>()
+            .map_err(|_| format!("invalid timezone '{}'", timezone))?;
+        Ok((start, end, tz))
+    }
+
+    /// Whether the given instant falls inside the window, in its own timezone
+    /// or else the device's. Windows that fail to parse never suppress anything.
+    pub fn contains(&self, now: DateTime<Utc>, device_timezone: Option<&str>) -> bool {
+        let Ok((start, end, tz)) = self.parse(device_timezone) else {
+            return false;
+        };
+        let local = now.with_timezone(&tz).time();
+        if start <= end {
+            local >= start && local < end
+        } else {
+            local >= start || local < end
+        }
+    }
 }
 
 fn default_units() -> String {
     "imperial".to_string()
 }
 
+pub fn default_language() -> String {
+    crate::forecast::DEFAULT_LANG.to_string()
+}
+
 fn default_true() -> bool {
     true
 }
 
+pub fn default_subscriptions() -> BTreeSet<EventType> {
+    EventType::ALL.into_iter().collect()
+}
+
 /// Request to register a new device
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DeviceRegistrationRequest {
     pub token: String,
@@ -68,31 +283,226 @@ pub struct DeviceRegistrationRequest {
     pub units: String,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    pub quiet_hours: Option<QuietHours>,
+    pub min_priority: Option<Priority>,
+    /// IANA timezone, e.g. "America/Chicago"
+    pub timezone: Option<String>,
+    pub language: Option<String>,
+    pub alert_preferences: Option<AlertPreferences>,
+    pub subscriptions: Option<BTreeSet<EventType>>,
+    pub cold_threshold: Option<f64>,
+    pub heat_threshold: Option<f64>,
+    pub metadata: Option<serde_json::Value>,
 }
 
 /// Request to unregister a device
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct DeviceUnregisterRequest {
     pub token: String,
 }
 
 /// Request to update device settings
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct DeviceSettingsRequest {
     pub token: String,
     pub enabled: Option<bool>,
     pub cities: Option<Vec<String>>,
     pub units: Option<String>,
+    /// Absent leaves quiet hours unchanged; `null` clears them
+    #[serde(default, alias = "quietHours", deserialize_with = "double_option")]
+    pub quiet_hours: Option<Option<QuietHours>>,
+    /// Absent leaves the threshold unchanged; `null` delivers everything
+    #[serde(default, alias = "minPriority", deserialize_with = "double_option")]
+    pub min_priority: Option<Option<Priority>>,
+    /// Absent leaves the timezone unchanged; `null` clears it
+    #[serde(default, deserialize_with = "double_option")]
+    pub timezone: Option<Option<String>>,
+    pub language: Option<String>,
+    #[serde(default, alias = "alertPreferences")]
+    pub alert_preferences: Option<AlertPreferences>,
+    pub subscriptions: Option<BTreeSet<EventType>>,
+    /// Absent leaves the threshold unchanged; `null` clears it
+    #[serde(default, alias = "coldThreshold", deserialize_with = "double_option")]
+    pub cold_threshold: Option<Option<f64>>,
+    /// Absent leaves the threshold unchanged; `null` clears it
+    #[serde(default, alias = "heatThreshold", deserialize_with = "double_option")]
+    pub heat_threshold: Option<Option<f64>>,
+    /// Absent leaves metadata unchanged; `null` clears it
+    #[serde(default, deserialize_with = "double_option")]
+    pub metadata: Option<Option<serde_json::Value>>,
+}
+
+/// Distinguish a missing field (`None`) from an explicit `null` (`Some(None)`)
+fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Periodic check-in from an installed app
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceHeartbeatRequest {
+    pub token: String,
+    pub app_version: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// IANA timezone the device is in now, e.g. after travelling
+    pub timezone: Option<String>,
+}
+
+/// Request to update a device's current location
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeviceLocationRequest {
+    pub token: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Query parameters identifying the calling device by push token
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeviceTokenQuery {
+    pub token: Option<String>,
 }
 
 /// Request to send a test notification
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct TestNotificationRequest {
     pub token: String,
 }
 
+/// Request to broadcast a notification to every enabled device
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeviceBroadcastRequest {
+    #[serde(flatten)]
+    pub message: NotificationMessage,
+    /// Only count recipients without sending anything
+    #[serde(default, alias = "dryRun")]
+    pub dry_run: bool,
+}
+
+/// Query parameters for listing devices
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeviceListQuery {
+    pub city: Option<String>,
+    pub platform: Option<Platform>,
+    pub enabled: Option<bool>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Device as shown in listings, with the push token abbreviated
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceSummary {
+    pub id: String,
+    pub platform: Platform,
+    pub device_name: Option<String>,
+    pub app_version: Option<String>,
+    pub enabled: bool,
+    pub cities: Vec<String>,
+    pub units: String,
+    pub language: String,
+    pub token_preview: String,
+    pub registered_at: i64,
+    pub updated_at: i64,
+    pub last_seen: Option<i64>,
+    pub quiet_hours: Option<QuietHours>,
+    pub min_priority: Option<Priority>,
+    pub timezone: Option<String>,
+    pub alert_preferences: AlertPreferences,
+    pub subscriptions: BTreeSet<EventType>,
+    pub cold_threshold: Option<f64>,
+    pub heat_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+impl From<Device> for DeviceSummary {
+    fn from(d: Device) -> Self {
+        let token_preview = format!(
+            "{}...{}",
+            &d.token[..20.min(d.token.len())],
+            &d.token[d.token.len().saturating_sub(10)..]
+        );
+        Self {
+            id: d.id,
+            platform: d.platform,
+            device_name: d.device_name,
+            app_version: d.app_version,
+            enabled: d.enabled,
+            cities: d.cities,
+            units: d.units,
+            language: d.language,
+            token_preview,
+            registered_at: d.registered_at,
+            updated_at: d.updated_at,
+            last_seen: d.last_seen,
+            quiet_hours: d.quiet_hours,
+            min_priority: d.min_priority,
+            timezone: d.timezone,
+            alert_preferences: d.alert_preferences,
+            subscriptions: d.subscriptions,
+            cold_threshold: d.cold_threshold,
+            heat_threshold: d.heat_threshold,
+            metadata: d.metadata,
+            owner: d.owner,
+        }
+    }
+}
+
+/// One page of devices with total counts
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceListResponse {
+    pub devices: Vec<DeviceSummary>,
+    pub total: usize,
+    pub page: u32,
+    pub per_page: u32,
+    pub total_pages: u32,
+}
+
+/// Outcome of a single push to a single device
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Accepted by Expo
+    Sent,
+    /// Held for the next notification digest
+    Queued,
+    /// Rejected by Expo or the request failed
+    Failed,
+    /// Dropped because the device hit its notification rate limit
+    #[serde(rename = "rate_limited")]
+    RateLimited,
+}
+
+/// Entry in the per-device notification delivery log
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeliveryRecord {
+    pub device_id: String,
+    pub title: String,
+    pub body: String,
+    pub priority: Priority,
+    pub status: DeliveryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: i64,
+}
+
+/// Query parameters for a device's notification history
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NotificationHistoryQuery {
+    pub limit: Option<u32>,
+}
+
 /// Response for device operations
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DeviceResponse {
     pub success: bool,
@@ -100,6 +510,9 @@ pub struct DeviceResponse {
     pub device_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    /// The device as registered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceSummary>,
 }
 
 impl DeviceResponse {
@@ -108,6 +521,16 @@ impl DeviceResponse {
             success: true,
             device_id,
             message: None,
+            device: None,
+        }
+    }
+
+    pub fn registered(device: Device) -> Self {
+        Self {
+            success: true,
+            device_id: Some(device.id.clone()),
+            message: None,
+            device: Some(device.into()),
         }
     }
 
@@ -116,6 +539,197 @@ impl DeviceResponse {
             success: false,
             device_id: None,
             message: Some(message.into()),
+            device: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn quiet(start: &str, end: &str) -> QuietHours {
+        QuietHours {
+            start: start.to_string(),
+            end: end.to_string(),
+            timezone: "America/Chicago".to_string(),
+            allow_urgent: false,
         }
     }
+
+    #[test]
+    fn test_quiet_hours_spanning_midnight() {
+        let q = quiet("22:00", "07:00");
+        // 08:00 UTC = 03:00 CDT
+        assert!(q.contains(Utc.with_ymd_and_hms(2024, 7, 1, 8, 0, 0).unwrap(), None));
+        // 18:00 UTC = 13:00 CDT
+        assert!(!q.contains(Utc.with_ymd_and_hms(2024, 7, 1, 18, 0, 0).unwrap(), None));
+    }
+
+    #[test]
+    fn test_quiet_hours_same_day_window() {
+        let q = quiet("13:00", "15:00");
+        assert!(q.contains(Utc.with_ymd_and_hms(2024, 7, 1, 19, 0, 0).unwrap(), None));
+        assert!(!q.contains(Utc.with_ymd_and_hms(2024, 7, 1, 20, 0, 0).unwrap(), None));
+    }
+
+    #[test]
+    fn test_quiet_hours_validation() {
+        assert!(quiet("22:00", "07:00").validate().is_ok());
+        assert!(quiet("25:00", "07:00").validate().is_err());
+        let mut q = quiet("22:00", "07:00");
+        q.timezone = "Mars/Olympus".to_string();
+        assert!(q.validate().is_err());
+    }
+
+    fn alert(event: &str) -> AlertResponse {
+        AlertResponse::new(
+            "NWS".to_string(),
+            event.to_string(),
+            0,
+            0,
+            String::new(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_alert_preferences_filtering() {
+        let all = AlertPreferences::default();
+        assert!(all.accepts(&alert("Special Weather Statement")));
+
+        let warnings_only = AlertPreferences {
+            min_level: AlertLevel::Warning,
+            ..Default::default()
+        };
+        assert!(warnings_only.accepts(&alert("Tornado Warning")));
+        assert!(!warnings_only.accepts(&alert("Winter Storm Watch")));
+        assert!(!warnings_only.accepts(&alert("Wind Advisory")));
+        // Unrecognized events are never dropped by level
+        assert!(warnings_only.accepts(&alert("Extreme heat")));
+
+        let muted = AlertPreferences {
+            min_level: AlertLevel::Statement,
+            muted_events: vec!["special weather statement".to_string()],
+            ..Default::default()
+        };
+        assert!(!muted.accepts(&alert("Special Weather Statement")));
+        assert!(muted.accepts(&alert("Wind Advisory")));
+
+        let severe_only = AlertPreferences {
+            min_severity: Some(AlertSeverity::Severe),
+            ..Default::default()
+        };
+        assert!(severe_only.accepts(&alert("Winter Storm Watch")));
+        assert!(!severe_only.accepts(&alert("Yellow Warning for Wind")));
+        assert!(severe_only.accepts(&alert("Unusual phenomenon")));
+    }
+
+    #[test]
+    fn test_broadcast_request_flattens_message() {
+        let request: DeviceBroadcastRequest = serde_json::from_value(serde_json::json!({
+            "title": "Maintenance",
+            "body": "Notifications may be delayed tonight",
+            "priority": "high",
+            "dryRun": true
+        }))
+        .unwrap();
+        assert!(request.dry_run);
+        assert_eq!(request.message.title, "Maintenance");
+        assert_eq!(request.message.priority, Priority::High);
+    }
+
+    #[test]
+    fn test_subscriptions_default_and_filtering() {
+        let mut device: Device = serde_json::from_value(serde_json::json!({
+            "id": "device-1",
+            "token": "ExponentPushToken[test]",
+            "platform": "ios",
+            "registered_at": 1700000000,
+            "updated_at": 1700000000
+        }))
+        .unwrap();
+        assert_eq!(device.subscriptions, default_subscriptions());
+
+        device.subscriptions = [EventType::SevereAlerts].into_iter().collect();
+        assert!(device.is_subscribed_to(&[]));
+        assert!(!device.is_subscribed_to(&[EventType::DailyBriefing]));
+        assert!(device.is_subscribed_to(&[EventType::DailyBriefing, EventType::SevereAlerts]));
+    }
+
+    #[test]
+    fn test_receives_alert_inside_area() {
+        let mut device: Device = serde_json::from_value(serde_json::json!({
+            "id": "device-1",
+            "token": "ExponentPushToken[test]",
+            "platform": "ios",
+            "registered_at": 1700000000,
+            "updated_at": 1700000000
+        }))
+        .unwrap();
+        let mut warning = alert("Tornado Warning");
+        warning.geometry = Some(serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [[[-88.0, 41.5], [-87.5, 41.5], [-87.5, 42.0], [-88.0, 42.0], [-88.0, 41.5]]]
+        }));
+        // Without a reported location, city matching decides
+        assert!(device.receives_alert(&warning));
+
+        device.lat = Some(41.88);
+        device.lon = Some(-87.63);
+        assert!(device.receives_alert(&warning));
+        device.lat = Some(43.04);
+        assert!(!device.receives_alert(&warning));
+        assert!(device.receives_alert(&alert("Tornado Warning")));
+    }
+
+    #[test]
+    fn test_quiet_hours_and_min_priority() {
+        let mut device: Device = serde_json::from_value(serde_json::json!({
+            "id": "device-1",
+            "token": "ExponentPushToken[test]",
+            "platform": "ios",
+            "registered_at": 1700000000,
+            "updated_at": 1700000000,
+            "timezone": "America/Chicago",
+            "quiet_hours": { "start": "22:00", "end": "07:00" },
+            "min_priority": "high"
+        }))
+        .unwrap();
+        // 10:00 UTC = 05:00 CDT, inside the window in the device's timezone
+        let night = Utc.with_ymd_and_hms(2024, 7, 1, 10, 0, 0).unwrap();
+        assert!(device.is_quiet_at(night, false));
+        // Urgent alerts go through unless the device opts out
+        assert!(!device.is_quiet_at(night, true));
+        device.quiet_hours.as_mut().unwrap().allow_urgent = false;
+        assert!(device.is_quiet_at(night, true));
+        device.timezone = None;
+        assert!(!device.is_quiet_at(night, false));
+
+        assert!(!device.receives_priority(Priority::Default));
+        assert!(device.receives_priority(Priority::High));
+        device.min_priority = Some(Priority::Urgent);
+        assert!(device.receives_priority(Priority::Urgent));
+        device.min_priority = None;
+        assert!(device.receives_priority(Priority::Min));
+    }
+
+    #[test]
+    fn test_haversine_km() {
+        // Chicago to Milwaukee is roughly 130 km
+        let d = haversine_km((41.88, -87.63), (43.04, -87.91));
+        assert!((125.0..135.0).contains(&d), "{}", d);
+        assert_eq!(haversine_km((41.88, -87.63), (41.88, -87.63)), 0.0);
+    }
+
+    #[test]
+    fn test_settings_quiet_hours_null_clears() {
+        let absent: DeviceSettingsRequest = serde_json::from_str(r#"{"token":"t"}"#).unwrap();
+        assert_eq!(absent.quiet_hours, None);
+
+        let cleared: DeviceSettingsRequest =
+            serde_json::from_str(r#"{"token":"t","quiet_hours":null}"#).unwrap();
+        assert_eq!(cleared.quiet_hours, Some(None));
+    }
 }
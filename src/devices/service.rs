@@ -1,13 +1,56 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use chrono::Utc;
 use reqwest::Client;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use uuid::Uuid;
 
-use crate::db::{DbError, DeviceRepository, SqliteDeviceRepository};
-use crate::notifications::{ExpoClient, NotificationMessage, Priority};
+use crate::cache::TtlCache;
+use crate::config::{DeviceRateLimitConfig, NotificationsConfig, UnregisteredAction};
+use crate::db::{
+    DbError, DeviceFilter, DeviceRepository, NotificationLogRepository, SqliteDeviceRepository,
+    SqliteNotificationLogRepository,
+};
+use crate::extractors::{Coords, CoordsRejection};
+use crate::notifications::{
+    build_digest, normalize_language, DigestQueue, ExpoClient, NotificationError,
+    NotificationMessage, Priority, ReadyTicket, ReceiptTracker,
+};
 
-use super::models::{Device, DeviceRegistrationRequest, DeviceSettingsRequest};
+use super::models::{
+    default_language, default_subscriptions, haversine_km, DeliveryRecord, DeliveryStatus, Device,
+    DeviceHeartbeatRequest, DeviceListQuery, DeviceListResponse, DeviceLocationRequest,
+    DeviceRegistrationRequest, DeviceSettingsRequest, DeviceSummary,
+};
+use crate::forecast::models::LocationInfo;
+
+/// Expo recommends waiting before fetching receipts; they are not available immediately
+const RECEIPT_MIN_AGE: Duration = Duration::from_secs(15 * 60);
+
+/// Devices with a reported location within this distance of a forecast
+/// location receive its notifications even without a matching city name
+const NEARBY_RADIUS_KM: f64 = 25.0;
+
+/// Page size for device listings when none is requested
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Largest page size a caller may request
+const MAX_PAGE_SIZE: u32 = 200;
+
+/// Largest serialized device metadata object accepted, in bytes
+const MAX_METADATA_BYTES: usize = 4096;
+
+/// How long a registration is remembered under its Idempotency-Key
+const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// Most Idempotency-Keys remembered at once
+const MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+
+/// Longest Idempotency-Key accepted
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
 
 #[derive(Error, Debug)]
 pub enum DevicesError {
@@ -22,23 +65,114 @@ pub enum DevicesError {
 
     #[error("Invalid token: {0}")]
     InvalidToken(String),
+
+    #[error("Invalid location: {0}")]
+    InvalidLocation(#[from] CoordsRejection),
+
+    #[error("Invalid quiet hours: {0}")]
+    InvalidQuietHours(String),
+
+    #[error("Invalid language code: {0}")]
+    InvalidLanguage(String),
+
+    #[error("Invalid timezone: {0}")]
+    InvalidTimezone(String),
+
+    #[error("Invalid metadata: {0}")]
+    InvalidMetadata(String),
+
+    #[error("Invalid Idempotency-Key: {0}")]
+    InvalidIdempotencyKey(String),
+
+    #[error("Idempotency-Key was already used for a different registration")]
+    IdempotencyKeyReused,
+
+    #[error("Device is registered to another user")]
+    OwnedByAnother,
+}
+
+/// A registration remembered under its Idempotency-Key
+#[derive(Clone, Serialize)]
+struct IdempotentRegistration {
+    /// Hash of the request and owner that made it
+    fingerprint: String,
+    device: Device,
 }
 
 /// Service for managing device registrations and sending push notifications
 pub struct DevicesService {
     repo: SqliteDeviceRepository,
+    /// Per-device record of sent, queued, and failed pushes
+    log: SqliteNotificationLogRepository,
     expo_client: ExpoClient,
+    /// Pending non-urgent notifications (None when digest batching is disabled)
+    digest: Option<DigestQueue>,
+    /// Push tickets awaiting receipt checks (None when receipt checking is disabled)
+    receipts: Option<ReceiptTracker>,
+    /// How devices with dead push tokens are handled
+    unregistered_action: UnregisteredAction,
+    /// Per-device caps on non-urgent notifications
+    rate_limit: DeviceRateLimitConfig,
+    /// Recent registrations by push token and Idempotency-Key
+    registrations: TtlCache<String, IdempotentRegistration>,
 }
 
 impl DevicesService {
     /// Create a new devices service backed by SQLite
-    pub fn new(client: Client, pool: sqlx::SqlitePool) -> Self {
+    pub fn new(
+        client: Client,
+        pool: sqlx::SqlitePool,
+        notifications: &NotificationsConfig,
+    ) -> Self {
         Self {
-            repo: SqliteDeviceRepository::new(pool),
-            expo_client: ExpoClient::new(client),
+            repo: SqliteDeviceRepository::new(pool.clone()),
+            log: SqliteNotificationLogRepository::new(pool),
+            expo_client: ExpoClient::new(client, notifications.expo.clone()),
+            digest: notifications.digest.enabled.then(DigestQueue::new),
+            receipts: notifications.receipts.enabled.then(ReceiptTracker::new),
+            unregistered_action: notifications.receipts.unregistered_action,
+            rate_limit: notifications.rate_limit.clone(),
+            registrations: TtlCache::new("device_registrations", IDEMPOTENCY_KEY_TTL)
+                .with_max_entries(MAX_IDEMPOTENCY_KEYS),
         }
     }
 
+    /// Validate and normalize an optional language code from a request
+    fn parse_language(language: Option<String>) -> Result<Option<String>, DevicesError> {
+        language
+            .map(|lang| normalize_language(&lang).ok_or(DevicesError::InvalidLanguage(lang)))
+            .transpose()
+    }
+
+    /// Check that an optional timezone from a request is an IANA name
+    fn validate_timezone(timezone: Option<&str>) -> Result<(), DevicesError> {
+        match timezone {
+            Some(tz) if tz.parse::<chrono_tz::Tz>().is_err() => {
+                Err(DevicesError::InvalidTimezone(tz.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that client metadata is a JSON object of reasonable size
+    fn validate_metadata(metadata: &Option<serde_json::Value>) -> Result<(), DevicesError> {
+        let Some(value) = metadata else {
+            return Ok(());
+        };
+        if !value.is_object() {
+            return Err(DevicesError::InvalidMetadata(
+                "metadata must be a JSON object".to_string(),
+            ));
+        }
+        if value.to_string().len() > MAX_METADATA_BYTES {
+            return Err(DevicesError::InvalidMetadata(format!(
+                "metadata must be at most {} bytes",
+                MAX_METADATA_BYTES
+            )));
+        }
+        Ok(())
+    }
+
     /// Get current timestamp
     fn now() -> i64 {
         SystemTime::now()
@@ -63,10 +197,12 @@ impl DevicesService {
         }
     }
 
-    /// Register a new device or update existing
+    /// Register a new device or update existing. A signed-in `owner` claims a
+    /// device nobody owns yet; one owned by someone else is refused.
     pub async fn register(
         &self,
         request: DeviceRegistrationRequest,
+        owner: Option<String>,
     ) -> Result<Device, DevicesError> {
         // Reject raw FCM tokens — only accept Expo push tokens
         if !request.token.starts_with("ExponentPushToken[") {
@@ -75,40 +211,41 @@ impl DevicesService {
             ));
         }
 
+        if let Some(ref quiet_hours) = request.quiet_hours {
+            quiet_hours
+                .validate()
+                .map_err(DevicesError::InvalidQuietHours)?;
+        }
+
+        let language = Self::parse_language(request.language.clone())?;
+        Self::validate_timezone(request.timezone.as_deref())?;
+        Self::validate_metadata(&request.metadata)?;
         let now = Self::now();
 
-        // Check if device already exists
-        let device = if let Some(mut existing) = self.repo.get_by_token(&request.token).await? {
-            // Update existing device — only overwrite cities if non-empty
-            // (token rotation re-registers without cities)
-            existing.platform = request.platform;
-            existing.device_name = request.device_name.or(existing.device_name);
-            existing.app_version = request.app_version.or(existing.app_version);
-            if !request.cities.is_empty() {
-                existing.cities = request.cities;
-            }
-            existing.units = request.units;
-            existing.enabled = request.enabled;
-            existing.updated_at = now;
-            existing
-        } else {
-            // Create new device
-            Device {
-                id: Uuid::new_v4().to_string(),
-                token: request.token,
-                platform: request.platform,
-                device_name: request.device_name,
-                app_version: request.app_version,
-                cities: request.cities,
-                units: request.units,
-                enabled: request.enabled,
-                registered_at: now,
-                updated_at: now,
+        // A racing registration for the same new token may insert it first;
+        // ours then updates that record, so both return the same device
+        let device = match self.repo.get_by_token(&request.token).await? {
+            Some(existing) => {
+                self.update_registration(existing, request, language, owner, now)
+                    .await?
+            }
+            None => {
+                let device =
+                    Self::new_device(request.clone(), language.clone(), owner.clone(), now);
+                if self.repo.insert(&device).await? {
+                    device
+                } else {
+                    let existing = self
+                        .repo
+                        .get_by_token(&request.token)
+                        .await?
+                        .ok_or(DevicesError::NotFound)?;
+                    self.update_registration(existing, request, language, owner, now)
+                        .await?
+                }
             }
         };
 
-        self.repo.upsert(&device).await?;
-
         tracing::info!(
             device_id = %device.id,
             platform = ?device.platform,
@@ -118,6 +255,141 @@ impl DevicesService {
         Ok(device)
     }
 
+    /// Register a device under a client-chosen Idempotency-Key, scoped to its
+    /// push token. A retry with the same key and request gets the original
+    /// device back without registering again (the flag is true); reusing the
+    /// key for a different request is an error.
+    pub async fn register_idempotent(
+        &self,
+        key: &str,
+        request: DeviceRegistrationRequest,
+        owner: Option<String>,
+    ) -> Result<(Device, bool), DevicesError> {
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+            return Err(DevicesError::InvalidIdempotencyKey(format!(
+                "must be 1 to {} characters",
+                MAX_IDEMPOTENCY_KEY_LEN
+            )));
+        }
+
+        let fingerprint = format!(
+            "{:x}",
+            Sha256::digest(format!("{:?}|{:?}", request, owner).as_bytes())
+        );
+        let cache_key = format!("{}|{}", request.token, key);
+        if let Some(previous) = self.registrations.get(&cache_key) {
+            if previous.fingerprint != fingerprint {
+                return Err(DevicesError::IdempotencyKeyReused);
+            }
+            return Ok((previous.device, true));
+        }
+
+        let device = self.register(request, owner).await?;
+        self.registrations.insert(
+            cache_key,
+            IdempotentRegistration {
+                fingerprint,
+                device: device.clone(),
+            },
+        );
+        Ok((device, false))
+    }
+
+    /// A device for a token registering for the first time
+    fn new_device(
+        request: DeviceRegistrationRequest,
+        language: Option<String>,
+        owner: Option<String>,
+        now: i64,
+    ) -> Device {
+        Device {
+            id: Uuid::new_v4().to_string(),
+            token: request.token,
+            platform: request.platform,
+            device_name: request.device_name,
+            app_version: request.app_version,
+            cities: request.cities,
+            units: request.units,
+            enabled: request.enabled,
+            registered_at: now,
+            updated_at: now,
+            last_seen: Some(now),
+            lat: None,
+            lon: None,
+            quiet_hours: request.quiet_hours,
+            min_priority: request.min_priority,
+            timezone: request.timezone,
+            language: language.unwrap_or_else(default_language),
+            alert_preferences: request.alert_preferences.unwrap_or_default(),
+            subscriptions: request.subscriptions.unwrap_or_else(default_subscriptions),
+            metadata: request.metadata,
+            cold_threshold: request.cold_threshold,
+            heat_threshold: request.heat_threshold,
+            owner,
+        }
+    }
+
+    /// Apply a re-registration to an already registered device and save it
+    async fn update_registration(
+        &self,
+        mut existing: Device,
+        request: DeviceRegistrationRequest,
+        language: Option<String>,
+        owner: Option<String>,
+        now: i64,
+    ) -> Result<Device, DevicesError> {
+        if let (Some(current), Some(caller)) = (existing.owner.as_deref(), owner.as_deref()) {
+            if current != caller {
+                return Err(DevicesError::OwnedByAnother);
+            }
+        }
+
+        // Update existing device — only overwrite cities if non-empty
+        // (token rotation re-registers without cities)
+        existing.platform = request.platform;
+        existing.device_name = request.device_name.or(existing.device_name);
+        existing.app_version = request.app_version.or(existing.app_version);
+        if !request.cities.is_empty() {
+            existing.cities = request.cities;
+        }
+        existing.units = request.units;
+        existing.enabled = request.enabled;
+        existing.updated_at = now;
+        existing.last_seen = Some(now);
+        if request.quiet_hours.is_some() {
+            existing.quiet_hours = request.quiet_hours;
+        }
+        if request.min_priority.is_some() {
+            existing.min_priority = request.min_priority;
+        }
+        if request.timezone.is_some() {
+            existing.timezone = request.timezone;
+        }
+        if let Some(language) = language {
+            existing.language = language;
+        }
+        if let Some(alert_preferences) = request.alert_preferences {
+            existing.alert_preferences = alert_preferences;
+        }
+        if let Some(subscriptions) = request.subscriptions {
+            existing.subscriptions = subscriptions;
+        }
+        if request.metadata.is_some() {
+            existing.metadata = request.metadata;
+        }
+        if request.cold_threshold.is_some() {
+            existing.cold_threshold = request.cold_threshold;
+        }
+        if request.heat_threshold.is_some() {
+            existing.heat_threshold = request.heat_threshold;
+        }
+        if owner.is_some() {
+            existing.owner = owner;
+        }
+        self.repo.upsert(&existing).await?;
+        Ok(existing)
+    }
+
     /// Unregister a device
     pub async fn unregister(&self, token: &str) -> Result<bool, DevicesError> {
         let removed = self.repo.remove(token).await?;
@@ -149,6 +421,38 @@ impl DevicesService {
         if let Some(units) = request.units {
             device.units = units;
         }
+        if let Some(quiet_hours) = request.quiet_hours {
+            if let Some(ref q) = quiet_hours {
+                q.validate().map_err(DevicesError::InvalidQuietHours)?;
+            }
+            device.quiet_hours = quiet_hours;
+        }
+        if let Some(min_priority) = request.min_priority {
+            device.min_priority = min_priority;
+        }
+        if let Some(timezone) = request.timezone {
+            Self::validate_timezone(timezone.as_deref())?;
+            device.timezone = timezone;
+        }
+        if let Some(language) = Self::parse_language(request.language)? {
+            device.language = language;
+        }
+        if let Some(alert_preferences) = request.alert_preferences {
+            device.alert_preferences = alert_preferences;
+        }
+        if let Some(subscriptions) = request.subscriptions {
+            device.subscriptions = subscriptions;
+        }
+        if let Some(metadata) = request.metadata {
+            Self::validate_metadata(&metadata)?;
+            device.metadata = metadata;
+        }
+        if let Some(cold_threshold) = request.cold_threshold {
+            device.cold_threshold = cold_threshold;
+        }
+        if let Some(heat_threshold) = request.heat_threshold {
+            device.heat_threshold = heat_threshold;
+        }
         device.updated_at = Self::now();
 
         self.repo.upsert(&device).await?;
@@ -158,6 +462,65 @@ impl DevicesService {
         Ok(device)
     }
 
+    /// Record a heartbeat from an installed app, updating its last-seen time
+    /// and optionally its app version and location
+    pub async fn heartbeat(&self, request: DeviceHeartbeatRequest) -> Result<Device, DevicesError> {
+        // Range-checked, but stored unrounded for alert polygon checks
+        Coords::from_pair(request.lat, request.lon)?;
+        Self::validate_timezone(request.timezone.as_deref())?;
+
+        let mut device = self
+            .repo
+            .get_by_token(&request.token)
+            .await?
+            .ok_or(DevicesError::NotFound)?;
+
+        device.last_seen = Some(Self::now());
+        if request.app_version.is_some() {
+            device.app_version = request.app_version;
+        }
+        if request.timezone.is_some() {
+            device.timezone = request.timezone;
+        }
+        if let (Some(lat), Some(lon)) = (request.lat, request.lon) {
+            device.lat = Some(lat);
+            device.lon = Some(lon);
+        }
+
+        self.repo.upsert(&device).await?;
+
+        tracing::debug!(device_id = %device.id, "Device heartbeat");
+
+        Ok(device)
+    }
+
+    /// Store a device's current coordinates for "my location" forecasts
+    pub async fn update_location(
+        &self,
+        request: DeviceLocationRequest,
+    ) -> Result<Device, DevicesError> {
+        // Range-checked, but stored unrounded for alert polygon checks
+        Coords::new(request.lat, request.lon)?;
+
+        let mut device = self
+            .repo
+            .get_by_token(&request.token)
+            .await?
+            .ok_or(DevicesError::NotFound)?;
+
+        let now = Self::now();
+        device.lat = Some(request.lat);
+        device.lon = Some(request.lon);
+        device.last_seen = Some(now);
+        device.updated_at = now;
+
+        self.repo.upsert(&device).await?;
+
+        tracing::info!(device_id = %device.id, "Device location updated");
+
+        Ok(device)
+    }
+
     /// Get a device by token
     pub async fn get_by_token(&self, token: &str) -> Option<Device> {
         self.repo.get_by_token(token).await.unwrap_or_else(|e| {
@@ -174,6 +537,49 @@ impl DevicesService {
         })
     }
 
+    /// List devices matching the query, one page at a time. `owner` limits the
+    /// listing to one user's devices.
+    pub async fn list(
+        &self,
+        query: DeviceListQuery,
+        owner: Option<String>,
+    ) -> Result<DeviceListResponse, DevicesError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query
+            .per_page
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let filter = DeviceFilter {
+            city: query.city,
+            platform: query.platform,
+            enabled: query.enabled,
+            owner,
+        };
+
+        let offset = (page - 1).saturating_mul(per_page);
+        let (devices, total) = self.repo.list(&filter, per_page, offset).await?;
+
+        Ok(DeviceListResponse {
+            devices: devices.into_iter().map(Into::into).collect(),
+            total,
+            page,
+            per_page,
+            total_pages: total.div_ceil(per_page as usize) as u32,
+        })
+    }
+
+    /// List devices matching the filter, newest first, continuing after the
+    /// `(registered_at, id)` of the previous page's last device
+    pub async fn list_after(
+        &self,
+        filter: &DeviceFilter,
+        after: Option<(i64, &str)>,
+        limit: u32,
+    ) -> Result<Vec<DeviceSummary>, DevicesError> {
+        let devices = self.repo.list_after(filter, after, limit).await?;
+        Ok(devices.into_iter().map(Into::into).collect())
+    }
+
     /// Get device count
     pub async fn count(&self) -> usize {
         self.repo.count().await.unwrap_or_else(|e| {
@@ -182,6 +588,21 @@ impl DevicesService {
         })
     }
 
+    /// Number of devices owned by a user
+    pub async fn count_owned(&self, owner: &str) -> usize {
+        let filter = DeviceFilter {
+            owner: Some(owner.to_string()),
+            ..Default::default()
+        };
+        match self.repo.list(&filter, 0, 0).await {
+            Ok((_, total)) => total,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to get device count");
+                0
+            }
+        }
+    }
+
     /// Send a test notification to a device
     pub async fn send_test(&self, token: &str) -> Result<(), DevicesError> {
         let message = NotificationMessage {
@@ -191,16 +612,31 @@ impl DevicesService {
             priority: Priority::Default,
             tags: vec!["test".to_string()],
             city: None,
+            events: Vec::new(),
         };
 
-        self.expo_client
-            .send_to_token(token, &message)
-            .await
-            .map_err(|e| DevicesError::NotificationError(e.to_string()))?;
+        let result = self.expo_client.send_to_token(token, &message).await;
+
+        if let Some(device) = self.get_by_token(token).await {
+            let record = Self::delivery_record(&device, &message, &result);
+            self.record_deliveries(&[record]).await;
+        }
+
+        result.map_err(|e| DevicesError::NotificationError(e.to_string()))?;
 
         Ok(())
     }
 
+    /// Number of devices a broadcast of `message` would reach, after the same
+    /// subscription, priority, quiet-hours and rate-limit checks as the send
+    pub async fn broadcast_recipient_count(
+        &self,
+        message: &NotificationMessage,
+    ) -> Result<usize, DevicesError> {
+        let devices = self.repo.get_enabled().await?;
+        Ok(self.recipients(&devices, message).await.0.len())
+    }
+
     /// Send a notification to all enabled devices
     pub async fn broadcast(&self, message: &NotificationMessage) -> Result<usize, DevicesError> {
         let devices = self.repo.get_enabled().await?;
@@ -210,10 +646,7 @@ impl DevicesService {
             return Ok(0);
         }
 
-        let tokens: Vec<String> = devices.iter().map(|d| d.token.clone()).collect();
-        let results = self.expo_client.send_to_tokens(&tokens, message).await;
-
-        let success_count = results.iter().filter(|r| r.is_ok()).count();
+        let success_count = self.deliver(&devices, message).await.len();
 
         tracing::info!(
             total = devices.len(),
@@ -237,10 +670,7 @@ impl DevicesService {
             return Ok(0);
         }
 
-        let tokens: Vec<String> = devices.iter().map(|d| d.token.clone()).collect();
-        let results = self.expo_client.send_to_tokens(&tokens, message).await;
-
-        let success_count = results.iter().filter(|r| r.is_ok()).count();
+        let success_count = self.deliver(&devices, message).await.len();
 
         tracing::info!(
             city = %city,
@@ -251,4 +681,633 @@ impl DevicesService {
 
         Ok(success_count)
     }
+
+    /// Devices that should receive a notification about a city: those subscribed
+    /// to the requested name, then to the geocoded name, plus devices whose reported
+    /// location is near the forecast location. Falls back to every enabled device
+    /// when nobody matches (device cities are often empty or mismatched).
+    pub async fn city_recipients(
+        &self,
+        city: &str,
+        location: &LocationInfo,
+    ) -> Result<Vec<Device>, DevicesError> {
+        let geocoded = location.city.as_str();
+        let mut devices = self.repo.get_by_city(city).await?;
+        if devices.is_empty() && !geocoded.eq_ignore_ascii_case(city) {
+            devices = self.repo.get_by_city(geocoded).await?;
+        }
+
+        let target = (location.lat, location.lon);
+        let nearby: Vec<Device> = self
+            .repo
+            .get_enabled()
+            .await?
+            .into_iter()
+            .filter(|d| {
+                d.coordinates()
+                    .is_some_and(|c| haversine_km(c, target) <= NEARBY_RADIUS_KM)
+            })
+            .filter(|d| !devices.iter().any(|existing| existing.id == d.id))
+            .collect();
+        devices.extend(nearby);
+
+        if devices.is_empty() {
+            tracing::debug!(city = %city, "No devices matched city, using all enabled devices");
+            devices = self.repo.get_enabled().await?;
+        }
+        Ok(devices)
+    }
+
+    /// Enabled devices among `ids`; ids of removed devices are skipped
+    pub async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<Device>, DevicesError> {
+        let mut devices = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(device) = self.repo.get_by_id(id).await? {
+                if device.enabled {
+                    devices.push(device);
+                }
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Send a notification to a specific set of devices
+    pub async fn send_to_devices(
+        &self,
+        devices: &[Device],
+        message: &NotificationMessage,
+    ) -> usize {
+        self.send_and_report(devices, message).await.len()
+    }
+
+    /// Send a notification to a specific set of devices, returning the ids of
+    /// the devices it was sent or queued for
+    pub async fn send_and_report(
+        &self,
+        devices: &[Device],
+        message: &NotificationMessage,
+    ) -> Vec<String> {
+        if devices.is_empty() {
+            return Vec::new();
+        }
+        self.deliver(devices, message).await
+    }
+
+    /// The devices out of `devices` a message would go out to: those
+    /// subscribed to its events, taking its priority, not in quiet hours, and
+    /// under their rate limit. Devices over the limit come back separately.
+    async fn recipients<'a>(
+        &self,
+        devices: &'a [Device],
+        message: &NotificationMessage,
+    ) -> (Vec<&'a Device>, Vec<&'a Device>) {
+        let now = Utc::now();
+        let urgent = message.priority == Priority::Urgent;
+        let devices: Vec<&Device> = devices
+            .iter()
+            .filter(|d| d.is_subscribed_to(&message.events))
+            .collect();
+        if devices.is_empty() {
+            tracing::debug!(title = %message.title, "No recipients subscribed to this event type");
+            return (Vec::new(), Vec::new());
+        }
+
+        let devices: Vec<&Device> = devices
+            .into_iter()
+            .filter(|d| d.receives_priority(message.priority))
+            .collect();
+        if devices.is_empty() {
+            tracing::debug!(
+                title = %message.title,
+                priority = ?message.priority,
+                "All recipients want higher-priority notifications only"
+            );
+            return (Vec::new(), Vec::new());
+        }
+
+        let devices: Vec<&Device> = devices
+            .into_iter()
+            .filter(|d| !d.is_quiet_at(now, urgent))
+            .collect();
+
+        if devices.is_empty() {
+            tracing::debug!(title = %message.title, "All recipients are in quiet hours");
+            return (Vec::new(), Vec::new());
+        }
+
+        self.split_rate_limited(devices, message).await
+    }
+
+    /// Deliver a message to a set of devices, skipping devices in quiet hours
+    /// and holding non-urgent messages for the digest when batching is enabled.
+    /// Returns the ids of the devices the message was sent or queued for.
+    async fn deliver(&self, devices: &[Device], message: &NotificationMessage) -> Vec<String> {
+        let (devices, limited) = self.recipients(devices, message).await;
+        self.record_rate_limited(&limited, message).await;
+        if devices.is_empty() {
+            return Vec::new();
+        }
+
+        if let Some(ref digest) = self.digest {
+            if DigestQueue::accepts(message) {
+                for device in &devices {
+                    digest.push(&device.token, message);
+                }
+                let records: Vec<DeliveryRecord> = devices
+                    .iter()
+                    .map(|d| DeliveryRecord {
+                        status: DeliveryStatus::Queued,
+                        ..Self::delivery_record(d, message, &Ok(()))
+                    })
+                    .collect();
+                self.record_deliveries(&records).await;
+                tracing::debug!(
+                    devices = devices.len(),
+                    title = %message.title,
+                    "Queued notification for digest"
+                );
+                return devices.iter().map(|d| d.id.clone()).collect();
+            }
+        }
+
+        let tokens: Vec<String> = devices.iter().map(|d| d.token.clone()).collect();
+        let results = self.expo_client.send_to_tokens(&tokens, message).await;
+
+        let records: Vec<DeliveryRecord> = devices
+            .iter()
+            .zip(&results)
+            .map(|(device, result)| Self::delivery_record(device, message, result))
+            .collect();
+        self.record_deliveries(&records).await;
+
+        let mut delivered = Vec::new();
+        let mut dead_tokens = Vec::new();
+        for ((device, token), result) in devices.iter().zip(&tokens).zip(&results) {
+            match result {
+                Ok(ticket) => {
+                    delivered.push(device.id.clone());
+                    if let (Some(receipts), Some(id)) = (&self.receipts, &ticket.id) {
+                        receipts.track(id, token);
+                    }
+                }
+                Err(NotificationError::DeviceNotRegistered(_)) => {
+                    dead_tokens.push(token.clone());
+                }
+                Err(_) => {}
+            }
+        }
+
+        self.prune_tokens(&dead_tokens).await;
+        delivered
+    }
+
+    /// Split off devices that have reached their hourly or daily cap. Urgent
+    /// messages are never limited. If the counts can't be read, everything is
+    /// let through rather than silently dropping alerts.
+    async fn split_rate_limited<'a>(
+        &self,
+        devices: Vec<&'a Device>,
+        message: &NotificationMessage,
+    ) -> (Vec<&'a Device>, Vec<&'a Device>) {
+        if message.priority == Priority::Urgent {
+            return (devices, Vec::new());
+        }
+
+        let now = Self::now();
+        let mut limits = Vec::new();
+        for (limit, window) in [
+            (self.rate_limit.per_hour, 3600),
+            (self.rate_limit.per_day, 86400),
+        ] {
+            let Some(limit) = limit else { continue };
+            match self.log.non_urgent_counts_since(now - window).await {
+                Ok(counts) => limits.push((limit, counts)),
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to read notification counts for rate limiting");
+                    return (devices, Vec::new());
+                }
+            }
+        }
+        if limits.is_empty() {
+            return (devices, Vec::new());
+        }
+
+        devices.into_iter().partition(|device| {
+            limits
+                .iter()
+                .all(|(limit, counts)| counts.get(&device.id).copied().unwrap_or(0) < *limit)
+        })
+    }
+
+    /// Log devices skipped for their rate limit as such
+    async fn record_rate_limited(&self, limited: &[&Device], message: &NotificationMessage) {
+        if limited.is_empty() {
+            return;
+        }
+        tracing::info!(
+            limited = limited.len(),
+            title = %message.title,
+            "Skipped devices over their notification rate limit"
+        );
+        let records: Vec<DeliveryRecord> = limited
+            .iter()
+            .map(|d| DeliveryRecord {
+                status: DeliveryStatus::RateLimited,
+                ..Self::delivery_record(d, message, &Ok(()))
+            })
+            .collect();
+        self.record_deliveries(&records).await;
+    }
+
+    /// Build a delivery log entry for one push attempt
+    fn delivery_record<T>(
+        device: &Device,
+        message: &NotificationMessage,
+        result: &Result<T, NotificationError>,
+    ) -> DeliveryRecord {
+        let (status, error) = match result {
+            Ok(_) => (DeliveryStatus::Sent, None),
+            Err(e) => (DeliveryStatus::Failed, Some(e.to_string())),
+        };
+        DeliveryRecord {
+            device_id: device.id.clone(),
+            title: message.title.clone(),
+            body: message.body.clone(),
+            priority: message.priority,
+            status,
+            error,
+            created_at: Self::now(),
+        }
+    }
+
+    /// Append entries to the delivery log; failures are logged, never fatal to sending
+    async fn record_deliveries(&self, records: &[DeliveryRecord]) {
+        if records.is_empty() {
+            return;
+        }
+        if let Err(e) = self.log.record(records).await {
+            tracing::error!(error = %e, "Failed to record notification deliveries");
+        }
+    }
+
+    /// Recent notifications sent to a device, newest first. With `owner` set,
+    /// other users' devices are reported as not found.
+    pub async fn notification_history(
+        &self,
+        device_id: &str,
+        owner: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<DeliveryRecord>, DevicesError> {
+        self.check_owner(device_id, owner).await?;
+        Ok(self.log.recent_for_device(device_id, limit).await?)
+    }
+
+    /// One page of a device's notification log, newest first, continuing
+    /// after the `(created_at, id)` of the previous page's last record.
+    /// Records come with their log ids.
+    pub async fn notification_page(
+        &self,
+        device_id: &str,
+        owner: Option<&str>,
+        before: Option<(i64, i64)>,
+        limit: u32,
+    ) -> Result<Vec<(i64, DeliveryRecord)>, DevicesError> {
+        self.check_owner(device_id, owner).await?;
+        Ok(self.log.page_for_device(device_id, before, limit).await?)
+    }
+
+    /// Report a missing device, or with `owner` set another user's device, as
+    /// not found
+    async fn check_owner(&self, device_id: &str, owner: Option<&str>) -> Result<(), DevicesError> {
+        let device = self.repo.get_by_id(device_id).await?;
+        if device.is_none_or(|d| owner.is_some_and(|o| d.owner.as_deref() != Some(o))) {
+            return Err(DevicesError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Most recent notifications across all devices, newest first
+    pub async fn recent_notifications(&self, limit: u32) -> Vec<DeliveryRecord> {
+        self.log.recent(limit).await.unwrap_or_else(|e| {
+            tracing::error!(error = %e, "Failed to load recent notifications");
+            Vec::new()
+        })
+    }
+
+    /// Delete delivery log entries older than the retention period
+    pub async fn prune_delivery_log(&self, retention: Duration) -> u64 {
+        let cutoff = Self::now() - retention.as_secs() as i64;
+        match self.log.prune_before(cutoff).await {
+            Ok(deleted) => {
+                if deleted > 0 {
+                    tracing::info!(deleted = deleted, "Pruned notification delivery log");
+                }
+                deleted
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to prune notification delivery log");
+                0
+            }
+        }
+    }
+
+    /// Disable or remove devices whose push tokens Expo no longer accepts.
+    /// Returns the number of devices pruned.
+    async fn prune_tokens(&self, tokens: &[String]) -> usize {
+        let mut pruned = 0;
+        for token in tokens {
+            let result = match self.unregistered_action {
+                UnregisteredAction::Remove => self.repo.remove(token).await,
+                UnregisteredAction::Disable => match self.repo.get_by_token(token).await {
+                    Ok(Some(mut device)) if device.enabled => {
+                        device.enabled = false;
+                        device.updated_at = Self::now();
+                        self.repo.upsert(&device).await.map(|_| true)
+                    }
+                    Ok(_) => Ok(false),
+                    Err(e) => Err(e),
+                },
+            };
+
+            match result {
+                Ok(true) => pruned += 1,
+                Ok(false) => {}
+                Err(e) => tracing::error!(error = %e, "Failed to prune unregistered device"),
+            }
+        }
+
+        if pruned > 0 {
+            metrics::counter!(crate::metrics::PUSH_TOKENS_PRUNED).increment(pruned as u64);
+            tracing::info!(
+                pruned = pruned,
+                action = ?self.unregistered_action,
+                "Pruned devices with unregistered push tokens"
+            );
+        }
+        pruned
+    }
+
+    /// Fetch receipts for tickets old enough to have one and prune devices
+    /// Expo reports as no longer registered. Returns the number of devices pruned.
+    pub async fn check_receipts(&self) -> usize {
+        let Some(ref receipts) = self.receipts else {
+            return 0;
+        };
+
+        let ready = receipts.take_ready(RECEIPT_MIN_AGE);
+        if ready.is_empty() {
+            return 0;
+        }
+
+        let ids: Vec<String> = ready.iter().map(|t| t.id.clone()).collect();
+        let fetched = match self.expo_client.get_receipts(&ids).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to fetch Expo push receipts, retrying later");
+                let dropped = receipts.requeue(ready);
+                if dropped > 0 {
+                    tracing::warn!(
+                        dropped = dropped,
+                        "Gave up on push receipts older than Expo keeps them"
+                    );
+                }
+                return 0;
+            }
+        };
+
+        let mut dead_tokens = Vec::new();
+        for ReadyTicket { id, token, .. } in ready {
+            let Some(receipt) = fetched.get(&id) else {
+                continue;
+            };
+            if receipt.is_device_not_registered() {
+                dead_tokens.push(token);
+            } else if receipt.status != "ok" {
+                tracing::warn!(
+                    ticket_id = %id,
+                    message = ?receipt.message,
+                    "Expo push receipt reported delivery error"
+                );
+            }
+        }
+
+        tracing::debug!(
+            checked = ids.len(),
+            received = fetched.len(),
+            unregistered = dead_tokens.len(),
+            "Checked Expo push receipts"
+        );
+
+        self.prune_tokens(&dead_tokens).await
+    }
+
+    /// Send one combined push per device for all queued digest notifications.
+    /// Returns the number of digests delivered successfully.
+    pub async fn flush_digests(&self) -> usize {
+        let Some(ref digest) = self.digest else {
+            return 0;
+        };
+
+        let now = Utc::now();
+        let mut sent = 0;
+        for (token, messages) in digest.drain() {
+            let device = self.get_by_token(&token).await;
+
+            // Hold digests for devices in quiet hours until the next window
+            if device.as_ref().is_some_and(|d| d.is_quiet_at(now, false)) {
+                for message in &messages {
+                    digest.push(&token, message);
+                }
+                continue;
+            }
+
+            let Some(message) = build_digest(messages) else {
+                continue;
+            };
+            let result = self.expo_client.send_to_token(&token, &message).await;
+            if let Some(ref device) = device {
+                let record = Self::delivery_record(device, &message, &result);
+                self.record_deliveries(&[record]).await;
+            }
+            match result {
+                Ok(ticket) => {
+                    sent += 1;
+                    if let (Some(receipts), Some(id)) = (&self.receipts, &ticket.id) {
+                        receipts.track(id, &token);
+                    }
+                }
+                Err(NotificationError::DeviceNotRegistered(_)) => {
+                    self.prune_tokens(&[token]).await;
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to send notification digest");
+                }
+            }
+        }
+
+        if sent > 0 {
+            tracing::info!(sent = sent, "Notification digests sent");
+        }
+        sent
+    }
+}
+
+/// Start a background task that sends queued notification digests every window
+pub fn start_digest_flush_task(devices_service: Arc<DevicesService>, window: Duration) {
+    // A zero period would panic the task and leave digests queued forever
+    let window = window.max(Duration::from_secs(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(window);
+        // The first tick completes immediately; skip it so the first digest covers a full window
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            devices_service.flush_digests().await;
+        }
+    });
+}
+
+/// Start a background task that checks Expo push receipts and prunes dead tokens
+pub fn start_receipt_check_task(devices_service: Arc<DevicesService>, interval: Duration) {
+    // A zero period would panic the task and stop receipt checks
+    let interval = interval.max(Duration::from_secs(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            devices_service.check_receipts().await;
+        }
+    });
+}
+
+/// Start a background task that trims the notification delivery log to the retention period
+pub fn start_delivery_log_prune_task(devices_service: Arc<DevicesService>, retention: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            devices_service.prune_delivery_log(retention).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+    use crate::devices::models::Platform;
+
+    fn registration(token: &str) -> DeviceRegistrationRequest {
+        DeviceRegistrationRequest {
+            token: token.to_string(),
+            platform: Platform::Ios,
+            device_name: None,
+            app_version: None,
+            cities: vec!["Chicago".to_string()],
+            units: "imperial".to_string(),
+            enabled: true,
+            quiet_hours: None,
+            min_priority: None,
+            timezone: None,
+            language: None,
+            alert_preferences: None,
+            subscriptions: None,
+            cold_threshold: None,
+            heat_threshold: None,
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reregistration_keeps_owner() {
+        let pool = create_pool(&DbConfig {
+            url: "sqlite::memory:".into(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        let service = DevicesService::new(Client::new(), pool, &NotificationsConfig::default());
+        let token = "ExponentPushToken[abc]";
+
+        // An unowned device can be claimed
+        service.register(registration(token), None).await.unwrap();
+        let device = service
+            .register(registration(token), Some("alice".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(device.owner.as_deref(), Some("alice"));
+
+        // Someone else can't take it over
+        let err = service
+            .register(registration(token), Some("bob".to_string()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DevicesError::OwnedByAnother));
+
+        // The owner, or the device itself without signing in, still can
+        service
+            .register(registration(token), Some("alice".to_string()))
+            .await
+            .unwrap();
+        let device = service.register(registration(token), None).await.unwrap();
+        assert_eq!(device.owner.as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_count_applies_delivery_filters() {
+        let pool = create_pool(&DbConfig {
+            url: "sqlite::memory:".into(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        let service = DevicesService::new(Client::new(), pool, &NotificationsConfig::default());
+
+        service
+            .register(registration("ExponentPushToken[a]"), None)
+            .await
+            .unwrap();
+        service
+            .register(
+                DeviceRegistrationRequest {
+                    min_priority: Some(Priority::High),
+                    ..registration("ExponentPushToken[b]")
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        service
+            .register(
+                DeviceRegistrationRequest {
+                    enabled: false,
+                    ..registration("ExponentPushToken[c]")
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let mut message = NotificationMessage {
+            title: "Notice".to_string(),
+            body: "Hello".to_string(),
+            subtitle: None,
+            priority: Priority::Default,
+            tags: Vec::new(),
+            city: None,
+            events: Vec::new(),
+        };
+        assert_eq!(
+            service.broadcast_recipient_count(&message).await.unwrap(),
+            1
+        );
+
+        message.priority = Priority::High;
+        assert_eq!(
+            service.broadcast_recipient_count(&message).await.unwrap(),
+            2
+        );
+    }
 }
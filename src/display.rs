@@ -0,0 +1,187 @@
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use utoipa::IntoParams;
+
+use crate::config::DisplayConfig;
+
+/// Field names accepted by `?display=`, matching the `[display]` config keys
+pub const DISPLAY_FIELDS: [&str; 7] = [
+    "temperature",
+    "feels_like",
+    "humidity",
+    "wind_speed",
+    "description",
+    "pressure",
+    "visibility",
+];
+
+/// Per-request override of the server's `[display]` profile
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DisplayQuery {
+    /// Comma-separated fields to show (temperature, feels_like, humidity,
+    /// wind_speed, description, pressure, visibility), or `all`. Defaults to
+    /// the server's display profile.
+    pub display: Option<String>,
+}
+
+#[derive(Error, Debug)]
+#[error("unknown display field {0:?} (expected one of {fields}, or all)", fields = DISPLAY_FIELDS.join(", "))]
+pub struct UnknownDisplayField(pub String);
+
+impl DisplayConfig {
+    /// Every field shown
+    pub fn all() -> Self {
+        Self {
+            temperature: true,
+            humidity: true,
+            wind_speed: true,
+            description: true,
+            feels_like: true,
+            pressure: true,
+            visibility: true,
+        }
+    }
+
+    /// Parse a `?display=` value: `all`, or a comma-separated list of the
+    /// fields to show. Anything not listed is hidden.
+    pub fn parse_list(list: &str) -> Result<Self, UnknownDisplayField> {
+        if list.trim().eq_ignore_ascii_case("all") {
+            return Ok(Self::all());
+        }
+        let mut profile = Self {
+            temperature: false,
+            humidity: false,
+            wind_speed: false,
+            description: false,
+            feels_like: false,
+            pressure: false,
+            visibility: false,
+        };
+        for field in list.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            match field.to_ascii_lowercase().as_str() {
+                "temperature" => profile.temperature = true,
+                "feels_like" => profile.feels_like = true,
+                "humidity" => profile.humidity = true,
+                "wind_speed" => profile.wind_speed = true,
+                "description" => profile.description = true,
+                "pressure" => profile.pressure = true,
+                "visibility" => profile.visibility = true,
+                _ => return Err(UnknownDisplayField(field.to_string())),
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Response keys this profile removes. Daily forecast entries carry
+    /// several temperatures and feels-likes, which follow their flag.
+    fn hidden_keys(&self) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+        if !self.temperature {
+            keys.extend([
+                "temperature",
+                "temp_min",
+                "temp_max",
+                "temp_day",
+                "temp_night",
+                "temp_morning",
+                "temp_evening",
+            ]);
+        }
+        if !self.feels_like {
+            keys.extend(["feels_like", "feels_like_day", "feels_like_night"]);
+        }
+        if !self.humidity {
+            keys.push("humidity");
+        }
+        if !self.wind_speed {
+            keys.extend(["wind_speed", "wind_gust"]);
+        }
+        if !self.description {
+            keys.push("description");
+        }
+        if !self.pressure {
+            keys.push("pressure");
+        }
+        if !self.visibility {
+            keys.push("visibility");
+        }
+        keys
+    }
+
+    /// Strip hidden fields from a serialized `/weather` response
+    pub fn filter_weather(&self, weather: &mut Value) {
+        strip(weather, &self.hidden_keys());
+    }
+
+    /// Strip hidden fields from the current, hourly, and daily entries of a
+    /// serialized `/forecast` response. Location and alerts are left alone.
+    pub fn filter_forecast(&self, forecast: &mut Value) {
+        let hidden = self.hidden_keys();
+        if hidden.is_empty() {
+            return;
+        }
+        if let Some(current) = forecast.get_mut("current") {
+            strip(current, &hidden);
+        }
+        for section in ["hourly", "daily"] {
+            if let Some(Value::Array(entries)) = forecast.get_mut(section) {
+                for entry in entries {
+                    strip(entry, &hidden);
+                }
+            }
+        }
+    }
+}
+
+fn strip(entry: &mut Value, keys: &[&str]) {
+    if let Value::Object(map) = entry {
+        for key in keys {
+            map.remove(*key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_list() {
+        let profile = DisplayConfig::parse_list("temperature, Humidity").unwrap();
+        assert!(profile.temperature && profile.humidity);
+        assert!(!profile.feels_like && !profile.description && !profile.pressure);
+
+        assert!(DisplayConfig::parse_list("all").unwrap().visibility);
+        assert!(DisplayConfig::parse_list("temperature,dew_point").is_err());
+    }
+
+    #[test]
+    fn test_filter_forecast() {
+        let profile = DisplayConfig::parse_list("temperature,description").unwrap();
+        let mut forecast = json!({
+            "location": {"city": "Chicago"},
+            "current": {"timestamp": 1, "temperature": 20.0, "humidity": 50, "description": "clear"},
+            "hourly": [{"timestamp": 2, "temperature": 19.0, "pressure": 1013, "wind_gust": 9.0}],
+            "daily": [{"timestamp": 3, "temp_max": 25.0, "feels_like_day": 26.0}],
+            "alerts": [{"description": "Heat advisory", "humidity": 1}]
+        });
+        profile.filter_forecast(&mut forecast);
+
+        assert_eq!(
+            forecast["current"],
+            json!({"timestamp": 1, "temperature": 20.0, "description": "clear"})
+        );
+        assert_eq!(
+            forecast["hourly"][0],
+            json!({"timestamp": 2, "temperature": 19.0})
+        );
+        assert_eq!(
+            forecast["daily"][0],
+            json!({"timestamp": 3, "temp_max": 25.0})
+        );
+        assert_eq!(forecast["alerts"][0]["humidity"], 1);
+    }
+}
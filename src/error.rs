@@ -12,6 +12,15 @@ pub struct ErrorResponse {
     pub error: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
+    /// Seconds to wait before retrying, also sent as `Retry-After`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    /// HTTP status OpenWeatherMap (or another upstream) answered with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_status: Option<u16>,
+    /// The upstream's own explanation, when it gave one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
 }
 
 impl ErrorResponse {
@@ -19,13 +28,16 @@ impl ErrorResponse {
         Self {
             error: error.into(),
             code: None,
+            retry_after: None,
+            upstream_status: None,
+            details: None,
         }
     }
 
     pub fn with_code(error: impl Into<String>, code: impl Into<String>) -> Self {
         Self {
-            error: error.into(),
             code: Some(code.into()),
+            ..Self::new(error)
         }
     }
 }
@@ -39,12 +51,28 @@ pub trait HttpError: std::error::Error {
     fn error_code(&self) -> Option<&'static str> {
         None
     }
+
+    /// Seconds the client should wait before retrying, sent as `Retry-After`
+    fn retry_after(&self) -> Option<u64> {
+        None
+    }
+
+    /// HTTP status of the upstream answer behind this error, if any
+    fn upstream_status(&self) -> Option<u16> {
+        None
+    }
+
+    /// Extra context for the client, such as the upstream's message
+    fn details(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Convert any HttpError into an Axum response
 pub fn into_response<E: HttpError>(err: E) -> Response {
     let status = err.status_code();
     let code = err.error_code();
+    let retry_after = err.retry_after();
     let message = err.to_string();
 
     tracing::error!(
@@ -54,13 +82,21 @@ pub fn into_response<E: HttpError>(err: E) -> Response {
         "API error"
     );
 
-    let body = if let Some(code) = code {
-        ErrorResponse::with_code(message, code)
-    } else {
-        ErrorResponse::new(message)
+    let body = ErrorResponse {
+        code: code.map(str::to_string),
+        retry_after,
+        upstream_status: err.upstream_status(),
+        details: err.details(),
+        ..ErrorResponse::new(message)
     };
 
-    (status, Json(body)).into_response()
+    let mut response = (status, Json(body)).into_response();
+    if let Some(secs) = retry_after {
+        response
+            .headers_mut()
+            .insert(axum::http::header::RETRY_AFTER, secs.into());
+    }
+    response
 }
 
 /// Macro to implement IntoResponse for HttpError types
@@ -0,0 +1,110 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use crate::db::history_repo::HistoryRecord;
+
+pub const HISTORY_HEADER: &str = "id,location_key,city,lat,lon,timestamp,units,temperature,\
+feels_like,humidity,pressure,wind_speed,wind_direction,clouds,visibility,description,icon,\
+rain_1h,snow_1h,fetched_at,wind_gust,dew_point,uvi,condition_id";
+
+/// Quote a field when it contains a separator, quote, or line break
+fn text(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// An optional number, empty when missing
+fn optional<T: Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Numbered history rows as CSV with a header line
+pub fn history_csv(rows: &[(i64, HistoryRecord)]) -> String {
+    let mut csv = String::from(HISTORY_HEADER);
+    csv.push('\n');
+    for (id, r) in rows {
+        csv.push_str(&history_csv_row(*id, r));
+    }
+    csv
+}
+
+/// One numbered history row as a CSV line, under `HISTORY_HEADER`
+pub fn history_csv_row(id: i64, r: &HistoryRecord) -> String {
+    let fields = [
+        id.to_string(),
+        text(&r.location_key).into_owned(),
+        text(&r.city).into_owned(),
+        r.lat.to_string(),
+        r.lon.to_string(),
+        r.timestamp.to_string(),
+        text(&r.units).into_owned(),
+        r.temperature.to_string(),
+        r.feels_like.to_string(),
+        r.humidity.to_string(),
+        r.pressure.to_string(),
+        r.wind_speed.to_string(),
+        optional(r.wind_direction),
+        optional(r.clouds),
+        optional(r.visibility),
+        text(r.description.as_deref().unwrap_or_default()).into_owned(),
+        text(r.icon.as_deref().unwrap_or_default()).into_owned(),
+        optional(r.rain_1h),
+        optional(r.snow_1h),
+        r.fetched_at.to_string(),
+        optional(r.wind_gust),
+        optional(r.dew_point),
+        optional(r.uvi),
+        optional(r.condition_id),
+    ];
+    let mut line = fields.join(",");
+    line.push('\n');
+    line
+}
+
+/// Bring CSV written under an earlier, shorter header up to the current
+/// columns, leaving the added ones empty. Columns are only ever appended.
+pub fn upgrade_history_csv(csv: String) -> String {
+    let Some((header, body)) = csv.split_once('\n') else {
+        return csv;
+    };
+    let Some(added) = HISTORY_HEADER
+        .strip_prefix(header)
+        .filter(|added| added.starts_with(','))
+    else {
+        return csv;
+    };
+    let padding = ",".repeat(added.matches(',').count());
+
+    let mut upgraded = format!("{}\n", HISTORY_HEADER);
+    for line in body.lines() {
+        upgraded.push_str(line);
+        upgraded.push_str(&padding);
+        upgraded.push('\n');
+    }
+    upgraded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_history_csv() {
+        let old = "id,location_key,city,lat,lon,timestamp,units,temperature,\
+feels_like,humidity,pressure,wind_speed,wind_direction,clouds,visibility,description,icon,\
+rain_1h,snow_1h,fetched_at\n1,k,Chicago,1,2,3,metric,4,5,6,7,8,,,,clear sky,01d,,,9\n";
+        let upgraded = upgrade_history_csv(old.to_string());
+        let mut lines = upgraded.lines();
+        assert_eq!(lines.next(), Some(HISTORY_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("1,k,Chicago,1,2,3,metric,4,5,6,7,8,,,,clear sky,01d,,,9,,,,")
+        );
+
+        let current = history_csv(&[]);
+        assert_eq!(upgrade_history_csv(current.clone()), current);
+    }
+}
@@ -0,0 +1,7 @@
+mod csv;
+mod parquet;
+mod runner;
+mod target;
+
+pub use csv::{history_csv, history_csv_row, upgrade_history_csv, HISTORY_HEADER};
+pub use runner::schedule_history_export_job;
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use parquet::data_type::{ByteArray, ByteArrayType, DataType, DoubleType, Int32Type, Int64Type};
+use parquet::errors::{ParquetError, Result};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedFileWriter, SerializedRowGroupWriter};
+use parquet::schema::parser::parse_message_type;
+
+use crate::db::history_repo::HistoryRecord;
+
+/// The CSV export's columns, in the same order, as a Parquet schema
+const HISTORY_SCHEMA: &str = "message weather_history {
+    REQUIRED INT64 id;
+    REQUIRED BYTE_ARRAY location_key (UTF8);
+    REQUIRED BYTE_ARRAY city (UTF8);
+    REQUIRED DOUBLE lat;
+    REQUIRED DOUBLE lon;
+    REQUIRED INT64 timestamp;
+    REQUIRED BYTE_ARRAY units (UTF8);
+    REQUIRED DOUBLE temperature;
+    REQUIRED DOUBLE feels_like;
+    REQUIRED INT32 humidity;
+    REQUIRED INT32 pressure;
+    REQUIRED DOUBLE wind_speed;
+    OPTIONAL INT32 wind_direction;
+    OPTIONAL INT32 clouds;
+    OPTIONAL INT32 visibility;
+    OPTIONAL BYTE_ARRAY description (UTF8);
+    OPTIONAL BYTE_ARRAY icon (UTF8);
+    OPTIONAL DOUBLE rain_1h;
+    OPTIONAL DOUBLE snow_1h;
+    REQUIRED INT64 fetched_at;
+    OPTIONAL DOUBLE wind_gust;
+    OPTIONAL DOUBLE dew_point;
+    OPTIONAL DOUBLE uvi;
+    OPTIONAL INT32 condition_id;
+}";
+
+type RowGroup<'a> = SerializedRowGroupWriter<'a, Vec<u8>>;
+
+/// Write the next column, which has no missing values
+fn required<T: DataType>(group: &mut RowGroup<'_>, values: &[T::T]) -> Result<()> {
+    let mut column = group
+        .next_column()?
+        .ok_or_else(|| ParquetError::General("schema has fewer columns than written".into()))?;
+    column.typed::<T>().write_batch(values, None, None)?;
+    column.close()
+}
+
+/// Write the next column, marking missing values as null
+fn optional<T: DataType>(group: &mut RowGroup<'_>, values: Vec<Option<T::T>>) -> Result<()> {
+    let levels: Vec<i16> = values.iter().map(|v| i16::from(v.is_some())).collect();
+    let present: Vec<T::T> = values.into_iter().flatten().collect();
+    let mut column = group
+        .next_column()?
+        .ok_or_else(|| ParquetError::General("schema has fewer columns than written".into()))?;
+    column
+        .typed::<T>()
+        .write_batch(&present, Some(&levels), None)?;
+    column.close()
+}
+
+fn text(value: &str) -> ByteArray {
+    ByteArray::from(value)
+}
+
+/// One field of every row
+fn column<T>(rows: &[(i64, HistoryRecord)], field: impl Fn(&HistoryRecord) -> T) -> Vec<T> {
+    rows.iter().map(|(_, r)| field(r)).collect()
+}
+
+/// Numbered history rows as a Parquet file with one row group
+pub fn history_parquet(rows: &[(i64, HistoryRecord)]) -> Result<Vec<u8>> {
+    let schema = Arc::new(parse_message_type(HISTORY_SCHEMA)?);
+    let props = Arc::new(WriterProperties::builder().build());
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema, props)?;
+
+    let mut group = writer.next_row_group()?;
+    required::<Int64Type>(
+        &mut group,
+        &rows.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+    )?;
+    required::<ByteArrayType>(&mut group, &column(rows, |r| text(&r.location_key)))?;
+    required::<ByteArrayType>(&mut group, &column(rows, |r| text(&r.city)))?;
+    required::<DoubleType>(&mut group, &column(rows, |r| r.lat))?;
+    required::<DoubleType>(&mut group, &column(rows, |r| r.lon))?;
+    required::<Int64Type>(&mut group, &column(rows, |r| r.timestamp))?;
+    required::<ByteArrayType>(&mut group, &column(rows, |r| text(&r.units)))?;
+    required::<DoubleType>(&mut group, &column(rows, |r| r.temperature))?;
+    required::<DoubleType>(&mut group, &column(rows, |r| r.feels_like))?;
+    required::<Int32Type>(&mut group, &column(rows, |r| r.humidity))?;
+    required::<Int32Type>(&mut group, &column(rows, |r| r.pressure))?;
+    required::<DoubleType>(&mut group, &column(rows, |r| r.wind_speed))?;
+    optional::<Int32Type>(&mut group, column(rows, |r| r.wind_direction))?;
+    optional::<Int32Type>(&mut group, column(rows, |r| r.clouds))?;
+    optional::<Int32Type>(&mut group, column(rows, |r| r.visibility))?;
+    optional::<ByteArrayType>(
+        &mut group,
+        column(rows, |r| r.description.as_deref().map(text)),
+    )?;
+    optional::<ByteArrayType>(&mut group, column(rows, |r| r.icon.as_deref().map(text)))?;
+    optional::<DoubleType>(&mut group, column(rows, |r| r.rain_1h))?;
+    optional::<DoubleType>(&mut group, column(rows, |r| r.snow_1h))?;
+    required::<Int64Type>(&mut group, &column(rows, |r| r.fetched_at))?;
+    optional::<DoubleType>(&mut group, column(rows, |r| r.wind_gust))?;
+    optional::<DoubleType>(&mut group, column(rows, |r| r.dew_point))?;
+    optional::<DoubleType>(&mut group, column(rows, |r| r.uvi))?;
+    optional::<Int32Type>(&mut group, column(rows, |r| r.condition_id))?;
+    group.close()?;
+
+    writer.into_inner()
+}
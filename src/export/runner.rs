@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use reqwest::Client;
+use sqlx::SqlitePool;
+use tokio_cron_scheduler::Job;
+
+use super::csv::history_csv;
+use super::parquet::history_parquet;
+use super::target::{ExportError, ExportTarget};
+use crate::config::{ExportFileFormat, HistoryExportConfig, ObjectStoreConfig};
+use crate::db::history_repo::{HistoryRepository, SqliteHistoryRepository};
+use crate::db::{ExportCursorRepository, SqliteExportCursorRepository};
+use crate::scheduler::{SchedulerError, SchedulerService};
+
+/// Export cursor and file category for the weather_history table
+const HISTORY_CURSOR: &str = "weather_history";
+
+/// Send history rows added since the last run to `target`, at most
+/// `batch_size` rows per file. The cursor advances after each file, so a
+/// failed run resumes where it stopped. Returns the number of rows exported.
+async fn run_history_export(
+    history: &dyn HistoryRepository,
+    cursors: &dyn ExportCursorRepository,
+    target: &ExportTarget,
+    batch_size: u32,
+    format: ExportFileFormat,
+) -> Result<usize, ExportError> {
+    let mut last_id = cursors.get(HISTORY_CURSOR).await?;
+    let mut exported = 0;
+
+    loop {
+        let rows = history.get_after_id(last_id, i64::from(batch_size)).await?;
+        let (Some(&(first_id, _)), Some(&(to_id, _))) = (rows.first(), rows.last()) else {
+            break;
+        };
+        let now = chrono::Utc::now();
+        let (body, extension, content_type) = match format {
+            ExportFileFormat::Csv => (history_csv(&rows).into_bytes(), "csv", "text/csv"),
+            ExportFileFormat::Parquet => (
+                history_parquet(&rows)?,
+                "parquet",
+                "application/vnd.apache.parquet",
+            ),
+        };
+        let name = format!(
+            "weather_history_{}_{}-{}.{}",
+            now.format("%Y%m%dT%H%M%SZ"),
+            first_id,
+            to_id,
+            extension
+        );
+        target
+            .put(HISTORY_CURSOR, &name, body, content_type)
+            .await?;
+        cursors.set(HISTORY_CURSOR, to_id, now.timestamp()).await?;
+        tracing::info!(file = %name, rows = rows.len(), "History export: file written");
+
+        last_id = to_id;
+        exported += rows.len();
+        if rows.len() < batch_size as usize {
+            break;
+        }
+    }
+
+    Ok(exported)
+}
+
+/// Register the history export cron job on the scheduler.
+pub async fn schedule_history_export_job(
+    scheduler_service: Arc<SchedulerService>,
+    pool: SqlitePool,
+    client: Client,
+    config: HistoryExportConfig,
+    object_store: &ObjectStoreConfig,
+) -> Result<(), SchedulerError> {
+    tracing::info!(cron = %config.cron, "Scheduling history export job");
+
+    let history = Arc::new(SqliteHistoryRepository::new(pool.clone()));
+    let cursors = Arc::new(SqliteExportCursorRepository::new(pool));
+    let target = Arc::new(ExportTarget::new(&config.target, object_store, client));
+    let batch_size = config.batch_size;
+    let format = config.format;
+    let scheduler_for_closure = Arc::clone(&scheduler_service);
+
+    let job = Job::new_async(config.cron.as_str(), move |_uuid, _lock| {
+        let history = Arc::clone(&history);
+        let cursors = Arc::clone(&cursors);
+        let target = Arc::clone(&target);
+        let scheduler_service = Arc::clone(&scheduler_for_closure);
+
+        Box::pin(async move {
+            let Some(_run) = scheduler_service.begin_run() else {
+                return;
+            };
+            match run_history_export(
+                history.as_ref(),
+                cursors.as_ref(),
+                &target,
+                batch_size,
+                format,
+            )
+            .await
+            {
+                Ok(exported) => {
+                    if exported > 0 {
+                        metrics::counter!(crate::metrics::HISTORY_ROWS_EXPORTED)
+                            .increment(exported as u64);
+                    }
+                    tracing::info!(rows = exported, "History export run finished");
+                }
+                Err(e) => tracing::error!(error = %e, "History export run failed"),
+            }
+        })
+    })
+    .map_err(|e| SchedulerError::Scheduler(e.to_string()))?;
+
+    scheduler_service.add_system_job(job).await?;
+
+    tracing::info!("History export job scheduled");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::history_repo::HistoryRecord;
+    use crate::db::{create_pool, run_migrations, DbConfig};
+    use crate::export::HISTORY_HEADER;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+
+    fn record(timestamp: i64, description: &str) -> HistoryRecord {
+        HistoryRecord {
+            city: "Chicago".to_string(),
+            location_key: "41.88,-87.63".to_string(),
+            lat: 41.88,
+            lon: -87.63,
+            timestamp,
+            temperature: 20.5,
+            feels_like: 19.0,
+            humidity: 65,
+            pressure: 1013,
+            wind_speed: 5.5,
+            wind_direction: None,
+            clouds: Some(40),
+            visibility: None,
+            description: Some(description.to_string()),
+            icon: None,
+            rain_1h: Some(0.25),
+            snow_1h: None,
+            units: "metric".to_string(),
+            fetched_at: 1700000000,
+            wind_gust: None,
+            dew_point: None,
+            uvi: None,
+            condition_id: None,
+            source: "timemachine".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_export_resumes_from_cursor() {
+        let pool = create_pool(&DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        run_migrations(&pool, &Default::default()).await.unwrap();
+        let history = SqliteHistoryRepository::new(pool.clone());
+        let cursors = SqliteExportCursorRepository::new(pool);
+        let dir = std::env::temp_dir().join(format!("weathrs-export-{}", uuid::Uuid::new_v4()));
+        let target = ExportTarget::Directory(dir.clone());
+
+        history
+            .insert_batch(&[
+                record(1700000000, "light rain"),
+                record(1700003600, "rain, \"heavy\""),
+                record(1700007200, "clear sky"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            run_history_export(&history, &cursors, &target, 2, ExportFileFormat::Csv)
+                .await
+                .unwrap(),
+            3
+        );
+        assert_eq!(cursors.get(HISTORY_CURSOR).await.unwrap(), 3);
+
+        let mut files: Vec<_> = std::fs::read_dir(dir.join(HISTORY_CURSOR))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        let first = std::fs::read_to_string(&files[0]).unwrap();
+        let lines: Vec<&str> = first.lines().collect();
+        assert!(lines[0].starts_with("id,location_key,city,"));
+        assert_eq!(
+            lines[2],
+            "2,\"41.88,-87.63\",Chicago,41.88,-87.63,1700003600,metric,20.5,19,65,1013,5.5,,40,,\"rain, \"\"heavy\"\"\",,0.25,,1700000000,,,,"
+        );
+
+        // Nothing new, nothing sent
+        assert_eq!(
+            run_history_export(&history, &cursors, &target, 2, ExportFileFormat::Csv)
+                .await
+                .unwrap(),
+            0
+        );
+
+        // The same columns as Parquet, with missing values as nulls
+        history
+            .insert_batch(&[record(1700010800, "snow")])
+            .await
+            .unwrap();
+        assert_eq!(
+            run_history_export(&history, &cursors, &target, 2, ExportFileFormat::Parquet)
+                .await
+                .unwrap(),
+            1
+        );
+        let file = std::fs::read_dir(dir.join(HISTORY_CURSOR))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|ext| ext == "parquet"))
+            .unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(file).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 1);
+        let row = reader.get_row_iter(None).unwrap().next().unwrap().unwrap();
+        assert_eq!(row.len(), HISTORY_HEADER.split(',').count());
+        assert_eq!(row.get_long(0).unwrap(), 4);
+        assert_eq!(row.get_string(15).unwrap(), "snow");
+        assert_eq!(row.get_int(13).unwrap(), 40);
+        assert!(row.get_int(12).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
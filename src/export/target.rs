@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use reqwest::Client;
+use thiserror::Error;
+
+use crate::config::{ExportTargetConfig, ObjectStoreConfig};
+use crate::db::DbError;
+use crate::object_store::{ObjectStore, ObjectStoreError};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Failed to write export: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to send export: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("Export endpoint returned {0}")]
+    Status(u16),
+
+    #[error(transparent)]
+    ObjectStore(#[from] ObjectStoreError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    #[error("Failed to encode Parquet: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+}
+
+/// Where export jobs send their files
+pub enum ExportTarget {
+    Directory(PathBuf),
+    Http {
+        client: Client,
+        url: String,
+        bearer_token: Option<String>,
+    },
+    ObjectStore(ObjectStore),
+}
+
+impl ExportTarget {
+    pub fn new(
+        config: &ExportTargetConfig,
+        object_store: &ObjectStoreConfig,
+        client: Client,
+    ) -> Self {
+        match config {
+            ExportTargetConfig::Directory { path } => Self::Directory(PathBuf::from(path)),
+            ExportTargetConfig::Http { url, bearer_token } => Self::Http {
+                client,
+                url: url.clone(),
+                bearer_token: bearer_token.clone(),
+            },
+            ExportTargetConfig::S3 => Self::ObjectStore(ObjectStore::new(object_store, client)),
+        }
+    }
+
+    /// Store one exported file of `category` (such as "weather_history")
+    /// under `name`. Directories get a folder per category; buckets get
+    /// `exports/<category>/YYYY/MM/DD/` keys.
+    pub async fn put(
+        &self,
+        category: &str,
+        name: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), ExportError> {
+        match self {
+            Self::Directory(dir) => {
+                let dir = dir.join(category);
+                tokio::fs::create_dir_all(&dir).await?;
+                // Write then rename, so readers never see a partial file
+                let partial = dir.join(format!("{}.partial", name));
+                tokio::fs::write(&partial, body).await?;
+                tokio::fs::rename(&partial, dir.join(name)).await?;
+            }
+            Self::Http {
+                client,
+                url,
+                bearer_token,
+            } => {
+                let mut request = client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, content_type)
+                    .header(
+                        reqwest::header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{}\"", name),
+                    )
+                    .body(body);
+                if let Some(token) = bearer_token {
+                    request = request.bearer_auth(token);
+                }
+                let status = request.send().await?.status();
+                if !status.is_success() {
+                    return Err(ExportError::Status(status.as_u16()));
+                }
+            }
+            Self::ObjectStore(store) => {
+                let key = store.dated_key(
+                    &format!("exports/{}", category),
+                    chrono::Utc::now().date_naive(),
+                    name,
+                );
+                store.put(&key, body, content_type).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -7,8 +7,14 @@ use axum::{
     Json,
 };
 use serde::Deserialize;
+use utoipa::IntoParams;
 
+use crate::config::DisplayConfig;
+use crate::display::DisplayQuery;
 use crate::error::ErrorResponse;
+use crate::forecast::{ProviderKind, ProviderQuery, UnknownProvider};
+use crate::geocode::models::{geo_location, make_location_key, round_coord};
+use crate::units::Units;
 
 /// Query parameters for weather/forecast requests
 #[derive(Debug, Deserialize)]
@@ -21,8 +27,9 @@ pub struct WeatherQuery {
 
 /// Extracts city from either path parameter or query parameter
 ///
-/// Checks path first, then falls back to query parameter.
-/// Returns None if city is not provided in either location.
+/// Checks path first, then the `city` query parameter, then `lat` and `lon`,
+/// which become a "geo:lat,lon" location so GPS clients needn't name a city.
+/// Returns None if no location is provided in any of them.
 #[derive(Debug)]
 pub struct CityParam(pub Option<String>);
 
@@ -42,7 +49,7 @@ impl<S> FromRequestParts<S> for CityParam
 where
     S: Send + Sync,
 {
-    type Rejection = CityParamRejection;
+    type Rejection = CoordsRejection;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         // Try to extract city from path first
@@ -53,23 +60,35 @@ where
         }
 
         // Fall back to query parameter
-        if let Ok(Query(query)) = Query::<WeatherQuery>::from_request_parts(parts, state).await {
-            return Ok(CityParam(query.city));
+        if let Ok(Query(WeatherQuery {
+            city: Some(city), ..
+        })) = Query::<WeatherQuery>::from_request_parts(parts, state).await
+        {
+            return Ok(CityParam(Some(city)));
         }
 
-        // No city provided - that's okay, handler can use default
-        Ok(CityParam(None))
+        // Then to coordinates, skipping the city geocoding step
+        let coords = <Coords as axum::extract::OptionalFromRequestParts<S>>::from_request_parts(
+            parts, state,
+        )
+        .await?;
+
+        // No location provided - that's okay, handler can use default
+        Ok(CityParam(coords.map(|c| c.as_location())))
     }
 }
 
 /// Extracts units from query parameter
+///
+/// Anything other than metric, imperial, or standard is rejected with a 400
+/// rather than passed on to OpenWeatherMap.
 #[derive(Debug)]
-pub struct UnitsParam(pub Option<String>);
+pub struct UnitsParam(pub Option<Units>);
 
 impl UnitsParam {
     /// Get the units value or use a default
-    pub fn or_default(self, default: impl Into<String>) -> String {
-        self.0.unwrap_or_else(|| default.into())
+    pub fn or_default(self, default: Units) -> Units {
+        self.0.unwrap_or(default)
     }
 }
 
@@ -77,23 +96,321 @@ impl<S> FromRequestParts<S> for UnitsParam
 where
     S: Send + Sync,
 {
-    type Rejection = CityParamRejection;
+    type Rejection = UnitsParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(Query(query)) = Query::<WeatherQuery>::from_request_parts(parts, state).await else {
+            return Ok(UnitsParam(None));
+        };
+        match query.units.as_deref().map(str::trim) {
+            None | Some("") => Ok(UnitsParam(None)),
+            Some(units) => units
+                .parse()
+                .map(|units| UnitsParam(Some(units)))
+                .map_err(|err| UnitsParamRejection(err.to_string())),
+        }
+    }
+}
+
+/// Rejection for an unknown `units` value
+#[derive(Debug)]
+pub struct UnitsParamRejection(pub String);
+
+impl IntoResponse for UnitsParamRejection {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::with_code(self.0, "INVALID_UNITS")),
+        )
+            .into_response()
+    }
+}
+
+/// Extracts a `display` field list overriding the configured profile
+///
+/// `None` when the parameter is absent, so the handler falls back to the
+/// server's `[display]` config. Unknown field names are rejected with a 400.
+#[derive(Debug)]
+pub struct DisplayParam(pub Option<DisplayConfig>);
+
+impl<S> FromRequestParts<S> for DisplayParam
+where
+    S: Send + Sync,
+{
+    type Rejection = DisplayParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(Query(query)) = Query::<DisplayQuery>::from_request_parts(parts, state).await else {
+            return Ok(DisplayParam(None));
+        };
+        match query.display.as_deref().map(str::trim) {
+            None | Some("") => Ok(DisplayParam(None)),
+            Some(list) => DisplayConfig::parse_list(list)
+                .map(|profile| DisplayParam(Some(profile)))
+                .map_err(|err| DisplayParamRejection(err.to_string())),
+        }
+    }
+}
+
+/// Rejection for an unknown `display` field
+#[derive(Debug)]
+pub struct DisplayParamRejection(pub String);
+
+impl IntoResponse for DisplayParamRejection {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::with_code(self.0, "INVALID_DISPLAY")),
+        )
+            .into_response()
+    }
+}
+
+/// Extracts a `provider` choosing where a forecast comes from
+///
+/// `None` when the parameter is absent, so the service uses the configured
+/// default. Unknown provider names are rejected with a 400.
+#[derive(Debug)]
+pub struct ProviderParam(pub Option<ProviderKind>);
+
+impl<S> FromRequestParts<S> for ProviderParam
+where
+    S: Send + Sync,
+{
+    type Rejection = ProviderParamRejection;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        if let Ok(Query(query)) = Query::<WeatherQuery>::from_request_parts(parts, state).await {
-            return Ok(UnitsParam(query.units));
+        let Ok(Query(query)) = Query::<ProviderQuery>::from_request_parts(parts, state).await
+        else {
+            return Ok(ProviderParam(None));
+        };
+        match query.provider.as_deref().map(str::trim) {
+            None | Some("") => Ok(ProviderParam(None)),
+            Some(name) => name
+                .parse()
+                .map(|kind| ProviderParam(Some(kind)))
+                .map_err(|err: UnknownProvider| ProviderParamRejection(err.to_string())),
+        }
+    }
+}
+
+/// Rejection for an unknown `provider` value
+#[derive(Debug)]
+pub struct ProviderParamRejection(pub String);
+
+impl IntoResponse for ProviderParamRejection {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::with_code(self.0, "INVALID_PROVIDER")),
+        )
+            .into_response()
+    }
+}
+
+/// Query parameters for coordinate-based requests
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CoordsQuery {
+    /// Latitude, -90 to 90
+    #[param(value_type = Option<f64>)]
+    pub lat: Option<String>,
+    /// Longitude, -180 to 180
+    #[param(value_type = Option<f64>)]
+    pub lon: Option<String>,
+}
+
+/// Extracts and range-checks `lat` and `lon` query parameters
+///
+/// Coordinates are rounded to 2 decimal places (about 1 km), the precision
+/// history location keys use, so nearby requests share cache entries. Use
+/// `Option<Coords>` for endpoints that also accept a city; it is `None` only
+/// when neither parameter is given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coords {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Coords {
+    /// Validate and round a coordinate pair
+    pub fn new(lat: f64, lon: f64) -> Result<Self, CoordsRejection> {
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(CoordsRejection(format!(
+                "lat must be between -90 and 90 (got {})",
+                lat
+            )));
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(CoordsRejection(format!(
+                "lon must be between -180 and 180 (got {})",
+                lon
+            )));
+        }
+        Ok(Self {
+            lat: round_coord(lat),
+            lon: round_coord(lon),
+        })
+    }
+
+    /// "lat,lon" as the geocoding layer accepts it, also usable as a cache key
+    pub fn as_query(&self) -> String {
+        make_location_key(self.lat, self.lon)
+    }
+
+    /// "geo:lat,lon", which services use as is rather than reverse geocoding
+    pub fn as_location(&self) -> String {
+        geo_location(self.lat, self.lon)
+    }
+
+    /// Validate an optional coordinate pair, as sent in a JSON body. `None`
+    /// when neither is given; one without the other is rejected.
+    pub fn from_pair(lat: Option<f64>, lon: Option<f64>) -> Result<Option<Self>, CoordsRejection> {
+        match (lat, lon) {
+            (Some(lat), Some(lon)) => Self::new(lat, lon).map(Some),
+            (None, None) => Ok(None),
+            _ => Err(CoordsRejection(
+                "lat and lon must be provided together".to_string(),
+            )),
         }
+    }
+
+    fn from_query(query: CoordsQuery) -> Result<Option<Self>, CoordsRejection> {
+        let parse = |name: &str, value: Option<String>| -> Result<Option<f64>, CoordsRejection> {
+            value
+                .map(|value| {
+                    value.trim().parse().map_err(|_| {
+                        CoordsRejection(format!("{} must be a number (got {:?})", name, value))
+                    })
+                })
+                .transpose()
+        };
+        Self::from_pair(parse("lat", query.lat)?, parse("lon", query.lon)?)
+    }
+}
+
+impl<S> axum::extract::OptionalFromRequestParts<S> for Coords
+where
+    S: Send + Sync,
+{
+    type Rejection = CoordsRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        let Query(query) = Query::<CoordsQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| CoordsRejection(e.body_text()))?;
+        Coords::from_query(query)
+    }
+}
+
+impl<S> FromRequestParts<S> for Coords
+where
+    S: Send + Sync,
+{
+    type Rejection = CoordsRejection;
 
-        Ok(UnitsParam(None))
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        <Coords as axum::extract::OptionalFromRequestParts<S>>::from_request_parts(parts, state)
+            .await?
+            .ok_or_else(|| CoordsRejection("lat and lon are required".to_string()))
     }
 }
 
-/// Rejection type for city parameter extraction failures
+/// Rejection for missing, malformed, or out-of-range coordinates
 #[derive(Debug)]
-pub struct CityParamRejection(pub String);
+pub struct CoordsRejection(pub String);
+
+impl std::fmt::Display for CoordsRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CoordsRejection {}
 
-impl IntoResponse for CityParamRejection {
+impl IntoResponse for CoordsRejection {
     fn into_response(self) -> Response {
-        (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(self.0))).into_response()
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::with_code(self.0, "INVALID_COORDINATES")),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(lat: Option<&str>, lon: Option<&str>) -> Result<Option<Coords>, CoordsRejection> {
+        Coords::from_query(CoordsQuery {
+            lat: lat.map(str::to_string),
+            lon: lon.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_coords_from_query() {
+        let coords = query(Some("41.8781"), Some(" -87.6298")).unwrap().unwrap();
+        assert_eq!(
+            coords,
+            Coords {
+                lat: 41.88,
+                lon: -87.63
+            }
+        );
+        assert_eq!(coords.as_query(), "41.88,-87.63");
+        assert_eq!(
+            crate::geocode::models::parse_geo_location(&coords.as_location()),
+            Some((41.88, -87.63))
+        );
+        assert!(crate::geocode::models::parse_geo_location("41.88,-87.63").is_none());
+
+        assert!(query(None, None).unwrap().is_none());
+        assert!(query(Some("41.88"), None).is_err());
+        assert!(query(Some("north"), Some("-87.63")).is_err());
+        assert!(query(Some("91"), Some("0")).is_err());
+        assert!(query(Some("0"), Some("-180.5")).is_err());
+        assert!(query(Some("NaN"), Some("0")).is_err());
+
+        assert_eq!(
+            Coords::from_pair(Some(41.8781), Some(-87.6298)).unwrap(),
+            Some(coords)
+        );
+        assert!(Coords::from_pair(None, None).unwrap().is_none());
+        assert!(Coords::from_pair(None, Some(-87.63)).is_err());
+        assert!(Coords::from_pair(Some(-90.5), Some(0.0)).is_err());
+    }
+
+    async fn city_param(uri: &str) -> Result<Option<String>, CoordsRejection> {
+        let (mut parts, _) = axum::http::Request::builder()
+            .uri(uri)
+            .body(())
+            .unwrap()
+            .into_parts();
+        CityParam::from_request_parts(&mut parts, &())
+            .await
+            .map(CityParam::into_inner)
+    }
+
+    #[tokio::test]
+    async fn test_city_param_accepts_coordinates() {
+        assert_eq!(
+            city_param("/forecast?lat=41.8781&lon=-87.6298")
+                .await
+                .unwrap(),
+            Some("geo:41.88,-87.63".to_string())
+        );
+        assert_eq!(
+            city_param("/forecast?city=London&lat=41.88&lon=-87.63")
+                .await
+                .unwrap(),
+            Some("London".to_string())
+        );
+        assert_eq!(city_param("/forecast?units=metric").await.unwrap(), None);
+        assert!(city_param("/forecast?lat=95&lon=0").await.is_err());
     }
 }
@@ -0,0 +1,95 @@
+use dashmap::DashMap;
+
+use super::provider::{ForecastParts, ProviderKind};
+
+/// Most forecasts whose demand is tracked; new ones are ignored past this
+/// until decay frees room
+const MAX_TRACKED: usize = 1_000;
+
+/// Scores below this are forgotten
+const MIN_SCORE: f64 = 0.05;
+
+/// Enough of a forecast request to repeat it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForecastRequest {
+    pub parts: ForecastParts,
+    pub city: String,
+    pub units: String,
+    pub lang: String,
+    pub provider: ProviderKind,
+}
+
+/// How often each cached forecast has been asked for lately. Scores decay,
+/// so they follow current demand rather than all-time totals.
+#[derive(Default)]
+pub struct DemandTracker {
+    scores: DashMap<String, (ForecastRequest, f64)>,
+}
+
+impl DemandTracker {
+    /// Count a request for the forecast cached under `cache_key`
+    pub fn record(&self, cache_key: &str, request: impl FnOnce() -> ForecastRequest) {
+        if let Some(mut entry) = self.scores.get_mut(cache_key) {
+            entry.1 += 1.0;
+        } else if self.scores.len() < MAX_TRACKED {
+            self.scores.insert(cache_key.to_string(), (request(), 1.0));
+        }
+    }
+
+    /// Scale every score by `factor`, forgetting forecasts nobody asks for
+    pub fn decay(&self, factor: f64) {
+        self.scores.retain(|_, (_, score)| {
+            *score *= factor;
+            *score >= MIN_SCORE
+        });
+    }
+
+    /// The `limit` most requested forecasts, busiest first
+    pub fn hottest(&self, limit: usize) -> Vec<(String, ForecastRequest)> {
+        let mut entries: Vec<(f64, String, ForecastRequest)> = self
+            .scores
+            .iter()
+            .map(|e| (e.1, e.key().clone(), e.0.clone()))
+            .collect();
+        entries.sort_by(|a, b| b.0.total_cmp(&a.0));
+        entries
+            .into_iter()
+            .take(limit)
+            .map(|(_, key, request)| (key, request))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(city: &str) -> ForecastRequest {
+        ForecastRequest {
+            parts: ForecastParts::Full,
+            city: city.to_string(),
+            units: "metric".to_string(),
+            lang: "en".to_string(),
+            provider: ProviderKind::OpenWeatherMap,
+        }
+    }
+
+    #[test]
+    fn test_hottest_follows_decayed_demand() {
+        let tracker = DemandTracker::default();
+        for _ in 0..3 {
+            tracker.record("full_chicago", || request("Chicago"));
+        }
+        tracker.record("full_london", || request("London"));
+        tracker.record("full_paris", || request("Paris"));
+        tracker.record("full_paris", || request("Paris"));
+
+        let hottest: Vec<String> = tracker.hottest(2).into_iter().map(|(k, _)| k).collect();
+        assert_eq!(hottest, vec!["full_chicago", "full_paris"]);
+        assert_eq!(tracker.hottest(1)[0].1, request("Chicago"));
+
+        // London decays to 0.04 and is forgotten; the others survive
+        tracker.decay(0.04);
+        assert_eq!(tracker.hottest(10).len(), 2);
+    }
+}
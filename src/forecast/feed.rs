@@ -0,0 +1,186 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::models::ForecastResponse;
+
+/// Render active alerts and the daily forecast for a location as an Atom feed
+///
+/// Entry IDs are stable per alert and per forecast day, so feed readers only
+/// show each one once; `updated` changes when the underlying data does.
+pub fn render_atom(forecast: &ForecastResponse, units: &str, self_href: &str, now: i64) -> String {
+    let location = &forecast.location;
+    let place = match location.state {
+        Some(ref state) => format!("{}, {}, {}", location.city, state, location.country),
+        None => format!("{}, {}", location.city, location.country),
+    };
+    let feed_id = format!("urn:weathrs:feed:{:.2},{:.2}", location.lat, location.lon);
+    let updated = forecast
+        .current
+        .as_ref()
+        .map(|c| c.timestamp)
+        .or_else(|| forecast.daily.first().map(|d| d.timestamp))
+        .unwrap_or(now);
+    let temp_unit = match units {
+        "imperial" => "°F",
+        "standard" => "K",
+        _ => "°C",
+    };
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    let _ = writeln!(xml, "  <title>Weather for {}</title>", escape(&place));
+    let _ = writeln!(xml, "  <id>{}</id>", feed_id);
+    let _ = writeln!(xml, "  <updated>{}</updated>", rfc3339(updated));
+    let _ = writeln!(
+        xml,
+        "  <link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>",
+        escape(self_href)
+    );
+    xml.push_str("  <author><name>Weathrs</name></author>\n");
+    xml.push_str("  <generator>Weathrs</generator>\n");
+
+    for alert in forecast.alerts.iter().filter(|a| a.end == 0 || a.end > now) {
+        let title = format!("{}: {}", alert.event, place);
+        let mut summary = alert.description.trim().to_string();
+        if let Some(ref instruction) = alert.instruction {
+            summary.push_str("\n\n");
+            summary.push_str(instruction.trim());
+        }
+        let entry_id = format!("{}:alert:{}:{}", feed_id, alert.start, slug(&alert.event));
+        write_entry(
+            &mut xml,
+            &entry_id,
+            &title,
+            alert.start.max(0),
+            &alert.sender,
+            &summary,
+        );
+    }
+
+    for day in &forecast.daily {
+        let date = DateTime::<Utc>::from_timestamp(day.timestamp, 0)
+            .map(|dt| dt.format("%a %b %-d").to_string())
+            .unwrap_or_default();
+        let title = format!(
+            "{}: {}, {:.0}{} / {:.0}{}",
+            date, day.description, day.temp_max, temp_unit, day.temp_min, temp_unit
+        );
+        let mut summary = day
+            .summary
+            .clone()
+            .unwrap_or_else(|| day.description.clone());
+        let _ = write!(
+            summary,
+            "\nHigh {:.0}{}, low {:.0}{}. Precipitation {:.0}%. Humidity {}%.",
+            day.temp_max,
+            temp_unit,
+            day.temp_min,
+            temp_unit,
+            day.precipitation_probability * 100.0,
+            day.humidity
+        );
+        let entry_id = format!("{}:daily:{}", feed_id, day.timestamp);
+        write_entry(&mut xml, &entry_id, &title, updated, "Weathrs", &summary);
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn write_entry(xml: &mut String, id: &str, title: &str, updated: i64, author: &str, summary: &str) {
+    xml.push_str("  <entry>\n");
+    let _ = writeln!(xml, "    <id>{}</id>", escape(id));
+    let _ = writeln!(xml, "    <title>{}</title>", escape(title));
+    let _ = writeln!(xml, "    <updated>{}</updated>", rfc3339(updated));
+    let _ = writeln!(xml, "    <author><name>{}</name></author>", escape(author));
+    let _ = writeln!(xml, "    <summary>{}</summary>", escape(summary));
+    xml.push_str("  </entry>\n");
+}
+
+fn rfc3339(ts: i64) -> String {
+    DateTime::<Utc>::from_timestamp(ts, 0)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn slug(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forecast::models::{AlertResponse, LocationInfo};
+
+    fn create_test_forecast() -> ForecastResponse {
+        ForecastResponse {
+            location: LocationInfo {
+                city: "Chicago".to_string(),
+                country: "US".to_string(),
+                state: Some("Illinois".to_string()),
+                lat: 41.8781,
+                lon: -87.6298,
+            },
+            timezone: "America/Chicago".to_string(),
+            current: None,
+            hourly: vec![],
+            daily: vec![],
+            alerts: vec![AlertResponse::new(
+                "NWS Chicago".to_string(),
+                "Heat Advisory".to_string(),
+                1700000000,
+                1700100000,
+                "Heat index <105> & humid".to_string(),
+                None,
+            )],
+        }
+    }
+
+    #[test]
+    fn test_render_atom_escapes_and_includes_alerts() {
+        let xml = render_atom(
+            &create_test_forecast(),
+            "imperial",
+            "/api/v1/feeds/Chicago.atom",
+            1700050000,
+        );
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<title>Weather for Chicago, Illinois, US</title>"));
+        assert!(xml.contains("<id>urn:weathrs:feed:41.88,-87.63</id>"));
+        assert!(xml.contains("<title>Heat Advisory: Chicago, Illinois, US</title>"));
+        assert!(xml.contains("Heat index &lt;105&gt; &amp; humid"));
+        assert!(xml.contains("<updated>2023-11-14T22:13:20Z</updated>"));
+        assert!(xml.trim_end().ends_with("</feed>"));
+    }
+
+    #[test]
+    fn test_render_atom_skips_expired_alerts() {
+        let xml = render_atom(&create_test_forecast(), "metric", "/feed", 1700200000);
+        assert!(!xml.contains("Heat Advisory"));
+    }
+}
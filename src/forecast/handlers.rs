@@ -1,12 +1,19 @@
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     http::{header, HeaderMap, HeaderValue},
     Json,
 };
 
-use super::models::{ForecastResponse, WidgetResponse};
-use super::service::ForecastError;
-use crate::extractors::{CityParam, UnitsParam};
+use super::feed::render_atom;
+use super::models::{
+    AlertsResponse, ForecastResponse, LocalTimeQuery, SunDetailQuery, WidgetResponse,
+};
+use super::provider::{ForecastParts, ProviderQuery};
+use super::service::{ForecastError, DEFAULT_LANG};
+use crate::display::DisplayQuery;
+use crate::error::ErrorResponse;
+use crate::extractors::{CityParam, DisplayParam, ProviderParam, UnitsParam};
+use crate::units::Units;
 use crate::AppState;
 
 /// Get full forecast (current + 48h hourly + 8 day daily)
@@ -14,16 +21,54 @@ use crate::AppState;
 /// Accepts city from either path parameter or query parameter:
 /// - GET /forecast?city=London&units=metric
 /// - GET /forecast/{city}?units=metric
+/// - GET /forecast?lat=41.88&lon=-87.63&units=metric
+#[utoipa::path(
+    get,
+    path = "/api/v1/forecast/{city}",
+    tag = "forecast",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code"), ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)"), LocalTimeQuery, SunDetailQuery, DisplayQuery, ProviderQuery),
+    responses(
+        (status = 200, description = "Current, hourly, and daily forecast", body = ForecastResponse),
+        (status = 400, description = "Invalid units, display field, or provider", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
 pub async fn get_forecast(
     State(state): State<AppState>,
     CityParam(city): CityParam,
     UnitsParam(units): UnitsParam,
-) -> Result<Json<ForecastResponse>, ForecastError> {
-    let city = city.unwrap_or_else(|| state.config.default_city.clone());
-    let units = units.unwrap_or_else(|| state.config.units.clone());
+    Query(local): Query<LocalTimeQuery>,
+    Query(sun): Query<SunDetailQuery>,
+    DisplayParam(display): DisplayParam,
+    ProviderParam(provider): ProviderParam,
+) -> Result<Json<serde_json::Value>, ForecastError> {
+    let city = city.unwrap_or_else(|| state.config.default_location());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
 
-    let forecast = state.forecast_service.get_forecast(&city, &units).await?;
-    Ok(Json(forecast))
+    let mut forecast = state
+        .forecast_service
+        .fetch(
+            ForecastParts::Full,
+            &city,
+            units.as_str(),
+            DEFAULT_LANG,
+            provider,
+        )
+        .await?;
+    if local.localtime == Some(true) {
+        forecast.localize();
+    }
+    if sun.sun_detail == Some(true) {
+        forecast.add_sun_detail();
+    }
+    let mut body = serde_json::to_value(&forecast).unwrap_or_default();
+    display
+        .as_ref()
+        .unwrap_or(&state.config.display)
+        .filter_forecast(&mut body);
+    Ok(Json(body))
 }
 
 /// Get daily forecast only (8 days)
@@ -31,19 +76,54 @@ pub async fn get_forecast(
 /// Accepts city from either path parameter or query parameter:
 /// - GET /forecast/daily?city=London&units=metric
 /// - GET /forecast/daily/{city}?units=metric
+/// - GET /forecast/daily?lat=41.88&lon=-87.63&units=metric
+#[utoipa::path(
+    get,
+    path = "/api/v1/forecast/daily/{city}",
+    tag = "forecast",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code"), ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)"), LocalTimeQuery, SunDetailQuery, DisplayQuery, ProviderQuery),
+    responses(
+        (status = 200, description = "Daily forecast", body = ForecastResponse),
+        (status = 400, description = "Invalid units, display field, or provider", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
 pub async fn get_daily_forecast(
     State(state): State<AppState>,
     CityParam(city): CityParam,
     UnitsParam(units): UnitsParam,
-) -> Result<Json<ForecastResponse>, ForecastError> {
-    let city = city.unwrap_or_else(|| state.config.default_city.clone());
-    let units = units.unwrap_or_else(|| state.config.units.clone());
+    Query(local): Query<LocalTimeQuery>,
+    Query(sun): Query<SunDetailQuery>,
+    DisplayParam(display): DisplayParam,
+    ProviderParam(provider): ProviderParam,
+) -> Result<Json<serde_json::Value>, ForecastError> {
+    let city = city.unwrap_or_else(|| state.config.default_location());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
 
-    let forecast = state
+    let mut forecast = state
         .forecast_service
-        .get_daily_forecast(&city, &units)
+        .fetch(
+            ForecastParts::Daily,
+            &city,
+            units.as_str(),
+            DEFAULT_LANG,
+            provider,
+        )
         .await?;
-    Ok(Json(forecast))
+    if local.localtime == Some(true) {
+        forecast.localize();
+    }
+    if sun.sun_detail == Some(true) {
+        forecast.add_sun_detail();
+    }
+    let mut body = serde_json::to_value(&forecast).unwrap_or_default();
+    display
+        .as_ref()
+        .unwrap_or(&state.config.display)
+        .filter_forecast(&mut body);
+    Ok(Json(body))
 }
 
 /// Get hourly forecast only (48 hours)
@@ -51,19 +131,129 @@ pub async fn get_daily_forecast(
 /// Accepts city from either path parameter or query parameter:
 /// - GET /forecast/hourly?city=London&units=metric
 /// - GET /forecast/hourly/{city}?units=metric
+/// - GET /forecast/hourly?lat=41.88&lon=-87.63&units=metric
+#[utoipa::path(
+    get,
+    path = "/api/v1/forecast/hourly/{city}",
+    tag = "forecast",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code"), ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)"), LocalTimeQuery, DisplayQuery, ProviderQuery),
+    responses(
+        (status = 200, description = "Hourly forecast", body = ForecastResponse),
+        (status = 400, description = "Invalid units, display field, or provider", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
 pub async fn get_hourly_forecast(
     State(state): State<AppState>,
     CityParam(city): CityParam,
     UnitsParam(units): UnitsParam,
-) -> Result<Json<ForecastResponse>, ForecastError> {
-    let city = city.unwrap_or_else(|| state.config.default_city.clone());
-    let units = units.unwrap_or_else(|| state.config.units.clone());
+    Query(local): Query<LocalTimeQuery>,
+    DisplayParam(display): DisplayParam,
+    ProviderParam(provider): ProviderParam,
+) -> Result<Json<serde_json::Value>, ForecastError> {
+    let city = city.unwrap_or_else(|| state.config.default_location());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
+
+    let mut forecast = state
+        .forecast_service
+        .fetch(
+            ForecastParts::Hourly,
+            &city,
+            units.as_str(),
+            DEFAULT_LANG,
+            provider,
+        )
+        .await?;
+    if local.localtime == Some(true) {
+        forecast.localize();
+    }
+    let mut body = serde_json::to_value(&forecast).unwrap_or_default();
+    display
+        .as_ref()
+        .unwrap_or(&state.config.display)
+        .filter_forecast(&mut body);
+    Ok(Json(body))
+}
+
+/// Get only the active government alerts for a location
+///
+/// Accepts city from either path parameter or query parameter:
+/// - GET /alerts?city=London
+/// - GET /alerts/{city}
+/// - GET /alerts?lat=41.88&lon=-87.63
+#[utoipa::path(
+    get,
+    path = "/api/v1/alerts/{city}",
+    tag = "forecast",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code")),
+    responses(
+        (status = 200, description = "Active government alerts", body = AlertsResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
+pub async fn get_alerts(
+    State(state): State<AppState>,
+    CityParam(city): CityParam,
+) -> Result<Json<AlertsResponse>, ForecastError> {
+    let city = city.unwrap_or_else(|| state.config.default_location());
+
+    let alerts = state.forecast_service.get_alerts(&city).await?;
+    Ok(Json(alerts))
+}
+
+/// Atom feed of active alerts and the daily forecast, for feed readers and smart displays
+///
+/// - GET /feeds/{city}.atom?units=metric
+#[utoipa::path(
+    get,
+    path = "/api/v1/feeds/{file}",
+    tag = "forecast",
+    params(("file" = String, Path, description = "City followed by .atom, e.g. Chicago.atom"), ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)")),
+    responses(
+        (status = 200, description = "Atom feed", content_type = "application/atom+xml", body = String),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
+pub async fn get_atom_feed(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    UnitsParam(units): UnitsParam,
+) -> Result<(HeaderMap, String), ForecastError> {
+    let city = file
+        .strip_suffix(".atom")
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| ForecastError::CityNotFound(file.clone()))?;
+    let units = units.unwrap_or_else(|| state.default_units(city));
 
     let forecast = state
         .forecast_service
-        .get_hourly_forecast(&city, &units)
+        .get_daily_forecast(city, units.as_str())
         .await?;
-    Ok(Json(forecast))
+    let self_href = format!("/api/v1/feeds/{}", file);
+    let xml = render_atom(
+        &forecast,
+        units.as_str(),
+        &self_href,
+        chrono::Utc::now().timestamp(),
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/atom+xml; charset=utf-8"),
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=900"),
+    );
+
+    Ok((headers, xml))
 }
 
 /// Get minimal widget data for home screen widgets
@@ -72,15 +262,30 @@ pub async fn get_hourly_forecast(
 /// Includes Cache-Control header for aggressive caching (5 minutes).
 ///
 /// - GET /widget/{city}?units=metric
+#[utoipa::path(
+    get,
+    path = "/api/v1/widget/{city}",
+    tag = "widget",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code"), ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)")),
+    responses(
+        (status = 200, description = "Widget summary", body = WidgetResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
 pub async fn get_widget(
     State(state): State<AppState>,
     CityParam(city): CityParam,
     UnitsParam(units): UnitsParam,
 ) -> Result<(HeaderMap, Json<WidgetResponse>), ForecastError> {
-    let city = city.unwrap_or_else(|| state.config.default_city.clone());
-    let units = units.unwrap_or_else(|| state.config.units.clone());
+    let city = city.unwrap_or_else(|| state.config.default_location());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
 
-    let forecast = state.forecast_service.get_forecast(&city, &units).await?;
+    let forecast = state
+        .forecast_service
+        .get_forecast(&city, units.as_str())
+        .await?;
 
     let current = forecast.current.as_ref().ok_or_else(|| {
         ForecastError::InvalidResponse("No current weather data available".to_string())
@@ -97,7 +302,7 @@ pub async fn get_widget(
         low: today.temp_min,
         icon: current.icon.clone(),
         description: current.description.clone(),
-        units,
+        units: units.to_string(),
         updated_at: current.timestamp,
     };
 
@@ -1,5 +1,15 @@
+mod demand;
+mod feed;
 pub mod handlers;
 pub mod models;
+mod nws;
+mod open_meteo;
+mod provider;
 mod service;
+mod sun;
+mod weatherapi;
 
-pub use service::ForecastService;
+pub use nws::NwsClient;
+pub use provider::{ForecastParts, ProviderKind, ProviderQuery, UnknownProvider};
+pub use service::{ForecastError, ForecastService, ForecastTtls, DEFAULT_LANG};
+pub use weatherapi::WeatherApi;
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 // ============================================================================
 // Geocoding API Response
@@ -177,18 +177,203 @@ pub struct WeatherAlert {
 // API Response Models (External - what we return to clients)
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ForecastResponse {
     pub location: LocationInfo,
     pub timezone: String,
     pub current: Option<CurrentWeatherResponse>,
     pub hourly: Vec<HourlyForecastResponse>,
     pub daily: Vec<DailyForecastResponse>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub alerts: Vec<AlertResponse>,
 }
 
-#[derive(Debug, Clone, Serialize, ToSchema)]
+/// The fields responses take from an entry's first weather condition
+struct FirstCondition {
+    description: String,
+    icon: String,
+    id: Option<u32>,
+    main: Option<String>,
+}
+
+impl FirstCondition {
+    /// Move the first condition's strings out of `weather`; empty when OWM
+    /// sent none
+    fn take(weather: Vec<WeatherCondition>) -> Self {
+        match weather.into_iter().next() {
+            Some(w) => Self {
+                description: w.description,
+                icon: w.icon,
+                id: Some(w.id),
+                main: Some(w.main),
+            },
+            None => Self {
+                description: String::new(),
+                icon: String::new(),
+                id: None,
+                main: None,
+            },
+        }
+    }
+}
+
+impl ForecastResponse {
+    /// Build the client response from a One Call result. This runs on every
+    /// uncached request for 48 hourly and 8 daily entries, so strings are
+    /// moved out of `data` rather than cloned.
+    pub fn from_one_call(data: OneCallResponse, location: GeoLocation) -> Self {
+        Self {
+            location: location.into(),
+            timezone: data.timezone,
+            current: data.current.map(|c| {
+                let condition = FirstCondition::take(c.weather);
+                CurrentWeatherResponse {
+                    timestamp: c.dt,
+                    temperature: c.temp,
+                    feels_like: c.feels_like,
+                    humidity: c.humidity,
+                    pressure: c.pressure,
+                    uv_index: c.uvi,
+                    clouds: c.clouds,
+                    visibility: c.visibility,
+                    wind_speed: c.wind_speed,
+                    wind_direction: c.wind_deg,
+                    wind_gust: c.wind_gust,
+                    description: condition.description,
+                    icon: condition.icon,
+                    condition_id: condition.id,
+                    condition_main: condition.main,
+                    local_time: None,
+                    sunrise_local: None,
+                    sunset_local: None,
+                    sunrise: c.sunrise,
+                    sunset: c.sunset,
+                }
+            }),
+            hourly: data
+                .hourly
+                .unwrap_or_default()
+                .into_iter()
+                .map(|h| {
+                    let condition = FirstCondition::take(h.weather);
+                    HourlyForecastResponse {
+                        timestamp: h.dt,
+                        temperature: h.temp,
+                        feels_like: h.feels_like,
+                        humidity: h.humidity,
+                        pressure: h.pressure,
+                        uv_index: h.uvi,
+                        clouds: h.clouds,
+                        wind_speed: h.wind_speed,
+                        wind_direction: h.wind_deg,
+                        wind_gust: h.wind_gust,
+                        precipitation_probability: h.pop,
+                        rain_volume: h.rain.and_then(|r| r.one_hour),
+                        snow_volume: h.snow.and_then(|s| s.one_hour),
+                        is_day: condition.id.map(|_| condition.icon.ends_with('d')),
+                        description: condition.description,
+                        icon: condition.icon,
+                        condition_id: condition.id,
+                        condition_main: condition.main,
+                        local_time: None,
+                    }
+                })
+                .collect(),
+            daily: data
+                .daily
+                .unwrap_or_default()
+                .into_iter()
+                .map(|d| {
+                    let condition = FirstCondition::take(d.weather);
+                    DailyForecastResponse {
+                        timestamp: d.dt,
+                        sunrise: d.sunrise,
+                        sunset: d.sunset,
+                        moonrise: (d.moonrise > 0).then_some(d.moonrise),
+                        moonset: (d.moonset > 0).then_some(d.moonset),
+                        moon_phase: d.moon_phase,
+                        summary: d.summary,
+                        temp_min: d.temp.min,
+                        temp_max: d.temp.max,
+                        temp_day: d.temp.day,
+                        temp_night: d.temp.night,
+                        temp_morning: d.temp.morn,
+                        temp_evening: d.temp.eve,
+                        feels_like_day: d.feels_like.day,
+                        feels_like_night: d.feels_like.night,
+                        humidity: d.humidity,
+                        pressure: d.pressure,
+                        uv_index: d.uvi,
+                        clouds: d.clouds,
+                        wind_speed: d.wind_speed,
+                        wind_direction: d.wind_deg,
+                        wind_gust: d.wind_gust,
+                        precipitation_probability: d.pop,
+                        rain_volume: d.rain,
+                        snow_volume: d.snow,
+                        description: condition.description,
+                        icon: condition.icon,
+                        condition_id: condition.id,
+                        condition_main: condition.main,
+                        local_time: None,
+                        sunrise_local: None,
+                        sunset_local: None,
+                        sun_detail: None,
+                    }
+                })
+                .collect(),
+            alerts: data
+                .alerts
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| {
+                    AlertResponse::new(
+                        a.sender_name,
+                        a.event,
+                        a.start,
+                        a.end,
+                        a.description,
+                        a.tags,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Fill in each entry's local-time strings from `timezone`. Entries are
+    /// left alone when the timezone is unknown.
+    pub fn localize(&mut self) {
+        let Ok(tz) = self.timezone.parse::<chrono_tz::Tz>() else {
+            return;
+        };
+        let local = |ts: i64| {
+            chrono::DateTime::from_timestamp(ts, 0).map(|t| t.with_timezone(&tz).to_rfc3339())
+        };
+        if let Some(ref mut current) = self.current {
+            current.local_time = local(current.timestamp);
+            current.sunrise_local = current.sunrise.and_then(local);
+            current.sunset_local = current.sunset.and_then(local);
+        }
+        for hour in &mut self.hourly {
+            hour.local_time = local(hour.timestamp);
+        }
+        for day in &mut self.daily {
+            day.local_time = local(day.timestamp);
+            day.sunrise_local = local(day.sunrise);
+            day.sunset_local = local(day.sunset);
+        }
+    }
+}
+
+/// `?localtime=true` adds ISO 8601 local-time strings next to timestamps
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LocalTimeQuery {
+    /// Include local-time strings computed from the response timezone
+    pub localtime: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LocationInfo {
     pub city: String,
     pub country: String,
@@ -198,7 +383,19 @@ pub struct LocationInfo {
     pub lon: f64,
 }
 
-#[derive(Debug, Clone, Serialize, ToSchema)]
+impl From<GeoLocation> for LocationInfo {
+    fn from(location: GeoLocation) -> Self {
+        Self {
+            city: location.name,
+            country: location.country,
+            state: location.state,
+            lat: location.lat,
+            lon: location.lon,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CurrentWeatherResponse {
     pub timestamp: i64,
     pub temperature: f64,
@@ -214,13 +411,27 @@ pub struct CurrentWeatherResponse {
     pub wind_gust: Option<f64>,
     pub description: String,
     pub icon: String,
+    /// OWM condition code (e.g. 501 for moderate rain), the same in every
+    /// language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition_id: Option<u32>,
+    /// OWM condition group ("Rain", "Clouds", ...), never localized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition_main: Option<String>,
+    /// `timestamp` in the location's timezone (ISO 8601), with `?localtime=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunrise_local: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset_local: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sunrise: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sunset: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HourlyForecastResponse {
     pub timestamp: i64,
     pub temperature: f64,
@@ -231,6 +442,8 @@ pub struct HourlyForecastResponse {
     pub clouds: u32,
     pub wind_speed: f64,
     pub wind_direction: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wind_gust: Option<f64>,
     pub precipitation_probability: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rain_volume: Option<f64>,
@@ -238,13 +451,32 @@ pub struct HourlyForecastResponse {
     pub snow_volume: Option<f64>,
     pub description: String,
     pub icon: String,
+    /// OWM condition code (e.g. 501 for moderate rain), the same in every
+    /// language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition_id: Option<u32>,
+    /// OWM condition group ("Rain", "Clouds", ...), never localized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition_main: Option<String>,
+    /// `timestamp` in the location's timezone (ISO 8601), with `?localtime=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_time: Option<String>,
+    /// Whether the sun is up, as OWM's day/night icon variant reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_day: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DailyForecastResponse {
     pub timestamp: i64,
     pub sunrise: i64,
     pub sunset: i64,
+    /// Absent on days the moon doesn't rise or set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moonrise: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moonset: Option<i64>,
+    /// 0 and 1 are new moon, 0.25 first quarter, 0.5 full, 0.75 last quarter
     pub moon_phase: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
@@ -262,6 +494,8 @@ pub struct DailyForecastResponse {
     pub clouds: u32,
     pub wind_speed: f64,
     pub wind_direction: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wind_gust: Option<f64>,
     pub precipitation_probability: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rain_volume: Option<f64>,
@@ -269,6 +503,55 @@ pub struct DailyForecastResponse {
     pub snow_volume: Option<f64>,
     pub description: String,
     pub icon: String,
+    /// OWM condition code (e.g. 501 for moderate rain), the same in every
+    /// language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition_id: Option<u32>,
+    /// OWM condition group ("Rain", "Clouds", ...), never localized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition_main: Option<String>,
+    /// `timestamp` in the location's timezone (ISO 8601), with `?localtime=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunrise_local: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset_local: Option<String>,
+    /// Golden and blue hours, with `?sun_detail=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sun_detail: Option<SunDetail>,
+}
+
+/// `?sun_detail=true` adds golden and blue hours to each day
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SunDetailQuery {
+    /// Include golden and blue hours computed from sunrise and sunset
+    pub sun_detail: Option<bool>,
+}
+
+/// A stretch of time, as unix timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SunPeriod {
+    pub start: i64,
+    pub end: i64,
+}
+
+/// Light for photography over one day. Golden hour is the sun between 6°
+/// above and 4° below the horizon, blue hour between 4° and 6° below.
+/// Periods the sun doesn't pass through that day (near the poles) are absent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SunDetail {
+    /// Midway between sunrise and sunset
+    pub solar_noon: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blue_hour_morning: Option<SunPeriod>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub golden_hour_morning: Option<SunPeriod>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub golden_hour_evening: Option<SunPeriod>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blue_hour_evening: Option<SunPeriod>,
 }
 
 /// Minimal response optimized for home screen widgets
@@ -284,7 +567,7 @@ pub struct WidgetResponse {
     pub updated_at: i64,
 }
 
-#[derive(Debug, Clone, Serialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AlertResponse {
     pub sender: String,
     pub event: String,
@@ -293,4 +576,536 @@ pub struct AlertResponse {
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+    pub severity: AlertSeverity,
+    pub urgency: AlertUrgency,
+    pub certainty: AlertCertainty,
+    /// Recommended action (NWS alerts only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+    /// GeoJSON geometry of the affected area (NWS alerts only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub geometry: Option<serde_json::Value>,
+}
+
+/// CAP-style severity, lowest to highest
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    #[default]
+    Unknown,
+    Minor,
+    Moderate,
+    Severe,
+    Extreme,
+}
+
+impl AlertSeverity {
+    /// Whether this severity passes a minimum. Unknown severities always do,
+    /// so unfamiliar alert phrasing is never silently filtered out.
+    pub fn meets(self, min: AlertSeverity) -> bool {
+        self == AlertSeverity::Unknown || self >= min
+    }
+}
+
+/// CAP-style urgency: how soon action should be taken
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertUrgency {
+    Immediate,
+    Expected,
+    Future,
+    #[default]
+    Unknown,
+}
+
+/// CAP-style certainty: how likely the event is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertCertainty {
+    Observed,
+    Likely,
+    Possible,
+    #[default]
+    Unknown,
+}
+
+/// Infer severity, urgency and certainty from an alert's event name.
+///
+/// OWM relays alerts as free text, so this maps common phrasing: MeteoAlarm
+/// colour levels and leading adjectives ("Orange Warning", "Moderate
+/// thunderstorm warning") and NWS product types ("Tornado Warning",
+/// "Winter Storm Watch", "Wind Advisory", "Special Weather Statement").
+pub fn classify_alert(event: &str) -> (AlertSeverity, AlertUrgency, AlertCertainty) {
+    let event = event.to_lowercase();
+    let words: Vec<&str> = event
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let has = |w: &str| words.contains(&w);
+
+    let (product_severity, urgency, certainty) = if has("emergency") {
+        (
+            AlertSeverity::Extreme,
+            AlertUrgency::Immediate,
+            AlertCertainty::Observed,
+        )
+    } else if has("warning") {
+        (
+            AlertSeverity::Severe,
+            AlertUrgency::Immediate,
+            AlertCertainty::Likely,
+        )
+    } else if has("watch") {
+        (
+            AlertSeverity::Severe,
+            AlertUrgency::Future,
+            AlertCertainty::Possible,
+        )
+    } else if has("advisory") {
+        (
+            AlertSeverity::Moderate,
+            AlertUrgency::Expected,
+            AlertCertainty::Likely,
+        )
+    } else if has("statement") {
+        (
+            AlertSeverity::Minor,
+            AlertUrgency::Expected,
+            AlertCertainty::Likely,
+        )
+    } else if has("outlook") {
+        (
+            AlertSeverity::Minor,
+            AlertUrgency::Future,
+            AlertCertainty::Possible,
+        )
+    } else {
+        (
+            AlertSeverity::Unknown,
+            AlertUrgency::Unknown,
+            AlertCertainty::Unknown,
+        )
+    };
+
+    // Explicit colour or adjective beats the product type
+    let severity = if has("red") || has("extreme") {
+        AlertSeverity::Extreme
+    } else if has("orange") || has("severe") {
+        AlertSeverity::Severe
+    } else if has("yellow") || has("moderate") {
+        AlertSeverity::Moderate
+    } else if has("green") || has("minor") {
+        AlertSeverity::Minor
+    } else {
+        product_severity
+    };
+
+    (severity, urgency, certainty)
+}
+
+/// Alert tier inferred from the event name, lowest to highest
+/// (e.g. "Special Weather Statement" < "Wind Advisory" < "Tornado Watch" < "Tornado Warning")
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertLevel {
+    #[default]
+    Statement,
+    Advisory,
+    Watch,
+    Warning,
+}
+
+impl AlertLevel {
+    /// Classify an alert event name. Unrecognized phrasing is treated as a
+    /// warning so unfamiliar alert types are never silently filtered out.
+    pub fn from_event(event: &str) -> Self {
+        let event = event.to_lowercase();
+        if event.contains("warning") || event.contains("emergency") {
+            Self::Warning
+        } else if event.contains("watch") {
+            Self::Watch
+        } else if event.contains("advisory") {
+            Self::Advisory
+        } else if event.contains("statement") || event.contains("outlook") {
+            Self::Statement
+        } else {
+            Self::Warning
+        }
+    }
+}
+
+impl AlertResponse {
+    /// Build an alert, classifying severity, urgency and certainty from the event name
+    pub fn new(
+        sender: String,
+        event: String,
+        start: i64,
+        end: i64,
+        description: String,
+        tags: Option<Vec<String>>,
+    ) -> Self {
+        let (severity, urgency, certainty) = classify_alert(&event);
+        Self {
+            sender,
+            event,
+            start,
+            end,
+            description,
+            tags,
+            severity,
+            urgency,
+            certainty,
+            instruction: None,
+            geometry: None,
+        }
+    }
+
+    pub fn level(&self) -> AlertLevel {
+        AlertLevel::from_event(&self.event)
+    }
+
+    /// Whether a point lies inside the alert's area. None when the alert has
+    /// no usable Polygon or MultiPolygon geometry.
+    pub fn covers(&self, lat: f64, lon: f64) -> Option<bool> {
+        let geometry = self.geometry.as_ref()?;
+        let coordinates = geometry.get("coordinates")?;
+        let polygons: Vec<&serde_json::Value> = match geometry.get("type")?.as_str()? {
+            "Polygon" => vec![coordinates],
+            "MultiPolygon" => coordinates.as_array()?.iter().collect(),
+            _ => return None,
+        };
+
+        let mut usable = false;
+        for polygon in polygons {
+            let rings: Vec<Vec<(f64, f64)>> =
+                polygon.as_array()?.iter().filter_map(parse_ring).collect();
+            let Some((outer, holes)) = rings.split_first() else {
+                continue;
+            };
+            usable = true;
+            if ring_contains(outer, lat, lon) && !holes.iter().any(|h| ring_contains(h, lat, lon)) {
+                return Some(true);
+            }
+        }
+        usable.then_some(false)
+    }
+}
+
+/// A GeoJSON linear ring as (lon, lat) pairs
+fn parse_ring(ring: &serde_json::Value) -> Option<Vec<(f64, f64)>> {
+    let points: Vec<(f64, f64)> = ring
+        .as_array()?
+        .iter()
+        .filter_map(|p| Some((p.get(0)?.as_f64()?, p.get(1)?.as_f64()?)))
+        .collect();
+    (points.len() >= 3).then_some(points)
+}
+
+/// Ray casting point-in-polygon test
+fn ring_contains(ring: &[(f64, f64)], lat: f64, lon: f64) -> bool {
+    let mut inside = false;
+    let mut j = ring.len() - 1;
+    for i in 0..ring.len() {
+        let (xi, yi) = ring[i];
+        let (xj, yj) = ring[j];
+        if (yi > lat) != (yj > lat) && lon < (xj - xi) * (lat - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Active government alerts for a location, without the forecast payload
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AlertsResponse {
+    pub location: LocationInfo,
+    pub timezone: String,
+    pub alerts: Vec<ActiveAlert>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ActiveAlert {
+    /// Id for acknowledging the alert with POST /alerts/{id}/ack
+    pub id: String,
+    pub event: String,
+    pub sender: String,
+    pub level: AlertLevel,
+    pub severity: AlertSeverity,
+    pub urgency: AlertUrgency,
+    pub certainty: AlertCertainty,
+    /// Unix timestamp the alert takes effect
+    pub effective: i64,
+    /// Unix timestamp the alert expires
+    pub expires: i64,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+    /// GeoJSON geometry of the affected area, for drawing on a map
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub geometry: Option<serde_json::Value>,
+}
+
+impl From<AlertResponse> for ActiveAlert {
+    fn from(a: AlertResponse) -> Self {
+        Self {
+            id: String::new(),
+            level: a.level(),
+            severity: a.severity,
+            urgency: a.urgency,
+            certainty: a.certainty,
+            event: a.event,
+            sender: a.sender,
+            effective: a.start,
+            expires: a.end,
+            description: a.description,
+            tags: a.tags,
+            instruction: a.instruction,
+            geometry: a.geometry,
+        }
+    }
+}
+
+impl AlertsResponse {
+    /// Keep only alerts that haven't expired as of `now` (unix seconds)
+    pub fn from_forecast(forecast: ForecastResponse, now: i64) -> Self {
+        Self {
+            location: forecast.location,
+            timezone: forecast.timezone,
+            alerts: forecast
+                .alerts
+                .into_iter()
+                .filter(|a| a.end > now)
+                .map(ActiveAlert::from)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localize() {
+        let mut forecast: ForecastResponse = serde_json::from_value(serde_json::json!({
+            "location": { "city": "Chicago", "country": "US", "lat": 41.88, "lon": -87.63 },
+            "timezone": "America/Chicago",
+            "current": null,
+            "hourly": [{
+                "timestamp": 1719838800, "temperature": 24.0, "feels_like": 24.0,
+                "humidity": 60, "pressure": 1012, "uv_index": 0.0, "clouds": 0,
+                "wind_speed": 2.0, "wind_direction": 180, "precipitation_probability": 0.0,
+                "description": "clear sky", "icon": "01n", "is_day": false
+            }],
+            "daily": []
+        }))
+        .unwrap();
+        let json = serde_json::to_value(&forecast).unwrap();
+        assert!(json["hourly"][0].get("local_time").is_none());
+
+        forecast.localize();
+        assert_eq!(
+            forecast.hourly[0].local_time.as_deref(),
+            Some("2024-07-01T08:00:00-05:00")
+        );
+        assert_eq!(forecast.hourly[0].is_day, Some(false));
+
+        forecast.timezone = "Not/AZone".to_string();
+        forecast.hourly[0].local_time = None;
+        forecast.localize();
+        assert!(forecast.hourly[0].local_time.is_none());
+    }
+
+    #[test]
+    fn test_classify_alert_nws_products() {
+        assert_eq!(
+            classify_alert("Tornado Warning"),
+            (
+                AlertSeverity::Severe,
+                AlertUrgency::Immediate,
+                AlertCertainty::Likely
+            )
+        );
+        assert_eq!(
+            classify_alert("Winter Storm Watch"),
+            (
+                AlertSeverity::Severe,
+                AlertUrgency::Future,
+                AlertCertainty::Possible
+            )
+        );
+        assert_eq!(classify_alert("Wind Advisory").0, AlertSeverity::Moderate);
+        assert_eq!(
+            classify_alert("Special Weather Statement").0,
+            AlertSeverity::Minor
+        );
+        assert_eq!(
+            classify_alert("Extreme Heat Warning").0,
+            AlertSeverity::Extreme
+        );
+    }
+
+    #[test]
+    fn test_classify_alert_meteoalarm_phrasing() {
+        assert_eq!(
+            classify_alert("Yellow Warning for Wind").0,
+            AlertSeverity::Moderate
+        );
+        assert_eq!(
+            classify_alert("Orange thunderstorm warning").0,
+            AlertSeverity::Severe
+        );
+        assert_eq!(
+            classify_alert("Moderate thunderstorm warning").0,
+            AlertSeverity::Moderate
+        );
+        // "red" must match as a word, not inside "reduced"
+        assert_eq!(
+            classify_alert("Reduced visibility").0,
+            AlertSeverity::Unknown
+        );
+    }
+
+    #[test]
+    fn test_alert_covers() {
+        let mut alert = AlertResponse::new(
+            "NWS Chicago IL".to_string(),
+            "Tornado Warning".to_string(),
+            0,
+            0,
+            String::new(),
+            None,
+        );
+        assert_eq!(alert.covers(41.88, -87.63), None);
+
+        alert.geometry = Some(serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [[[-88.0, 41.5], [-87.5, 41.5], [-87.5, 42.0], [-88.0, 42.0], [-88.0, 41.5]]]
+        }));
+        assert_eq!(alert.covers(41.88, -87.63), Some(true));
+        assert_eq!(alert.covers(42.3, -87.63), Some(false));
+        assert_eq!(alert.covers(41.88, -87.2), Some(false));
+    }
+
+    #[test]
+    fn test_unknown_severity_always_meets_minimum() {
+        assert!(AlertSeverity::Unknown.meets(AlertSeverity::Extreme));
+        assert!(AlertSeverity::Severe.meets(AlertSeverity::Moderate));
+        assert!(!AlertSeverity::Minor.meets(AlertSeverity::Moderate));
+    }
+
+    fn create_test_location() -> GeoLocation {
+        GeoLocation {
+            name: "Chicago".to_string(),
+            lat: 41.8781,
+            lon: -87.6298,
+            country: "US".to_string(),
+            state: Some("Illinois".to_string()),
+        }
+    }
+
+    fn create_minimal_one_call_response() -> OneCallResponse {
+        OneCallResponse {
+            lat: 41.8781,
+            lon: -87.6298,
+            timezone: "America/Chicago".to_string(),
+            timezone_offset: -18000,
+            current: None,
+            minutely: None,
+            hourly: None,
+            daily: None,
+            alerts: None,
+        }
+    }
+
+    #[test]
+    fn test_from_one_call_minimal() {
+        let data = create_minimal_one_call_response();
+        let location = create_test_location();
+        let result = ForecastResponse::from_one_call(data, location);
+
+        assert_eq!(result.location.city, "Chicago");
+        assert_eq!(result.location.country, "US");
+        assert_eq!(result.timezone, "America/Chicago");
+        assert!(result.current.is_none());
+        assert!(result.hourly.is_empty());
+        assert!(result.daily.is_empty());
+        assert!(result.alerts.is_empty());
+    }
+
+    #[test]
+    fn test_from_one_call_with_current_weather() {
+        let mut data = create_minimal_one_call_response();
+        data.current = Some(CurrentWeather {
+            dt: 1700000000,
+            sunrise: Some(1699980000),
+            sunset: Some(1700020000),
+            temp: 20.5,
+            feels_like: 19.0,
+            pressure: 1013,
+            humidity: 65,
+            dew_point: 14.0,
+            uvi: 3.5,
+            clouds: 40,
+            visibility: Some(10000),
+            wind_speed: 5.5,
+            wind_deg: 180,
+            wind_gust: Some(8.0),
+            weather: vec![WeatherCondition {
+                id: 800,
+                main: "Clear".to_string(),
+                description: "clear sky".to_string(),
+                icon: "01d".to_string(),
+            }],
+        });
+
+        let location = create_test_location();
+        let result = ForecastResponse::from_one_call(data, location);
+
+        let current = result.current.expect("Current weather should be present");
+        assert_eq!(current.temperature, 20.5);
+        assert_eq!(current.feels_like, 19.0);
+        assert_eq!(current.humidity, 65);
+        assert_eq!(current.description, "clear sky");
+        assert_eq!(current.icon, "01d");
+        assert_eq!(current.condition_id, Some(800));
+        assert_eq!(current.condition_main.as_deref(), Some("Clear"));
+    }
+
+    #[test]
+    fn test_from_one_call_with_alerts() {
+        let mut data = create_minimal_one_call_response();
+        data.alerts = Some(vec![WeatherAlert {
+            sender_name: "NWS Chicago".to_string(),
+            event: "Heat Advisory".to_string(),
+            start: 1700000000,
+            end: 1700100000,
+            description: "Excessive heat expected".to_string(),
+            tags: Some(vec!["Extreme temperature".to_string()]),
+        }]);
+
+        let location = create_test_location();
+        let result = ForecastResponse::from_one_call(data, location);
+
+        assert_eq!(result.alerts.len(), 1);
+        assert_eq!(result.alerts[0].event, "Heat Advisory");
+        assert_eq!(result.alerts[0].sender, "NWS Chicago");
+
+        let end = result.alerts[0].end;
+        let active = AlertsResponse::from_forecast(result.clone(), end - 1);
+        assert_eq!(active.alerts.len(), 1);
+        assert_eq!(active.alerts[0].level, AlertLevel::Advisory);
+        assert_eq!(active.alerts[0].severity, AlertSeverity::Moderate);
+        assert_eq!(active.alerts[0].expires, end);
+        assert!(AlertsResponse::from_forecast(result, end).alerts.is_empty());
+    }
 }
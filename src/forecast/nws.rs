@@ -0,0 +1,615 @@
+use async_trait::async_trait;
+use chrono::DateTime;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::models::{
+    classify_alert, AlertCertainty, AlertResponse, AlertSeverity, AlertUrgency,
+    CurrentWeatherResponse, DailyForecastResponse, ForecastResponse, GeoLocation,
+    HourlyForecastResponse,
+};
+use super::provider::{owm_icon, ForecastParts, ProviderKind, WeatherProvider};
+use super::service::ForecastError;
+use crate::units::Units;
+use crate::upstream::UpstreamFailure;
+
+const NWS_ALERTS_URL: &str = "https://api.weather.gov/alerts/active";
+const NWS_POINTS_URL: &str = "https://api.weather.gov/points";
+
+/// Client for the National Weather Service CAP alert feed (US only)
+///
+/// api.weather.gov rejects requests without an identifying User-Agent.
+#[derive(Clone)]
+pub struct NwsClient {
+    client: Client,
+    user_agent: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwsAlertCollection {
+    #[serde(default)]
+    features: Vec<NwsAlertFeature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwsAlertFeature {
+    #[serde(default)]
+    geometry: Option<serde_json::Value>,
+    properties: NwsAlertProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NwsAlertProperties {
+    event: String,
+    #[serde(default)]
+    sender_name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    instruction: Option<String>,
+    #[serde(default)]
+    severity: Option<String>,
+    #[serde(default)]
+    urgency: Option<String>,
+    #[serde(default)]
+    certainty: Option<String>,
+    #[serde(default)]
+    effective: Option<String>,
+    #[serde(default)]
+    onset: Option<String>,
+    #[serde(default)]
+    expires: Option<String>,
+    #[serde(default)]
+    ends: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwsPoint {
+    properties: NwsPointProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NwsPointProperties {
+    forecast: String,
+    forecast_hourly: String,
+    time_zone: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwsForecast {
+    properties: NwsForecastProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwsForecastProperties {
+    #[serde(default)]
+    periods: Vec<NwsPeriod>,
+}
+
+/// A forecast period: an hour, or a day or night
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NwsPeriod {
+    start_time: String,
+    is_daytime: bool,
+    temperature: f64,
+    #[serde(default)]
+    temperature_unit: Option<String>,
+    #[serde(default)]
+    probability_of_precipitation: Option<NwsValue>,
+    #[serde(default)]
+    relative_humidity: Option<NwsValue>,
+    /// "10 mph" or "5 to 10 mph"
+    #[serde(default)]
+    wind_speed: Option<String>,
+    /// Compass point ("SW")
+    #[serde(default)]
+    wind_direction: Option<String>,
+    #[serde(default)]
+    short_forecast: String,
+    #[serde(default)]
+    detailed_forecast: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwsValue {
+    value: Option<f64>,
+}
+
+impl NwsClient {
+    pub fn new(client: Client, user_agent: &str) -> Self {
+        Self {
+            client,
+            user_agent: user_agent.to_string(),
+        }
+    }
+
+    /// Active alerts whose area contains the given point
+    pub async fn active_alerts(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<Vec<AlertResponse>, ForecastError> {
+        metrics::counter!(crate::metrics::NWS_API_CALLS).increment(1);
+        let response = self
+            .client
+            .get(NWS_ALERTS_URL)
+            .header(reqwest::header::USER_AGENT, &self.user_agent)
+            .header(reqwest::header::ACCEPT, "application/geo+json")
+            .query(&[("point", format!("{:.4},{:.4}", lat, lon))])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(UpstreamFailure::read("NWS alerts", response).await.into());
+        }
+
+        let collection: NwsAlertCollection = response.json().await?;
+        Ok(parse_alerts(collection))
+    }
+
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        context: &'static str,
+    ) -> Result<T, ForecastError> {
+        metrics::counter!(crate::metrics::NWS_API_CALLS).increment(1);
+        let response = self
+            .client
+            .get(url)
+            .header(reqwest::header::USER_AGENT, &self.user_agent)
+            .header(reqwest::header::ACCEPT, "application/geo+json")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(UpstreamFailure::read(context, response).await.into());
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// NWS gridpoint forecasts. The daily forecast comes as day and night
+/// periods, which are paired into days; NWS has no pressure, UV, cloud,
+/// or sun times, so those are zero.
+#[async_trait]
+impl WeatherProvider for NwsClient {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Nws
+    }
+
+    async fn forecast(
+        &self,
+        location: GeoLocation,
+        parts: ForecastParts,
+        units: Units,
+        _lang: &str,
+    ) -> Result<ForecastResponse, ForecastError> {
+        if location.country != "US" {
+            return Err(ForecastError::ProviderUnavailable(format!(
+                "nws only covers US locations ({} is in {})",
+                location.name, location.country
+            )));
+        }
+
+        let alerts = self.active_alerts(location.lat, location.lon).await?;
+        if parts == ForecastParts::Alerts {
+            return Ok(ForecastResponse {
+                location: location.into(),
+                timezone: "UTC".to_string(),
+                current: None,
+                hourly: Vec::new(),
+                daily: Vec::new(),
+                alerts,
+            });
+        }
+
+        let url = format!("{}/{:.4},{:.4}", NWS_POINTS_URL, location.lat, location.lon);
+        let point: NwsPoint = self.get_json(&url, "NWS points").await?;
+        let point = point.properties;
+        // The hourly forecast also supplies current conditions
+        let hourly: NwsForecast = self
+            .get_json(&point.forecast_hourly, "NWS hourly forecast")
+            .await?;
+        let daily = if parts == ForecastParts::Hourly {
+            Vec::new()
+        } else {
+            let daily: NwsForecast = self.get_json(&point.forecast, "NWS forecast").await?;
+            daily.properties.periods
+        };
+
+        let mut forecast = to_response(hourly.properties.periods, daily, location, units);
+        forecast.timezone = point.time_zone;
+        forecast.alerts = alerts;
+        Ok(forecast)
+    }
+}
+
+fn to_response(
+    hourly: Vec<NwsPeriod>,
+    daily: Vec<NwsPeriod>,
+    location: GeoLocation,
+    units: Units,
+) -> ForecastResponse {
+    let hourly: Vec<HourlyForecastResponse> = hourly
+        .into_iter()
+        .take(48)
+        .map(|p| {
+            let (id, main, icon) = condition(&p.short_forecast);
+            HourlyForecastResponse {
+                timestamp: parse_timestamp(&p.start_time),
+                temperature: temperature(&p, units),
+                feels_like: temperature(&p, units),
+                humidity: value(&p.relative_humidity).round() as u32,
+                pressure: 0,
+                uv_index: 0.0,
+                clouds: 0,
+                wind_speed: wind_speed(&p, units),
+                wind_direction: compass_degrees(p.wind_direction.as_deref()),
+                wind_gust: None,
+                precipitation_probability: value(&p.probability_of_precipitation) / 100.0,
+                rain_volume: None,
+                snow_volume: None,
+                description: p.short_forecast.to_lowercase(),
+                icon: owm_icon(icon, p.is_daytime),
+                condition_id: Some(id),
+                condition_main: Some(main.to_string()),
+                local_time: None,
+                is_day: Some(p.is_daytime),
+            }
+        })
+        .collect();
+
+    let current = hourly.first().map(|h| CurrentWeatherResponse {
+        timestamp: h.timestamp,
+        temperature: h.temperature,
+        feels_like: h.feels_like,
+        humidity: h.humidity,
+        pressure: 0,
+        uv_index: 0.0,
+        clouds: 0,
+        visibility: None,
+        wind_speed: h.wind_speed,
+        wind_direction: h.wind_direction,
+        wind_gust: None,
+        description: h.description.clone(),
+        icon: h.icon.clone(),
+        condition_id: h.condition_id,
+        condition_main: h.condition_main.clone(),
+        local_time: None,
+        sunrise_local: None,
+        sunset_local: None,
+        sunrise: None,
+        sunset: None,
+    });
+
+    // Pair each day period with the night after it. The first period is
+    // "Tonight" when the forecast is fetched in the evening.
+    let mut days: Vec<(Option<NwsPeriod>, Option<NwsPeriod>)> = Vec::new();
+    for period in daily {
+        match days.last_mut() {
+            Some((Some(_), night @ None)) if !period.is_daytime => *night = Some(period),
+            _ if period.is_daytime => days.push((Some(period), None)),
+            _ => days.push((None, Some(period))),
+        }
+    }
+    let daily = days
+        .into_iter()
+        .filter_map(|(day, night)| {
+            let main_period = day.as_ref().or(night.as_ref())?;
+            let high = temperature(main_period, units);
+            let low = night.as_ref().map_or(high, |n| temperature(n, units));
+            let (id, main, icon) = condition(&main_period.short_forecast);
+            let pop = [day.as_ref(), night.as_ref()]
+                .into_iter()
+                .flatten()
+                .map(|p| value(&p.probability_of_precipitation))
+                .fold(0.0, f64::max);
+            Some(DailyForecastResponse {
+                timestamp: parse_timestamp(&main_period.start_time),
+                sunrise: 0,
+                sunset: 0,
+                moonrise: None,
+                moonset: None,
+                moon_phase: 0.0,
+                summary: main_period.detailed_forecast.clone(),
+                temp_min: low,
+                temp_max: high,
+                temp_day: high,
+                temp_night: low,
+                temp_morning: (high + low) / 2.0,
+                temp_evening: (high + low) / 2.0,
+                feels_like_day: high,
+                feels_like_night: low,
+                humidity: value(&main_period.relative_humidity).round() as u32,
+                pressure: 0,
+                uv_index: 0.0,
+                clouds: 0,
+                wind_speed: wind_speed(main_period, units),
+                wind_direction: compass_degrees(main_period.wind_direction.as_deref()),
+                wind_gust: None,
+                precipitation_probability: pop / 100.0,
+                rain_volume: None,
+                snow_volume: None,
+                description: main_period.short_forecast.to_lowercase(),
+                icon: owm_icon(icon, day.is_some()),
+                condition_id: Some(id),
+                condition_main: Some(main.to_string()),
+                local_time: None,
+                sunrise_local: None,
+                sunset_local: None,
+                sun_detail: None,
+            })
+        })
+        .collect();
+
+    ForecastResponse {
+        location: location.into(),
+        timezone: "UTC".to_string(),
+        current,
+        hourly,
+        daily,
+        alerts: Vec::new(),
+    }
+}
+
+fn value(v: &Option<NwsValue>) -> f64 {
+    v.as_ref().and_then(|v| v.value).unwrap_or_default()
+}
+
+/// A period's temperature in `units`. NWS reports °F unless asked otherwise.
+fn temperature(period: &NwsPeriod, units: Units) -> f64 {
+    let celsius = match period.temperature_unit.as_deref() {
+        Some("C") => period.temperature,
+        _ => (period.temperature - 32.0) * 5.0 / 9.0,
+    };
+    units.temperature_from_celsius(celsius)
+}
+
+/// The top of a "5 to 10 mph" wind speed, in `units`
+fn wind_speed(period: &NwsPeriod, units: Units) -> f64 {
+    let mph = period
+        .wind_speed
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|w| w.parse::<f64>().ok())
+        .fold(0.0, f64::max);
+    units.speed_from_ms(mph * 0.44704)
+}
+
+fn compass_degrees(direction: Option<&str>) -> u32 {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+    direction
+        .and_then(|d| POINTS.iter().position(|p| *p == d))
+        .map_or(0, |i| (i as f64 * 22.5).round() as u32)
+}
+
+/// OWM condition id, group, and icon base for an NWS short forecast
+/// ("Chance Rain Showers", "Mostly Sunny")
+fn condition(short_forecast: &str) -> (u32, &'static str, &'static str) {
+    let text = short_forecast.to_lowercase();
+    if text.contains("thunder") {
+        (211, "Thunderstorm", "11")
+    } else if text.contains("freezing") || text.contains("sleet") {
+        (511, "Rain", "13")
+    } else if text.contains("snow") || text.contains("flurries") {
+        (601, "Snow", "13")
+    } else if text.contains("drizzle") {
+        (300, "Drizzle", "09")
+    } else if text.contains("showers") {
+        (521, "Rain", "09")
+    } else if text.contains("rain") {
+        (500, "Rain", "10")
+    } else if text.contains("fog") || text.contains("haze") || text.contains("smoke") {
+        (741, "Fog", "50")
+    } else if text.contains("partly") {
+        (802, "Clouds", "03")
+    } else if text.contains("mostly cloudy") || text.contains("overcast") {
+        (803, "Clouds", "04")
+    } else if text.contains("cloudy") {
+        (804, "Clouds", "04")
+    } else if text.contains("mostly") {
+        (801, "Clouds", "02")
+    } else {
+        (800, "Clear", "01")
+    }
+}
+
+fn parse_alerts(collection: NwsAlertCollection) -> Vec<AlertResponse> {
+    collection
+        .features
+        .into_iter()
+        .map(|feature| {
+            let p = feature.properties;
+            let (severity, urgency, certainty) = classify_alert(&p.event);
+            let start = p.onset.or(p.effective);
+            let end = p.ends.or(p.expires);
+
+            AlertResponse {
+                sender: p.sender_name.unwrap_or_else(|| "NWS".to_string()),
+                severity: p
+                    .severity
+                    .as_deref()
+                    .and_then(cap_severity)
+                    .unwrap_or(severity),
+                urgency: p
+                    .urgency
+                    .as_deref()
+                    .and_then(cap_urgency)
+                    .unwrap_or(urgency),
+                certainty: p
+                    .certainty
+                    .as_deref()
+                    .and_then(cap_certainty)
+                    .unwrap_or(certainty),
+                event: p.event,
+                start: start.as_deref().map(parse_timestamp).unwrap_or(0),
+                end: end.as_deref().map(parse_timestamp).unwrap_or(0),
+                description: p.description.unwrap_or_default(),
+                tags: None,
+                instruction: p.instruction,
+                geometry: feature.geometry.filter(|g| !g.is_null()),
+            }
+        })
+        .collect()
+}
+
+fn parse_timestamp(s: &str) -> i64 {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.timestamp())
+        .unwrap_or(0)
+}
+
+fn cap_severity(s: &str) -> Option<AlertSeverity> {
+    match s {
+        "Extreme" => Some(AlertSeverity::Extreme),
+        "Severe" => Some(AlertSeverity::Severe),
+        "Moderate" => Some(AlertSeverity::Moderate),
+        "Minor" => Some(AlertSeverity::Minor),
+        _ => None,
+    }
+}
+
+fn cap_urgency(s: &str) -> Option<AlertUrgency> {
+    match s {
+        "Immediate" => Some(AlertUrgency::Immediate),
+        "Expected" => Some(AlertUrgency::Expected),
+        "Future" => Some(AlertUrgency::Future),
+        _ => None,
+    }
+}
+
+fn cap_certainty(s: &str) -> Option<AlertCertainty> {
+    match s {
+        "Observed" => Some(AlertCertainty::Observed),
+        "Likely" => Some(AlertCertainty::Likely),
+        "Possible" => Some(AlertCertainty::Possible),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_alerts() {
+        let collection: NwsAlertCollection = serde_json::from_value(serde_json::json!({
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[-87.7, 41.8], [-87.6, 41.8], [-87.6, 41.9], [-87.7, 41.8]]]
+                    },
+                    "properties": {
+                        "event": "Severe Thunderstorm Warning",
+                        "senderName": "NWS Chicago IL",
+                        "description": "At 512 PM CDT, a severe thunderstorm was located...",
+                        "instruction": "Move to an interior room on the lowest floor.",
+                        "severity": "Severe",
+                        "urgency": "Immediate",
+                        "certainty": "Observed",
+                        "effective": "2024-06-01T17:12:00-05:00",
+                        "onset": "2024-06-01T17:12:00-05:00",
+                        "expires": "2024-06-01T18:00:00-05:00",
+                        "ends": null
+                    }
+                },
+                {
+                    "geometry": null,
+                    "properties": {
+                        "event": "Heat Advisory",
+                        "severity": "Unknown",
+                        "effective": "2024-06-01T10:00:00-05:00",
+                        "expires": "2024-06-01T20:00:00-05:00"
+                    }
+                }
+            ]
+        }))
+        .unwrap();
+
+        let alerts = parse_alerts(collection);
+        assert_eq!(alerts.len(), 2);
+
+        let warning = &alerts[0];
+        assert_eq!(warning.sender, "NWS Chicago IL");
+        assert_eq!(warning.severity, AlertSeverity::Severe);
+        assert_eq!(warning.certainty, AlertCertainty::Observed);
+        assert_eq!(warning.start, 1717279920);
+        assert_eq!(warning.end, 1717282800);
+        assert!(warning.instruction.is_some());
+        assert!(warning.geometry.is_some());
+
+        // Unknown CAP severity falls back to phrasing
+        let advisory = &alerts[1];
+        assert_eq!(advisory.sender, "NWS");
+        assert_eq!(advisory.severity, AlertSeverity::Moderate);
+        assert!(advisory.geometry.is_none());
+    }
+
+    #[test]
+    fn test_to_response() {
+        let periods =
+            |value: serde_json::Value| -> Vec<NwsPeriod> { serde_json::from_value(value).unwrap() };
+        let hourly = periods(serde_json::json!([
+            {
+                "startTime": "2024-06-01T18:00:00-05:00", "isDaytime": true,
+                "temperature": 86, "temperatureUnit": "F",
+                "probabilityOfPrecipitation": {"value": 20},
+                "relativeHumidity": {"value": 55},
+                "windSpeed": "10 mph", "windDirection": "SW",
+                "shortForecast": "Chance Showers And Thunderstorms"
+            }
+        ]));
+        let daily = periods(serde_json::json!([
+            {
+                "startTime": "2024-06-01T18:00:00-05:00", "isDaytime": false,
+                "temperature": 68, "windSpeed": "5 mph", "shortForecast": "Mostly Clear"
+            },
+            {
+                "startTime": "2024-06-02T06:00:00-05:00", "isDaytime": true,
+                "temperature": 88, "windSpeed": "5 to 15 mph", "windDirection": "S",
+                "probabilityOfPrecipitation": {"value": 30},
+                "shortForecast": "Partly Sunny", "detailedForecast": "Partly sunny, with a high near 88."
+            },
+            {
+                "startTime": "2024-06-02T18:00:00-05:00", "isDaytime": false,
+                "temperature": 70, "probabilityOfPrecipitation": {"value": 60},
+                "shortForecast": "Rain Likely"
+            }
+        ]));
+        let location = GeoLocation {
+            name: "Chicago".to_string(),
+            lat: 41.88,
+            lon: -87.63,
+            country: "US".to_string(),
+            state: Some("Illinois".to_string()),
+        };
+        let forecast = to_response(hourly, daily, location, Units::Metric);
+
+        let current = forecast.current.unwrap();
+        assert_eq!(current.timestamp, 1717282800);
+        assert!((current.temperature - 30.0).abs() < 1e-9);
+        assert!((current.wind_speed - 4.4704).abs() < 1e-9);
+        assert_eq!(current.wind_direction, 225);
+        assert_eq!(current.condition_id, Some(211));
+        assert_eq!(forecast.hourly[0].precipitation_probability, 0.2);
+
+        // "Tonight" stands alone, then day and night are paired
+        assert_eq!(forecast.daily.len(), 2);
+        assert_eq!(forecast.daily[0].icon, "02n");
+        let tomorrow = &forecast.daily[1];
+        assert!((tomorrow.temp_max - 31.111).abs() < 1e-3);
+        assert!((tomorrow.temp_min - 21.111).abs() < 1e-3);
+        assert_eq!(tomorrow.precipitation_probability, 0.6);
+        assert_eq!(tomorrow.condition_id, Some(802));
+        assert!(tomorrow.summary.is_some());
+    }
+}
@@ -0,0 +1,400 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::models::{
+    CurrentWeatherResponse, DailyForecastResponse, ForecastResponse, GeoLocation,
+    HourlyForecastResponse,
+};
+use super::provider::{owm_icon, ForecastParts, ProviderKind, WeatherProvider};
+use super::service::ForecastError;
+use crate::units::Units;
+use crate::upstream::UpstreamFailure;
+
+const OPEN_METEO_API_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+const CURRENT_FIELDS: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,is_day,\
+weather_code,cloud_cover,pressure_msl,wind_speed_10m,wind_direction_10m,wind_gusts_10m,\
+visibility,uv_index";
+const HOURLY_FIELDS: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,\
+precipitation_probability,rain,snowfall,weather_code,pressure_msl,cloud_cover,wind_speed_10m,\
+wind_direction_10m,wind_gusts_10m,uv_index,is_day";
+const DAILY_FIELDS: &str = "weather_code,temperature_2m_max,temperature_2m_min,\
+apparent_temperature_max,apparent_temperature_min,sunrise,sunset,uv_index_max,rain_sum,\
+snowfall_sum,precipitation_probability_max,wind_speed_10m_max,wind_gusts_10m_max,\
+wind_direction_10m_dominant,relative_humidity_2m_mean,pressure_msl_mean,cloud_cover_mean";
+
+/// Open-Meteo forecasts. Values are requested in metric and converted, and
+/// descriptions come from the WMO weather code, in English.
+pub struct OpenMeteo {
+    client: Client,
+}
+
+impl OpenMeteo {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoResponse {
+    timezone: String,
+    current: Option<OpenMeteoCurrent>,
+    hourly: Option<OpenMeteoHourly>,
+    daily: Option<OpenMeteoDaily>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoCurrent {
+    time: i64,
+    temperature_2m: f64,
+    relative_humidity_2m: f64,
+    apparent_temperature: f64,
+    is_day: u8,
+    weather_code: u32,
+    cloud_cover: f64,
+    pressure_msl: f64,
+    wind_speed_10m: f64,
+    wind_direction_10m: f64,
+    wind_gusts_10m: Option<f64>,
+    visibility: Option<f64>,
+    uv_index: Option<f64>,
+}
+
+/// Hourly values, one array per field; any entry may be null
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct OpenMeteoHourly {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f64>>,
+    relative_humidity_2m: Vec<Option<f64>>,
+    apparent_temperature: Vec<Option<f64>>,
+    precipitation_probability: Vec<Option<f64>>,
+    rain: Vec<Option<f64>>,
+    /// cm
+    snowfall: Vec<Option<f64>>,
+    weather_code: Vec<Option<u32>>,
+    pressure_msl: Vec<Option<f64>>,
+    cloud_cover: Vec<Option<f64>>,
+    wind_speed_10m: Vec<Option<f64>>,
+    wind_direction_10m: Vec<Option<f64>>,
+    wind_gusts_10m: Vec<Option<f64>>,
+    uv_index: Vec<Option<f64>>,
+    is_day: Vec<Option<u8>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct OpenMeteoDaily {
+    time: Vec<i64>,
+    weather_code: Vec<Option<u32>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    apparent_temperature_max: Vec<Option<f64>>,
+    apparent_temperature_min: Vec<Option<f64>>,
+    sunrise: Vec<Option<i64>>,
+    sunset: Vec<Option<i64>>,
+    uv_index_max: Vec<Option<f64>>,
+    rain_sum: Vec<Option<f64>>,
+    /// cm
+    snowfall_sum: Vec<Option<f64>>,
+    precipitation_probability_max: Vec<Option<f64>>,
+    wind_speed_10m_max: Vec<Option<f64>>,
+    wind_gusts_10m_max: Vec<Option<f64>>,
+    wind_direction_10m_dominant: Vec<Option<f64>>,
+    relative_humidity_2m_mean: Vec<Option<f64>>,
+    pressure_msl_mean: Vec<Option<f64>>,
+    cloud_cover_mean: Vec<Option<f64>>,
+}
+
+/// Entry `i` of an Open-Meteo value array, if present and not null
+fn at<T: Copy>(values: &[Option<T>], i: usize) -> Option<T> {
+    values.get(i).copied().flatten()
+}
+
+/// OWM equivalents of a WMO weather code: condition id, group, description,
+/// and icon base
+fn wmo_condition(code: u32) -> (u32, &'static str, &'static str, &'static str) {
+    match code {
+        0 => (800, "Clear", "clear sky", "01"),
+        1 => (801, "Clouds", "mainly clear", "02"),
+        2 => (802, "Clouds", "partly cloudy", "03"),
+        3 => (804, "Clouds", "overcast", "04"),
+        45 | 48 => (741, "Fog", "fog", "50"),
+        51 => (300, "Drizzle", "light drizzle", "09"),
+        53 => (301, "Drizzle", "drizzle", "09"),
+        55 => (302, "Drizzle", "heavy drizzle", "09"),
+        56 | 57 => (311, "Drizzle", "freezing drizzle", "09"),
+        61 => (500, "Rain", "light rain", "10"),
+        63 => (501, "Rain", "moderate rain", "10"),
+        65 => (502, "Rain", "heavy rain", "10"),
+        66 | 67 => (511, "Rain", "freezing rain", "13"),
+        71 => (600, "Snow", "light snow", "13"),
+        73 | 77 => (601, "Snow", "snow", "13"),
+        75 => (602, "Snow", "heavy snow", "13"),
+        80 => (520, "Rain", "light rain showers", "09"),
+        81 => (521, "Rain", "rain showers", "09"),
+        82 => (522, "Rain", "heavy rain showers", "09"),
+        85 => (620, "Snow", "light snow showers", "13"),
+        86 => (621, "Snow", "snow showers", "13"),
+        95 => (211, "Thunderstorm", "thunderstorm", "11"),
+        96 | 99 => (202, "Thunderstorm", "thunderstorm with hail", "11"),
+        _ => (803, "Clouds", "cloudy", "04"),
+    }
+}
+
+/// Description, icon, condition id, and group for an optional WMO code
+fn condition(code: Option<u32>, is_day: bool) -> (String, String, Option<u32>, Option<String>) {
+    match code.map(wmo_condition) {
+        Some((id, main, description, icon)) => (
+            description.to_string(),
+            owm_icon(icon, is_day),
+            Some(id),
+            Some(main.to_string()),
+        ),
+        None => (String::new(), String::new(), None, None),
+    }
+}
+
+fn to_response(data: OpenMeteoResponse, location: GeoLocation, units: Units) -> ForecastResponse {
+    let temp = |c: f64| units.temperature_from_celsius(c);
+    let speed = |ms: f64| units.speed_from_ms(ms);
+
+    let current = data.current.map(|c| {
+        let (description, icon, condition_id, condition_main) =
+            condition(Some(c.weather_code), c.is_day == 1);
+        CurrentWeatherResponse {
+            timestamp: c.time,
+            temperature: temp(c.temperature_2m),
+            feels_like: temp(c.apparent_temperature),
+            humidity: c.relative_humidity_2m.round() as u32,
+            pressure: c.pressure_msl.round() as u32,
+            uv_index: c.uv_index.unwrap_or_default(),
+            clouds: c.cloud_cover.round() as u32,
+            visibility: c.visibility.map(|v| v.round() as u32),
+            wind_speed: speed(c.wind_speed_10m),
+            wind_direction: c.wind_direction_10m.round() as u32,
+            wind_gust: c.wind_gusts_10m.map(speed),
+            description,
+            icon,
+            condition_id,
+            condition_main,
+            local_time: None,
+            sunrise_local: None,
+            sunset_local: None,
+            sunrise: None,
+            sunset: None,
+        }
+    });
+
+    let h = data.hourly.unwrap_or_default();
+    let hourly = (0..h.time.len())
+        .map(|i| {
+            let is_day = at(&h.is_day, i).map(|d| d == 1);
+            let (description, icon, condition_id, condition_main) =
+                condition(at(&h.weather_code, i), is_day.unwrap_or(true));
+            HourlyForecastResponse {
+                timestamp: h.time[i],
+                temperature: temp(at(&h.temperature_2m, i).unwrap_or_default()),
+                feels_like: temp(at(&h.apparent_temperature, i).unwrap_or_default()),
+                humidity: at(&h.relative_humidity_2m, i).unwrap_or_default().round() as u32,
+                pressure: at(&h.pressure_msl, i).unwrap_or_default().round() as u32,
+                uv_index: at(&h.uv_index, i).unwrap_or_default(),
+                clouds: at(&h.cloud_cover, i).unwrap_or_default().round() as u32,
+                wind_speed: speed(at(&h.wind_speed_10m, i).unwrap_or_default()),
+                wind_direction: at(&h.wind_direction_10m, i).unwrap_or_default().round() as u32,
+                wind_gust: at(&h.wind_gusts_10m, i).map(speed),
+                precipitation_probability: at(&h.precipitation_probability, i).unwrap_or_default()
+                    / 100.0,
+                rain_volume: at(&h.rain, i).filter(|&r| r > 0.0),
+                snow_volume: at(&h.snowfall, i).filter(|&s| s > 0.0).map(|cm| cm * 10.0),
+                description,
+                icon,
+                condition_id,
+                condition_main,
+                local_time: None,
+                is_day,
+            }
+        })
+        .collect();
+
+    let d = data.daily.unwrap_or_default();
+    let daily = (0..d.time.len())
+        .map(|i| {
+            let (description, icon, condition_id, condition_main) =
+                condition(at(&d.weather_code, i), true);
+            let max = at(&d.temperature_2m_max, i).unwrap_or_default();
+            let min = at(&d.temperature_2m_min, i).unwrap_or_default();
+            DailyForecastResponse {
+                timestamp: d.time[i],
+                sunrise: at(&d.sunrise, i).unwrap_or_default(),
+                sunset: at(&d.sunset, i).unwrap_or_default(),
+                moonrise: None,
+                moonset: None,
+                moon_phase: 0.0,
+                summary: None,
+                temp_min: temp(min),
+                temp_max: temp(max),
+                temp_day: temp(max),
+                temp_night: temp(min),
+                temp_morning: temp((min + max) / 2.0),
+                temp_evening: temp((min + max) / 2.0),
+                feels_like_day: temp(at(&d.apparent_temperature_max, i).unwrap_or_default()),
+                feels_like_night: temp(at(&d.apparent_temperature_min, i).unwrap_or_default()),
+                humidity: at(&d.relative_humidity_2m_mean, i)
+                    .unwrap_or_default()
+                    .round() as u32,
+                pressure: at(&d.pressure_msl_mean, i).unwrap_or_default().round() as u32,
+                uv_index: at(&d.uv_index_max, i).unwrap_or_default(),
+                clouds: at(&d.cloud_cover_mean, i).unwrap_or_default().round() as u32,
+                wind_speed: speed(at(&d.wind_speed_10m_max, i).unwrap_or_default()),
+                wind_direction: at(&d.wind_direction_10m_dominant, i)
+                    .unwrap_or_default()
+                    .round() as u32,
+                wind_gust: at(&d.wind_gusts_10m_max, i).map(speed),
+                precipitation_probability: at(&d.precipitation_probability_max, i)
+                    .unwrap_or_default()
+                    / 100.0,
+                rain_volume: at(&d.rain_sum, i).filter(|&r| r > 0.0),
+                snow_volume: at(&d.snowfall_sum, i)
+                    .filter(|&s| s > 0.0)
+                    .map(|cm| cm * 10.0),
+                description,
+                icon,
+                condition_id,
+                condition_main,
+                local_time: None,
+                sunrise_local: None,
+                sunset_local: None,
+                sun_detail: None,
+            }
+        })
+        .collect();
+
+    ForecastResponse {
+        location: location.into(),
+        timezone: data.timezone,
+        current,
+        hourly,
+        daily,
+        alerts: Vec::new(),
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenMeteo {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::OpenMeteo
+    }
+
+    async fn forecast(
+        &self,
+        location: GeoLocation,
+        parts: ForecastParts,
+        units: Units,
+        _lang: &str,
+    ) -> Result<ForecastResponse, ForecastError> {
+        // Open-Meteo has no alerts
+        if parts == ForecastParts::Alerts {
+            return Ok(ForecastResponse {
+                location: location.into(),
+                timezone: "UTC".to_string(),
+                current: None,
+                hourly: Vec::new(),
+                daily: Vec::new(),
+                alerts: Vec::new(),
+            });
+        }
+
+        let mut query = vec![
+            ("latitude", location.lat.to_string()),
+            ("longitude", location.lon.to_string()),
+            ("current", CURRENT_FIELDS.to_string()),
+            ("timezone", "auto".to_string()),
+            ("timeformat", "unixtime".to_string()),
+            ("wind_speed_unit", "ms".to_string()),
+        ];
+        if parts != ForecastParts::Daily {
+            query.push(("hourly", HOURLY_FIELDS.to_string()));
+            query.push(("forecast_hours", "48".to_string()));
+        }
+        if parts != ForecastParts::Hourly {
+            query.push(("daily", DAILY_FIELDS.to_string()));
+            query.push(("forecast_days", "8".to_string()));
+        }
+
+        let response = self
+            .client
+            .get(OPEN_METEO_API_URL)
+            .query(&query)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(UpstreamFailure::read("Open-Meteo", response).await.into());
+        }
+        let data: OpenMeteoResponse = response.json().await?;
+        Ok(to_response(data, location, units))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_response() {
+        let data: OpenMeteoResponse = serde_json::from_value(serde_json::json!({
+            "timezone": "America/Chicago",
+            "current": {
+                "time": 1700000000, "temperature_2m": 20.0, "relative_humidity_2m": 64.6,
+                "apparent_temperature": 19.0, "is_day": 0, "weather_code": 61,
+                "cloud_cover": 90.0, "pressure_msl": 1012.8, "wind_speed_10m": 4.4704,
+                "wind_direction_10m": 180.0, "wind_gusts_10m": null
+            },
+            "hourly": {
+                "time": [1700000000, 1700003600],
+                "temperature_2m": [20.0, null],
+                "precipitation_probability": [40, 80],
+                "snowfall": [0.0, 1.2],
+                "weather_code": [61, 73],
+                "is_day": [0, 1]
+            },
+            "daily": {
+                "time": [1699941600],
+                "temperature_2m_max": [25.0],
+                "temperature_2m_min": [15.0],
+                "sunrise": [1699965000],
+                "weather_code": [3]
+            }
+        }))
+        .unwrap();
+        let location = GeoLocation {
+            name: "Chicago".to_string(),
+            lat: 41.88,
+            lon: -87.63,
+            country: "US".to_string(),
+            state: None,
+        };
+        let forecast = to_response(data, location, Units::Imperial);
+
+        let current = forecast.current.unwrap();
+        assert_eq!(current.temperature, 68.0);
+        assert!((current.wind_speed - 10.0).abs() < 1e-9);
+        assert_eq!(current.humidity, 65);
+        assert_eq!(current.description, "light rain");
+        assert_eq!(current.icon, "10n");
+        assert_eq!(current.condition_id, Some(500));
+
+        assert_eq!(forecast.hourly.len(), 2);
+        assert_eq!(forecast.hourly[0].precipitation_probability, 0.4);
+        assert_eq!(forecast.hourly[1].snow_volume, Some(12.0));
+        assert_eq!(forecast.hourly[1].is_day, Some(true));
+        assert_eq!(forecast.hourly[1].condition_main.as_deref(), Some("Snow"));
+
+        let today = &forecast.daily[0];
+        assert_eq!(today.temp_max, 77.0);
+        assert_eq!(today.temp_min, 59.0);
+        assert_eq!(today.sunrise, 1699965000);
+        assert_eq!(today.icon, "04d");
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
+
+use super::models::{ForecastResponse, GeoLocation, OneCallResponse};
+use super::service::ForecastError;
+use crate::api_budget::BudgetFeature;
+use crate::api_keys::ApiKeyPool;
+use crate::units::Units;
+use crate::upstream::UpstreamFailure;
+
+const ONE_CALL_API_URL: &str = "https://api.openweathermap.org/data/3.0/onecall";
+
+/// Where forecasts come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum ProviderKind {
+    /// OpenWeatherMap One Call 3.0 (needs an OWM key with a One Call subscription)
+    #[default]
+    #[serde(rename = "openweathermap")]
+    OpenWeatherMap,
+    /// Open-Meteo (no key)
+    #[serde(rename = "open-meteo")]
+    OpenMeteo,
+    /// WeatherAPI.com (needs `providers.weatherapi_key`)
+    #[serde(rename = "weatherapi")]
+    WeatherApi,
+    /// National Weather Service (US locations only)
+    #[serde(rename = "nws")]
+    Nws,
+}
+
+impl ProviderKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::OpenWeatherMap => "openweathermap",
+            Self::OpenMeteo => "open-meteo",
+            Self::WeatherApi => "weatherapi",
+            Self::Nws => "nws",
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("provider must be openweathermap, open-meteo, weatherapi, or nws (got {0:?})")]
+pub struct UnknownProvider(pub String);
+
+impl FromStr for ProviderKind {
+    type Err = UnknownProvider;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "openweathermap" | "owm" => Ok(Self::OpenWeatherMap),
+            "open-meteo" | "openmeteo" => Ok(Self::OpenMeteo),
+            "weatherapi" => Ok(Self::WeatherApi),
+            "nws" => Ok(Self::Nws),
+            _ => Err(UnknownProvider(s.to_string())),
+        }
+    }
+}
+
+/// Per-request choice of forecast provider
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProviderQuery {
+    /// openweathermap, open-meteo, weatherapi, or nws (default from config)
+    pub provider: Option<String>,
+}
+
+/// Which sections of a forecast a request needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForecastParts {
+    /// Current conditions, 48 hours, and 8 days
+    Full,
+    /// Current conditions and 8 days
+    Daily,
+    /// Current conditions and 48 hours
+    Hourly,
+    /// Alerts only
+    Alerts,
+}
+
+impl ForecastParts {
+    /// Drop the sections a provider returned that weren't asked for
+    pub fn trim(self, forecast: &mut ForecastResponse) {
+        match self {
+            Self::Full => {}
+            Self::Daily => forecast.hourly.clear(),
+            Self::Hourly => forecast.daily.clear(),
+            Self::Alerts => {
+                forecast.current = None;
+                forecast.hourly.clear();
+                forecast.daily.clear();
+            }
+        }
+    }
+}
+
+/// A weather data source, normalized into `ForecastResponse`. Sections a
+/// provider has no data for are left empty, and fields it doesn't report
+/// are zero.
+#[async_trait]
+pub trait WeatherProvider: Send + Sync {
+    fn kind(&self) -> ProviderKind;
+
+    /// Forecast for an already geocoded location. `lang` is a hint; providers
+    /// without translations answer in English.
+    async fn forecast(
+        &self,
+        location: GeoLocation,
+        parts: ForecastParts,
+        units: Units,
+        lang: &str,
+    ) -> Result<ForecastResponse, ForecastError>;
+}
+
+/// OpenWeatherMap One Call 3.0, through the shared key pool
+pub struct OpenWeatherMap {
+    client: Client,
+    api_keys: Arc<ApiKeyPool>,
+}
+
+impl OpenWeatherMap {
+    pub fn new(client: Client, api_keys: Arc<ApiKeyPool>) -> Self {
+        Self { client, api_keys }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenWeatherMap {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::OpenWeatherMap
+    }
+
+    async fn forecast(
+        &self,
+        location: GeoLocation,
+        parts: ForecastParts,
+        units: Units,
+        lang: &str,
+    ) -> Result<ForecastResponse, ForecastError> {
+        let (endpoint, exclude) = match parts {
+            ForecastParts::Full => ("onecall", "minutely"),
+            ForecastParts::Daily => ("onecall_daily", "minutely,hourly"),
+            ForecastParts::Hourly => ("onecall_hourly", "minutely,daily"),
+            ForecastParts::Alerts => ("onecall_alerts", "current,minutely,hourly,daily"),
+        };
+        metrics::counter!(crate::metrics::OWM_API_CALLS, "endpoint" => endpoint).increment(1);
+        let mut query = vec![
+            ("lat", location.lat.to_string()),
+            ("lon", location.lon.to_string()),
+            ("exclude", exclude.to_string()),
+        ];
+        if parts != ForecastParts::Alerts {
+            query.push(("units", units.to_string()));
+            query.push(("lang", lang.to_string()));
+        }
+        let response = self
+            .api_keys
+            .send_budgeted(
+                BudgetFeature::Forecast,
+                self.client.get(ONE_CALL_API_URL).query(&query),
+            )
+            .await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(ForecastError::SubscriptionRequired);
+        }
+        if !status.is_success() {
+            return Err(UpstreamFailure::read("One Call", response).await.into());
+        }
+
+        let data: OneCallResponse = response.json().await?;
+        Ok(ForecastResponse::from_one_call(data, location))
+    }
+}
+
+/// OWM-style icon code ("10d") for a base ("10") and time of day
+pub(super) fn owm_icon(base: &str, is_day: bool) -> String {
+    format!("{}{}", base, if is_day { 'd' } else { 'n' })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_provider() {
+        assert_eq!(
+            "Open-Meteo".parse::<ProviderKind>().unwrap(),
+            ProviderKind::OpenMeteo
+        );
+        assert_eq!(
+            "owm".parse::<ProviderKind>().unwrap(),
+            ProviderKind::OpenWeatherMap
+        );
+        assert!("accuweather".parse::<ProviderKind>().is_err());
+        for kind in [
+            ProviderKind::OpenWeatherMap,
+            ProviderKind::OpenMeteo,
+            ProviderKind::WeatherApi,
+            ProviderKind::Nws,
+        ] {
+            assert_eq!(kind.as_str().parse::<ProviderKind>().unwrap(), kind);
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+        }
+    }
+}
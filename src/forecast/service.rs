@@ -2,19 +2,40 @@ use axum::http::StatusCode;
 use reqwest::Client;
 use thiserror::Error;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use super::demand::{DemandTracker, ForecastRequest};
 use super::models::*;
-use crate::api_budget::ApiCallBudget;
-use crate::cache::{normalize_cache_key, CachedGeoLocation, GeoCache, TtlCache};
+use super::nws::NwsClient;
+use super::open_meteo::OpenMeteo;
+use super::provider::{ForecastParts, OpenWeatherMap, ProviderKind, WeatherProvider};
+use crate::api_budget::{ApiCallBudget, BudgetFeature};
+use crate::api_keys::ApiKeyPool;
+use crate::cache::{normalize_cache_key, CacheStats, CachedGeoLocation, GeoCache, TtlCache};
+use crate::cache_backend::SharedCache;
+use crate::db::{
+    alert_fingerprint, ArchivedForecast, ForecastArchiveRepository, SqliteForecastArchiveRepository,
+};
 use crate::error::HttpError;
-use crate::impl_into_response;
+use crate::geocode::models::{make_location_key, resolve_geo_location};
+use crate::locations::{LocationsService, SavedLocation};
+use crate::middleware::record_cache_age;
+use crate::units::{Units, UnknownUnits};
+use crate::upstream::UpstreamFailure;
+use crate::{impl_from_upstream, impl_into_response};
 
 const GEOCODING_API_URL: &str = "https://api.openweathermap.org/geo/1.0/direct";
 const ZIP_GEOCODING_API_URL: &str = "https://api.openweathermap.org/geo/1.0/zip";
 const REVERSE_GEOCODING_API_URL: &str = "https://api.openweathermap.org/geo/1.0/reverse";
-const ONE_CALL_API_URL: &str = "https://api.openweathermap.org/data/3.0/onecall";
+
+/// OWM response language used when the caller has no preference
+pub const DEFAULT_LANG: &str = "en";
+
+/// Share of forecast demand kept on each hot refresh pass; at one pass a
+/// minute, demand halves in a little over an hour
+const DEMAND_DECAY: f64 = 0.99;
 
 #[derive(Error, Debug)]
 pub enum ForecastError {
@@ -32,6 +53,18 @@ pub enum ForecastError {
 
     #[error("One Call API subscription required. Subscribe at https://openweathermap.org/api/one-call-3")]
     SubscriptionRequired,
+
+    #[error("Daily API budget exhausted, retry in {retry_after}s")]
+    BudgetExhausted { retry_after: u64 },
+
+    #[error("OpenWeatherMap is unavailable, retry in {retry_after}s")]
+    UpstreamUnavailable { retry_after: u64 },
+
+    #[error("Forecast provider unavailable: {0}")]
+    ProviderUnavailable(String),
+
+    #[error(transparent)]
+    Upstream(#[from] UpstreamFailure),
 }
 
 impl HttpError for ForecastError {
@@ -42,6 +75,10 @@ impl HttpError for ForecastError {
             Self::ApiError(_) => StatusCode::BAD_REQUEST,
             Self::InvalidResponse(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::SubscriptionRequired => StatusCode::PAYMENT_REQUIRED,
+            Self::BudgetExhausted { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::UpstreamUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Self::ProviderUnavailable(_) => StatusCode::BAD_REQUEST,
+            Self::Upstream(failure) => failure.status_code(),
         }
     }
 
@@ -52,33 +89,307 @@ impl HttpError for ForecastError {
             Self::ApiError(_) => Some("API_ERROR"),
             Self::InvalidResponse(_) => Some("INVALID_RESPONSE"),
             Self::SubscriptionRequired => Some("SUBSCRIPTION_REQUIRED"),
+            Self::BudgetExhausted { .. } => Some("API_BUDGET_EXHAUSTED"),
+            Self::UpstreamUnavailable { .. } => Some("UPSTREAM_UNAVAILABLE"),
+            Self::ProviderUnavailable(_) => Some("PROVIDER_UNAVAILABLE"),
+            Self::Upstream(failure) => Some(failure.error_code()),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::BudgetExhausted { retry_after } | Self::UpstreamUnavailable { retry_after } => {
+                Some(*retry_after)
+            }
+            Self::Upstream(failure) => failure.retry_after(),
+            _ => None,
+        }
+    }
+
+    fn upstream_status(&self) -> Option<u16> {
+        match self {
+            Self::Upstream(failure) => Some(failure.status),
+            _ => None,
+        }
+    }
+
+    fn details(&self) -> Option<String> {
+        match self {
+            Self::Upstream(failure) => failure.details(),
+            _ => None,
         }
     }
 }
 
 impl_into_response!(ForecastError);
+impl_from_upstream!(ForecastError);
+
+/// How long each forecast endpoint's responses are cached
+#[derive(Debug, Clone, Copy)]
+pub struct ForecastTtls {
+    pub full: Duration,
+    pub daily: Duration,
+    pub hourly: Duration,
+    pub alerts: Duration,
+}
+
+impl ForecastTtls {
+    /// The same TTL for every endpoint
+    pub fn uniform(ttl: Duration) -> Self {
+        Self {
+            full: ttl,
+            daily: ttl,
+            hourly: ttl,
+            alerts: ttl,
+        }
+    }
+}
+
+/// One in-memory cache per forecast endpoint, so each has its own TTL and
+/// hit rate
+struct ForecastCaches {
+    full: TtlCache<String, ForecastResponse>,
+    daily: TtlCache<String, ForecastResponse>,
+    hourly: TtlCache<String, ForecastResponse>,
+    alerts: TtlCache<String, ForecastResponse>,
+}
+
+impl ForecastCaches {
+    fn new(ttls: ForecastTtls, max_entries: usize) -> Self {
+        Self {
+            full: TtlCache::new("forecast", ttls.full).with_max_entries(max_entries),
+            daily: TtlCache::new("forecast_daily", ttls.daily).with_max_entries(max_entries),
+            hourly: TtlCache::new("forecast_hourly", ttls.hourly).with_max_entries(max_entries),
+            alerts: TtlCache::new("alerts", ttls.alerts).with_max_entries(max_entries),
+        }
+    }
+
+    fn get(&self, parts: ForecastParts) -> &TtlCache<String, ForecastResponse> {
+        match parts {
+            ForecastParts::Full => &self.full,
+            ForecastParts::Daily => &self.daily,
+            ForecastParts::Hourly => &self.hourly,
+            ForecastParts::Alerts => &self.alerts,
+        }
+    }
+
+    fn stats(&self) -> Vec<CacheStats> {
+        vec![
+            self.full.stats(),
+            self.daily.stats(),
+            self.hourly.stats(),
+            self.alerts.stats(),
+        ]
+    }
+}
 
 pub struct ForecastService {
     client: Client,
-    api_key: String,
+    api_keys: Arc<ApiKeyPool>,
     geo_cache: GeoCache,
     api_budget: Arc<ApiCallBudget>,
-    forecast_cache: TtlCache<String, ForecastResponse>,
+    caches: ForecastCaches,
+    demand: DemandTracker,
+    shared_cache: Option<SharedCache>,
+    locations: Option<Arc<LocationsService>>,
+    nws: Option<NwsClient>,
+    archive: Option<Arc<SqliteForecastArchiveRepository>>,
+    providers: HashMap<ProviderKind, Arc<dyn WeatherProvider>>,
+    default_provider: ProviderKind,
+    allow_provider_override: bool,
 }
 
 impl ForecastService {
     pub fn new(
         client: Client,
-        api_key: &str,
+        api_keys: Arc<ApiKeyPool>,
         geo_cache: GeoCache,
         api_budget: Arc<ApiCallBudget>,
     ) -> Self {
+        let mut providers: HashMap<ProviderKind, Arc<dyn WeatherProvider>> = HashMap::new();
+        providers.insert(
+            ProviderKind::OpenWeatherMap,
+            Arc::new(OpenWeatherMap::new(client.clone(), Arc::clone(&api_keys))),
+        );
+        providers.insert(
+            ProviderKind::OpenMeteo,
+            Arc::new(OpenMeteo::new(client.clone())),
+        );
         Self {
             client,
-            api_key: api_key.to_string(),
+            api_keys,
             geo_cache,
             api_budget,
-            forecast_cache: TtlCache::new(Duration::from_secs(15 * 60)),
+            caches: ForecastCaches::new(ForecastTtls::uniform(Duration::from_secs(15 * 60)), 0),
+            demand: DemandTracker::default(),
+            shared_cache: None,
+            locations: None,
+            nws: None,
+            archive: None,
+            providers,
+            default_provider: ProviderKind::OpenWeatherMap,
+            allow_provider_override: true,
+        }
+    }
+
+    /// Make `provider` available to `?provider=` (and as a default)
+    pub fn with_provider(mut self, provider: Arc<dyn WeatherProvider>) -> Self {
+        self.providers.insert(provider.kind(), provider);
+        self
+    }
+
+    /// Serve forecasts from `kind` unless a request asks for another. With
+    /// `allow_override` false, requests for any other provider are rejected.
+    pub fn with_default_provider(mut self, kind: ProviderKind, allow_override: bool) -> Self {
+        self.default_provider = kind;
+        self.allow_provider_override = allow_override;
+        self
+    }
+
+    /// Keep each endpoint's forecasts for its TTL in `ttls` instead of the
+    /// default 15 minutes, in at most `max_entries` entries per endpoint (0
+    /// for unbounded)
+    pub fn with_cache_limits(mut self, ttls: ForecastTtls, max_entries: usize) -> Self {
+        self.caches = ForecastCaches::new(ttls, max_entries);
+        self
+    }
+
+    /// Also read and write forecasts in a cache shared with other instances
+    pub fn with_shared_cache(mut self, shared: SharedCache) -> Self {
+        self.shared_cache = Some(shared);
+        self
+    }
+
+    /// A fresh forecast from memory, else from the shared cache. Shared hits
+    /// are kept in memory for a full TTL.
+    async fn cached_forecast(
+        &self,
+        parts: ForecastParts,
+        cache_key: &str,
+    ) -> Option<ForecastResponse> {
+        let cache = self.caches.get(parts);
+        let cache_key = cache_key.to_string();
+        if let Some(cached) = cache.get(&cache_key) {
+            record_cache_age(cache.age(&cache_key));
+            return Some(cached);
+        }
+        let shared = self.shared_cache.as_ref()?;
+        let forecast: ForecastResponse =
+            shared.get_json(&format!("forecast:{}", cache_key)).await?;
+        cache.insert(cache_key, forecast.clone());
+        Some(forecast)
+    }
+
+    async fn cache_forecast(
+        &self,
+        parts: ForecastParts,
+        cache_key: String,
+        forecast: &ForecastResponse,
+    ) {
+        let cache = self.caches.get(parts);
+        if let Some(ref shared) = self.shared_cache {
+            let key = format!("forecast:{}", cache_key);
+            shared.set_json(&key, forecast, cache.ttl()).await;
+        }
+        cache.insert(cache_key, forecast.clone());
+    }
+
+    /// Counters for each endpoint's forecast cache and the in-memory
+    /// geocoding layer
+    pub fn cache_stats(&self) -> Vec<CacheStats> {
+        let mut stats = self.caches.stats();
+        stats.push(self.geo_cache.stats());
+        stats
+    }
+
+    /// Resolve `loc:<id>` references to saved locations
+    pub fn with_locations(mut self, locations: Arc<LocationsService>) -> Self {
+        self.locations = Some(locations);
+        self
+    }
+
+    /// Source US alerts from the NWS CAP feed instead of OWM's relay
+    pub fn with_nws(mut self, nws: NwsClient) -> Self {
+        self.nws = Some(nws);
+        self
+    }
+
+    /// Keep every forecast fetched from OWM in the forecast archive
+    pub fn with_archive(mut self, pool: sqlx::SqlitePool) -> Self {
+        self.archive = Some(Arc::new(SqliteForecastArchiveRepository::new(pool)));
+        self
+    }
+
+    /// Archive a freshly fetched forecast without holding up the caller
+    fn archive_forecast(&self, kind: &str, units: &str, forecast: &ForecastResponse) {
+        let Some(ref archive) = self.archive else {
+            return;
+        };
+        let archive = Arc::clone(archive);
+        let location_key = make_location_key(forecast.location.lat, forecast.location.lon);
+        let archived = ArchivedForecast {
+            kind: kind.to_string(),
+            units: units.to_string(),
+            issued_at: chrono::Utc::now().timestamp(),
+            forecast: forecast.clone(),
+        };
+        tokio::spawn(async move {
+            if let Err(e) = archive.record(&location_key, &archived).await {
+                tracing::warn!(location_key = %location_key, error = %e, "Failed to archive forecast");
+            }
+        });
+    }
+
+    /// Reserve an API call for `cache_key`. While the circuit breaker is open
+    /// or once today's budget is spent, this returns the stale cached copy if
+    /// there is one, else `UpstreamUnavailable` or `BudgetExhausted`.
+    fn over_budget(
+        &self,
+        parts: ForecastParts,
+        cache_key: &String,
+    ) -> Option<Result<ForecastResponse, ForecastError>> {
+        let err = if let Some(retry_after) = self.api_keys.circuit_open_for() {
+            ForecastError::UpstreamUnavailable { retry_after }
+        } else if self.api_budget.try_acquire(BudgetFeature::Forecast) {
+            return None;
+        } else {
+            ForecastError::BudgetExhausted {
+                retry_after: self.api_budget.seconds_until_reset(),
+            }
+        };
+        let cache = self.caches.get(parts);
+        Some(match cache.get_stale(cache_key) {
+            Some(stale) => {
+                record_cache_age(cache.age(cache_key));
+                tracing::warn!(key = %cache_key, reason = %err, "Serving stale forecast");
+                Ok(stale)
+            }
+            None => Err(err),
+        })
+    }
+
+    /// Replace OWM alerts with NWS alerts for US locations. OWM's alerts are
+    /// kept if the NWS request fails.
+    async fn apply_nws_alerts(&self, forecast: &mut ForecastResponse) {
+        let Some(ref nws) = self.nws else {
+            return;
+        };
+        if forecast.location.country != "US" {
+            return;
+        }
+
+        match nws
+            .active_alerts(forecast.location.lat, forecast.location.lon)
+            .await
+        {
+            Ok(alerts) => forecast.alerts = alerts,
+            Err(e) => {
+                tracing::warn!(
+                    city = %forecast.location.city,
+                    error = %e,
+                    "Failed to fetch NWS alerts, using OWM alerts"
+                );
+            }
         }
     }
 
@@ -108,10 +419,38 @@ impl ForecastService {
         }
     }
 
+    /// The saved location `location` refers to, if it is a `loc:<id>` reference
+    fn saved_location(&self, location: &str) -> Result<Option<SavedLocation>, ForecastError> {
+        match self.locations {
+            Some(ref locations) => locations
+                .resolve(location)
+                .map_err(|_| ForecastError::CityNotFound(location.to_string())),
+            None => Ok(None),
+        }
+    }
+
     /// Get coordinates for a location using the Geocoding API
-    /// Supports both city names ("Chicago") and zip codes ("60601" or "60601,US")
-    /// Results are cached for 24 hours
+    /// Supports city names ("Chicago"), zip codes ("60601" or "60601,US"),
+    /// coordinates, saved locations ("loc:<id>"), and aliases ("home"), which
+    /// take their label as the name. Results are cached for 24 hours.
+    /// "geo:lat,lon" coordinates are used as they are, with no upstream call.
     pub async fn geocode(&self, location: &str) -> Result<GeoLocation, ForecastError> {
+        if let Some(resolved) = resolve_geo_location(location) {
+            return Ok(resolved);
+        }
+        let Some(saved) = self.saved_location(location)? else {
+            return self.geocode_query(location).await;
+        };
+        let mut resolved = self.geocode_query(&saved.query()).await?;
+        resolved.name = saved.label;
+        if let (Some(lat), Some(lon)) = (saved.lat, saved.lon) {
+            resolved.lat = lat;
+            resolved.lon = lon;
+        }
+        Ok(resolved)
+    }
+
+    async fn geocode_query(&self, location: &str) -> Result<GeoLocation, ForecastError> {
         let cache_key = normalize_cache_key(location);
 
         // Check cache first (memory + SQLite)
@@ -167,19 +506,17 @@ impl ForecastService {
         tracing::debug!(city = %city, "Geocoding city");
 
         let response = self
-            .client
-            .get(GEOCODING_API_URL)
-            .query(&[("q", city), ("limit", "1"), ("appid", &self.api_key)])
-            .send()
+            .api_keys
+            .send(
+                self.client
+                    .get(GEOCODING_API_URL)
+                    .query(&[("q", city), ("limit", "1")]),
+            )
             .await?;
 
         let status = response.status();
         if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(ForecastError::ApiError(format!(
-                "Geocoding failed: {}",
-                text
-            )));
+            return Err(UpstreamFailure::read("Geocoding", response).await.into());
         }
 
         let locations: Vec<GeoLocation> = response.json().await?;
@@ -204,19 +541,19 @@ impl ForecastService {
         tracing::debug!(zip = %zip_query, "Geocoding zip code");
 
         let response = self
-            .client
-            .get(ZIP_GEOCODING_API_URL)
-            .query(&[("zip", &zip_query), ("appid", &self.api_key)])
-            .send()
+            .api_keys
+            .send(
+                self.client
+                    .get(ZIP_GEOCODING_API_URL)
+                    .query(&[("zip", &zip_query)]),
+            )
             .await?;
 
         let status = response.status();
         if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(ForecastError::ApiError(format!(
-                "Zip geocoding failed: {}",
-                text
-            )));
+            return Err(UpstreamFailure::read("Zip geocoding", response)
+                .await
+                .into());
         }
 
         let location: ZipGeoLocation = response.json().await?;
@@ -231,24 +568,19 @@ impl ForecastService {
         tracing::debug!(lat = %lat, lon = %lon, "Reverse geocoding coordinates");
 
         let response = self
-            .client
-            .get(REVERSE_GEOCODING_API_URL)
-            .query(&[
+            .api_keys
+            .send(self.client.get(REVERSE_GEOCODING_API_URL).query(&[
                 ("lat", lat.to_string()),
                 ("lon", lon.to_string()),
                 ("limit", "1".to_string()),
-                ("appid", self.api_key.clone()),
-            ])
-            .send()
+            ]))
             .await?;
 
         let status = response.status();
         if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(ForecastError::ApiError(format!(
-                "Reverse geocoding failed: {}",
-                text
-            )));
+            return Err(UpstreamFailure::read("Reverse geocoding", response)
+                .await
+                .into());
         }
 
         let locations: Vec<GeoLocation> = response.json().await?;
@@ -258,98 +590,238 @@ impl ForecastService {
             .ok_or_else(|| ForecastError::CityNotFound(format!("{},{}", lat, lon)))
     }
 
-    /// Get full forecast using One Call API 3.0
-    pub async fn get_forecast(
+    /// The provider for a request: `requested` when per-request selection is
+    /// allowed, else the configured default
+    fn provider(
+        &self,
+        requested: Option<ProviderKind>,
+    ) -> Result<Arc<dyn WeatherProvider>, ForecastError> {
+        let kind = match requested {
+            Some(kind) if kind != self.default_provider && !self.allow_provider_override => {
+                return Err(ForecastError::ProviderUnavailable(format!(
+                    "this server only serves {} forecasts",
+                    self.default_provider
+                )));
+            }
+            Some(kind) => kind,
+            None => self.default_provider,
+        };
+        self.providers.get(&kind).cloned().ok_or_else(|| {
+            ForecastError::ProviderUnavailable(format!("{} is not configured", kind))
+        })
+    }
+
+    /// Fetch `parts` of the forecast for `city` from `provider` (the
+    /// configured default if None). Geocoding always goes through OWM; the
+    /// daily API budget and circuit breaker only apply to OWM forecasts.
+    pub async fn fetch(
         &self,
+        parts: ForecastParts,
         city: &str,
         units: &str,
+        lang: &str,
+        provider: Option<ProviderKind>,
     ) -> Result<ForecastResponse, ForecastError> {
-        let cache_key = format!("full_{}_{}", normalize_cache_key(city), units);
-        if let Some(cached) = self.forecast_cache.get(&cache_key) {
+        let source = self.provider(provider)?;
+        let kind = source.kind();
+        let city_key = normalize_cache_key(city);
+        let mut cache_key = match parts {
+            ForecastParts::Full => format!("full_{}_{}_{}", city_key, units, lang),
+            ForecastParts::Daily => format!("daily_{}_{}_{}", city_key, units, lang),
+            ForecastParts::Hourly => format!("hourly_{}_{}", city_key, units),
+            ForecastParts::Alerts => format!("alerts_{}", city_key),
+        };
+        if kind != ProviderKind::OpenWeatherMap {
+            cache_key = format!("{}_{}", cache_key, kind);
+        }
+        self.demand.record(&cache_key, || ForecastRequest {
+            parts,
+            city: city.to_string(),
+            units: units.to_string(),
+            lang: lang.to_string(),
+            provider: kind,
+        });
+        if let Some(cached) = self.cached_forecast(parts, &cache_key).await {
             return Ok(cached);
         }
+        self.fetch_upstream(source, parts, city, units, lang, cache_key)
+            .await
+    }
+
+    /// Fetch a forecast from `source` and cache it under `cache_key`
+    async fn fetch_upstream(
+        &self,
+        source: Arc<dyn WeatherProvider>,
+        parts: ForecastParts,
+        city: &str,
+        units: &str,
+        lang: &str,
+        cache_key: String,
+    ) -> Result<ForecastResponse, ForecastError> {
+        let kind = source.kind();
+        if kind == ProviderKind::OpenWeatherMap {
+            if let Some(result) = self.over_budget(parts, &cache_key) {
+                return result;
+            }
+        }
 
+        let parsed_units: Units = units
+            .parse()
+            .map_err(|e: UnknownUnits| ForecastError::ApiError(e.to_string()))?;
         let location = self.geocode(city).await?;
 
         tracing::debug!(
             city = %location.name,
             lat = %location.lat,
             lon = %location.lon,
+            provider = %kind,
             "Fetching forecast"
         );
 
-        self.api_budget.record_call();
-        metrics::counter!(crate::metrics::OWM_API_CALLS, "endpoint" => "onecall").increment(1);
-        let response = self
-            .client
-            .get(ONE_CALL_API_URL)
-            .query(&[
-                ("lat", location.lat.to_string()),
-                ("lon", location.lon.to_string()),
-                ("units", units.to_string()),
-                ("appid", self.api_key.clone()),
-                ("exclude", "minutely".to_string()),
-            ])
-            .send()
-            .await?;
-
-        let status = response.status();
-        if status == reqwest::StatusCode::UNAUTHORIZED {
-            return Err(ForecastError::SubscriptionRequired);
+        let mut result = source.forecast(location, parts, parsed_units, lang).await?;
+        parts.trim(&mut result);
+        // The NWS provider reports its own alerts
+        if kind != ProviderKind::Nws {
+            self.apply_nws_alerts(&mut result).await;
         }
-        if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(ForecastError::ApiError(text));
+        self.cache_forecast(parts, cache_key, &result).await;
+        // Only archive one provider's forecasts, so accuracy comparisons
+        // aren't mixing sources
+        if kind == self.default_provider {
+            let archive_kind = match parts {
+                ForecastParts::Full => Some("full"),
+                ForecastParts::Daily => Some("daily"),
+                ForecastParts::Hourly => Some("hourly"),
+                ForecastParts::Alerts => None,
+            };
+            if let Some(archive_kind) = archive_kind {
+                self.archive_forecast(archive_kind, units, &result);
+            }
         }
-
-        let data: OneCallResponse = response.json().await?;
-        let result = self.transform_response(data, location);
-        self.forecast_cache.insert(cache_key, result.clone());
         Ok(result)
     }
 
+    /// Refetch the `limit` most requested forecasts whose cached copy is
+    /// missing or expires within `lead`, making at most `max_calls` calls.
+    /// OWM forecasts stop being refreshed while less than `reserve_percent`
+    /// of the day's forecast budget is left, so interactive requests keep it.
+    /// Meant to run about once a minute, which sets how fast demand decays.
+    /// Returns the number of forecasts refreshed.
+    pub async fn refresh_hot(
+        &self,
+        limit: usize,
+        lead: Duration,
+        max_calls: usize,
+        reserve_percent: u8,
+    ) -> usize {
+        self.demand.decay(DEMAND_DECAY);
+        let budget = self
+            .api_budget
+            .feature_limit(BudgetFeature::Forecast)
+            .unwrap_or(self.api_budget.daily_limit());
+        let reserve = u64::from(budget) * u64::from(reserve_percent.min(100)) / 100;
+
+        let mut refreshed = 0;
+        for (cache_key, request) in self.demand.hottest(limit) {
+            if refreshed >= max_calls {
+                break;
+            }
+            let cache = self.caches.get(request.parts);
+            if cache.expires_in(&cache_key).is_some_and(|left| left > lead) {
+                continue;
+            }
+            if request.provider == ProviderKind::OpenWeatherMap
+                && u64::from(self.api_budget.remaining_for(BudgetFeature::Forecast)) <= reserve
+            {
+                continue;
+            }
+            let Ok(source) = self.provider(Some(request.provider)) else {
+                continue;
+            };
+            let result = self
+                .fetch_upstream(
+                    source,
+                    request.parts,
+                    &request.city,
+                    &request.units,
+                    &request.lang,
+                    cache_key,
+                )
+                .await;
+            match result {
+                Ok(_) => {
+                    refreshed += 1;
+                    metrics::counter!(crate::metrics::FORECAST_CACHE_REFRESHES).increment(1);
+                }
+                Err(e) => tracing::warn!(
+                    city = %request.city,
+                    error = %e,
+                    "Failed to refresh hot forecast"
+                ),
+            }
+        }
+        refreshed
+    }
+
+    /// Get full forecast (current, 48 hours, 8 days)
+    pub async fn get_forecast(
+        &self,
+        city: &str,
+        units: &str,
+    ) -> Result<ForecastResponse, ForecastError> {
+        self.get_forecast_in(city, units, DEFAULT_LANG).await
+    }
+
+    /// Get full forecast with descriptions and summaries in the given OWM language
+    pub async fn get_forecast_in(
+        &self,
+        city: &str,
+        units: &str,
+        lang: &str,
+    ) -> Result<ForecastResponse, ForecastError> {
+        self.fetch(ForecastParts::Full, city, units, lang, None)
+            .await
+    }
+
     /// Get only daily forecast (8 days)
     pub async fn get_daily_forecast(
         &self,
         city: &str,
         units: &str,
     ) -> Result<ForecastResponse, ForecastError> {
-        let cache_key = format!("daily_{}_{}", normalize_cache_key(city), units);
-        if let Some(cached) = self.forecast_cache.get(&cache_key) {
-            return Ok(cached);
-        }
+        self.get_daily_forecast_in(city, units, DEFAULT_LANG).await
+    }
 
-        let location = self.geocode(city).await?;
+    /// Get only daily forecast in the given OWM language
+    pub async fn get_daily_forecast_in(
+        &self,
+        city: &str,
+        units: &str,
+        lang: &str,
+    ) -> Result<ForecastResponse, ForecastError> {
+        self.fetch(ForecastParts::Daily, city, units, lang, None)
+            .await
+    }
 
-        self.api_budget.record_call();
-        metrics::counter!(crate::metrics::OWM_API_CALLS, "endpoint" => "onecall_daily")
-            .increment(1);
-        let response = self
-            .client
-            .get(ONE_CALL_API_URL)
-            .query(&[
-                ("lat", location.lat.to_string()),
-                ("lon", location.lon.to_string()),
-                ("units", units.to_string()),
-                ("appid", self.api_key.clone()),
-                ("exclude", "minutely,hourly".to_string()),
-            ])
-            .send()
+    /// Get only the active alerts for a location, each with the id scheduled
+    /// runs track it by. Units don't affect alerts, so one cached entry
+    /// serves every caller.
+    pub async fn get_alerts(&self, city: &str) -> Result<AlertsResponse, ForecastError> {
+        let forecast = self
+            .fetch(ForecastParts::Alerts, city, "metric", DEFAULT_LANG, None)
             .await?;
-
-        let status = response.status();
-        if status == reqwest::StatusCode::UNAUTHORIZED {
-            return Err(ForecastError::SubscriptionRequired);
+        let location_key = make_location_key(forecast.location.lat, forecast.location.lon);
+        let mut response = AlertsResponse::from_forecast(forecast, chrono::Utc::now().timestamp());
+        for alert in &mut response.alerts {
+            alert.id = alert_fingerprint(
+                &location_key,
+                &alert.sender,
+                &alert.event,
+                alert.effective,
+                alert.expires,
+            );
         }
-        if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(ForecastError::ApiError(text));
-        }
-
-        let data: OneCallResponse = response.json().await?;
-        let result = self.transform_response(data, location);
-        self.forecast_cache.insert(cache_key, result.clone());
-        Ok(result)
+        Ok(response)
     }
 
     /// Get only hourly forecast (48 hours)
@@ -358,146 +830,8 @@ impl ForecastService {
         city: &str,
         units: &str,
     ) -> Result<ForecastResponse, ForecastError> {
-        let cache_key = format!("hourly_{}_{}", normalize_cache_key(city), units);
-        if let Some(cached) = self.forecast_cache.get(&cache_key) {
-            return Ok(cached);
-        }
-
-        let location = self.geocode(city).await?;
-
-        self.api_budget.record_call();
-        metrics::counter!(crate::metrics::OWM_API_CALLS, "endpoint" => "onecall_hourly")
-            .increment(1);
-        let response = self
-            .client
-            .get(ONE_CALL_API_URL)
-            .query(&[
-                ("lat", location.lat.to_string()),
-                ("lon", location.lon.to_string()),
-                ("units", units.to_string()),
-                ("appid", self.api_key.clone()),
-                ("exclude", "minutely,daily".to_string()),
-            ])
-            .send()
-            .await?;
-
-        let status = response.status();
-        if status == reqwest::StatusCode::UNAUTHORIZED {
-            return Err(ForecastError::SubscriptionRequired);
-        }
-        if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(ForecastError::ApiError(text));
-        }
-
-        let data: OneCallResponse = response.json().await?;
-        let result = self.transform_response(data, location);
-        self.forecast_cache.insert(cache_key, result.clone());
-        Ok(result)
-    }
-
-    fn transform_response(&self, data: OneCallResponse, location: GeoLocation) -> ForecastResponse {
-        ForecastResponse {
-            location: LocationInfo {
-                city: location.name,
-                country: location.country,
-                state: location.state,
-                lat: location.lat,
-                lon: location.lon,
-            },
-            timezone: data.timezone,
-            current: data.current.map(|c| {
-                let weather = c.weather.first();
-                CurrentWeatherResponse {
-                    timestamp: c.dt,
-                    temperature: c.temp,
-                    feels_like: c.feels_like,
-                    humidity: c.humidity,
-                    pressure: c.pressure,
-                    uv_index: c.uvi,
-                    clouds: c.clouds,
-                    visibility: c.visibility,
-                    wind_speed: c.wind_speed,
-                    wind_direction: c.wind_deg,
-                    wind_gust: c.wind_gust,
-                    description: weather.map(|w| w.description.clone()).unwrap_or_default(),
-                    icon: weather.map(|w| w.icon.clone()).unwrap_or_default(),
-                    sunrise: c.sunrise,
-                    sunset: c.sunset,
-                }
-            }),
-            hourly: data
-                .hourly
-                .unwrap_or_default()
-                .into_iter()
-                .map(|h| {
-                    let weather = h.weather.first();
-                    HourlyForecastResponse {
-                        timestamp: h.dt,
-                        temperature: h.temp,
-                        feels_like: h.feels_like,
-                        humidity: h.humidity,
-                        pressure: h.pressure,
-                        uv_index: h.uvi,
-                        clouds: h.clouds,
-                        wind_speed: h.wind_speed,
-                        wind_direction: h.wind_deg,
-                        precipitation_probability: h.pop,
-                        rain_volume: h.rain.and_then(|r| r.one_hour),
-                        snow_volume: h.snow.and_then(|s| s.one_hour),
-                        description: weather.map(|w| w.description.clone()).unwrap_or_default(),
-                        icon: weather.map(|w| w.icon.clone()).unwrap_or_default(),
-                    }
-                })
-                .collect(),
-            daily: data
-                .daily
-                .unwrap_or_default()
-                .into_iter()
-                .map(|d| {
-                    let weather = d.weather.first();
-                    DailyForecastResponse {
-                        timestamp: d.dt,
-                        sunrise: d.sunrise,
-                        sunset: d.sunset,
-                        moon_phase: d.moon_phase,
-                        summary: d.summary,
-                        temp_min: d.temp.min,
-                        temp_max: d.temp.max,
-                        temp_day: d.temp.day,
-                        temp_night: d.temp.night,
-                        temp_morning: d.temp.morn,
-                        temp_evening: d.temp.eve,
-                        feels_like_day: d.feels_like.day,
-                        feels_like_night: d.feels_like.night,
-                        humidity: d.humidity,
-                        pressure: d.pressure,
-                        uv_index: d.uvi,
-                        clouds: d.clouds,
-                        wind_speed: d.wind_speed,
-                        wind_direction: d.wind_deg,
-                        precipitation_probability: d.pop,
-                        rain_volume: d.rain,
-                        snow_volume: d.snow,
-                        description: weather.map(|w| w.description.clone()).unwrap_or_default(),
-                        icon: weather.map(|w| w.icon.clone()).unwrap_or_default(),
-                    }
-                })
-                .collect(),
-            alerts: data
-                .alerts
-                .unwrap_or_default()
-                .into_iter()
-                .map(|a| AlertResponse {
-                    sender: a.sender_name,
-                    event: a.event,
-                    start: a.start,
-                    end: a.end,
-                    description: a.description,
-                    tags: a.tags,
-                })
-                .collect(),
-        }
+        self.fetch(ForecastParts::Hourly, city, units, DEFAULT_LANG, None)
+            .await
     }
 }
 
@@ -505,16 +839,6 @@ impl ForecastService {
 mod tests {
     use super::*;
 
-    async fn test_geo_cache() -> crate::cache::GeoCache {
-        let pool = crate::db::create_pool(&crate::db::DbConfig {
-            url: "sqlite::memory:".to_string(),
-            max_connections: 1,
-        })
-        .await
-        .unwrap();
-        crate::cache::create_geo_cache(pool)
-    }
-
     #[test]
     fn test_is_zip_code_us_numeric() {
         assert!(ForecastService::is_zip_code("60601"));
@@ -558,124 +882,4 @@ mod tests {
     fn test_is_not_zip_code_multiple_commas() {
         assert!(!ForecastService::is_zip_code("60601,US,IL"));
     }
-
-    fn create_test_location() -> GeoLocation {
-        GeoLocation {
-            name: "Chicago".to_string(),
-            lat: 41.8781,
-            lon: -87.6298,
-            country: "US".to_string(),
-            state: Some("Illinois".to_string()),
-        }
-    }
-
-    fn create_minimal_one_call_response() -> OneCallResponse {
-        OneCallResponse {
-            lat: 41.8781,
-            lon: -87.6298,
-            timezone: "America/Chicago".to_string(),
-            timezone_offset: -18000,
-            current: None,
-            minutely: None,
-            hourly: None,
-            daily: None,
-            alerts: None,
-        }
-    }
-
-    #[tokio::test]
-    async fn test_transform_response_minimal() {
-        let geo_cache = test_geo_cache().await;
-        let service = ForecastService::new(
-            reqwest::Client::new(),
-            "test_api_key",
-            geo_cache,
-            std::sync::Arc::new(crate::api_budget::ApiCallBudget::new(1000)),
-        );
-
-        let data = create_minimal_one_call_response();
-        let location = create_test_location();
-        let result = service.transform_response(data, location);
-
-        assert_eq!(result.location.city, "Chicago");
-        assert_eq!(result.location.country, "US");
-        assert_eq!(result.timezone, "America/Chicago");
-        assert!(result.current.is_none());
-        assert!(result.hourly.is_empty());
-        assert!(result.daily.is_empty());
-        assert!(result.alerts.is_empty());
-    }
-
-    #[tokio::test]
-    async fn test_transform_response_with_current_weather() {
-        let geo_cache = test_geo_cache().await;
-        let service = ForecastService::new(
-            reqwest::Client::new(),
-            "test_api_key",
-            geo_cache,
-            std::sync::Arc::new(crate::api_budget::ApiCallBudget::new(1000)),
-        );
-
-        let mut data = create_minimal_one_call_response();
-        data.current = Some(CurrentWeather {
-            dt: 1700000000,
-            sunrise: Some(1699980000),
-            sunset: Some(1700020000),
-            temp: 20.5,
-            feels_like: 19.0,
-            pressure: 1013,
-            humidity: 65,
-            dew_point: 14.0,
-            uvi: 3.5,
-            clouds: 40,
-            visibility: Some(10000),
-            wind_speed: 5.5,
-            wind_deg: 180,
-            wind_gust: Some(8.0),
-            weather: vec![WeatherCondition {
-                id: 800,
-                main: "Clear".to_string(),
-                description: "clear sky".to_string(),
-                icon: "01d".to_string(),
-            }],
-        });
-
-        let location = create_test_location();
-        let result = service.transform_response(data, location);
-
-        let current = result.current.expect("Current weather should be present");
-        assert_eq!(current.temperature, 20.5);
-        assert_eq!(current.feels_like, 19.0);
-        assert_eq!(current.humidity, 65);
-        assert_eq!(current.description, "clear sky");
-        assert_eq!(current.icon, "01d");
-    }
-
-    #[tokio::test]
-    async fn test_transform_response_with_alerts() {
-        let geo_cache = test_geo_cache().await;
-        let service = ForecastService::new(
-            reqwest::Client::new(),
-            "test_api_key",
-            geo_cache,
-            std::sync::Arc::new(crate::api_budget::ApiCallBudget::new(1000)),
-        );
-
-        let mut data = create_minimal_one_call_response();
-        data.alerts = Some(vec![WeatherAlert {
-            sender_name: "NWS Chicago".to_string(),
-            event: "Heat Advisory".to_string(),
-            start: 1700000000,
-            end: 1700100000,
-            description: "Excessive heat expected".to_string(),
-            tags: Some(vec!["Extreme temperature".to_string()]),
-        }]);
-
-        let location = create_test_location();
-        let result = service.transform_response(data, location);
-
-        assert_eq!(result.alerts.len(), 1);
-        assert_eq!(result.alerts[0].event, "Heat Advisory");
-        assert_eq!(result.alerts[0].sender, "NWS Chicago");
-    }
 }
@@ -0,0 +1,134 @@
+use std::f64::consts::PI;
+
+use super::models::{DailyForecastResponse, ForecastResponse, SunDetail, SunPeriod};
+
+/// Sun elevations bounding golden and blue hour, in degrees
+const GOLDEN_HOUR_TOP: f64 = 6.0;
+const BLUE_HOUR_TOP: f64 = -4.0;
+const BLUE_HOUR_BOTTOM: f64 = -6.0;
+
+/// Seconds the sun's hour angle takes to move one radian
+const SECONDS_PER_RADIAN: f64 = 86400.0 / (2.0 * PI);
+
+/// Solar declination on the day containing `timestamp`, in radians
+fn declination(timestamp: i64) -> f64 {
+    let day_of_year = chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| chrono::Datelike::ordinal(&t))
+        .unwrap_or(1);
+    23.44_f64.to_radians() * (2.0 * PI * (284.0 + f64::from(day_of_year)) / 365.0).sin()
+}
+
+/// Seconds from solar noon until the sun sinks to `elevation` degrees: 0
+/// when it never gets that high, 12 hours when it never gets that low
+fn offset_from_noon(lat: f64, declination: f64, elevation: f64) -> i64 {
+    let lat = lat.to_radians();
+    let cos_hour_angle = (elevation.to_radians().sin() - lat.sin() * declination.sin())
+        / (lat.cos() * declination.cos());
+    (cos_hour_angle.clamp(-1.0, 1.0).acos() * SECONDS_PER_RADIAN).round() as i64
+}
+
+/// A period, unless the sun doesn't pass through it
+fn period(start: i64, end: i64) -> Option<SunPeriod> {
+    (start < end).then_some(SunPeriod { start, end })
+}
+
+/// Golden and blue hours for a day at latitude `lat`. Solar noon is taken as
+/// midway between the day's sunrise and sunset, so it follows the provider's
+/// times; days without both have no detail.
+fn sun_detail(lat: f64, day: &DailyForecastResponse) -> Option<SunDetail> {
+    if day.sunrise <= 0 || day.sunset <= day.sunrise {
+        return None;
+    }
+    let noon = day.sunrise + (day.sunset - day.sunrise) / 2;
+    let declination = declination(noon);
+    let golden = offset_from_noon(lat, declination, GOLDEN_HOUR_TOP);
+    let blue_top = offset_from_noon(lat, declination, BLUE_HOUR_TOP);
+    let blue_bottom = offset_from_noon(lat, declination, BLUE_HOUR_BOTTOM);
+
+    Some(SunDetail {
+        solar_noon: noon,
+        blue_hour_morning: period(noon - blue_bottom, noon - blue_top),
+        golden_hour_morning: period(noon - blue_top, noon - golden),
+        golden_hour_evening: period(noon + golden, noon + blue_top),
+        blue_hour_evening: period(noon + blue_top, noon + blue_bottom),
+    })
+}
+
+impl ForecastResponse {
+    /// Fill in each day's golden and blue hours
+    pub fn add_sun_detail(&mut self) {
+        let lat = self.location.lat;
+        for day in &mut self.daily {
+            day.sun_detail = sun_detail(lat, day);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(sunrise: i64, sunset: i64) -> DailyForecastResponse {
+        serde_json::from_value(serde_json::json!({
+            "timestamp": sunrise + 6 * 3600, "sunrise": sunrise, "sunset": sunset,
+            "moon_phase": 0.5, "temp_min": 18.0, "temp_max": 29.0, "temp_day": 27.0,
+            "temp_night": 20.0, "temp_morning": 19.0, "temp_evening": 25.0,
+            "feels_like_day": 27.0, "feels_like_night": 20.0, "humidity": 55,
+            "pressure": 1015, "uv_index": 8.0, "clouds": 10, "wind_speed": 3.0,
+            "wind_direction": 200, "precipitation_probability": 0.1,
+            "description": "clear sky", "icon": "01d"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sun_detail_chicago_midsummer() {
+        // Chicago, 2024-07-01: sunrise 05:18, sunset 20:29 CDT
+        let detail = sun_detail(41.88, &day(1_719_829_080, 1_719_883_740)).unwrap();
+        assert_eq!(detail.solar_noon, 1_719_856_410);
+
+        let golden = detail.golden_hour_evening.unwrap();
+        let blue = detail.blue_hour_evening.unwrap();
+        // The sun drops to 6° about 43 minutes before sunset; golden hour
+        // runs past sunset, straight into blue hour
+        assert!((golden.start - (1_719_883_740 - 43 * 60)).abs() < 3 * 60);
+        assert!(golden.end > 1_719_883_740);
+        assert_eq!(golden.end, blue.start);
+        assert!((blue.end - blue.start) > 10 * 60 && (blue.end - blue.start) < 30 * 60);
+
+        let morning = detail.golden_hour_morning.unwrap();
+        assert_eq!(morning.start, detail.blue_hour_morning.unwrap().end);
+        assert_eq!(morning.end - morning.start, golden.end - golden.start);
+    }
+
+    #[test]
+    fn test_sun_detail_polar_winter() {
+        // Tromsø in late January: the sun barely rises, so golden hour runs
+        // through midday, and there's no detail during polar night
+        let detail = sun_detail(69.65, &day(1_737_369_480, 1_737_374_160)).unwrap();
+        let morning = detail.golden_hour_morning;
+        let evening = detail.golden_hour_evening;
+        assert!(morning.is_some_and(|p| p.end == detail.solar_noon));
+        assert!(evening.is_some_and(|p| p.start == detail.solar_noon));
+
+        assert!(sun_detail(69.65, &day(0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_add_sun_detail_only_on_request() {
+        let mut forecast: ForecastResponse = serde_json::from_value(serde_json::json!({
+            "location": { "city": "Chicago", "country": "US", "lat": 41.88, "lon": -87.63 },
+            "timezone": "America/Chicago",
+            "current": null,
+            "hourly": [],
+            "daily": [serde_json::to_value(day(1_719_829_080, 1_719_883_740)).unwrap()]
+        }))
+        .unwrap();
+        let json = serde_json::to_value(&forecast).unwrap();
+        assert!(json["daily"][0].get("sun_detail").is_none());
+
+        forecast.add_sun_detail();
+        let json = serde_json::to_value(&forecast).unwrap();
+        assert!(json["daily"][0]["sun_detail"]["golden_hour_evening"]["start"].is_i64());
+    }
+}
@@ -0,0 +1,478 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveTime, TimeZone};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::models::{
+    classify_alert, AlertResponse, CurrentWeatherResponse, DailyForecastResponse, ForecastResponse,
+    GeoLocation, HourlyForecastResponse,
+};
+use super::provider::{owm_icon, ForecastParts, ProviderKind, WeatherProvider};
+use super::service::ForecastError;
+use crate::units::Units;
+use crate::upstream::UpstreamFailure;
+
+const WEATHERAPI_FORECAST_URL: &str = "https://api.weatherapi.com/v1/forecast.json";
+
+/// WeatherAPI.com forecasts (8 days on paid plans, 3 on the free tier).
+/// Values are requested in metric and converted.
+pub struct WeatherApi {
+    client: Client,
+    api_key: String,
+}
+
+impl WeatherApi {
+    pub fn new(client: Client, api_key: &str) -> Self {
+        Self {
+            client,
+            api_key: api_key.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiResponse {
+    location: WeatherApiLocation,
+    current: WeatherApiCurrent,
+    #[serde(default)]
+    forecast: Option<WeatherApiForecast>,
+    #[serde(default)]
+    alerts: Option<WeatherApiAlerts>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiLocation {
+    tz_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiCondition {
+    text: String,
+    code: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiCurrent {
+    last_updated_epoch: i64,
+    temp_c: f64,
+    feelslike_c: f64,
+    is_day: u8,
+    condition: WeatherApiCondition,
+    wind_kph: f64,
+    wind_degree: u32,
+    #[serde(default)]
+    gust_kph: Option<f64>,
+    pressure_mb: f64,
+    humidity: u32,
+    cloud: u32,
+    vis_km: f64,
+    uv: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiForecast {
+    forecastday: Vec<WeatherApiForecastDay>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiForecastDay {
+    /// Local date, "2024-06-01"
+    date: String,
+    date_epoch: i64,
+    day: WeatherApiDay,
+    astro: WeatherApiAstro,
+    #[serde(default)]
+    hour: Vec<WeatherApiHour>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiDay {
+    maxtemp_c: f64,
+    mintemp_c: f64,
+    avgtemp_c: f64,
+    maxwind_kph: f64,
+    totalprecip_mm: f64,
+    #[serde(default)]
+    totalsnow_cm: f64,
+    avghumidity: f64,
+    daily_chance_of_rain: f64,
+    daily_chance_of_snow: f64,
+    condition: WeatherApiCondition,
+    uv: f64,
+}
+
+/// Local clock times ("06:45 AM"), or "No moonrise"
+#[derive(Debug, Deserialize)]
+struct WeatherApiAstro {
+    sunrise: String,
+    sunset: String,
+    moonrise: String,
+    moonset: String,
+    moon_phase: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiHour {
+    time_epoch: i64,
+    temp_c: f64,
+    feelslike_c: f64,
+    is_day: u8,
+    condition: WeatherApiCondition,
+    wind_kph: f64,
+    wind_degree: u32,
+    #[serde(default)]
+    gust_kph: Option<f64>,
+    pressure_mb: f64,
+    precip_mm: f64,
+    #[serde(default)]
+    snow_cm: f64,
+    humidity: u32,
+    cloud: u32,
+    chance_of_rain: f64,
+    chance_of_snow: f64,
+    uv: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiAlerts {
+    #[serde(default)]
+    alert: Vec<WeatherApiAlert>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherApiAlert {
+    event: String,
+    #[serde(default)]
+    headline: Option<String>,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    instruction: Option<String>,
+    #[serde(default)]
+    effective: Option<String>,
+    #[serde(default)]
+    expires: Option<String>,
+}
+
+/// OWM condition id, group, and icon base for a WeatherAPI.com condition
+/// code
+fn condition(code: u32) -> (u32, &'static str, &'static str) {
+    match code {
+        1000 => (800, "Clear", "01"),
+        1003 => (802, "Clouds", "03"),
+        1006 => (803, "Clouds", "04"),
+        1009 => (804, "Clouds", "04"),
+        1030 | 1135 | 1147 => (741, "Fog", "50"),
+        1063 | 1180 | 1183 => (500, "Rain", "10"),
+        1186 | 1189 => (501, "Rain", "10"),
+        1192 | 1195 => (502, "Rain", "10"),
+        1150 | 1153 => (300, "Drizzle", "09"),
+        1072 | 1168 | 1171 | 1198 | 1201 => (511, "Rain", "13"),
+        1069 | 1204 | 1207 | 1249 | 1252 => (611, "Snow", "13"),
+        1066 | 1210 | 1213 | 1216 => (600, "Snow", "13"),
+        1114 | 1219 | 1222 | 1225 | 1237 => (601, "Snow", "13"),
+        1117 => (602, "Snow", "13"),
+        1240 => (520, "Rain", "09"),
+        1243 | 1246 => (521, "Rain", "09"),
+        1255 | 1258 | 1261 | 1264 => (621, "Snow", "13"),
+        1087 | 1273 | 1276 => (211, "Thunderstorm", "11"),
+        1279 | 1282 => (601, "Snow", "13"),
+        _ => (803, "Clouds", "04"),
+    }
+}
+
+/// Description, icon, condition id, and group
+fn describe(c: WeatherApiCondition, is_day: bool) -> (String, String, Option<u32>, Option<String>) {
+    let (id, main, icon) = condition(c.code);
+    (
+        c.text.trim().to_lowercase(),
+        owm_icon(icon, is_day),
+        Some(id),
+        Some(main.to_string()),
+    )
+}
+
+fn moon_phase(name: &str) -> f64 {
+    match name {
+        "Waxing Crescent" => 0.125,
+        "First Quarter" => 0.25,
+        "Waxing Gibbous" => 0.375,
+        "Full Moon" => 0.5,
+        "Waning Gibbous" => 0.625,
+        "Last Quarter" => 0.75,
+        "Waning Crescent" => 0.875,
+        _ => 0.0,
+    }
+}
+
+/// Unix time of a local "06:45 AM" on `date` in `tz`
+fn local_time(tz: chrono_tz::Tz, date: &str, time: &str) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let time = NaiveTime::parse_from_str(time, "%I:%M %p").ok()?;
+    tz.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|dt| dt.timestamp())
+}
+
+fn parse_timestamp(s: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.timestamp())
+        .unwrap_or(0)
+}
+
+fn to_response(data: WeatherApiResponse, location: GeoLocation, units: Units) -> ForecastResponse {
+    let temp = |c: f64| units.temperature_from_celsius(c);
+    let speed = |kph: f64| units.speed_from_ms(kph / 3.6);
+    let tz: chrono_tz::Tz = data.location.tz_id.parse().unwrap_or(chrono_tz::UTC);
+
+    let c = data.current;
+    let (description, icon, condition_id, condition_main) = describe(c.condition, c.is_day == 1);
+    let current = CurrentWeatherResponse {
+        timestamp: c.last_updated_epoch,
+        temperature: temp(c.temp_c),
+        feels_like: temp(c.feelslike_c),
+        humidity: c.humidity,
+        pressure: c.pressure_mb.round() as u32,
+        uv_index: c.uv,
+        clouds: c.cloud,
+        visibility: Some((c.vis_km * 1000.0).round() as u32),
+        wind_speed: speed(c.wind_kph),
+        wind_direction: c.wind_degree,
+        wind_gust: c.gust_kph.map(speed),
+        description,
+        icon,
+        condition_id,
+        condition_main,
+        local_time: None,
+        sunrise_local: None,
+        sunset_local: None,
+        sunrise: None,
+        sunset: None,
+    };
+
+    let mut hourly = Vec::new();
+    let mut daily = Vec::new();
+    for day in data.forecast.map(|f| f.forecastday).unwrap_or_default() {
+        for h in day.hour {
+            let (description, icon, condition_id, condition_main) =
+                describe(h.condition, h.is_day == 1);
+            hourly.push(HourlyForecastResponse {
+                timestamp: h.time_epoch,
+                temperature: temp(h.temp_c),
+                feels_like: temp(h.feelslike_c),
+                humidity: h.humidity,
+                pressure: h.pressure_mb.round() as u32,
+                uv_index: h.uv,
+                clouds: h.cloud,
+                wind_speed: speed(h.wind_kph),
+                wind_direction: h.wind_degree,
+                wind_gust: h.gust_kph.map(speed),
+                precipitation_probability: h.chance_of_rain.max(h.chance_of_snow) / 100.0,
+                rain_volume: Some(h.precip_mm).filter(|&r| r > 0.0),
+                snow_volume: Some(h.snow_cm).filter(|&s| s > 0.0).map(|cm| cm * 10.0),
+                description,
+                icon,
+                condition_id,
+                condition_main,
+                local_time: None,
+                is_day: Some(h.is_day == 1),
+            });
+        }
+
+        let d = day.day;
+        let astro = day.astro;
+        let (description, icon, condition_id, condition_main) = describe(d.condition, true);
+        daily.push(DailyForecastResponse {
+            timestamp: day.date_epoch,
+            sunrise: local_time(tz, &day.date, &astro.sunrise).unwrap_or_default(),
+            sunset: local_time(tz, &day.date, &astro.sunset).unwrap_or_default(),
+            moonrise: local_time(tz, &day.date, &astro.moonrise),
+            moonset: local_time(tz, &day.date, &astro.moonset),
+            moon_phase: moon_phase(&astro.moon_phase),
+            summary: None,
+            temp_min: temp(d.mintemp_c),
+            temp_max: temp(d.maxtemp_c),
+            temp_day: temp(d.maxtemp_c),
+            temp_night: temp(d.mintemp_c),
+            temp_morning: temp(d.avgtemp_c),
+            temp_evening: temp(d.avgtemp_c),
+            feels_like_day: temp(d.maxtemp_c),
+            feels_like_night: temp(d.mintemp_c),
+            humidity: d.avghumidity.round() as u32,
+            pressure: 0,
+            uv_index: d.uv,
+            clouds: 0,
+            wind_speed: speed(d.maxwind_kph),
+            wind_direction: 0,
+            wind_gust: None,
+            precipitation_probability: d.daily_chance_of_rain.max(d.daily_chance_of_snow) / 100.0,
+            rain_volume: Some(d.totalprecip_mm).filter(|&r| r > 0.0),
+            snow_volume: Some(d.totalsnow_cm)
+                .filter(|&s| s > 0.0)
+                .map(|cm| cm * 10.0),
+            description,
+            icon,
+            condition_id,
+            condition_main,
+            local_time: None,
+            sunrise_local: None,
+            sunset_local: None,
+            sun_detail: None,
+        });
+    }
+    // WeatherAPI.com returns whole days; keep the next 48 hours
+    let now = current.timestamp - 3600;
+    hourly.retain(|h| h.timestamp >= now);
+    hourly.truncate(48);
+
+    let alerts = data
+        .alerts
+        .map(|a| a.alert)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| {
+            let (severity, urgency, certainty) = classify_alert(&a.event);
+            AlertResponse {
+                sender: a.headline.unwrap_or_else(|| "WeatherAPI.com".to_string()),
+                event: a.event,
+                start: a.effective.as_deref().map(parse_timestamp).unwrap_or(0),
+                end: a.expires.as_deref().map(parse_timestamp).unwrap_or(0),
+                description: a.desc,
+                tags: None,
+                severity,
+                urgency,
+                certainty,
+                instruction: a.instruction.filter(|i| !i.is_empty()),
+                geometry: None,
+            }
+        })
+        .collect();
+
+    ForecastResponse {
+        location: location.into(),
+        timezone: data.location.tz_id,
+        current: Some(current),
+        hourly,
+        daily,
+        alerts,
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for WeatherApi {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::WeatherApi
+    }
+
+    async fn forecast(
+        &self,
+        location: GeoLocation,
+        parts: ForecastParts,
+        units: Units,
+        lang: &str,
+    ) -> Result<ForecastResponse, ForecastError> {
+        let days = match parts {
+            ForecastParts::Alerts => "1",
+            ForecastParts::Hourly => "3",
+            ForecastParts::Full | ForecastParts::Daily => "8",
+        };
+        let response = self
+            .client
+            .get(WEATHERAPI_FORECAST_URL)
+            .query(&[
+                ("key", self.api_key.as_str()),
+                ("q", &format!("{},{}", location.lat, location.lon)),
+                ("days", days),
+                ("lang", lang),
+                ("alerts", "yes"),
+                ("aqi", "no"),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(UpstreamFailure::read("WeatherAPI.com", response)
+                .await
+                .into());
+        }
+        let data: WeatherApiResponse = response.json().await?;
+        Ok(to_response(data, location, units))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_response() {
+        let hour = |epoch: i64, code: u32| {
+            serde_json::json!({
+                "time_epoch": epoch, "temp_c": 10.0, "feelslike_c": 8.0, "is_day": 0,
+                "condition": {"text": "Patchy rain nearby ", "code": code},
+                "wind_kph": 36.0, "wind_degree": 90, "pressure_mb": 1012.0,
+                "precip_mm": 0.3, "snow_cm": 0.0, "humidity": 80, "cloud": 75,
+                "chance_of_rain": 70, "chance_of_snow": 0, "uv": 0.0
+            })
+        };
+        let data: WeatherApiResponse = serde_json::from_value(serde_json::json!({
+            "location": {"name": "London", "tz_id": "Europe/London"},
+            "current": {
+                "last_updated_epoch": 1717250400, "temp_c": 12.0, "feelslike_c": 11.0,
+                "is_day": 1, "condition": {"text": "Sunny", "code": 1000},
+                "wind_kph": 18.0, "wind_degree": 250, "pressure_mb": 1016.0,
+                "humidity": 70, "cloud": 0, "vis_km": 10.0, "uv": 4.0
+            },
+            "forecast": {"forecastday": [{
+                "date": "2024-06-01", "date_epoch": 1717200000,
+                "day": {
+                    "maxtemp_c": 18.0, "mintemp_c": 9.0, "avgtemp_c": 13.5,
+                    "maxwind_kph": 25.2, "totalprecip_mm": 1.2, "avghumidity": 72,
+                    "daily_chance_of_rain": 80, "daily_chance_of_snow": 0,
+                    "condition": {"text": "Patchy rain nearby", "code": 1063}, "uv": 5.0
+                },
+                "astro": {
+                    "sunrise": "04:44 AM", "sunset": "09:11 PM", "moonrise": "01:58 AM",
+                    "moonset": "No moonset", "moon_phase": "Waning Crescent"
+                },
+                "hour": [hour(1717246800, 1063), hour(1717250400, 1000)]
+            }]},
+            "alerts": {"alert": [{
+                "headline": "Met Office", "event": "Yellow warning for rain",
+                "desc": "Heavy rain", "effective": "2024-06-01T06:00:00+00:00",
+                "expires": "2024-06-01T18:00:00+00:00"
+            }]}
+        }))
+        .unwrap();
+        let location = GeoLocation {
+            name: "London".to_string(),
+            lat: 51.51,
+            lon: -0.13,
+            country: "GB".to_string(),
+            state: None,
+        };
+        let forecast = to_response(data, location, Units::Metric);
+
+        assert_eq!(forecast.timezone, "Europe/London");
+        let current = forecast.current.unwrap();
+        assert!((current.wind_speed - 5.0).abs() < 1e-9);
+        assert_eq!(current.visibility, Some(10000));
+        assert_eq!(current.icon, "01d");
+
+        assert_eq!(forecast.hourly.len(), 2);
+        assert_eq!(forecast.hourly[0].description, "patchy rain nearby");
+        assert_eq!(forecast.hourly[0].icon, "10n");
+        assert_eq!(forecast.hourly[0].precipitation_probability, 0.7);
+        assert!((forecast.hourly[0].wind_speed - 10.0).abs() < 1e-9);
+
+        let today = &forecast.daily[0];
+        // 04:44 BST
+        assert_eq!(today.sunrise, 1717213440);
+        assert!(today.moonrise.is_some());
+        assert_eq!(today.moonset, None);
+        assert_eq!(today.moon_phase, 0.875);
+        assert_eq!(today.condition_id, Some(500));
+
+        assert_eq!(forecast.alerts.len(), 1);
+        assert_eq!(forecast.alerts[0].start, 1717221600);
+    }
+}
@@ -1,9 +1,22 @@
 use axum::extract::{Query, State};
 use axum::Json;
 
+use crate::error::ErrorResponse;
+use crate::extractors::{Coords, CoordsQuery};
+use crate::forecast::ForecastError;
 use crate::geocode::models::{make_location_key, round_coord, GeocodeQuery, GeocodeResponse};
 use crate::AppState;
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/geocode",
+    tag = "geocode",
+    params(GeocodeQuery),
+    responses(
+        (status = 200, description = "Resolved location", body = GeocodeResponse),
+        (status = 400, description = "Location could not be geocoded", body = String)
+    )
+)]
 pub async fn geocode(
     State(state): State<AppState>,
     Query(query): Query<GeocodeQuery>,
@@ -31,3 +44,35 @@ pub async fn geocode(
         location_key: make_location_key(lat, lon),
     }))
 }
+
+/// Name the place at a pair of coordinates, using OpenWeatherMap reverse
+/// geocoding. The returned coordinates are the requested ones, rounded.
+///
+/// GET /geocode/reverse?lat={lat}&lon={lon}
+#[utoipa::path(
+    get,
+    path = "/api/v1/geocode/reverse",
+    tag = "geocode",
+    params(CoordsQuery),
+    responses(
+        (status = 200, description = "Place at the coordinates", body = GeocodeResponse),
+        (status = 400, description = "Missing or invalid coordinates", body = ErrorResponse),
+        (status = 404, description = "No named place near the coordinates", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
+pub async fn reverse_geocode(
+    State(state): State<AppState>,
+    coords: Coords,
+) -> Result<Json<GeocodeResponse>, ForecastError> {
+    let geo = state.forecast_service.geocode(&coords.as_query()).await?;
+
+    Ok(Json(GeocodeResponse {
+        name: geo.name,
+        lat: coords.lat,
+        lon: coords.lon,
+        country: geo.country,
+        state: geo.state,
+        location_key: coords.as_query(),
+    }))
+}
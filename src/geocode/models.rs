@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Deserialize)]
+use crate::forecast::models::GeoLocation;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GeocodeQuery {
+    /// City name, "City,Country", or US zip code
     pub q: String,
 }
 
@@ -25,3 +29,33 @@ pub fn round_coord(val: f64) -> f64 {
 pub fn make_location_key(lat: f64, lon: f64) -> String {
     format!("{:.2},{:.2}", round_coord(lat), round_coord(lon))
 }
+
+/// Prefix of a location string holding raw coordinates ("geo:41.88,-87.63",
+/// as in RFC 5870). Unlike a plain "lat,lon", which is reverse geocoded to
+/// a place name, these resolve without any geocoding call.
+const GEO_PREFIX: &str = "geo:";
+
+/// "geo:lat,lon" for coordinates
+pub fn geo_location(lat: f64, lon: f64) -> String {
+    format!("{}{}", GEO_PREFIX, make_location_key(lat, lon))
+}
+
+/// The coordinates of a "geo:lat,lon" location string, if in range
+pub fn parse_geo_location(location: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = location.trim().strip_prefix(GEO_PREFIX)?.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// Resolve a "geo:lat,lon" location string, named by its coordinates
+pub fn resolve_geo_location(location: &str) -> Option<GeoLocation> {
+    let (lat, lon) = parse_geo_location(location)?;
+    Some(GeoLocation {
+        name: make_location_key(lat, lon),
+        lat,
+        lon,
+        country: String::new(),
+        state: None,
+    })
+}
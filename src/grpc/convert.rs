@@ -0,0 +1,141 @@
+use super::proto;
+use crate::forecast::models::{
+    AlertResponse, CurrentWeatherResponse, DailyForecastResponse, ForecastResponse,
+    HourlyForecastResponse, LocationInfo,
+};
+use crate::history::models::{DailyHistoryResponse, DailyHistorySummary};
+use crate::scheduler::ForecastJob;
+
+impl From<LocationInfo> for proto::Location {
+    fn from(l: LocationInfo) -> Self {
+        Self {
+            city: l.city,
+            country: l.country,
+            state: l.state,
+            lat: l.lat,
+            lon: l.lon,
+        }
+    }
+}
+
+impl From<CurrentWeatherResponse> for proto::CurrentConditions {
+    fn from(c: CurrentWeatherResponse) -> Self {
+        Self {
+            timestamp: c.timestamp,
+            temperature: c.temperature,
+            feels_like: c.feels_like,
+            humidity: c.humidity,
+            pressure: c.pressure,
+            uv_index: c.uv_index,
+            clouds: c.clouds,
+            wind_speed: c.wind_speed,
+            wind_direction: c.wind_direction,
+            wind_gust: c.wind_gust,
+            description: c.description,
+            icon: c.icon,
+        }
+    }
+}
+
+impl From<HourlyForecastResponse> for proto::HourlyForecast {
+    fn from(h: HourlyForecastResponse) -> Self {
+        Self {
+            timestamp: h.timestamp,
+            temperature: h.temperature,
+            feels_like: h.feels_like,
+            humidity: h.humidity,
+            wind_speed: h.wind_speed,
+            wind_direction: h.wind_direction,
+            precipitation_probability: h.precipitation_probability,
+            description: h.description,
+            icon: h.icon,
+        }
+    }
+}
+
+impl From<DailyForecastResponse> for proto::DailyForecast {
+    fn from(d: DailyForecastResponse) -> Self {
+        Self {
+            timestamp: d.timestamp,
+            sunrise: d.sunrise,
+            sunset: d.sunset,
+            temp_min: d.temp_min,
+            temp_max: d.temp_max,
+            humidity: d.humidity,
+            wind_speed: d.wind_speed,
+            precipitation_probability: d.precipitation_probability,
+            rain_volume: d.rain_volume,
+            snow_volume: d.snow_volume,
+            summary: d.summary,
+            description: d.description,
+            icon: d.icon,
+        }
+    }
+}
+
+impl From<AlertResponse> for proto::Alert {
+    fn from(a: AlertResponse) -> Self {
+        Self {
+            severity: format!("{:?}", a.severity).to_lowercase(),
+            sender: a.sender,
+            event: a.event,
+            start: a.start,
+            end: a.end,
+            description: a.description,
+        }
+    }
+}
+
+impl From<ForecastResponse> for proto::ForecastReply {
+    fn from(f: ForecastResponse) -> Self {
+        Self {
+            location: Some(f.location.into()),
+            timezone: f.timezone,
+            current: f.current.map(Into::into),
+            hourly: f.hourly.into_iter().map(Into::into).collect(),
+            daily: f.daily.into_iter().map(Into::into).collect(),
+            alerts: f.alerts.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<DailyHistorySummary> for proto::DailyHistory {
+    fn from(d: DailyHistorySummary) -> Self {
+        Self {
+            date: d.date,
+            temp_min: d.temp_min,
+            temp_max: d.temp_max,
+            temp_avg: d.temp_avg,
+            humidity_avg: d.humidity_avg,
+            wind_speed_avg: d.wind_speed_avg,
+            precipitation_total: d.precipitation_total,
+            dominant_condition: d.dominant_condition,
+        }
+    }
+}
+
+impl From<DailyHistoryResponse> for proto::DailyHistoryReply {
+    fn from(h: DailyHistoryResponse) -> Self {
+        Self {
+            city: h.city,
+            units: h.units,
+            period: h.period,
+            days: h.days.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ForecastJob> for proto::Job {
+    fn from(j: ForecastJob) -> Self {
+        Self {
+            id: j.id,
+            name: j.name,
+            city: j.city,
+            units: j.units,
+            cron: j.cron,
+            timezone: j.timezone,
+            enabled: j.enabled,
+            owner: j.owner,
+        }
+    }
+}
@@ -0,0 +1,239 @@
+mod convert;
+
+use std::future::Future;
+use std::net::SocketAddr;
+
+use axum::http::StatusCode;
+use tonic::{transport::Server, Code, Request, Response, Status};
+
+use crate::auth::Principal;
+use crate::error::HttpError;
+use crate::units::Units;
+use crate::AppState;
+
+use proto::forecast_server::{Forecast, ForecastServer};
+use proto::history_server::{History, HistoryServer};
+use proto::scheduler_server::{Scheduler, SchedulerServer};
+
+pub mod proto {
+    tonic::include_proto!("weathrs.v1");
+}
+
+/// Map a service error onto the closest gRPC status
+fn to_status<E: HttpError>(err: E) -> Status {
+    let code = match err.status_code() {
+        StatusCode::BAD_REQUEST => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    Status::new(code, err.to_string())
+}
+
+/// Requested units, else the default for `location`. Unknown units are
+/// rejected rather than passed on to OpenWeatherMap.
+fn units(state: &AppState, requested: Option<String>, location: &str) -> Result<Units, Status> {
+    match requested {
+        Some(units) => units
+            .parse()
+            .map_err(|err: crate::units::UnknownUnits| Status::invalid_argument(err.to_string())),
+        None => Ok(state.default_units(location)),
+    }
+}
+
+/// Count the request against its client key quota (`x-api-key` metadata)
+fn admit<T>(state: &AppState, request: &Request<T>) -> Result<(), Status> {
+    let provided = request
+        .metadata()
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok());
+    state
+        .client_keys
+        .admit(provided, chrono::Utc::now())
+        .map_err(to_status)
+}
+
+/// Verify the bearer token in `authorization` metadata. `None` when JWT auth
+/// is disabled.
+fn authenticate<T>(state: &AppState, request: &Request<T>) -> Result<Option<Principal>, Status> {
+    let Some(ref keys) = state.jwt_keys else {
+        return Ok(None);
+    };
+    let token = request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| to_status(crate::auth::AuthError::MissingToken))?;
+    keys.verify(token.trim(), chrono::Utc::now().timestamp())
+        .map(Some)
+        .map_err(to_status)
+}
+
+struct ForecastGrpc {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl Forecast for ForecastGrpc {
+    async fn get_forecast(
+        &self,
+        request: Request<proto::ForecastRequest>,
+    ) -> Result<Response<proto::ForecastReply>, Status> {
+        admit(&self.state, &request)?;
+        let request = request.into_inner();
+        let units = units(&self.state, request.units, &request.location)?;
+        let forecast = self
+            .state
+            .forecast_service
+            .get_forecast(&request.location, units.as_str())
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(forecast.into()))
+    }
+
+    async fn get_daily_forecast(
+        &self,
+        request: Request<proto::ForecastRequest>,
+    ) -> Result<Response<proto::ForecastReply>, Status> {
+        admit(&self.state, &request)?;
+        let request = request.into_inner();
+        let units = units(&self.state, request.units, &request.location)?;
+        let forecast = self
+            .state
+            .forecast_service
+            .get_daily_forecast(&request.location, units.as_str())
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(forecast.into()))
+    }
+}
+
+struct HistoryGrpc {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl History for HistoryGrpc {
+    async fn get_daily_history(
+        &self,
+        request: Request<proto::HistoryRequest>,
+    ) -> Result<Response<proto::DailyHistoryReply>, Status> {
+        admit(&self.state, &request)?;
+        let request = request.into_inner();
+        let units = units(&self.state, request.units, &request.location)?;
+        let history = self
+            .state
+            .history_service
+            .get_daily_history(
+                &request.location,
+                request.start,
+                request.end,
+                units.as_str(),
+            )
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(history.into()))
+    }
+}
+
+struct SchedulerGrpc {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl Scheduler for SchedulerGrpc {
+    async fn list_jobs(
+        &self,
+        request: Request<proto::ListJobsRequest>,
+    ) -> Result<Response<proto::ListJobsReply>, Status> {
+        let principal = authenticate(&self.state, &request)?;
+        let jobs = self
+            .state
+            .scheduler_service
+            .get_jobs()
+            .await
+            .into_iter()
+            .filter(|job| {
+                principal
+                    .as_ref()
+                    .is_none_or(|p| p.can_access(job.owner.as_deref()))
+            })
+            .map(Into::into)
+            .collect();
+        Ok(Response::new(proto::ListJobsReply { jobs }))
+    }
+
+    async fn trigger_forecast(
+        &self,
+        request: Request<proto::TriggerRequest>,
+    ) -> Result<Response<proto::TriggerReply>, Status> {
+        let principal = authenticate(&self.state, &request)?;
+        let owner = principal.and_then(|p| p.owner_filter());
+        let request = request.into_inner();
+        if request.city.trim().is_empty() {
+            return Err(Status::invalid_argument("city is required"));
+        }
+        let units = units(&self.state, request.units, &request.city)?;
+
+        self.state
+            .scheduler_service
+            .run_now(&request.city, units.as_str(), owner.as_deref())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(proto::TriggerReply {
+            message: format!("Forecast triggered for {}", request.city),
+        }))
+    }
+}
+
+/// Serve the Forecast, History, and Scheduler gRPC services on `addr` until
+/// `shutdown` completes. History and Scheduler are left out when their
+/// subsystems are disabled.
+pub async fn serve(
+    state: AppState,
+    addr: SocketAddr,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    tracing::info!("gRPC server listening on {}", addr);
+    let features = state.config.features.clone();
+    Server::builder()
+        .add_service(ForecastServer::new(ForecastGrpc {
+            state: state.clone(),
+        }))
+        .add_optional_service(features.history.then(|| {
+            HistoryServer::new(HistoryGrpc {
+                state: state.clone(),
+            })
+        }))
+        .add_optional_service(
+            features
+                .scheduler
+                .then(|| SchedulerServer::new(SchedulerGrpc { state })),
+        )
+        .serve_with_shutdown(addr, shutdown)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_keys::ClientKeyError;
+
+    #[test]
+    fn test_to_status_maps_http_errors() {
+        let status = to_status(ClientKeyError::QuotaExceeded {
+            name: "friend".to_string(),
+            retry_after: 60,
+        });
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(
+            to_status(crate::auth::AuthError::Forbidden).code(),
+            Code::PermissionDenied
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use chrono::{Datelike, Duration, NaiveTime, Utc};
+use tokio_cron_scheduler::Job;
+
+use super::HistoryService;
+use crate::config::HistoryArchiveConfig;
+use crate::scheduler::{SchedulerError, SchedulerService};
+
+/// Start of the UTC month holding the day `retention_days` ago. Everything
+/// before it is archived, so only whole months move.
+fn archive_cutoff(now: chrono::DateTime<Utc>, retention_days: u32) -> i64 {
+    let oldest_kept = (now - Duration::days(i64::from(retention_days))).date_naive();
+    oldest_kept
+        .with_day(1)
+        .unwrap_or(oldest_kept)
+        .and_time(NaiveTime::MIN)
+        .and_utc()
+        .timestamp()
+}
+
+/// Register the history archival cron job on the scheduler.
+pub async fn schedule_history_archive_job(
+    scheduler_service: Arc<SchedulerService>,
+    history_service: Arc<HistoryService>,
+    config: HistoryArchiveConfig,
+    retention_days: u32,
+) -> Result<(), SchedulerError> {
+    tracing::info!(cron = %config.cron, retention_days, "Scheduling history archive job");
+
+    let scheduler_for_closure = Arc::clone(&scheduler_service);
+
+    let job = Job::new_async(config.cron.as_str(), move |_uuid, _lock| {
+        let history_service = Arc::clone(&history_service);
+        let scheduler_service = Arc::clone(&scheduler_for_closure);
+
+        Box::pin(async move {
+            let Some(_run) = scheduler_service.begin_run() else {
+                return;
+            };
+            let cutoff = archive_cutoff(Utc::now(), retention_days);
+            match history_service.archive_history(cutoff).await {
+                Ok(moved) => tracing::info!(rows = moved, "History archive run finished"),
+                Err(e) => tracing::error!(error = %e, "History archive run failed"),
+            }
+        })
+    })
+    .map_err(|e| SchedulerError::Scheduler(e.to_string()))?;
+
+    scheduler_service.add_system_job(job).await?;
+
+    tracing::info!("History archive job scheduled");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_cutoff_is_month_start() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-06-15T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        // 90 days before is 2024-03-17; March stays live
+        let march = chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+            .unwrap()
+            .timestamp();
+        assert_eq!(archive_cutoff(now, 90), march);
+    }
+}
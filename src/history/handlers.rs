@@ -1,17 +1,36 @@
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
     Json,
 };
 
 use super::models::{
-    DailyHistoryResponse, HistoryQuery, HistoryResponse, TrendResponse, TrendsQuery,
+    AccuracyQuery, AccuracyResponse, AlertHistoryQuery, AlertHistoryResponse,
+    AnomalyHistoryResponse, ArchivedForecastQuery, ArchivedForecastResponse, DailyHistoryResponse,
+    ExportFormat, HistoryArchivesResponse, HistoryExportQuery, HistoryPageQuery, HistoryQuery,
+    HistoryResponse, TrendResponse, TrendsQuery, DEFAULT_HISTORY_PER_PAGE,
 };
 use super::service::HistoryError;
+use crate::error::ErrorResponse;
+use crate::extractors::{CityParam, UnitsParam};
+use crate::units::Units;
 use crate::AppState;
 
 /// Delete all history records for a location key
 ///
 /// DELETE /history/location/{location_key}
+#[utoipa::path(
+    delete,
+    path = "/api/v1/history/location/{location_key}",
+    tag = "history",
+    params(("location_key" = String, Path, description = "Location key from /geocode")),
+    responses(
+        (status = 200, description = "Records deleted", body = Object),
+        (status = 403, description = "Admin only", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
 pub async fn delete_history(
     State(state): State<AppState>,
     Path(location_key): Path<String>,
@@ -30,6 +49,16 @@ pub async fn delete_history(
 /// Normalize duplicate city names across location keys
 ///
 /// POST /history/cleanup
+#[utoipa::path(
+    post,
+    path = "/api/v1/history/cleanup",
+    tag = "history",
+    responses(
+        (status = 200, description = "Duplicate city names normalized", body = Object),
+        (status = 403, description = "Admin only", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
 pub async fn cleanup_history(
     State(state): State<AppState>,
 ) -> Result<Json<serde_json::Value>, HistoryError> {
@@ -43,35 +72,295 @@ pub async fn cleanup_history(
 
 /// Get hourly history data for a city
 ///
-/// GET /history/{city}?start={unix}&end={unix}&units={units}
+/// GET /history/{city}?start={unix}&end={unix}&units={units}&page={n}&per_page={n}
+/// GET /history?lat={lat}&lon={lon}&start={unix}&end={unix}
+///
+/// Without a city or coordinates, the default location is used.
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), HistoryQuery, ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)"), HistoryPageQuery),
+    responses(
+        (status = 200, description = "Hourly history", body = HistoryResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
 pub async fn get_history(
     State(state): State<AppState>,
-    Path(city): Path<String>,
+    CityParam(city): CityParam,
     Query(query): Query<HistoryQuery>,
+    UnitsParam(units): UnitsParam,
+    Query(paging): Query<HistoryPageQuery>,
 ) -> Result<Json<HistoryResponse>, HistoryError> {
-    let units = query.units.unwrap_or_else(|| state.config.units.clone());
+    let city = city.unwrap_or_else(|| state.config.default_location());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
 
     let response = state
         .history_service
-        .get_history(&city, query.start, query.end, &units)
+        .get_history(&city, query.start, query.end, units.as_str())
+        .await?;
+
+    if paging.page.is_none() && paging.per_page.is_none() {
+        return Ok(Json(response));
+    }
+    Ok(Json(response.paginate(
+        paging.page.unwrap_or(1),
+        paging.per_page.unwrap_or(DEFAULT_HISTORY_PER_PAGE),
+    )))
+}
+
+/// Get weather alerts observed for a city
+///
+/// GET /history/{city}/alerts?start={unix}&end={unix}&event={name}
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/alerts",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), AlertHistoryQuery),
+    responses(
+        (status = 200, description = "Alerts observed for the city", body = AlertHistoryResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_alert_history(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+    Query(query): Query<AlertHistoryQuery>,
+) -> Result<Json<AlertHistoryResponse>, HistoryError> {
+    let response = state
+        .history_service
+        .get_alert_history(&city, query.start, query.end, query.event.as_deref())
         .await?;
 
     Ok(Json(response))
 }
 
+/// Get readings for a city kept out of history and notifications by the
+/// sanity checks (default: the last 30 days)
+///
+/// GET /history/{city}/anomalies?start={unix}&end={unix}
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/anomalies",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), HistoryQuery),
+    responses(
+        (status = 200, description = "Quarantined readings for the city, oldest first", body = AnomalyHistoryResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_anomalies(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<AnomalyHistoryResponse>, HistoryError> {
+    let response = state
+        .history_service
+        .get_anomalies(&city, query.start, query.end)
+        .await?;
+
+    Ok(Json(response))
+}
+
+/// Get mean absolute error of forecasts issued by scheduled jobs, 1, 3,
+/// and 7 days ahead, against recorded history
+///
+/// GET /history/{city}/accuracy?days={n}&units={units}
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/accuracy",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), AccuracyQuery, ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)")),
+    responses(
+        (status = 200, description = "Forecast error by lead time", body = AccuracyResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_accuracy(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+    Query(query): Query<AccuracyQuery>,
+    UnitsParam(units): UnitsParam,
+) -> Result<Json<AccuracyResponse>, HistoryError> {
+    let units = units.unwrap_or_else(|| state.default_units(&city));
+
+    let response = state
+        .history_service
+        .get_accuracy(&city, query.days, units.as_str())
+        .await?;
+
+    Ok(Json(response))
+}
+
+/// Get what the forecast said at a past time: the latest forecast fetched
+/// for the city at or before `at`
+///
+/// GET /history/{city}/forecast?at={unix}
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/forecast",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), ArchivedForecastQuery),
+    responses(
+        (status = 200, description = "Archived forecast", body = ArchivedForecastResponse),
+        (status = 404, description = "City not found or nothing archived by then", body = ErrorResponse)
+    )
+)]
+pub async fn get_archived_forecast(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+    Query(query): Query<ArchivedForecastQuery>,
+) -> Result<Json<ArchivedForecastResponse>, HistoryError> {
+    let response = state
+        .history_service
+        .get_archived_forecast(&city, query.at)
+        .await?;
+
+    Ok(Json(response))
+}
+
+/// List months of a city's hourly history moved out of the live table.
+/// These no longer appear in /history/{city} and aren't refetched.
+///
+/// GET /history/{city}/archives
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/archives",
+    tag = "history",
+    params(("city" = String, Path, description = "City name")),
+    responses(
+        (status = 200, description = "Archived months, oldest first", body = HistoryArchivesResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
+pub async fn list_history_archives(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+) -> Result<Json<HistoryArchivesResponse>, HistoryError> {
+    let response = state.history_service.list_history_archives(&city).await?;
+
+    Ok(Json(response))
+}
+
+/// Download an archived month of hourly history as gzip-compressed CSV,
+/// in the same columns as the scheduled history export
+///
+/// GET /history/{city}/archives/{month}
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/archives/{month}",
+    tag = "history",
+    params(
+        ("city" = String, Path, description = "City name"),
+        ("month" = String, Path, description = "UTC month, YYYY-MM")
+    ),
+    responses(
+        (status = 200, description = "Gzip-compressed CSV", content_type = "application/gzip", body = Vec<u8>),
+        (status = 404, description = "City not found or month not archived", body = ErrorResponse)
+    )
+)]
+pub async fn download_history_archive(
+    State(state): State<AppState>,
+    Path((city, month)): Path<(String, String)>,
+) -> Result<(HeaderMap, Vec<u8>), HistoryError> {
+    let (file_name, data) = state
+        .history_service
+        .get_history_archive(&city, &month)
+        .await?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/gzip"),
+    );
+    if let Ok(disposition) =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file_name))
+    {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+
+    Ok((headers, data))
+}
+
+/// Export a city's stored hourly history as JSON or CSV
+///
+/// GET /history/{city}/export?start={unix}&end={unix}&units={units}&format={json|csv}
+///
+/// The body is streamed while rows are read, so long ranges don't build up
+/// in memory or run into the request timeout. Unlike GET /history/{city},
+/// missing hours aren't backfilled.
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/export",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), HistoryExportQuery, ("units" = Option<Units>, Query, description = "metric, imperial, or standard for JSON (default from config); CSV is always metric")),
+    responses(
+        (status = 200, description = "History in the HistoryResponse shape, or CSV with the scheduled export's columns", body = HistoryResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
+pub async fn export_history(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+    Query(query): Query<HistoryExportQuery>,
+    UnitsParam(units): UnitsParam,
+) -> Result<(HeaderMap, Body), HistoryError> {
+    let units = units.unwrap_or_else(|| state.default_units(&city));
+    let format = query.format.unwrap_or_default();
+
+    let (file_name, chunks) = state
+        .history_service
+        .export_history(&city, query.start, query.end, units.as_str(), format)
+        .await?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(match format {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }),
+    );
+    if let Ok(disposition) =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file_name))
+    {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+
+    Ok((headers, Body::from_stream(chunks)))
+}
+
 /// Get daily aggregated history for a city
 ///
 /// GET /history/{city}/daily?start={unix}&end={unix}&units={units}
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/daily",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), HistoryQuery, ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)")),
+    responses(
+        (status = 200, description = "Daily aggregates", body = DailyHistoryResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
 pub async fn get_daily_history(
     State(state): State<AppState>,
     Path(city): Path<String>,
     Query(query): Query<HistoryQuery>,
+    UnitsParam(units): UnitsParam,
 ) -> Result<Json<DailyHistoryResponse>, HistoryError> {
-    let units = query.units.unwrap_or_else(|| state.config.units.clone());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
 
     let response = state
         .history_service
-        .get_daily_history(&city, query.start, query.end, &units)
+        .get_daily_history(&city, query.start, query.end, units.as_str())
         .await?;
 
     Ok(Json(response))
@@ -80,17 +369,29 @@ pub async fn get_daily_history(
 /// Get weather trends with summary statistics
 ///
 /// GET /history/{city}/trends?period=7d|30d|90d&units={units}
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/{city}/trends",
+    tag = "history",
+    params(("city" = String, Path, description = "City name"), TrendsQuery, ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)")),
+    responses(
+        (status = 200, description = "Trend summary", body = TrendResponse),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse)
+    )
+)]
 pub async fn get_trends(
     State(state): State<AppState>,
     Path(city): Path<String>,
     Query(query): Query<TrendsQuery>,
+    UnitsParam(units): UnitsParam,
 ) -> Result<Json<TrendResponse>, HistoryError> {
-    let units = query.units.unwrap_or_else(|| state.config.units.clone());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
     let period = query.period.unwrap_or_else(|| "7d".to_string());
 
     let response = state
         .history_service
-        .get_trends(&city, &period, &units, query.start, query.end)
+        .get_trends(&city, &period, units.as_str(), query.start, query.end)
         .await?;
 
     Ok(Json(response))
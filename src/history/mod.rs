@@ -1,5 +1,10 @@
+mod archive;
 pub mod handlers;
 pub mod models;
+mod observations;
 mod service;
 
-pub use service::HistoryService;
+pub use archive::schedule_history_archive_job;
+pub use observations::{start_observation_task, ObservationSources};
+
+pub use service::{start_forecast_archive_prune_task, HistoryError, HistoryService, STORAGE_UNITS};
@@ -1,5 +1,9 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::forecast::models::{AlertSeverity, ForecastResponse};
 
 // ============================================================================
 // API Response Models (External - what we return to clients)
@@ -28,6 +32,19 @@ pub struct HistoryDataPoint {
     pub rain_1h: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub snow_1h: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_gust: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dew_point: Option<f64>,
+    /// UV index, when OWM reports one for the hour
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uvi: Option<f64>,
+    /// OWM weather condition code (e.g. 501 for moderate rain)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition_id: Option<i32>,
+    /// "timemachine" for hourly history from OWM, "observation" for a sample
+    /// of current conditions
+    pub source: String,
 }
 
 /// Aggregated daily history summary
@@ -40,6 +57,8 @@ pub struct DailyHistorySummary {
     pub humidity_avg: f64,
     pub wind_speed_avg: f64,
     pub precipitation_total: f64,
+    /// Part of `precipitation_total` that fell as snow
+    pub snow_total: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dominant_condition: Option<String>,
 }
@@ -51,6 +70,49 @@ pub struct HistoryResponse {
     pub units: String,
     pub period: String,
     pub data_points: Vec<HistoryDataPoint>,
+    /// Present when the request asked for a page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<HistoryPagination>,
+}
+
+impl HistoryResponse {
+    /// Keep only the requested page of data points, oldest first
+    pub fn paginate(mut self, page: u32, per_page: u32) -> Self {
+        let total = self.data_points.len();
+        let per_page = per_page.clamp(1, MAX_HISTORY_PER_PAGE);
+        let page = page.max(1);
+        let skip = (page as usize - 1).saturating_mul(per_page as usize);
+        self.data_points = self
+            .data_points
+            .into_iter()
+            .skip(skip)
+            .take(per_page as usize)
+            .collect();
+        self.pagination = Some(HistoryPagination {
+            page,
+            per_page,
+            total,
+            total_pages: total.div_ceil(per_page as usize) as u32,
+        });
+        self
+    }
+}
+
+/// Points per page when only `page` is given
+pub const DEFAULT_HISTORY_PER_PAGE: u32 = 500;
+
+/// Largest page of hourly history
+pub const MAX_HISTORY_PER_PAGE: u32 = 1000;
+
+/// Where a page of hourly history sits in the full range
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryPagination {
+    /// 1-based page number
+    pub page: u32,
+    pub per_page: u32,
+    /// Data points across all pages
+    pub total: usize,
+    pub total_pages: u32,
 }
 
 /// Response wrapper for daily history summaries
@@ -72,6 +134,116 @@ pub struct TrendResponse {
     pub summary: TrendSummary,
 }
 
+/// A weather alert observed for a location
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AlertHistoryEntry {
+    pub event: String,
+    pub sender: String,
+    pub severity: AlertSeverity,
+    pub start: i64,
+    pub end: i64,
+    pub description: String,
+    /// When weathrs first saw this alert
+    pub first_seen: i64,
+}
+
+/// Response wrapper for alert history
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AlertHistoryResponse {
+    pub city: String,
+    pub period: String,
+    pub total: usize,
+    /// Number of alerts per event name (e.g. "Wind Advisory": 4)
+    pub counts: BTreeMap<String, usize>,
+    pub alerts: Vec<AlertHistoryEntry>,
+}
+
+/// A reading kept out of history or notifications by the sanity checks
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnomalyEntry {
+    /// timemachine, observation, or forecast
+    pub source: String,
+    /// Time of the reading
+    pub observed_at: i64,
+    /// When it was quarantined
+    pub detected_at: i64,
+    /// The checks that failed
+    pub reasons: Vec<String>,
+    /// The reading as it arrived (history rows in metric, forecast entries
+    /// in the job's units)
+    #[schema(value_type = Object)]
+    pub reading: serde_json::Value,
+}
+
+/// Response wrapper for quarantined readings
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnomalyHistoryResponse {
+    pub city: String,
+    pub period: String,
+    pub total: usize,
+    pub anomalies: Vec<AnomalyEntry>,
+}
+
+/// How far off issued forecasts were, for one lead time
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LeadAccuracy {
+    /// Days between the forecast run and the day forecast (1 = tomorrow)
+    pub lead_days: i64,
+    /// Days scored
+    pub samples: usize,
+    /// Mean absolute error of the daily high, in the requested units
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_max_mae: Option<f64>,
+    /// Mean absolute error of the daily low, in the requested units
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_min_mae: Option<f64>,
+    /// Mean absolute error of daily precipitation, mm
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub precipitation_mae: Option<f64>,
+}
+
+/// Response wrapper for forecast accuracy
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccuracyResponse {
+    pub city: String,
+    pub units: String,
+    pub period: String,
+    pub leads: Vec<LeadAccuracy>,
+}
+
+/// A month of hourly history moved out of the live table
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryArchiveEntry {
+    /// UTC calendar month, YYYY-MM
+    pub month: String,
+    pub rows: i64,
+    /// Compressed size
+    pub size_bytes: i64,
+    /// When the month was (last) archived
+    pub archived_at: i64,
+}
+
+/// Response wrapper for archived history months
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryArchivesResponse {
+    pub city: String,
+    pub location_key: String,
+    pub archives: Vec<HistoryArchiveEntry>,
+}
+
+/// A forecast as it was fetched from OWM
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArchivedForecastResponse {
+    pub city: String,
+    /// When the forecast was fetched
+    pub issued_at: i64,
+    /// Which fetch produced it: full, daily, or hourly
+    pub kind: String,
+    /// Units the forecast was fetched in
+    pub units: String,
+    pub forecast: ForecastResponse,
+}
+
 /// Summary of weather trends over a period
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TrendSummary {
@@ -80,7 +252,16 @@ pub struct TrendSummary {
     pub max_temp: TrendExtreme,
     pub min_temp: TrendExtreme,
     pub total_precipitation: f64,
+    /// Direction of daily precipitation totals over the period
+    pub precipitation_trend: String,
     pub avg_humidity: f64,
+    pub avg_wind_speed: f64,
+    /// Direction of daily average wind speed over the period
+    pub wind_trend: String,
+    /// Days with measurable rain
+    pub rainy_days: u32,
+    /// Days with measurable snow
+    pub snowy_days: u32,
 }
 
 /// A temperature extreme (max or min) with its date
@@ -121,6 +302,12 @@ pub struct TimemachineData {
     #[serde(default)]
     pub wind_deg: Option<u32>,
     #[serde(default)]
+    pub wind_gust: Option<f64>,
+    #[serde(default)]
+    pub dew_point: Option<f64>,
+    #[serde(default)]
+    pub uvi: Option<f64>,
+    #[serde(default)]
     pub weather: Vec<TimemachineWeather>,
     #[serde(default)]
     pub rain: Option<TimemachinePrecip>,
@@ -130,6 +317,8 @@ pub struct TimemachineData {
 
 #[derive(Debug, Deserialize)]
 pub struct TimemachineWeather {
+    #[serde(default)]
+    pub id: Option<i32>,
     pub description: String,
     pub icon: String,
 }
@@ -140,23 +329,205 @@ pub struct TimemachinePrecip {
     pub one_hour: Option<f64>,
 }
 
+/// Raw response from the OWM 2.5 current weather endpoint, sampled by the
+/// observation recorder
+#[derive(Debug, Deserialize)]
+pub struct ObservationResponse {
+    pub dt: i64,
+    pub main: ObservationMain,
+    #[serde(default)]
+    pub visibility: Option<u32>,
+    pub wind: ObservationWind,
+    #[serde(default)]
+    pub clouds: Option<ObservationClouds>,
+    #[serde(default)]
+    pub weather: Vec<TimemachineWeather>,
+    #[serde(default)]
+    pub rain: Option<TimemachinePrecip>,
+    #[serde(default)]
+    pub snow: Option<TimemachinePrecip>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ObservationMain {
+    pub temp: f64,
+    pub feels_like: f64,
+    pub pressure: u32,
+    pub humidity: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ObservationWind {
+    pub speed: f64,
+    #[serde(default)]
+    pub deg: Option<u32>,
+    #[serde(default)]
+    pub gust: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ObservationClouds {
+    pub all: u32,
+}
+
+impl From<ObservationResponse> for TimemachineData {
+    fn from(o: ObservationResponse) -> Self {
+        TimemachineData {
+            dt: o.dt,
+            temp: o.main.temp,
+            feels_like: o.main.feels_like,
+            pressure: o.main.pressure,
+            humidity: o.main.humidity,
+            clouds: o.clouds.map(|c| c.all),
+            visibility: o.visibility,
+            wind_speed: o.wind.speed,
+            wind_deg: o.wind.deg,
+            wind_gust: o.wind.gust,
+            // Current weather reports neither
+            dew_point: None,
+            uvi: None,
+            weather: o.weather,
+            rain: o.rain,
+            snow: o.snow,
+        }
+    }
+}
+
 // ============================================================================
 // Query Parameters
 // ============================================================================
 
 /// Query parameters for history endpoints
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct HistoryQuery {
+    /// Unix timestamp to start from
+    pub start: Option<i64>,
+    /// Unix timestamp to end at
+    pub end: Option<i64>,
+}
+
+/// Paging for the hourly history endpoint. Without either parameter the
+/// whole range is returned in one response.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryPageQuery {
+    /// 1-based page number (default 1)
+    pub page: Option<u32>,
+    /// Points per page (default 500, max 1000)
+    pub per_page: Option<u32>,
+}
+
+/// Body format for a history export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// The `HistoryResponse` shape, without pagination
+    #[default]
+    Json,
+    /// The scheduled export's columns, in stored (metric) units
+    Csv,
+}
+
+/// Query parameters for the history export endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryExportQuery {
+    /// Unix timestamp to start from (default: 7 days before end)
     pub start: Option<i64>,
+    /// Unix timestamp to end at (default: now)
     pub end: Option<i64>,
-    pub units: Option<String>,
+    /// json (default) or csv
+    pub format: Option<ExportFormat>,
+}
+
+/// Query parameters for alert history endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AlertHistoryQuery {
+    /// Unix timestamp to start from
+    pub start: Option<i64>,
+    /// Unix timestamp to end at
+    pub end: Option<i64>,
+    /// Only include alerts with this event name (case-insensitive)
+    pub event: Option<String>,
+}
+
+/// Query parameters for forecast accuracy endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AccuracyQuery {
+    /// Days of history to score (default 30, max 90)
+    pub days: Option<i64>,
+}
+
+/// Query parameters for archived forecast endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ArchivedForecastQuery {
+    /// Unix timestamp; returns the latest forecast fetched at or before it
+    /// (default now)
+    pub at: Option<i64>,
 }
 
 /// Query parameters for trends endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TrendsQuery {
+    /// 7d, 30d, or 90d
     pub period: Option<String>,
-    pub units: Option<String>,
+    /// Unix timestamp to start from, instead of a period
     pub start: Option<i64>,
+    /// Unix timestamp to end at
     pub end: Option<i64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(points: i64) -> HistoryResponse {
+        HistoryResponse {
+            city: "Chicago".to_string(),
+            units: "metric".to_string(),
+            period: "2024-07-01 to 2024-07-02".to_string(),
+            data_points: (0..points)
+                .map(|i| HistoryDataPoint {
+                    timestamp: i * 3600,
+                    temperature: 20.0,
+                    feels_like: 20.0,
+                    humidity: 50,
+                    pressure: 1013,
+                    wind_speed: 3.0,
+                    wind_direction: None,
+                    clouds: None,
+                    visibility: None,
+                    description: None,
+                    icon: None,
+                    rain_1h: None,
+                    snow_1h: None,
+                    wind_gust: None,
+                    dew_point: None,
+                    uvi: None,
+                    condition_id: None,
+                    source: "timemachine".to_string(),
+                })
+                .collect(),
+            pagination: None,
+        }
+    }
+
+    #[test]
+    fn test_paginate() {
+        let page = response(25).paginate(3, 10);
+        assert_eq!(page.data_points.len(), 5);
+        assert_eq!(page.data_points[0].timestamp, 20 * 3600);
+        let pagination = page.pagination.unwrap();
+        assert_eq!((pagination.total, pagination.total_pages), (25, 3));
+
+        assert!(response(25).paginate(4, 10).data_points.is_empty());
+        let clamped = response(3).paginate(0, 0);
+        assert_eq!(clamped.data_points.len(), 1);
+        assert_eq!(clamped.pagination.unwrap().page, 1);
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use indexmap::IndexSet;
+
+use super::service::{HistoryError, HistoryService};
+use crate::backfill::build_city_list;
+use crate::config::HistoryObservationsConfig;
+use crate::devices::DevicesService;
+use crate::geocode::models::make_location_key;
+use crate::scheduler::SchedulerService;
+
+/// Where the observation recorder finds its cities
+pub struct ObservationSources {
+    /// `None` when the devices subsystem is disabled
+    pub devices: Option<Arc<DevicesService>>,
+    /// `None` when the scheduler is disabled
+    pub scheduler: Option<Arc<SchedulerService>>,
+}
+
+/// Sample current conditions for every tracked city once
+async fn record_observations(
+    history_service: &HistoryService,
+    sources: &ObservationSources,
+    config: &HistoryObservationsConfig,
+) {
+    let devices = match sources.devices {
+        Some(ref devices) => devices.get_all().await,
+        None => Vec::new(),
+    };
+    let jobs = match sources.scheduler {
+        Some(ref scheduler) => scheduler.get_jobs().await,
+        None => Vec::new(),
+    };
+    let cities = build_city_list(&devices, &jobs, &config.cities);
+
+    let mut seen_locations = IndexSet::new();
+    let mut recorded = 0;
+    for city in &cities {
+        let location = match history_service.geocode(city).await {
+            Ok(location) => location,
+            Err(e) => {
+                tracing::warn!(city = %city, error = %e, "Observations: failed to geocode, skipping");
+                continue;
+            }
+        };
+        if !seen_locations.insert(make_location_key(location.lat, location.lon)) {
+            continue;
+        }
+
+        match history_service
+            .record_observation(&location.name, &location)
+            .await
+        {
+            Ok(true) => recorded += 1,
+            Ok(false) => {}
+            Err(e @ HistoryError::BudgetExhausted { .. }) => {
+                tracing::info!(reason = %e, "Observations: stopping for now");
+                break;
+            }
+            Err(e) => {
+                tracing::warn!(city = %location.name, error = %e, "Observations: failed to record")
+            }
+        }
+    }
+
+    tracing::debug!(
+        locations = seen_locations.len(),
+        recorded = recorded,
+        "Observations recorded"
+    );
+}
+
+/// Start a background task that samples current conditions for tracked
+/// cities (devices, then jobs, then `cities`) into history every
+/// `interval_mins`
+pub fn start_observation_task(
+    history_service: Arc<HistoryService>,
+    sources: ObservationSources,
+    config: HistoryObservationsConfig,
+) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.interval_mins as u64 * 60));
+        loop {
+            interval.tick().await;
+            record_observations(&history_service, &sources, &config).await;
+        }
+    });
+}
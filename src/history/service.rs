@@ -1,23 +1,49 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::http::StatusCode;
 use reqwest::Client;
 use sqlx::SqlitePool;
 use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
 use super::models::*;
-use crate::api_budget::ApiCallBudget;
+use crate::api_budget::{ApiCallBudget, BudgetFeature};
+use crate::api_keys::ApiKeyPool;
 use crate::cache::{normalize_cache_key, CachedGeoLocation, GeoCache};
-use crate::db::history_repo::{HistoryRecord, HistoryRepository, SqliteHistoryRepository};
+use crate::compression::{gunzip, gzip};
+use crate::config::ValidationConfig;
+use crate::db::history_repo::{
+    DailySummaryRow, HistoryRecord, HistoryRepository, SqliteHistoryRepository, SOURCE_OBSERVATION,
+    SOURCE_TIMEMACHINE,
+};
+use crate::db::{
+    AlertHistoryRepository, AnomalyRepository, DbError, ForecastArchiveRepository, ForecastIssue,
+    ForecastIssueRepository, HistoryArchive, HistoryArchiveRepository,
+    SqliteAlertHistoryRepository, SqliteAnomalyRepository, SqliteForecastArchiveRepository,
+    SqliteForecastIssueRepository, SqliteHistoryArchiveRepository, WeatherAnomaly,
+};
 use crate::error::HttpError;
+use crate::export::{history_csv, history_csv_row, upgrade_history_csv, HISTORY_HEADER};
 use crate::forecast::models::GeoLocation;
-use crate::geocode::models::make_location_key;
-use crate::impl_into_response;
+use crate::geocode::models::{make_location_key, resolve_geo_location};
+use crate::locations::{LocationsService, SavedLocation};
+use crate::sanity::MAX_COMPARE_GAP_SECS;
+use crate::upstream::UpstreamFailure;
+use crate::{impl_from_upstream, impl_into_response};
 
 const GEOCODING_API_URL: &str = "https://api.openweathermap.org/geo/1.0/direct";
 const ZIP_GEOCODING_API_URL: &str = "https://api.openweathermap.org/geo/1.0/zip";
 const REVERSE_GEOCODING_API_URL: &str = "https://api.openweathermap.org/geo/1.0/reverse";
 const TIMEMACHINE_API_URL: &str = "https://api.openweathermap.org/data/3.0/onecall/timemachine";
+const CURRENT_WEATHER_API_URL: &str = "https://api.openweathermap.org/data/2.5/weather";
+
+/// Unit system history is fetched and stored in, whatever clients ask for.
+/// Responses are converted on read, so one row serves every unit system.
+pub const STORAGE_UNITS: &str = "metric";
 
 /// Maximum number of API calls (days) per request to avoid OWM throttling.
 const MAX_DAYS_PER_REQUEST: usize = 800;
@@ -28,9 +54,49 @@ const DEFAULT_RANGE_DAYS: i64 = 7;
 /// Maximum date range for hourly history requests: 800 days
 const MAX_HOURLY_RANGE_DAYS: i64 = 800;
 
+/// Longest range one export may cover, in days
+const MAX_EXPORT_RANGE_DAYS: i64 = 3660;
+
+/// Rows read from the database per export chunk
+const EXPORT_PAGE_ROWS: i64 = 500;
+
+/// Export chunks buffered ahead of a slow client
+const EXPORT_BUFFERED_PAGES: usize = 4;
+
 /// Maximum date range for daily history requests: 800 days
 const MAX_DAILY_RANGE_DAYS: i64 = 800;
 
+/// Trend slopes, per day, beyond which a series is rising or falling:
+/// degrees of daily average temperature, mm of daily precipitation, and
+/// units of daily average wind speed
+const TEMP_TREND_THRESHOLD: f64 = 0.1;
+const PRECIPITATION_TREND_THRESHOLD: f64 = 0.1;
+const WIND_TREND_THRESHOLD: f64 = 0.1;
+
+/// Least precipitation in a day, in mm, that makes it rainy or snowy
+const MEASURABLE_PRECIPITATION_MM: f64 = 0.1;
+
+/// Default range for alert history queries (days)
+const DEFAULT_ALERT_RANGE_DAYS: i64 = 365;
+
+/// Maximum range for alert history queries (days)
+const MAX_ALERT_RANGE_DAYS: i64 = 3660;
+
+/// Default range for quarantined reading queries (days)
+const DEFAULT_ANOMALY_RANGE_DAYS: i64 = 30;
+
+/// Default number of past days scored for forecast accuracy
+const DEFAULT_ACCURACY_DAYS: i64 = 30;
+
+/// Maximum number of past days scored for forecast accuracy
+const MAX_ACCURACY_DAYS: i64 = 90;
+
+/// Lead times reported by the accuracy endpoint (days ahead)
+const ACCURACY_LEADS: [i64; 3] = [1, 3, 7];
+
+/// Fewest hourly records a day needs before its actuals are trusted
+const MIN_ACCURACY_READINGS: i64 = 18;
+
 #[derive(Error, Debug)]
 pub enum HistoryError {
     #[error("Failed to fetch data: {0}")]
@@ -48,8 +114,23 @@ pub enum HistoryError {
     #[error("Invalid date range: {0}")]
     InvalidDateRange(String),
 
+    #[error("No archived forecast for {0} at that time")]
+    NoArchivedForecast(String),
+
+    #[error("No archived history for {0}")]
+    ArchiveNotFound(String),
+
     #[error("One Call API subscription required")]
     SubscriptionRequired,
+
+    #[error("Daily API budget exhausted, retry in {retry_after}s")]
+    BudgetExhausted { retry_after: u64 },
+
+    #[error("OpenWeatherMap is unavailable, retry in {retry_after}s")]
+    UpstreamUnavailable { retry_after: u64 },
+
+    #[error(transparent)]
+    Upstream(#[from] UpstreamFailure),
 }
 
 impl HttpError for HistoryError {
@@ -60,7 +141,12 @@ impl HttpError for HistoryError {
             Self::ApiError(_) => StatusCode::BAD_REQUEST,
             Self::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::InvalidDateRange(_) => StatusCode::BAD_REQUEST,
+            Self::NoArchivedForecast(_) => StatusCode::NOT_FOUND,
+            Self::ArchiveNotFound(_) => StatusCode::NOT_FOUND,
             Self::SubscriptionRequired => StatusCode::PAYMENT_REQUIRED,
+            Self::BudgetExhausted { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::UpstreamUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Upstream(failure) => failure.status_code(),
         }
     }
 
@@ -71,25 +157,56 @@ impl HttpError for HistoryError {
             Self::ApiError(_) => Some("API_ERROR"),
             Self::DatabaseError(_) => Some("DATABASE_ERROR"),
             Self::InvalidDateRange(_) => Some("INVALID_DATE_RANGE"),
+            Self::NoArchivedForecast(_) => Some("NO_ARCHIVED_FORECAST"),
+            Self::ArchiveNotFound(_) => Some("ARCHIVE_NOT_FOUND"),
             Self::SubscriptionRequired => Some("SUBSCRIPTION_REQUIRED"),
+            Self::BudgetExhausted { .. } => Some("API_BUDGET_EXHAUSTED"),
+            Self::UpstreamUnavailable { .. } => Some("UPSTREAM_UNAVAILABLE"),
+            Self::Upstream(failure) => Some(failure.error_code()),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::BudgetExhausted { retry_after } | Self::UpstreamUnavailable { retry_after } => {
+                Some(*retry_after)
+            }
+            Self::Upstream(failure) => failure.retry_after(),
+            _ => None,
+        }
+    }
+
+    fn upstream_status(&self) -> Option<u16> {
+        match self {
+            Self::Upstream(failure) => Some(failure.status),
+            _ => None,
+        }
+    }
+
+    fn details(&self) -> Option<String> {
+        match self {
+            Self::Upstream(failure) => failure.details(),
+            _ => None,
         }
     }
 }
 
 impl_into_response!(HistoryError);
+impl_from_upstream!(HistoryError);
 
 /// Convert a DbError into a HistoryError
 fn db_err(e: crate::db::DbError) -> HistoryError {
     HistoryError::DatabaseError(sqlx::Error::Protocol(e.to_string()))
 }
 
-/// Convert a timemachine data point into a HistoryRecord
+/// Convert a timemachine data point (or an observation converted to one)
+/// into a HistoryRecord
 fn to_record(
     city: &str,
     lat: f64,
     lon: f64,
     dp: TimemachineData,
-    units: &str,
+    source: &str,
     now: i64,
 ) -> HistoryRecord {
     HistoryRecord {
@@ -110,36 +227,81 @@ fn to_record(
         icon: dp.weather.first().map(|w| w.icon.clone()),
         rain_1h: dp.rain.and_then(|r| r.one_hour),
         snow_1h: dp.snow.and_then(|s| s.one_hour),
-        units: units.to_string(),
+        units: STORAGE_UNITS.to_string(),
         fetched_at: now,
+        wind_gust: dp.wind_gust,
+        dew_point: dp.dew_point,
+        uvi: dp.uvi,
+        condition_id: dp.weather.first().and_then(|w| w.id),
+        source: source.to_string(),
     }
 }
 
 pub struct HistoryService {
     client: Client,
-    api_key: String,
+    api_keys: Arc<ApiKeyPool>,
     geo_cache: GeoCache,
     repo: SqliteHistoryRepository,
+    alert_repo: SqliteAlertHistoryRepository,
+    issue_repo: SqliteForecastIssueRepository,
+    archive_repo: SqliteForecastArchiveRepository,
+    history_archives: SqliteHistoryArchiveRepository,
+    anomalies: SqliteAnomalyRepository,
+    /// Sanity checks applied before rows are stored
+    validation: ValidationConfig,
     api_budget: Arc<ApiCallBudget>,
+    /// When the last backfill run finished (0 = not since startup)
+    last_backfill: AtomicI64,
+    locations: Option<Arc<LocationsService>>,
 }
 
 impl HistoryService {
     pub fn new(
         client: Client,
-        api_key: &str,
+        api_keys: Arc<ApiKeyPool>,
         geo_cache: GeoCache,
         pool: SqlitePool,
         api_budget: Arc<ApiCallBudget>,
     ) -> Self {
         Self {
             client,
-            api_key: api_key.to_string(),
+            api_keys,
             geo_cache,
-            repo: SqliteHistoryRepository::new(pool),
+            repo: SqliteHistoryRepository::new(pool.clone()),
+            alert_repo: SqliteAlertHistoryRepository::new(pool.clone()),
+            issue_repo: SqliteForecastIssueRepository::new(pool.clone()),
+            archive_repo: SqliteForecastArchiveRepository::new(pool.clone()),
+            history_archives: SqliteHistoryArchiveRepository::new(pool.clone()),
+            anomalies: SqliteAnomalyRepository::new(pool),
+            validation: ValidationConfig::default(),
             api_budget,
+            last_backfill: AtomicI64::new(0),
+            locations: None,
         }
     }
 
+    /// Resolve `loc:<id>` references to saved locations
+    pub fn with_locations(mut self, locations: Arc<LocationsService>) -> Self {
+        self.locations = Some(locations);
+        self
+    }
+
+    /// Check fetched rows with these settings before storing them
+    pub fn with_validation(mut self, validation: ValidationConfig) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Note that a backfill run finished at `timestamp`
+    pub fn mark_backfill_finished(&self, timestamp: i64) {
+        self.last_backfill.store(timestamp, Ordering::Relaxed);
+    }
+
+    /// When the last backfill run finished, if one has since startup
+    pub fn last_backfill(&self) -> Option<i64> {
+        Some(self.last_backfill.load(Ordering::Relaxed)).filter(|ts| *ts > 0)
+    }
+
     /// Check if input looks like "lat,lon" coordinates (e.g., "41.51,-90.77")
     fn is_coordinates(input: &str) -> bool {
         let parts: Vec<&str> = input.split(',').collect();
@@ -165,9 +327,37 @@ impl HistoryService {
         }
     }
 
+    /// The saved location `location` refers to, if it is a `loc:<id>` reference
+    fn saved_location(&self, location: &str) -> Result<Option<SavedLocation>, HistoryError> {
+        match self.locations {
+            Some(ref locations) => locations
+                .resolve(location)
+                .map_err(|_| HistoryError::CityNotFound(location.to_string())),
+            None => Ok(None),
+        }
+    }
+
     /// Geocode a location string to coordinates (reuses ForecastService pattern)
-    /// Supports city names ("Chicago"), zip codes ("60601"), and coordinates ("41.88,-87.63")
+    /// Supports city names ("Chicago"), zip codes ("60601"), coordinates
+    /// ("41.88,-87.63"), saved locations ("loc:<id>"), and aliases ("home").
+    /// "geo:lat,lon" coordinates are used as they are, with no upstream call.
     pub async fn geocode(&self, location: &str) -> Result<GeoLocation, HistoryError> {
+        if let Some(resolved) = resolve_geo_location(location) {
+            return Ok(resolved);
+        }
+        let Some(saved) = self.saved_location(location)? else {
+            return self.geocode_query(location).await;
+        };
+        let mut resolved = self.geocode_query(&saved.query()).await?;
+        resolved.name = saved.label;
+        if let (Some(lat), Some(lon)) = (saved.lat, saved.lon) {
+            resolved.lat = lat;
+            resolved.lon = lon;
+        }
+        Ok(resolved)
+    }
+
+    async fn geocode_query(&self, location: &str) -> Result<GeoLocation, HistoryError> {
         let cache_key = normalize_cache_key(location);
 
         if let Some(cached) = self.geo_cache.get(&cache_key).await {
@@ -213,18 +403,16 @@ impl HistoryService {
 
     async fn geocode_city(&self, city: &str) -> Result<GeoLocation, HistoryError> {
         let response = self
-            .client
-            .get(GEOCODING_API_URL)
-            .query(&[("q", city), ("limit", "1"), ("appid", &self.api_key)])
-            .send()
+            .api_keys
+            .send(
+                self.client
+                    .get(GEOCODING_API_URL)
+                    .query(&[("q", city), ("limit", "1")]),
+            )
             .await?;
 
         if !response.status().is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(HistoryError::ApiError(format!(
-                "Geocoding failed: {}",
-                text
-            )));
+            return Err(UpstreamFailure::read("Geocoding", response).await.into());
         }
 
         let locations: Vec<GeoLocation> = response.json().await?;
@@ -242,18 +430,18 @@ impl HistoryService {
         };
 
         let response = self
-            .client
-            .get(ZIP_GEOCODING_API_URL)
-            .query(&[("zip", &zip_query), ("appid", &self.api_key)])
-            .send()
+            .api_keys
+            .send(
+                self.client
+                    .get(ZIP_GEOCODING_API_URL)
+                    .query(&[("zip", &zip_query)]),
+            )
             .await?;
 
         if !response.status().is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(HistoryError::ApiError(format!(
-                "Zip geocoding failed: {}",
-                text
-            )));
+            return Err(UpstreamFailure::read("Zip geocoding", response)
+                .await
+                .into());
         }
 
         let location: crate::forecast::models::ZipGeoLocation = response.json().await?;
@@ -268,23 +456,18 @@ impl HistoryService {
         tracing::debug!(lat = %lat, lon = %lon, "Reverse geocoding coordinates");
 
         let response = self
-            .client
-            .get(REVERSE_GEOCODING_API_URL)
-            .query(&[
+            .api_keys
+            .send(self.client.get(REVERSE_GEOCODING_API_URL).query(&[
                 ("lat", lat.to_string()),
                 ("lon", lon.to_string()),
                 ("limit", "1".to_string()),
-                ("appid", self.api_key.clone()),
-            ])
-            .send()
+            ]))
             .await?;
 
         if !response.status().is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(HistoryError::ApiError(format!(
-                "Reverse geocoding failed: {}",
-                text
-            )));
+            return Err(UpstreamFailure::read("Reverse geocoding", response)
+                .await
+                .into());
         }
 
         let locations: Vec<GeoLocation> = response.json().await?;
@@ -294,6 +477,116 @@ impl HistoryService {
             .ok_or_else(|| HistoryError::CityNotFound(format!("{},{}", lat, lon)))
     }
 
+    /// Stream a city's stored hourly history between `start` and `end`,
+    /// without backfilling. Rows are read from the database a page at a time
+    /// and written as they're read, so memory use doesn't grow with the range.
+    /// Returns the export's file name and its body chunks.
+    pub async fn export_history(
+        &self,
+        city: &str,
+        start: Option<i64>,
+        end: Option<i64>,
+        units: &str,
+        format: ExportFormat,
+    ) -> Result<(String, ReceiverStream<Result<String, DbError>>), HistoryError> {
+        let now = chrono::Utc::now().timestamp();
+        let end_ts = end.unwrap_or(now);
+        let start_ts = start.unwrap_or(end_ts - DEFAULT_RANGE_DAYS * 86400);
+        validate_date_range(start_ts, end_ts, now, MAX_EXPORT_RANGE_DAYS)?;
+
+        let location = self.geocode(city).await?;
+        let location_key = make_location_key(location.lat, location.lon);
+        let period = format_period(start_ts, end_ts);
+        let day = |ts: i64| {
+            chrono::DateTime::from_timestamp(ts, 0)
+                .map(|d| d.format("%Y%m%d").to_string())
+                .unwrap_or_default()
+        };
+        let file_name = format!(
+            "history_{}_{}-{}.{}",
+            location_key.replace(',', "_"),
+            day(start_ts),
+            day(end_ts),
+            match format {
+                ExportFormat::Json => "json",
+                ExportFormat::Csv => "csv",
+            }
+        );
+
+        let head = match format {
+            ExportFormat::Json => format!(
+                "{{\"city\":{},\"units\":{},\"period\":{},\"data_points\":[",
+                serde_json::Value::from(location.name),
+                serde_json::Value::from(units),
+                serde_json::Value::from(period),
+            ),
+            ExportFormat::Csv => format!("{}\n", HISTORY_HEADER),
+        };
+        let repo = self.repo.clone();
+        let units = units.to_string();
+        let (tx, rx) = mpsc::channel(EXPORT_BUFFERED_PAGES);
+        tokio::spawn(async move {
+            if tx.send(Ok(head)).await.is_err() {
+                return;
+            }
+            let mut after_ts = start_ts - 1;
+            let mut first = true;
+            loop {
+                let rows = match repo
+                    .get_page_after(
+                        &location_key,
+                        after_ts,
+                        end_ts,
+                        STORAGE_UNITS,
+                        EXPORT_PAGE_ROWS,
+                    )
+                    .await
+                {
+                    Ok(rows) => rows,
+                    Err(e) => {
+                        tracing::warn!(location_key = %location_key, error = %e, "History export failed");
+                        // Ending the body with an error aborts the response,
+                        // so the client sees a truncated download
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+                let Some((_, last)) = rows.last() else {
+                    break;
+                };
+                after_ts = last.timestamp;
+                let more = rows.len() == EXPORT_PAGE_ROWS as usize;
+
+                let mut chunk = String::new();
+                for (id, record) in rows {
+                    match format {
+                        ExportFormat::Csv => chunk.push_str(&history_csv_row(id, &record)),
+                        ExportFormat::Json => {
+                            if !first {
+                                chunk.push(',');
+                            }
+                            first = false;
+                            let point = data_point(record, &units);
+                            chunk.push_str(&serde_json::to_string(&point).unwrap_or_default());
+                        }
+                    }
+                }
+                // The client went away
+                if tx.send(Ok(chunk)).await.is_err() {
+                    return;
+                }
+                if !more {
+                    break;
+                }
+            }
+            if format == ExportFormat::Json {
+                let _ = tx.send(Ok("]}".to_string())).await;
+            }
+        });
+
+        Ok((file_name, ReceiverStream::new(rx)))
+    }
+
     /// Get hourly history data for a city within a time range
     pub async fn get_history(
         &self,
@@ -312,34 +605,16 @@ impl HistoryService {
         let city_name = location.name.clone();
         let location_key = make_location_key(location.lat, location.lon);
 
-        // Always store/query in metric (canonical units)
-        self.backfill_data(&city_name, &location, start_ts, end_ts, "metric")
+        self.backfill_data(&city_name, &location, start_ts, end_ts)
             .await?;
 
         let records = self
             .repo
-            .get_range(&location_key, start_ts, end_ts, "metric")
+            .get_range(&location_key, start_ts, end_ts, STORAGE_UNITS)
             .await
             .map_err(db_err)?;
 
-        let data_points = records
-            .into_iter()
-            .map(|r| HistoryDataPoint {
-                timestamp: r.timestamp,
-                temperature: convert_temp(r.temperature, units),
-                feels_like: convert_temp(r.feels_like, units),
-                humidity: r.humidity,
-                pressure: r.pressure,
-                wind_speed: convert_speed(r.wind_speed, units),
-                wind_direction: r.wind_direction,
-                clouds: r.clouds,
-                visibility: r.visibility,
-                description: r.description,
-                icon: r.icon,
-                rain_1h: r.rain_1h,
-                snow_1h: r.snow_1h,
-            })
-            .collect();
+        let data_points = records.into_iter().map(|r| data_point(r, units)).collect();
 
         let period = format_period(start_ts, end_ts);
 
@@ -348,9 +623,362 @@ impl HistoryService {
             units: units.to_string(),
             period,
             data_points,
+            pagination: None,
+        })
+    }
+
+    /// Get weather alerts observed for a city that were in effect during a time range
+    pub async fn get_alert_history(
+        &self,
+        city: &str,
+        start: Option<i64>,
+        end: Option<i64>,
+        event: Option<&str>,
+    ) -> Result<AlertHistoryResponse, HistoryError> {
+        let now = chrono::Utc::now().timestamp();
+        let end_ts = end.unwrap_or(now);
+        let start_ts = start.unwrap_or(end_ts - DEFAULT_ALERT_RANGE_DAYS * 86400);
+
+        validate_date_range(start_ts, end_ts, now, MAX_ALERT_RANGE_DAYS)?;
+
+        let location = self.geocode(city).await?;
+        let location_key = make_location_key(location.lat, location.lon);
+
+        let alerts: Vec<AlertHistoryEntry> = self
+            .alert_repo
+            .get_range(&location_key, start_ts, end_ts)
+            .await
+            .map_err(db_err)?
+            .into_iter()
+            .filter(|r| event.is_none_or(|e| r.event.eq_ignore_ascii_case(e.trim())))
+            .map(|r| AlertHistoryEntry {
+                event: r.event,
+                sender: r.sender,
+                severity: r.severity,
+                start: r.start,
+                end: r.end,
+                description: r.description,
+                first_seen: r.first_seen,
+            })
+            .collect();
+
+        let mut counts = std::collections::BTreeMap::new();
+        for alert in &alerts {
+            *counts.entry(alert.event.clone()).or_insert(0) += 1;
+        }
+
+        Ok(AlertHistoryResponse {
+            city: location.name,
+            period: format_period(start_ts, end_ts),
+            total: alerts.len(),
+            counts,
+            alerts,
+        })
+    }
+
+    /// Readings for a city quarantined by the sanity checks during a time range
+    pub async fn get_anomalies(
+        &self,
+        city: &str,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> Result<AnomalyHistoryResponse, HistoryError> {
+        let now = chrono::Utc::now().timestamp();
+        let end_ts = end.unwrap_or(now);
+        let start_ts = start.unwrap_or(end_ts - DEFAULT_ANOMALY_RANGE_DAYS * 86400);
+
+        validate_date_range(start_ts, end_ts, now, MAX_ALERT_RANGE_DAYS)?;
+
+        let location = self.geocode(city).await?;
+        let location_key = make_location_key(location.lat, location.lon);
+
+        let anomalies: Vec<AnomalyEntry> = self
+            .anomalies
+            .get_range(&location_key, start_ts, end_ts)
+            .await
+            .map_err(db_err)?
+            .into_iter()
+            .map(|a| AnomalyEntry {
+                source: a.source,
+                observed_at: a.observed_at,
+                detected_at: a.detected_at,
+                reasons: a.reasons,
+                reading: a.payload,
+            })
+            .collect();
+
+        Ok(AnomalyHistoryResponse {
+            city: location.name,
+            period: format_period(start_ts, end_ts),
+            total: anomalies.len(),
+            anomalies,
+        })
+    }
+
+    /// Score forecasts issued by scheduled jobs against the history recorded
+    /// for the days they covered, over the last `days` complete days
+    pub async fn get_accuracy(
+        &self,
+        city: &str,
+        days: Option<i64>,
+        units: &str,
+    ) -> Result<AccuracyResponse, HistoryError> {
+        let days = days.unwrap_or(DEFAULT_ACCURACY_DAYS);
+        if !(1..=MAX_ACCURACY_DAYS).contains(&days) {
+            return Err(HistoryError::InvalidDateRange(format!(
+                "days must be between 1 and {}",
+                MAX_ACCURACY_DAYS
+            )));
+        }
+
+        // Whole UTC days, stopping before today since it isn't over yet
+        let today = chrono::Utc::now().date_naive();
+        let first_day = today - chrono::Duration::days(days);
+        let start_ts = first_day
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc()
+            .timestamp();
+        let end_ts = today.and_time(chrono::NaiveTime::MIN).and_utc().timestamp() - 1;
+
+        let location = self.geocode(city).await?;
+        let location_key = make_location_key(location.lat, location.lon);
+
+        let actuals = self
+            .repo
+            .get_daily_summary(&location_key, start_ts, end_ts, STORAGE_UNITS)
+            .await
+            .map_err(db_err)?;
+        let issues = self
+            .issue_repo
+            .get_range(
+                &location_key,
+                &first_day.to_string(),
+                &(today - chrono::Duration::days(1)).to_string(),
+            )
+            .await
+            .map_err(db_err)?;
+
+        Ok(AccuracyResponse {
+            city: location.name,
+            units: units.to_string(),
+            period: format_period(start_ts, end_ts + 1),
+            leads: score_accuracy(&issues, &actuals, units),
+        })
+    }
+
+    /// The latest forecast for a city fetched at or before `at` (default now),
+    /// from the forecast archive
+    pub async fn get_archived_forecast(
+        &self,
+        city: &str,
+        at: Option<i64>,
+    ) -> Result<ArchivedForecastResponse, HistoryError> {
+        let at = at.unwrap_or_else(|| chrono::Utc::now().timestamp());
+        let location = self.geocode(city).await?;
+        let location_key = make_location_key(location.lat, location.lon);
+
+        let archived = self
+            .archive_repo
+            .get_at(&location_key, at)
+            .await
+            .map_err(db_err)?
+            .ok_or_else(|| HistoryError::NoArchivedForecast(location.name.clone()))?;
+
+        Ok(ArchivedForecastResponse {
+            city: location.name,
+            issued_at: archived.issued_at,
+            kind: archived.kind,
+            units: archived.units,
+            forecast: archived.forecast,
+        })
+    }
+
+    /// Delete archived forecasts older than the retention period
+    pub async fn prune_forecast_archive(&self, retention: Duration) -> u64 {
+        let cutoff = chrono::Utc::now().timestamp() - retention.as_secs() as i64;
+        match self.archive_repo.prune_before(cutoff).await {
+            Ok(deleted) => {
+                if deleted > 0 {
+                    tracing::info!(deleted = deleted, "Pruned forecast archive");
+                }
+                deleted
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to prune forecast archive");
+                0
+            }
+        }
+    }
+
+    /// Months of a city's history moved into archives
+    pub async fn list_history_archives(
+        &self,
+        city: &str,
+    ) -> Result<HistoryArchivesResponse, HistoryError> {
+        let location = self.geocode(city).await?;
+        let location_key = make_location_key(location.lat, location.lon);
+
+        let archives = self
+            .history_archives
+            .list(&location_key)
+            .await
+            .map_err(db_err)?
+            .into_iter()
+            .map(|a| HistoryArchiveEntry {
+                month: a.month,
+                rows: a.row_count,
+                size_bytes: a.size_bytes,
+                archived_at: a.created_at,
+            })
+            .collect();
+
+        Ok(HistoryArchivesResponse {
+            city: location.name,
+            location_key,
+            archives,
         })
     }
 
+    /// One archived month of a city's history as gzip-compressed CSV, with
+    /// a file name for it
+    pub async fn get_history_archive(
+        &self,
+        city: &str,
+        month: &str,
+    ) -> Result<(String, Vec<u8>), HistoryError> {
+        let location = self.geocode(city).await?;
+        let location_key = make_location_key(location.lat, location.lon);
+
+        let archive = self
+            .history_archives
+            .get(&location_key, month)
+            .await
+            .map_err(db_err)?
+            .ok_or_else(|| {
+                HistoryError::ArchiveNotFound(format!("{} in {}", location.name, month))
+            })?;
+
+        let file_name = format!(
+            "weather_history_{}_{}.csv.gz",
+            location_key.replace(',', "_"),
+            archive.month
+        );
+        Ok((file_name, archive.data))
+    }
+
+    /// Move hourly history from whole UTC months ending by `before_ts` out
+    /// of the live table into monthly archives. Returns the number of rows
+    /// moved.
+    pub async fn archive_history(&self, before_ts: i64) -> Result<u64, HistoryError> {
+        let months = self.repo.months_before(before_ts).await.map_err(db_err)?;
+        let mut moved = 0;
+        for (location_key, month) in months {
+            let Some((start_ts, end_ts)) = month_bounds(&month) else {
+                continue;
+            };
+            if end_ts > before_ts {
+                continue;
+            }
+            let rows = self
+                .archive_month(&location_key, &month, start_ts, end_ts)
+                .await
+                .map_err(db_err)?;
+            tracing::info!(
+                location_key = %location_key,
+                month = %month,
+                rows = rows,
+                "Archived history month"
+            );
+            moved += rows;
+        }
+        Ok(moved)
+    }
+
+    /// Add a month's live rows to its archive, then delete them. Rows
+    /// already in the archive (from a run interrupted after storing) are
+    /// skipped rather than duplicated.
+    async fn archive_month(
+        &self,
+        location_key: &str,
+        month: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<u64, DbError> {
+        let mut rows = self
+            .repo
+            .get_numbered_range(location_key, start_ts, end_ts)
+            .await?;
+        let (Some(max_id), Some((_, first))) = (rows.iter().map(|(id, _)| *id).max(), rows.first())
+        else {
+            return Ok(0);
+        };
+        let city = first.city.clone();
+
+        let (mut csv, mut row_count) = match self.history_archives.get(location_key, month).await? {
+            Some(existing) => (
+                upgrade_history_csv(String::from_utf8_lossy(&gunzip(&existing.data)?).into_owned()),
+                existing.row_count,
+            ),
+            None => (String::new(), 0),
+        };
+        let archived_ids: HashSet<i64> = csv
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split(',').next()?.parse().ok())
+            .collect();
+        rows.retain(|(id, _)| !archived_ids.contains(id));
+
+        let fresh = history_csv(&rows);
+        if csv.is_empty() {
+            csv = fresh;
+        } else if let Some((_, body)) = fresh.split_once('\n') {
+            csv.push_str(body);
+        }
+        row_count += rows.len() as i64;
+
+        self.history_archives
+            .store(&HistoryArchive {
+                location_key: location_key.to_string(),
+                month: month.to_string(),
+                city,
+                row_count,
+                created_at: chrono::Utc::now().timestamp(),
+                data: gzip(csv.as_bytes())?,
+            })
+            .await?;
+        self.repo
+            .delete_range(location_key, start_ts, end_ts, max_id)
+            .await
+    }
+
+    /// Missing days for a location, leaving out archived months so their
+    /// history is never refetched from OWM
+    async fn unarchived_missing_days(
+        &self,
+        location_key: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<Vec<i64>, DbError> {
+        let missing = self
+            .repo
+            .get_missing_days(location_key, start_ts, end_ts, STORAGE_UNITS)
+            .await?;
+        if missing.is_empty() {
+            return Ok(missing);
+        }
+        let archived: HashSet<String> = self
+            .history_archives
+            .list(location_key)
+            .await?
+            .into_iter()
+            .map(|a| a.month)
+            .collect();
+        Ok(missing
+            .into_iter()
+            .filter(|day| !archived.contains(&month_of(*day)))
+            .collect())
+    }
+
     /// Get daily aggregated history for a city
     pub async fn get_daily_history(
         &self,
@@ -369,12 +997,12 @@ impl HistoryService {
         let city_name = location.name.clone();
         let location_key = make_location_key(location.lat, location.lon);
 
-        self.backfill_data(&city_name, &location, start_ts, end_ts, "metric")
+        self.backfill_data(&city_name, &location, start_ts, end_ts)
             .await?;
 
         let summaries = self
             .repo
-            .get_daily_summary(&location_key, start_ts, end_ts, "metric")
+            .get_daily_summary(&location_key, start_ts, end_ts, STORAGE_UNITS)
             .await
             .map_err(db_err)?;
 
@@ -388,6 +1016,7 @@ impl HistoryService {
                 humidity_avg: round_2(s.humidity_avg),
                 wind_speed_avg: round_2(convert_speed(s.wind_speed_avg, units)),
                 precipitation_total: round_2(s.precipitation_total),
+                snow_total: round_2(s.snow_total),
                 dominant_condition: s.dominant_condition,
             })
             .collect();
@@ -445,12 +1074,12 @@ impl HistoryService {
         let city_name = location.name.clone();
         let location_key = make_location_key(location.lat, location.lon);
 
-        self.backfill_data(&city_name, &location, start_ts, end_ts, "metric")
+        self.backfill_data(&city_name, &location, start_ts, end_ts)
             .await?;
 
         let summaries = self
             .repo
-            .get_daily_summary(&location_key, start_ts, end_ts, "metric")
+            .get_daily_summary(&location_key, start_ts, end_ts, STORAGE_UNITS)
             .await
             .map_err(db_err)?;
 
@@ -464,6 +1093,7 @@ impl HistoryService {
                 humidity_avg: round_2(s.humidity_avg),
                 wind_speed_avg: round_2(convert_speed(s.wind_speed_avg, units)),
                 precipitation_total: round_2(s.precipitation_total),
+                snow_total: round_2(s.snow_total),
                 dominant_condition: s.dominant_condition,
             })
             .collect();
@@ -488,12 +1118,10 @@ impl HistoryService {
         location: &GeoLocation,
         start_ts: i64,
         end_ts: i64,
-        units: &str,
     ) -> Result<(), HistoryError> {
         let location_key = make_location_key(location.lat, location.lon);
         let missing_days = self
-            .repo
-            .get_missing_days(&location_key, start_ts, end_ts, units)
+            .unarchived_missing_days(&location_key, start_ts, end_ts)
             .await
             .map_err(db_err)?;
 
@@ -508,7 +1136,7 @@ impl HistoryService {
         );
 
         // Limit to the smaller of MAX_DAYS_PER_REQUEST or remaining API budget
-        let budget_remaining = self.api_budget.remaining() as usize;
+        let budget_remaining = self.api_budget.remaining_for(BudgetFeature::History) as usize;
         let limit = MAX_DAYS_PER_REQUEST.min(budget_remaining);
         let days_to_fetch: Vec<i64> = missing_days.into_iter().take(limit).collect();
 
@@ -525,15 +1153,30 @@ impl HistoryService {
             // Use noon UTC for the API call to ensure we get the right day
             let fetch_ts = day_ts + 12 * 3600;
             match self
-                .fetch_timemachine(location.lat, location.lon, fetch_ts, units)
+                .fetch_timemachine(location.lat, location.lon, fetch_ts, BudgetFeature::History)
                 .await
             {
                 Ok(data_points) => {
                     fetched_count += 1;
                     for dp in data_points {
-                        records.push(to_record(city, location.lat, location.lon, dp, units, now));
+                        records.push(to_record(
+                            city,
+                            location.lat,
+                            location.lon,
+                            dp,
+                            SOURCE_TIMEMACHINE,
+                            now,
+                        ));
                     }
                 }
+                Err(
+                    e @ (HistoryError::BudgetExhausted { .. }
+                    | HistoryError::UpstreamUnavailable { .. }),
+                ) => {
+                    // Serve what's already stored; the rest fills in later
+                    tracing::warn!(city = %city, reason = %e, "Returning stored history");
+                    break;
+                }
                 Err(e) => {
                     tracing::warn!(
                         city = %city,
@@ -545,6 +1188,7 @@ impl HistoryService {
             }
         }
 
+        let records = self.screen(records).await;
         if !records.is_empty() {
             let inserted = self.repo.insert_batch(&records).await.map_err(db_err)?;
 
@@ -565,22 +1209,26 @@ impl HistoryService {
         lat: f64,
         lon: f64,
         timestamp: i64,
-        units: &str,
+        feature: BudgetFeature,
     ) -> Result<Vec<TimemachineData>, HistoryError> {
-        self.api_budget.record_call();
+        if !self.api_budget.try_acquire(feature) {
+            return Err(HistoryError::BudgetExhausted {
+                retry_after: self.api_budget.seconds_until_reset(),
+            });
+        }
         metrics::counter!(crate::metrics::OWM_API_CALLS, "endpoint" => "timemachine").increment(1);
 
         let response = self
-            .client
-            .get(TIMEMACHINE_API_URL)
-            .query(&[
-                ("lat", lat.to_string()),
-                ("lon", lon.to_string()),
-                ("dt", timestamp.to_string()),
-                ("units", units.to_string()),
-                ("appid", self.api_key.clone()),
-            ])
-            .send()
+            .api_keys
+            .send_budgeted(
+                feature,
+                self.client.get(TIMEMACHINE_API_URL).query(&[
+                    ("lat", lat.to_string()),
+                    ("lon", lon.to_string()),
+                    ("dt", timestamp.to_string()),
+                    ("units", STORAGE_UNITS.to_string()),
+                ]),
+            )
             .await?;
 
         let status = response.status();
@@ -590,8 +1238,9 @@ impl HistoryService {
         }
 
         if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(HistoryError::ApiError(text));
+            return Err(UpstreamFailure::read("One Call timemachine", response)
+                .await
+                .into());
         }
 
         let data: TimemachineResponse = response.json().await?;
@@ -617,45 +1266,66 @@ impl HistoryService {
     }
 
     /// Get missing days for a location within a time range.
-    /// Returns midnight-UTC timestamps for days that have no data in the DB.
+    /// Returns midnight-UTC timestamps for days that have no data in the DB
+    /// and aren't in an archived month.
     /// The `location_key` should be a canonical key from `make_location_key()`.
     pub async fn get_missing_days(
         &self,
         location_key: &str,
         start_ts: i64,
         end_ts: i64,
-        units: &str,
     ) -> Result<Vec<i64>, HistoryError> {
-        self.repo
-            .get_missing_days(location_key, start_ts, end_ts, units)
+        self.unarchived_missing_days(location_key, start_ts, end_ts)
             .await
             .map_err(db_err)
     }
 
     /// Fetch one day of history if API budget allows.
-    /// Returns `Some(count)` of inserted records, or `None` if budget is exhausted.
+    /// Returns `Some(count)` of inserted records, or `None` if budget is exhausted
+    /// or OWM is unavailable.
     pub async fn fetch_day_if_budget(
         &self,
         city: &str,
         location: &GeoLocation,
         day_ts: i64,
-        units: &str,
     ) -> Result<Option<usize>, HistoryError> {
-        if self.api_budget.remaining() == 0 {
+        if self.api_budget.remaining_for(BudgetFeature::Backfill) == 0 {
             return Ok(None);
         }
 
         let fetch_ts = day_ts + 12 * 3600; // noon UTC
-        let data_points = self
-            .fetch_timemachine(location.lat, location.lon, fetch_ts, units)
-            .await?;
+        let data_points = match self
+            .fetch_timemachine(
+                location.lat,
+                location.lon,
+                fetch_ts,
+                BudgetFeature::Backfill,
+            )
+            .await
+        {
+            Ok(data_points) => data_points,
+            Err(
+                HistoryError::BudgetExhausted { .. } | HistoryError::UpstreamUnavailable { .. },
+            ) => return Ok(None),
+            Err(e) => return Err(e),
+        };
 
         let now = chrono::Utc::now().timestamp();
         let records: Vec<HistoryRecord> = data_points
             .into_iter()
-            .map(|dp| to_record(city, location.lat, location.lon, dp, units, now))
+            .map(|dp| {
+                to_record(
+                    city,
+                    location.lat,
+                    location.lon,
+                    dp,
+                    SOURCE_TIMEMACHINE,
+                    now,
+                )
+            })
             .collect();
 
+        let records = self.screen(records).await;
         if records.is_empty() {
             return Ok(Some(0));
         }
@@ -664,6 +1334,104 @@ impl HistoryService {
 
         Ok(Some(inserted))
     }
+
+    /// Sample current conditions at a location into history, one call to
+    /// the free current weather API. Returns whether a row was added; OWM
+    /// updates its observations every ten minutes or so, and a repeat of
+    /// the last one is skipped.
+    pub async fn record_observation(
+        &self,
+        city: &str,
+        location: &GeoLocation,
+    ) -> Result<bool, HistoryError> {
+        if !self.api_budget.try_acquire(BudgetFeature::History) {
+            return Err(HistoryError::BudgetExhausted {
+                retry_after: self.api_budget.seconds_until_reset(),
+            });
+        }
+        metrics::counter!(crate::metrics::OWM_API_CALLS, "endpoint" => "observation").increment(1);
+
+        let response = self
+            .api_keys
+            .send_budgeted(
+                BudgetFeature::History,
+                self.client.get(CURRENT_WEATHER_API_URL).query(&[
+                    ("lat", location.lat.to_string()),
+                    ("lon", location.lon.to_string()),
+                    ("units", STORAGE_UNITS.to_string()),
+                ]),
+            )
+            .await?;
+        if !response.status().is_success() {
+            return Err(UpstreamFailure::read("current weather", response)
+                .await
+                .into());
+        }
+        let observation: ObservationResponse = response.json().await?;
+
+        let record = to_record(
+            city,
+            location.lat,
+            location.lon,
+            observation.into(),
+            SOURCE_OBSERVATION,
+            chrono::Utc::now().timestamp(),
+        );
+        let records = self.screen(vec![record]).await;
+        if records.is_empty() {
+            return Ok(false);
+        }
+        let inserted = self.repo.insert_batch(&records).await.map_err(db_err)?;
+        Ok(inserted > 0)
+    }
+
+    /// Quarantine implausible rows from a batch fetched for one location and
+    /// return the rest. The first row's temperature is compared with the
+    /// latest stored one shortly before it.
+    async fn screen(&self, records: Vec<HistoryRecord>) -> Vec<HistoryRecord> {
+        if !self.validation.enabled || records.is_empty() {
+            return records;
+        }
+        let first = records.iter().min_by_key(|r| r.timestamp).unwrap();
+        let previous = self
+            .repo
+            .get_range(
+                &first.location_key,
+                first.timestamp - MAX_COMPARE_GAP_SECS,
+                first.timestamp - 1,
+                STORAGE_UNITS,
+            )
+            .await
+            .inspect_err(|e| tracing::warn!(error = %e, "Failed to read history before batch"))
+            .ok()
+            .and_then(|mut rows| rows.pop());
+
+        let (accepted, rejected) = self.validation.screen_history(records, previous.as_ref());
+        if rejected.is_empty() {
+            return accepted;
+        }
+        let now = chrono::Utc::now().timestamp();
+        let anomalies: Vec<WeatherAnomaly> = rejected
+            .into_iter()
+            .map(|r| {
+                let (key, city, source) = (
+                    r.reading.location_key.clone(),
+                    r.reading.city.clone(),
+                    r.reading.source.clone(),
+                );
+                tracing::warn!(city = %city, timestamp = r.timestamp, reasons = ?r.reasons, "Quarantined implausible history row");
+                r.into_anomaly(&key, &city, &source, now)
+            })
+            .collect();
+        for anomaly in &anomalies {
+            metrics::counter!(crate::metrics::WEATHER_ANOMALIES, "source" => anomaly.source.clone())
+                .increment(1);
+        }
+        if let Err(e) = self.anomalies.record(&anomalies).await {
+            tracing::warn!(error = %e, "Failed to quarantine history rows");
+        }
+        accepted
+    }
 }
 
 /// Compute trend summary from daily summaries
@@ -681,7 +1449,12 @@ fn compute_trend_summary(days: &[DailyHistorySummary]) -> TrendSummary {
                 date: String::new(),
             },
             total_precipitation: 0.0,
+            precipitation_trend: "stable".to_string(),
             avg_humidity: 0.0,
+            avg_wind_speed: 0.0,
+            wind_trend: "stable".to_string(),
+            rainy_days: 0,
+            snowy_days: 0,
         };
     }
 
@@ -703,11 +1476,29 @@ fn compute_trend_summary(days: &[DailyHistorySummary]) -> TrendSummary {
     // Total precipitation
     let total_precipitation: f64 = days.iter().map(|d| d.precipitation_total).sum();
 
-    // Average humidity
+    // Average humidity and wind speed
     let avg_humidity: f64 = days.iter().map(|d| d.humidity_avg).sum::<f64>() / n;
+    let avg_wind_speed: f64 = days.iter().map(|d| d.wind_speed_avg).sum::<f64>() / n;
 
-    // Simple linear regression for trend direction
-    let temp_trend = compute_trend_direction(days);
+    // Rain is whatever precipitation didn't fall as snow; a day can be both
+    let rainy_days = days
+        .iter()
+        .filter(|d| d.precipitation_total - d.snow_total >= MEASURABLE_PRECIPITATION_MM)
+        .count() as u32;
+    let snowy_days = days
+        .iter()
+        .filter(|d| d.snow_total >= MEASURABLE_PRECIPITATION_MM)
+        .count() as u32;
+
+    // Simple linear regression for trend directions
+    let series =
+        |value: fn(&DailyHistorySummary) -> f64| days.iter().map(value).collect::<Vec<_>>();
+    let temp_trend = compute_trend_direction(&series(|d| d.temp_avg), TEMP_TREND_THRESHOLD);
+    let precipitation_trend = compute_trend_direction(
+        &series(|d| d.precipitation_total),
+        PRECIPITATION_TREND_THRESHOLD,
+    );
+    let wind_trend = compute_trend_direction(&series(|d| d.wind_speed_avg), WIND_TREND_THRESHOLD);
 
     TrendSummary {
         avg_temp: round_2(avg_temp),
@@ -721,26 +1512,28 @@ fn compute_trend_summary(days: &[DailyHistorySummary]) -> TrendSummary {
             date: min_day.date.clone(),
         },
         total_precipitation: round_2(total_precipitation),
+        precipitation_trend,
         avg_humidity: round_2(avg_humidity),
+        avg_wind_speed: round_2(avg_wind_speed),
+        wind_trend,
+        rainy_days,
+        snowy_days,
     }
 }
 
-/// Compute trend direction using simple linear regression on daily avg temperatures
-fn compute_trend_direction(days: &[DailyHistorySummary]) -> String {
-    let n = days.len() as f64;
+/// Compute trend direction using simple linear regression on a daily series,
+/// calling it rising or falling when the slope passes `threshold` per day
+fn compute_trend_direction(values: &[f64], threshold: f64) -> String {
+    let n = values.len() as f64;
     if n < 2.0 {
         return "stable".to_string();
     }
 
-    // x = day index (0, 1, 2, ...), y = temp_avg
-    let sum_x: f64 = (0..days.len()).map(|i| i as f64).sum();
-    let sum_y: f64 = days.iter().map(|d| d.temp_avg).sum();
-    let sum_xy: f64 = days
-        .iter()
-        .enumerate()
-        .map(|(i, d)| i as f64 * d.temp_avg)
-        .sum();
-    let sum_x2: f64 = (0..days.len()).map(|i| (i as f64) * (i as f64)).sum();
+    // x = day index (0, 1, 2, ...), y = the day's value
+    let sum_x: f64 = (0..values.len()).map(|i| i as f64).sum();
+    let sum_y: f64 = values.iter().sum();
+    let sum_xy: f64 = values.iter().enumerate().map(|(i, y)| i as f64 * y).sum();
+    let sum_x2: f64 = (0..values.len()).map(|i| (i as f64) * (i as f64)).sum();
 
     let denominator = n * sum_x2 - sum_x * sum_x;
     if denominator.abs() < f64::EPSILON {
@@ -749,8 +1542,6 @@ fn compute_trend_direction(days: &[DailyHistorySummary]) -> String {
 
     let slope = (n * sum_xy - sum_x * sum_y) / denominator;
 
-    // Use a threshold relative to the temperature range
-    let threshold = 0.1; // degrees per day
     if slope > threshold {
         "rising".to_string()
     } else if slope < -threshold {
@@ -760,6 +1551,30 @@ fn compute_trend_direction(days: &[DailyHistorySummary]) -> String {
     }
 }
 
+/// A stored record as a response data point in `units`
+fn data_point(r: HistoryRecord, units: &str) -> HistoryDataPoint {
+    HistoryDataPoint {
+        timestamp: r.timestamp,
+        temperature: convert_temp(r.temperature, units),
+        feels_like: convert_temp(r.feels_like, units),
+        humidity: r.humidity,
+        pressure: r.pressure,
+        wind_speed: convert_speed(r.wind_speed, units),
+        wind_direction: r.wind_direction,
+        clouds: r.clouds,
+        visibility: r.visibility,
+        description: r.description,
+        icon: r.icon,
+        rain_1h: r.rain_1h,
+        snow_1h: r.snow_1h,
+        wind_gust: r.wind_gust.map(|g| convert_speed(g, units)),
+        dew_point: r.dew_point.map(|d| convert_temp(d, units)),
+        uvi: r.uvi,
+        condition_id: r.condition_id,
+        source: r.source,
+    }
+}
+
 /// Convert temperature from metric (Celsius) to the requested units
 fn convert_temp(celsius: f64, units: &str) -> f64 {
     match units {
@@ -777,6 +1592,78 @@ fn convert_speed(ms: f64, units: &str) -> f64 {
     }
 }
 
+/// Start a background task that trims the forecast archive to the retention period
+pub fn start_forecast_archive_prune_task(
+    history_service: Arc<HistoryService>,
+    retention: Duration,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            history_service.prune_forecast_archive(retention).await;
+        }
+    });
+}
+
+/// First timestamp of a UTC month given as YYYY-MM, and of the month after
+fn month_bounds(month: &str) -> Option<(i64, i64)> {
+    let first = chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()?;
+    let next = first.checked_add_months(chrono::Months::new(1))?;
+    let start =
+        |date: chrono::NaiveDate| date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp();
+    Some((start(first), start(next)))
+}
+
+/// The UTC month (YYYY-MM) a timestamp falls in
+fn month_of(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m").to_string())
+        .unwrap_or_default()
+}
+
+/// Mean absolute error of each reported lead time's forecasts against days
+/// with enough recorded history
+fn score_accuracy(
+    issues: &[ForecastIssue],
+    actuals: &[DailySummaryRow],
+    units: &str,
+) -> Vec<LeadAccuracy> {
+    let actuals: std::collections::HashMap<&str, &DailySummaryRow> = actuals
+        .iter()
+        .filter(|a| a.readings >= MIN_ACCURACY_READINGS)
+        .map(|a| (a.date.as_str(), a))
+        .collect();
+    // Temperature differences scale, but don't shift, between units
+    let temp_scale = if units == "imperial" { 1.8 } else { 1.0 };
+    let mean = |errors: &[f64], scale: f64| {
+        (!errors.is_empty())
+            .then(|| round_2(errors.iter().sum::<f64>() / errors.len() as f64 * scale))
+    };
+
+    ACCURACY_LEADS
+        .iter()
+        .map(|&lead_days| {
+            let (mut high, mut low, mut precip) = (Vec::new(), Vec::new(), Vec::new());
+            for issue in issues.iter().filter(|i| i.lead_days == lead_days) {
+                let Some(actual) = actuals.get(issue.target_date.as_str()) else {
+                    continue;
+                };
+                high.push((issue.temp_max - actual.temp_max).abs());
+                low.push((issue.temp_min - actual.temp_min).abs());
+                precip.push((issue.precipitation - actual.precipitation_total).abs());
+            }
+            LeadAccuracy {
+                lead_days,
+                samples: high.len(),
+                temp_max_mae: mean(&high, temp_scale),
+                temp_min_mae: mean(&low, temp_scale),
+                precipitation_mae: mean(&precip, 1.0),
+            }
+        })
+        .collect()
+}
+
 fn round_2(val: f64) -> f64 {
     (val * 100.0).round() / 100.0
 }
@@ -829,6 +1716,7 @@ mod tests {
             humidity_avg: 60.0,
             wind_speed_avg: 5.0,
             precipitation_total: 0.0,
+            snow_total: 0.0,
             dominant_condition: Some("clear sky".to_string()),
         }
     }
@@ -877,6 +1765,48 @@ mod tests {
         assert_eq!(summary.temp_trend, "stable");
     }
 
+    #[test]
+    fn test_trend_precipitation_and_wind() {
+        let days: Vec<_> = [
+            (0.0, 0.0, 2.0),
+            (1.5, 0.0, 3.0),
+            (4.0, 3.0, 4.5),
+            (6.0, 0.0, 6.0),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (precipitation, snow, wind))| DailyHistorySummary {
+            precipitation_total: precipitation,
+            snow_total: snow,
+            wind_speed_avg: wind,
+            ..make_daily(&format!("2024-01-0{}", i + 1), 0.0, -2.0, 2.0)
+        })
+        .collect();
+
+        let summary = compute_trend_summary(&days);
+        assert_eq!(summary.temp_trend, "stable");
+        assert_eq!(summary.precipitation_trend, "rising");
+        assert_eq!(summary.wind_trend, "rising");
+        assert_eq!(summary.avg_wind_speed, 3.88);
+        // Day 3's 1mm of rain with its snow counts it as both
+        assert_eq!(summary.rainy_days, 3);
+        assert_eq!(summary.snowy_days, 1);
+
+        let calming: Vec<_> = days
+            .into_iter()
+            .rev()
+            .map(|d| DailyHistorySummary {
+                precipitation_total: 0.0,
+                snow_total: 0.0,
+                ..d
+            })
+            .collect();
+        let summary = compute_trend_summary(&calming);
+        assert_eq!(summary.precipitation_trend, "stable");
+        assert_eq!(summary.wind_trend, "falling");
+        assert_eq!(summary.rainy_days, 0);
+    }
+
     #[test]
     fn test_trend_empty_days() {
         let summary = compute_trend_summary(&[]);
@@ -899,10 +1829,95 @@ mod tests {
         assert_eq!(format_period(0, 30 * 86400), "30d");
     }
 
+    #[test]
+    fn test_month_bounds() {
+        assert_eq!(month_bounds("2023-12"), Some((1701388800, 1704067200)));
+        assert_eq!(month_bounds("2023-13"), None);
+        assert_eq!(month_of(1701388800), "2023-12");
+        assert_eq!(month_of(1701388799), "2023-11");
+    }
+
     #[test]
     fn test_round_2() {
         assert_eq!(round_2(15.456), 15.46);
         assert_eq!(round_2(15.0), 15.0);
         assert_eq!(round_2(15.005), 15.01);
     }
+
+    #[test]
+    fn test_observation_record() {
+        let observation: ObservationResponse = serde_json::from_value(serde_json::json!({
+            "dt": 1700000000,
+            "main": { "temp": 4.2, "feels_like": 1.0, "pressure": 1012, "humidity": 81 },
+            "visibility": 10000,
+            "wind": { "speed": 5.1, "deg": 250 },
+            "clouds": { "all": 75 },
+            "weather": [{ "id": 500, "main": "Rain", "description": "light rain", "icon": "10d" }],
+            "rain": { "1h": 0.3 }
+        }))
+        .unwrap();
+        let record = to_record(
+            "Chicago",
+            41.88,
+            -87.63,
+            observation.into(),
+            SOURCE_OBSERVATION,
+            1700000100,
+        );
+        assert_eq!(record.timestamp, 1700000000);
+        assert_eq!(record.clouds, Some(75));
+        assert_eq!(record.rain_1h, Some(0.3));
+        assert_eq!(record.condition_id, Some(500));
+        assert_eq!(record.units, STORAGE_UNITS);
+        assert_eq!(record.source, "observation");
+    }
+
+    #[test]
+    fn test_score_accuracy() {
+        let issue = |date: &str, lead_days: i64, temp_max: f64, precipitation: f64| ForecastIssue {
+            target_date: date.to_string(),
+            lead_days,
+            issued_at: 0,
+            temp_min: temp_max - 10.0,
+            temp_max,
+            precipitation,
+        };
+        let actual = |date: &str, temp_max: f64, readings: i64| DailySummaryRow {
+            date: date.to_string(),
+            temp_min: temp_max - 10.0,
+            temp_max,
+            temp_avg: temp_max - 5.0,
+            humidity_avg: 60.0,
+            wind_speed_avg: 3.0,
+            precipitation_total: 2.0,
+            snow_total: 0.0,
+            dominant_condition: None,
+            readings,
+        };
+        let issues = [
+            issue("2024-07-02", 1, 21.0, 2.0),
+            issue("2024-07-03", 1, 24.0, 6.0),
+            issue("2024-07-04", 1, 30.0, 0.0),
+            issue("2024-07-03", 3, 30.0, 0.0),
+        ];
+        let actuals = [
+            actual("2024-07-02", 20.0, 24),
+            actual("2024-07-03", 20.0, 24),
+            // Too few readings to score
+            actual("2024-07-04", 20.0, 6),
+        ];
+
+        let leads = score_accuracy(&issues, &actuals, "metric");
+        assert_eq!(leads.len(), 3);
+        assert_eq!(leads[0].samples, 2);
+        assert_eq!(leads[0].temp_max_mae, Some(2.5));
+        assert_eq!(leads[0].precipitation_mae, Some(2.0));
+        assert_eq!(leads[1].temp_min_mae, Some(10.0));
+        assert_eq!(leads[2].samples, 0);
+        assert_eq!(leads[2].temp_max_mae, None);
+
+        let imperial = score_accuracy(&issues, &actuals, "imperial");
+        assert_eq!(imperial[0].temp_max_mae, Some(4.5));
+        assert_eq!(imperial[0].precipitation_mae, Some(2.0));
+    }
 }
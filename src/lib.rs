@@ -0,0 +1,101 @@
+//! Weather API server: forecasts, history, and scheduled push notifications
+//! from OpenWeatherMap. The binary in main.rs wires these modules together.
+
+pub mod air_quality;
+pub mod api_budget;
+pub mod api_keys;
+pub mod api_v2;
+pub mod audit;
+pub mod auth;
+pub mod backfill;
+pub mod cache;
+pub mod cache_backend;
+pub mod charts;
+pub mod client_keys;
+pub mod compression;
+pub mod config;
+pub mod dashboard;
+pub mod db;
+pub mod devices;
+pub mod display;
+pub mod error;
+pub mod export;
+pub mod extractors;
+pub mod forecast;
+pub mod geocode;
+pub mod grpc;
+pub mod history;
+pub mod live;
+pub mod locations;
+pub mod metrics;
+pub mod middleware;
+pub mod notifications;
+pub mod object_store;
+pub mod openapi;
+pub mod ops_stats;
+pub mod recommendations;
+pub mod routes;
+pub mod sanity;
+pub mod scheduler;
+pub mod stats;
+pub mod units;
+pub mod upstream;
+pub mod warmup;
+pub mod weather;
+pub mod webhooks;
+
+use reqwest::Client;
+use std::sync::Arc;
+
+use crate::air_quality::AirQualityService;
+use crate::config::AppConfig;
+use crate::devices::DevicesService;
+use crate::forecast::ForecastService;
+use crate::history::HistoryService;
+use crate::locations::LocationsService;
+use crate::scheduler::SchedulerService;
+use crate::units::Units;
+use crate::weather::WeatherService;
+use metrics_exporter_prometheus::PrometheusHandle;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub http_client: Client,
+    pub db_pool: sqlx::SqlitePool,
+    pub weather_service: Arc<WeatherService>,
+    pub forecast_service: Arc<ForecastService>,
+    pub history_service: Arc<HistoryService>,
+    pub scheduler_service: Arc<SchedulerService>,
+    pub devices_service: Arc<DevicesService>,
+    pub air_quality_service: Arc<AirQualityService>,
+    pub config: Arc<AppConfig>,
+    pub metrics_handle: PrometheusHandle,
+    /// Rolling 24-hour counters for GET /admin/stats
+    pub ops_stats: Arc<ops_stats::OpsStats>,
+    pub api_budget: Arc<api_budget::ApiCallBudget>,
+    pub api_keys: Arc<api_keys::ApiKeyPool>,
+    /// Present when JWT auth is enabled
+    pub jwt_keys: Option<Arc<auth::JwtKeys>>,
+    pub client_keys: Arc<client_keys::ClientKeys>,
+    pub live: Arc<live::LiveUpdates>,
+    pub webhooks_service: Arc<webhooks::WebhooksService>,
+    pub locations_service: Arc<LocationsService>,
+    pub audit: Arc<audit::AuditLog>,
+    /// Unix time the server started
+    pub started_at: i64,
+}
+
+impl AppState {
+    /// Units for a request on `city` that didn't choose any: the saved
+    /// location's units for a `loc:<id>` reference, else the configured default
+    pub fn default_units(&self, city: &str) -> Units {
+        self.locations_service
+            .resolve(city)
+            .ok()
+            .flatten()
+            .and_then(|location| location.units)
+            .unwrap_or_else(|| self.config.units.clone())
+            .parse()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::Response,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::IntoParams;
+
+use super::models::{ClientMessage, ControlMessage};
+use super::{LiveUpdates, Subscriptions};
+use crate::auth::Principal;
+use crate::error::ErrorResponse;
+use crate::AppState;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LiveQuery {
+    /// Comma-separated cities to subscribe to on connect
+    pub cities: Option<String>,
+}
+
+/// Open a WebSocket for live updates
+///
+/// GET /api/v1/ws?cities=Chicago,Denver
+///
+/// Clients receive current conditions, new and ended alerts, and job results
+/// for subscribed cities as forecast runs happen. Job results are only sent
+/// for the caller's own jobs, unless the caller is an admin. Subscriptions can be changed
+/// at any time by sending `{"action": "subscribe", "cities": [...]}` or
+/// `{"action": "unsubscribe", "cities": [...]}`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/ws",
+    tag = "live",
+    params(LiveQuery),
+    responses(
+        (status = 101, description = "WebSocket upgrade; messages are LiveEvent JSON objects"),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn live_updates(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Query(query): Query<LiveQuery>,
+) -> Response {
+    let mut subscriptions = Subscriptions::default();
+    if let Some(cities) = query.cities {
+        let cities: Vec<String> = cities.split(',').map(str::to_string).collect();
+        subscriptions.add(&cities);
+    }
+    let live = Arc::clone(&state.live);
+    let principal = principal.map(|Extension(p)| p);
+    ws.on_upgrade(move |socket| handle_socket(socket, live, subscriptions, principal))
+}
+
+async fn handle_socket(
+    mut socket: WebSocket,
+    live: Arc<LiveUpdates>,
+    mut subs: Subscriptions,
+    principal: Option<Principal>,
+) {
+    let mut events = live.subscribe();
+    metrics::gauge!(crate::metrics::LIVE_CONNECTIONS).increment(1.0);
+
+    let greeting = ControlMessage::Subscribed {
+        cities: subs.cities(),
+    };
+    if send_json(&mut socket, &greeting).await {
+        loop {
+            let open = tokio::select! {
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        let reply = apply_client_message(&mut subs, text.as_str());
+                        send_json(&mut socket, &reply).await
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => false,
+                    Some(Ok(_)) => true,
+                },
+                event = events.recv() => match event {
+                    Ok(event) if subs.matches(&event) && event.visible_to(principal.as_ref()) => {
+                        send_json(&mut socket, &event).await
+                    }
+                    Ok(_) => true,
+                    Err(RecvError::Lagged(missed)) => {
+                        send_json(&mut socket, &ControlMessage::Lagged { missed }).await
+                    }
+                    Err(RecvError::Closed) => false,
+                },
+            };
+            if !open {
+                break;
+            }
+        }
+    }
+
+    metrics::gauge!(crate::metrics::LIVE_CONNECTIONS).decrement(1.0);
+}
+
+/// Update subscriptions from a client message and build the reply
+fn apply_client_message(subs: &mut Subscriptions, text: &str) -> ControlMessage {
+    match serde_json::from_str::<ClientMessage>(text) {
+        Ok(ClientMessage::Subscribe { cities }) => subs.add(&cities),
+        Ok(ClientMessage::Unsubscribe { cities }) => subs.remove(&cities),
+        Err(e) => {
+            return ControlMessage::Error {
+                message: format!("Invalid message: {}", e),
+            }
+        }
+    }
+    ControlMessage::Subscribed {
+        cities: subs.cities(),
+    }
+}
+
+/// Send a JSON text frame. Returns false once the client has gone away.
+async fn send_json<T: Serialize>(socket: &mut WebSocket, value: &T) -> bool {
+    let Ok(json) = serde_json::to_string(value) else {
+        return true;
+    };
+    socket.send(Message::Text(json.into())).await.is_ok()
+}
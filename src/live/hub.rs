@@ -0,0 +1,124 @@
+use std::collections::BTreeSet;
+
+use tokio::sync::broadcast;
+
+use super::models::LiveEvent;
+use crate::cache::normalize_cache_key;
+
+/// Updates buffered per subscriber before slow clients start missing some
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Fan-out of live weather updates from the scheduler and alert expiry task
+/// to connected WebSocket clients
+pub struct LiveUpdates {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl LiveUpdates {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Push an update to every connected client; a no-op when nobody is listening
+    pub fn publish(&self, event: LiveEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for LiveUpdates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cities one WebSocket client has subscribed to, matched case- and
+/// whitespace-insensitively against the requested or geocoded city
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    cities: BTreeSet<String>,
+}
+
+impl Subscriptions {
+    pub fn add(&mut self, cities: &[String]) {
+        self.cities.extend(
+            cities
+                .iter()
+                .map(|c| normalize_cache_key(c))
+                .filter(|c| !c.is_empty()),
+        );
+    }
+
+    pub fn remove(&mut self, cities: &[String]) {
+        for city in cities {
+            self.cities.remove(&normalize_cache_key(city));
+        }
+    }
+
+    pub fn cities(&self) -> Vec<String> {
+        self.cities.iter().cloned().collect()
+    }
+
+    pub fn matches(&self, event: &LiveEvent) -> bool {
+        self.cities.contains(&normalize_cache_key(event.city()))
+            || event
+                .location()
+                .is_some_and(|l| self.cities.contains(&normalize_cache_key(&l.city)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{Principal, Role};
+
+    fn job_result(city: &str) -> LiveEvent {
+        LiveEvent::JobResult {
+            city: city.to_string(),
+            job: Some("Morning".to_string()),
+            success: true,
+            error: None,
+            notifications_sent: 1,
+            owner: Some("alice".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_subscriptions_match_normalized_city() {
+        let mut subs = Subscriptions::default();
+        subs.add(&["Chicago".to_string(), " ".to_string()]);
+        assert_eq!(subs.cities(), vec!["chicago"]);
+        assert!(subs.matches(&job_result("  CHICAGO ")));
+        assert!(!subs.matches(&job_result("Denver")));
+
+        subs.remove(&["chicago".to_string()]);
+        assert!(!subs.matches(&job_result("Chicago")));
+    }
+
+    #[test]
+    fn test_job_results_visible_to_owner() {
+        let principal = |username: &str, role| Principal {
+            username: username.to_string(),
+            role,
+        };
+        let event = job_result("Chicago");
+        assert!(event.visible_to(None));
+        assert!(event.visible_to(Some(&principal("alice", Role::User))));
+        assert!(event.visible_to(Some(&principal("root", Role::Admin))));
+        assert!(!event.visible_to(Some(&principal("bob", Role::User))));
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let live = LiveUpdates::new();
+        live.publish(job_result("Nowhere"));
+
+        let mut rx = live.subscribe();
+        live.publish(job_result("Chicago"));
+        assert_eq!(rx.recv().await.unwrap().city(), "Chicago");
+    }
+}
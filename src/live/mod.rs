@@ -0,0 +1,6 @@
+pub mod handlers;
+mod hub;
+pub mod models;
+
+pub use hub::{LiveUpdates, Subscriptions};
+pub use models::LiveEvent;
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::auth::Principal;
+use crate::forecast::models::{AlertResponse, CurrentWeatherResponse, LocationInfo};
+
+/// An update pushed to WebSocket subscribers of a city
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LiveEvent {
+    /// Current conditions from a fresh forecast run
+    Conditions {
+        city: String,
+        location: LocationInfo,
+        current: CurrentWeatherResponse,
+    },
+    /// An alert that wasn't active on the city's previous run
+    Alert {
+        city: String,
+        location: LocationInfo,
+        alert: AlertResponse,
+    },
+    /// A tracked alert that expired or was cancelled
+    AlertEnded {
+        city: String,
+        location: LocationInfo,
+        alert: AlertResponse,
+    },
+    /// Outcome of a scheduled job run or manual trigger
+    #[serde(rename_all = "camelCase")]
+    JobResult {
+        city: String,
+        /// Job name; absent for manual triggers
+        #[serde(skip_serializing_if = "Option::is_none")]
+        job: Option<String>,
+        success: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        notifications_sent: usize,
+        /// Owner of the job, or of the caller who triggered it
+        #[serde(skip)]
+        owner: Option<String>,
+    },
+}
+
+impl LiveEvent {
+    /// City the event was produced for, as the job or trigger named it
+    pub fn city(&self) -> &str {
+        match self {
+            Self::Conditions { city, .. }
+            | Self::Alert { city, .. }
+            | Self::AlertEnded { city, .. }
+            | Self::JobResult { city, .. } => city,
+        }
+    }
+
+    /// Whether `principal` may see the event: job results go only to their
+    /// owner and admins, weather to everyone. Without auth all events are.
+    pub fn visible_to(&self, principal: Option<&Principal>) -> bool {
+        match self {
+            Self::JobResult { owner, .. } => {
+                principal.is_none_or(|p| p.can_access(owner.as_deref()))
+            }
+            _ => true,
+        }
+    }
+
+    /// Geocoded location, when the event carries one
+    pub fn location(&self) -> Option<&LocationInfo> {
+        match self {
+            Self::Conditions { location, .. }
+            | Self::Alert { location, .. }
+            | Self::AlertEnded { location, .. } => Some(location),
+            Self::JobResult { .. } => None,
+        }
+    }
+}
+
+/// Messages a WebSocket client sends to manage its subscriptions
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum ClientMessage {
+    Subscribe { cities: Vec<String> },
+    Unsubscribe { cities: Vec<String> },
+}
+
+/// Control messages sent back to a WebSocket client
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ControlMessage {
+    /// Current subscriptions after a subscribe or unsubscribe
+    Subscribed {
+        cities: Vec<String>,
+    },
+    /// The client fell behind and this many updates were dropped
+    Lagged {
+        missed: u64,
+    },
+    Error {
+        message: String,
+    },
+}
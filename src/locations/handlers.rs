@@ -0,0 +1,204 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+use super::models::{parse_reference, LocationListResponse, LocationRequest, SavedLocation};
+use super::LocationError;
+use crate::audit::{self, AuditAction, AuditActor, AuditResource};
+use crate::auth::Principal;
+use crate::error::ErrorResponse;
+use crate::AppState;
+
+/// List saved locations. Users only see their own.
+/// GET /locations
+#[utoipa::path(
+    get,
+    path = "/api/v1/locations",
+    tag = "locations",
+    responses(
+        (status = 200, description = "Saved locations the caller can manage", body = LocationListResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_locations(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> Json<LocationListResponse> {
+    let principal = principal.map(|Extension(p)| p);
+    let locations = state.locations_service.list(principal.as_ref());
+    Json(LocationListResponse {
+        count: locations.len(),
+        locations,
+    })
+}
+
+/// Get a saved location
+/// GET /locations/{id}
+#[utoipa::path(
+    get,
+    path = "/api/v1/locations/{id}",
+    tag = "locations",
+    params(("id" = String, Path, description = "Location ID")),
+    responses(
+        (status = 200, description = "Saved location", body = SavedLocation),
+        (status = 404, description = "Location not found", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_location(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<Json<SavedLocation>, LocationError> {
+    let principal = principal.map(|Extension(p)| p);
+    let location = state.locations_service.get(&id, principal.as_ref())?;
+    Ok(Json(location))
+}
+
+/// Save a location. Use it as `loc:<id>` wherever a city is accepted.
+/// POST /locations
+#[utoipa::path(
+    post,
+    path = "/api/v1/locations",
+    tag = "locations",
+    request_body = LocationRequest,
+    responses(
+        (status = 201, description = "Location saved", body = SavedLocation),
+        (status = 400, description = "Invalid location", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create_location(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
+    Json(request): Json<LocationRequest>,
+) -> Result<(StatusCode, Json<SavedLocation>), LocationError> {
+    let principal = principal.map(|Extension(p)| p);
+    let location = state
+        .locations_service
+        .create(request, principal.as_ref())
+        .await?;
+    state
+        .audit
+        .record(
+            &actor,
+            AuditAction::Create,
+            AuditResource::Location,
+            &location.id,
+            audit::snapshot(&location),
+        )
+        .await;
+    Ok((StatusCode::CREATED, Json(location)))
+}
+
+/// Replace a saved location's label, place, and units. Jobs and devices
+/// using it pick up the change on their next run.
+/// PUT /locations/{id}
+#[utoipa::path(
+    put,
+    path = "/api/v1/locations/{id}",
+    tag = "locations",
+    params(("id" = String, Path, description = "Location ID")),
+    request_body = LocationRequest,
+    responses(
+        (status = 200, description = "Location updated", body = SavedLocation),
+        (status = 400, description = "Invalid location", body = ErrorResponse),
+        (status = 404, description = "Location not found", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn update_location(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
+    Path(id): Path<String>,
+    Json(request): Json<LocationRequest>,
+) -> Result<Json<SavedLocation>, LocationError> {
+    let principal = principal.map(|Extension(p)| p);
+    let (before, location) = state
+        .locations_service
+        .update(&id, request, principal.as_ref())
+        .await?;
+    state
+        .audit
+        .record(
+            &actor,
+            AuditAction::Update,
+            AuditResource::Location,
+            &id,
+            audit::changes(&before, &location),
+        )
+        .await;
+    Ok(Json(location))
+}
+
+/// Describe the jobs and devices referring to saved location `id`, if any
+async fn location_users(state: &AppState, id: &str) -> Option<String> {
+    let refers = |city: &str| parse_reference(city) == Some(id);
+    let features = &state.config.features;
+
+    let jobs = if features.scheduler {
+        let jobs = state.scheduler_service.get_jobs().await;
+        jobs.iter().filter(|job| refers(&job.city)).count()
+    } else {
+        0
+    };
+    let devices = if features.devices {
+        let devices = state.devices_service.get_all().await;
+        devices
+            .iter()
+            .filter(|device| device.cities.iter().any(|c| refers(c)))
+            .count()
+    } else {
+        0
+    };
+
+    (jobs + devices > 0).then(|| format!("{} job(s) and {} device(s)", jobs, devices))
+}
+
+/// Delete a saved location. Refused while jobs or devices still use it.
+/// DELETE /locations/{id}
+#[utoipa::path(
+    delete,
+    path = "/api/v1/locations/{id}",
+    tag = "locations",
+    params(("id" = String, Path, description = "Location ID")),
+    responses(
+        (status = 204, description = "Location deleted"),
+        (status = 404, description = "Location not found", body = ErrorResponse),
+        (status = 409, description = "Location still used by jobs or devices", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete_location(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
+    Path(id): Path<String>,
+) -> Result<StatusCode, LocationError> {
+    let principal = principal.map(|Extension(p)| p);
+    // Check access before revealing who uses it
+    state.locations_service.get(&id, principal.as_ref())?;
+    if let Some(users) = location_users(&state, &id).await {
+        return Err(LocationError::InUse(users));
+    }
+
+    let location = state
+        .locations_service
+        .delete(&id, principal.as_ref())
+        .await?;
+    state
+        .audit
+        .record(
+            &actor,
+            AuditAction::Delete,
+            AuditResource::Location,
+            &id,
+            audit::snapshot(&location),
+        )
+        .await;
+    Ok(StatusCode::NO_CONTENT)
+}
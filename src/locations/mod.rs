@@ -0,0 +1,6 @@
+pub mod handlers;
+pub mod models;
+mod service;
+
+pub use models::{SavedLocation, REFERENCE_PREFIX};
+pub use service::{LocationError, LocationsService};
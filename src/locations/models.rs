@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Prefix marking a saved location reference in place of a city
+pub const REFERENCE_PREFIX: &str = "loc:";
+
+/// A named place. Jobs, devices, and API calls refer to it as `loc:<id>`
+/// wherever a city is accepted, so renaming or moving it updates everything
+/// that uses it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedLocation {
+    pub id: String,
+    /// Shown in place of the geocoded city name
+    pub label: String,
+    /// City name, "City,Country", or zip code; used when no coordinates are set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lat: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lon: Option<f64>,
+    /// Units for API calls on this location that don't choose any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    /// Owning user (None = shared, admin-managed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl SavedLocation {
+    /// A configured alias, such as `home = "41.88,-87.63"` or
+    /// `cabin = "Minocqua,WI,US"`. Its name is shown as the label.
+    pub fn alias(name: &str, target: &str) -> Self {
+        let coordinates = parse_coordinates(target);
+        Self {
+            id: name.to_string(),
+            label: name.to_string(),
+            city: coordinates.is_none().then(|| target.trim().to_string()),
+            lat: coordinates.map(|c| c.0),
+            lon: coordinates.map(|c| c.1),
+            units: None,
+            owner: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    /// What to geocode: the coordinates when set, else the city
+    pub fn query(&self) -> String {
+        match (self.lat, self.lon) {
+            (Some(lat), Some(lon)) => format!("{},{}", lat, lon),
+            _ => self.city.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// ID of the saved location `input` refers to, if it is a `loc:<id>` reference
+pub fn parse_reference(input: &str) -> Option<&str> {
+    input
+        .trim()
+        .strip_prefix(REFERENCE_PREFIX)
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+}
+
+/// "lat,lon" within valid ranges, e.g. "41.88,-87.63"
+pub fn parse_coordinates(input: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = input.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// Request to create or replace a saved location. Set `city`, or both `lat`
+/// and `lon`.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationRequest {
+    pub label: String,
+    pub city: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// metric, imperial, or standard
+    pub units: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LocationListResponse {
+    pub locations: Vec<SavedLocation>,
+    pub count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(parse_reference("loc:abc-123"), Some("abc-123"));
+        assert_eq!(parse_reference(" loc: abc "), Some("abc"));
+        assert_eq!(parse_reference("loc:"), None);
+        assert_eq!(parse_reference("London"), None);
+    }
+
+    #[test]
+    fn test_alias_targets() {
+        let home = SavedLocation::alias("home", "41.88, -87.63");
+        assert_eq!((home.lat, home.lon), (Some(41.88), Some(-87.63)));
+        assert_eq!(home.city, None);
+
+        let cabin = SavedLocation::alias("cabin", "Minocqua,WI,US");
+        assert_eq!(cabin.city.as_deref(), Some("Minocqua,WI,US"));
+        assert_eq!(cabin.query(), "Minocqua,WI,US");
+        assert_eq!(parse_coordinates("95,10"), None);
+    }
+}
@@ -0,0 +1,348 @@
+use axum::http::StatusCode;
+use dashmap::DashMap;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::models::{parse_reference, LocationRequest, SavedLocation};
+use crate::auth::Principal;
+use crate::db::{DbError, LocationRepository, SqliteLocationRepository};
+use crate::error::HttpError;
+use crate::impl_into_response;
+use crate::units::Units;
+
+#[derive(Error, Debug)]
+pub enum LocationError {
+    #[error("Saved location not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid location: {0}")]
+    Invalid(String),
+
+    #[error("Saved location is used by {0}")]
+    InUse(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+}
+
+impl HttpError for LocationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Invalid(_) => StatusCode::BAD_REQUEST,
+            Self::InUse(_) => StatusCode::CONFLICT,
+            Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::NotFound(_) => Some("LOCATION_NOT_FOUND"),
+            Self::Invalid(_) => Some("INVALID_LOCATION"),
+            Self::InUse(_) => Some("LOCATION_IN_USE"),
+            Self::Database(_) => Some("DATABASE_ERROR"),
+        }
+    }
+}
+
+impl_into_response!(LocationError);
+
+/// Check a create/replace request, returning the trimmed label and city
+fn validate(request: &LocationRequest) -> Result<(String, Option<String>), LocationError> {
+    let label = request.label.trim().to_string();
+    if label.is_empty() {
+        return Err(LocationError::Invalid("label is required".to_string()));
+    }
+    let city = request
+        .city
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(str::to_string);
+
+    match (request.lat, request.lon) {
+        (Some(lat), Some(lon)) => {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                return Err(LocationError::Invalid(
+                    "lat must be within ±90 and lon within ±180".to_string(),
+                ));
+            }
+        }
+        (None, None) if city.is_some() => {}
+        (None, None) => {
+            return Err(LocationError::Invalid(
+                "set city, or both lat and lon".to_string(),
+            ))
+        }
+        _ => {
+            return Err(LocationError::Invalid(
+                "lat and lon must be set together".to_string(),
+            ))
+        }
+    }
+
+    if let Some(ref units) = request.units {
+        units
+            .parse::<Units>()
+            .map_err(|err| LocationError::Invalid(err.to_string()))?;
+    }
+
+    Ok((label, city))
+}
+
+/// Service for managing saved locations and resolving `loc:<id>` references
+/// and aliases
+///
+/// Every location is kept in memory, so resolving a reference never waits
+/// on the database.
+pub struct LocationsService {
+    repo: SqliteLocationRepository,
+    locations: DashMap<String, SavedLocation>,
+    /// Configured aliases, keyed by lowercase name
+    aliases: HashMap<String, SavedLocation>,
+}
+
+impl LocationsService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            repo: SqliteLocationRepository::new(pool),
+            locations: DashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Resolve these names (case-insensitively) to a place: "lat,lon" or
+    /// anything the geocoder accepts
+    pub fn with_aliases(mut self, aliases: &HashMap<String, String>) -> Self {
+        self.aliases = aliases
+            .iter()
+            .map(|(name, target)| {
+                let name = name.trim();
+                (name.to_lowercase(), SavedLocation::alias(name, target))
+            })
+            .collect();
+        self
+    }
+
+    /// Load saved locations from the database
+    pub async fn init(&self) -> Result<(), LocationError> {
+        for location in self.repo.get_all().await? {
+            self.locations.insert(location.id.clone(), location);
+        }
+        tracing::info!(count = self.locations.len(), "Loaded saved locations");
+        Ok(())
+    }
+
+    /// The saved location `input` refers to: a `loc:<id>` reference, a
+    /// configured alias, or the label of a shared saved location, in that
+    /// order. `Ok(None)` when it is none of these.
+    pub fn resolve(&self, input: &str) -> Result<Option<SavedLocation>, LocationError> {
+        if let Some(id) = parse_reference(input) {
+            return self
+                .locations
+                .get(id)
+                .map(|l| Some(l.clone()))
+                .ok_or_else(|| LocationError::NotFound(id.to_string()));
+        }
+
+        let name = input.trim().to_lowercase();
+        if let Some(alias) = self.aliases.get(&name) {
+            return Ok(Some(alias.clone()));
+        }
+        // Users' own labels stay private; only shared ones act as aliases
+        Ok(self
+            .locations
+            .iter()
+            .filter(|l| l.owner.is_none() && l.label.to_lowercase() == name)
+            .min_by_key(|l| l.created_at)
+            .map(|l| l.clone()))
+    }
+
+    /// Saved locations the caller can see, by label
+    pub fn list(&self, principal: Option<&Principal>) -> Vec<SavedLocation> {
+        let mut locations: Vec<SavedLocation> = self
+            .locations
+            .iter()
+            .filter(|l| principal.is_none_or(|p| p.can_access(l.owner.as_deref())))
+            .map(|l| l.clone())
+            .collect();
+        locations.sort_by(|a, b| a.label.cmp(&b.label));
+        locations
+    }
+
+    /// A saved location, if the caller can see it
+    pub fn get(
+        &self,
+        id: &str,
+        principal: Option<&Principal>,
+    ) -> Result<SavedLocation, LocationError> {
+        self.locations
+            .get(id)
+            .map(|l| l.clone())
+            .filter(|l| principal.is_none_or(|p| p.can_access(l.owner.as_deref())))
+            .ok_or_else(|| LocationError::NotFound(id.to_string()))
+    }
+
+    /// Save a new location owned by the caller
+    pub async fn create(
+        &self,
+        request: LocationRequest,
+        principal: Option<&Principal>,
+    ) -> Result<SavedLocation, LocationError> {
+        let (label, city) = validate(&request)?;
+        let now = chrono::Utc::now().timestamp();
+        let location = SavedLocation {
+            id: Uuid::new_v4().to_string(),
+            label,
+            city,
+            lat: request.lat,
+            lon: request.lon,
+            units: request.units,
+            owner: principal.and_then(|p| p.owner_filter()),
+            created_at: now,
+            updated_at: now,
+        };
+        self.repo.upsert(&location).await?;
+        self.locations.insert(location.id.clone(), location.clone());
+        tracing::info!(location_id = %location.id, label = %location.label, "Saved location");
+        Ok(location)
+    }
+
+    /// Replace a saved location's details. Returns it before and after.
+    pub async fn update(
+        &self,
+        id: &str,
+        request: LocationRequest,
+        principal: Option<&Principal>,
+    ) -> Result<(SavedLocation, SavedLocation), LocationError> {
+        let before = self.get(id, principal)?;
+        let (label, city) = validate(&request)?;
+        let location = SavedLocation {
+            label,
+            city,
+            lat: request.lat,
+            lon: request.lon,
+            units: request.units,
+            updated_at: chrono::Utc::now().timestamp(),
+            ..before.clone()
+        };
+        self.repo.upsert(&location).await?;
+        self.locations.insert(id.to_string(), location.clone());
+        tracing::info!(location_id = %id, label = %location.label, "Updated saved location");
+        Ok((before, location))
+    }
+
+    /// Delete a saved location, returning it
+    pub async fn delete(
+        &self,
+        id: &str,
+        principal: Option<&Principal>,
+    ) -> Result<SavedLocation, LocationError> {
+        let location = self.get(id, principal)?;
+        if !self.repo.remove(id).await? {
+            return Err(LocationError::NotFound(id.to_string()));
+        }
+        self.locations.remove(id);
+        tracing::info!(location_id = %id, "Deleted saved location");
+        Ok(location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(label: &str, city: Option<&str>, coords: Option<(f64, f64)>) -> LocationRequest {
+        LocationRequest {
+            label: label.to_string(),
+            city: city.map(str::to_string),
+            lat: coords.map(|c| c.0),
+            lon: coords.map(|c| c.1),
+            units: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&request("Home", None, Some((41.88, -87.63)))).is_ok());
+        assert!(validate(&request("Cabin", Some("Minocqua,WI,US"), None)).is_ok());
+        assert!(validate(&request(" ", Some("Chicago"), None)).is_err());
+        assert!(validate(&request("Home", Some(" "), None)).is_err());
+        assert!(validate(&request("Home", None, Some((95.0, 0.0)))).is_err());
+
+        let mut half = request("Home", Some("Chicago"), None);
+        half.lat = Some(41.88);
+        assert!(validate(&half).is_err());
+
+        let mut units = request("Home", Some("Chicago"), None);
+        units.units = Some("kelvin".to_string());
+        assert!(validate(&units).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_follows_updates() {
+        let pool = crate::db::create_pool(&crate::db::DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        crate::db::run_migrations(&pool, &Default::default())
+            .await
+            .unwrap();
+        let service = LocationsService::new(pool);
+
+        let home = service
+            .create(request("Home", Some("Chicago"), None), None)
+            .await
+            .unwrap();
+        let reference = format!("loc:{}", home.id);
+        assert_eq!(
+            service.resolve(&reference).unwrap().unwrap().query(),
+            "Chicago"
+        );
+        assert!(service.resolve("Chicago").unwrap().is_none());
+        assert!(service.resolve("loc:missing").is_err());
+
+        service
+            .update(&home.id, request("Home", None, Some((44.1, -89.5))), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            service.resolve(&reference).unwrap().unwrap().query(),
+            "44.1,-89.5"
+        );
+
+        service.delete(&home.id, None).await.unwrap();
+        assert!(service.resolve(&reference).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_aliases() {
+        let pool = crate::db::create_pool(&crate::db::DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        crate::db::run_migrations(&pool, &Default::default())
+            .await
+            .unwrap();
+        let aliases = HashMap::from([("home".to_string(), "41.88,-87.63".to_string())]);
+        let service = LocationsService::new(pool).with_aliases(&aliases);
+
+        let home = service.resolve(" Home ").unwrap().unwrap();
+        assert_eq!(home.label, "home");
+        assert_eq!(home.query(), "41.88,-87.63");
+
+        service
+            .create(request("Cabin", Some("Minocqua,WI,US"), None), None)
+            .await
+            .unwrap();
+        let cabin = service.resolve("cabin").unwrap().unwrap();
+        assert_eq!(cabin.query(), "Minocqua,WI,US");
+        assert!(service.resolve("Chicago").unwrap().is_none());
+    }
+}
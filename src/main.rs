@@ -1,30 +1,9 @@
-mod air_quality;
-mod api_budget;
-mod backfill;
-mod cache;
-mod config;
-mod db;
-mod devices;
-mod error;
-mod extractors;
-mod forecast;
-mod geocode;
-mod history;
-mod metrics;
-mod middleware;
-mod notifications;
-mod openapi;
-mod routes;
-mod scheduler;
-mod stats;
-mod weather;
-
 use axum::{
     error_handling::HandleErrorLayer,
     http::{self, Method, StatusCode},
     BoxError,
 };
-use reqwest::Client;
+use reqwest::{Client, NoProxy, Proxy};
 use std::net::SocketAddr;
 use std::{sync::Arc, time::Duration};
 use tower::ServiceBuilder;
@@ -33,42 +12,54 @@ use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::air_quality::AirQualityService;
-use crate::cache::{create_geo_cache, start_cache_cleanup_task};
-use crate::config::AppConfig;
-use crate::devices::DevicesService;
-use crate::forecast::ForecastService;
-use crate::history::HistoryService;
-use crate::metrics::init_metrics;
-use crate::scheduler::{JobConfig, SchedulerService};
-use crate::weather::WeatherService;
-use metrics_exporter_prometheus::PrometheusHandle;
+use weathrs::air_quality::AirQualityService;
+use weathrs::cache::{create_geo_cache, start_cache_cleanup_task};
+use weathrs::cache_backend::create_shared_cache;
+use weathrs::config::AppConfig;
+use weathrs::devices::{
+    start_delivery_log_prune_task, start_digest_flush_task, start_receipt_check_task,
+    DevicesService,
+};
+use weathrs::forecast::{ForecastService, NwsClient, WeatherApi};
+use weathrs::history::{start_forecast_archive_prune_task, HistoryService};
+use weathrs::locations::LocationsService;
+use weathrs::metrics::init_metrics;
+use weathrs::notifications::AdminNotifier;
+use weathrs::scheduler::{
+    start_alert_expiry_task, start_device_zone_refresh_task, JobConfig, SchedulerService,
+};
+use weathrs::weather::WeatherService;
+use weathrs::{
+    api_budget, api_keys, audit, auth, backfill, client_keys, db, export, grpc, history, live,
+    routes, upstream, warmup, webhooks, AppState,
+};
 
 const HTTP_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
 
-#[derive(Clone)]
-pub struct AppState {
-    pub http_client: Client,
-    pub db_pool: sqlx::SqlitePool,
-    pub weather_service: Arc<WeatherService>,
-    pub forecast_service: Arc<ForecastService>,
-    pub history_service: Arc<HistoryService>,
-    pub scheduler_service: Arc<SchedulerService>,
-    pub devices_service: Arc<DevicesService>,
-    pub air_quality_service: Arc<AirQualityService>,
-    pub config: Arc<AppConfig>,
-    pub metrics_handle: PrometheusHandle,
-    pub api_budget: Arc<api_budget::ApiCallBudget>,
-}
-
-/// Create shared HTTP client with connection pooling
+/// Create shared HTTP client with connection pooling. A configured proxy
+/// replaces the one reqwest otherwise picks up from HTTPS_PROXY and friends.
 fn create_http_client(config: &AppConfig) -> Result<Client, reqwest::Error> {
-    Client::builder()
+    let mut builder = Client::builder()
         .timeout(Duration::from_secs(config.request_timeout_secs))
         .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
         .pool_idle_timeout(Duration::from_secs(HTTP_POOL_IDLE_TIMEOUT_SECS))
-        .pool_max_idle_per_host(10)
-        .build()
+        .pool_max_idle_per_host(10);
+
+    if let Some(ref url) = config.proxy.url {
+        let no_proxy = if config.proxy.no_proxy.is_empty() {
+            NoProxy::from_env()
+        } else {
+            NoProxy::from_string(&config.proxy.no_proxy.join(","))
+        };
+        builder = builder.proxy(Proxy::all(url)?.no_proxy(no_proxy));
+        // The URL may carry credentials, so it isn't logged
+        tracing::info!(
+            bypass = config.proxy.no_proxy.len(),
+            "Routing outbound requests through configured proxy"
+        );
+    }
+
+    builder.build()
 }
 
 /// Handle request timeout errors
@@ -110,8 +101,45 @@ async fn shutdown_signal() {
     tracing::info!("Shutdown signal received, starting graceful shutdown");
 }
 
+/// Resolve once the shared shutdown signal has fired
+async fn wait_for_shutdown(mut rx: tokio::sync::watch::Receiver<()>) {
+    let _ = rx.changed().await;
+}
+
+/// `weathrs check-config`: load and validate the configuration without
+/// starting the server. Exits non-zero if anything is wrong.
+fn check_config() -> i32 {
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: failed to load configuration: {}", e);
+            return 1;
+        }
+    };
+
+    let problems = config.validate();
+    if problems.is_empty() {
+        println!(
+            "Configuration OK ({} API key(s), {} scheduled job(s))",
+            config.owm_api_keys().len(),
+            config.scheduler.jobs.len()
+        );
+        return 0;
+    }
+
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+    eprintln!("{} configuration problem(s) found", problems.len());
+    1
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("check-config") {
+        std::process::exit(check_config());
+    }
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -122,12 +150,22 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     // Initialize Prometheus metrics recorder
-    let metrics_handle = init_metrics();
+    let (metrics_handle, ops_stats) = init_metrics();
     tracing::info!("Prometheus metrics initialized");
 
     // Load configuration
     let config = AppConfig::load()?;
     tracing::info!("Configuration loaded successfully");
+    for problem in config.validate() {
+        tracing::warn!(problem = %problem, "Configuration problem");
+    }
+    let auth_problems = config.auth.problems();
+    if !auth_problems.is_empty() {
+        anyhow::bail!(
+            "refusing to start with auth enabled: {}",
+            auth_problems.join("; ")
+        );
+    }
 
     // Create shared HTTP client with connection pooling
     let http_client = create_http_client(&config)?;
@@ -139,93 +177,236 @@ async fn main() -> anyhow::Result<()> {
         ..Default::default()
     };
     let db_pool = db::create_pool(&db_config).await?;
-    db::run_migrations(&db_pool).await?;
+    db::run_migrations(&db_pool, &config.features).await?;
     tracing::info!("Database initialized");
 
     // Create geocoding cache with in-memory TTL + SQLite persistence
-    let geo_cache = create_geo_cache(db_pool.clone());
-    start_cache_cleanup_task(geo_cache.clone());
+    let shared_cache = create_shared_cache(&config.cache).await?;
+    let geo_cache = create_geo_cache(db_pool.clone(), &config.cache, shared_cache.clone());
+    start_cache_cleanup_task(
+        geo_cache.clone(),
+        Duration::from_secs(config.cache.cleanup_interval_secs),
+    );
     tracing::debug!("Geocoding cache initialized");
 
     // Create shared API call budget
-    let api_budget = Arc::new(api_budget::ApiCallBudget::new(
-        config.history_backfill.daily_budget,
-    ));
+    let mut api_budget = api_budget::ApiCallBudget::new(config.api_daily_limit);
+    match config.api_budget.reset_timezone.parse::<chrono_tz::Tz>() {
+        Ok(tz) => api_budget = api_budget.with_reset_timezone(tz),
+        Err(_) => tracing::warn!(
+            timezone = %config.api_budget.reset_timezone,
+            "Invalid API budget reset timezone, using UTC"
+        ),
+    }
+    for (feature, percent) in &config.api_budget.feature_limits {
+        api_budget = api_budget.with_feature_limit(*feature, *percent);
+    }
+    let api_budget = Arc::new(api_budget);
+
+    // Rotate between configured OWM API keys
+    let api_keys = Arc::new(
+        api_keys::ApiKeyPool::new(config.owm_api_keys())
+            .with_upstream(&config.upstream)
+            .with_upstream_alert(&config.upstream_alert)
+            .with_budget(Arc::clone(&api_budget)),
+    );
+    if api_keys.len() > 1 {
+        tracing::info!(
+            keys = api_keys.len(),
+            "OpenWeatherMap API key rotation enabled"
+        );
+    }
+
+    // Saved locations, referenced as "loc:<id>" in place of a city
+    let locations_service =
+        Arc::new(LocationsService::new(db_pool.clone()).with_aliases(&config.aliases));
+    locations_service.init().await?;
 
     // Initialize services with shared client
-    let weather_service = Arc::new(WeatherService::new(
-        http_client.clone(),
-        &config.openweathermap_api_key,
-        Arc::clone(&api_budget),
-    ));
-    let forecast_service = Arc::new(ForecastService::new(
+    let weather_service = Arc::new(
+        WeatherService::new(
+            http_client.clone(),
+            Arc::clone(&api_keys),
+            Arc::clone(&api_budget),
+        )
+        .with_cache_limits(
+            Duration::from_secs(config.cache.weather_ttl_secs),
+            config.cache.weather_max_entries,
+        )
+        .with_locations(Arc::clone(&locations_service)),
+    );
+    let mut forecast_service = ForecastService::new(
         http_client.clone(),
-        &config.openweathermap_api_key,
+        Arc::clone(&api_keys),
         geo_cache.clone(),
         Arc::clone(&api_budget),
-    ));
-    let history_service = Arc::new(HistoryService::new(
+    )
+    .with_cache_limits(
+        config.cache.forecast_ttls(),
+        config.cache.forecast_max_entries,
+    )
+    .with_locations(Arc::clone(&locations_service))
+    .with_provider(Arc::new(NwsClient::new(
         http_client.clone(),
-        &config.openweathermap_api_key,
-        geo_cache,
-        db_pool.clone(),
-        Arc::clone(&api_budget),
-    ));
+        &config.nws.user_agent,
+    )))
+    .with_default_provider(config.providers.default, config.providers.allow_override);
+    if let Some(key) = config.providers.weatherapi_key() {
+        forecast_service =
+            forecast_service.with_provider(Arc::new(WeatherApi::new(http_client.clone(), key)));
+    }
+    if let Some(shared) = shared_cache {
+        forecast_service = forecast_service.with_shared_cache(shared);
+    }
+    if config.forecast_archive.enabled && config.features.history {
+        forecast_service = forecast_service.with_archive(db_pool.clone());
+    }
+    if config.nws.enabled {
+        forecast_service =
+            forecast_service.with_nws(NwsClient::new(http_client.clone(), &config.nws.user_agent));
+    }
+    let forecast_service = Arc::new(forecast_service);
+    let history_service = Arc::new(
+        HistoryService::new(
+            http_client.clone(),
+            Arc::clone(&api_keys),
+            geo_cache,
+            db_pool.clone(),
+            Arc::clone(&api_budget),
+        )
+        .with_locations(Arc::clone(&locations_service))
+        .with_validation(config.validation.clone()),
+    );
+
+    if config.forecast_archive.enabled
+        && config.features.history
+        && config.forecast_archive.retention_days > 0
+    {
+        start_forecast_archive_prune_task(
+            Arc::clone(&history_service),
+            Duration::from_secs(u64::from(config.forecast_archive.retention_days) * 86400),
+        );
+    }
 
     // Run duplicate location cleanup on startup
-    match history_service.cleanup_duplicate_locations().await {
-        Ok(updated) if updated > 0 => {
-            tracing::info!("Cleaned up {} duplicate location records", updated);
-        }
-        Ok(_) => {}
-        Err(e) => {
-            tracing::warn!("Duplicate location cleanup failed: {}", e);
+    if config.features.history {
+        match history_service.cleanup_duplicate_locations().await {
+            Ok(updated) if updated > 0 => {
+                tracing::info!("Cleaned up {} duplicate location records", updated);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Duplicate location cleanup failed: {}", e);
+            }
         }
     }
 
     // Initialize air quality service
     let air_quality_service = Arc::new(AirQualityService::new(
         http_client.clone(),
-        &config.openweathermap_api_key,
+        Arc::clone(&api_keys),
         Arc::clone(&forecast_service),
         Arc::clone(&api_budget),
     ));
 
     // Initialize devices service backed by SQLite
-    let devices_service = Arc::new(DevicesService::new(http_client.clone(), db_pool.clone()));
-    devices_service.cleanup_invalid_tokens().await;
-    tracing::info!("Devices service initialized");
-
-    // Initialize scheduler backed by SQLite
-    let scheduler_service = Arc::new(
-        SchedulerService::new(
-            Arc::clone(&forecast_service),
+    let devices_service = Arc::new(DevicesService::new(
+        http_client.clone(),
+        db_pool.clone(),
+        &config.notifications,
+    ));
+    if config.features.devices {
+        devices_service.cleanup_invalid_tokens().await;
+        if config.notifications.digest.enabled {
+            start_digest_flush_task(
+                Arc::clone(&devices_service),
+                Duration::from_secs(config.notifications.digest.window_secs),
+            );
+            tracing::info!(
+                window_secs = config.notifications.digest.window_secs,
+                "Notification digest batching enabled"
+            );
+        }
+        start_delivery_log_prune_task(
             Arc::clone(&devices_service),
-            db_pool.clone(),
-        )
-        .await?,
-    );
+            Duration::from_secs(u64::from(config.notifications.log_retention_days) * 86400),
+        );
+        if config.notifications.receipts.enabled {
+            start_receipt_check_task(
+                Arc::clone(&devices_service),
+                Duration::from_secs(config.notifications.receipts.check_interval_secs),
+            );
+        }
+        tracing::info!("Devices service initialized");
+
+        // Operator warnings bypass the per-device quiet hours and rate limits
+        let admin_notifier = Arc::new(AdminNotifier::new(
+            http_client.clone(),
+            config.notifications.expo.clone(),
+        ));
+        api_budget::start_budget_warning_task(
+            Arc::clone(&api_budget),
+            Arc::clone(&admin_notifier),
+            config.budget_warning.clone(),
+            config.notifications.conditions.clone(),
+        );
+        upstream::start_upstream_alert_task(
+            Arc::clone(&api_keys),
+            admin_notifier,
+            config.upstream_alert.clone(),
+            config.notifications.conditions.clone(),
+        );
+    } else {
+        tracing::info!("Devices disabled");
+    }
 
-    // Initialize scheduler storage and load persisted jobs
-    scheduler_service.init().await?;
+    // Live updates for WebSocket clients, fed by the scheduler
+    let live = Arc::new(live::LiveUpdates::new());
 
-    // Load jobs from config file (if any, and if not already in storage)
-    if config.scheduler.enabled && !config.scheduler.jobs.is_empty() {
-        let job_config = JobConfig {
-            jobs: config.scheduler.jobs.clone(),
-        };
-        scheduler_service.load_jobs(&job_config).await?;
+    // Initialize scheduler backed by SQLite
+    let mut scheduler_service = SchedulerService::new(
+        Arc::clone(&forecast_service),
+        Arc::clone(&devices_service),
+        db_pool.clone(),
+        config.notifications.conditions.clone(),
+        Arc::clone(&live),
+    )
+    .await?;
+    if config.features.history {
+        scheduler_service = scheduler_service.with_history(db_pool.clone());
     }
+    let scheduler_service = scheduler_service
+        .with_clothing_rules(config.recommendations.clothing.clone())
+        .with_validation(config.validation.clone());
+    let scheduler_service = Arc::new(scheduler_service);
+
+    if config.features.scheduler {
+        // Initialize scheduler storage and load persisted jobs
+        scheduler_service.init().await?;
+
+        // Load jobs from config file (if any, and if not already in storage)
+        if config.scheduler.enabled && !config.scheduler.jobs.is_empty() {
+            let job_config = JobConfig {
+                jobs: config.scheduler.jobs.clone(),
+            };
+            scheduler_service.load_jobs(&job_config).await?;
+        }
 
-    // Start the scheduler
-    scheduler_service.start().await?;
-    tracing::info!(
-        job_count = scheduler_service.get_jobs().await.len(),
-        "Scheduler started"
-    );
+        // Start the scheduler
+        scheduler_service.start().await?;
+        tracing::info!(
+            job_count = scheduler_service.get_jobs().await.len(),
+            "Scheduler started"
+        );
+
+        start_alert_expiry_task(Arc::clone(&scheduler_service));
+        start_device_zone_refresh_task(Arc::clone(&scheduler_service));
+    } else {
+        tracing::info!("Scheduler disabled");
+    }
 
     // Schedule history backfill job if enabled
-    if config.history_backfill.enabled {
+    if config.history_backfill.enabled && config.features.history && config.features.scheduler {
         backfill::schedule_backfill_job(
             Arc::clone(&scheduler_service),
             Arc::clone(&history_service),
@@ -236,7 +417,100 @@ async fn main() -> anyhow::Result<()> {
         .await?;
     }
 
+    if config.history_observations.enabled && config.features.history {
+        history::start_observation_task(
+            Arc::clone(&history_service),
+            history::ObservationSources {
+                devices: config
+                    .features
+                    .devices
+                    .then(|| Arc::clone(&devices_service)),
+                scheduler: config
+                    .features
+                    .scheduler
+                    .then(|| Arc::clone(&scheduler_service)),
+            },
+            config.history_observations.clone(),
+        );
+    }
+
+    if config.history_archive.enabled && config.features.history && config.features.scheduler {
+        history::schedule_history_archive_job(
+            Arc::clone(&scheduler_service),
+            Arc::clone(&history_service),
+            config.history_archive.clone(),
+            config.history_retention_days,
+        )
+        .await?;
+    }
+
+    if config.history_export.enabled && config.features.history && config.features.scheduler {
+        export::schedule_history_export_job(
+            Arc::clone(&scheduler_service),
+            db_pool.clone(),
+            http_client.clone(),
+            config.history_export.clone(),
+            &config.object_store,
+        )
+        .await?;
+    }
+
+    warmup::start_hot_refresh_task(Arc::clone(&forecast_service), &config.cache);
+    if config.cache.warm_on_startup {
+        warmup::start_cache_warming(
+            Arc::clone(&forecast_service),
+            warmup::WarmupSources {
+                devices: config
+                    .features
+                    .devices
+                    .then(|| Arc::clone(&devices_service)),
+                scheduler: config
+                    .features
+                    .scheduler
+                    .then(|| Arc::clone(&scheduler_service)),
+                fallback_cities: config.history_backfill.fallback_cities.clone(),
+                forecasts: config.cache.warm_forecasts,
+            },
+        );
+    }
+
+    let webhooks_service = Arc::new(webhooks::WebhooksService::new(
+        db_pool.clone(),
+        Arc::clone(&scheduler_service),
+        config.units.clone(),
+    ));
+
+    let audit = Arc::new(audit::AuditLog::new(db_pool.clone()));
+
     // Create shared application state
+    let shutdown_scheduler = Arc::clone(&scheduler_service);
+    let shutdown_devices = Arc::clone(&devices_service);
+    let shutdown_pool = db_pool.clone();
+
+    let jwt_keys = config.auth.enabled.then(|| {
+        tracing::info!(
+            users = config.auth.users.len(),
+            "JWT authentication enabled"
+        );
+        Arc::new(auth::JwtKeys::new(
+            &config.auth.jwt_secret,
+            config.auth.token_ttl_secs,
+        ))
+    });
+
+    let client_keys = Arc::new(client_keys::ClientKeys::new(
+        &config.client_keys,
+        config.device_api_key.clone(),
+        api_budget.reset_timezone(),
+    ));
+    if !config.client_keys.keys.is_empty() {
+        tracing::info!(
+            keys = config.client_keys.keys.len(),
+            required = config.client_keys.required,
+            "Client API key quotas enabled"
+        );
+    }
+
     let state = AppState {
         http_client,
         db_pool,
@@ -248,7 +522,16 @@ async fn main() -> anyhow::Result<()> {
         air_quality_service,
         config: Arc::new(config.clone()),
         metrics_handle,
+        ops_stats,
         api_budget,
+        api_keys,
+        jwt_keys,
+        client_keys,
+        live,
+        webhooks_service,
+        locations_service,
+        audit,
+        started_at: chrono::Utc::now().timestamp(),
     };
 
     // Build CORS layer
@@ -272,10 +555,30 @@ async fn main() -> anyhow::Result<()> {
             .allow_headers([
                 http::header::CONTENT_TYPE,
                 http::header::HeaderName::from_static("x-api-key"),
+                http::header::HeaderName::from_static("x-hook-secret"),
+                http::header::HeaderName::from_static("idempotency-key"),
                 http::header::AUTHORIZATION,
             ])
     };
 
+    // One shutdown signal shared by the HTTP and gRPC servers
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(());
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(());
+    });
+
+    let grpc_server = if config.grpc.enabled {
+        let addr: SocketAddr = format!("{}:{}", config.host, config.grpc.port).parse()?;
+        Some(tokio::spawn(grpc::serve(
+            state.clone(),
+            addr,
+            wait_for_shutdown(shutdown_rx.clone()),
+        )))
+    } else {
+        None
+    };
+
     // Build router using the routes module
     let app = routes::build_router(state.clone())
         .layer(cors)
@@ -299,9 +602,27 @@ async fn main() -> anyhow::Result<()> {
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
-    .with_graceful_shutdown(shutdown_signal())
+    .with_graceful_shutdown(wait_for_shutdown(shutdown_rx))
     .await?;
 
+    if let Some(grpc_server) = grpc_server {
+        if let Ok(Err(e)) = grpc_server.await {
+            tracing::error!("gRPC server error: {}", e);
+        }
+    }
+
+    // Drain scheduled work before the runtime drops it mid-run
+    if config.features.scheduler {
+        shutdown_scheduler
+            .shutdown(Duration::from_secs(config.shutdown_timeout_secs))
+            .await;
+    }
+    let flushed = shutdown_devices.flush_digests().await;
+    if flushed > 0 {
+        tracing::info!(sent = flushed, "Flushed pending notification digests");
+    }
+    shutdown_pool.close().await;
+
     tracing::info!("Server shutdown complete");
 
     Ok(())
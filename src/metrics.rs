@@ -1,21 +1,49 @@
 use axum::{extract::Request, middleware::Next, response::Response};
 use metrics::{counter, histogram};
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::sync::Arc;
 use std::time::Instant;
 
+use crate::ops_stats::{OpsStats, RollingRecorder};
+
 // Metric name constants
 pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
 pub const HTTP_REQUEST_DURATION: &str = "http_request_duration_seconds";
 pub const OWM_API_CALLS: &str = "weathrs_owm_api_calls_total";
+pub const OWM_RETRIES: &str = "weathrs_owm_retries_total";
+pub const UPSTREAM_CIRCUIT_OPENED: &str = "weathrs_upstream_circuit_opened_total";
+pub const NWS_API_CALLS: &str = "weathrs_nws_api_calls_total";
+pub const API_BUDGET_REJECTIONS: &str = "weathrs_api_budget_rejections_total";
+pub const CLIENT_KEY_REQUESTS: &str = "weathrs_client_key_requests_total";
+pub const CLIENT_KEY_REJECTIONS: &str = "weathrs_client_key_rejections_total";
 pub const CACHE_HITS: &str = "weathrs_cache_hits_total";
 pub const CACHE_MISSES: &str = "weathrs_cache_misses_total";
+pub const TTL_CACHE_HITS: &str = "weathrs_ttl_cache_hits_total";
+pub const TTL_CACHE_MISSES: &str = "weathrs_ttl_cache_misses_total";
+pub const TTL_CACHE_EVICTIONS: &str = "weathrs_ttl_cache_evictions_total";
+pub const TTL_CACHE_ENTRIES: &str = "weathrs_ttl_cache_entries";
+pub const TTL_CACHE_BYTES: &str = "weathrs_ttl_cache_approx_bytes";
+pub const SHARED_CACHE_HITS: &str = "weathrs_shared_cache_hits_total";
+pub const SHARED_CACHE_MISSES: &str = "weathrs_shared_cache_misses_total";
+pub const SHARED_CACHE_ERRORS: &str = "weathrs_shared_cache_errors_total";
+pub const FORECAST_CACHE_REFRESHES: &str = "weathrs_forecast_cache_refreshes_total";
 pub const BACKFILL_DAYS_FETCHED: &str = "weathrs_backfill_days_fetched_total";
-
-/// Initialize the Prometheus metrics recorder and return a handle for the scrape endpoint.
-pub fn init_metrics() -> PrometheusHandle {
-    PrometheusBuilder::new()
-        .install_recorder()
-        .expect("failed to install Prometheus recorder")
+pub const HISTORY_ROWS_EXPORTED: &str = "weathrs_history_rows_exported_total";
+pub const WEATHER_ANOMALIES: &str = "weathrs_weather_anomalies_total";
+pub const LIVE_CONNECTIONS: &str = "weathrs_live_connections";
+pub const PUSH_TOKENS_PRUNED: &str = "weathrs_push_tokens_pruned_total";
+pub const NOTIFICATIONS_SENT: &str = "weathrs_notifications_sent_total";
+pub const SCHEDULER_JOB_RUNS: &str = "weathrs_scheduler_job_runs_total";
+
+/// Initialize the Prometheus metrics recorder and return a handle for the scrape endpoint,
+/// plus the rolling counters it feeds for GET /admin/stats.
+pub fn init_metrics() -> (PrometheusHandle, Arc<OpsStats>) {
+    let recorder = PrometheusBuilder::new().build_recorder();
+    let handle = recorder.handle();
+    let ops_stats = Arc::new(OpsStats::default());
+    metrics::set_global_recorder(RollingRecorder::new(recorder, Arc::clone(&ops_stats)))
+        .expect("failed to install Prometheus recorder");
+    (handle, ops_stats)
 }
 
 /// Normalize a request path to avoid high-cardinality labels.
@@ -0,0 +1,121 @@
+use axum::{
+    body::Body,
+    extract::Extension,
+    http::{header, HeaderValue, Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::cell::Cell;
+
+tokio::task_local! {
+    /// When the oldest cached data in the response being built was fetched
+    static FETCHED_AT: Cell<Option<DateTime<Utc>>>;
+}
+
+/// Note that the response being built is served from a cache entry `age`
+/// old, so its caching headers count from when the entry was fetched rather
+/// than from now. Does nothing outside a route with caching headers.
+pub fn record_cache_age(age: Option<std::time::Duration>) {
+    let Some(age) = age.and_then(|age| Duration::from_std(age).ok()) else {
+        return;
+    };
+    let fetched_at = Utc::now() - age;
+    let _ = FETCHED_AT.try_with(|oldest| {
+        oldest.set(Some(oldest.get().map_or(fetched_at, |t| t.min(fetched_at))));
+    });
+}
+
+/// How long responses in a route group may be cached downstream
+#[derive(Clone, Copy)]
+pub struct CachePolicy {
+    pub max_age_secs: u64,
+    /// Whether shared caches such as reverse proxies may store responses.
+    /// Off when client keys are required, so a proxy can't hand data to
+    /// callers without a key.
+    pub shared: bool,
+}
+
+/// Middleware that adds `Cache-Control`, `Expires`, and `Last-Modified` to
+/// successful GET responses, matching how long the data is cached here
+///
+/// A response served from a cache entry gets the entry's fetch time as
+/// `Last-Modified` and only the rest of its TTL as `max-age`. Responses that
+/// already carry `Cache-Control` are left alone, as are `Expires` and
+/// `Last-Modified` headers a handler set.
+pub async fn cache_headers(
+    Extension(policy): Extension<CachePolicy>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let is_get = request.method() == Method::GET;
+    let (mut response, fetched_at) = FETCHED_AT
+        .scope(Cell::new(None), async {
+            let response = next.run(request).await;
+            (response, FETCHED_AT.with(Cell::get))
+        })
+        .await;
+    if !is_get
+        || !response.status().is_success()
+        || response.headers().contains_key(header::CACHE_CONTROL)
+    {
+        return response;
+    }
+
+    let now = Utc::now();
+    let fetched_at = fetched_at.map_or(now, |at| at.min(now));
+    let max_age = remaining_max_age(policy.max_age_secs, fetched_at, now);
+    let scope = if policy.shared { "public" } else { "private" };
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_str(&format!("{}, max-age={}", scope, max_age))
+            .expect("valid header value"),
+    );
+    headers
+        .entry(header::EXPIRES)
+        .or_insert_with(|| http_date(now + Duration::seconds(max_age as i64)));
+    headers
+        .entry(header::LAST_MODIFIED)
+        .or_insert_with(|| http_date(fetched_at));
+    response
+}
+
+/// What's left of `max_age_secs` for data fetched at `fetched_at`
+fn remaining_max_age(max_age_secs: u64, fetched_at: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    let age = (now - fetched_at).num_seconds().max(0) as u64;
+    max_age_secs.saturating_sub(age)
+}
+
+/// Format a timestamp as an HTTP-date (RFC 9110 IMF-fixdate)
+fn http_date(at: DateTime<Utc>) -> HeaderValue {
+    HeaderValue::from_str(&at.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+        .expect("valid header value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_max_age_counts_from_oldest_cached_entry() {
+        // Outside a cached route there is nothing to record into
+        record_cache_age(Some(std::time::Duration::from_secs(60)));
+
+        let fetched_at = FETCHED_AT
+            .scope(Cell::new(None), async {
+                record_cache_age(Some(std::time::Duration::from_secs(60)));
+                record_cache_age(Some(std::time::Duration::from_secs(300)));
+                record_cache_age(None);
+                FETCHED_AT.with(Cell::get)
+            })
+            .await
+            .unwrap();
+        let max_age = remaining_max_age(600, fetched_at, Utc::now());
+        assert!((299..=300).contains(&max_age), "max-age {}", max_age);
+
+        let now = Utc::now();
+        assert_eq!(remaining_max_age(600, now, now), 600);
+        assert_eq!(remaining_max_age(600, now - Duration::seconds(900), now), 0);
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Extension, MatchedPath},
+    http::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::client_keys::ClientKeys;
+
+/// Middleware that counts requests against the caller's client key quota
+///
+/// Requests with the device API key or, unless keys are required, no key at
+/// all are let through unmetered. An unrecognized key is always rejected.
+pub async fn client_key_quota(
+    Extension(keys): Extension<Arc<ClientKeys>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get("X-API-Key")
+        .and_then(|v| v.to_str().ok());
+
+    match keys.admit(provided, chrono::Utc::now()) {
+        Ok(()) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Middleware that records each request's route and response status against
+/// the key it presented, for GET /admin/usage
+pub async fn track_key_usage(
+    Extension(keys): Extension<Arc<ClientKeys>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get("X-API-Key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("(unmatched)", |p| p.as_str())
+        .to_string();
+
+    let response = next.run(request).await;
+    keys.record_response(provided.as_deref(), &route, response.status());
+    response
+}
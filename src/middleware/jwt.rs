@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::Extension,
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::auth::{AuthError, JwtKeys, Principal};
+
+/// Wrapper type for the JWT keys (None when auth is disabled)
+#[derive(Clone)]
+pub struct JwtAuth(pub Option<Arc<JwtKeys>>);
+
+/// Verify the request's bearer token, if it has one
+fn bearer_principal(
+    keys: &JwtKeys,
+    request: &Request<Body>,
+) -> Option<Result<Principal, AuthError>> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))?;
+
+    Some(
+        keys.verify(token.trim(), chrono::Utc::now().timestamp())
+            .inspect_err(|e| tracing::warn!(error = %e, "Rejected bearer token")),
+    )
+}
+
+/// Middleware that requires a valid `Authorization: Bearer` token
+///
+/// If auth is disabled, all requests are allowed. Otherwise the caller's
+/// `Principal` is attached to the request for handlers to read.
+pub async fn require_jwt(
+    Extension(JwtAuth(keys)): Extension<JwtAuth>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(keys) = keys else {
+        return next.run(request).await;
+    };
+
+    match bearer_principal(&keys, &request) {
+        Some(Ok(principal)) => {
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Some(Err(e)) => e.into_response(),
+        None => AuthError::MissingToken.into_response(),
+    }
+}
+
+/// Like `require_jwt`, but requests without a token are let through
+/// anonymously. Used on device self-service routes so the app can register
+/// a device to the signed-in user.
+pub async fn optional_jwt(
+    Extension(JwtAuth(keys)): Extension<JwtAuth>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    if let Some(keys) = keys {
+        match bearer_principal(&keys, &request) {
+            Some(Ok(principal)) => {
+                request.extensions_mut().insert(principal);
+            }
+            Some(Err(e)) => return e.into_response(),
+            None => {}
+        }
+    }
+    next.run(request).await
+}
+
+/// Middleware that rejects non-admin principals. Must run inside
+/// `require_jwt`; with auth disabled there is no principal and all requests
+/// are allowed.
+pub async fn require_admin(request: Request<Body>, next: Next) -> Response {
+    match request.extensions().get::<Principal>() {
+        Some(principal) if !principal.is_admin() => AuthError::Forbidden.into_response(),
+        _ => next.run(request).await,
+    }
+}
@@ -1,3 +1,9 @@
 mod auth;
+mod cache_headers;
+mod client_keys;
+mod jwt;
 
 pub use auth::{require_api_key, DeviceApiKey};
+pub use cache_headers::{cache_headers, record_cache_age, CachePolicy};
+pub use client_keys::{client_key_quota, track_key_usage};
+pub use jwt::{optional_jwt, require_admin, require_jwt, JwtAuth};
@@ -0,0 +1,40 @@
+use reqwest::Client;
+
+use super::{ExpoClient, NotificationMessage};
+use crate::config::ExpoChannelConfig;
+
+/// Operator notices, such as the API budget and upstream failure warnings,
+/// pushed straight to admin device tokens.
+///
+/// These skip the device pipeline (quiet hours, rate limits, digests and
+/// event subscriptions), which exists to throttle weather messages for
+/// users, not to hold back warnings about the service itself.
+pub struct AdminNotifier {
+    expo_client: ExpoClient,
+}
+
+impl AdminNotifier {
+    pub fn new(client: Client, config: ExpoChannelConfig) -> Self {
+        Self {
+            expo_client: ExpoClient::new(client, config),
+        }
+    }
+
+    /// Push a notice to each admin token. Returns how many Expo accepted.
+    pub async fn notify(&self, tokens: &[String], message: &NotificationMessage) -> usize {
+        if tokens.is_empty() {
+            return 0;
+        }
+        let results = self.expo_client.send_to_tokens(tokens, message).await;
+        let mut sent = 0;
+        for result in results {
+            match result {
+                Ok(_) => sent += 1,
+                Err(e) => {
+                    tracing::warn!(title = %message.title, error = %e, "Failed to push admin notice")
+                }
+            }
+        }
+        sent
+    }
+}
@@ -0,0 +1,130 @@
+use serde::Deserialize;
+
+/// Broad weather condition groups used to pick notification tags and emoji
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionGroup {
+    Thunderstorm,
+    Drizzle,
+    Rain,
+    Snow,
+    Atmosphere,
+    Clear,
+    Clouds,
+    /// Active weather alert (takes precedence over the current condition)
+    Alert,
+}
+
+impl ConditionGroup {
+    /// Group for a forecast entry: by its OWM condition code when the
+    /// provider gave one, otherwise by its icon
+    pub fn from_condition(code: Option<u32>, icon: &str) -> Self {
+        code.map_or_else(|| Self::from_icon(icon), Self::from_code)
+    }
+
+    /// Map an OWM condition code (e.g. 500 = light rain) to its group
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            200..=299 => Self::Thunderstorm,
+            300..=399 => Self::Drizzle,
+            500..=599 => Self::Rain,
+            600..=699 => Self::Snow,
+            700..=799 => Self::Atmosphere,
+            800 => Self::Clear,
+            _ => Self::Clouds,
+        }
+    }
+
+    /// Map an OWM icon code (e.g. "10d") to its group. Icons are coarser than
+    /// codes: "09" covers both drizzle and shower rain.
+    pub fn from_icon(icon: &str) -> Self {
+        match icon.get(..2) {
+            Some("01") => Self::Clear,
+            Some("09") => Self::Drizzle,
+            Some("10") => Self::Rain,
+            Some("11") => Self::Thunderstorm,
+            Some("13") => Self::Snow,
+            Some("50") => Self::Atmosphere,
+            _ => Self::Clouds,
+        }
+    }
+
+    /// Config key for this group
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Thunderstorm => "thunderstorm",
+            Self::Drizzle => "drizzle",
+            Self::Rain => "rain",
+            Self::Snow => "snow",
+            Self::Atmosphere => "atmosphere",
+            Self::Clear => "clear",
+            Self::Clouds => "clouds",
+            Self::Alert => "alert",
+        }
+    }
+
+    /// Built-in tag and emoji for this group
+    pub fn default_style(&self) -> ConditionStyle {
+        let (tag, emoji) = match self {
+            Self::Thunderstorm => ("zap", "\u{26C8}\u{FE0F}"),
+            Self::Drizzle => ("umbrella", "\u{1F326}\u{FE0F}"),
+            Self::Rain => ("umbrella", "\u{1F327}\u{FE0F}"),
+            Self::Snow => ("snowflake", "\u{2744}\u{FE0F}"),
+            Self::Atmosphere => ("fog", "\u{1F32B}\u{FE0F}"),
+            Self::Clear => ("sunny", "\u{2600}\u{FE0F}"),
+            Self::Clouds => ("cloud", "\u{2601}\u{FE0F}"),
+            Self::Alert => ("warning", "\u{26A0}\u{FE0F}"),
+        };
+        ConditionStyle {
+            tag: Some(tag.to_string()),
+            emoji: Some(emoji.to_string()),
+        }
+    }
+}
+
+/// Tag and emoji attached to notifications for a condition group.
+/// Set either to an empty string in config to omit it.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ConditionStyle {
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub emoji: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(ConditionGroup::from_code(211), ConditionGroup::Thunderstorm);
+        assert_eq!(ConditionGroup::from_code(301), ConditionGroup::Drizzle);
+        assert_eq!(ConditionGroup::from_code(502), ConditionGroup::Rain);
+        assert_eq!(ConditionGroup::from_code(601), ConditionGroup::Snow);
+        assert_eq!(ConditionGroup::from_code(741), ConditionGroup::Atmosphere);
+        assert_eq!(ConditionGroup::from_code(800), ConditionGroup::Clear);
+        assert_eq!(ConditionGroup::from_code(803), ConditionGroup::Clouds);
+    }
+
+    #[test]
+    fn test_from_icon() {
+        assert_eq!(ConditionGroup::from_icon("01d"), ConditionGroup::Clear);
+        assert_eq!(ConditionGroup::from_icon("04n"), ConditionGroup::Clouds);
+        assert_eq!(ConditionGroup::from_icon("10d"), ConditionGroup::Rain);
+        assert_eq!(ConditionGroup::from_icon("13n"), ConditionGroup::Snow);
+        assert_eq!(ConditionGroup::from_icon(""), ConditionGroup::Clouds);
+    }
+
+    #[test]
+    fn test_from_condition_prefers_code() {
+        // Shower rain shares the drizzle icon
+        assert_eq!(
+            ConditionGroup::from_condition(Some(521), "09d"),
+            ConditionGroup::Rain
+        );
+        assert_eq!(
+            ConditionGroup::from_condition(None, "09d"),
+            ConditionGroup::Drizzle
+        );
+    }
+}
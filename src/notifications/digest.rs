@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::{EventType, NotificationMessage, Priority};
+
+/// Buffers non-urgent notifications per device token so they can be
+/// delivered as a single combined push at the end of the digest window
+#[derive(Default)]
+pub struct DigestQueue {
+    pending: Mutex<HashMap<String, Vec<NotificationMessage>>>,
+}
+
+impl DigestQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a message should be held for the digest instead of sent immediately
+    pub fn accepts(message: &NotificationMessage) -> bool {
+        message.priority < Priority::High
+    }
+
+    /// Queue a message for a device token
+    pub fn push(&self, token: &str, message: &NotificationMessage) {
+        self.pending
+            .lock()
+            .unwrap()
+            .entry(token.to_string())
+            .or_default()
+            .push(message.clone());
+    }
+
+    /// Take all queued messages, leaving the queue empty
+    pub fn drain(&self) -> Vec<(String, Vec<NotificationMessage>)> {
+        self.pending.lock().unwrap().drain().collect()
+    }
+}
+
+/// Combine queued messages into one digest notification.
+/// A single queued message is returned unchanged.
+pub fn build_digest(mut messages: Vec<NotificationMessage>) -> Option<NotificationMessage> {
+    if messages.len() <= 1 {
+        return messages.pop();
+    }
+
+    let priority = messages
+        .iter()
+        .map(|m| m.priority)
+        .max()
+        .unwrap_or_default();
+
+    let mut body = String::new();
+    for message in &messages {
+        body.push_str(&format!("\u{2022} {}", message.title));
+        if let Some(ref subtitle) = message.subtitle {
+            body.push_str(&format!(": {}", subtitle));
+        }
+        body.push('\n');
+    }
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in messages.iter().flat_map(|m| m.tags.iter()) {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+
+    // Only keep the city for navigation if every message is about the same one
+    let city = messages[0].city.clone();
+    let city = if messages.iter().all(|m| m.city == city) {
+        city
+    } else {
+        None
+    };
+
+    let mut events: Vec<EventType> = messages.iter().flat_map(|m| m.events.clone()).collect();
+    events.sort();
+    events.dedup();
+
+    Some(NotificationMessage {
+        title: format!("Weather digest ({} updates)", messages.len()),
+        subtitle: None,
+        body: body.trim_end().to_string(),
+        priority,
+        tags,
+        city,
+        events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(title: &str, city: &str, priority: Priority) -> NotificationMessage {
+        NotificationMessage {
+            title: title.to_string(),
+            body: "body".to_string(),
+            subtitle: Some("20\u{00B0} \u{2022} clear sky".to_string()),
+            priority,
+            tags: vec!["weather".to_string()],
+            city: Some(city.to_string()),
+            events: vec![EventType::DailyBriefing],
+        }
+    }
+
+    #[test]
+    fn test_accepts_only_non_urgent() {
+        assert!(DigestQueue::accepts(&message(
+            "a",
+            "Chicago",
+            Priority::Default
+        )));
+        assert!(!DigestQueue::accepts(&message(
+            "a",
+            "Chicago",
+            Priority::High
+        )));
+        assert!(!DigestQueue::accepts(&message(
+            "a",
+            "Chicago",
+            Priority::Urgent
+        )));
+    }
+
+    #[test]
+    fn test_queue_push_and_drain() {
+        let queue = DigestQueue::new();
+        queue.push(
+            "token1",
+            &message("Chicago, US", "Chicago", Priority::Default),
+        );
+        queue.push("token1", &message("London, GB", "London", Priority::Low));
+        queue.push("token2", &message("Paris, FR", "Paris", Priority::Default));
+        let drained = queue.drain();
+        assert_eq!(drained.len(), 2);
+        assert!(queue.drain().is_empty());
+    }
+
+    #[test]
+    fn test_build_digest_single_message_unchanged() {
+        let digest = build_digest(vec![message("Chicago, US", "Chicago", Priority::Low)]).unwrap();
+        assert_eq!(digest.title, "Chicago, US");
+    }
+
+    #[test]
+    fn test_build_digest_combines_messages() {
+        let digest = build_digest(vec![
+            message("Chicago, US", "Chicago", Priority::Low),
+            message("London, GB", "London", Priority::Default),
+        ])
+        .unwrap();
+
+        assert_eq!(digest.title, "Weather digest (2 updates)");
+        assert!(digest.body.contains("Chicago, US"));
+        assert!(digest.body.contains("London, GB"));
+        assert!(matches!(digest.priority, Priority::Default));
+        assert_eq!(digest.tags, vec!["weather".to_string()]);
+        assert!(digest.city.is_none());
+    }
+}
@@ -1,13 +1,21 @@
+use std::collections::HashMap;
+
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use super::{NotificationError, NotificationMessage, Priority};
+use super::{NotificationError, NotificationMessage, Priority, DEVICE_NOT_REGISTERED};
+use crate::config::ExpoChannelConfig;
 
 const EXPO_PUSH_URL: &str = "https://exp.host/--/api/v2/push/send";
+const EXPO_RECEIPTS_URL: &str = "https://exp.host/--/api/v2/push/getReceipts";
+
+/// Expo accepts at most 1000 receipt IDs per request
+const RECEIPT_CHUNK_SIZE: usize = 1000;
 
 /// Expo push notification client
 pub struct ExpoClient {
     client: Client,
+    config: ExpoChannelConfig,
 }
 
 /// Data payload for push notifications
@@ -89,22 +97,62 @@ pub struct ExpoPushTicket {
     #[serde(default)]
     pub message: Option<String>,
     #[serde(default)]
-    #[allow(dead_code)]
     pub details: Option<serde_json::Value>,
 }
 
+impl ExpoPushTicket {
+    /// Convert a failed ticket into an error, surfacing unregistered devices
+    fn into_error(self) -> NotificationError {
+        let error_msg = self.message.unwrap_or_else(|| "Unknown error".to_string());
+        if details_error(&self.details) == Some(DEVICE_NOT_REGISTERED) {
+            NotificationError::DeviceNotRegistered(error_msg)
+        } else {
+            NotificationError::ServiceError(error_msg)
+        }
+    }
+}
+
+/// Delivery receipt for a previously accepted push ticket
+#[derive(Debug, Deserialize)]
+pub struct ExpoPushReceipt {
+    pub status: String,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ExpoPushReceipt {
+    /// Whether Expo reported the target device as no longer registered
+    pub fn is_device_not_registered(&self) -> bool {
+        self.status == "error" && details_error(&self.details) == Some(DEVICE_NOT_REGISTERED)
+    }
+}
+
+/// Response from the Expo receipts API, keyed by ticket ID
+#[derive(Debug, Deserialize)]
+struct ExpoReceiptsResponse {
+    data: HashMap<String, ExpoPushReceipt>,
+}
+
+/// Extract the `details.error` code from a ticket or receipt
+fn details_error(details: &Option<serde_json::Value>) -> Option<&str> {
+    details.as_ref()?.get("error")?.as_str()
+}
+
 impl ExpoClient {
-    pub fn new(client: Client) -> Self {
-        Self { client }
+    pub fn new(client: Client, config: ExpoChannelConfig) -> Self {
+        Self { client, config }
     }
 
-    /// Convert our priority to Expo priority
-    fn convert_priority(priority: Priority) -> String {
-        match priority {
-            Priority::Min | Priority::Low => "normal".to_string(),
-            Priority::Default => "default".to_string(),
-            Priority::High | Priority::Urgent => "high".to_string(),
-        }
+    /// Convert our priority to Expo priority using the configured mapping,
+    /// raising it to the configured floor first
+    fn convert_priority(&self, priority: Priority) -> String {
+        let priority = match self.config.min_priority {
+            Some(floor) => priority.max(floor),
+            None => priority,
+        };
+        self.config.priority_map.get(priority).to_string()
     }
 
     /// Build the data payload for a notification
@@ -134,7 +182,7 @@ impl ExpoClient {
             subtitle: message.subtitle.clone(),
             body: message.body.clone(),
             data: Self::build_data(message),
-            priority: Some(Self::convert_priority(message.priority)),
+            priority: Some(self.convert_priority(message.priority)),
             sound: Some("default".to_string()),
             badge: None,
             channel_id: Some("weather".to_string()),
@@ -179,13 +227,12 @@ impl ExpoClient {
 
         if ticket.status == "ok" {
             tracing::info!(ticket_id = ?ticket.id, "Successfully sent Expo push notification");
+            metrics::counter!(crate::metrics::NOTIFICATIONS_SENT).increment(1);
             Ok(ticket)
         } else {
-            let error_msg = ticket
-                .message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            tracing::error!(status = %ticket.status, message = %error_msg, "Expo push notification failed");
-            Err(NotificationError::ServiceError(error_msg))
+            let error = ticket.into_error();
+            tracing::error!(error = %error, "Expo push notification failed");
+            Err(error)
         }
     }
 
@@ -214,7 +261,7 @@ impl ExpoClient {
                     subtitle: message.subtitle.clone(),
                     body: message.body.clone(),
                     data: data.clone(),
-                    priority: Some(Self::convert_priority(message.priority)),
+                    priority: Some(self.convert_priority(message.priority)),
                     sound: Some("default".to_string()),
                     badge: None,
                     channel_id: Some("weather".to_string()),
@@ -249,16 +296,9 @@ impl ExpoClient {
                                         tracing::debug!(ticket_id = ?ticket.id, "Push ticket OK");
                                         results.push(Ok(ticket));
                                     } else {
-                                        let error_msg = ticket
-                                            .message
-                                            .unwrap_or_else(|| "Unknown error".to_string());
-                                        tracing::error!(
-                                            status = %ticket.status,
-                                            error = %error_msg,
-                                            "Push ticket failed"
-                                        );
-                                        results
-                                            .push(Err(NotificationError::ServiceError(error_msg)));
+                                        let error = ticket.into_error();
+                                        tracing::error!(error = %error, "Push ticket failed");
+                                        results.push(Err(error));
                                     }
                                 }
                             }
@@ -298,6 +338,7 @@ impl ExpoClient {
         }
 
         let success_count = results.iter().filter(|r| r.is_ok()).count();
+        metrics::counter!(crate::metrics::NOTIFICATIONS_SENT).increment(success_count as u64);
         let error_count = results.iter().filter(|r| r.is_err()).count();
         tracing::info!(
             total = tokens.len(),
@@ -308,6 +349,47 @@ impl ExpoClient {
 
         results
     }
+
+    /// Fetch delivery receipts for previously accepted push tickets.
+    /// Tickets without a receipt yet are simply absent from the result.
+    pub async fn get_receipts(
+        &self,
+        ticket_ids: &[String],
+    ) -> Result<HashMap<String, ExpoPushReceipt>, NotificationError> {
+        let mut receipts = HashMap::with_capacity(ticket_ids.len());
+
+        for chunk in ticket_ids.chunks(RECEIPT_CHUNK_SIZE) {
+            let response = self
+                .client
+                .post(EXPO_RECEIPTS_URL)
+                .header("Accept", "application/json")
+                .header("Accept-Encoding", "gzip, deflate")
+                .header("Content-Type", "application/json")
+                .json(&serde_json::json!({ "ids": chunk }))
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(NotificationError::ServiceError(format!(
+                    "Expo receipts API returned {}: {}",
+                    status, body
+                )));
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            let parsed: ExpoReceiptsResponse = serde_json::from_str(&body).map_err(|e| {
+                NotificationError::ServiceError(format!(
+                    "Failed to parse Expo receipts response: {}",
+                    e
+                ))
+            })?;
+            receipts.extend(parsed.data);
+        }
+
+        Ok(receipts)
+    }
 }
 
 #[cfg(test)]
@@ -316,10 +398,59 @@ mod tests {
 
     #[test]
     fn test_priority_conversion() {
-        assert_eq!(ExpoClient::convert_priority(Priority::Min), "normal");
-        assert_eq!(ExpoClient::convert_priority(Priority::Low), "normal");
-        assert_eq!(ExpoClient::convert_priority(Priority::Default), "default");
-        assert_eq!(ExpoClient::convert_priority(Priority::High), "high");
-        assert_eq!(ExpoClient::convert_priority(Priority::Urgent), "high");
+        let client = ExpoClient::new(Client::new(), ExpoChannelConfig::default());
+        assert_eq!(client.convert_priority(Priority::Min), "normal");
+        assert_eq!(client.convert_priority(Priority::Low), "normal");
+        assert_eq!(client.convert_priority(Priority::Default), "default");
+        assert_eq!(client.convert_priority(Priority::High), "high");
+        assert_eq!(client.convert_priority(Priority::Urgent), "high");
+    }
+
+    #[test]
+    fn test_ticket_device_not_registered() {
+        let ticket: ExpoPushTicket = serde_json::from_str(
+            r#"{"status":"error","message":"not a valid token","details":{"error":"DeviceNotRegistered"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            ticket.into_error(),
+            NotificationError::DeviceNotRegistered(_)
+        ));
+
+        let ticket: ExpoPushTicket = serde_json::from_str(
+            r#"{"status":"error","message":"too big","details":{"error":"MessageTooBig"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            ticket.into_error(),
+            NotificationError::ServiceError(_)
+        ));
+    }
+
+    #[test]
+    fn test_receipts_response_parsing() {
+        let parsed: ExpoReceiptsResponse = serde_json::from_str(
+            r#"{"data":{
+                "a":{"status":"ok"},
+                "b":{"status":"error","message":"gone","details":{"error":"DeviceNotRegistered"}}
+            }}"#,
+        )
+        .unwrap();
+        assert!(!parsed.data["a"].is_device_not_registered());
+        assert!(parsed.data["b"].is_device_not_registered());
+    }
+
+    #[test]
+    fn test_priority_conversion_custom_map_and_floor() {
+        let mut config = ExpoChannelConfig::default();
+        config.priority_map.default = "high".to_string();
+        config.min_priority = Some(Priority::Default);
+        let client = ExpoClient::new(Client::new(), config);
+
+        // Min and Low are raised to the Default floor, which is mapped to "high"
+        assert_eq!(client.convert_priority(Priority::Min), "high");
+        assert_eq!(client.convert_priority(Priority::Low), "high");
+        assert_eq!(client.convert_priority(Priority::Default), "high");
+        assert_eq!(client.convert_priority(Priority::Urgent), "high");
     }
 }
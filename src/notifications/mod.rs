@@ -1,9 +1,20 @@
+mod admin;
+mod conditions;
+mod digest;
 mod expo;
+mod receipts;
+mod templates;
 
+pub use admin::AdminNotifier;
+pub use conditions::{ConditionGroup, ConditionStyle};
+pub use digest::{build_digest, DigestQueue};
 pub use expo::ExpoClient;
+pub use receipts::{ReadyTicket, ReceiptTracker, DEVICE_NOT_REGISTERED};
+pub use templates::{normalize_language, template_strings, TemplateStrings};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Error, Debug)]
 pub enum NotificationError {
@@ -12,9 +23,12 @@ pub enum NotificationError {
 
     #[error("Notification service returned error: {0}")]
     ServiceError(String),
+
+    #[error("Device is no longer registered: {0}")]
+    DeviceNotRegistered(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NotificationMessage {
     pub title: String,
     pub body: String,
@@ -26,9 +40,36 @@ pub struct NotificationMessage {
     pub tags: Vec<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub city: Option<String>,
+    /// Event classes this message belongs to. Devices only receive it if they
+    /// subscribe to at least one; empty means everyone (tests, broadcasts).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventType>,
+}
+
+/// Class of notification a device can subscribe to
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    DailyBriefing,
+    SevereAlerts,
+    PrecipitationNowcast,
+    AirQuality,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 4] = [
+        EventType::DailyBriefing,
+        EventType::SevereAlerts,
+        EventType::PrecipitationNowcast,
+        EventType::AirQuality,
+    ];
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord, ToSchema,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum Priority {
     Min,
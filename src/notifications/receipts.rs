@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Expo error code for tokens whose app was uninstalled or whose
+/// registration expired
+pub const DEVICE_NOT_REGISTERED: &str = "DeviceNotRegistered";
+
+/// Expo keeps push receipts for a day; a ticket still unchecked after that
+/// can never be, so it is dropped instead of requeued forever
+const RECEIPT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A ticket whose receipt is due to be fetched
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadyTicket {
+    pub id: String,
+    /// Device token the ticket was sent to
+    pub token: String,
+    sent_at: Instant,
+}
+
+/// Tracks push ticket IDs awaiting receipt verification, along with the
+/// device token each ticket was sent to
+#[derive(Default)]
+pub struct ReceiptTracker {
+    /// Ticket id -> (token, when it was sent, when it is next due)
+    pending: Mutex<HashMap<String, (String, Instant, Instant)>>,
+}
+
+impl ReceiptTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember a ticket so its receipt can be checked later
+    pub fn track(&self, ticket_id: &str, token: &str) {
+        let now = Instant::now();
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(ticket_id.to_string(), (token.to_string(), now, now));
+    }
+
+    /// Take all tickets at least `min_age` old. Younger tickets stay queued
+    /// since Expo may not have a receipt for them yet.
+    pub fn take_ready(&self, min_age: Duration) -> Vec<ReadyTicket> {
+        let mut pending = self.pending.lock().unwrap();
+        let ready: Vec<String> = pending
+            .iter()
+            .filter(|(_, (_, _, queued_at))| queued_at.elapsed() >= min_age)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        ready
+            .into_iter()
+            .filter_map(|id| {
+                pending
+                    .remove(&id)
+                    .map(|(token, sent_at, _)| ReadyTicket { id, token, sent_at })
+            })
+            .collect()
+    }
+
+    /// Put back tickets whose receipts couldn't be fetched. They become ready
+    /// again after another `min_age`; tickets older than Expo keeps receipts
+    /// for are dropped. Returns how many were dropped.
+    pub fn requeue(&self, tickets: Vec<ReadyTicket>) -> usize {
+        let mut pending = self.pending.lock().unwrap();
+        let mut dropped = 0;
+        for ticket in tickets {
+            if ticket.sent_at.elapsed() >= RECEIPT_TTL {
+                dropped += 1;
+                continue;
+            }
+            pending.insert(ticket.id, (ticket.token, ticket.sent_at, Instant::now()));
+        }
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_ready_respects_min_age() {
+        let tracker = ReceiptTracker::new();
+        tracker.track("ticket-1", "ExponentPushToken[a]");
+        tracker.track("ticket-2", "ExponentPushToken[b]");
+
+        // Nothing is old enough yet
+        assert!(tracker.take_ready(Duration::from_secs(3600)).is_empty());
+
+        let mut ready = tracker.take_ready(Duration::ZERO);
+        ready.sort_by(|a, b| a.id.cmp(&b.id));
+        let pairs: Vec<(&str, &str)> = ready
+            .iter()
+            .map(|t| (t.id.as_str(), t.token.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("ticket-1", "ExponentPushToken[a]"),
+                ("ticket-2", "ExponentPushToken[b]"),
+            ]
+        );
+
+        // Taken tickets are no longer pending
+        assert!(tracker.take_ready(Duration::ZERO).is_empty());
+
+        // Until put back after a failed fetch
+        assert_eq!(tracker.requeue(ready), 0);
+        assert!(tracker.take_ready(Duration::from_secs(3600)).is_empty());
+        assert_eq!(tracker.take_ready(Duration::ZERO).len(), 2);
+    }
+
+    #[test]
+    fn test_requeue_drops_tickets_past_receipt_ttl() {
+        let tracker = ReceiptTracker::new();
+        let Some(sent_at) = Instant::now().checked_sub(RECEIPT_TTL) else {
+            return;
+        };
+        let ticket = ReadyTicket {
+            id: "ticket-1".to_string(),
+            token: "ExponentPushToken[a]".to_string(),
+            sent_at,
+        };
+        assert_eq!(tracker.requeue(vec![ticket]), 1);
+        assert!(tracker.take_ready(Duration::ZERO).is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+/// Fixed wording used when building forecast notifications. Weather
+/// descriptions and summaries come from OWM already translated via `lang`.
+#[derive(Debug)]
+pub struct TemplateStrings {
+    pub feels: &'static str,
+    pub humidity: &'static str,
+    pub wind: &'static str,
+    pub precip: &'static str,
+    pub alerts: &'static str,
+    pub all_clear: &'static str,
+    pub ended: &'static str,
+    pub warmer_than_yesterday: &'static str,
+    pub colder_than_yesterday: &'static str,
+    pub same_as_yesterday: &'static str,
+    pub wear: &'static str,
+    pub full_moon: &'static str,
+    pub new_moon: &'static str,
+    pub moonrise: &'static str,
+}
+
+const EN: TemplateStrings = TemplateStrings {
+    feels: "feels",
+    humidity: "humidity",
+    wind: "wind",
+    precip: "precip",
+    alerts: "ALERTS",
+    all_clear: "All clear",
+    ended: "has ended",
+    warmer_than_yesterday: "warmer than yesterday",
+    colder_than_yesterday: "colder than yesterday",
+    same_as_yesterday: "Same high as yesterday",
+    wear: "Wear",
+    full_moon: "Full moon",
+    new_moon: "New moon",
+    moonrise: "rises",
+};
+
+const ES: TemplateStrings = TemplateStrings {
+    feels: "sensación",
+    humidity: "humedad",
+    wind: "viento",
+    precip: "precip.",
+    alerts: "ALERTAS",
+    all_clear: "Fin de alerta",
+    ended: "ha terminado",
+    warmer_than_yesterday: "más cálido que ayer",
+    colder_than_yesterday: "más frío que ayer",
+    same_as_yesterday: "Misma máxima que ayer",
+    wear: "Ropa",
+    full_moon: "Luna llena",
+    new_moon: "Luna nueva",
+    moonrise: "sale",
+};
+
+const FR: TemplateStrings = TemplateStrings {
+    feels: "ressenti",
+    humidity: "humidité",
+    wind: "vent",
+    precip: "précip.",
+    alerts: "ALERTES",
+    all_clear: "Fin d'alerte",
+    ended: "a pris fin",
+    warmer_than_yesterday: "plus chaud qu'hier",
+    colder_than_yesterday: "plus froid qu'hier",
+    same_as_yesterday: "Même maximale qu'hier",
+    wear: "Tenue",
+    full_moon: "Pleine lune",
+    new_moon: "Nouvelle lune",
+    moonrise: "lever",
+};
+
+const DE: TemplateStrings = TemplateStrings {
+    feels: "gefühlt",
+    humidity: "Feuchte",
+    wind: "Wind",
+    precip: "Niederschl.",
+    alerts: "WARNUNGEN",
+    all_clear: "Entwarnung",
+    ended: "ist beendet",
+    warmer_than_yesterday: "wärmer als gestern",
+    colder_than_yesterday: "kälter als gestern",
+    same_as_yesterday: "Gleiche Höchsttemperatur wie gestern",
+    wear: "Kleidung",
+    full_moon: "Vollmond",
+    new_moon: "Neumond",
+    moonrise: "Aufgang",
+};
+
+/// Template strings for a language code, falling back to English.
+/// Regional variants ("pt_br", "es-MX") use their base language.
+pub fn template_strings(lang: &str) -> &'static TemplateStrings {
+    let base = lang.split(['_', '-']).next().unwrap_or(lang);
+    match base.to_ascii_lowercase().as_str() {
+        "es" => &ES,
+        "fr" => &FR,
+        "de" => &DE,
+        _ => &EN,
+    }
+}
+
+/// Normalize a language code to OWM's form ("pt-BR" -> "pt_br").
+/// Returns None if it doesn't look like a language code.
+pub fn normalize_language(lang: &str) -> Option<String> {
+    let normalized = lang.trim().to_ascii_lowercase().replace('-', "_");
+    let mut parts = normalized.split('_');
+    let base = parts.next()?;
+    let region = parts.next();
+    let valid_base = base.len() == 2 && base.chars().all(|c| c.is_ascii_lowercase());
+    let valid_region =
+        region.is_none_or(|r| r.len() == 2 && r.chars().all(|c| c.is_ascii_lowercase()));
+    (valid_base && valid_region && parts.next().is_none()).then_some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_strings_fallback() {
+        assert_eq!(template_strings("es").alerts, "ALERTAS");
+        assert_eq!(template_strings("fr_ca").alerts, "ALERTES");
+        assert_eq!(template_strings("ja").alerts, "ALERTS");
+    }
+
+    #[test]
+    fn test_normalize_language() {
+        assert_eq!(normalize_language("EN"), Some("en".to_string()));
+        assert_eq!(normalize_language("pt-BR"), Some("pt_br".to_string()));
+        assert_eq!(normalize_language("english"), None);
+        assert_eq!(normalize_language("e1"), None);
+        assert_eq!(normalize_language("zh_cn_x"), None);
+    }
+}
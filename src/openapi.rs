@@ -1,19 +1,67 @@
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
+use crate::air_quality::handlers as air_quality_handlers;
 use crate::air_quality::models::{AirQualityComponents, AirQualityResponse};
+use crate::api_v2::handlers as api_v2_handlers;
+use crate::api_v2::Pagination;
+use crate::audit::handlers as audit_handlers;
+use crate::auth::handlers as auth_handlers;
+use crate::charts::handlers as chart_handlers;
+use crate::dashboard::handlers as dashboard_handlers;
+use crate::devices::handlers as devices_handlers;
+use crate::devices::models::DeliveryRecord;
 use crate::error::ErrorResponse;
-use crate::forecast::models::WidgetResponse;
+use crate::forecast::handlers as forecast_handlers;
+use crate::forecast::models::{
+    ActiveAlert, AlertCertainty, AlertLevel, AlertSeverity, AlertUrgency, AlertsResponse,
+    WidgetResponse,
+};
+use crate::geocode::handlers as geocode_handlers;
+use crate::history::handlers as history_handlers;
 use crate::history::models::{
-    DailyHistoryResponse, DailyHistorySummary, HistoryDataPoint, HistoryResponse, TrendExtreme,
-    TrendResponse, TrendSummary,
+    AccuracyResponse, AlertHistoryEntry, AlertHistoryResponse, AnomalyEntry,
+    AnomalyHistoryResponse, ArchivedForecastResponse, DailyHistoryResponse, DailyHistorySummary,
+    ExportFormat, HistoryArchiveEntry, HistoryArchivesResponse, HistoryDataPoint,
+    HistoryPagination, HistoryResponse, LeadAccuracy, TrendExtreme, TrendResponse, TrendSummary,
 };
+use crate::live::handlers as live_handlers;
+use crate::live::LiveEvent;
+use crate::locations::handlers as location_handlers;
+use crate::recommendations::handlers as recommendations_handlers;
+use crate::recommendations::models::ClothingResponse;
+use crate::scheduler::handlers as scheduler_handlers;
+use crate::stats;
+use crate::units::Units;
+use crate::weather::handlers as weather_handlers;
 use crate::weather::service::WeatherResponse;
+use crate::webhooks::handlers as webhook_handlers;
+
+/// Registers the auth schemes referenced by path `security` entries: a
+/// bearer token from /auth/login and the device `X-API-Key`
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+    }
+}
 
 /// OpenAPI documentation for the Weathrs API
-///
-/// This provides basic schema documentation. Full path annotations
-/// can be added incrementally to handlers as needed.
 #[derive(OpenApi)]
 #[openapi(
     info(
@@ -29,29 +77,139 @@ use crate::weather::service::WeatherResponse;
             url = "https://github.com/jsprague84/weathrs"
         )
     ),
+    paths(
+        weather_handlers::health,
+        weather_handlers::health_ready,
+        weather_handlers::health_deep,
+        weather_handlers::get_weather,
+        forecast_handlers::get_forecast,
+        forecast_handlers::get_daily_forecast,
+        forecast_handlers::get_hourly_forecast,
+        forecast_handlers::get_alerts,
+        forecast_handlers::get_widget,
+        forecast_handlers::get_atom_feed,
+        air_quality_handlers::get_air_quality,
+        recommendations_handlers::get_clothing,
+        chart_handlers::get_temperature_chart,
+        geocode_handlers::geocode,
+        geocode_handlers::reverse_geocode,
+        history_handlers::get_history,
+        history_handlers::get_daily_history,
+        history_handlers::get_trends,
+        history_handlers::get_alert_history,
+        history_handlers::get_anomalies,
+        history_handlers::get_accuracy,
+        history_handlers::get_archived_forecast,
+        history_handlers::list_history_archives,
+        history_handlers::download_history_archive,
+        history_handlers::export_history,
+        history_handlers::delete_history,
+        history_handlers::cleanup_history,
+        auth_handlers::login,
+        auth_handlers::me,
+        scheduler_handlers::scheduler_status,
+        scheduler_handlers::scheduler_events,
+        scheduler_handlers::list_jobs,
+        scheduler_handlers::create_job,
+        scheduler_handlers::get_job,
+        scheduler_handlers::simulate_job,
+        scheduler_handlers::update_job,
+        scheduler_handlers::delete_job,
+        scheduler_handlers::trigger_forecast,
+        scheduler_handlers::trigger_forecast_by_city,
+        scheduler_handlers::acknowledge_alert,
+        devices_handlers::register_device,
+        devices_handlers::unregister_device,
+        devices_handlers::update_device_settings,
+        devices_handlers::device_heartbeat,
+        devices_handlers::get_my_device,
+        devices_handlers::update_device_location,
+        devices_handlers::send_test_notification,
+        devices_handlers::broadcast_notification,
+        devices_handlers::get_device_count,
+        devices_handlers::list_devices,
+        devices_handlers::get_device_notifications,
+        stats::get_stats,
+        stats::get_client_key_stats,
+        stats::get_usage,
+        stats::get_ops_stats,
+        stats::get_cache_stats,
+        stats::get_status,
+        stats::report_tiles,
+        dashboard_handlers::get_dashboard,
+        audit_handlers::get_audit_log,
+        live_handlers::live_updates,
+        webhook_handlers::list_webhooks,
+        webhook_handlers::create_webhook,
+        webhook_handlers::delete_webhook,
+        webhook_handlers::trigger_webhook,
+        location_handlers::list_locations,
+        location_handlers::get_location,
+        location_handlers::create_location,
+        location_handlers::update_location,
+        location_handlers::delete_location,
+        api_v2_handlers::list_jobs,
+        api_v2_handlers::list_devices,
+        api_v2_handlers::list_device_notifications,
+        api_v2_handlers::list_history,
+    ),
     tags(
+        (name = "health", description = "Liveness and readiness probes"),
         (name = "weather", description = "Current weather data"),
         (name = "forecast", description = "Weather forecasts (daily, hourly)"),
         (name = "widget", description = "Lightweight widget data"),
         (name = "air-quality", description = "Air quality and pollution data"),
+        (name = "recommendations", description = "What to wear, from configurable forecast rules"),
         (name = "history", description = "Historical weather data and trends"),
+        (name = "charts", description = "Rendered chart images"),
         (name = "scheduler", description = "Scheduled forecast jobs"),
-        (name = "devices", description = "Device registration for push notifications")
+        (name = "devices", description = "Device registration for push notifications"),
+        (name = "geocode", description = "Location lookup"),
+        (name = "auth", description = "Bearer token sign-in"),
+        (name = "stats", description = "Usage statistics and dashboard data"),
+        (name = "live", description = "Live updates over WebSocket"),
+        (name = "webhooks", description = "Incoming webhooks that trigger forecast pushes"),
+        (name = "locations", description = "Saved locations, referenced as `loc:<id>` wherever a city is accepted"),
+        (name = "audit", description = "Who changed jobs, devices, webhooks, and saved locations"),
+        (name = "v2", description = "List endpoints with `{data, pagination, request_id}` envelopes and cursor pagination")
     ),
+    modifiers(&SecurityAddon),
     components(
         schemas(
             ErrorResponse,
             WeatherResponse,
             HistoryResponse,
+            HistoryPagination,
             HistoryDataPoint,
             DailyHistoryResponse,
             DailyHistorySummary,
             TrendResponse,
             TrendSummary,
             TrendExtreme,
+            AlertHistoryResponse,
+            AlertHistoryEntry,
+            AnomalyHistoryResponse,
+            AnomalyEntry,
+            AccuracyResponse,
+            LeadAccuracy,
+            ArchivedForecastResponse,
+            HistoryArchivesResponse,
+            ExportFormat,
+            HistoryArchiveEntry,
             WidgetResponse,
+            AlertsResponse,
+            ActiveAlert,
+            AlertLevel,
+            AlertSeverity,
+            AlertUrgency,
+            AlertCertainty,
             AirQualityResponse,
             AirQualityComponents,
+            ClothingResponse,
+            DeliveryRecord,
+            LiveEvent,
+            Pagination,
+            Units,
         )
     )
 )]
@@ -61,3 +219,46 @@ pub struct ApiDoc;
 pub fn swagger_ui() -> SwaggerUi {
     SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_api_routes() {
+        let spec = ApiDoc::openapi();
+        for path in [
+            "/api/v1/forecast/{city}",
+            "/api/v1/geocode/reverse",
+            "/api/v1/recommendations/{city}/clothing",
+            "/api/v1/history/{city}/daily",
+            "/api/v1/charts/{city}/temperature.png",
+            "/api/v1/history/{city}/anomalies",
+            "/api/v1/history/{city}/accuracy",
+            "/api/v1/history/{city}/forecast",
+            "/api/v1/history/{city}/archives",
+            "/api/v1/history/{city}/archives/{month}",
+            "/api/v1/history/{city}/export",
+            "/api/v1/scheduler/jobs/{id}",
+            "/api/v1/scheduler/jobs/{id}/simulate",
+            "/api/v1/alerts/{id}/ack",
+            "/api/v1/devices/register",
+            "/api/v1/stats",
+            "/api/v1/stats/cache",
+            "/api/v1/admin/usage",
+            "/api/v1/admin/stats",
+            "/api/v1/locations/{id}",
+            "/api/v1/status",
+            "/api/v2/devices",
+            "/api/v2/history/{city}",
+        ] {
+            assert!(spec.paths.paths.contains_key(path), "missing {}", path);
+        }
+
+        let schemas = &spec.components.as_ref().unwrap().schemas;
+        for schema in ["ForecastJob", "DeviceRegistrationRequest", "SchedulerEvent"] {
+            assert!(schemas.contains_key(schema), "missing schema {}", schema);
+        }
+        assert!(spec.to_json().is_ok());
+    }
+}
@@ -0,0 +1,305 @@
+use std::sync::{Arc, Mutex};
+
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+};
+use metrics_exporter_prometheus::PrometheusRecorder;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::metrics::{
+    CACHE_HITS, CACHE_MISSES, HTTP_REQUESTS_TOTAL, NOTIFICATIONS_SENT, NWS_API_CALLS,
+    OWM_API_CALLS, SCHEDULER_JOB_RUNS, TTL_CACHE_HITS, TTL_CACHE_MISSES,
+};
+
+/// Minutes of history kept per counter
+const WINDOW_MINUTES: i64 = 24 * 60;
+
+/// Complete minutes averaged for `requestsPerMinute`
+const RATE_MINUTES: i64 = 5;
+
+/// What the rolling counters track, each fed by one or more Prometheus
+/// counters
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OpsCounter {
+    Requests,
+    UpstreamCalls,
+    CacheHits,
+    CacheMisses,
+    NotificationsSent,
+    JobSuccesses,
+    JobFailures,
+}
+
+impl OpsCounter {
+    const ALL: [Self; 7] = [
+        Self::Requests,
+        Self::UpstreamCalls,
+        Self::CacheHits,
+        Self::CacheMisses,
+        Self::NotificationsSent,
+        Self::JobSuccesses,
+        Self::JobFailures,
+    ];
+
+    /// The rolling counter a Prometheus counter feeds, if any
+    fn for_key(key: &Key) -> Option<Self> {
+        match key.name() {
+            HTTP_REQUESTS_TOTAL => Some(Self::Requests),
+            OWM_API_CALLS | NWS_API_CALLS => Some(Self::UpstreamCalls),
+            CACHE_HITS | TTL_CACHE_HITS => Some(Self::CacheHits),
+            CACHE_MISSES | TTL_CACHE_MISSES => Some(Self::CacheMisses),
+            NOTIFICATIONS_SENT => Some(Self::NotificationsSent),
+            SCHEDULER_JOB_RUNS => {
+                match key
+                    .labels()
+                    .find(|l| l.key() == "result")
+                    .map(|l| l.value())
+                {
+                    Some("success") => Some(Self::JobSuccesses),
+                    Some("failure") => Some(Self::JobFailures),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&c| c == self).unwrap()
+    }
+}
+
+/// Per-minute counts over the last 24 hours, in a ring indexed by minute
+struct RollingCounter {
+    /// (minute since the epoch, count) per slot
+    buckets: Mutex<Vec<(i64, u64)>>,
+}
+
+impl RollingCounter {
+    fn new() -> Self {
+        Self {
+            buckets: Mutex::new(vec![(i64::MIN, 0); WINDOW_MINUTES as usize]),
+        }
+    }
+
+    fn add(&self, minute: i64, value: u64) {
+        let mut buckets = self.buckets.lock().unwrap();
+        let slot = &mut buckets[minute.rem_euclid(WINDOW_MINUTES) as usize];
+        if slot.0 != minute {
+            *slot = (minute, 0);
+        }
+        slot.1 += value;
+    }
+
+    /// Total over minutes `from..=to`
+    fn sum(&self, from: i64, to: i64) -> u64 {
+        self.buckets
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(minute, _)| (from..=to).contains(minute))
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// A counter's total over the last hour and the last 24 hours
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RollingCount {
+    pub last_hour: u64,
+    pub last_24h: u64,
+}
+
+/// Rolling operational counters for GET /admin/stats
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpsStatsResponse {
+    pub generated_at: i64,
+    /// Mean over the last five complete minutes
+    pub requests_per_minute: f64,
+    pub requests: RollingCount,
+    /// OpenWeatherMap and NWS calls, retries included
+    pub upstream_calls: RollingCount,
+    pub cache_hits: RollingCount,
+    pub cache_misses: RollingCount,
+    /// Share of cache lookups served from cache over the last 24 hours
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_hit_rate_24h: Option<f64>,
+    /// Push notifications accepted by Expo
+    pub notifications_sent: RollingCount,
+    pub job_successes: RollingCount,
+    pub job_failures: RollingCount,
+}
+
+/// Rolling 24-hour totals of the request, upstream, cache, notification,
+/// and job counters, kept alongside Prometheus for instances without it
+pub struct OpsStats {
+    counters: Vec<RollingCounter>,
+}
+
+impl Default for OpsStats {
+    fn default() -> Self {
+        Self {
+            counters: OpsCounter::ALL
+                .iter()
+                .map(|_| RollingCounter::new())
+                .collect(),
+        }
+    }
+}
+
+impl OpsStats {
+    fn add(&self, counter: OpsCounter, value: u64, now: i64) {
+        self.counters[counter.index()].add(now.div_euclid(60), value);
+    }
+
+    fn count(&self, counter: OpsCounter, now: i64) -> RollingCount {
+        let minute = now.div_euclid(60);
+        let counter = &self.counters[counter.index()];
+        RollingCount {
+            last_hour: counter.sum(minute - 59, minute),
+            last_24h: counter.sum(minute - WINDOW_MINUTES + 1, minute),
+        }
+    }
+
+    /// Current totals as of `now` (Unix seconds)
+    pub fn snapshot(&self, now: i64) -> OpsStatsResponse {
+        let minute = now.div_euclid(60);
+        let recent =
+            self.counters[OpsCounter::Requests.index()].sum(minute - RATE_MINUTES, minute - 1);
+        let cache_hits = self.count(OpsCounter::CacheHits, now);
+        let cache_misses = self.count(OpsCounter::CacheMisses, now);
+        let lookups = cache_hits.last_24h + cache_misses.last_24h;
+
+        OpsStatsResponse {
+            generated_at: now,
+            requests_per_minute: recent as f64 / RATE_MINUTES as f64,
+            requests: self.count(OpsCounter::Requests, now),
+            upstream_calls: self.count(OpsCounter::UpstreamCalls, now),
+            cache_hits,
+            cache_misses,
+            cache_hit_rate_24h: (lookups > 0).then(|| cache_hits.last_24h as f64 / lookups as f64),
+            notifications_sent: self.count(OpsCounter::NotificationsSent, now),
+            job_successes: self.count(OpsCounter::JobSuccesses, now),
+            job_failures: self.count(OpsCounter::JobFailures, now),
+        }
+    }
+}
+
+/// Prometheus recorder that also feeds `OpsStats` from the counters it tracks
+pub struct RollingRecorder {
+    inner: PrometheusRecorder,
+    stats: Arc<OpsStats>,
+}
+
+impl RollingRecorder {
+    pub fn new(inner: PrometheusRecorder, stats: Arc<OpsStats>) -> Self {
+        Self { inner, stats }
+    }
+}
+
+/// A Prometheus counter whose increments are also added to a rolling counter
+struct TeeCounter {
+    inner: Counter,
+    stats: Arc<OpsStats>,
+    counter: OpsCounter,
+}
+
+impl CounterFn for TeeCounter {
+    fn increment(&self, value: u64) {
+        self.inner.increment(value);
+        self.stats
+            .add(self.counter, value, chrono::Utc::now().timestamp());
+    }
+
+    fn absolute(&self, value: u64) {
+        self.inner.absolute(value);
+    }
+}
+
+impl Recorder for RollingRecorder {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(&self, key: &Key, metadata: &Metadata<'_>) -> Counter {
+        let inner = self.inner.register_counter(key, metadata);
+        match OpsCounter::for_key(key) {
+            Some(counter) => Counter::from_arc(Arc::new(TeeCounter {
+                inner,
+                stats: Arc::clone(&self.stats),
+                counter,
+            })),
+            None => inner,
+        }
+    }
+
+    fn register_gauge(&self, key: &Key, metadata: &Metadata<'_>) -> Gauge {
+        self.inner.register_gauge(key, metadata)
+    }
+
+    fn register_histogram(&self, key: &Key, metadata: &Metadata<'_>) -> Histogram {
+        self.inner.register_histogram(key, metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_windows() {
+        let stats = OpsStats::default();
+        let now = 1_700_000_000 - 1_700_000_000 % 60 + 30;
+
+        // Older than the window; its slot is reused a minute ago
+        stats.add(OpsCounter::Requests, 100, now - 25 * 3600);
+        stats.add(OpsCounter::Requests, 10, now);
+        stats.add(OpsCounter::Requests, 5, now - 60);
+        stats.add(OpsCounter::Requests, 5, now - 2 * 3600);
+        stats.add(OpsCounter::CacheHits, 3, now);
+        stats.add(OpsCounter::CacheMisses, 1, now);
+
+        let snapshot = stats.snapshot(now);
+        assert_eq!(snapshot.requests.last_hour, 15);
+        assert_eq!(snapshot.requests.last_24h, 20);
+        assert_eq!(snapshot.requests_per_minute, 1.0);
+        assert_eq!(snapshot.cache_hit_rate_24h, Some(0.75));
+        assert_eq!(snapshot.job_failures.last_24h, 0);
+
+        // A day later the same slots hold nothing current
+        assert_eq!(stats.snapshot(now + 24 * 3600).requests.last_24h, 0);
+    }
+
+    #[test]
+    fn test_job_runs_split_by_result() {
+        let success = Key::from_parts(
+            SCHEDULER_JOB_RUNS,
+            vec![metrics::Label::new("result", "success")],
+        );
+        let failure = Key::from_parts(
+            SCHEDULER_JOB_RUNS,
+            vec![metrics::Label::new("result", "failure")],
+        );
+        assert_eq!(
+            OpsCounter::for_key(&success),
+            Some(OpsCounter::JobSuccesses)
+        );
+        assert_eq!(OpsCounter::for_key(&failure), Some(OpsCounter::JobFailures));
+        assert_eq!(
+            OpsCounter::for_key(&Key::from_name(TTL_CACHE_HITS)),
+            Some(OpsCounter::CacheHits)
+        );
+        assert_eq!(OpsCounter::for_key(&Key::from_name("other")), None);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::config::ClothingRule;
+use crate::forecast::models::DailyForecastResponse;
+
+/// Today's conditions in the units clothing rules are written in
+#[derive(Debug, Clone, Copy)]
+struct Conditions {
+    /// Daytime feels-like, °C
+    feels_like: f64,
+    /// m/s
+    wind_speed: f64,
+    precipitation: f64,
+    uv_index: f64,
+}
+
+impl Conditions {
+    fn new(today: &DailyForecastResponse, units: &str) -> Self {
+        let feels_like = match units {
+            "imperial" => (today.feels_like_day - 32.0) * 5.0 / 9.0,
+            "standard" => today.feels_like_day - 273.15,
+            _ => today.feels_like_day,
+        };
+        let wind_speed = match units {
+            "imperial" => today.wind_speed * 0.44704,
+            _ => today.wind_speed,
+        };
+        Self {
+            feels_like,
+            wind_speed,
+            precipitation: today.precipitation_probability,
+            uv_index: today.uv_index,
+        }
+    }
+}
+
+fn matches(rule: &ClothingRule, conditions: &Conditions) -> bool {
+    rule.min_feels_like
+        .is_none_or(|t| conditions.feels_like >= t)
+        && rule
+            .max_feels_like
+            .is_none_or(|t| conditions.feels_like < t)
+        && rule
+            .min_wind_speed
+            .is_none_or(|w| conditions.wind_speed >= w)
+        && rule
+            .min_precipitation
+            .is_none_or(|p| conditions.precipitation >= p)
+        && rule.min_uv_index.is_none_or(|uv| conditions.uv_index >= uv)
+}
+
+/// Suggestions from every rule `today`, fetched in `units`, matches, in rule
+/// order and without repeats
+pub fn clothing_suggestions(
+    rules: &[ClothingRule],
+    today: &DailyForecastResponse,
+    units: &str,
+) -> Vec<String> {
+    let conditions = Conditions::new(today, units);
+    let mut suggestions: Vec<String> = Vec::new();
+    for rule in rules.iter().filter(|r| matches(r, &conditions)) {
+        if !suggestions.contains(&rule.suggestion) {
+            suggestions.push(rule.suggestion.clone());
+        }
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RecommendationsConfig;
+
+    fn today(feels_like: f64, wind_speed: f64, pop: f64, uvi: f64) -> DailyForecastResponse {
+        DailyForecastResponse {
+            timestamp: 0,
+            sunrise: 0,
+            sunset: 0,
+            moonrise: None,
+            moonset: None,
+            moon_phase: 0.0,
+            summary: None,
+            temp_min: feels_like,
+            temp_max: feels_like,
+            temp_day: feels_like,
+            temp_night: feels_like,
+            temp_morning: feels_like,
+            temp_evening: feels_like,
+            feels_like_day: feels_like,
+            feels_like_night: feels_like,
+            humidity: 50,
+            pressure: 1013,
+            uv_index: uvi,
+            clouds: 0,
+            wind_speed,
+            wind_direction: 0,
+            wind_gust: None,
+            precipitation_probability: pop,
+            rain_volume: None,
+            snow_volume: None,
+            description: "clear sky".to_string(),
+            icon: "01d".to_string(),
+            condition_id: Some(800),
+            condition_main: Some("Clear".to_string()),
+            local_time: None,
+            sunrise_local: None,
+            sunset_local: None,
+            sun_detail: None,
+        }
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let rules = RecommendationsConfig::default().clothing;
+        assert_eq!(
+            clothing_suggestions(&rules, &today(14.0, 3.0, 0.7, 2.0), "metric"),
+            vec!["light jacket", "umbrella"]
+        );
+        // 86°F and 25 mph is 30°C and about 11 m/s
+        assert_eq!(
+            clothing_suggestions(&rules, &today(86.0, 25.0, 0.0, 9.0), "imperial"),
+            vec!["shorts", "windbreaker", "sunscreen"]
+        );
+        assert!(clothing_suggestions(&rules, &today(21.0, 2.0, 0.1, 3.0), "metric").is_empty());
+    }
+}
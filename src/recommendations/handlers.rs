@@ -0,0 +1,61 @@
+use axum::{extract::State, Json};
+
+use super::clothing_suggestions;
+use super::models::ClothingResponse;
+use crate::error::ErrorResponse;
+use crate::extractors::{CityParam, UnitsParam};
+use crate::forecast::ForecastError;
+use crate::units::Units;
+use crate::AppState;
+
+/// Get clothing suggestions for today
+///
+/// Checks today's forecast against the configured clothing rules
+/// (`recommendations.clothing`).
+///
+/// - GET /recommendations/{city}/clothing?units=metric
+#[utoipa::path(
+    get,
+    path = "/api/v1/recommendations/{city}/clothing",
+    tag = "recommendations",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code"), ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)")),
+    responses(
+        (status = 200, description = "Clothing suggestions for today", body = ClothingResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse)
+    )
+)]
+pub async fn get_clothing(
+    State(state): State<AppState>,
+    CityParam(city): CityParam,
+    UnitsParam(units): UnitsParam,
+) -> Result<Json<ClothingResponse>, ForecastError> {
+    let city = city.unwrap_or_else(|| state.config.default_location());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
+
+    let forecast = state
+        .forecast_service
+        .get_forecast(&city, units.as_str())
+        .await?;
+
+    let today = forecast.daily.first().ok_or_else(|| {
+        ForecastError::InvalidResponse("No daily forecast data available".to_string())
+    })?;
+
+    let suggestions = clothing_suggestions(
+        &state.config.recommendations.clothing,
+        today,
+        units.as_str(),
+    );
+
+    Ok(Json(ClothingResponse {
+        city: forecast.location.city.clone(),
+        units,
+        feels_like: today.feels_like_day,
+        wind_speed: today.wind_speed,
+        precipitation_probability: today.precipitation_probability,
+        uv_index: today.uv_index,
+        suggestions,
+    }))
+}
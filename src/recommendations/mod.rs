@@ -0,0 +1,5 @@
+mod clothing;
+pub mod handlers;
+pub mod models;
+
+pub use clothing::clothing_suggestions;
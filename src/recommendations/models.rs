@@ -0,0 +1,19 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::units::Units;
+
+/// What to wear today, with the conditions the suggestions were based on
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ClothingResponse {
+    pub city: String,
+    pub units: Units,
+    /// Today's daytime feels-like temperature
+    pub feels_like: f64,
+    pub wind_speed: f64,
+    /// Chance of precipitation, 0-1
+    pub precipitation_probability: f64,
+    pub uv_index: f64,
+    /// Suggestions from every matching rule, in configured order
+    pub suggestions: Vec<String>,
+}
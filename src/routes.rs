@@ -6,22 +6,36 @@ use axum::{
     routing::{delete, get, post, put},
     Extension, Router,
 };
+use tower::limit::GlobalConcurrencyLimitLayer;
 use tower_governor::{
     governor::GovernorConfigBuilder, key_extractor::SmartIpKeyExtractor, GovernorLayer,
 };
 
 use crate::air_quality::handlers as air_quality_handlers;
-use crate::config::RateLimitConfig;
+use crate::api_v2::handlers as api_v2_handlers;
+use crate::audit::handlers as audit_handlers;
+use crate::auth::handlers as auth_handlers;
+use crate::charts::handlers as chart_handlers;
+use crate::client_keys::ClientKeys;
+use crate::config::{CacheConfig, ConcurrencyConfig, FeaturesConfig, RateLimitConfig};
+use crate::dashboard::handlers as dashboard_handlers;
 use crate::devices::handlers as devices_handlers;
 use crate::forecast::handlers as forecast_handlers;
 use crate::geocode::handlers as geocode_handlers;
 use crate::history::handlers as history_handlers;
+use crate::live::handlers as live_handlers;
+use crate::locations::handlers as location_handlers;
 use crate::metrics::track_metrics;
-use crate::middleware::{require_api_key, DeviceApiKey};
+use crate::middleware::{
+    cache_headers, client_key_quota, optional_jwt, require_admin, require_api_key, require_jwt,
+    track_key_usage, CachePolicy, DeviceApiKey, JwtAuth,
+};
 use crate::openapi::swagger_ui;
+use crate::recommendations::handlers as recommendations_handlers;
 use crate::scheduler::handlers as scheduler_handlers;
 use crate::stats;
 use crate::weather::handlers as weather_handlers;
+use crate::webhooks::handlers as webhook_handlers;
 use crate::AppState;
 
 /// Build the weather API routes
@@ -52,11 +66,14 @@ fn forecast_routes() -> Router<AppState> {
             "/forecast/hourly/{city}",
             get(forecast_handlers::get_hourly_forecast),
         )
+        .route("/alerts", get(forecast_handlers::get_alerts))
+        .route("/alerts/{city}", get(forecast_handlers::get_alerts))
         .route("/widget/{city}", get(forecast_handlers::get_widget))
+        .route("/feeds/{file}", get(forecast_handlers::get_atom_feed))
 }
 
-/// Build the scheduler API routes with separate rate limits for reads vs mutations
-fn scheduler_routes(rate_limit: &RateLimitConfig) -> Router<AppState> {
+/// Stricter per-IP rate limit for mutation and login endpoints
+fn with_mutation_limit(router: Router<AppState>, rate_limit: &RateLimitConfig) -> Router<AppState> {
     let mutation_config = Arc::new(
         GovernorConfigBuilder::default()
             .per_second(rate_limit.mutation_rpm as u64 / 60 + 1)
@@ -66,39 +83,125 @@ fn scheduler_routes(rate_limit: &RateLimitConfig) -> Router<AppState> {
             .finish()
             .unwrap(),
     );
+    router.layer(GovernorLayer::new(mutation_config))
+}
 
+/// Build the scheduler API routes with separate rate limits for reads vs mutations
+fn scheduler_routes(rate_limit: &RateLimitConfig, jwt: JwtAuth) -> Router<AppState> {
     // Read endpoints (use general rate limit applied at router level)
     let read_routes = Router::new()
         .route(
             "/scheduler/status",
             get(scheduler_handlers::scheduler_status),
         )
-        .route("/scheduler/jobs", get(scheduler_handlers::list_jobs))
-        .route("/scheduler/jobs/{id}", get(scheduler_handlers::get_job));
-
-    // Mutation endpoints (stricter rate limit)
-    let mutation_routes = Router::new()
-        .route("/scheduler/jobs", post(scheduler_handlers::create_job))
-        .route(
-            "/scheduler/jobs/{id}",
-            put(scheduler_handlers::update_job).delete(scheduler_handlers::delete_job),
-        )
         .route(
-            "/scheduler/trigger",
-            post(scheduler_handlers::trigger_forecast),
+            "/scheduler/events",
+            get(scheduler_handlers::scheduler_events),
         )
+        .route("/scheduler/jobs", get(scheduler_handlers::list_jobs))
+        .route("/scheduler/jobs/{id}", get(scheduler_handlers::get_job))
         .route(
-            "/scheduler/trigger/{city}",
-            post(scheduler_handlers::trigger_forecast_by_city),
-        )
-        .layer(GovernorLayer::new(mutation_config));
+            "/scheduler/jobs/{id}/simulate",
+            get(scheduler_handlers::simulate_job),
+        );
+
+    // Mutation endpoints (stricter rate limit)
+    let mutation_routes = with_mutation_limit(
+        Router::new()
+            .route("/scheduler/jobs", post(scheduler_handlers::create_job))
+            .route(
+                "/scheduler/jobs/{id}",
+                put(scheduler_handlers::update_job).delete(scheduler_handlers::delete_job),
+            )
+            .route(
+                "/scheduler/trigger",
+                post(scheduler_handlers::trigger_forecast),
+            )
+            .route(
+                "/scheduler/trigger/{city}",
+                post(scheduler_handlers::trigger_forecast_by_city),
+            )
+            .route(
+                "/alerts/{id}/ack",
+                post(scheduler_handlers::acknowledge_alert)
+                    .layer(middleware::from_fn(require_admin)),
+            ),
+        rate_limit,
+    );
+
+    read_routes
+        .merge(mutation_routes)
+        .layer(middleware::from_fn(require_jwt))
+        .layer(Extension(jwt))
+}
+
+/// Build the auth API routes
+fn auth_routes(rate_limit: &RateLimitConfig, jwt: JwtAuth) -> Router<AppState> {
+    let me = Router::new()
+        .route("/auth/me", get(auth_handlers::me))
+        .layer(middleware::from_fn(require_jwt))
+        .layer(Extension(jwt));
+
+    with_mutation_limit(
+        Router::new().route("/auth/login", post(auth_handlers::login)),
+        rate_limit,
+    )
+    .merge(me)
+}
+
+/// Build the webhook API routes
+///
+/// Managing hooks needs a bearer token when JWT auth is enabled. Firing one
+/// only needs that hook's secret, so external systems never hold an API key.
+fn webhook_routes(rate_limit: &RateLimitConfig, jwt: JwtAuth) -> Router<AppState> {
+    let manage = Router::new()
+        .route("/hooks", get(webhook_handlers::list_webhooks))
+        .merge(with_mutation_limit(
+            Router::new()
+                .route("/hooks", post(webhook_handlers::create_webhook))
+                .route("/hooks/{id}", delete(webhook_handlers::delete_webhook)),
+            rate_limit,
+        ))
+        .layer(middleware::from_fn(require_jwt))
+        .layer(Extension(jwt));
+
+    let trigger = with_mutation_limit(
+        Router::new().route("/hooks/{id}", post(webhook_handlers::trigger_webhook)),
+        rate_limit,
+    );
 
-    read_routes.merge(mutation_routes)
+    manage.merge(trigger)
 }
 
-/// Build the devices API routes (protected by API key auth)
-fn devices_routes(api_key: Option<String>) -> Router<AppState> {
+/// Build the saved locations API routes
+///
+/// Needs a bearer token when JWT auth is enabled; users manage their own.
+fn locations_routes(rate_limit: &RateLimitConfig, jwt: JwtAuth) -> Router<AppState> {
     Router::new()
+        .route("/locations", get(location_handlers::list_locations))
+        .route("/locations/{id}", get(location_handlers::get_location))
+        .merge(with_mutation_limit(
+            Router::new()
+                .route("/locations", post(location_handlers::create_location))
+                .route(
+                    "/locations/{id}",
+                    put(location_handlers::update_location)
+                        .delete(location_handlers::delete_location),
+                ),
+            rate_limit,
+        ))
+        .layer(middleware::from_fn(require_jwt))
+        .layer(Extension(jwt))
+}
+
+/// Build the devices API routes
+///
+/// Self-service routes used by the app are protected by the API key; a bearer
+/// token there is optional and makes the caller the device's owner. Admin
+/// routes need a bearer token when JWT auth is enabled, else the API key.
+/// Users only see their own devices; broadcasting is admin-only.
+fn devices_routes(api_key: Option<String>, jwt: JwtAuth) -> Router<AppState> {
+    let self_service = Router::new()
         .route("/devices/register", post(devices_handlers::register_device))
         .route(
             "/devices/unregister",
@@ -108,14 +211,56 @@ fn devices_routes(api_key: Option<String>) -> Router<AppState> {
             "/devices/settings",
             put(devices_handlers::update_device_settings),
         )
+        .route("/devices/me", get(devices_handlers::get_my_device))
+        .route(
+            "/devices/location",
+            put(devices_handlers::update_device_location),
+        )
+        .route(
+            "/devices/heartbeat",
+            post(devices_handlers::device_heartbeat),
+        )
         .route(
             "/devices/test",
             post(devices_handlers::send_test_notification),
         )
-        .route("/devices/count", get(devices_handlers::get_device_count))
-        .route("/devices/debug", get(devices_handlers::list_devices))
+        .layer(middleware::from_fn(optional_jwt))
         .layer(middleware::from_fn(require_api_key))
-        .layer(Extension(DeviceApiKey(api_key)))
+        .layer(Extension(DeviceApiKey(api_key.clone())))
+        .layer(Extension(jwt.clone()));
+
+    let admin = Router::new()
+        .route(
+            "/devices/broadcast",
+            post(devices_handlers::broadcast_notification)
+                .layer(middleware::from_fn(require_admin)),
+        )
+        .route("/devices/count", get(devices_handlers::get_device_count))
+        .route("/devices", get(devices_handlers::list_devices))
+        .route(
+            "/devices/{id}/notifications",
+            get(devices_handlers::get_device_notifications),
+        );
+
+    self_service.merge(with_device_admin_auth(admin, api_key, jwt))
+}
+
+/// Device admin routes need a bearer token when JWT auth is enabled, else the
+/// device API key
+fn with_device_admin_auth(
+    router: Router<AppState>,
+    api_key: Option<String>,
+    jwt: JwtAuth,
+) -> Router<AppState> {
+    if jwt.0.is_some() {
+        router
+            .layer(middleware::from_fn(require_jwt))
+            .layer(Extension(jwt))
+    } else {
+        router
+            .layer(middleware::from_fn(require_api_key))
+            .layer(Extension(DeviceApiKey(api_key)))
+    }
 }
 
 /// Build the air quality API routes
@@ -126,14 +271,43 @@ fn air_quality_routes() -> Router<AppState> {
     )
 }
 
+/// Build the recommendation API routes
+fn recommendations_routes() -> Router<AppState> {
+    Router::new().route(
+        "/recommendations/{city}/clothing",
+        get(recommendations_handlers::get_clothing),
+    )
+}
+
+/// Build the chart image routes
+fn chart_routes() -> Router<AppState> {
+    Router::new().route(
+        "/charts/{city}/temperature.png",
+        get(chart_handlers::get_temperature_chart),
+    )
+}
+
 /// Build the geocode API routes
 fn geocode_routes() -> Router<AppState> {
-    Router::new().route("/geocode", get(geocode_handlers::geocode))
+    Router::new()
+        .route("/geocode", get(geocode_handlers::geocode))
+        .route("/geocode/reverse", get(geocode_handlers::reverse_geocode))
 }
 
 /// Build the history API routes
-fn history_routes() -> Router<AppState> {
+fn history_routes(jwt: JwtAuth) -> Router<AppState> {
+    let maintenance = Router::new()
+        .route(
+            "/history/location/{location_key}",
+            delete(history_handlers::delete_history),
+        )
+        .route("/history/cleanup", post(history_handlers::cleanup_history))
+        .layer(middleware::from_fn(require_admin))
+        .layer(middleware::from_fn(require_jwt))
+        .layer(Extension(jwt));
+
     Router::new()
+        .route("/history", get(history_handlers::get_history))
         .route("/history/{city}", get(history_handlers::get_history))
         .route(
             "/history/{city}/daily",
@@ -141,27 +315,204 @@ fn history_routes() -> Router<AppState> {
         )
         .route("/history/{city}/trends", get(history_handlers::get_trends))
         .route(
-            "/history/location/{location_key}",
-            delete(history_handlers::delete_history),
+            "/history/{city}/alerts",
+            get(history_handlers::get_alert_history),
         )
-        .route("/history/cleanup", post(history_handlers::cleanup_history))
+        .route(
+            "/history/{city}/anomalies",
+            get(history_handlers::get_anomalies),
+        )
+        .route(
+            "/history/{city}/accuracy",
+            get(history_handlers::get_accuracy),
+        )
+        .route(
+            "/history/{city}/forecast",
+            get(history_handlers::get_archived_forecast),
+        )
+        .route(
+            "/history/{city}/archives",
+            get(history_handlers::list_history_archives),
+        )
+        .route(
+            "/history/{city}/archives/{month}",
+            get(history_handlers::download_history_archive),
+        )
+        .route(
+            "/history/{city}/export",
+            get(history_handlers::export_history),
+        )
+        .merge(maintenance)
+}
+
+/// Let clients, and proxies when `shared`, cache successful GETs in `router`
+/// for `max_age_secs`
+fn with_cache_headers(
+    router: Router<AppState>,
+    max_age_secs: u64,
+    shared: bool,
+) -> Router<AppState> {
+    router
+        .layer(middleware::from_fn(cache_headers))
+        .layer(Extension(CachePolicy {
+            max_age_secs,
+            shared,
+        }))
+}
+
+/// Cap in-flight requests across every route in `router` (0 = unlimited).
+/// One semaphore is shared by the whole group rather than one per route.
+fn limit_concurrency(router: Router<AppState>, max: usize) -> Router<AppState> {
+    if max == 0 {
+        router
+    } else {
+        router.layer(GlobalConcurrencyLimitLayer::new(max))
+    }
 }
 
 /// Build all API v1 routes
+///
+/// Routes that may call OpenWeatherMap share a smaller concurrency budget than
+/// routes served from local state, so a burst of uncached forecast requests
+/// can't tie up the whole server. Health and metrics routes are unlimited.
+/// Those routes also count against the caller's client key quota.
+///
+/// Weather, forecast, and history responses carry caching headers.
+///
+/// Routes of subsystems switched off in `features` aren't mounted.
 pub fn api_v1_routes(
     device_api_key: Option<String>,
+    jwt: JwtAuth,
+    client_keys: Arc<ClientKeys>,
     rate_limit: &RateLimitConfig,
+    concurrency: &ConcurrencyConfig,
+    features: &FeaturesConfig,
+    cache: &CacheConfig,
 ) -> Router<AppState> {
-    Router::new()
-        .merge(weather_routes())
-        .merge(forecast_routes())
+    let shared = !client_keys.required();
+    let mut upstream = Router::new()
+        .merge(with_cache_headers(
+            weather_routes(),
+            cache.weather_ttl_secs,
+            shared,
+        ))
+        .merge(with_cache_headers(
+            forecast_routes(),
+            cache.forecast_ttl_secs,
+            shared,
+        ))
+        .merge(with_cache_headers(
+            recommendations_routes(),
+            cache.forecast_ttl_secs,
+            shared,
+        ))
         .merge(geocode_routes())
-        .merge(air_quality_routes())
-        .merge(history_routes())
-        .merge(scheduler_routes(rate_limit))
-        .merge(devices_routes(device_api_key))
+        .merge(air_quality_routes());
+    if features.history {
+        upstream = upstream.merge(with_cache_headers(
+            history_routes(jwt.clone()),
+            cache.history_max_age_secs,
+            shared,
+        ));
+        // Charts draw on recorded history
+        upstream = upstream.merge(with_cache_headers(
+            chart_routes(),
+            cache.forecast_ttl_secs,
+            shared,
+        ));
+    }
+    let upstream = upstream
+        .layer(middleware::from_fn(client_key_quota))
+        .layer(Extension(Arc::clone(&client_keys)));
+
+    // Live updates come from scheduler runs. They don't call OpenWeatherMap
+    // but still need a client key when keys are required, and a bearer token
+    // so job results reach only their owners.
+    let live = Router::new()
+        .route("/ws", get(live_handlers::live_updates))
+        .layer(middleware::from_fn(require_jwt))
+        .layer(Extension(jwt.clone()))
+        .layer(middleware::from_fn(client_key_quota))
+        .layer(Extension(client_keys));
+
+    let admin_stats = Router::new()
         .route("/stats", get(stats::get_stats))
-        .route("/stats/tiles", post(stats::report_tiles))
+        .route("/stats/keys", get(stats::get_client_key_stats))
+        .route("/stats/cache", get(stats::get_cache_stats))
+        .route("/admin/usage", get(stats::get_usage))
+        .route("/admin/stats", get(stats::get_ops_stats))
+        .route("/status", get(stats::get_status))
+        .route("/dashboard", get(dashboard_handlers::get_dashboard))
+        .route("/audit", get(audit_handlers::get_audit_log))
+        .layer(middleware::from_fn(require_admin))
+        .layer(middleware::from_fn(require_jwt))
+        .layer(Extension(jwt.clone()));
+
+    let mut local = Router::new()
+        .merge(auth_routes(rate_limit, jwt.clone()))
+        .merge(locations_routes(rate_limit, jwt.clone()));
+    if features.scheduler {
+        local = local
+            .merge(scheduler_routes(rate_limit, jwt.clone()))
+            .merge(webhook_routes(rate_limit, jwt.clone()))
+            .merge(live);
+    }
+    if features.devices {
+        local = local.merge(devices_routes(device_api_key, jwt));
+    }
+    let local = local
+        .merge(admin_stats)
+        .route("/stats/tiles", post(stats::report_tiles));
+
+    limit_concurrency(upstream, concurrency.upstream)
+        .merge(limit_concurrency(local, concurrency.local))
+}
+
+/// Build all API v2 routes
+///
+/// v2 covers the list endpoints, each answering with the cursor-paginated
+/// envelope from `api_v2`. Auth, quotas, and concurrency budgets match the v1
+/// routes they mirror, as does which subsystems are mounted.
+pub fn api_v2_routes(
+    device_api_key: Option<String>,
+    jwt: JwtAuth,
+    client_keys: Arc<ClientKeys>,
+    concurrency: &ConcurrencyConfig,
+    features: &FeaturesConfig,
+    cache: &CacheConfig,
+) -> Router<AppState> {
+    let mut upstream = Router::new();
+    if features.history {
+        let history = Router::new().route("/history/{city}", get(api_v2_handlers::list_history));
+        upstream = with_cache_headers(history, cache.history_max_age_secs, !client_keys.required())
+            .layer(middleware::from_fn(client_key_quota))
+            .layer(Extension(client_keys));
+    }
+
+    let mut local = Router::new();
+    if features.scheduler {
+        local = local.merge(
+            Router::new()
+                .route("/scheduler/jobs", get(api_v2_handlers::list_jobs))
+                .layer(middleware::from_fn(require_jwt))
+                .layer(Extension(jwt.clone())),
+        );
+    }
+    if features.devices {
+        local = local.merge(with_device_admin_auth(
+            Router::new()
+                .route("/devices", get(api_v2_handlers::list_devices))
+                .route(
+                    "/devices/{id}/notifications",
+                    get(api_v2_handlers::list_device_notifications),
+                ),
+            device_api_key,
+            jwt,
+        ));
+    }
+
+    limit_concurrency(upstream, concurrency.upstream)
+        .merge(limit_concurrency(local, concurrency.local))
 }
 
 /// Prometheus metrics scrape endpoint
@@ -172,7 +523,13 @@ async fn metrics_handler(State(state): State<AppState>) -> String {
 /// Build the complete application router
 pub fn build_router(state: AppState) -> Router<AppState> {
     let device_api_key = state.config.device_api_key.clone();
+    let jwt = JwtAuth(state.jwt_keys.clone());
+    let client_keys = Arc::clone(&state.client_keys);
     let rate_limit = state.config.rate_limit.clone();
+    let concurrency = state.config.concurrency.clone();
+    let dashboard_enabled = state.config.dashboard.enabled;
+    let features = state.config.features.clone();
+    let cache = state.config.cache.clone();
 
     // General rate limit for all API routes
     let general_config = Arc::new(
@@ -185,20 +542,64 @@ pub fn build_router(state: AppState) -> Router<AppState> {
             .unwrap(),
     );
 
-    Router::new()
+    let router = Router::new()
         // Health check at root level (no rate limit)
         .route("/", get(weather_handlers::health))
         .route("/health", get(weather_handlers::health))
+        .route("/health/live", get(weather_handlers::health))
+        .route("/health/ready", get(weather_handlers::health_ready))
         .route("/health/deep", get(weather_handlers::health_deep))
         // Prometheus metrics endpoint (no rate limit)
         .route("/metrics", get(metrics_handler))
         // API v1 routes with general rate limiting
         .nest(
             "/api/v1",
-            api_v1_routes(device_api_key, &rate_limit).layer(GovernorLayer::new(general_config)),
+            api_v1_routes(
+                device_api_key.clone(),
+                jwt.clone(),
+                Arc::clone(&client_keys),
+                &rate_limit,
+                &concurrency,
+                &features,
+                &cache,
+            )
+            .layer(GovernorLayer::new(Arc::clone(&general_config)))
+            // Outside the rate limit, so rejected requests count too
+            .layer(middleware::from_fn(track_key_usage))
+            .layer(Extension(Arc::clone(&client_keys))),
         )
-        // Swagger UI for API documentation
-        .merge(swagger_ui())
-        // Metrics middleware for all routes
-        .layer(middleware::from_fn(track_metrics))
+        // API v2 list endpoints share the same per-IP budget
+        .nest(
+            "/api/v2",
+            api_v2_routes(
+                device_api_key,
+                jwt,
+                Arc::clone(&client_keys),
+                &concurrency,
+                &features,
+                &cache,
+            )
+            .layer(GovernorLayer::new(general_config))
+            .layer(middleware::from_fn(track_key_usage))
+            .layer(Extension(Arc::clone(&client_keys))),
+        );
+
+    // Swagger UI for API documentation
+    let router = if features.swagger_ui {
+        router.merge(swagger_ui())
+    } else {
+        router
+    };
+
+    // Built-in dashboard; its data comes from /api/v1/dashboard
+    let router = if dashboard_enabled {
+        router
+            .route("/ui", get(dashboard_handlers::index))
+            .route("/ui/{*path}", get(dashboard_handlers::asset))
+    } else {
+        router
+    };
+
+    // Metrics middleware for all routes
+    router.layer(middleware::from_fn(track_metrics))
 }
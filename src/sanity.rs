@@ -0,0 +1,343 @@
+use serde::Serialize;
+
+use crate::config::ValidationConfig;
+use crate::db::history_repo::HistoryRecord;
+use crate::db::WeatherAnomaly;
+use crate::forecast::models::ForecastResponse;
+use crate::units::Units;
+
+/// Coldest and hottest believable air temperatures, in °C (the records are
+/// -89.2 and 56.7)
+const TEMPERATURE_RANGE_C: (f64, f64) = (-95.0, 65.0);
+
+/// Believable sea-level pressures, in hPa (the records are 870 and 1084)
+const PRESSURE_RANGE_HPA: (i64, i64) = (850, 1090);
+
+/// Fastest believable wind or gust, in m/s
+const MAX_WIND_MS: f64 = 120.0;
+
+/// Readings further apart than this aren't compared for temperature jumps
+pub const MAX_COMPARE_GAP_SECS: i64 = 3 * 3600;
+
+/// Source recorded for quarantined forecast values
+pub const SOURCE_FORECAST: &str = "forecast";
+
+/// A reading that failed the checks, and why
+#[derive(Debug)]
+pub struct Rejected<T> {
+    pub timestamp: i64,
+    pub reading: T,
+    pub reasons: Vec<String>,
+}
+
+impl<T: Serialize> Rejected<T> {
+    /// The quarantine row for this reading
+    pub fn into_anomaly(
+        self,
+        location_key: &str,
+        city: &str,
+        source: &str,
+        detected_at: i64,
+    ) -> WeatherAnomaly {
+        WeatherAnomaly {
+            location_key: location_key.to_string(),
+            city: city.to_string(),
+            source: source.to_string(),
+            observed_at: self.timestamp,
+            detected_at,
+            reasons: self.reasons,
+            payload: serde_json::to_value(&self.reading).unwrap_or_default(),
+        }
+    }
+}
+
+/// The checked values of one reading, in metric
+struct Reading {
+    timestamp: i64,
+    temperature: f64,
+    humidity: i64,
+    /// None when the source doesn't report pressure
+    pressure: Option<i64>,
+    wind_speed: f64,
+    wind_gust: Option<f64>,
+}
+
+impl From<&HistoryRecord> for Reading {
+    fn from(r: &HistoryRecord) -> Self {
+        Self {
+            timestamp: r.timestamp,
+            temperature: r.temperature,
+            humidity: r.humidity.into(),
+            pressure: Some(r.pressure.into()),
+            wind_speed: r.wind_speed,
+            wind_gust: r.wind_gust,
+        }
+    }
+}
+
+impl ValidationConfig {
+    /// Why a reading is implausible, given the last accepted one before it
+    fn problems(&self, reading: &Reading, previous: Option<&Reading>) -> Vec<String> {
+        let mut problems = Vec::new();
+        let (coldest, hottest) = TEMPERATURE_RANGE_C;
+        if !(coldest..=hottest).contains(&reading.temperature) {
+            problems.push(format!(
+                "temperature {:.1}°C outside {}..{}°C",
+                reading.temperature, coldest, hottest
+            ));
+        }
+        if !(0..=100).contains(&reading.humidity) {
+            problems.push(format!("humidity {}% outside 0..100%", reading.humidity));
+        }
+        let (lowest, highest) = PRESSURE_RANGE_HPA;
+        if let Some(pressure) = reading.pressure.filter(|p| !(lowest..=highest).contains(p)) {
+            problems.push(format!(
+                "pressure {} hPa outside {}..{} hPa",
+                pressure, lowest, highest
+            ));
+        }
+        for (name, speed) in [
+            ("wind speed", Some(reading.wind_speed)),
+            ("wind gust", reading.wind_gust),
+        ] {
+            if let Some(speed) = speed.filter(|s| !(0.0..=MAX_WIND_MS).contains(s)) {
+                problems.push(format!(
+                    "{} {:.1} m/s outside 0..{} m/s",
+                    name, speed, MAX_WIND_MS
+                ));
+            }
+        }
+        if let Some(previous) = previous {
+            let gap = reading.timestamp - previous.timestamp;
+            if gap > 0 && gap <= MAX_COMPARE_GAP_SECS {
+                let hours = (gap as f64 / 3600.0).max(1.0);
+                let change = (reading.temperature - previous.temperature).abs();
+                if change > self.max_hourly_temp_change * hours {
+                    problems.push(format!(
+                        "temperature changed {:.1}°C in {:.1} h (limit {}°C per hour)",
+                        change,
+                        gap as f64 / 3600.0,
+                        self.max_hourly_temp_change
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Split history records into plausible ones, oldest first, and rejected
+    /// ones. `previous` is the latest stored reading before the batch, which
+    /// the first temperature is compared against.
+    pub fn screen_history(
+        &self,
+        mut records: Vec<HistoryRecord>,
+        previous: Option<&HistoryRecord>,
+    ) -> (Vec<HistoryRecord>, Vec<Rejected<HistoryRecord>>) {
+        if !self.enabled {
+            return (records, Vec::new());
+        }
+        records.sort_by_key(|r| r.timestamp);
+        let mut last = previous.map(Reading::from);
+        let mut accepted = Vec::with_capacity(records.len());
+        let mut rejected = Vec::new();
+        for record in records {
+            let reading = Reading::from(&record);
+            let reasons = self.problems(&reading, last.as_ref());
+            if reasons.is_empty() {
+                last = Some(reading);
+                accepted.push(record);
+            } else {
+                rejected.push(Rejected {
+                    timestamp: record.timestamp,
+                    reading: record,
+                    reasons,
+                });
+            }
+        }
+        (accepted, rejected)
+    }
+
+    /// Implausible current and hourly values in a forecast given in `units`.
+    /// A pressure of 0 is taken as not reported, since some providers don't.
+    pub fn check_forecast(
+        &self,
+        forecast: &ForecastResponse,
+        units: Units,
+    ) -> Vec<Rejected<serde_json::Value>> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let speed = |s: f64| units.speed_to_ms(s);
+        let current = forecast.current.iter().map(|c| {
+            let reading = Reading {
+                timestamp: c.timestamp,
+                temperature: units.temperature_to_celsius(c.temperature),
+                humidity: c.humidity.into(),
+                pressure: Some(c.pressure.into()).filter(|p| *p != 0),
+                wind_speed: speed(c.wind_speed),
+                wind_gust: c.wind_gust.map(speed),
+            };
+            (reading, serde_json::to_value(c).unwrap_or_default())
+        });
+        let hourly = forecast.hourly.iter().map(|h| {
+            let reading = Reading {
+                timestamp: h.timestamp,
+                temperature: units.temperature_to_celsius(h.temperature),
+                humidity: h.humidity.into(),
+                pressure: Some(h.pressure.into()).filter(|p| *p != 0),
+                wind_speed: speed(h.wind_speed),
+                wind_gust: h.wind_gust.map(speed),
+            };
+            (reading, serde_json::to_value(h).unwrap_or_default())
+        });
+
+        let mut last: Option<Reading> = None;
+        let mut rejected = Vec::new();
+        for (reading, payload) in current.chain(hourly) {
+            let reasons = self.problems(&reading, last.as_ref());
+            if reasons.is_empty() {
+                last = Some(reading);
+            } else {
+                rejected.push(Rejected {
+                    timestamp: reading.timestamp,
+                    reading: payload,
+                    reasons,
+                });
+            }
+        }
+        rejected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::history_repo::SOURCE_OBSERVATION;
+    use crate::forecast::models::{CurrentWeatherResponse, LocationInfo};
+
+    fn record(timestamp: i64, temperature: f64) -> HistoryRecord {
+        HistoryRecord {
+            city: "Chicago".to_string(),
+            location_key: "41.88,-87.63".to_string(),
+            lat: 41.88,
+            lon: -87.63,
+            timestamp,
+            temperature,
+            feels_like: temperature,
+            humidity: 60,
+            pressure: 1013,
+            wind_speed: 4.0,
+            wind_direction: None,
+            clouds: None,
+            visibility: None,
+            description: None,
+            icon: None,
+            rain_1h: None,
+            snow_1h: None,
+            units: "metric".to_string(),
+            fetched_at: timestamp,
+            wind_gust: None,
+            dew_point: None,
+            uvi: None,
+            condition_id: None,
+            source: SOURCE_OBSERVATION.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_screen_history() {
+        let config = ValidationConfig::default();
+        let mut no_pressure = record(7200, 21.0);
+        no_pressure.pressure = 0;
+        let mut soaked = record(10800, 21.0);
+        soaked.humidity = 140;
+        let records = vec![
+            record(3600, 45.0),
+            record(0, 20.0),
+            no_pressure,
+            soaked,
+            record(14400, 22.0),
+        ];
+
+        let (accepted, rejected) = config.screen_history(records, Some(&record(-3600, 19.0)));
+        let kept: Vec<i64> = accepted.iter().map(|r| r.timestamp).collect();
+        assert_eq!(kept, vec![0, 14400]);
+        let reasons: Vec<&str> = rejected.iter().map(|r| r.reasons[0].as_str()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "temperature changed 25.0°C in 1.0 h (limit 22°C per hour)",
+                "pressure 0 hPa outside 850..1090 hPa",
+                "humidity 140% outside 0..100%",
+            ]
+        );
+
+        let anomaly = rejected.into_iter().next().unwrap().into_anomaly(
+            "41.88,-87.63",
+            "Chicago",
+            SOURCE_OBSERVATION,
+            99,
+        );
+        assert_eq!(anomaly.observed_at, 3600);
+        assert_eq!(anomaly.payload["temperature"], 45.0);
+
+        let disabled = ValidationConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let (accepted, rejected) = disabled.screen_history(vec![record(0, 99.0)], None);
+        assert_eq!((accepted.len(), rejected.len()), (1, 0));
+    }
+
+    #[test]
+    fn test_check_forecast() {
+        let current = CurrentWeatherResponse {
+            timestamp: 0,
+            temperature: 50.0,
+            feels_like: 50.0,
+            humidity: 70,
+            pressure: 0,
+            uv_index: 0.0,
+            clouds: 0,
+            visibility: None,
+            wind_speed: 10.0,
+            wind_direction: 0,
+            wind_gust: None,
+            description: String::new(),
+            icon: String::new(),
+            condition_id: None,
+            condition_main: None,
+            local_time: None,
+            sunrise_local: None,
+            sunset_local: None,
+            sunrise: None,
+            sunset: None,
+        };
+        let mut forecast = ForecastResponse {
+            location: LocationInfo {
+                city: "Chicago".to_string(),
+                country: "US".to_string(),
+                state: None,
+                lat: 41.88,
+                lon: -87.63,
+            },
+            timezone: "America/Chicago".to_string(),
+            current: Some(current.clone()),
+            hourly: Vec::new(),
+            daily: Vec::new(),
+            alerts: Vec::new(),
+        };
+        let config = ValidationConfig::default();
+        // 50°F with pressure unreported is fine
+        assert!(config.check_forecast(&forecast, Units::Imperial).is_empty());
+
+        // 50°C is within range but 101% humidity is not
+        let mut wet = current;
+        wet.humidity = 101;
+        forecast.current = Some(wet);
+        let rejected = config.check_forecast(&forecast, Units::Metric);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].reasons, vec!["humidity 101% outside 0..100%"]);
+        assert_eq!(rejected[0].reading["humidity"], 101);
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::cache::normalize_cache_key;
+use crate::forecast::models::{AlertResponse, ForecastResponse, LocationInfo};
+
+/// Remembers which alerts were active per city on the last forecast run, so
+/// an "all clear" can be sent once an alert expires or is cancelled
+#[derive(Default)]
+pub struct ActiveAlertTracker {
+    cities: Mutex<HashMap<String, TrackedCity>>,
+}
+
+struct TrackedCity {
+    city: String,
+    location: LocationInfo,
+    alerts: HashMap<String, AlertResponse>,
+}
+
+/// An alert that was active for a city and no longer is
+#[derive(Debug, Clone)]
+pub struct EndedAlert {
+    pub city: String,
+    pub location: LocationInfo,
+    pub alert: AlertResponse,
+}
+
+/// Alerts are matched by sender and event; a re-issued alert with a new
+/// end time is the same alert, not a new one
+fn alert_key(alert: &AlertResponse) -> String {
+    format!(
+        "{}|{}",
+        alert.sender.to_lowercase(),
+        alert.event.trim().to_lowercase()
+    )
+}
+
+impl ActiveAlertTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Active alerts in a fresh forecast for `city` that weren't tracked on the
+    /// previous run. Call before `observe`.
+    pub fn started(&self, city: &str, forecast: &ForecastResponse, now: i64) -> Vec<AlertResponse> {
+        let cities = self.cities.lock().unwrap();
+        let tracked = cities.get(&normalize_cache_key(city));
+        forecast
+            .alerts
+            .iter()
+            .filter(|a| a.end == 0 || a.end > now)
+            .filter(|a| tracked.is_none_or(|t| !t.alerts.contains_key(&alert_key(a))))
+            .cloned()
+            .collect()
+    }
+
+    /// Record the alerts in a fresh forecast for `city`. Returns alerts that
+    /// were tracked before but are now missing (cancelled) or past their end.
+    pub fn observe(&self, city: &str, forecast: &ForecastResponse, now: i64) -> Vec<EndedAlert> {
+        let current: HashMap<String, AlertResponse> = forecast
+            .alerts
+            .iter()
+            .filter(|a| a.end == 0 || a.end > now)
+            .map(|a| (alert_key(a), a.clone()))
+            .collect();
+
+        let mut cities = self.cities.lock().unwrap();
+        let key = normalize_cache_key(city);
+        let previous = cities.remove(&key);
+
+        let ended = previous
+            .map(|tracked| {
+                tracked
+                    .alerts
+                    .into_iter()
+                    .filter(|(k, _)| !current.contains_key(k))
+                    .map(|(_, alert)| EndedAlert {
+                        city: tracked.city.clone(),
+                        location: tracked.location.clone(),
+                        alert,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if !current.is_empty() {
+            cities.insert(
+                key,
+                TrackedCity {
+                    city: city.to_string(),
+                    location: forecast.location.clone(),
+                    alerts: current,
+                },
+            );
+        }
+
+        ended
+    }
+
+    /// Remove and return tracked alerts whose end time has passed, so expiry
+    /// is noticed between forecast runs
+    pub fn take_expired(&self, now: i64) -> Vec<EndedAlert> {
+        let mut cities = self.cities.lock().unwrap();
+        let mut ended = Vec::new();
+
+        for tracked in cities.values_mut() {
+            let expired: Vec<String> = tracked
+                .alerts
+                .iter()
+                .filter(|(_, a)| a.end != 0 && a.end <= now)
+                .map(|(k, _)| k.clone())
+                .collect();
+            for key in expired {
+                if let Some(alert) = tracked.alerts.remove(&key) {
+                    ended.push(EndedAlert {
+                        city: tracked.city.clone(),
+                        location: tracked.location.clone(),
+                        alert,
+                    });
+                }
+            }
+        }
+        cities.retain(|_, tracked| !tracked.alerts.is_empty());
+
+        ended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forecast_with(alerts: Vec<AlertResponse>) -> ForecastResponse {
+        ForecastResponse {
+            location: LocationInfo {
+                city: "Chicago".to_string(),
+                country: "US".to_string(),
+                state: None,
+                lat: 41.88,
+                lon: -87.63,
+            },
+            timezone: "America/Chicago".to_string(),
+            current: None,
+            hourly: vec![],
+            daily: vec![],
+            alerts,
+        }
+    }
+
+    fn alert(event: &str, end: i64) -> AlertResponse {
+        AlertResponse::new(
+            "NWS Chicago".to_string(),
+            event.to_string(),
+            1000,
+            end,
+            String::new(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_observe_reports_cancelled_alerts() {
+        let tracker = ActiveAlertTracker::new();
+        let both = forecast_with(vec![
+            alert("Heat Advisory", 5000),
+            alert("Wind Advisory", 5000),
+        ]);
+        assert_eq!(tracker.started("Chicago", &both, 2000).len(), 2);
+        assert!(tracker.observe("Chicago", &both, 2000).is_empty());
+
+        // Re-issued with a later end time: still the same alert
+        let extended = forecast_with(vec![
+            alert("Heat Advisory", 9000),
+            alert("Wind Advisory", 5000),
+        ]);
+        assert!(tracker.started("chicago", &extended, 2500).is_empty());
+        assert!(tracker.observe("chicago", &extended, 2500).is_empty());
+
+        let heat_only = forecast_with(vec![alert("Heat Advisory", 9000)]);
+        let ended = tracker.observe("Chicago", &heat_only, 3000);
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].alert.event, "Wind Advisory");
+        assert_eq!(ended[0].location.city, "Chicago");
+    }
+
+    #[test]
+    fn test_take_expired() {
+        let tracker = ActiveAlertTracker::new();
+        let forecast = forecast_with(vec![
+            alert("Heat Advisory", 5000),
+            alert("Wind Advisory", 8000),
+        ]);
+        tracker.observe("Chicago", &forecast, 2000);
+
+        assert!(tracker.take_expired(4000).is_empty());
+
+        let ended = tracker.take_expired(6000);
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].alert.event, "Heat Advisory");
+
+        // Already reported; a later run without it doesn't repeat the all-clear
+        let remaining = forecast_with(vec![alert("Wind Advisory", 8000)]);
+        assert!(tracker.observe("Chicago", &remaining, 6500).is_empty());
+    }
+}
@@ -0,0 +1,625 @@
+//! Job notify conditions: small boolean expressions over a forecast, such as
+//! `daily[0].pop > 0.6 && daily[0].temp_max < 5`.
+//!
+//! Expressions can only read forecast fields, compare them, and combine the
+//! results with `&&`, `||`, `!`, and parentheses. Fields are named as in the
+//! forecast API response, with `pop`, `uvi`, `temp`, `rain`, and `snow`
+//! accepted as short names. A field the forecast leaves out (no gusts, no
+//! hourly data, day 9 of 8) makes any comparison with it false.
+
+use thiserror::Error;
+
+use crate::forecast::models::ForecastResponse;
+
+/// Fields by forecast section, as serialized
+const CURRENT_FIELDS: &[&str] = &[
+    "timestamp",
+    "temperature",
+    "feels_like",
+    "humidity",
+    "pressure",
+    "uv_index",
+    "clouds",
+    "visibility",
+    "wind_speed",
+    "wind_direction",
+    "wind_gust",
+    "description",
+    "icon",
+    "sunrise",
+    "sunset",
+];
+const HOURLY_FIELDS: &[&str] = &[
+    "timestamp",
+    "temperature",
+    "feels_like",
+    "humidity",
+    "pressure",
+    "uv_index",
+    "clouds",
+    "wind_speed",
+    "wind_direction",
+    "wind_gust",
+    "precipitation_probability",
+    "rain_volume",
+    "snow_volume",
+    "description",
+    "icon",
+];
+const DAILY_FIELDS: &[&str] = &[
+    "timestamp",
+    "sunrise",
+    "sunset",
+    "moonrise",
+    "moonset",
+    "moon_phase",
+    "summary",
+    "temp_min",
+    "temp_max",
+    "temp_day",
+    "temp_night",
+    "temp_morning",
+    "temp_evening",
+    "feels_like_day",
+    "feels_like_night",
+    "humidity",
+    "pressure",
+    "uv_index",
+    "clouds",
+    "wind_speed",
+    "wind_direction",
+    "wind_gust",
+    "precipitation_probability",
+    "rain_volume",
+    "snow_volume",
+    "description",
+    "icon",
+];
+const ALERT_FIELDS: &[&str] = &[
+    "sender",
+    "event",
+    "start",
+    "end",
+    "description",
+    "severity",
+    "urgency",
+    "certainty",
+];
+const LOCATION_FIELDS: &[&str] = &["city", "country", "state", "lat", "lon"];
+
+/// Longest condition accepted, in bytes
+const MAX_CONDITION_LEN: usize = 1024;
+
+/// Deepest nesting of parentheses and `!` accepted. Parsing recurses once per
+/// level, so this keeps hostile input from exhausting the stack.
+const MAX_DEPTH: usize = 32;
+
+/// Short names accepted for response fields
+fn canonical_field(name: &str) -> &str {
+    match name {
+        "pop" => "precipitation_probability",
+        "uvi" => "uv_index",
+        "temp" => "temperature",
+        "rain" => "rain_volume",
+        "snow" => "snow_volume",
+        other => other,
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("{message} at position {position}")]
+pub struct ConditionError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl ConditionError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Dot,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Cmp(CmpOp),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ConditionError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let two = |next: char| chars.get(i + 1) == Some(&next);
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '.' => Token::Dot,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '&' if two('&') => {
+                i += 1;
+                Token::And
+            }
+            '|' if two('|') => {
+                i += 1;
+                Token::Or
+            }
+            '<' | '>' | '=' | '!' if two('=') => {
+                i += 1;
+                Token::Cmp(match c {
+                    '<' => CmpOp::Le,
+                    '>' => CmpOp::Ge,
+                    '=' => CmpOp::Eq,
+                    _ => CmpOp::Ne,
+                })
+            }
+            '<' => Token::Cmp(CmpOp::Lt),
+            '>' => Token::Cmp(CmpOp::Gt),
+            '!' => Token::Not,
+            '"' | '\'' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == c)
+                    .ok_or_else(|| ConditionError::new("unterminated string", start))?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                i += end + 1;
+                Token::Str(text)
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let mut end = i + 1;
+                while end < chars.len() && (chars[end].is_ascii_digit() || chars[end] == '.') {
+                    end += 1;
+                }
+                let text: String = chars[i..end].iter().collect();
+                let value = text
+                    .parse()
+                    .map_err(|_| ConditionError::new(format!("bad number {:?}", text), start))?;
+                i = end - 1;
+                Token::Number(value)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = i + 1;
+                while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_')
+                {
+                    end += 1;
+                }
+                let text: String = chars[i..end].iter().collect();
+                i = end - 1;
+                Token::Ident(text)
+            }
+            other => {
+                return Err(ConditionError::new(
+                    format!("unexpected {:?}", other),
+                    start,
+                ))
+            }
+        };
+        tokens.push((token, start));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+/// One step in a field path
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    Field(Vec<Segment>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(CmpOp, Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, p)| *p)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ConditionError> {
+        let position = self.position();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(ConditionError::new(format!("expected {}", what), position)),
+        }
+    }
+
+    /// Go one level deeper into `!` or parentheses
+    fn descend(&mut self, position: usize) -> Result<(), ConditionError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ConditionError::new(
+                format!("nested more than {} levels deep", MAX_DEPTH),
+                position,
+            ));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr, ConditionError> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, ConditionError> {
+        let mut left = self.not()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, ConditionError> {
+        if self.peek() == Some(&Token::Not) {
+            self.descend(self.position())?;
+            self.pos += 1;
+            let inner = self.not()?;
+            self.depth -= 1;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ConditionError> {
+        let left = self.primary()?;
+        if let Some(Token::Cmp(op)) = self.peek().cloned() {
+            self.pos += 1;
+            let right = self.primary()?;
+            return Ok(Expr::Compare(op, Box::new(left), Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ConditionError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::LParen) => {
+                self.descend(position)?;
+                let inner = self.or()?;
+                self.expect(Token::RParen, "')'")?;
+                self.depth -= 1;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) if name == "true" => Ok(Expr::Bool(true)),
+            Some(Token::Ident(name)) if name == "false" => Ok(Expr::Bool(false)),
+            Some(Token::Ident(name)) => self.field(name, position),
+            _ => Err(ConditionError::new("expected a value or field", position)),
+        }
+    }
+
+    /// A field path starting with `root`, checked against the forecast's fields
+    fn field(&mut self, root: String, position: usize) -> Result<Expr, ConditionError> {
+        let fields = match root.as_str() {
+            "current" => CURRENT_FIELDS,
+            "location" => LOCATION_FIELDS,
+            "hourly" | "daily" | "alerts" => {
+                self.expect(Token::LBracket, &format!("'[' after {}", root))?;
+                let index_position = self.position();
+                let index = match self.next() {
+                    Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => n as usize,
+                    _ => {
+                        return Err(ConditionError::new(
+                            "expected an index like [0]",
+                            index_position,
+                        ))
+                    }
+                };
+                self.expect(Token::RBracket, "']'")?;
+                let fields = match root.as_str() {
+                    "hourly" => HOURLY_FIELDS,
+                    "daily" => DAILY_FIELDS,
+                    _ => ALERT_FIELDS,
+                };
+                let field = self.member(fields)?;
+                return Ok(Expr::Field(vec![
+                    Segment::Field(root),
+                    Segment::Index(index),
+                    Segment::Field(field),
+                ]));
+            }
+            _ => {
+                return Err(ConditionError::new(
+                    format!(
+                    "unknown field {:?} (use current, hourly[n], daily[n], alerts[n], or location)",
+                    root
+                ),
+                    position,
+                ))
+            }
+        };
+        let field = self.member(fields)?;
+        Ok(Expr::Field(vec![
+            Segment::Field(root),
+            Segment::Field(field),
+        ]))
+    }
+
+    /// `.name`, where name must be one of `fields`
+    fn member(&mut self, fields: &[&str]) -> Result<String, ConditionError> {
+        self.expect(Token::Dot, "'.' and a field name")?;
+        let position = self.position();
+        match self.next() {
+            Some(Token::Ident(name)) => {
+                let name = canonical_field(&name);
+                if fields.contains(&name) {
+                    Ok(name.to_string())
+                } else {
+                    Err(ConditionError::new(
+                        format!("unknown field {:?}", name),
+                        position,
+                    ))
+                }
+            }
+            _ => Err(ConditionError::new("expected a field name", position)),
+        }
+    }
+}
+
+/// Values an expression can produce
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    /// A field the forecast doesn't have
+    Missing,
+}
+
+impl Value {
+    fn from_json(value: Option<&serde_json::Value>) -> Self {
+        match value {
+            Some(serde_json::Value::Number(n)) => n.as_f64().map_or(Self::Missing, Self::Number),
+            Some(serde_json::Value::String(s)) => Self::Str(s.clone()),
+            Some(serde_json::Value::Bool(b)) => Self::Bool(*b),
+            _ => Self::Missing,
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        matches!(self, Self::Bool(true))
+    }
+}
+
+fn compare(op: CmpOp, left: &Value, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    let Some(ordering) = ordering else {
+        return false;
+    };
+    match op {
+        CmpOp::Lt => ordering.is_lt(),
+        CmpOp::Le => ordering.is_le(),
+        CmpOp::Gt => ordering.is_gt(),
+        CmpOp::Ge => ordering.is_ge(),
+        CmpOp::Eq => ordering.is_eq(),
+        CmpOp::Ne => ordering.is_ne(),
+    }
+}
+
+fn evaluate(expr: &Expr, forecast: &serde_json::Value) -> Value {
+    match expr {
+        Expr::Number(n) => Value::Number(*n),
+        Expr::Str(s) => Value::Str(s.clone()),
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::Field(path) => {
+            let value = path
+                .iter()
+                .try_fold(forecast, |value, segment| match segment {
+                    Segment::Field(name) => value.get(name),
+                    Segment::Index(i) => value.get(i),
+                });
+            Value::from_json(value)
+        }
+        Expr::Not(inner) => Value::Bool(!evaluate(inner, forecast).truthy()),
+        Expr::And(a, b) => {
+            Value::Bool(evaluate(a, forecast).truthy() && evaluate(b, forecast).truthy())
+        }
+        Expr::Or(a, b) => {
+            Value::Bool(evaluate(a, forecast).truthy() || evaluate(b, forecast).truthy())
+        }
+        Expr::Compare(op, a, b) => {
+            Value::Bool(compare(*op, &evaluate(a, forecast), &evaluate(b, forecast)))
+        }
+    }
+}
+
+/// A parsed notify condition
+#[derive(Debug, Clone)]
+pub struct Condition {
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(input: &str) -> Result<Self, ConditionError> {
+        if input.len() > MAX_CONDITION_LEN {
+            return Err(ConditionError::new(
+                format!("condition is longer than {} bytes", MAX_CONDITION_LEN),
+                0,
+            ));
+        }
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(ConditionError::new("condition is empty", 0));
+        }
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: input.chars().count(),
+            depth: 0,
+        };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(ConditionError::new(
+                "unexpected input after the condition",
+                parser.position(),
+            ));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Whether the condition holds for a forecast
+    pub fn matches(&self, forecast: &ForecastResponse) -> bool {
+        serde_json::to_value(forecast)
+            .map(|json| evaluate(&self.expr, &json).truthy())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forecast() -> ForecastResponse {
+        serde_json::from_value(serde_json::json!({
+            "location": {"city": "Chicago", "country": "US", "lat": 41.88, "lon": -87.63},
+            "timezone": "America/Chicago",
+            "current": null,
+            "hourly": [],
+            "daily": [{
+                "timestamp": 1700000000, "sunrise": 0, "sunset": 0, "moon_phase": 0.5,
+                "temp_min": -2.0, "temp_max": 3.5, "temp_day": 2.0, "temp_night": -1.0,
+                "temp_morning": -1.5, "temp_evening": 1.0, "feels_like_day": 0.0,
+                "feels_like_night": -4.0, "humidity": 80, "pressure": 1010, "uv_index": 1.0,
+                "clouds": 90, "wind_speed": 6.0, "wind_direction": 270,
+                "precipitation_probability": 0.8, "snow_volume": 12.0,
+                "description": "snow", "icon": "13d"
+            }],
+            "alerts": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_condition_matches() {
+        let forecast = forecast();
+        let holds = |input: &str| Condition::parse(input).unwrap().matches(&forecast);
+
+        assert!(holds("daily[0].pop > 0.6 && daily[0].temp_max < 5"));
+        assert!(holds("daily[0].snow >= 10 || current.temperature < -20"));
+        assert!(holds(
+            "!(daily[0].icon == '01d') && location.city == \"Chicago\""
+        ));
+        assert!(!holds("daily[0].temp_min > -2"));
+        // Missing data never matches
+        assert!(!holds("current.temperature < 100"));
+        assert!(!holds("daily[3].pop > 0.1"));
+        assert!(!holds("daily[0].wind_gust > 0"));
+    }
+
+    #[test]
+    fn test_condition_parse_errors() {
+        let error = |input: &str| Condition::parse(input).unwrap_err();
+
+        assert_eq!(error("").message, "condition is empty");
+        assert_eq!(error("daily[0].popp > 1").position, 9);
+        assert!(error("forecast.temp > 1").message.contains("unknown field"));
+        assert!(error("daily.pop > 1").message.contains("'['"));
+        assert!(error("(current.temp > 1").message.contains("')'"));
+        assert!(error("current.temp > 1 current")
+            .message
+            .contains("unexpected"));
+        assert!(error("current.temp # 1").message.contains("unexpected"));
+    }
+
+    #[test]
+    fn test_condition_limits() {
+        let nested = |open: &str, depth: usize| {
+            format!(
+                "{}current.temp > 1{}",
+                open.repeat(depth),
+                if open == "(" {
+                    ")".repeat(depth)
+                } else {
+                    String::new()
+                }
+            )
+        };
+
+        assert!(Condition::parse(&nested("(", MAX_DEPTH)).is_ok());
+        assert!(Condition::parse(&nested("!", MAX_DEPTH)).is_ok());
+        assert!(Condition::parse(&nested("(", MAX_DEPTH + 1))
+            .unwrap_err()
+            .message
+            .contains("nested"));
+        assert!(Condition::parse(&nested("!(", 20))
+            .unwrap_err()
+            .message
+            .contains("nested"));
+
+        let long = "(".repeat(300_000);
+        assert!(Condition::parse(&long)
+            .unwrap_err()
+            .message
+            .contains("longer than"));
+    }
+}
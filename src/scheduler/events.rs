@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+/// Events buffered per subscriber before a slow client starts missing some
+const CHANNEL_CAPACITY: usize = 128;
+
+/// Finished runs are kept this long for `GET /scheduler/status`, and the
+/// most recent one is always kept
+pub const RECENT_RUNS_SECS: i64 = 3600;
+
+/// Cap on finished runs kept, however many finished within the hour
+const MAX_RECENT_RUNS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum SchedulerEventKind {
+    #[serde(rename = "job-started")]
+    Started,
+    #[serde(rename = "job-succeeded")]
+    Succeeded,
+    #[serde(rename = "job-failed")]
+    Failed,
+}
+
+impl SchedulerEventKind {
+    /// SSE event name
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Started => "job-started",
+            Self::Succeeded => "job-succeeded",
+            Self::Failed => "job-failed",
+        }
+    }
+}
+
+/// A scheduled job run or manual trigger starting or finishing
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerEvent {
+    pub kind: SchedulerEventKind,
+    /// Absent for manual triggers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_name: Option<String>,
+    pub city: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Unix time the event happened
+    pub timestamp: i64,
+    /// Run time so far, set on success and failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications_sent: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Broadcast of job run activity for `GET /scheduler/events`, and the runs
+/// in progress and recently finished for `GET /scheduler/status`
+pub struct SchedulerEvents {
+    sender: broadcast::Sender<SchedulerEvent>,
+    next_run: AtomicU64,
+    /// Start events of runs in progress, by run number
+    active: Mutex<BTreeMap<u64, SchedulerEvent>>,
+    /// Outcome events, oldest first
+    recent: Mutex<VecDeque<SchedulerEvent>>,
+}
+
+impl SchedulerEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            next_run: AtomicU64::new(0),
+            active: Mutex::new(BTreeMap::new()),
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Start events of the runs in progress, oldest first
+    pub fn active(&self) -> Vec<SchedulerEvent> {
+        self.active.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Outcome events of runs that finished in the last `RECENT_RUNS_SECS`
+    /// (and the last run, however long ago), oldest first
+    pub fn recent(&self) -> Vec<SchedulerEvent> {
+        self.recent.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn finished(&self, event: SchedulerEvent) {
+        let cutoff = event.timestamp - RECENT_RUNS_SECS;
+        let mut recent = self.recent.lock().unwrap();
+        recent.push_back(event);
+        while recent.len() > MAX_RECENT_RUNS
+            || (recent.len() > 1 && recent.front().is_some_and(|e| e.timestamp < cutoff))
+        {
+            recent.pop_front();
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SchedulerEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Announce a run starting. Report its outcome through the returned run.
+    pub fn start(
+        &self,
+        job_id: Option<&str>,
+        job_name: Option<&str>,
+        city: &str,
+        owner: Option<&str>,
+    ) -> EventRun<'_> {
+        let event = SchedulerEvent {
+            kind: SchedulerEventKind::Started,
+            job_id: job_id.map(str::to_string),
+            job_name: job_name.map(str::to_string),
+            city: city.to_string(),
+            owner: owner.map(str::to_string),
+            timestamp: chrono::Utc::now().timestamp(),
+            duration_ms: None,
+            notifications_sent: None,
+            error: None,
+        };
+        let _ = self.sender.send(event.clone());
+        let run = self.next_run.fetch_add(1, Ordering::Relaxed);
+        self.active.lock().unwrap().insert(run, event.clone());
+        EventRun {
+            events: self,
+            run,
+            event,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Default for SchedulerEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A run announced by `SchedulerEvents::start`. It stops counting as in
+/// progress when dropped, reported or not.
+pub struct EventRun<'a> {
+    events: &'a SchedulerEvents,
+    run: u64,
+    event: SchedulerEvent,
+    started: Instant,
+}
+
+impl Drop for EventRun<'_> {
+    fn drop(&mut self) {
+        self.events.active.lock().unwrap().remove(&self.run);
+    }
+}
+
+impl EventRun<'_> {
+    pub fn succeeded(self, notifications_sent: usize) {
+        self.finish(
+            SchedulerEventKind::Succeeded,
+            Some(notifications_sent),
+            None,
+        );
+    }
+
+    pub fn failed(self, error: String) {
+        self.finish(SchedulerEventKind::Failed, None, Some(error));
+    }
+
+    fn finish(
+        mut self,
+        kind: SchedulerEventKind,
+        notifications_sent: Option<usize>,
+        error: Option<String>,
+    ) {
+        let result = match kind {
+            SchedulerEventKind::Failed => "failure",
+            _ => "success",
+        };
+        metrics::counter!(crate::metrics::SCHEDULER_JOB_RUNS, "result" => result).increment(1);
+        self.event.kind = kind;
+        self.event.timestamp = chrono::Utc::now().timestamp();
+        self.event.duration_ms = Some(self.started.elapsed().as_millis() as u64);
+        self.event.notifications_sent = notifications_sent;
+        self.event.error = error;
+        self.events.finished(self.event.clone());
+        let _ = self.events.sender.send(self.event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_reports_start_and_outcome() {
+        let events = SchedulerEvents::new();
+        let mut rx = events.subscribe();
+
+        events
+            .start(Some("job-1"), Some("Morning"), "Chicago", None)
+            .failed("upstream down".to_string());
+
+        let started = rx.recv().await.unwrap();
+        assert_eq!(started.kind, SchedulerEventKind::Started);
+        assert!(started.duration_ms.is_none());
+
+        let failed = rx.recv().await.unwrap();
+        assert_eq!(failed.kind.as_str(), "job-failed");
+        assert_eq!(failed.job_id.as_deref(), Some("job-1"));
+        assert!(failed.duration_ms.is_some());
+        assert_eq!(failed.error.as_deref(), Some("upstream down"));
+    }
+
+    #[test]
+    fn test_tracks_active_and_recent_runs() {
+        let events = SchedulerEvents::new();
+
+        let first = events.start(Some("job-1"), Some("Morning"), "Chicago", None);
+        let second = events.start(None, None, "Denver", Some("alice"));
+        let active = events.active();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].city, "Chicago");
+
+        first.succeeded(3);
+        assert_eq!(events.active().len(), 1);
+        // A run dropped without an outcome is no longer in progress
+        drop(second);
+        assert!(events.active().is_empty());
+
+        let recent = events.recent();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].kind, SchedulerEventKind::Succeeded);
+        assert_eq!(recent[0].notifications_sent, Some(3));
+
+        // Runs older than the window are dropped, but the last is kept
+        events.recent.lock().unwrap()[0].timestamp -= 2 * RECENT_RUNS_SECS;
+        events
+            .start(None, None, "Chicago", None)
+            .failed("timeout".to_string());
+        let recent = events.recent();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].kind, SchedulerEventKind::Failed);
+    }
+}
@@ -1,40 +1,55 @@
+use std::convert::Infallible;
+
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::IntoResponse,
-    Json,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Extension, Json,
 };
 use serde::{Deserialize, Serialize};
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use super::events::{SchedulerEvent, SchedulerEventKind, RECENT_RUNS_SECS};
 use super::jobs::{ForecastJob, NotifyConfig};
+use crate::audit::{self, AuditAction, AuditActor, AuditResource};
+use crate::auth::Principal;
+use crate::forecast::models::AlertSeverity;
 use crate::AppState;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct JobListResponse {
     pub jobs: Vec<ForecastJob>,
     pub count: usize,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, ToSchema)]
 pub struct TriggerRequest {
     pub city: Option<String>,
     pub units: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TriggerResponse {
     pub status: String,
     pub message: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = SchedulerErrorResponse)]
 pub struct ErrorResponse {
     pub error: String,
 }
 
 /// Request to create a new job
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateJobRequest {
     pub name: String,
@@ -42,9 +57,9 @@ pub struct CreateJobRequest {
     #[serde(default = "default_units")]
     pub units: String,
     pub cron: String,
-    /// IANA timezone (e.g., "America/Chicago"). Defaults to UTC.
-    #[serde(default = "default_timezone")]
-    pub timezone: String,
+    /// IANA timezone (e.g., "America/Chicago"). Defaults to the city's
+    /// timezone, or UTC if it can't be looked up.
+    pub timezone: Option<String>,
     #[serde(default = "default_true")]
     pub include_daily: bool,
     #[serde(default)]
@@ -53,10 +68,20 @@ pub struct CreateJobRequest {
     pub enabled: bool,
     #[serde(default)]
     pub notify: Option<NotifyConfigRequest>,
+    /// Deliver at the cron time in each recipient device's timezone
+    #[serde(default)]
+    pub device_local_time: bool,
+    /// Notification title, with {name}, {city}, {country}, and {emoji}
+    /// filled in
+    pub title_template: Option<String>,
+    /// Line put before the briefing body
+    pub body_prefix: Option<String>,
+    /// Line put at the end of the briefing, ahead of any alerts
+    pub body_suffix: Option<String>,
 }
 
 /// Request to update a job
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateJobRequest {
     pub name: Option<String>,
@@ -69,9 +94,16 @@ pub struct UpdateJobRequest {
     pub include_hourly: Option<bool>,
     pub enabled: Option<bool>,
     pub notify: Option<NotifyConfigRequest>,
+    pub device_local_time: Option<bool>,
+    /// An empty string clears the title template
+    pub title_template: Option<String>,
+    /// An empty string clears the prefix
+    pub body_prefix: Option<String>,
+    /// An empty string clears the suffix
+    pub body_suffix: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct NotifyConfigRequest {
     pub on_run: Option<bool>,
@@ -79,22 +111,28 @@ pub struct NotifyConfigRequest {
     pub on_precipitation: Option<bool>,
     pub cold_threshold: Option<f64>,
     pub heat_threshold: Option<f64>,
+    pub wind_threshold: Option<f64>,
+    pub gust_threshold: Option<f64>,
+    pub snow_threshold_mm: Option<f64>,
+    pub snow_window_hours: Option<u32>,
+    /// Expression over the forecast; an empty string removes it
+    pub condition: Option<String>,
+    pub min_severity: Option<AlertSeverity>,
+    pub compare_yesterday: Option<bool>,
+    pub include_clothing: Option<bool>,
+    pub moon_events: Option<bool>,
 }
 
 fn default_units() -> String {
     "metric".to_string()
 }
 
-fn default_timezone() -> String {
-    "UTC".to_string()
-}
-
 fn default_true() -> bool {
     true
 }
 
 /// Response for job operations
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JobResponse {
     pub success: bool,
@@ -104,33 +142,98 @@ pub struct JobResponse {
     pub message: Option<String>,
 }
 
-/// List all scheduled jobs
+/// User to restrict the request to; `None` when auth is disabled or the
+/// caller is an admin
+fn owner_filter(principal: &Option<Extension<Principal>>) -> Option<String> {
+    principal.as_ref().and_then(|Extension(p)| p.owner_filter())
+}
+
+/// Whether the caller may see and change a job. Other users' jobs are
+/// reported as not found.
+fn can_access(principal: &Option<Extension<Principal>>, job: &ForecastJob) -> bool {
+    principal
+        .as_ref()
+        .is_none_or(|Extension(p)| p.can_access(job.owner.as_deref()))
+}
+
+/// An updated optional text field: unchanged when absent, cleared when blank
+fn update_text(new: Option<String>, existing: Option<String>) -> Option<String> {
+    match new {
+        Some(text) if text.trim().is_empty() => None,
+        Some(text) => Some(text),
+        None => existing,
+    }
+}
+
+fn job_not_found(id: &str) -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(JobResponse {
+            success: false,
+            job: None,
+            message: Some(format!("Job not found: {}", id)),
+        }),
+    )
+        .into_response()
+}
+
+/// List scheduled jobs (the caller's own, for non-admins)
 /// GET /scheduler/jobs
-pub async fn list_jobs(State(state): State<AppState>) -> Json<JobListResponse> {
-    let jobs = state.scheduler_service.get_jobs().await;
+#[utoipa::path(
+    get,
+    path = "/api/v1/scheduler/jobs",
+    tag = "scheduler",
+    responses((status = 200, description = "Jobs visible to the caller", body = JobListResponse)),
+    security(("bearer" = []))
+)]
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> Json<JobListResponse> {
+    let mut jobs = state.scheduler_service.get_jobs().await;
+    jobs.retain(|job| can_access(&principal, job));
     Json(JobListResponse {
         count: jobs.len(),
         jobs,
     })
 }
 
-/// Trigger a manual forecast with notification
+/// Trigger a manual forecast with notification. Non-admin users only notify
+/// their own devices.
 /// POST /scheduler/trigger
-pub async fn trigger_forecast(State(state): State<AppState>, body: String) -> impl IntoResponse {
+#[utoipa::path(
+    post,
+    path = "/api/v1/scheduler/trigger",
+    tag = "scheduler",
+    request_body(content = Option<TriggerRequest>, description = "City and units; both default from config"),
+    responses(
+        (status = 200, description = "Forecast fetched and notifications sent", body = TriggerResponse),
+        (status = 500, description = "Forecast or delivery failed", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn trigger_forecast(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    body: String,
+) -> impl IntoResponse {
     let request: TriggerRequest = serde_json::from_str(&body).unwrap_or_default();
     let units = request.units.unwrap_or_else(|| state.config.units.clone());
+    let owner = owner_filter(&principal);
 
-    // Resolve city: use provided city, or fall back to first enabled device's primary city
+    // Resolve city: use provided city, or fall back to the first enabled device's
+    // reported location, then its primary city
     let city = if let Some(city) = request.city {
         city
     } else {
         let devices = state.devices_service.get_all().await;
         match devices
             .iter()
+            .filter(|d| owner.is_none() || d.owner == owner)
             .find(|d| d.enabled)
-            .and_then(|d| d.cities.first())
+            .and_then(|d| d.location_query().or_else(|| d.cities.first().cloned()))
         {
-            Some(c) => c.clone(),
+            Some(c) => c,
             None => {
                 return (
                     StatusCode::BAD_REQUEST,
@@ -143,7 +246,11 @@ pub async fn trigger_forecast(State(state): State<AppState>, body: String) -> im
         }
     };
 
-    if let Err(e) = state.scheduler_service.run_now(&city, &units).await {
+    if let Err(e) = state
+        .scheduler_service
+        .run_now(&city, &units, owner.as_deref())
+        .await
+    {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -165,13 +272,30 @@ pub async fn trigger_forecast(State(state): State<AppState>, body: String) -> im
 
 /// Trigger forecast for a specific city via path
 /// POST /scheduler/trigger/{city}
+#[utoipa::path(
+    post,
+    path = "/api/v1/scheduler/trigger/{city}",
+    tag = "scheduler",
+    params(("city" = String, Path, description = "City name")),
+    responses(
+        (status = 200, description = "Forecast fetched and notifications sent", body = TriggerResponse),
+        (status = 500, description = "Forecast or delivery failed", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
 pub async fn trigger_forecast_by_city(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(city): Path<String>,
 ) -> impl IntoResponse {
     let units = &state.config.units;
+    let owner = owner_filter(&principal);
 
-    if let Err(e) = state.scheduler_service.run_now(&city, units).await {
+    if let Err(e) = state
+        .scheduler_service
+        .run_now(&city, units, owner.as_deref())
+        .await
+    {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -191,29 +315,183 @@ pub async fn trigger_forecast_by_city(
         .into_response()
 }
 
-/// Get scheduler status
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AlertAckResponse {
+    pub id: String,
+    pub acknowledged: bool,
+}
+
+/// Acknowledge an alert so scheduled runs stop notifying it to anyone (admin only)
+/// POST /alerts/{id}/ack
+#[utoipa::path(
+    post,
+    path = "/api/v1/alerts/{id}/ack",
+    tag = "scheduler",
+    params(("id" = String, Path, description = "Alert id, as listed by GET /alerts/{city}")),
+    responses(
+        (status = 200, description = "Alert acknowledged", body = AlertAckResponse),
+        (status = 403, description = "Admin only", body = ErrorResponse),
+        (status = 404, description = "Alert has not been notified", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn acknowledge_alert(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.scheduler_service.acknowledge_alert(&id).await {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(AlertAckResponse {
+                id,
+                acknowledged: true,
+            }),
+        )
+            .into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Alert not found: {}", id),
+            }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to acknowledge alert");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Get scheduler status: whether it is running and ticking, runs in
+/// progress, the last run, and recent failures (the caller's own runs, for
+/// non-admins)
 /// GET /scheduler/status
-pub async fn scheduler_status(State(state): State<AppState>) -> Json<SchedulerStatus> {
-    let jobs = state.scheduler_service.get_jobs().await;
+#[utoipa::path(
+    get,
+    path = "/api/v1/scheduler/status",
+    tag = "scheduler",
+    responses((status = 200, description = "Scheduler status", body = SchedulerStatus)),
+    security(("bearer" = []))
+)]
+pub async fn scheduler_status(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> Json<SchedulerStatus> {
+    let scheduler = &state.scheduler_service;
+    let jobs = scheduler.get_jobs().await;
     let device_count = state.devices_service.count().await;
+
+    let owner = owner_filter(&principal);
+    let visible = |event: &SchedulerEvent| owner.is_none() || event.owner == owner;
+    let now = chrono::Utc::now().timestamp();
+    let recent: Vec<SchedulerEvent> = scheduler
+        .recent_runs()
+        .into_iter()
+        .filter(visible)
+        .collect();
+    let errors_last_hour = recent
+        .iter()
+        .filter(|e| e.kind == SchedulerEventKind::Failed && now - e.timestamp <= RECENT_RUNS_SECS)
+        .count();
+
     Json(SchedulerStatus {
-        running: true,
+        running: scheduler.is_running(),
+        ticking: scheduler.is_ticking(now),
+        started_at: scheduler.started_at(),
+        last_tick: scheduler.last_tick(),
         job_count: jobs.len(),
         device_count,
+        running_jobs: scheduler
+            .active_runs()
+            .into_iter()
+            .filter(visible)
+            .collect(),
+        last_run: recent.into_iter().last(),
+        errors_last_hour,
     })
 }
 
-#[derive(Debug, Serialize)]
+/// Stream job-started, job-succeeded, and job-failed events as they happen
+/// (the caller's own jobs, for non-admins). A `lagged` event carries the
+/// number of events a slow client missed.
+/// GET /scheduler/events
+#[utoipa::path(
+    get,
+    path = "/api/v1/scheduler/events",
+    tag = "scheduler",
+    responses((
+        status = 200,
+        description = "Server-Sent Events named job-started, job-succeeded, job-failed, or lagged",
+        content_type = "text/event-stream",
+        body = SchedulerEvent
+    )),
+    security(("bearer" = []))
+)]
+pub async fn scheduler_events(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let owner = owner_filter(&principal);
+    let stream =
+        BroadcastStream::new(state.scheduler_service.subscribe_events()).filter_map(move |event| {
+            match event {
+                Ok(event) if owner.is_none() || event.owner == owner => Event::default()
+                    .event(event.kind.as_str())
+                    .json_data(&event)
+                    .ok()
+                    .map(Ok),
+                Ok(_) => None,
+                Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Ok(Event::default()
+                    .event("lagged")
+                    .data(missed.to_string()))),
+            }
+        });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SchedulerStatus {
+    /// Started and not shutting down
     pub running: bool,
+    /// Whether the once-a-minute heartbeat job ran in the last two minutes
+    pub ticking: bool,
+    /// Unix time the scheduler started
+    pub started_at: Option<i64>,
+    /// Unix time of the last heartbeat
+    pub last_tick: Option<i64>,
     pub job_count: usize,
     pub device_count: usize,
+    /// Job runs and manual triggers in progress (their start events)
+    pub running_jobs: Vec<SchedulerEvent>,
+    /// Outcome of the most recent run to finish
+    pub last_run: Option<SchedulerEvent>,
+    /// Runs that failed in the last hour
+    pub errors_last_hour: usize,
 }
 
-/// Create a new scheduled job
+/// Create a new scheduled job, owned by the caller when signed in
 /// POST /scheduler/jobs
+#[utoipa::path(
+    post,
+    path = "/api/v1/scheduler/jobs",
+    tag = "scheduler",
+    request_body = CreateJobRequest,
+    responses(
+        (status = 201, description = "Job created", body = JobResponse),
+        (status = 400, description = "Invalid cron expression, timezone, or title template", body = JobResponse)
+    ),
+    security(("bearer" = []))
+)]
 pub async fn create_job(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
     Json(request): Json<CreateJobRequest>,
 ) -> impl IntoResponse {
     let notify_config = request
@@ -224,32 +502,68 @@ pub async fn create_job(
             on_precipitation: n.on_precipitation.unwrap_or(false),
             cold_threshold: n.cold_threshold,
             heat_threshold: n.heat_threshold,
+            wind_threshold: n.wind_threshold,
+            gust_threshold: n.gust_threshold,
+            snow_threshold_mm: n.snow_threshold_mm,
+            snow_window_hours: n.snow_window_hours,
+            condition: n.condition.filter(|c| !c.trim().is_empty()),
+            min_severity: n.min_severity,
+            compare_yesterday: n.compare_yesterday.unwrap_or(false),
+            include_clothing: n.include_clothing.unwrap_or(false),
+            moon_events: n.moon_events.unwrap_or(false),
         })
         .unwrap_or_default();
 
+    let timezone = match request.timezone {
+        Some(timezone) => timezone,
+        None => {
+            state
+                .scheduler_service
+                .city_timezone(&request.city, &request.units)
+                .await
+        }
+    };
+
     let job = ForecastJob {
         id: Uuid::new_v4().to_string(),
         name: request.name,
         city: request.city,
         units: request.units,
         cron: request.cron,
-        timezone: request.timezone,
+        timezone,
         include_daily: request.include_daily,
         include_hourly: request.include_hourly,
         enabled: request.enabled,
         notify: notify_config,
+        owner: principal.map(|Extension(p)| p.username),
+        device_local_time: request.device_local_time,
+        title_template: request.title_template.filter(|t| !t.trim().is_empty()),
+        body_prefix: request.body_prefix.filter(|t| !t.trim().is_empty()),
+        body_suffix: request.body_suffix.filter(|t| !t.trim().is_empty()),
     };
 
     match state.scheduler_service.create_job(job).await {
-        Ok(created) => (
-            StatusCode::CREATED,
-            Json(JobResponse {
-                success: true,
-                job: Some(created),
-                message: None,
-            }),
-        )
-            .into_response(),
+        Ok(created) => {
+            state
+                .audit
+                .record(
+                    &actor,
+                    AuditAction::Create,
+                    AuditResource::Job,
+                    &created.id,
+                    audit::snapshot(&created),
+                )
+                .await;
+            (
+                StatusCode::CREATED,
+                Json(JobResponse {
+                    success: true,
+                    job: Some(created),
+                    message: None,
+                }),
+            )
+                .into_response()
+        }
         Err(e) => {
             tracing::error!(error = %e, "Failed to create job");
             (
@@ -267,9 +581,24 @@ pub async fn create_job(
 
 /// Get a job by ID
 /// GET /scheduler/jobs/{id}
-pub async fn get_job(State(state): State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
+#[utoipa::path(
+    get,
+    path = "/api/v1/scheduler/jobs/{id}",
+    tag = "scheduler",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "The job", body = JobResponse),
+        (status = 404, description = "Job not found", body = JobResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_job(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
     match state.scheduler_service.get_job(&id).await {
-        Some(job) => (
+        Some(job) if can_access(&principal, &job) => (
             StatusCode::OK,
             Json(JobResponse {
                 success: true,
@@ -278,40 +607,150 @@ pub async fn get_job(State(state): State<AppState>, Path(id): Path<String>) -> i
             }),
         )
             .into_response(),
-        None => (
-            StatusCode::NOT_FOUND,
+        _ => job_not_found(&id),
+    }
+}
+
+/// Default number of days a schedule simulation covers
+const DEFAULT_SIMULATION_DAYS: i64 = 7;
+
+/// Most days a schedule simulation covers
+const MAX_SIMULATION_DAYS: i64 = 31;
+
+/// Most run times a schedule simulation lists
+const MAX_SIMULATED_RUNS: usize = 1000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SimulateQuery {
+    /// Days ahead to cover, 1-31 (default 7)
+    pub days: Option<i64>,
+}
+
+/// One time a job would fire
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SimulatedRun {
+    pub timestamp: i64,
+    /// RFC 3339 in the job's timezone, with the UTC offset in effect
+    pub local: String,
+}
+
+/// When a job would fire over the coming days
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResponse {
+    pub job_id: String,
+    pub cron: String,
+    pub timezone: String,
+    pub days: i64,
+    pub runs: Vec<SimulatedRun>,
+    /// More runs fall in the period than are listed
+    pub truncated: bool,
+}
+
+/// List every time a job would fire in the next N days, in its timezone,
+/// to check a cron expression across DST changes before relying on it
+/// GET /scheduler/jobs/{id}/simulate?days=7
+#[utoipa::path(
+    get,
+    path = "/api/v1/scheduler/jobs/{id}/simulate",
+    tag = "scheduler",
+    params(("id" = String, Path, description = "Job ID"), SimulateQuery),
+    responses(
+        (status = 200, description = "Upcoming run times", body = SimulationResponse),
+        (status = 400, description = "Invalid days, cron expression, or timezone", body = JobResponse),
+        (status = 404, description = "Job not found", body = JobResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn simulate_job(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(query): Query<SimulateQuery>,
+) -> impl IntoResponse {
+    let Some(job) = state
+        .scheduler_service
+        .get_job(&id)
+        .await
+        .filter(|job| can_access(&principal, job))
+    else {
+        return job_not_found(&id);
+    };
+
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
             Json(JobResponse {
                 success: false,
                 job: None,
-                message: Some(format!("Job not found: {}", id)),
+                message: Some(message),
             }),
         )
-            .into_response(),
+            .into_response()
+    };
+
+    let days = query.days.unwrap_or(DEFAULT_SIMULATION_DAYS);
+    if !(1..=MAX_SIMULATION_DAYS).contains(&days) {
+        return bad_request(format!(
+            "days must be between 1 and {}",
+            MAX_SIMULATION_DAYS
+        ));
     }
+
+    // One extra to tell whether the list was cut short
+    let mut times = match job.fire_times(chrono::Utc::now(), days, MAX_SIMULATED_RUNS + 1) {
+        Ok(times) => times,
+        Err(e) => return bad_request(e.to_string()),
+    };
+    let truncated = times.len() > MAX_SIMULATED_RUNS;
+    times.truncate(MAX_SIMULATED_RUNS);
+
+    Json(SimulationResponse {
+        job_id: job.id,
+        cron: job.cron,
+        timezone: job.timezone,
+        days,
+        runs: times
+            .iter()
+            .map(|time| SimulatedRun {
+                timestamp: time.timestamp(),
+                local: time.to_rfc3339(),
+            })
+            .collect(),
+        truncated,
+    })
+    .into_response()
 }
 
 /// Update a job
 /// PUT /scheduler/jobs/{id}
+#[utoipa::path(
+    put,
+    path = "/api/v1/scheduler/jobs/{id}",
+    tag = "scheduler",
+    params(("id" = String, Path, description = "Job ID")),
+    request_body = UpdateJobRequest,
+    responses(
+        (status = 200, description = "Job updated", body = JobResponse),
+        (status = 400, description = "Invalid cron expression, timezone, or title template", body = JobResponse),
+        (status = 404, description = "Job not found", body = JobResponse)
+    ),
+    security(("bearer" = []))
+)]
 pub async fn update_job(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
     Path(id): Path<String>,
     Json(request): Json<UpdateJobRequest>,
 ) -> impl IntoResponse {
     // Get existing job first
     let existing = match state.scheduler_service.get_job(&id).await {
-        Some(job) => job,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(JobResponse {
-                    success: false,
-                    job: None,
-                    message: Some(format!("Job not found: {}", id)),
-                }),
-            )
-                .into_response();
-        }
+        Some(job) if can_access(&principal, &job) => job,
+        _ => return job_not_found(&id),
     };
+    let before = existing.clone();
 
     // Merge updates
     let notify_config = if let Some(n) = request.notify {
@@ -323,6 +762,23 @@ pub async fn update_job(
                 .unwrap_or(existing.notify.on_precipitation),
             cold_threshold: n.cold_threshold.or(existing.notify.cold_threshold),
             heat_threshold: n.heat_threshold.or(existing.notify.heat_threshold),
+            wind_threshold: n.wind_threshold.or(existing.notify.wind_threshold),
+            gust_threshold: n.gust_threshold.or(existing.notify.gust_threshold),
+            snow_threshold_mm: n.snow_threshold_mm.or(existing.notify.snow_threshold_mm),
+            snow_window_hours: n.snow_window_hours.or(existing.notify.snow_window_hours),
+            condition: match n.condition {
+                Some(c) if c.trim().is_empty() => None,
+                Some(c) => Some(c),
+                None => existing.notify.condition.clone(),
+            },
+            min_severity: n.min_severity.or(existing.notify.min_severity),
+            compare_yesterday: n
+                .compare_yesterday
+                .unwrap_or(existing.notify.compare_yesterday),
+            include_clothing: n
+                .include_clothing
+                .unwrap_or(existing.notify.include_clothing),
+            moon_events: n.moon_events.unwrap_or(existing.notify.moon_events),
         }
     } else {
         existing.notify.clone()
@@ -339,18 +795,37 @@ pub async fn update_job(
         include_hourly: request.include_hourly.unwrap_or(existing.include_hourly),
         enabled: request.enabled.unwrap_or(existing.enabled),
         notify: notify_config,
+        owner: existing.owner,
+        device_local_time: request
+            .device_local_time
+            .unwrap_or(existing.device_local_time),
+        title_template: update_text(request.title_template, existing.title_template),
+        body_prefix: update_text(request.body_prefix, existing.body_prefix),
+        body_suffix: update_text(request.body_suffix, existing.body_suffix),
     };
 
     match state.scheduler_service.update_job(updated_job).await {
-        Ok(job) => (
-            StatusCode::OK,
-            Json(JobResponse {
-                success: true,
-                job: Some(job),
-                message: None,
-            }),
-        )
-            .into_response(),
+        Ok(job) => {
+            state
+                .audit
+                .record(
+                    &actor,
+                    AuditAction::Update,
+                    AuditResource::Job,
+                    &job.id,
+                    audit::changes(&before, &job),
+                )
+                .await;
+            (
+                StatusCode::OK,
+                Json(JobResponse {
+                    success: true,
+                    job: Some(job),
+                    message: None,
+                }),
+            )
+                .into_response()
+        }
         Err(e) => {
             tracing::error!(error = %e, "Failed to update job");
             (
@@ -368,29 +843,55 @@ pub async fn update_job(
 
 /// Delete a job
 /// DELETE /scheduler/jobs/{id}
+#[utoipa::path(
+    delete,
+    path = "/api/v1/scheduler/jobs/{id}",
+    tag = "scheduler",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Job deleted", body = JobResponse),
+        (status = 404, description = "Job not found", body = JobResponse)
+    ),
+    security(("bearer" = []))
+)]
 pub async fn delete_job(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
+    let Some(job) = state
+        .scheduler_service
+        .get_job(&id)
+        .await
+        .filter(|job| can_access(&principal, job))
+    else {
+        return job_not_found(&id);
+    };
+
     match state.scheduler_service.delete_job(&id).await {
-        Ok(true) => (
-            StatusCode::OK,
-            Json(JobResponse {
-                success: true,
-                job: None,
-                message: Some("Job deleted".to_string()),
-            }),
-        )
-            .into_response(),
-        Ok(false) => (
-            StatusCode::NOT_FOUND,
-            Json(JobResponse {
-                success: false,
-                job: None,
-                message: Some(format!("Job not found: {}", id)),
-            }),
-        )
-            .into_response(),
+        Ok(true) => {
+            state
+                .audit
+                .record(
+                    &actor,
+                    AuditAction::Delete,
+                    AuditResource::Job,
+                    &id,
+                    audit::snapshot(&job),
+                )
+                .await;
+            (
+                StatusCode::OK,
+                Json(JobResponse {
+                    success: true,
+                    job: None,
+                    message: Some("Job deleted".to_string()),
+                }),
+            )
+                .into_response()
+        }
+        Ok(false) => job_not_found(&id),
         Err(e) => {
             tracing::error!(error = %e, "Failed to delete job");
             (
@@ -1,8 +1,15 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use croner::parser::{CronParser, Seconds};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+use super::SchedulerError;
+use crate::forecast::models::AlertSeverity;
+
 /// Configuration for a scheduled forecast job
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ForecastJob {
     /// Unique job identifier
@@ -31,9 +38,28 @@ pub struct ForecastJob {
     /// Notification settings
     #[serde(default)]
     pub notify: NotifyConfig,
+    /// User who created the job (None = shared, managed by admins). Owned jobs
+    /// only notify the owner's devices.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Fire at the cron time in each recipient device's own timezone rather
+    /// than once in `timezone`. Devices without a timezone follow `timezone`.
+    #[serde(default)]
+    pub device_local_time: bool,
+    /// Notification title, with {name}, {city}, {country}, and {emoji}
+    /// filled in. Defaults to "City, Country", after the condition emoji if
+    /// those are turned on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_template: Option<String>,
+    /// Line put before the briefing body (e.g. "🏫 School-run forecast")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_prefix: Option<String>,
+    /// Line put at the end of the briefing, ahead of any alerts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_suffix: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct NotifyConfig {
     /// Send notification on every run
@@ -49,6 +75,37 @@ pub struct NotifyConfig {
     pub cold_threshold: Option<f64>,
     /// Temperature threshold for heat alerts (send if above)
     pub heat_threshold: Option<f64>,
+    /// Wind speed threshold (send if above now or within 24 hours), in the
+    /// job's units: m/s, or mph for imperial
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wind_threshold: Option<f64>,
+    /// Wind gust threshold, like `wind_threshold`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gust_threshold: Option<f64>,
+    /// Expected snowfall threshold in mm (send if more is forecast within
+    /// `snow_window_hours`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snow_threshold_mm: Option<f64>,
+    /// Hours ahead snowfall is summed over, up to 48 (default: 24)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snow_window_hours: Option<u32>,
+    /// Expression over the forecast (send if true), e.g.
+    /// `daily[0].pop > 0.6 && daily[0].temp_max < 5`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// Ignore alerts below this normalized severity (unknown severities always count)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<AlertSeverity>,
+    /// Start briefings with how today's forecast high compares to yesterday's
+    /// recorded high ("7° colder than yesterday"). Needs stored history.
+    #[serde(default)]
+    pub compare_yesterday: bool,
+    /// End briefings with clothing suggestions from `recommendations.clothing`
+    #[serde(default)]
+    pub include_clothing: bool,
+    /// Send on full and new moon days, with the moonrise time
+    #[serde(default)]
+    pub moon_events: bool,
 }
 
 fn default_units() -> String {
@@ -76,6 +133,11 @@ impl ForecastJob {
             include_hourly: false,
             enabled: true,
             notify: NotifyConfig::default(),
+            owner: None,
+            device_local_time: false,
+            title_template: None,
+            body_prefix: None,
+            body_suffix: None,
         }
     }
 
@@ -83,6 +145,40 @@ impl ForecastJob {
         self.timezone = timezone.to_string();
         self
     }
+
+    /// When the job's schedule fires in its timezone over the `days` days
+    /// after `from`, at most `limit` times
+    pub fn fire_times(
+        &self,
+        from: DateTime<Utc>,
+        days: i64,
+        limit: usize,
+    ) -> Result<Vec<DateTime<Tz>>, SchedulerError> {
+        let timezone: Tz = self
+            .timezone
+            .parse()
+            .map_err(|_| SchedulerError::InvalidTimezone(self.timezone.clone()))?;
+        // Parsed the way the job scheduler parses it
+        let cron = CronParser::builder()
+            .seconds(Seconds::Required)
+            .dom_and_dow(true)
+            .build()
+            .parse(&self.cron)
+            .map_err(|e| SchedulerError::InvalidCron(e.to_string()))?;
+
+        let until = from + Duration::days(days);
+        Ok(cron
+            .iter_after(from.with_timezone(&timezone))
+            .take_while(|time| *time <= until)
+            .take(limit)
+            .collect())
+    }
+}
+
+/// Whether `cron` is a schedule the job scheduler accepts
+/// (six fields, seconds first: "0 30 5 * * *")
+pub fn is_valid_cron(cron: &str) -> bool {
+    tokio_cron_scheduler::Job::new_async(cron, |_, _| Box::pin(async {})).is_ok()
 }
 
 /// Full job configuration from config file
@@ -92,3 +188,60 @@ pub struct JobConfig {
     #[serde(default)]
     pub jobs: Vec<ForecastJob>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_fire_times_follow_dst() {
+        let local = |job: &ForecastJob, from: &str, days: i64| -> Vec<String> {
+            job.fire_times(utc(from), days, 10)
+                .unwrap()
+                .iter()
+                .map(|t| t.to_rfc3339())
+                .collect()
+        };
+
+        // 2:30 doesn't exist on the spring-forward day; the job fires at 3:00
+        let gap =
+            ForecastJob::new("Gap", "Chicago", "0 30 2 * * *").with_timezone("America/Chicago");
+        assert_eq!(
+            local(&gap, "2024-03-09T00:00:00Z", 3),
+            vec![
+                "2024-03-09T02:30:00-06:00",
+                "2024-03-10T03:00:00-05:00",
+                "2024-03-11T02:30:00-05:00"
+            ]
+        );
+
+        // 1:30 happens twice on the fall-back day; the job fires once
+        let overlap =
+            ForecastJob::new("Overlap", "Chicago", "0 30 1 * * *").with_timezone("America/Chicago");
+        assert_eq!(
+            local(&overlap, "2024-11-02T00:00:00Z", 3),
+            vec![
+                "2024-11-02T01:30:00-05:00",
+                "2024-11-03T01:30:00-05:00",
+                "2024-11-04T01:30:00-06:00"
+            ]
+        );
+
+        let hourly = ForecastJob::new("Hourly", "Chicago", "0 0 * * * *");
+        assert_eq!(
+            hourly
+                .fire_times(utc("2024-11-02T00:00:00Z"), 7, 5)
+                .unwrap()
+                .len(),
+            5
+        );
+        let invalid = ForecastJob::new("Morning", "Chicago", "every morning");
+        assert!(invalid.fire_times(Utc::now(), 1, 1).is_err());
+    }
+}
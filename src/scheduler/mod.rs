@@ -1,6 +1,12 @@
+mod alert_tracker;
+mod condition;
+pub mod events;
 pub mod handlers;
 pub mod jobs;
 mod service;
 
-pub use jobs::{ForecastJob, JobConfig, NotifyConfig};
-pub use service::{SchedulerError, SchedulerService};
+pub use condition::Condition;
+pub use jobs::{is_valid_cron, ForecastJob, JobConfig, NotifyConfig};
+pub use service::{
+    start_alert_expiry_task, start_device_zone_refresh_task, SchedulerError, SchedulerService,
+};
@@ -1,17 +1,68 @@
 use anyhow::Result;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use tokio_cron_scheduler::{Job, JobBuilder, JobScheduler};
 use uuid::Uuid;
 
-use crate::db::{DbError, JobRepository, SqliteJobRepository};
-use crate::devices::DevicesService;
-use crate::forecast::ForecastService;
-use crate::notifications::{NotificationMessage, Priority};
-
-use super::jobs::{ForecastJob, JobConfig};
+use crate::config::{ClothingRule, ConditionStylesConfig, ValidationConfig};
+use crate::db::history_repo::{HistoryRepository, SqliteHistoryRepository};
+use crate::db::{
+    AlertHistoryRepository, AlertLedger, AlertRepository, AnomalyRepository, DbError,
+    ForecastIssue, ForecastIssueRepository, JobRepository, SqliteAlertHistoryRepository,
+    SqliteAlertRepository, SqliteAnomalyRepository, SqliteForecastIssueRepository,
+    SqliteJobRepository,
+};
+use crate::devices::{Device, DevicesError, DevicesService};
+use crate::forecast::models::{AlertResponse, ForecastResponse};
+use crate::forecast::{ForecastError, ForecastService, DEFAULT_LANG};
+use crate::geocode::models::make_location_key;
+use crate::history::STORAGE_UNITS;
+use crate::live::{LiveEvent, LiveUpdates};
+use crate::notifications::{
+    template_strings, ConditionGroup, EventType, NotificationMessage, Priority, TemplateStrings,
+};
+use crate::recommendations::clothing_suggestions;
+use crate::sanity::SOURCE_FORECAST;
+use crate::units::Units;
+
+use super::alert_tracker::{ActiveAlertTracker, EndedAlert};
+use super::condition::Condition;
+use super::events::{SchedulerEvent, SchedulerEvents};
+use super::jobs::{is_valid_cron, ForecastJob, JobConfig, NotifyConfig};
+
+/// How often tracked alerts are checked for expiry between forecast runs
+const ALERT_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How often device-local-time jobs pick up devices' timezone changes
+const DEVICE_ZONE_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Hours ahead that wind thresholds look at
+const WIND_LOOKAHEAD_HOURS: usize = 24;
+
+/// Default and longest window snow thresholds sum over; hourly data
+/// covers 48 hours
+const DEFAULT_SNOW_WINDOW_HOURS: u32 = 24;
+const MAX_SNOW_WINDOW_HOURS: u32 = 48;
+
+/// How far a day's moon phase may sit from exactly full or new and still
+/// count; the phase advances about 1/29.5 per day
+const MOON_PHASE_TOLERANCE: f64 = 1.0 / 59.0;
+
+/// Hourly readings yesterday needs before its high is worth comparing against
+const MIN_YESTERDAY_READINGS: usize = 12;
+
+/// The heartbeat job fires every minute; the scheduler counts as ticking
+/// while the last one is at most this old
+const HEARTBEAT_CRON: &str = "0 * * * * *";
+const TICK_STALE_SECS: i64 = 120;
+
+/// What a job's `title_template` may fill in
+const TITLE_PLACEHOLDERS: [&str; 4] = ["{name}", "{city}", "{country}", "{emoji}"];
 
 #[derive(Error, Debug)]
 pub enum SchedulerError {
@@ -24,11 +75,89 @@ pub enum SchedulerError {
     #[error("Invalid timezone: {0}")]
     InvalidTimezone(String),
 
+    #[error("Invalid notify condition: {0}")]
+    InvalidCondition(String),
+
+    #[error(
+        "Invalid title template (placeholders are {{name}}, {{city}}, {{country}}, {{emoji}}): {0}"
+    )]
+    InvalidTitleTemplate(String),
+
     #[error("Database error: {0}")]
     Database(#[from] DbError),
 
     #[error("Scheduler error: {0}")]
     Scheduler(String),
+
+    #[error("Scheduler is shutting down")]
+    ShuttingDown,
+}
+
+/// Tracks job runs in progress so shutdown can wait for them to finish
+#[derive(Default)]
+struct InFlightJobs {
+    count: AtomicUsize,
+    closed: AtomicBool,
+    idle: Notify,
+}
+
+/// Held for the duration of a job run; dropping it marks the run finished
+pub struct JobRunGuard(Arc<InFlightJobs>);
+
+impl Drop for JobRunGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl InFlightJobs {
+    /// Register a run, or `None` once shutdown has started
+    fn enter(self: &Arc<Self>) -> Option<JobRunGuard> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        let guard = JobRunGuard(Arc::clone(self));
+        if self.closed.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Wait until no runs are in progress. Returns false on timeout.
+    async fn wait_idle(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.count.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+/// One timezone a device-local-time job fires in
+#[derive(Debug, Clone)]
+struct DeviceZone {
+    timezone: String,
+    /// The run in the job's own timezone, which also serves devices whose
+    /// timezone has no run of its own
+    fallback: bool,
+    /// Every device timezone the job has a separate run for
+    scheduled: Arc<BTreeSet<String>>,
+}
+
+impl DeviceZone {
+    /// Whether this run delivers to `device`
+    fn serves(&self, device: &Device) -> bool {
+        match device.timezone.as_deref() {
+            Some(tz) if self.scheduled.contains(tz) => tz == self.timezone,
+            _ => self.fallback,
+        }
+    }
 }
 
 /// Service for managing scheduled forecast jobs
@@ -36,10 +165,46 @@ pub struct SchedulerService {
     scheduler: JobScheduler,
     forecast_service: Arc<ForecastService>,
     devices_service: Arc<DevicesService>,
-    /// Maps our job IDs to scheduler's internal UUIDs
-    job_uuids: Arc<RwLock<HashMap<String, Uuid>>>,
+    /// Maps our job IDs to scheduler's internal UUIDs; device-local-time jobs
+    /// have one per timezone
+    job_uuids: Arc<RwLock<HashMap<String, Vec<Uuid>>>>,
+    /// Device timezones each device-local-time job is scheduled in, besides
+    /// its own
+    device_zones: RwLock<HashMap<String, Arc<BTreeSet<String>>>>,
     /// SQLite repository for jobs
     repo: SqliteJobRepository,
+    /// Tags and emoji applied to forecast notifications
+    styles: Arc<ConditionStylesConfig>,
+    /// Alerts active per city as of the last run, for all-clear notifications
+    alert_tracker: Arc<ActiveAlertTracker>,
+    /// Every alert seen by a job run, for GET /history/{city}/alerts
+    alert_history: Arc<SqliteAlertHistoryRepository>,
+    /// Alerts notified per location, so scheduled runs don't repeat them
+    alerts: Arc<SqliteAlertRepository>,
+    /// Job runs in progress, drained on shutdown
+    in_flight: Arc<InFlightJobs>,
+    /// Conditions, alerts, and job results pushed to WebSocket clients
+    live: Arc<LiveUpdates>,
+    /// Job run activity for GET /scheduler/events
+    events: Arc<SchedulerEvents>,
+    /// Stored weather history, for comparing against yesterday. None when the
+    /// history feature is off.
+    history: Option<Arc<SqliteHistoryRepository>>,
+    /// Daily forecasts as issued, for GET /history/{city}/accuracy. None when
+    /// the history feature is off.
+    forecast_issues: Option<Arc<SqliteForecastIssueRepository>>,
+    /// Rules for clothing suggestions in briefings that ask for them
+    clothing: Arc<Vec<ClothingRule>>,
+    /// Sanity checks a forecast must pass before it is sent
+    validation: Arc<ValidationConfig>,
+    /// Forecast values that failed them
+    anomalies: Arc<SqliteAnomalyRepository>,
+    /// Set once the cron scheduler is started, cleared on shutdown
+    running: AtomicBool,
+    /// Unix time the cron scheduler was started (0 = not yet)
+    started_at: AtomicI64,
+    /// Unix time of the last heartbeat job run (0 = none yet)
+    last_tick: Arc<AtomicI64>,
 }
 
 impl SchedulerService {
@@ -47,19 +212,57 @@ impl SchedulerService {
         forecast_service: Arc<ForecastService>,
         devices_service: Arc<DevicesService>,
         pool: sqlx::SqlitePool,
+        styles: ConditionStylesConfig,
+        live: Arc<LiveUpdates>,
     ) -> Result<Self> {
         let scheduler = JobScheduler::new().await?;
-        let repo = SqliteJobRepository::new(pool);
+        let repo = SqliteJobRepository::new(pool.clone());
 
         Ok(Self {
             scheduler,
             forecast_service,
             devices_service,
             job_uuids: Arc::new(RwLock::new(HashMap::new())),
+            device_zones: RwLock::new(HashMap::new()),
             repo,
+            styles: Arc::new(styles),
+            alert_tracker: Arc::new(ActiveAlertTracker::new()),
+            alert_history: Arc::new(SqliteAlertHistoryRepository::new(pool.clone())),
+            alerts: Arc::new(SqliteAlertRepository::new(pool.clone())),
+            in_flight: Arc::new(InFlightJobs::default()),
+            live,
+            events: Arc::new(SchedulerEvents::new()),
+            history: None,
+            forecast_issues: None,
+            clothing: Arc::new(Vec::new()),
+            validation: Arc::new(ValidationConfig::default()),
+            anomalies: Arc::new(SqliteAnomalyRepository::new(pool)),
+            running: AtomicBool::new(false),
+            started_at: AtomicI64::new(0),
+            last_tick: Arc::new(AtomicI64::new(0)),
         })
     }
 
+    /// Read stored weather history so briefings can compare against
+    /// yesterday, and keep issued forecasts to score against it
+    pub fn with_history(mut self, pool: sqlx::SqlitePool) -> Self {
+        self.history = Some(Arc::new(SqliteHistoryRepository::new(pool.clone())));
+        self.forecast_issues = Some(Arc::new(SqliteForecastIssueRepository::new(pool)));
+        self
+    }
+
+    /// Suggest clothing with these rules in briefings that include it
+    pub fn with_clothing_rules(mut self, rules: Vec<ClothingRule>) -> Self {
+        self.clothing = Arc::new(rules);
+        self
+    }
+
+    /// Check forecasts with these settings before sending them
+    pub fn with_validation(mut self, validation: ValidationConfig) -> Self {
+        self.validation = Arc::new(validation);
+        self
+    }
+
     /// Initialize: load persisted jobs from SQLite and schedule them
     pub async fn init(&self) -> Result<(), SchedulerError> {
         // Load all enabled jobs from SQLite
@@ -75,13 +278,91 @@ impl SchedulerService {
         Ok(())
     }
 
-    /// Start the scheduler
+    /// Start the scheduler, with a heartbeat job showing it still ticks
     pub async fn start(&self) -> Result<()> {
         tracing::info!("Starting scheduler");
+        let last_tick = Arc::clone(&self.last_tick);
+        let heartbeat = Job::new_async(HEARTBEAT_CRON, move |_, _| {
+            let last_tick = Arc::clone(&last_tick);
+            Box::pin(async move {
+                last_tick.store(chrono::Utc::now().timestamp(), Ordering::SeqCst);
+            })
+        })?;
+        self.scheduler.add(heartbeat).await?;
         self.scheduler.start().await?;
+
+        let now = chrono::Utc::now().timestamp();
+        self.started_at.store(now, Ordering::SeqCst);
+        self.last_tick.store(now, Ordering::SeqCst);
+        self.running.store(true, Ordering::SeqCst);
         Ok(())
     }
 
+    /// Whether the scheduler has started and is not shutting down
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Unix time the scheduler started, if it has
+    pub fn started_at(&self) -> Option<i64> {
+        Some(self.started_at.load(Ordering::SeqCst)).filter(|&t| t > 0)
+    }
+
+    /// Unix time the heartbeat job last ran (the start time until its first
+    /// run), if the scheduler has started
+    pub fn last_tick(&self) -> Option<i64> {
+        Some(self.last_tick.load(Ordering::SeqCst)).filter(|&t| t > 0)
+    }
+
+    /// Whether the scheduler is running and its heartbeat is recent
+    pub fn is_ticking(&self, now: i64) -> bool {
+        self.is_running() && self.last_tick().is_some_and(|t| now - t <= TICK_STALE_SECS)
+    }
+
+    /// Job runs and manual triggers in progress, oldest first
+    pub fn active_runs(&self) -> Vec<SchedulerEvent> {
+        self.events.active()
+    }
+
+    /// Job runs and manual triggers finished in the last hour (and the last
+    /// one, however long ago), oldest first
+    pub fn recent_runs(&self) -> Vec<SchedulerEvent> {
+        self.events.recent()
+    }
+
+    /// Stop the scheduler: refuse new job runs, wait up to `timeout` for runs
+    /// in progress (including their notification sends), then shut down the
+    /// cron scheduler
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.running.store(false, Ordering::SeqCst);
+        self.in_flight.closed.store(true, Ordering::SeqCst);
+
+        let running = self.in_flight.count.load(Ordering::SeqCst);
+        if running > 0 {
+            tracing::info!(running = running, "Waiting for in-flight scheduled jobs");
+        }
+        if !self.in_flight.wait_idle(timeout).await {
+            tracing::warn!(
+                running = self.in_flight.count.load(Ordering::SeqCst),
+                timeout_secs = timeout.as_secs(),
+                "Timed out waiting for scheduled jobs, shutting down anyway"
+            );
+        }
+
+        let mut scheduler = self.scheduler.clone();
+        if let Err(e) = scheduler.shutdown().await {
+            tracing::error!(error = %e, "Failed to shut down job scheduler");
+        } else {
+            tracing::info!("Scheduler stopped");
+        }
+    }
+
+    /// Mark the start of a job run. Returns `None` once shutdown has started,
+    /// in which case the run should be skipped.
+    pub fn begin_run(&self) -> Option<JobRunGuard> {
+        self.in_flight.enter()
+    }
+
     /// Load jobs from configuration file (merges with stored jobs)
     pub async fn load_jobs(&self, config: &JobConfig) -> Result<()> {
         for job in &config.jobs {
@@ -96,30 +377,118 @@ impl SchedulerService {
         Ok(())
     }
 
-    /// Schedule a job in the cron scheduler (internal)
-    async fn schedule_job(&self, job_config: &ForecastJob) -> Result<Uuid, SchedulerError> {
+    /// Timezones of enabled devices a device-local-time job may reach,
+    /// other than its own
+    async fn device_timezones(&self, job: &ForecastJob) -> BTreeSet<String> {
+        self.devices_service
+            .get_all()
+            .await
+            .into_iter()
+            .filter(|d| d.enabled)
+            .filter(|d| job.owner.is_none() || d.owner == job.owner)
+            .filter_map(|d| d.timezone)
+            .filter(|tz| *tz != job.timezone && tz.parse::<chrono_tz::Tz>().is_ok())
+            .collect()
+    }
+
+    /// Schedule a job in the cron scheduler (internal). A device-local-time
+    /// job gets one cron entry per device timezone, each delivering only to
+    /// devices in that timezone.
+    async fn schedule_job(&self, job_config: &ForecastJob) -> Result<(), SchedulerError> {
+        // Parse the timezone string to chrono_tz::Tz
+        let timezone: chrono_tz::Tz = job_config
+            .timezone
+            .parse()
+            .map_err(|_| SchedulerError::InvalidTimezone(job_config.timezone.clone()))?;
+
+        let mut runs = vec![(timezone, None)];
+        let mut scheduled = None;
+        if job_config.device_local_time {
+            let zones = Arc::new(self.device_timezones(job_config).await);
+            let zone = |timezone: &str, fallback| DeviceZone {
+                timezone: timezone.to_string(),
+                fallback,
+                scheduled: Arc::clone(&zones),
+            };
+            runs[0].1 = Some(zone(&job_config.timezone, true));
+            for tz in zones.iter() {
+                if let Ok(parsed) = tz.parse() {
+                    runs.push((parsed, Some(zone(tz, false))));
+                }
+            }
+            scheduled = Some(Arc::clone(&zones));
+        }
+
+        let mut uuids = Vec::with_capacity(runs.len());
+        let mut result = Ok(());
+        for (timezone, zone) in runs {
+            match self.schedule_run(job_config, timezone, zone).await {
+                Ok(uuid) => uuids.push(uuid),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        // Store mapping, including any runs added before a failure so they
+        // can be unscheduled
+        if !uuids.is_empty() {
+            self.job_uuids
+                .write()
+                .await
+                .insert(job_config.id.clone(), uuids);
+        }
+        if let Some(zones) = scheduled {
+            self.device_zones
+                .write()
+                .await
+                .insert(job_config.id.clone(), zones);
+        }
+        result
+    }
+
+    /// Add one cron entry for a job, firing in `timezone`. With a `zone`,
+    /// only devices it serves are notified, and only the fallback run
+    /// records alerts and forecast issues.
+    async fn schedule_run(
+        &self,
+        job_config: &ForecastJob,
+        timezone: chrono_tz::Tz,
+        zone: Option<DeviceZone>,
+    ) -> Result<Uuid, SchedulerError> {
         let job_id = job_config.id.clone();
         let job_name = job_config.name.clone();
         let city = job_config.city.clone();
         let units = job_config.units.clone();
         let notify_config = job_config.notify.clone();
+        let wording = JobWording::of(job_config);
         let include_daily = job_config.include_daily;
+        let owner = job_config.owner.clone();
 
         let forecast_service = Arc::clone(&self.forecast_service);
         let devices_service = Arc::clone(&self.devices_service);
-
-        // Parse the timezone string to chrono_tz::Tz
-        let timezone: chrono_tz::Tz = job_config
-            .timezone
-            .parse()
-            .map_err(|_| SchedulerError::InvalidTimezone(job_config.timezone.clone()))?;
+        let styles = Arc::clone(&self.styles);
+        let alert_tracker = Arc::clone(&self.alert_tracker);
+        let alert_history = Arc::clone(&self.alert_history);
+        let alerts = Arc::clone(&self.alerts);
+        let in_flight = Arc::clone(&self.in_flight);
+        let live = Arc::clone(&self.live);
+        let events = Arc::clone(&self.events);
+        let history = self.history.clone();
+        let forecast_issues = self.forecast_issues.clone();
+        let clothing = Arc::clone(&self.clothing);
+        let validation = Arc::clone(&self.validation);
+        let anomalies = Arc::clone(&self.anomalies);
+        let run_job_id = job_id.clone();
+        let primary = zone.as_ref().is_none_or(|z| z.fallback);
 
         tracing::info!(
             job_id = %job_id,
             job_name = %job_name,
             city = %city,
             cron = %job_config.cron,
-            timezone = %job_config.timezone,
+            timezone = %timezone,
             "Scheduling forecast job"
         );
 
@@ -133,11 +502,32 @@ impl SchedulerService {
                 let units = units.clone();
                 let job_name = job_name.clone();
                 let notify_config = notify_config.clone();
+                let wording = wording.clone();
+                let owner = owner.clone();
                 let forecast_service = Arc::clone(&forecast_service);
                 let devices_service = Arc::clone(&devices_service);
+                let styles = Arc::clone(&styles);
+                let alert_tracker = Arc::clone(&alert_tracker);
+                let alert_history = Arc::clone(&alert_history);
+                let alerts = Arc::clone(&alerts);
+                let in_flight = Arc::clone(&in_flight);
+                let live = Arc::clone(&live);
+                let events = Arc::clone(&events);
+                let history = history.clone();
+                let forecast_issues = forecast_issues.clone();
+                let clothing = Arc::clone(&clothing);
+                let validation = Arc::clone(&validation);
+                let anomalies = Arc::clone(&anomalies);
+                let zone = zone.clone();
+                let job_id = run_job_id.clone();
 
                 Box::pin(async move {
+                    let Some(_run) = in_flight.enter() else {
+                        tracing::info!(job = %job_name, "Skipping scheduled job during shutdown");
+                        return;
+                    };
                     tracing::info!(job = %job_name, city = %city, "Running scheduled forecast job");
+                    let run = events.start(Some(&job_id), Some(&job_name), &city, owner.as_deref());
 
                     // Fetch forecast
                     let forecast_result = if include_daily {
@@ -154,26 +544,94 @@ impl SchedulerService {
                                 "Forecast fetched successfully"
                             );
 
-                            // Check if we should send notification
-                            let should_notify = should_notify_for_forecast(&forecast, &notify_config);
+                            let now = chrono::Utc::now().timestamp();
+                            // Other timezones' runs of the job see the same
+                            // forecast, so only the primary one records it
+                            let forecast = screen_forecast(
+                                anomalies.as_ref(),
+                                &validation,
+                                &forecast,
+                                &units,
+                                primary,
+                                now,
+                            )
+                            .await;
+                            let extras = BriefingExtras {
+                                wording,
+                                ..briefing_extras(
+                                    history.as_deref(),
+                                    &clothing,
+                                    &notify_config,
+                                    &forecast,
+                                    &units,
+                                    now,
+                                )
+                                .await
+                            };
 
-                            if should_notify {
-                                let message = build_notification_message(&forecast);
+                            // Alerts a device has already been sent are left out
+                            // of its message, so long-running alerts don't go out
+                            // on every run
+                            let dedup = AlertDedup::load(alerts.as_ref(), &forecast, now).await;
+
+                            // Each recipient is checked against the job's notify rules
+                            // combined with its own thresholds and alert preferences
+                            let sent = notify_city(
+                                &forecast_service,
+                                &devices_service,
+                                &styles,
+                                &city,
+                                &units,
+                                include_daily,
+                                &forecast,
+                                Some(&notify_config),
+                                owner.as_deref(),
+                                zone.as_ref(),
+                                &dedup,
+                                &extras,
+                            )
+                            .await
+                            .unwrap_or(0);
+                            if sent > 0 {
+                                tracing::info!(city = %city, geocoded = %forecast.location.city, sent = sent, "Sent push notifications");
+                            }
 
-                                // Send to devices subscribed to this city, geocoded name, or broadcast as fallback
-                                let geocoded = &forecast.location.city;
-                                let mut sent = devices_service.send_to_city(&city, &message).await.unwrap_or(0);
-                                if sent == 0 && geocoded.to_lowercase() != city.to_lowercase() {
-                                    sent = devices_service.send_to_city(geocoded, &message).await.unwrap_or(0);
-                                }
-                                if sent == 0 {
-                                    sent = devices_service.broadcast(&message).await.unwrap_or(0);
+                            if primary {
+                                record_alert_history(alert_history.as_ref(), &forecast, now)
+                                    .await;
+                                if let Some(ref repo) = forecast_issues {
+                                    record_forecast_issues(repo.as_ref(), &forecast, &units, now)
+                                        .await;
                                 }
-                                tracing::info!(city = %city, geocoded = %geocoded, sent = sent, "Sent push notifications");
+                                let ended =
+                                    observe_run(&alert_tracker, &live, &city, &forecast, now);
+                                notify_alerts_ended(alerts.as_ref(), &devices_service, &styles, ended).await;
                             }
+
+                            live.publish(LiveEvent::JobResult {
+                                city: city.clone(),
+                                job: Some(job_name.clone()),
+                                success: true,
+                                error: None,
+                                notifications_sent: sent,
+                                owner: owner.clone(),
+                            });
+                            run.succeeded(sent);
                         }
                         Err(e) => {
                             tracing::error!(job = %job_name, error = %e, "Failed to fetch forecast");
+                            live.publish(LiveEvent::JobResult {
+                                city: city.clone(),
+                                job: Some(job_name.clone()),
+                                success: false,
+                                error: Some(e.to_string()),
+                                notifications_sent: 0,
+                                owner: owner.clone(),
+                            });
+                            run.failed(e.to_string());
+                            if !primary {
+                                return;
+                            }
 
                             // Send error notification to devices subscribed to this city
                             let message = NotificationMessage {
@@ -181,8 +639,9 @@ impl SchedulerService {
                                 subtitle: None,
                                 body: format!("Failed to fetch forecast for {}: {}", city, e),
                                 priority: Priority::High,
-                                tags: vec!["warning".to_string()],
+                                tags: styles.tag(ConditionGroup::Alert).into_iter().collect(),
                                 city: Some(city.clone()),
+                                events: vec![EventType::DailyBriefing],
                             };
 
                             let _ = devices_service.send_to_city(&city, &message).await;
@@ -193,41 +652,81 @@ impl SchedulerService {
             .build()
             .map_err(|e| SchedulerError::Scheduler(e.to_string()))?;
 
-        let uuid = self
-            .scheduler
+        self.scheduler
             .add(cron_job)
             .await
-            .map_err(|e| SchedulerError::Scheduler(e.to_string()))?;
-
-        // Store mapping
-        self.job_uuids.write().await.insert(job_id, uuid);
-
-        Ok(uuid)
+            .map_err(|e| SchedulerError::Scheduler(e.to_string()))
     }
 
     /// Unschedule a job from the cron scheduler (internal)
     async fn unschedule_job(&self, job_id: &str) -> Result<(), SchedulerError> {
-        let uuid = {
+        let uuids = {
             let uuids = self.job_uuids.read().await;
-            uuids.get(job_id).copied()
+            uuids.get(job_id).cloned()
         };
 
-        if let Some(uuid) = uuid {
-            self.scheduler
-                .remove(&uuid)
-                .await
-                .map_err(|e| SchedulerError::Scheduler(e.to_string()))?;
+        if let Some(uuids) = uuids {
+            for uuid in &uuids {
+                self.scheduler
+                    .remove(uuid)
+                    .await
+                    .map_err(|e| SchedulerError::Scheduler(e.to_string()))?;
+            }
             self.job_uuids.write().await.remove(job_id);
+            self.device_zones.write().await.remove(job_id);
             tracing::info!(job_id = %job_id, "Unscheduled job");
         }
 
         Ok(())
     }
 
+    /// Reschedule device-local-time jobs whose devices' timezones changed.
+    /// Returns how many were rescheduled.
+    pub async fn refresh_device_zones(&self) -> usize {
+        let scheduled = self.device_zones.read().await.clone();
+        let mut rescheduled = 0;
+        for (job_id, zones) in scheduled {
+            let Some(job) = self.get_job(&job_id).await else {
+                continue;
+            };
+            if self.device_timezones(&job).await == *zones {
+                continue;
+            }
+            let result = match self.unschedule_job(&job_id).await {
+                Ok(()) => self.schedule_job(&job).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => rescheduled += 1,
+                Err(e) => {
+                    tracing::error!(job_id = %job_id, error = %e, "Failed to reschedule job for device timezones")
+                }
+            }
+        }
+        rescheduled
+    }
+
+    /// IANA timezone of a city, as reported with its forecast. That goes
+    /// through the forecast cache and API budget, and the job fetches the
+    /// forecast anyway, so the lookup also warms the cache. Falls back to UTC
+    /// when the city can't be resolved.
+    pub async fn city_timezone(&self, city: &str, units: &str) -> String {
+        let timezone = self
+            .forecast_service
+            .get_daily_forecast(city, units)
+            .await
+            .map(|forecast| forecast.timezone);
+        timezone_or_utc(city, timezone)
+    }
+
     /// Create a new job
     pub async fn create_job(&self, job: ForecastJob) -> Result<ForecastJob, SchedulerError> {
+        if self.in_flight.closed.load(Ordering::SeqCst) {
+            return Err(SchedulerError::ShuttingDown);
+        }
+
         // Validate cron expression by trying to parse it
-        if Job::new_async(job.cron.as_str(), |_, _| Box::pin(async {})).is_err() {
+        if !is_valid_cron(&job.cron) {
             return Err(SchedulerError::InvalidCron(job.cron.clone()));
         }
 
@@ -236,6 +735,9 @@ impl SchedulerService {
             return Err(SchedulerError::InvalidTimezone(job.timezone.clone()));
         }
 
+        validate_condition(&job.notify)?;
+        validate_title_template(&job)?;
+
         // Save to SQLite
         self.repo.upsert(&job).await?;
 
@@ -257,7 +759,7 @@ impl SchedulerService {
         }
 
         // Validate cron expression
-        if Job::new_async(job.cron.as_str(), |_, _| Box::pin(async {})).is_err() {
+        if !is_valid_cron(&job.cron) {
             return Err(SchedulerError::InvalidCron(job.cron.clone()));
         }
 
@@ -266,6 +768,9 @@ impl SchedulerService {
             return Err(SchedulerError::InvalidTimezone(job.timezone.clone()));
         }
 
+        validate_condition(&job.notify)?;
+        validate_title_template(&job)?;
+
         // Unschedule old job
         self.unschedule_job(&job.id).await?;
 
@@ -312,6 +817,30 @@ impl SchedulerService {
         })
     }
 
+    /// Next run time of each scheduled job, soonest first
+    pub async fn upcoming_runs(&self) -> Vec<(ForecastJob, i64)> {
+        let uuids = self.job_uuids.read().await.clone();
+        let mut scheduler = self.scheduler.clone();
+        let mut runs = Vec::new();
+        for job in self.get_jobs().await {
+            let Some(job_uuids) = uuids.get(&job.id) else {
+                continue;
+            };
+            let mut next_run: Option<i64> = None;
+            for &uuid in job_uuids {
+                if let Ok(Some(next)) = scheduler.next_tick_for_job(uuid).await {
+                    let next = next.timestamp();
+                    next_run = Some(next_run.map_or(next, |soonest| soonest.min(next)));
+                }
+            }
+            if let Some(next) = next_run {
+                runs.push((job, next));
+            }
+        }
+        runs.sort_by_key(|(_, next)| *next);
+        runs
+    }
+
     /// Add a raw cron `Job` to the scheduler (used by system jobs like backfill).
     pub async fn add_system_job(&self, job: Job) -> Result<Uuid, SchedulerError> {
         let uuid = self
@@ -322,38 +851,500 @@ impl SchedulerService {
         Ok(uuid)
     }
 
-    /// Run a job immediately (manual trigger) - sends to all devices subscribed to the city
-    pub async fn run_now(&self, city: &str, units: &str) -> Result<()> {
-        tracing::info!(city = %city, "Running manual forecast job");
-
-        let forecast = self
-            .forecast_service
-            .get_daily_forecast(city, units)
+    /// Stop notifying an alert (by the id from GET /alerts), even if it
+    /// changes. Returns false if it was never notified.
+    pub async fn acknowledge_alert(&self, id: &str) -> Result<bool, SchedulerError> {
+        let acknowledged = self
+            .alerts
+            .acknowledge(id, chrono::Utc::now().timestamp())
             .await?;
+        if acknowledged {
+            tracing::info!(alert_id = %id, "Alert acknowledged");
+        }
+        Ok(acknowledged)
+    }
 
-        let message = build_notification_message(&forecast);
+    /// Send all-clear notifications for tracked alerts whose end time has passed
+    pub async fn check_expired_alerts(&self) -> usize {
+        let Some(_run) = self.begin_run() else {
+            return 0;
+        };
+        let ended = self
+            .alert_tracker
+            .take_expired(chrono::Utc::now().timestamp());
+        publish_alerts_ended(&self.live, &ended);
+        notify_alerts_ended(
+            self.alerts.as_ref(),
+            &self.devices_service,
+            &self.styles,
+            ended,
+        )
+        .await
+    }
 
-        // Try the input city name first, then the geocoded name if different
-        let geocoded_city = &forecast.location.city;
-        let mut sent = self.devices_service.send_to_city(city, &message).await?;
-        if sent == 0 && geocoded_city.to_lowercase() != city.to_lowercase() {
-            sent = self
-                .devices_service
-                .send_to_city(geocoded_city, &message)
-                .await?;
+    /// Run a job immediately (manual trigger) - sends to all devices subscribed
+    /// to the city, or only to `owner`'s devices when given. Returns the number
+    /// of notifications sent.
+    pub async fn run_now(&self, city: &str, units: &str, owner: Option<&str>) -> Result<usize> {
+        self.run_manual(city, units, owner, None).await
+    }
+
+    /// Run a saved job immediately, outside its schedule, with its own
+    /// notification rules. Returns the number of notifications sent.
+    pub async fn run_job_now(&self, job: &ForecastJob) -> Result<usize> {
+        self.run_manual(&job.city, &job.units, job.owner.as_deref(), Some(job))
+            .await
+    }
+
+    /// Shared by `run_now` and `run_job_now`; `job` supplies the notify rules
+    /// and forecast shape when running a saved job
+    async fn run_manual(
+        &self,
+        city: &str,
+        units: &str,
+        owner: Option<&str>,
+        job: Option<&ForecastJob>,
+    ) -> Result<usize> {
+        let Some(_run) = self.begin_run() else {
+            return Err(SchedulerError::ShuttingDown.into());
+        };
+        let job_name = job.map(|j| j.name.clone());
+        tracing::info!(city = %city, job = ?job_name, "Running manual forecast job");
+
+        let run = self
+            .events
+            .start(job.map(|j| j.id.as_str()), job_name.as_deref(), city, owner);
+        match self.run_manual_inner(city, units, owner, job).await {
+            Ok(sent) => {
+                run.succeeded(sent);
+                Ok(sent)
+            }
+            Err(e) => {
+                self.live.publish(LiveEvent::JobResult {
+                    city: city.to_string(),
+                    job: job_name,
+                    success: false,
+                    error: Some(e.to_string()),
+                    notifications_sent: 0,
+                    owner: owner.map(str::to_string),
+                });
+                run.failed(e.to_string());
+                Err(e)
+            }
         }
+    }
 
-        // Fallback: if no city-matched devices found, broadcast to all enabled devices
-        // (handles cases where device cities are empty or mismatched)
-        if sent == 0 {
-            tracing::warn!(city = %city, "No devices matched city, broadcasting to all enabled devices");
-            sent = self.devices_service.broadcast(&message).await?;
+    /// Body of `run_manual`. Returns the number of notifications sent.
+    async fn run_manual_inner(
+        &self,
+        city: &str,
+        units: &str,
+        owner: Option<&str>,
+        job: Option<&ForecastJob>,
+    ) -> Result<usize> {
+        let include_daily = job.is_none_or(|j| j.include_daily);
+        let forecast = if include_daily {
+            self.forecast_service
+                .get_daily_forecast(city, units)
+                .await?
+        } else {
+            self.forecast_service.get_forecast(city, units).await?
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let forecast = screen_forecast(
+            self.anomalies.as_ref(),
+            &self.validation,
+            &forecast,
+            units,
+            true,
+            now,
+        )
+        .await;
+        let extras = match job {
+            Some(job) => BriefingExtras {
+                wording: JobWording::of(job),
+                ..briefing_extras(
+                    self.history.as_deref(),
+                    &self.clothing,
+                    &job.notify,
+                    &forecast,
+                    units,
+                    now,
+                )
+                .await
+            },
+            None => BriefingExtras::default(),
+        };
+
+        let sent = notify_city(
+            &self.forecast_service,
+            &self.devices_service,
+            &self.styles,
+            city,
+            units,
+            include_daily,
+            &forecast,
+            job.map(|j| &j.notify),
+            owner,
+            None,
+            &AlertDedup::fresh(self.alerts.as_ref(), &forecast, now),
+            &extras,
+        )
+        .await?;
+
+        // The alert history and tracker are shared by every owner, so like a
+        // scheduled job that isn't its city's primary, a run scoped to one
+        // owner leaves them (and the all-clears they drive) to unscoped runs
+        if owner.is_none() {
+            record_alert_history(self.alert_history.as_ref(), &forecast, now).await;
+            if let Some(ref repo) = self.forecast_issues {
+                record_forecast_issues(repo.as_ref(), &forecast, units, now).await;
+            }
+            let ended = observe_run(&self.alert_tracker, &self.live, city, &forecast, now);
+            notify_alerts_ended(
+                self.alerts.as_ref(),
+                &self.devices_service,
+                &self.styles,
+                ended,
+            )
+            .await;
         }
+        self.live.publish(LiveEvent::JobResult {
+            city: city.to_string(),
+            job: job.map(|j| j.name.clone()),
+            success: true,
+            error: None,
+            notifications_sent: sent,
+            owner: owner.map(str::to_string),
+        });
 
+        let geocoded_city = &forecast.location.city;
         tracing::info!(city = %city, geocoded = %geocoded_city, sent = sent, "Manual trigger complete");
 
-        Ok(())
+        Ok(sent)
     }
+
+    /// Subscribe to job run activity
+    pub fn subscribe_events(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<super::events::SchedulerEvent> {
+        self.events.subscribe()
+    }
+}
+
+/// Start a background task that reschedules device-local-time jobs as
+/// devices report new timezones
+pub fn start_device_zone_refresh_task(scheduler_service: Arc<SchedulerService>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DEVICE_ZONE_REFRESH_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let rescheduled = scheduler_service.refresh_device_zones().await;
+            if rescheduled > 0 {
+                tracing::info!(
+                    jobs = rescheduled,
+                    "Rescheduled jobs for device timezone changes"
+                );
+            }
+        }
+    });
+}
+
+/// Start a background task that sends all-clear notifications when tracked
+/// alerts expire between forecast runs
+pub fn start_alert_expiry_task(scheduler_service: Arc<SchedulerService>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ALERT_EXPIRY_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            scheduler_service.check_expired_alerts().await;
+        }
+    });
+}
+
+/// Check a forecast's current and hourly values before it is sent, leaving
+/// out the implausible ones so the rest, alerts included, still goes out.
+/// They are quarantined when `record` is set.
+async fn screen_forecast<'a>(
+    repo: &dyn AnomalyRepository,
+    validation: &ValidationConfig,
+    forecast: &'a ForecastResponse,
+    units: &str,
+    record: bool,
+    now: i64,
+) -> Cow<'a, ForecastResponse> {
+    let rejected = validation.check_forecast(forecast, units.parse().unwrap_or(Units::Metric));
+    let Some(first) = rejected.first() else {
+        return Cow::Borrowed(forecast);
+    };
+    let location = &forecast.location;
+    tracing::warn!(
+        city = %location.city,
+        rejected = rejected.len(),
+        example = %first.reasons.join(", "),
+        "Leaving implausible values out of forecast"
+    );
+    let rejected_at: HashSet<i64> = rejected.iter().map(|r| r.timestamp).collect();
+    let screened = ForecastResponse {
+        current: forecast
+            .current
+            .clone()
+            .filter(|c| !rejected_at.contains(&c.timestamp)),
+        hourly: forecast
+            .hourly
+            .iter()
+            .filter(|h| !rejected_at.contains(&h.timestamp))
+            .cloned()
+            .collect(),
+        ..forecast.clone()
+    };
+
+    if record {
+        let location_key = make_location_key(location.lat, location.lon);
+        metrics::counter!(crate::metrics::WEATHER_ANOMALIES, "source" => SOURCE_FORECAST)
+            .increment(rejected.len() as u64);
+        let anomalies: Vec<_> = rejected
+            .into_iter()
+            .map(|r| r.into_anomaly(&location_key, &location.city, SOURCE_FORECAST, now))
+            .collect();
+        if let Err(e) = repo.record(&anomalies).await {
+            tracing::warn!(error = %e, "Failed to quarantine forecast values");
+        }
+    }
+    Cow::Owned(screened)
+}
+
+/// Per-device alert dedup for one run: which devices each of the location's
+/// alerts has reached, and where to record the deliveries this run makes
+struct AlertDedup<'a> {
+    repo: &'a dyn AlertRepository,
+    ledger: AlertLedger,
+    now: i64,
+}
+
+impl<'a> AlertDedup<'a> {
+    /// Load what the forecast location's alerts have reached so far. On a
+    /// database error nothing has, so alerts are repeated rather than missed.
+    async fn load(repo: &'a dyn AlertRepository, forecast: &ForecastResponse, now: i64) -> Self {
+        let location = &forecast.location;
+        let location_key = make_location_key(location.lat, location.lon);
+        let ledger = if forecast.alerts.is_empty() {
+            AlertLedger::empty(&location_key)
+        } else {
+            repo.get_ledger(&location_key, now)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(city = %location.city, error = %e, "Failed to read notified alerts");
+                    AlertLedger::empty(&location_key)
+                })
+        };
+        Self { repo, ledger, now }
+    }
+
+    /// Send every alert regardless of past deliveries (manual runs), still
+    /// recording what is delivered
+    fn fresh(repo: &'a dyn AlertRepository, forecast: &ForecastResponse, now: i64) -> Self {
+        let location_key = make_location_key(forecast.location.lat, forecast.location.lon);
+        Self {
+            repo,
+            ledger: AlertLedger::empty(&location_key),
+            now,
+        }
+    }
+
+    /// Record alerts as delivered to the devices in `device_ids`
+    async fn record(&self, city: &str, alerts: &[&AlertResponse], device_ids: &[String]) {
+        for alert in alerts {
+            if let Err(e) = self
+                .repo
+                .record_delivered(
+                    self.ledger.location_key(),
+                    city,
+                    alert,
+                    device_ids,
+                    self.now,
+                )
+                .await
+            {
+                tracing::warn!(city = %city, error = %e, "Failed to record notified alerts");
+            }
+        }
+    }
+}
+
+/// Persist the alerts in a forecast to the alert history
+async fn record_alert_history(
+    repo: &dyn AlertHistoryRepository,
+    forecast: &ForecastResponse,
+    now: i64,
+) {
+    if forecast.alerts.is_empty() {
+        return;
+    }
+    let location = &forecast.location;
+    let location_key = make_location_key(location.lat, location.lon);
+    if let Err(e) = repo
+        .record(&location_key, &location.city, &forecast.alerts, now)
+        .await
+    {
+        tracing::warn!(city = %location.city, error = %e, "Failed to record alert history");
+    }
+}
+
+/// Persist a forecast's daily outlook, in metric, so it can be scored once the
+/// days it covers are in the weather history
+async fn record_forecast_issues(
+    repo: &dyn ForecastIssueRepository,
+    forecast: &ForecastResponse,
+    units: &str,
+    now: i64,
+) {
+    let issues: Vec<ForecastIssue> = forecast
+        .daily
+        .iter()
+        .enumerate()
+        .filter_map(|(lead, day)| {
+            let date = chrono::DateTime::from_timestamp(day.timestamp, 0)?.date_naive();
+            Some(ForecastIssue {
+                target_date: date.to_string(),
+                lead_days: lead as i64,
+                issued_at: now,
+                temp_min: convert_temperature(day.temp_min, units, "metric"),
+                temp_max: convert_temperature(day.temp_max, units, "metric"),
+                precipitation: day.rain_volume.unwrap_or(0.0) + day.snow_volume.unwrap_or(0.0),
+            })
+        })
+        .collect();
+    if issues.is_empty() {
+        return;
+    }
+    let location = &forecast.location;
+    let location_key = make_location_key(location.lat, location.lon);
+    if let Err(e) = repo.record(&location_key, &issues).await {
+        tracing::warn!(city = %location.city, error = %e, "Failed to record forecast issues");
+    }
+}
+
+/// Track a forecast run's alerts and push its conditions and alert changes to
+/// live subscribers. Returns the alerts that ended since the city's last run.
+fn observe_run(
+    tracker: &ActiveAlertTracker,
+    live: &LiveUpdates,
+    city: &str,
+    forecast: &ForecastResponse,
+    now: i64,
+) -> Vec<EndedAlert> {
+    if let Some(ref current) = forecast.current {
+        live.publish(LiveEvent::Conditions {
+            city: city.to_string(),
+            location: forecast.location.clone(),
+            current: current.clone(),
+        });
+    }
+    for alert in tracker.started(city, forecast, now) {
+        live.publish(LiveEvent::Alert {
+            city: city.to_string(),
+            location: forecast.location.clone(),
+            alert,
+        });
+    }
+
+    let ended = tracker.observe(city, forecast, now);
+    publish_alerts_ended(live, &ended);
+    ended
+}
+
+fn publish_alerts_ended(live: &LiveUpdates, ended: &[EndedAlert]) {
+    for e in ended {
+        live.publish(LiveEvent::AlertEnded {
+            city: e.city.clone(),
+            location: e.location.clone(),
+            alert: e.alert.clone(),
+        });
+    }
+}
+
+/// Tell devices that heard about an alert that it has expired or been
+/// cancelled. Recipients are the devices the alert ledger records it as
+/// delivered to, grouped by language.
+async fn notify_alerts_ended(
+    alerts: &dyn AlertRepository,
+    devices_service: &DevicesService,
+    styles: &ConditionStylesConfig,
+    ended: Vec<EndedAlert>,
+) -> usize {
+    let mut sent = 0;
+    for EndedAlert {
+        city,
+        location,
+        alert,
+    } in ended
+    {
+        let location_key = make_location_key(location.lat, location.lon);
+        let recipients = match alerts.get_recipients(&location_key, &alert).await {
+            Ok(ids) => devices_service.get_by_ids(&ids).await,
+            Err(e) => Err(e.into()),
+        };
+        let recipients = match recipients {
+            Ok(devices) => devices,
+            Err(e) => {
+                tracing::warn!(city = %city, error = %e, "Failed to load all-clear recipients");
+                continue;
+            }
+        };
+
+        let mut by_language: BTreeMap<String, Vec<Device>> = BTreeMap::new();
+        for device in recipients {
+            by_language
+                .entry(device.language.clone())
+                .or_default()
+                .push(device);
+        }
+
+        let mut alert_sent = 0;
+        for (lang, devices) in by_language {
+            let strings = template_strings(&lang);
+            let message = NotificationMessage {
+                title: format!("{}: {}", strings.all_clear, alert.event),
+                subtitle: None,
+                body: format!("{} {} ({})", alert.event, strings.ended, location.city),
+                priority: Priority::Default,
+                tags: styles.tag(ConditionGroup::Clear).into_iter().collect(),
+                city: Some(city.clone()),
+                events: vec![EventType::SevereAlerts],
+            };
+            alert_sent += devices_service.send_to_devices(&devices, &message).await;
+        }
+
+        if alert_sent > 0 {
+            tracing::info!(city = %city, event = %alert.event, sent = alert_sent, "Alert ended, sent all-clear");
+        }
+        sent += alert_sent;
+    }
+    sent
+}
+
+/// Reject a notify condition that doesn't parse
+fn validate_condition(notify: &NotifyConfig) -> Result<(), SchedulerError> {
+    if let Some(ref condition) = notify.condition {
+        Condition::parse(condition).map_err(|e| SchedulerError::InvalidCondition(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Reject a title template with placeholders other than `TITLE_PLACEHOLDERS`
+fn validate_title_template(job: &ForecastJob) -> Result<(), SchedulerError> {
+    if let Some(ref template) = job.title_template {
+        let rest = TITLE_PLACEHOLDERS
+            .iter()
+            .fold(template.clone(), |rest, p| rest.replace(p, ""));
+        if rest.contains(['{', '}']) {
+            return Err(SchedulerError::InvalidTitleTemplate(template.clone()));
+        }
+    }
+    Ok(())
 }
 
 fn should_notify_for_forecast(
@@ -365,8 +1356,13 @@ fn should_notify_for_forecast(
         return true;
     }
 
-    // Check for weather alerts
-    if config.on_alert && !forecast.alerts.is_empty() {
+    // Check for weather alerts at or above the configured severity
+    if config.on_alert
+        && forecast
+            .alerts
+            .iter()
+            .any(|a| config.min_severity.is_none_or(|min| a.severity.meets(min)))
+    {
         return true;
     }
 
@@ -393,18 +1389,482 @@ fn should_notify_for_forecast(
         }
     }
 
+    // Check wind thresholds now and over the next day
+    if config.wind_threshold.is_some() || config.gust_threshold.is_some() {
+        let (speed, gust) = peak_wind(forecast);
+        if config.wind_threshold.is_some_and(|t| speed > t) {
+            return true;
+        }
+        if let (Some(threshold), Some(gust)) = (config.gust_threshold, gust) {
+            if gust > threshold {
+                return true;
+            }
+        }
+    }
+
+    // Check for a full or new moon today
+    if config.moon_events
+        && forecast
+            .daily
+            .first()
+            .and_then(|d| MoonEvent::from_phase(d.moon_phase))
+            .is_some()
+    {
+        return true;
+    }
+
+    // Check the job's own condition
+    if let Some(ref condition) = config.condition {
+        match Condition::parse(condition) {
+            Ok(condition) if condition.matches(forecast) => return true,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(condition = %condition, error = %e, "Skipping invalid notify condition")
+            }
+        }
+    }
+
+    // Check expected snowfall
+    if let Some(threshold) = config.snow_threshold_mm {
+        let window = config
+            .snow_window_hours
+            .unwrap_or(DEFAULT_SNOW_WINDOW_HOURS)
+            .clamp(1, MAX_SNOW_WINDOW_HOURS);
+        if expected_snow_mm(forecast, window) > threshold {
+            return true;
+        }
+    }
+
     false
 }
 
+/// Snowfall in mm expected over the next `hours`. Daily-only forecasts sum
+/// whole days instead, rounding the window up.
+fn expected_snow_mm(forecast: &ForecastResponse, hours: u32) -> f64 {
+    if forecast.hourly.is_empty() {
+        forecast
+            .daily
+            .iter()
+            .take(hours.div_ceil(24) as usize)
+            .filter_map(|d| d.snow_volume)
+            .sum()
+    } else {
+        forecast
+            .hourly
+            .iter()
+            .take(hours as usize)
+            .filter_map(|h| h.snow_volume)
+            .sum()
+    }
+}
+
+/// Highest wind speed and gust now and over the next 24 hours. Daily-only
+/// forecasts have no hourly data, so today's daily figures stand in for it.
+fn peak_wind(forecast: &ForecastResponse) -> (f64, Option<f64>) {
+    let mut readings: Vec<(f64, Option<f64>)> = Vec::new();
+    if let Some(ref current) = forecast.current {
+        readings.push((current.wind_speed, current.wind_gust));
+    }
+    if forecast.hourly.is_empty() {
+        if let Some(today) = forecast.daily.first() {
+            readings.push((today.wind_speed, today.wind_gust));
+        }
+    } else {
+        readings.extend(
+            forecast
+                .hourly
+                .iter()
+                .take(WIND_LOOKAHEAD_HOURS)
+                .map(|h| (h.wind_speed, h.wind_gust)),
+        );
+    }
+
+    let speed = readings.iter().map(|r| r.0).fold(0.0, f64::max);
+    let gust = readings.iter().filter_map(|r| r.1).reduce(f64::max);
+    (speed, gust)
+}
+
+/// Convert a temperature between OWM unit systems ("metric", "imperial", "standard")
+fn convert_temperature(value: f64, from: &str, to: &str) -> f64 {
+    let celsius = match from {
+        "imperial" => (value - 32.0) * 5.0 / 9.0,
+        "standard" => value - 273.15,
+        _ => value,
+    };
+    match to {
+        "imperial" => celsius * 9.0 / 5.0 + 32.0,
+        "standard" => celsius + 273.15,
+        _ => celsius,
+    }
+}
+
+/// A city's forecast timezone, or UTC when the forecast failed or reported
+/// a zone chrono-tz doesn't know
+fn timezone_or_utc(city: &str, timezone: Result<String, ForecastError>) -> String {
+    match timezone {
+        Ok(timezone) if timezone.parse::<chrono_tz::Tz>().is_ok() => timezone,
+        Ok(timezone) => {
+            tracing::warn!(
+                city = %city,
+                timezone = %timezone,
+                "Unrecognized forecast timezone, defaulting job to UTC"
+            );
+            "UTC".to_string()
+        }
+        Err(e) => {
+            tracing::warn!(
+                city = %city,
+                error = %e,
+                "Couldn't look up city timezone, defaulting job to UTC"
+            );
+            "UTC".to_string()
+        }
+    }
+}
+
+/// Whether a device should be notified about a forecast fetched in `units`.
+/// The device's own temperature thresholds (in its own units) replace the job's,
+/// and only alerts that reach the device (`Device::receives_alert`) and that
+/// it hasn't already been sent count.
+fn device_should_notify(
+    forecast: &ForecastResponse,
+    job: &NotifyConfig,
+    device: &Device,
+    units: &str,
+    ledger: &AlertLedger,
+) -> bool {
+    let config = NotifyConfig {
+        cold_threshold: device
+            .cold_threshold
+            .map(|t| convert_temperature(t, &device.units, units))
+            .or(job.cold_threshold),
+        heat_threshold: device
+            .heat_threshold
+            .map(|t| convert_temperature(t, &device.units, units))
+            .or(job.heat_threshold),
+        ..job.clone()
+    };
+
+    let takes =
+        |a: &AlertResponse| device.receives_alert(a) && ledger.needs_notifying(a, &device.id);
+    if forecast.alerts.iter().all(takes) {
+        should_notify_for_forecast(forecast, &config)
+    } else {
+        let filtered = ForecastResponse {
+            alerts: forecast
+                .alerts
+                .iter()
+                .filter(|a| takes(a))
+                .cloned()
+                .collect(),
+            ..forecast.clone()
+        };
+        should_notify_for_forecast(&filtered, &config)
+    }
+}
+
+/// Send a forecast notification to every recipient for a city. When
+/// `notify_config` is given, only devices for which `device_should_notify`
+/// holds are included. Devices are grouped by language; each non-default
+/// language costs one extra forecast fetch so OWM descriptions and summaries
+/// arrive translated. Alerts are filtered per device by `Device::receives_alert`
+/// and by what `dedup` says the device has already been sent, and recorded as
+/// sent once a push carrying them is delivered. With `owner` set, only that
+/// user's devices are notified, and with `zone` set, only the devices that
+/// run serves.
+#[allow(clippy::too_many_arguments)]
+async fn notify_city(
+    forecast_service: &ForecastService,
+    devices_service: &DevicesService,
+    styles: &ConditionStylesConfig,
+    city: &str,
+    units: &str,
+    include_daily: bool,
+    forecast: &ForecastResponse,
+    notify_config: Option<&NotifyConfig>,
+    owner: Option<&str>,
+    zone: Option<&DeviceZone>,
+    dedup: &AlertDedup<'_>,
+    extras: &BriefingExtras,
+) -> Result<usize, DevicesError> {
+    let ledger = &dedup.ledger;
+    let mut recipients = devices_service
+        .city_recipients(city, &forecast.location)
+        .await?;
+    if let Some(owner) = owner {
+        recipients.retain(|d| d.owner.as_deref() == Some(owner));
+    }
+    if let Some(zone) = zone {
+        recipients.retain(|d| zone.serves(d));
+    }
+    if let Some(config) = notify_config {
+        recipients.retain(|d| device_should_notify(forecast, config, d, units, ledger));
+    }
+
+    // Decided on the default-language alerts, whose ids localized copies share
+    let pending: HashMap<&str, HashSet<String>> = recipients
+        .iter()
+        .map(|d| {
+            let ids = forecast
+                .alerts
+                .iter()
+                .filter(|a| d.receives_alert(a) && ledger.needs_notifying(a, &d.id))
+                .map(|a| ledger.id_of(a))
+                .collect();
+            (d.id.as_str(), ids)
+        })
+        .collect();
+    let originals: HashMap<String, &AlertResponse> = forecast
+        .alerts
+        .iter()
+        .map(|a| (ledger.id_of(a), a))
+        .collect();
+
+    let mut by_language: BTreeMap<&str, Vec<Device>> = BTreeMap::new();
+    for device in &recipients {
+        by_language
+            .entry(device.language.as_str())
+            .or_default()
+            .push(device.clone());
+    }
+
+    let mut sent = 0;
+    for (lang, devices) in by_language {
+        let localized = if lang == DEFAULT_LANG {
+            None
+        } else {
+            let result = if include_daily {
+                forecast_service
+                    .get_daily_forecast_in(city, units, lang)
+                    .await
+            } else {
+                forecast_service.get_forecast_in(city, units, lang).await
+            };
+            result
+                .inspect_err(|e| {
+                    tracing::warn!(lang = %lang, error = %e, "Failed to fetch localized forecast, using default language");
+                })
+                .ok()
+        };
+
+        let forecast = localized.as_ref().unwrap_or(forecast);
+
+        // Devices that take the same subset of alerts share one message
+        let mut by_alerts: BTreeMap<Vec<usize>, Vec<Device>> = BTreeMap::new();
+        for device in devices {
+            let ids = &pending[device.id.as_str()];
+            let accepted = forecast
+                .alerts
+                .iter()
+                .enumerate()
+                .filter(|(_, alert)| ids.contains(&ledger.id_of(alert)))
+                .map(|(i, _)| i)
+                .collect();
+            by_alerts.entry(accepted).or_default().push(device);
+        }
+
+        for (accepted, devices) in by_alerts {
+            let filtered;
+            let forecast = if accepted.len() == forecast.alerts.len() {
+                forecast
+            } else {
+                filtered = ForecastResponse {
+                    alerts: accepted
+                        .iter()
+                        .map(|&i| forecast.alerts[i].clone())
+                        .collect(),
+                    ..forecast.clone()
+                };
+                &filtered
+            };
+
+            let message = build_notification_message(forecast, styles, lang, extras);
+            let delivered = devices_service.send_and_report(&devices, &message).await;
+            sent += delivered.len();
+            let alerts: Vec<&AlertResponse> = forecast
+                .alerts
+                .iter()
+                .filter_map(|a| originals.get(&ledger.id_of(a)).copied())
+                .collect();
+            if !delivered.is_empty() {
+                dedup
+                    .record(&forecast.location.city, &alerts, &delivered)
+                    .await;
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Moon phases worth a notification
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MoonEvent {
+    Full,
+    New,
+}
+
+impl MoonEvent {
+    /// The event on a day with this OWM moon phase (0 and 1 new, 0.5 full)
+    fn from_phase(phase: f64) -> Option<Self> {
+        if (phase - 0.5).abs() <= MOON_PHASE_TOLERANCE {
+            Some(Self::Full)
+        } else if phase <= MOON_PHASE_TOLERANCE || phase >= 1.0 - MOON_PHASE_TOLERANCE {
+            Some(Self::New)
+        } else {
+            None
+        }
+    }
+}
+
+/// Optional lines a job's notify settings add to its briefings
+#[derive(Debug, Default)]
+struct BriefingExtras {
+    /// Yesterday's recorded high, in the forecast's units
+    yesterday_high: Option<f64>,
+    /// What to wear today
+    clothing: Vec<String>,
+    /// Full or new moon today
+    moon: Option<MoonEvent>,
+    /// The job's own title and body wording
+    wording: JobWording,
+}
+
+/// A job's title template and body prefix/suffix
+#[derive(Debug, Clone, Default)]
+struct JobWording {
+    name: String,
+    title_template: Option<String>,
+    body_prefix: Option<String>,
+    body_suffix: Option<String>,
+}
+
+impl JobWording {
+    fn of(job: &ForecastJob) -> Self {
+        Self {
+            name: job.name.clone(),
+            title_template: job.title_template.clone(),
+            body_prefix: job.body_prefix.clone(),
+            body_suffix: job.body_suffix.clone(),
+        }
+    }
+
+    /// Fill in the title template, if the job has one
+    fn title(&self, city: &str, country: &str, emoji: Option<&str>) -> Option<String> {
+        let template = self.title_template.as_ref()?;
+        let title = template
+            .replace("{name}", &self.name)
+            .replace("{city}", city)
+            .replace("{country}", country)
+            .replace("{emoji}", emoji.unwrap_or(""));
+        Some(title.trim().to_string())
+    }
+}
+
+/// The extra briefing lines `notify` asks for, for a forecast in `units`
+async fn briefing_extras(
+    history: Option<&SqliteHistoryRepository>,
+    clothing_rules: &[ClothingRule],
+    notify: &NotifyConfig,
+    forecast: &ForecastResponse,
+    units: &str,
+    now: i64,
+) -> BriefingExtras {
+    let yesterday_high = match history {
+        Some(repo) if notify.compare_yesterday => yesterday_high(repo, forecast, units, now).await,
+        _ => None,
+    };
+    let clothing = match forecast.daily.first() {
+        Some(today) if notify.include_clothing => {
+            clothing_suggestions(clothing_rules, today, units)
+        }
+        _ => Vec::new(),
+    };
+    let moon = forecast
+        .daily
+        .first()
+        .filter(|_| notify.moon_events)
+        .and_then(|d| MoonEvent::from_phase(d.moon_phase));
+    BriefingExtras {
+        yesterday_high,
+        clothing,
+        moon,
+        wording: JobWording::default(),
+    }
+}
+
+/// Yesterday's highest recorded temperature at the forecast's location, in
+/// `units`. Yesterday runs midnight to midnight in the location's timezone;
+/// `None` when history has too few readings for it.
+async fn yesterday_high(
+    repo: &dyn HistoryRepository,
+    forecast: &ForecastResponse,
+    units: &str,
+    now: i64,
+) -> Option<f64> {
+    let tz: chrono_tz::Tz = forecast.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let today = chrono::DateTime::from_timestamp(now, 0)?
+        .with_timezone(&tz)
+        .date_naive();
+    let midnight = |date: chrono::NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(tz).earliest())
+            .map(|t| t.timestamp())
+    };
+    let start = midnight(today.pred_opt()?)?;
+    let end = midnight(today)? - 1;
+
+    let location_key = make_location_key(forecast.location.lat, forecast.location.lon);
+    let records = repo
+        .get_range(&location_key, start, end, STORAGE_UNITS)
+        .await
+        .inspect_err(|e| tracing::warn!(city = %forecast.location.city, error = %e, "Failed to load yesterday's history"))
+        .ok()?;
+    if records.len() < MIN_YESTERDAY_READINGS {
+        return None;
+    }
+    let high = records
+        .iter()
+        .map(|r| r.temperature)
+        .fold(f64::NEG_INFINITY, f64::max);
+    Some(convert_temperature(high, STORAGE_UNITS, units))
+}
+
+/// "7° colder than yesterday", comparing rounded highs
+fn yesterday_comparison(today_high: f64, yesterday_high: f64, strings: &TemplateStrings) -> String {
+    let diff = today_high.round() - yesterday_high.round();
+    if diff > 0.0 {
+        format!("{:.0}\u{00B0} {}", diff, strings.warmer_than_yesterday)
+    } else if diff < 0.0 {
+        format!("{:.0}\u{00B0} {}", -diff, strings.colder_than_yesterday)
+    } else {
+        strings.same_as_yesterday.to_string()
+    }
+}
+
 fn build_notification_message(
-    forecast: &crate::forecast::models::ForecastResponse,
+    forecast: &ForecastResponse,
+    styles: &ConditionStylesConfig,
+    lang: &str,
+    extras: &BriefingExtras,
 ) -> NotificationMessage {
+    let strings = template_strings(lang);
     let city = &forecast.location.city;
     let country = &forecast.location.country;
 
     let mut subtitle = String::new();
     let mut body = String::new();
 
+    if let Some(ref prefix) = extras.wording.body_prefix {
+        body.push_str(prefix);
+        body.push('\n');
+    }
+
+    if let (Some(yesterday), Some(today)) = (extras.yesterday_high, forecast.daily.first()) {
+        body.push_str(&yesterday_comparison(today.temp_max, yesterday, strings));
+        body.push('\n');
+    }
+
     if let Some(ref current) = forecast.current {
         subtitle = format!(
             "{:.0}\u{00B0} \u{2022} {}",
@@ -412,12 +1872,12 @@ fn build_notification_message(
         );
 
         body.push_str(&format!(
-            "{:.0}\u{00B0}  feels {:.0}\u{00B0}\n",
-            current.temperature, current.feels_like
+            "{:.0}\u{00B0}  {} {:.0}\u{00B0}\n",
+            current.temperature, strings.feels, current.feels_like
         ));
         body.push_str(&format!(
-            "humidity {}%  wind {:.0}mph\n",
-            current.humidity, current.wind_speed
+            "{} {}%  {} {:.0}mph\n",
+            strings.humidity, current.humidity, strings.wind, current.wind_speed
         ));
     }
 
@@ -428,7 +1888,8 @@ fn build_notification_message(
         ));
         if today.precipitation_probability > 0.0 {
             body.push_str(&format!(
-                "  precip {:.0}%",
+                "  {} {:.0}%",
+                strings.precip,
                 today.precipitation_probability * 100.0
             ));
         }
@@ -438,8 +1899,43 @@ fn build_notification_message(
         }
     }
 
+    if let (Some(event), Some(today)) = (extras.moon, forecast.daily.first()) {
+        if !body.is_empty() && !body.ends_with('\n') {
+            body.push('\n');
+        }
+        body.push_str(match event {
+            MoonEvent::Full => strings.full_moon,
+            MoonEvent::New => strings.new_moon,
+        });
+        let tz: chrono_tz::Tz = forecast.timezone.parse().unwrap_or(chrono_tz::UTC);
+        if let Some(rise) = today
+            .moonrise
+            .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        {
+            body.push_str(&format!(
+                " \u{2022} {} {}",
+                strings.moonrise,
+                rise.with_timezone(&tz).format("%H:%M")
+            ));
+        }
+    }
+
+    if !extras.clothing.is_empty() {
+        if !body.is_empty() && !body.ends_with('\n') {
+            body.push('\n');
+        }
+        body.push_str(&format!("{}: {}", strings.wear, extras.clothing.join(", ")));
+    }
+
+    if let Some(ref suffix) = extras.wording.body_suffix {
+        if !body.is_empty() && !body.ends_with('\n') {
+            body.push('\n');
+        }
+        body.push_str(suffix);
+    }
+
     let priority = if !forecast.alerts.is_empty() {
-        body.push_str("\n\nALERTS:\n");
+        body.push_str(&format!("\n\n{}:\n", strings.alerts));
         for alert in &forecast.alerts {
             body.push_str(&format!("\u{2022} {}\n", alert.event));
         }
@@ -448,14 +1944,40 @@ fn build_notification_message(
         Priority::Default
     };
 
-    let tags = if !forecast.alerts.is_empty() {
-        vec!["warning".to_string(), "weather".to_string()]
+    let group = if !forecast.alerts.is_empty() {
+        ConditionGroup::Alert
     } else {
-        vec!["sunny".to_string(), "weather".to_string()]
+        forecast
+            .current
+            .as_ref()
+            .map(|c| ConditionGroup::from_condition(c.condition_id, &c.icon))
+            .or_else(|| {
+                forecast
+                    .daily
+                    .first()
+                    .map(|d| ConditionGroup::from_condition(d.condition_id, &d.icon))
+            })
+            .unwrap_or(ConditionGroup::Clear)
+    };
+
+    let mut tags: Vec<String> = styles.tag(group).into_iter().collect();
+    tags.push("weather".to_string());
+
+    let emoji = styles.emoji(group);
+    let title = match (extras.wording.title(city, country, emoji.as_deref()), emoji) {
+        (Some(title), _) => title,
+        (None, Some(emoji)) => format!("{} {}, {}", emoji, city, country),
+        (None, None) => format!("{}, {}", city, country),
     };
 
+    // Devices subscribed only to severe alerts still get briefings that carry alerts
+    let mut events = vec![EventType::DailyBriefing];
+    if !forecast.alerts.is_empty() {
+        events.push(EventType::SevereAlerts);
+    }
+
     NotificationMessage {
-        title: format!("{}, {}", city, country),
+        title,
         subtitle: if subtitle.is_empty() {
             None
         } else {
@@ -465,6 +1987,7 @@ fn build_notification_message(
         priority,
         tags,
         city: Some(city.clone()),
+        events,
     }
 }
 
@@ -501,6 +2024,11 @@ mod tests {
                 wind_gust: None,
                 description: "clear sky".to_string(),
                 icon: "01d".to_string(),
+                condition_id: Some(800),
+                condition_main: Some("Clear".to_string()),
+                local_time: None,
+                sunrise_local: None,
+                sunset_local: None,
                 sunrise: Some(1699980000),
                 sunset: Some(1700020000),
             }),
@@ -509,6 +2037,8 @@ mod tests {
                 timestamp: 1700000000,
                 sunrise: 1699980000,
                 sunset: 1700020000,
+                moonrise: None,
+                moonset: None,
                 moon_phase: 0.5,
                 summary: Some("Clear skies".to_string()),
                 temp_min: 15.0,
@@ -525,16 +2055,290 @@ mod tests {
                 clouds: 20,
                 wind_speed: 4.0,
                 wind_direction: 180,
+                wind_gust: None,
                 precipitation_probability: daily_precip_prob,
                 rain_volume: None,
                 snow_volume: None,
                 description: "clear sky".to_string(),
                 icon: "01d".to_string(),
+                condition_id: Some(800),
+                condition_main: Some("Clear".to_string()),
+                local_time: None,
+                sunrise_local: None,
+                sunset_local: None,
+                sun_detail: None,
             }],
             alerts,
         }
     }
 
+    fn create_test_device(units: &str) -> Device {
+        serde_json::from_value(serde_json::json!({
+            "id": "device-1",
+            "token": "ExponentPushToken[test]",
+            "platform": "ios",
+            "units": units,
+            "registered_at": 1700000000,
+            "updated_at": 1700000000
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_convert_temperature() {
+        assert!((convert_temperature(32.0, "imperial", "metric") - 0.0).abs() < 1e-9);
+        assert!((convert_temperature(100.0, "metric", "imperial") - 212.0).abs() < 1e-9);
+        assert!((convert_temperature(0.0, "metric", "standard") - 273.15).abs() < 1e-9);
+        assert_eq!(convert_temperature(20.0, "metric", "metric"), 20.0);
+    }
+
+    #[test]
+    fn test_timezone_or_utc() {
+        // Cities near zone borders keep the zone their forecast reports
+        for (city, zone) in [
+            ("Dallas", "America/Chicago"),
+            ("Houston", "America/Chicago"),
+            ("San Antonio", "America/Chicago"),
+            ("Oklahoma City", "America/Chicago"),
+            ("Atlanta", "America/New_York"),
+            ("El Paso", "America/Denver"),
+            ("Phoenix", "America/Phoenix"),
+        ] {
+            assert_eq!(timezone_or_utc(city, Ok(zone.to_string())), zone);
+        }
+        assert_eq!(
+            timezone_or_utc("Somewhere", Ok("Mars/Olympus_Mons".to_string())),
+            "UTC"
+        );
+        assert_eq!(
+            timezone_or_utc(
+                "Nowhere",
+                Err(ForecastError::CityNotFound("Nowhere".to_string()))
+            ),
+            "UTC"
+        );
+    }
+
+    #[test]
+    fn test_device_zones_split_recipients() {
+        let scheduled = Arc::new(BTreeSet::from(["Europe/London".to_string()]));
+        let home = DeviceZone {
+            timezone: "America/Chicago".to_string(),
+            fallback: true,
+            scheduled: Arc::clone(&scheduled),
+        };
+        let london = DeviceZone {
+            timezone: "Europe/London".to_string(),
+            fallback: false,
+            scheduled,
+        };
+
+        let mut device = create_test_device("metric");
+        assert!(home.serves(&device) && !london.serves(&device));
+
+        device.timezone = Some("Europe/London".to_string());
+        assert!(!home.serves(&device) && london.serves(&device));
+
+        // A timezone reported since the job was scheduled waits on the fallback
+        device.timezone = Some("Asia/Tokyo".to_string());
+        assert!(home.serves(&device) && !london.serves(&device));
+    }
+
+    #[test]
+    fn test_device_threshold_overrides_job() {
+        // Forecast in metric: 5°C = 41°F
+        let forecast = create_test_forecast(Some(5.0), vec![], 0.0);
+        let job = create_default_notify_config();
+
+        let mut device = create_test_device("imperial");
+        assert!(!device_should_notify(
+            &forecast,
+            &job,
+            &device,
+            "metric",
+            &AlertLedger::default()
+        ));
+
+        device.cold_threshold = Some(45.0);
+        assert!(device_should_notify(
+            &forecast,
+            &job,
+            &device,
+            "metric",
+            &AlertLedger::default()
+        ));
+
+        device.cold_threshold = Some(35.0);
+        assert!(!device_should_notify(
+            &forecast,
+            &job,
+            &device,
+            "metric",
+            &AlertLedger::default()
+        ));
+    }
+
+    #[test]
+    fn test_device_alert_preferences_gate_alert_notifications() {
+        let alerts = vec![AlertResponse::new(
+            "NWS".to_string(),
+            "Special Weather Statement".to_string(),
+            1700000000,
+            1700100000,
+            "Fog".to_string(),
+            None,
+        )];
+        let forecast = create_test_forecast(Some(20.0), alerts, 0.0);
+        let mut job = create_default_notify_config();
+        job.on_alert = true;
+
+        let mut device = create_test_device("metric");
+        assert!(device_should_notify(
+            &forecast,
+            &job,
+            &device,
+            "metric",
+            &AlertLedger::default()
+        ));
+
+        device.alert_preferences.min_level = AlertLevel::Warning;
+        assert!(!device_should_notify(
+            &forecast,
+            &job,
+            &device,
+            "metric",
+            &AlertLedger::default()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_alert_already_sent_to_device_does_not_notify_it_again() {
+        let pool = crate::db::create_pool(&crate::db::DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        crate::db::run_migrations(&pool, &Default::default())
+            .await
+            .unwrap();
+        let repo = SqliteAlertRepository::new(pool);
+
+        let alert = AlertResponse::new(
+            "NWS".to_string(),
+            "Winter Storm Warning".to_string(),
+            1700000000,
+            1700100000,
+            "Heavy snow".to_string(),
+            None,
+        );
+        let forecast = create_test_forecast(Some(20.0), vec![alert.clone()], 0.0);
+        let mut job = create_default_notify_config();
+        job.on_alert = true;
+        let device = create_test_device("metric");
+        let mut other = create_test_device("metric");
+        other.id = "device-2".to_string();
+
+        let now = 1700000100;
+        let dedup = AlertDedup::load(&repo, &forecast, now).await;
+        assert!(device_should_notify(
+            &forecast,
+            &job,
+            &device,
+            "metric",
+            &dedup.ledger
+        ));
+        dedup
+            .record("Test City", &[&alert], std::slice::from_ref(&device.id))
+            .await;
+
+        let dedup = AlertDedup::load(&repo, &forecast, now + 3600).await;
+        assert!(!device_should_notify(
+            &forecast,
+            &job,
+            &device,
+            "metric",
+            &dedup.ledger
+        ));
+        // A device the alert hasn't reached yet still gets it
+        assert!(device_should_notify(
+            &forecast,
+            &job,
+            &other,
+            "metric",
+            &dedup.ledger
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_screen_forecast_keeps_alerts_when_an_hour_is_rejected() {
+        let pool = crate::db::create_pool(&crate::db::DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+        })
+        .await
+        .unwrap();
+        crate::db::run_migrations(&pool, &Default::default())
+            .await
+            .unwrap();
+        let anomalies = SqliteAnomalyRepository::new(pool);
+
+        let alert = AlertResponse::new(
+            "NWS".to_string(),
+            "Tornado Warning".to_string(),
+            1700000000,
+            1700100000,
+            "Take shelter".to_string(),
+            None,
+        );
+        let mut forecast = create_test_forecast(Some(20.0), vec![alert], 0.0);
+        forecast.hourly = (0..4)
+            .map(|i| HourlyForecastResponse {
+                timestamp: 1700003600 + i * 3600,
+                temperature: if i == 2 { 95.0 } else { 20.0 },
+                feels_like: 20.0,
+                humidity: 60,
+                pressure: 1013,
+                uv_index: 0.0,
+                clouds: 0,
+                wind_speed: 3.0,
+                wind_direction: 180,
+                wind_gust: None,
+                precipitation_probability: 0.0,
+                rain_volume: None,
+                snow_volume: None,
+                description: "clear sky".to_string(),
+                icon: "01d".to_string(),
+                condition_id: Some(800),
+                condition_main: Some("Clear".to_string()),
+                local_time: None,
+                is_day: None,
+            })
+            .collect();
+
+        let screened = screen_forecast(
+            &anomalies,
+            &ValidationConfig::default(),
+            &forecast,
+            "metric",
+            true,
+            1700000100,
+        )
+        .await;
+        assert_eq!(screened.hourly.len(), 3);
+        assert!(screened.hourly.iter().all(|h| h.temperature == 20.0));
+        assert!(screened.current.is_some());
+
+        let message = build_notification_message(
+            &screened,
+            &ConditionStylesConfig::default(),
+            DEFAULT_LANG,
+            &BriefingExtras::default(),
+        );
+        assert_eq!(message.priority, Priority::Urgent);
+        assert!(message.body.contains("Tornado Warning"));
+    }
+
     fn create_default_notify_config() -> super::super::jobs::NotifyConfig {
         super::super::jobs::NotifyConfig {
             on_run: false,
@@ -542,6 +2346,15 @@ mod tests {
             on_precipitation: false,
             cold_threshold: None,
             heat_threshold: None,
+            wind_threshold: None,
+            gust_threshold: None,
+            snow_threshold_mm: None,
+            snow_window_hours: None,
+            condition: None,
+            min_severity: None,
+            compare_yesterday: false,
+            include_clothing: false,
+            moon_events: false,
         }
     }
 
@@ -564,18 +2377,39 @@ mod tests {
 
     #[test]
     fn test_should_notify_on_alert_with_alerts() {
-        let alerts = vec![AlertResponse {
-            sender: "NWS".to_string(),
-            event: "Heat Advisory".to_string(),
-            start: 1700000000,
-            end: 1700100000,
-            description: "Heat warning".to_string(),
-            tags: None,
-        }];
+        let alerts = vec![AlertResponse::new(
+            "NWS".to_string(),
+            "Heat Advisory".to_string(),
+            1700000000,
+            1700100000,
+            "Heat warning".to_string(),
+            None,
+        )];
+        let forecast = create_test_forecast(Some(35.0), alerts, 0.0);
+        let mut config = create_default_notify_config();
+        config.on_alert = true;
+
+        assert!(should_notify_for_forecast(&forecast, &config));
+    }
+
+    #[test]
+    fn test_should_notify_on_alert_min_severity() {
+        let alerts = vec![AlertResponse::new(
+            "NWS".to_string(),
+            "Heat Advisory".to_string(),
+            1700000000,
+            1700100000,
+            "Heat warning".to_string(),
+            None,
+        )];
         let forecast = create_test_forecast(Some(35.0), alerts, 0.0);
         let mut config = create_default_notify_config();
         config.on_alert = true;
+        config.min_severity = Some(AlertSeverity::Severe);
+
+        assert!(!should_notify_for_forecast(&forecast, &config));
 
+        config.min_severity = Some(AlertSeverity::Moderate);
         assert!(should_notify_for_forecast(&forecast, &config));
     }
 
@@ -642,6 +2476,205 @@ mod tests {
         assert!(!should_notify_for_forecast(&forecast, &config));
     }
 
+    #[test]
+    fn test_should_notify_wind_thresholds() {
+        let mut forecast = create_test_forecast(Some(20.0), vec![], 0.0);
+        let mut config = create_default_notify_config();
+        config.wind_threshold = Some(10.0);
+        config.gust_threshold = Some(15.0);
+        assert!(!should_notify_for_forecast(&forecast, &config));
+
+        // Daily-only forecasts fall back to today's wind
+        forecast.daily[0].wind_gust = Some(16.0);
+        assert!(should_notify_for_forecast(&forecast, &config));
+
+        forecast.daily[0].wind_gust = None;
+        forecast.hourly = (0..30)
+            .map(|i| HourlyForecastResponse {
+                timestamp: 1700000000 + i * 3600,
+                temperature: 20.0,
+                feels_like: 20.0,
+                humidity: 60,
+                pressure: 1013,
+                uv_index: 0.0,
+                clouds: 0,
+                wind_speed: if i == 12 { 11.0 } else { 3.0 },
+                wind_direction: 180,
+                wind_gust: None,
+                precipitation_probability: 0.0,
+                rain_volume: None,
+                snow_volume: None,
+                description: "clear sky".to_string(),
+                icon: "01d".to_string(),
+                condition_id: Some(800),
+                condition_main: Some("Clear".to_string()),
+                local_time: None,
+                is_day: None,
+            })
+            .collect();
+        assert!(should_notify_for_forecast(&forecast, &config));
+
+        // Beyond the next 24 hours
+        forecast.hourly[12].wind_speed = 3.0;
+        forecast.hourly[27].wind_speed = 11.0;
+        assert!(!should_notify_for_forecast(&forecast, &config));
+    }
+
+    #[test]
+    fn test_should_notify_snow_threshold() {
+        let mut forecast = create_test_forecast(Some(-2.0), vec![], 0.0);
+        let mut config = create_default_notify_config();
+        config.snow_threshold_mm = Some(50.0);
+
+        let tomorrow = DailyForecastResponse {
+            snow_volume: Some(40.0),
+            ..forecast.daily[0].clone()
+        };
+        forecast.daily[0].snow_volume = Some(20.0);
+        forecast.daily.push(tomorrow);
+        assert!(!should_notify_for_forecast(&forecast, &config));
+
+        config.snow_window_hours = Some(48);
+        assert!(should_notify_for_forecast(&forecast, &config));
+    }
+
+    #[test]
+    fn test_moon_events() {
+        assert_eq!(MoonEvent::from_phase(0.5), Some(MoonEvent::Full));
+        assert_eq!(MoonEvent::from_phase(0.99), Some(MoonEvent::New));
+        assert_eq!(MoonEvent::from_phase(0.25), None);
+
+        let mut forecast = create_test_forecast(Some(20.0), vec![], 0.0);
+        let mut config = create_default_notify_config();
+        assert!(!should_notify_for_forecast(&forecast, &config));
+        config.moon_events = true;
+        assert!(should_notify_for_forecast(&forecast, &config));
+
+        // 17:42 in Chicago
+        forecast.daily[0].moonrise = Some(1700005320);
+        let extras = BriefingExtras {
+            moon: Some(MoonEvent::Full),
+            ..Default::default()
+        };
+        let styles = ConditionStylesConfig::default();
+        let message = build_notification_message(&forecast, &styles, "en", &extras);
+        assert!(message.body.ends_with("Full moon \u{2022} rises 17:42"));
+    }
+
+    #[test]
+    fn test_build_notification_message_condition_tags() {
+        let styles = ConditionStylesConfig {
+            emoji: true,
+            ..Default::default()
+        };
+
+        let forecast = create_test_forecast(Some(20.0), vec![], 0.0);
+        let message =
+            build_notification_message(&forecast, &styles, "en", &BriefingExtras::default());
+        assert_eq!(message.tags, vec!["sunny", "weather"]);
+        assert_eq!(message.title, "\u{2600}\u{FE0F} Chicago, US");
+
+        // The condition code wins over the icon: shower rain shares the
+        // drizzle icon, and snow under a clear-sky icon is still snow
+        let mut forecast = create_test_forecast(Some(20.0), vec![], 0.0);
+        let current = forecast.current.as_mut().unwrap();
+        current.icon = "09d".to_string();
+        current.condition_id = Some(521);
+        let message =
+            build_notification_message(&forecast, &styles, "en", &BriefingExtras::default());
+        assert_eq!(message.tags, vec!["umbrella", "weather"]);
+        assert_eq!(message.title, "\u{1F327}\u{FE0F} Chicago, US");
+
+        let current = forecast.current.as_mut().unwrap();
+        current.icon = "01d".to_string();
+        current.condition_id = Some(601);
+        let message =
+            build_notification_message(&forecast, &styles, "en", &BriefingExtras::default());
+        assert_eq!(message.tags, vec!["snowflake", "weather"]);
+        assert_eq!(message.title, "\u{2744}\u{FE0F} Chicago, US");
+
+        // Without a code, the icon decides
+        let current = forecast.current.as_mut().unwrap();
+        current.icon = "09d".to_string();
+        current.condition_id = None;
+        let message =
+            build_notification_message(&forecast, &styles, "en", &BriefingExtras::default());
+        assert_eq!(message.tags, vec!["umbrella", "weather"]);
+        assert_eq!(message.title, "\u{1F326}\u{FE0F} Chicago, US");
+
+        let alerts = vec![AlertResponse::new(
+            "NWS".to_string(),
+            "Tornado Warning".to_string(),
+            1700000000,
+            1700100000,
+            "Take shelter".to_string(),
+            None,
+        )];
+        let forecast = create_test_forecast(Some(20.0), alerts, 0.0);
+        let message =
+            build_notification_message(&forecast, &styles, "en", &BriefingExtras::default());
+        assert_eq!(message.tags, vec!["warning", "weather"]);
+    }
+
+    #[test]
+    fn test_build_notification_message_localized_template() {
+        let styles = ConditionStylesConfig::default();
+        let forecast = create_test_forecast(Some(20.0), vec![], 0.5);
+        let message =
+            build_notification_message(&forecast, &styles, "es", &BriefingExtras::default());
+        assert!(message.body.contains("humedad 65%"));
+        assert!(message.body.contains("precip. 50%"));
+    }
+
+    #[test]
+    fn test_build_notification_message_emoji_off_by_default() {
+        let styles = ConditionStylesConfig::default();
+        let forecast = create_test_forecast(Some(20.0), vec![], 0.0);
+        let message =
+            build_notification_message(&forecast, &styles, "en", &BriefingExtras::default());
+        assert_eq!(message.title, "Chicago, US");
+    }
+
+    #[test]
+    fn test_build_notification_message_job_wording() {
+        let styles = ConditionStylesConfig::default();
+        let forecast = create_test_forecast(Some(20.0), vec![], 0.0);
+        let mut job = ForecastJob::new("School run", "Chicago", "0 30 7 * * *");
+        job.title_template = Some("\u{1F3EB} {name} ({city})".to_string());
+        job.body_prefix = Some("Morning drop-off".to_string());
+        job.body_suffix = Some("Bring the umbrella".to_string());
+        let extras = BriefingExtras {
+            wording: JobWording::of(&job),
+            ..Default::default()
+        };
+        let message = build_notification_message(&forecast, &styles, "en", &extras);
+        assert_eq!(message.title, "\u{1F3EB} School run (Chicago)");
+        assert!(message.body.starts_with("Morning drop-off\n20\u{00B0}"));
+        assert!(message.body.ends_with("\nBring the umbrella"));
+
+        job.title_template = Some("{emoji} {city} at {time}".to_string());
+        assert!(validate_title_template(&job).is_err());
+        job.title_template = Some("{emoji} {city}".to_string());
+        assert!(validate_title_template(&job).is_ok());
+    }
+
+    #[test]
+    fn test_yesterday_comparison() {
+        let strings = template_strings("en");
+        assert_eq!(
+            yesterday_comparison(15.2, 22.4, strings),
+            "7\u{00B0} colder than yesterday"
+        );
+        assert_eq!(
+            yesterday_comparison(24.0, 21.6, strings),
+            "2\u{00B0} warmer than yesterday"
+        );
+        assert_eq!(
+            yesterday_comparison(20.4, 19.8, strings),
+            "Same high as yesterday"
+        );
+    }
+
     #[test]
     fn test_should_notify_no_current_weather() {
         let forecast = create_test_forecast(None, vec![], 0.0);
@@ -2,11 +2,16 @@ use std::collections::HashMap;
 
 use axum::{extract::State, Json};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
+use crate::api_budget::BudgetFeature;
+use crate::cache::CacheStats;
+use crate::client_keys::{ClientKeyUsage, KeyTraffic};
 use crate::db::history_repo::CityHistoryStats;
+use crate::ops_stats::OpsStatsResponse;
 use crate::AppState;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StatsResponse {
     pub api_budget: ApiBudgetStats,
@@ -18,22 +23,56 @@ pub struct StatsResponse {
     pub database: DatabaseStats,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiBudgetStats {
     pub daily_limit: u32,
     pub used_today: u32,
     pub remaining_today: u32,
+    pub keys_configured: usize,
+    pub keys_available: usize,
+    pub reset_timezone: String,
+    pub features: Vec<FeatureBudgetStats>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureBudgetStats {
+    pub feature: &'static str,
+    pub used_today: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientKeyStatsResponse {
+    pub reset_timezone: String,
+    pub keys: Vec<ClientKeyUsage>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageResponse {
+    /// When counting started (server startup)
+    pub since: i64,
+    pub keys: Vec<KeyTraffic>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStatsResponse {
+    pub caches: Vec<CacheStats>,
+}
+
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct HistoryStatsResponse {
     pub total_records: i64,
     pub cities: Vec<CityHistoryStats>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DeviceStats {
     pub total: usize,
@@ -41,14 +80,14 @@ pub struct DeviceStats {
     pub by_platform: HashMap<String, usize>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SchedulerStats {
     pub total_jobs: usize,
     pub enabled_jobs: usize,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BackfillConfigStats {
     pub enabled: bool,
@@ -57,21 +96,56 @@ pub struct BackfillConfigStats {
     pub cron: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DatabaseStats {
     pub size_bytes: u64,
     pub size_human: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub built_at: i64,
+    pub started_at: i64,
+    pub uptime_secs: i64,
+    pub providers: ProviderStatus,
+    pub channels: ChannelStatus,
+    pub job_count: usize,
+    pub device_count: usize,
+    /// When the last history backfill run finished, if one has since startup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_backfill: Option<i64>,
+}
+
+/// Which upstream data sources are configured
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStatus {
+    pub openweathermap: bool,
+    pub nws: bool,
+}
+
+/// Which delivery channels and client interfaces are enabled
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelStatus {
+    pub push: bool,
+    pub webhooks: bool,
+    pub grpc: bool,
+    pub dashboard: bool,
+}
+
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TileUsageStats {
     pub owm_tiles: TileBudget,
     pub google_maps_tiles: TileBudget,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TileBudget {
     pub used_today: i64,
@@ -98,25 +172,58 @@ fn get_db_size(url: &str) -> (u64, String) {
     }
 }
 
-pub async fn get_stats(State(state): State<AppState>) -> Json<StatsResponse> {
-    let api_budget = ApiBudgetStats {
+/// Today's OpenWeatherMap budget usage, overall and per feature
+pub fn api_budget_stats(state: &AppState) -> ApiBudgetStats {
+    ApiBudgetStats {
         daily_limit: state.api_budget.daily_limit(),
         used_today: state.api_budget.used_today(),
         remaining_today: state.api_budget.remaining(),
-    };
+        keys_configured: state.api_keys.len(),
+        keys_available: state.api_keys.available(),
+        reset_timezone: state.api_budget.reset_timezone().to_string(),
+        features: BudgetFeature::ALL
+            .iter()
+            .map(|&feature| FeatureBudgetStats {
+                feature: feature.key(),
+                used_today: state.api_budget.used_by(feature),
+                limit: state.api_budget.feature_limit(feature),
+            })
+            .collect(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats",
+    tag = "stats",
+    responses((status = 200, description = "Usage and storage statistics", body = StatsResponse)),
+    security(("bearer" = []))
+)]
+pub async fn get_stats(State(state): State<AppState>) -> Json<StatsResponse> {
+    let api_budget = api_budget_stats(&state);
 
-    let history = match state.history_service.get_stats().await {
-        Ok(stats) => HistoryStatsResponse {
+    let features = &state.config.features;
+    let history = if features.history {
+        state.history_service.get_stats().await.ok()
+    } else {
+        None
+    };
+    let history = match history {
+        Some(stats) => HistoryStatsResponse {
             total_records: stats.total_records,
             cities: stats.cities,
         },
-        Err(_) => HistoryStatsResponse {
+        None => HistoryStatsResponse {
             total_records: 0,
             cities: Vec::new(),
         },
     };
 
-    let all_devices = state.devices_service.get_all().await;
+    let all_devices = if features.devices {
+        state.devices_service.get_all().await
+    } else {
+        Vec::new()
+    };
     let enabled = all_devices.iter().filter(|d| d.enabled).count();
     let mut by_platform: HashMap<String, usize> = HashMap::new();
     for device in &all_devices {
@@ -130,7 +237,11 @@ pub async fn get_stats(State(state): State<AppState>) -> Json<StatsResponse> {
         by_platform,
     };
 
-    let jobs = state.scheduler_service.get_jobs().await;
+    let jobs = if features.scheduler {
+        state.scheduler_service.get_jobs().await
+    } else {
+        Vec::new()
+    };
     let scheduler = SchedulerStats {
         total_jobs: jobs.len(),
         enabled_jobs: jobs.iter().filter(|j| j.enabled).count(),
@@ -179,12 +290,129 @@ pub async fn get_stats(State(state): State<AppState>) -> Json<StatsResponse> {
     })
 }
 
-#[derive(Deserialize)]
+/// Instance overview: build, uptime, what's configured, and how much is
+/// scheduled
+/// GET /status
+#[utoipa::path(
+    get,
+    path = "/api/v1/status",
+    tag = "stats",
+    responses((status = 200, description = "Instance overview", body = StatusResponse)),
+    security(("bearer" = []))
+)]
+pub async fn get_status(State(state): State<AppState>) -> Json<StatusResponse> {
+    let config = &state.config;
+    let now = chrono::Utc::now().timestamp();
+
+    let job_count = if config.features.scheduler {
+        state.scheduler_service.get_jobs().await.len()
+    } else {
+        0
+    };
+    let device_count = if config.features.devices {
+        state.devices_service.get_all().await.len()
+    } else {
+        0
+    };
+
+    Json(StatusResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: env!("WEATHRS_GIT_COMMIT"),
+        built_at: env!("WEATHRS_BUILT_AT").parse().unwrap_or(0),
+        started_at: state.started_at,
+        uptime_secs: now - state.started_at,
+        providers: ProviderStatus {
+            openweathermap: !config.owm_api_keys().is_empty(),
+            nws: config.nws.enabled,
+        },
+        channels: ChannelStatus {
+            push: config.features.devices,
+            webhooks: config.features.scheduler,
+            grpc: config.grpc.enabled,
+            dashboard: config.dashboard.enabled,
+        },
+        job_count,
+        device_count,
+        last_backfill: state.history_service.last_backfill(),
+    })
+}
+
+/// Today's request counts for each client API key
+/// GET /stats/keys
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats/keys",
+    tag = "stats",
+    responses((status = 200, description = "Today's usage per client key", body = ClientKeyStatsResponse)),
+    security(("bearer" = []))
+)]
+pub async fn get_client_key_stats(State(state): State<AppState>) -> Json<ClientKeyStatsResponse> {
+    Json(ClientKeyStatsResponse {
+        reset_timezone: state.client_keys.reset_timezone().to_string(),
+        keys: state.client_keys.usage(chrono::Utc::now()),
+    })
+}
+
+/// Requests, error rates, and busiest routes per API key since startup
+/// GET /admin/usage
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/usage",
+    tag = "stats",
+    responses((status = 200, description = "Request counts per API key", body = UsageResponse)),
+    security(("bearer" = []))
+)]
+pub async fn get_usage(State(state): State<AppState>) -> Json<UsageResponse> {
+    Json(UsageResponse {
+        since: state.started_at,
+        keys: state.client_keys.traffic(),
+    })
+}
+
+/// Requests, upstream calls, cache hits, notifications, and job runs over
+/// the last hour and day, for dashboards without Prometheus
+/// GET /admin/stats
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/stats",
+    tag = "stats",
+    responses((status = 200, description = "Rolling operational counters", body = OpsStatsResponse)),
+    security(("bearer" = []))
+)]
+pub async fn get_ops_stats(State(state): State<AppState>) -> Json<OpsStatsResponse> {
+    Json(state.ops_stats.snapshot(chrono::Utc::now().timestamp()))
+}
+
+/// Hit rates and memory use of the in-memory caches
+/// GET /stats/cache
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats/cache",
+    tag = "stats",
+    responses((status = 200, description = "Counters for each cache", body = CacheStatsResponse)),
+    security(("bearer" = []))
+)]
+pub async fn get_cache_stats(State(state): State<AppState>) -> Json<CacheStatsResponse> {
+    let mut caches = vec![state.weather_service.cache_stats()];
+    caches.extend(state.forecast_service.cache_stats());
+    Json(CacheStatsResponse { caches })
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct TileReport {
     pub owm_tiles: i64,
     pub google_maps_tiles: i64,
 }
 
+/// Add map tiles the app loaded to today's tile usage
+/// POST /stats/tiles
+#[utoipa::path(
+    post,
+    path = "/api/v1/stats/tiles",
+    tag = "stats",
+    request_body = TileReport,
+    responses((status = 200, description = "Recorded", body = Object))
+)]
 pub async fn report_tiles(
     State(state): State<AppState>,
     Json(report): Json<TileReport>,
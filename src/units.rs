@@ -0,0 +1,113 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Measurement system for temperatures and speeds, as OpenWeatherMap names it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// Celsius, m/s
+    #[default]
+    Metric,
+    /// Fahrenheit, mph
+    Imperial,
+    /// Kelvin, m/s
+    Standard,
+}
+
+impl Units {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Metric => "metric",
+            Self::Imperial => "imperial",
+            Self::Standard => "standard",
+        }
+    }
+
+    /// A Celsius temperature in these units
+    pub fn temperature_from_celsius(self, celsius: f64) -> f64 {
+        match self {
+            Self::Metric => celsius,
+            Self::Imperial => celsius * 9.0 / 5.0 + 32.0,
+            Self::Standard => celsius + 273.15,
+        }
+    }
+
+    /// A speed in m/s in these units (mph for imperial)
+    pub fn speed_from_ms(self, ms: f64) -> f64 {
+        match self {
+            Self::Imperial => ms / 0.44704,
+            Self::Metric | Self::Standard => ms,
+        }
+    }
+
+    /// A temperature in these units, in Celsius
+    pub fn temperature_to_celsius(self, temperature: f64) -> f64 {
+        match self {
+            Self::Metric => temperature,
+            Self::Imperial => (temperature - 32.0) * 5.0 / 9.0,
+            Self::Standard => temperature - 273.15,
+        }
+    }
+
+    /// A speed in these units, in m/s
+    pub fn speed_to_ms(self, speed: f64) -> f64 {
+        match self {
+            Self::Imperial => speed * 0.44704,
+            Self::Metric | Self::Standard => speed,
+        }
+    }
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("units must be metric, imperial, or standard (got {0:?})")]
+pub struct UnknownUnits(pub String);
+
+impl FromStr for Units {
+    type Err = UnknownUnits;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "metric" => Ok(Self::Metric),
+            "imperial" => Ok(Self::Imperial),
+            "standard" => Ok(Self::Standard),
+            _ => Err(UnknownUnits(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!("imperial".parse::<Units>().unwrap(), Units::Imperial);
+        assert_eq!(" Metric ".parse::<Units>().unwrap(), Units::Metric);
+        let err = "kelvin".parse::<Units>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "units must be metric, imperial, or standard (got \"kelvin\")"
+        );
+    }
+
+    #[test]
+    fn test_convert_from_metric() {
+        assert_eq!(Units::Imperial.temperature_from_celsius(100.0), 212.0);
+        assert_eq!(Units::Standard.temperature_from_celsius(0.0), 273.15);
+        assert!((Units::Imperial.speed_from_ms(0.44704) - 1.0).abs() < 1e-9);
+        assert_eq!(Units::Standard.speed_from_ms(3.0), 3.0);
+        assert_eq!(Units::Imperial.temperature_to_celsius(212.0), 100.0);
+        assert_eq!(Units::Standard.temperature_to_celsius(273.15), 0.0);
+        assert!((Units::Imperial.speed_to_ms(1.0) - 0.44704).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,576 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
+use std::hash::BuildHasher;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use reqwest::Response;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::api_keys::ApiKeyPool;
+use crate::config::{ConditionStylesConfig, UpstreamAlertConfig, UpstreamConfig};
+use crate::notifications::{AdminNotifier, ConditionGroup, NotificationMessage, Priority};
+
+#[derive(Error, Debug)]
+pub enum UpstreamError {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+
+    #[error("OpenWeatherMap is failing, calls paused for {retry_after}s")]
+    CircuitOpen { retry_after: u64 },
+}
+
+/// Seconds to suggest waiting when an upstream rate limits us without saying
+/// how long
+const DEFAULT_RATE_LIMIT_RETRY_SECS: u64 = 60;
+
+/// Longest upstream message passed on to clients
+const MAX_DETAILS_LEN: usize = 300;
+
+/// How often OWM failure rates are compared against the alert threshold
+const ALERT_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A non-success answer from an upstream API, kept so clients can tell its
+/// rate limiting or outages apart from problems with their own request
+#[derive(Error, Debug)]
+#[error("{context} failed with upstream status {status}")]
+pub struct UpstreamFailure {
+    /// Which call failed, e.g. "Geocoding"
+    pub context: &'static str,
+    pub status: u16,
+    /// The upstream's own message, e.g. OWM's `{"message": ...}`
+    pub message: String,
+    /// From the upstream's `Retry-After` header
+    pub retry_after: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct UpstreamMessage {
+    message: String,
+}
+
+impl UpstreamFailure {
+    /// Read a failed response's status, message, and `Retry-After`
+    pub async fn read(context: &'static str, response: Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<UpstreamMessage>(&body)
+            .map(|m| m.message)
+            .unwrap_or(body);
+        Self {
+            context,
+            status,
+            message: message.trim().chars().take(MAX_DETAILS_LEN).collect(),
+            retry_after,
+        }
+    }
+
+    /// 503 when the upstream rate limits us, 502 when it fails, and 400 when
+    /// it rejects the request
+    pub fn status_code(&self) -> StatusCode {
+        match self.status {
+            429 => StatusCode::SERVICE_UNAVAILABLE,
+            s if s >= 500 => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        match self.status {
+            429 => "UPSTREAM_RATE_LIMITED",
+            s if s >= 500 => "UPSTREAM_ERROR",
+            _ => "UPSTREAM_REJECTED",
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<u64> {
+        match self.status {
+            429 => Some(self.retry_after.unwrap_or(DEFAULT_RATE_LIMIT_RETRY_SECS)),
+            _ => self.retry_after,
+        }
+    }
+
+    /// The upstream's message, if it gave one
+    pub fn details(&self) -> Option<String> {
+        (!self.message.is_empty()).then(|| self.message.clone())
+    }
+}
+
+/// Macro to convert `UpstreamError` into a service error with `RequestError`
+/// and `UpstreamUnavailable` variants
+#[macro_export]
+macro_rules! impl_from_upstream {
+    ($error_type:ty) => {
+        impl From<$crate::upstream::UpstreamError> for $error_type {
+            fn from(err: $crate::upstream::UpstreamError) -> Self {
+                match err {
+                    $crate::upstream::UpstreamError::Request(e) => Self::RequestError(e),
+                    $crate::upstream::UpstreamError::CircuitOpen { retry_after } => {
+                        Self::UpstreamUnavailable { retry_after }
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Whether an attempt failed in a way that says OWM itself is unhealthy:
+/// a timeout, a failed connection, or a 5xx. These are retried and count
+/// against the circuit breaker; 4xx answers are neither.
+pub fn is_upstream_failure(outcome: &reqwest::Result<Response>) -> bool {
+    match outcome {
+        Ok(response) => response.status().is_server_error(),
+        Err(e) => e.is_timeout() || e.is_connect(),
+    }
+}
+
+/// How idempotent GETs are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// Timeout for each attempt, so one hung call can't use up the whole
+    /// request timeout
+    pub attempt_timeout: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(config: &UpstreamConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            attempt_timeout: Duration::from_secs(config.attempt_timeout_secs),
+        }
+    }
+
+    /// Wait before retry number `attempt` (0-based): exponential backoff with
+    /// equal jitter, so between half and all of `base_delay * 2^attempt`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self.base_delay.saturating_mul(1 << attempt.min(10));
+        let half = ceiling / 2;
+        half + half.mul_f64(jitter())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(&UpstreamConfig::default())
+    }
+}
+
+/// Uniform value in [0, 1) from std's randomly seeded hasher
+fn jitter() -> f64 {
+    (RandomState::new().hash_one(0u8) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Stops calling OWM while it is failing.
+///
+/// Opens once `failure_percent` of the last `window` calls failed. While
+/// open, calls are refused for `open_secs`; after that one probe call is let
+/// through, which closes the breaker on success or reopens it on failure.
+pub struct CircuitBreaker {
+    failure_percent: u8,
+    window: usize,
+    open_secs: i64,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    /// Outcomes of recent calls, `true` for a failure
+    recent: VecDeque<bool>,
+    open_until: Option<i64>,
+    probing: bool,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &UpstreamConfig) -> Self {
+        Self {
+            failure_percent: config.breaker_failure_percent,
+            window: config.breaker_window.max(1),
+            open_secs: config.breaker_open_secs as i64,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.failure_percent > 0
+    }
+
+    /// Seconds until calls resume, if the breaker is open
+    pub fn open_for(&self, now: i64) -> Option<u64> {
+        let state = self.state.lock().unwrap();
+        state
+            .open_until
+            .filter(|until| *until > now)
+            .map(|until| (until - now) as u64)
+    }
+
+    /// Admit a call, or return the seconds until calls resume. The first call
+    /// after the open period becomes the probe.
+    pub fn check(&self, now: i64) -> Result<(), u64> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            Some(until) if until > now => Err((until - now) as u64),
+            Some(_) => {
+                // Re-arm so other callers keep waiting while the probe runs;
+                // a probe that never reports back just allows another later
+                state.open_until = Some(now + self.open_secs);
+                state.probing = true;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Record the outcome of an admitted call
+    pub fn record(&self, failed: bool, now: i64) {
+        if !self.enabled() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.probing {
+            state.probing = false;
+            if failed {
+                state.open_until = Some(now + self.open_secs);
+                tracing::warn!(
+                    open_secs = self.open_secs,
+                    "OpenWeatherMap probe failed, circuit breaker stays open"
+                );
+            } else {
+                state.open_until = None;
+                state.recent.clear();
+                tracing::info!("OpenWeatherMap recovered, circuit breaker closed");
+            }
+            return;
+        }
+        if state.open_until.is_some() {
+            // A call that was in flight when the breaker opened
+            return;
+        }
+
+        state.recent.push_back(failed);
+        if state.recent.len() > self.window {
+            state.recent.pop_front();
+        }
+        let failures = state.recent.iter().filter(|f| **f).count();
+        if state.recent.len() == self.window
+            && failures * 100 >= self.window * self.failure_percent as usize
+        {
+            state.open_until = Some(now + self.open_secs);
+            metrics::counter!(crate::metrics::UPSTREAM_CIRCUIT_OPENED).increment(1);
+            tracing::warn!(
+                failures = failures,
+                window = self.window,
+                open_secs = self.open_secs,
+                "OpenWeatherMap error rate too high, circuit breaker opened"
+            );
+        }
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(&UpstreamConfig::default())
+    }
+}
+
+/// Which OWM API a request URL path belongs to, for alerts
+pub fn api_name(path: &str) -> &'static str {
+    if path.contains("/onecall/timemachine") {
+        "One Call timemachine"
+    } else if path.contains("/onecall") {
+        "One Call"
+    } else if path.contains("/air_pollution") {
+        "Air Pollution"
+    } else if path.starts_with("/geo/") {
+        "Geocoding"
+    } else if path.starts_with("/map/") || path.contains("/tile") {
+        "Map tiles"
+    } else {
+        "OpenWeatherMap"
+    }
+}
+
+/// An OWM API failing at or above the alert threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamOutage {
+    pub api: &'static str,
+    pub failure_percent: u32,
+    pub calls: u32,
+}
+
+/// Call outcomes per OWM API over a sliding window of whole minutes.
+///
+/// An API alerts once when its failure rate reaches `failure_percent`, and
+/// again only after it has dropped back below.
+pub struct UpstreamHealth {
+    failure_percent: u8,
+    window_mins: i64,
+    min_calls: u32,
+    state: Mutex<HashMap<&'static str, ApiHealth>>,
+}
+
+#[derive(Default)]
+struct ApiHealth {
+    /// (minute since the epoch, calls, failures), oldest first
+    minutes: VecDeque<(i64, u32, u32)>,
+    alerted: bool,
+}
+
+impl UpstreamHealth {
+    pub fn new(config: &UpstreamAlertConfig) -> Self {
+        Self {
+            failure_percent: config.failure_percent,
+            window_mins: i64::from(config.window_mins.max(1)),
+            min_calls: config.min_calls.max(1),
+            state: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.failure_percent > 0
+    }
+
+    pub fn window_mins(&self) -> i64 {
+        self.window_mins
+    }
+
+    /// Record the outcome of one call to `api`
+    pub fn record(&self, api: &'static str, failed: bool, now: i64) {
+        if !self.enabled() {
+            return;
+        }
+        let minute = now.div_euclid(60);
+        let mut state = self.state.lock().unwrap();
+        let health = state.entry(api).or_default();
+        match health.minutes.back_mut() {
+            Some(last) if last.0 == minute => {
+                last.1 += 1;
+                last.2 += u32::from(failed);
+            }
+            _ => health.minutes.push_back((minute, 1, u32::from(failed))),
+        }
+    }
+
+    /// APIs that reached the threshold since the last check
+    pub fn new_outages(&self, now: i64) -> Vec<UpstreamOutage> {
+        if !self.enabled() {
+            return Vec::new();
+        }
+        let oldest = now.div_euclid(60) - self.window_mins + 1;
+        let mut state = self.state.lock().unwrap();
+        let mut outages = Vec::new();
+        for (&api, health) in state.iter_mut() {
+            while health.minutes.front().is_some_and(|m| m.0 < oldest) {
+                health.minutes.pop_front();
+            }
+            let (calls, failures) = health
+                .minutes
+                .iter()
+                .fold((0, 0), |(c, f), m| (c + m.1, f + m.2));
+            let failing = calls >= self.min_calls
+                && failures * 100 >= calls * u32::from(self.failure_percent);
+
+            if failing && !health.alerted {
+                outages.push(UpstreamOutage {
+                    api,
+                    failure_percent: failures * 100 / calls,
+                    calls,
+                });
+            } else if !failing && health.alerted {
+                tracing::info!(
+                    api = api,
+                    "OpenWeatherMap API failure rate back below alert threshold"
+                );
+            }
+            health.alerted = failing;
+        }
+        outages.sort_by_key(|o| o.api);
+        outages
+    }
+}
+
+/// Start a background task that notifies admins when an OWM API's failure
+/// rate crosses the configured threshold
+pub fn start_upstream_alert_task(
+    api_keys: Arc<ApiKeyPool>,
+    admin: Arc<AdminNotifier>,
+    config: UpstreamAlertConfig,
+    styles: ConditionStylesConfig,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ALERT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let health = api_keys.health();
+            let outages = health.new_outages(chrono::Utc::now().timestamp());
+            if outages.is_empty() {
+                continue;
+            }
+
+            for outage in outages {
+                let body = format!(
+                    "{} API failing {}% of requests over the last {} minutes ({} calls)",
+                    outage.api,
+                    outage.failure_percent,
+                    health.window_mins(),
+                    outage.calls
+                );
+                tracing::warn!(api = outage.api, "{}", body);
+                let message = NotificationMessage {
+                    title: "OpenWeatherMap is failing".to_string(),
+                    subtitle: None,
+                    body,
+                    priority: Priority::High,
+                    tags: styles.tag(ConditionGroup::Alert).into_iter().collect(),
+                    city: None,
+                    events: vec![],
+                };
+                admin.notify(&config.admin_tokens, &message).await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(&UpstreamConfig {
+            breaker_failure_percent: 50,
+            breaker_window: 4,
+            breaker_open_secs: 30,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_upstream_failure_classification() {
+        let failure = |status| UpstreamFailure {
+            context: "One Call",
+            status,
+            message: String::new(),
+            retry_after: None,
+        };
+        let limited = failure(429);
+        assert_eq!(limited.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(limited.error_code(), "UPSTREAM_RATE_LIMITED");
+        assert_eq!(limited.retry_after(), Some(DEFAULT_RATE_LIMIT_RETRY_SECS));
+        assert_eq!(limited.details(), None);
+
+        assert_eq!(failure(503).status_code(), StatusCode::BAD_GATEWAY);
+        assert_eq!(failure(400).error_code(), "UPSTREAM_REJECTED");
+        assert_eq!(
+            failure(400).to_string(),
+            "One Call failed with upstream status 400"
+        );
+    }
+
+    #[test]
+    fn test_breaker_opens_and_recovers_through_probe() {
+        let breaker = breaker();
+        for failed in [true, false, true] {
+            assert!(breaker.check(1000).is_ok());
+            breaker.record(failed, 1000);
+        }
+        // Window not full yet
+        assert!(breaker.check(1000).is_ok());
+        breaker.record(true, 1000);
+        assert_eq!(breaker.check(1010), Err(20));
+        assert_eq!(breaker.open_for(1010), Some(20));
+
+        // One probe after the open period; others keep waiting
+        assert!(breaker.check(1030).is_ok());
+        assert_eq!(breaker.check(1031), Err(29));
+        breaker.record(true, 1031);
+        assert_eq!(breaker.open_for(1031), Some(30));
+
+        assert!(breaker.check(1061).is_ok());
+        breaker.record(false, 1061);
+        assert!(breaker.check(1061).is_ok());
+        assert_eq!(breaker.open_for(1061), None);
+    }
+
+    #[test]
+    fn test_outage_alerts_once_per_episode() {
+        let health = UpstreamHealth::new(&UpstreamAlertConfig {
+            failure_percent: 50,
+            window_mins: 15,
+            min_calls: 4,
+            admin_tokens: vec![],
+        });
+        let now = 1_700_000_000;
+        for failed in [true, true, false] {
+            health.record("One Call", failed, now);
+        }
+        // Too few calls to judge
+        assert!(health.new_outages(now).is_empty());
+
+        health.record("One Call", true, now + 60);
+        health.record("Geocoding", false, now + 60);
+        let outages = health.new_outages(now + 60);
+        assert_eq!(
+            outages,
+            vec![UpstreamOutage {
+                api: "One Call",
+                failure_percent: 75,
+                calls: 4,
+            }]
+        );
+        assert!(health.new_outages(now + 120).is_empty());
+
+        // Once the failures age out of the window the API can alert again
+        for _ in 0..4 {
+            health.record("One Call", false, now + 20 * 60);
+        }
+        assert!(health.new_outages(now + 20 * 60).is_empty());
+        for _ in 0..4 {
+            health.record("One Call", true, now + 21 * 60);
+        }
+        assert_eq!(health.new_outages(now + 21 * 60).len(), 1);
+
+        assert_eq!(
+            api_name("/data/3.0/onecall/timemachine"),
+            "One Call timemachine"
+        );
+        assert_eq!(api_name("/geo/1.0/zip"), "Geocoding");
+    }
+
+    #[test]
+    fn test_disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new(&UpstreamConfig {
+            breaker_failure_percent: 0,
+            ..Default::default()
+        });
+        for _ in 0..50 {
+            breaker.record(true, 1000);
+        }
+        assert!(breaker.check(1000).is_ok());
+    }
+
+    #[test]
+    fn test_backoff_grows_with_jitter() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            attempt_timeout: Duration::from_secs(10),
+        };
+        for attempt in 0..3 {
+            let ceiling = Duration::from_millis(200 << attempt);
+            let delay = policy.backoff(attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?}", delay);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::backfill::build_city_list;
+use crate::config::CacheConfig;
+use crate::devices::DevicesService;
+use crate::forecast::ForecastService;
+use crate::scheduler::SchedulerService;
+
+/// Where cache warming finds its cities and what it fetches
+pub struct WarmupSources {
+    /// `None` when the devices subsystem is disabled
+    pub devices: Option<Arc<DevicesService>>,
+    /// `None` when the scheduler is disabled
+    pub scheduler: Option<Arc<SchedulerService>>,
+    pub fallback_cities: Vec<String>,
+    /// Prefetch each enabled job's forecast as well as geocoding
+    pub forecasts: bool,
+}
+
+/// Warm the geocoding (and optionally forecast) caches in the background,
+/// so the first scheduled runs after a restart don't start cold. Cities are
+/// taken in backfill priority order; failures are logged and skipped.
+pub fn start_cache_warming(forecast_service: Arc<ForecastService>, sources: WarmupSources) {
+    tokio::spawn(async move {
+        let devices = match sources.devices {
+            Some(ref devices) => devices.get_all().await,
+            None => Vec::new(),
+        };
+        let jobs = match sources.scheduler {
+            Some(ref scheduler) => scheduler.get_jobs().await,
+            None => Vec::new(),
+        };
+        let cities = build_city_list(&devices, &jobs, &sources.fallback_cities);
+        if cities.is_empty() {
+            return;
+        }
+
+        let mut geocoded = 0;
+        for city in &cities {
+            match forecast_service.geocode(city).await {
+                Ok(_) => geocoded += 1,
+                Err(e) => {
+                    tracing::warn!(city = %city, error = %e, "Cache warming: failed to geocode")
+                }
+            }
+        }
+
+        let mut prefetched = 0;
+        if sources.forecasts {
+            for job in jobs.iter().filter(|j| j.enabled) {
+                // Same call the job makes when it runs, so it hits this entry
+                let result = if job.include_daily {
+                    forecast_service
+                        .get_daily_forecast(&job.city, &job.units)
+                        .await
+                } else {
+                    forecast_service.get_forecast(&job.city, &job.units).await
+                };
+                match result {
+                    Ok(_) => prefetched += 1,
+                    Err(e) => tracing::warn!(
+                        job = %job.name,
+                        city = %job.city,
+                        error = %e,
+                        "Cache warming: failed to prefetch forecast"
+                    ),
+                }
+            }
+        }
+
+        tracing::info!(
+            cities = cities.len(),
+            geocoded = geocoded,
+            forecasts = prefetched,
+            "Cache warming finished"
+        );
+    });
+}
+
+/// How often hot forecasts are checked for refreshing
+const HOT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Start a background task that refetches the most requested forecasts
+/// shortly before they expire, within the hourly cap and budget reserve
+/// from `config`. Does nothing unless `hot_refresh_forecasts` is set.
+pub fn start_hot_refresh_task(forecast_service: Arc<ForecastService>, config: &CacheConfig) {
+    if config.hot_refresh_forecasts == 0 {
+        return;
+    }
+    let limit = config.hot_refresh_forecasts;
+    let lead = Duration::from_secs(config.hot_refresh_lead_secs);
+    let max_per_hour = config.hot_refresh_max_per_hour;
+    let reserve_percent = config.hot_refresh_reserve_percent;
+    tokio::spawn(async move {
+        // When each refetch in the last hour happened
+        let mut recent: VecDeque<Instant> = VecDeque::new();
+        let mut interval = tokio::time::interval(HOT_REFRESH_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let now = Instant::now();
+            while recent
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= Duration::from_secs(3600))
+            {
+                recent.pop_front();
+            }
+            let allowed = max_per_hour.saturating_sub(recent.len());
+            let refreshed = forecast_service
+                .refresh_hot(limit, lead, allowed, reserve_percent)
+                .await;
+            if refreshed > 0 {
+                tracing::debug!(forecasts = refreshed, "Refreshed hot forecasts");
+                recent.extend(std::iter::repeat_n(Instant::now(), refreshed));
+            }
+        }
+    });
+}
@@ -1,18 +1,30 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::Serialize;
 use std::time::Instant;
+use utoipa::ToSchema;
 
 use super::service::{WeatherError, WeatherResponse};
-use crate::extractors::{CityParam, UnitsParam};
+use crate::display::DisplayQuery;
+use crate::error::ErrorResponse;
+use crate::extractors::{CityParam, DisplayParam, ProviderParam, UnitsParam};
+use crate::forecast::{ForecastParts, ProviderKind, ProviderQuery, DEFAULT_LANG};
+use crate::units::Units;
 use crate::AppState;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: &'static str,
     pub version: &'static str,
 }
 
 /// Health check endpoint (lightweight, for load balancers)
+/// GET /health, GET /health/live
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "Service is up", body = HealthResponse))
+)]
 pub async fn health() -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok",
@@ -20,27 +32,89 @@ pub async fn health() -> Json<HealthResponse> {
     })
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DeepHealthResponse {
     pub status: String,
     pub components: DeepHealthComponents,
     pub version: &'static str,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DeepHealthComponents {
     pub database: ComponentHealth,
     pub openweathermap: ComponentHealth,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ComponentHealth {
     pub status: String,
     pub latency_ms: u64,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub status: &'static str,
+}
+
+/// Readiness probe - 503 until the service can take traffic
+/// GET /health/ready
+///
+/// Unlike /health/deep this makes no upstream calls, so an OWM outage
+/// doesn't pull every instance out of rotation. The endpoint is public, so
+/// which check failed is only logged.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready for traffic", body = ReadinessResponse),
+        (status = 503, description = "Not ready", body = ReadinessResponse)
+    )
+)]
+pub async fn health_ready(State(state): State<AppState>) -> impl IntoResponse {
+    let database = sqlx::query_scalar::<_, i32>("SELECT 1")
+        .fetch_one(&state.db_pool)
+        .await
+        .is_ok();
+    let migrations = database
+        && crate::db::migrations_applied(&state.db_pool, &state.config.features)
+            .await
+            .unwrap_or(false);
+    let scheduler = !state.config.features.scheduler || state.scheduler_service.is_running();
+    let problems = state.config.validate();
+    let config = problems.is_empty();
+
+    let ready = database && migrations && scheduler && config;
+    if !ready {
+        tracing::warn!(
+            database = database,
+            migrations = migrations,
+            scheduler = scheduler,
+            config_problems = ?problems,
+            "Readiness check failed"
+        );
+    }
+
+    let (status_code, status) = if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+
+    (status_code, Json(ReadinessResponse { status }))
+}
+
 /// Deep health check endpoint - verifies database and OWM connectivity
 /// GET /health/deep
+#[utoipa::path(
+    get,
+    path = "/health/deep",
+    tag = "health",
+    responses(
+        (status = 200, description = "All components healthy", body = DeepHealthResponse),
+        (status = 503, description = "A component is degraded or down", body = DeepHealthResponse)
+    )
+)]
 pub async fn health_deep(State(state): State<AppState>) -> impl IntoResponse {
     let mut overall_healthy = true;
 
@@ -66,14 +140,13 @@ pub async fn health_deep(State(state): State<AppState>) -> impl IntoResponse {
     // Check OWM API with a lightweight geocoding request
     let owm_start = Instant::now();
     let owm_status = match state
-        .http_client
-        .get("https://api.openweathermap.org/geo/1.0/direct")
-        .query(&[
-            ("q", "London"),
-            ("limit", "1"),
-            ("appid", &state.config.openweathermap_api_key),
-        ])
-        .send()
+        .api_keys
+        .send(
+            state
+                .http_client
+                .get("https://api.openweathermap.org/geo/1.0/direct")
+                .query(&[("q", "London"), ("limit", "1")]),
+        )
         .await
     {
         Ok(resp) if resp.status().is_success() => ComponentHealth {
@@ -127,14 +200,59 @@ pub async fn health_deep(State(state): State<AppState>) -> impl IntoResponse {
 /// Accepts city from either path parameter or query parameter:
 /// - GET /weather?city=London&units=metric
 /// - GET /weather/{city}?units=metric
+/// - GET /weather?lat=41.88&lon=-87.63&units=metric
+#[utoipa::path(
+    get,
+    path = "/api/v1/weather/{city}",
+    tag = "weather",
+    params(("city" = String, Path, description = "City name, \"City,Country\", or US zip code"), ("units" = Option<Units>, Query, description = "metric, imperial, or standard (default from config)"), DisplayQuery, ProviderQuery),
+    responses(
+        (status = 200, description = "Current weather", body = WeatherResponse),
+        (status = 400, description = "Invalid units, display field, or provider, or request rejected by OpenWeatherMap", body = ErrorResponse),
+        (status = 404, description = "City not found", body = ErrorResponse),
+        (status = 429, description = "API budget or client key quota exhausted", body = ErrorResponse),
+        (status = 500, description = "Unexpected OpenWeatherMap response", body = ErrorResponse),
+        (status = 502, description = "OpenWeatherMap error", body = ErrorResponse),
+        (status = 503, description = "OpenWeatherMap unavailable or rate limiting", body = ErrorResponse)
+    )
+)]
 pub async fn get_weather(
     State(state): State<AppState>,
     CityParam(city): CityParam,
     UnitsParam(units): UnitsParam,
-) -> Result<Json<WeatherResponse>, WeatherError> {
-    let city = city.unwrap_or_else(|| state.config.default_city.clone());
-    let units = units.unwrap_or_else(|| state.config.units.clone());
+    DisplayParam(display): DisplayParam,
+    ProviderParam(provider): ProviderParam,
+) -> Result<Json<serde_json::Value>, WeatherError> {
+    let city = city.unwrap_or_else(|| state.config.default_location());
+    let units = units.unwrap_or_else(|| state.default_units(&city));
 
-    let weather = state.weather_service.get_weather(&city, &units).await?;
-    Ok(Json(weather))
+    // OWM has a current-weather endpoint; other providers' current conditions
+    // come with their forecast
+    let providers = &state.config.providers;
+    let provider = provider.unwrap_or(providers.default);
+    let owm_allowed = providers.default == ProviderKind::OpenWeatherMap || providers.allow_override;
+    let weather = if provider == ProviderKind::OpenWeatherMap && owm_allowed {
+        state
+            .weather_service
+            .get_weather(&city, units.as_str())
+            .await?
+    } else {
+        let forecast = state
+            .forecast_service
+            .fetch(
+                ForecastParts::Daily,
+                &city,
+                units.as_str(),
+                DEFAULT_LANG,
+                Some(provider),
+            )
+            .await?;
+        WeatherResponse::from_forecast(forecast)?
+    };
+    let mut body = serde_json::to_value(&weather).unwrap_or_default();
+    display
+        .as_ref()
+        .unwrap_or(&state.config.display)
+        .filter_weather(&mut body);
+    Ok(Json(body))
 }
@@ -7,10 +7,17 @@ use utoipa::ToSchema;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::api_budget::ApiCallBudget;
-use crate::cache::TtlCache;
+use crate::api_budget::{ApiCallBudget, BudgetFeature};
+use crate::api_keys::ApiKeyPool;
+use crate::cache::{normalize_cache_key, CacheStats, TtlCache};
 use crate::error::HttpError;
-use crate::impl_into_response;
+use crate::forecast::models::ForecastResponse;
+use crate::forecast::ForecastError;
+use crate::geocode::models::parse_geo_location;
+use crate::locations::LocationsService;
+use crate::middleware::record_cache_age;
+use crate::upstream::UpstreamFailure;
+use crate::{impl_from_upstream, impl_into_response};
 
 const OPENWEATHERMAP_API_URL: &str = "https://api.openweathermap.org/data/2.5/weather";
 
@@ -22,11 +29,21 @@ pub enum WeatherError {
     #[error("City not found: {0}")]
     CityNotFound(String),
 
-    #[error("API error: {0}")]
-    ApiError(String),
-
     #[error("Invalid API response: {0}")]
     InvalidResponse(String),
+
+    #[error("Daily API budget exhausted, retry in {retry_after}s")]
+    BudgetExhausted { retry_after: u64 },
+
+    #[error("OpenWeatherMap is unavailable, retry in {retry_after}s")]
+    UpstreamUnavailable { retry_after: u64 },
+
+    #[error(transparent)]
+    Upstream(#[from] UpstreamFailure),
+
+    /// Current conditions from a forecast provider other than OWM
+    #[error(transparent)]
+    Provider(#[from] ForecastError),
 }
 
 impl HttpError for WeatherError {
@@ -34,8 +51,11 @@ impl HttpError for WeatherError {
         match self {
             Self::CityNotFound(_) => StatusCode::NOT_FOUND,
             Self::RequestError(_) => StatusCode::BAD_GATEWAY,
-            Self::ApiError(_) => StatusCode::BAD_REQUEST,
             Self::InvalidResponse(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::BudgetExhausted { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::UpstreamUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Upstream(failure) => failure.status_code(),
+            Self::Provider(e) => e.status_code(),
         }
     }
 
@@ -43,13 +63,44 @@ impl HttpError for WeatherError {
         match self {
             Self::CityNotFound(_) => Some("CITY_NOT_FOUND"),
             Self::RequestError(_) => Some("REQUEST_ERROR"),
-            Self::ApiError(_) => Some("API_ERROR"),
             Self::InvalidResponse(_) => Some("INVALID_RESPONSE"),
+            Self::BudgetExhausted { .. } => Some("API_BUDGET_EXHAUSTED"),
+            Self::UpstreamUnavailable { .. } => Some("UPSTREAM_UNAVAILABLE"),
+            Self::Upstream(failure) => Some(failure.error_code()),
+            Self::Provider(e) => e.error_code(),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::BudgetExhausted { retry_after } | Self::UpstreamUnavailable { retry_after } => {
+                Some(*retry_after)
+            }
+            Self::Upstream(failure) => failure.retry_after(),
+            Self::Provider(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+
+    fn upstream_status(&self) -> Option<u16> {
+        match self {
+            Self::Upstream(failure) => Some(failure.status),
+            Self::Provider(e) => e.upstream_status(),
+            _ => None,
+        }
+    }
+
+    fn details(&self) -> Option<String> {
+        match self {
+            Self::Upstream(failure) => failure.details(),
+            Self::Provider(e) => e.details(),
+            _ => None,
         }
     }
 }
 
 impl_into_response!(WeatherError);
+impl_from_upstream!(WeatherError);
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WeatherResponse {
@@ -66,6 +117,28 @@ pub struct WeatherResponse {
     pub visibility: Option<u32>,
 }
 
+impl WeatherResponse {
+    /// Current conditions from a forecast, for providers without a separate
+    /// current-weather endpoint
+    pub fn from_forecast(forecast: ForecastResponse) -> Result<Self, WeatherError> {
+        let current = forecast.current.ok_or_else(|| {
+            WeatherError::InvalidResponse("provider returned no current conditions".to_string())
+        })?;
+        Ok(Self {
+            city: forecast.location.city,
+            country: forecast.location.country,
+            temperature: current.temperature,
+            feels_like: current.feels_like,
+            humidity: current.humidity,
+            pressure: current.pressure,
+            wind_speed: current.wind_speed,
+            description: current.description,
+            icon: current.icon,
+            visibility: current.visibility,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenWeatherMapResponse {
     name: String,
@@ -100,28 +173,43 @@ struct WindInfo {
     speed: f64,
 }
 
-#[derive(Debug, Deserialize)]
-struct OpenWeatherMapError {
-    message: String,
-}
-
 pub struct WeatherService {
     client: Client,
-    api_key: String,
+    api_keys: Arc<ApiKeyPool>,
     api_budget: Arc<ApiCallBudget>,
     weather_cache: TtlCache<String, WeatherResponse>,
+    locations: Option<Arc<LocationsService>>,
 }
 
 impl WeatherService {
-    pub fn new(client: Client, api_key: &str, api_budget: Arc<ApiCallBudget>) -> Self {
+    pub fn new(client: Client, api_keys: Arc<ApiKeyPool>, api_budget: Arc<ApiCallBudget>) -> Self {
         Self {
             client,
-            api_key: api_key.to_string(),
+            api_keys,
             api_budget,
-            weather_cache: TtlCache::new(Duration::from_secs(5 * 60)),
+            weather_cache: TtlCache::new("weather", Duration::from_secs(5 * 60)),
+            locations: None,
         }
     }
 
+    /// Resolve `loc:<id>` references to saved locations
+    pub fn with_locations(mut self, locations: Arc<LocationsService>) -> Self {
+        self.locations = Some(locations);
+        self
+    }
+
+    /// Keep current weather for `ttl` instead of the default 5 minutes, in at
+    /// most `max_entries` entries (0 for unbounded)
+    pub fn with_cache_limits(mut self, ttl: Duration, max_entries: usize) -> Self {
+        self.weather_cache = TtlCache::new("weather", ttl).with_max_entries(max_entries);
+        self
+    }
+
+    /// Counters for the current-weather cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.weather_cache.stats()
+    }
+
     /// Check if input looks like a zip code (digits only, or digits,country)
     fn is_zip_code(input: &str) -> bool {
         let parts: Vec<&str> = input.split(',').collect();
@@ -137,41 +225,86 @@ impl WeatherService {
         location: &str,
         units: &str,
     ) -> Result<WeatherResponse, WeatherError> {
-        let cache_key = format!("{}_{}", location.trim().to_lowercase(), units);
+        let cache_key = format!("{}_{}", normalize_cache_key(location), units);
         if let Some(cached) = self.weather_cache.get(&cache_key) {
+            record_cache_age(self.weather_cache.age(&cache_key));
             return Ok(cached);
         }
 
+        // A saved location is queried by its coordinates or city, and shown
+        // under its label; "geo:lat,lon" is queried by its coordinates
+        let saved = match self.locations {
+            Some(ref locations) => locations
+                .resolve(location)
+                .map_err(|_| WeatherError::CityNotFound(location.to_string()))?,
+            None => None,
+        };
+        let query = saved
+            .as_ref()
+            .map_or(location, |s| s.city.as_deref().unwrap_or_default());
+        let coordinates = saved
+            .as_ref()
+            .and_then(|s| s.lat.zip(s.lon))
+            .or_else(|| parse_geo_location(location));
+
+        if let Some(retry_after) = self.api_keys.circuit_open_for() {
+            if let Some(stale) = self.weather_cache.get_stale(&cache_key) {
+                record_cache_age(self.weather_cache.age(&cache_key));
+                tracing::warn!(location = %location, "OpenWeatherMap unavailable, serving stale weather");
+                return Ok(stale);
+            }
+            return Err(WeatherError::UpstreamUnavailable { retry_after });
+        }
+
+        if !self.api_budget.try_acquire(BudgetFeature::Weather) {
+            if let Some(stale) = self.weather_cache.get_stale(&cache_key) {
+                record_cache_age(self.weather_cache.age(&cache_key));
+                tracing::warn!(location = %location, "API budget exhausted, serving stale weather");
+                return Ok(stale);
+            }
+            return Err(WeatherError::BudgetExhausted {
+                retry_after: self.api_budget.seconds_until_reset(),
+            });
+        }
+
         tracing::debug!(location = %location, units = %units, "Fetching weather data");
-        self.api_budget.record_call();
 
-        // Build query based on whether input is zip code or city name
-        let response = if Self::is_zip_code(location) {
+        // Build query based on whether input is coordinates, zip code, or city name
+        let response = if let Some((lat, lon)) = coordinates {
+            self.api_keys
+                .send_budgeted(
+                    BudgetFeature::Weather,
+                    self.client.get(OPENWEATHERMAP_API_URL).query(&[
+                        ("lat", lat.to_string()),
+                        ("lon", lon.to_string()),
+                        ("units", units.to_string()),
+                    ]),
+                )
+                .await?
+        } else if Self::is_zip_code(query) {
             // For zip codes, default to US if no country specified
-            let zip_query = if location.contains(',') {
-                location.to_string()
+            let zip_query = if query.contains(',') {
+                query.to_string()
             } else {
-                format!("{},US", location)
+                format!("{},US", query)
             };
             tracing::debug!(zip = %zip_query, "Using zip code query");
-            self.client
-                .get(OPENWEATHERMAP_API_URL)
-                .query(&[
-                    ("zip", zip_query.as_str()),
-                    ("appid", self.api_key.as_str()),
-                    ("units", units),
-                ])
-                .send()
+            self.api_keys
+                .send_budgeted(
+                    BudgetFeature::Weather,
+                    self.client
+                        .get(OPENWEATHERMAP_API_URL)
+                        .query(&[("zip", zip_query.as_str()), ("units", units)]),
+                )
                 .await?
         } else {
-            self.client
-                .get(OPENWEATHERMAP_API_URL)
-                .query(&[
-                    ("q", location),
-                    ("appid", self.api_key.as_str()),
-                    ("units", units),
-                ])
-                .send()
+            self.api_keys
+                .send_budgeted(
+                    BudgetFeature::Weather,
+                    self.client
+                        .get(OPENWEATHERMAP_API_URL)
+                        .query(&[("q", query), ("units", units)]),
+                )
                 .await?
         };
 
@@ -183,10 +316,9 @@ impl WeatherService {
         }
 
         if !status.is_success() {
-            let error: OpenWeatherMapError = response.json().await.unwrap_or(OpenWeatherMapError {
-                message: format!("HTTP {}", status),
-            });
-            return Err(WeatherError::ApiError(error.message));
+            return Err(UpstreamFailure::read("Current weather", response)
+                .await
+                .into());
         }
 
         let data: OpenWeatherMapResponse = response.json().await?;
@@ -196,7 +328,7 @@ impl WeatherService {
         })?;
 
         let weather = WeatherResponse {
-            city: data.name,
+            city: saved.map_or(data.name, |s| s.label),
             country: data.sys.country,
             temperature: data.main.temp,
             feels_like: data.main.feels_like,
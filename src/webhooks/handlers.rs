@@ -0,0 +1,157 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+
+use super::models::{
+    CreateWebhookRequest, WebhookCreatedResponse, WebhookListResponse, WebhookTriggerResponse,
+};
+use super::WebhookError;
+use crate::audit::{self, AuditAction, AuditActor, AuditResource};
+use crate::auth::Principal;
+use crate::error::ErrorResponse;
+use crate::AppState;
+
+/// List webhooks. Users only see their own.
+/// GET /hooks
+#[utoipa::path(
+    get,
+    path = "/api/v1/hooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Webhooks the caller can manage", body = WebhookListResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<WebhookListResponse>, WebhookError> {
+    let principal = principal.map(|Extension(p)| p);
+    let webhooks = state.webhooks_service.list(principal.as_ref()).await?;
+    Ok(Json(WebhookListResponse {
+        count: webhooks.len(),
+        webhooks,
+    }))
+}
+
+/// Create a webhook for a saved job or an ad-hoc city forecast. The secret
+/// is only returned here.
+/// POST /hooks
+#[utoipa::path(
+    post,
+    path = "/api/v1/hooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook created", body = WebhookCreatedResponse),
+        (status = 400, description = "Invalid target or units", body = ErrorResponse),
+        (status = 404, description = "Job not found", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<WebhookCreatedResponse>), WebhookError> {
+    let principal = principal.map(|Extension(p)| p);
+    let (webhook, secret) = state
+        .webhooks_service
+        .create(request, principal.as_ref())
+        .await?;
+    state
+        .audit
+        .record(
+            &actor,
+            AuditAction::Create,
+            AuditResource::Webhook,
+            &webhook.id,
+            audit::snapshot(&webhook),
+        )
+        .await;
+    let url = format!("/api/v1/hooks/{}", webhook.id);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WebhookCreatedResponse {
+            webhook,
+            secret,
+            url,
+        }),
+    ))
+}
+
+/// Delete a webhook
+/// DELETE /hooks/{id}
+#[utoipa::path(
+    delete,
+    path = "/api/v1/hooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 404, description = "Webhook not found", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    actor: AuditActor,
+    Path(id): Path<String>,
+) -> Result<StatusCode, WebhookError> {
+    let principal = principal.map(|Extension(p)| p);
+    let webhook = state
+        .webhooks_service
+        .delete(&id, principal.as_ref())
+        .await?;
+    state
+        .audit
+        .record(
+            &actor,
+            AuditAction::Delete,
+            AuditResource::Webhook,
+            &id,
+            audit::snapshot(&webhook),
+        )
+        .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Fire a webhook. Authenticated by the hook's own secret, sent as the
+/// `X-Hook-Secret` header. It isn't accepted in the query string, where it
+/// would end up in access logs and proxy caches.
+/// POST /hooks/{id}
+#[utoipa::path(
+    post,
+    path = "/api/v1/hooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Webhook ID"),
+        ("X-Hook-Secret" = String, Header, description = "Hook secret")
+    ),
+    responses(
+        (status = 200, description = "Forecast fetched and notifications sent", body = WebhookTriggerResponse),
+        (status = 401, description = "Unknown webhook or invalid secret", body = ErrorResponse),
+        (status = 403, description = "Webhook disabled", body = ErrorResponse),
+        (status = 500, description = "Forecast or delivery failed", body = ErrorResponse)
+    )
+)]
+pub async fn trigger_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<WebhookTriggerResponse>, WebhookError> {
+    let secret = headers.get("x-hook-secret").and_then(|v| v.to_str().ok());
+
+    let (target, sent) = state.webhooks_service.trigger(&id, secret).await?;
+
+    Ok(Json(WebhookTriggerResponse {
+        status: "success".to_string(),
+        message: format!("Forecast triggered for {}", target),
+        notifications_sent: sent,
+    }))
+}
@@ -0,0 +1,6 @@
+pub mod handlers;
+pub mod models;
+mod service;
+
+pub use models::Webhook;
+pub use service::{WebhookError, WebhooksService};
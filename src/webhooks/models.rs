@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// An incoming webhook. Firing it runs a saved job, or sends an ad-hoc
+/// forecast push for a city.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: String,
+    pub name: String,
+    /// SHA-256 of the hook's secret; the secret itself is only shown once
+    #[serde(skip)]
+    pub secret_hash: String,
+    /// Saved job to run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// City for an ad-hoc forecast push
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    /// Units for an ad-hoc push; defaults to the server's units
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    pub enabled: bool,
+    /// Owning user (None = shared, admin-managed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_triggered: Option<i64>,
+}
+
+/// Request to create a webhook. Set exactly one of `jobId` or `city`.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+    pub name: String,
+    pub job_id: Option<String>,
+    pub city: Option<String>,
+    /// metric, imperial, or standard
+    pub units: Option<String>,
+}
+
+/// A new webhook and its secret, which can't be retrieved again
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookCreatedResponse {
+    pub webhook: Webhook,
+    /// Send as the `X-Hook-Secret` header when firing the hook
+    pub secret: String,
+    /// Path to POST to fire the hook
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookListResponse {
+    pub webhooks: Vec<Webhook>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookTriggerResponse {
+    pub status: String,
+    pub message: String,
+    pub notifications_sent: usize,
+}
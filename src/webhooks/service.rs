@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::models::{CreateWebhookRequest, Webhook};
+use crate::auth::Principal;
+use crate::db::{DbError, SqliteWebhookRepository, WebhookRepository};
+use crate::error::HttpError;
+use crate::impl_into_response;
+use crate::scheduler::SchedulerService;
+use crate::units::Units;
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("Webhook not found: {0}")]
+    NotFound(String),
+
+    /// Unknown hook or wrong secret; the two aren't distinguished
+    #[error("Unknown webhook or invalid secret")]
+    Unauthorized,
+
+    #[error("Webhook is disabled")]
+    Disabled,
+
+    #[error("Invalid webhook: {0}")]
+    Invalid(String),
+
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+
+    #[error("Trigger failed: {0}")]
+    TriggerFailed(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+}
+
+impl HttpError for WebhookError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) | Self::JobNotFound(_) => StatusCode::NOT_FOUND,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Disabled => StatusCode::FORBIDDEN,
+            Self::Invalid(_) => StatusCode::BAD_REQUEST,
+            Self::TriggerFailed(_) | Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::NotFound(_) => Some("WEBHOOK_NOT_FOUND"),
+            Self::Unauthorized => Some("INVALID_WEBHOOK_SECRET"),
+            Self::Disabled => Some("WEBHOOK_DISABLED"),
+            Self::Invalid(_) => Some("INVALID_WEBHOOK"),
+            Self::JobNotFound(_) => Some("JOB_NOT_FOUND"),
+            Self::TriggerFailed(_) => Some("TRIGGER_FAILED"),
+            Self::Database(_) => Some("DATABASE_ERROR"),
+        }
+    }
+}
+
+impl_into_response!(WebhookError);
+
+/// What firing a webhook does
+#[derive(Debug, PartialEq)]
+enum HookTarget {
+    Job(String),
+    City(String),
+}
+
+/// A hook targets exactly one of a saved job or a city
+fn parse_target(job_id: Option<String>, city: Option<String>) -> Result<HookTarget, WebhookError> {
+    let non_empty = |s: Option<String>| s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    match (non_empty(job_id), non_empty(city)) {
+        (Some(job_id), None) => Ok(HookTarget::Job(job_id)),
+        (None, Some(city)) => Ok(HookTarget::City(city)),
+        _ => Err(WebhookError::Invalid(
+            "set exactly one of jobId or city".to_string(),
+        )),
+    }
+}
+
+/// Check a hook's units up front, so a typo fails here and not on every firing
+fn parse_units(units: Option<String>) -> Result<Option<String>, WebhookError> {
+    units
+        .map(|u| {
+            u.parse::<Units>()
+                .map(|u| u.to_string())
+                .map_err(|e| WebhookError::Invalid(e.to_string()))
+        })
+        .transpose()
+}
+
+/// 256 random bits, hex-encoded
+fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn secret_matches(webhook: &Webhook, provided: &str) -> bool {
+    bool::from(
+        hash_secret(provided)
+            .as_bytes()
+            .ct_eq(webhook.secret_hash.as_bytes()),
+    )
+}
+
+/// Service for managing and firing incoming webhooks
+pub struct WebhooksService {
+    repo: SqliteWebhookRepository,
+    scheduler: Arc<SchedulerService>,
+    /// Units for ad-hoc pushes from hooks that don't set any
+    default_units: String,
+}
+
+impl WebhooksService {
+    pub fn new(pool: SqlitePool, scheduler: Arc<SchedulerService>, default_units: String) -> Self {
+        Self {
+            repo: SqliteWebhookRepository::new(pool),
+            scheduler,
+            default_units,
+        }
+    }
+
+    /// Create a webhook owned by the caller. Returns the hook and its secret,
+    /// which is only stored hashed.
+    pub async fn create(
+        &self,
+        request: CreateWebhookRequest,
+        principal: Option<&Principal>,
+    ) -> Result<(Webhook, String), WebhookError> {
+        let name = request.name.trim().to_string();
+        if name.is_empty() {
+            return Err(WebhookError::Invalid("name is required".to_string()));
+        }
+        let units = parse_units(request.units)?;
+
+        let (job_id, city) = match parse_target(request.job_id, request.city)? {
+            HookTarget::Job(job_id) => {
+                // Users can only hook up jobs they can see
+                self.scheduler
+                    .get_job(&job_id)
+                    .await
+                    .filter(|job| principal.is_none_or(|p| p.can_access(job.owner.as_deref())))
+                    .ok_or_else(|| WebhookError::JobNotFound(job_id.clone()))?;
+                (Some(job_id), None)
+            }
+            HookTarget::City(city) => (None, Some(city)),
+        };
+
+        let secret = generate_secret();
+        let webhook = Webhook {
+            id: Uuid::new_v4().to_string(),
+            name,
+            secret_hash: hash_secret(&secret),
+            job_id,
+            city,
+            units,
+            enabled: true,
+            owner: principal.and_then(|p| p.owner_filter()),
+            created_at: chrono::Utc::now().timestamp(),
+            last_triggered: None,
+        };
+        self.repo.upsert(&webhook).await?;
+        tracing::info!(webhook_id = %webhook.id, name = %webhook.name, "Created webhook");
+
+        Ok((webhook, secret))
+    }
+
+    /// Webhooks the caller can see
+    pub async fn list(&self, principal: Option<&Principal>) -> Result<Vec<Webhook>, WebhookError> {
+        let mut webhooks = self.repo.get_all().await?;
+        webhooks.retain(|w| principal.is_none_or(|p| p.can_access(w.owner.as_deref())));
+        Ok(webhooks)
+    }
+
+    /// Delete a webhook, returning it. Other users' hooks are reported as
+    /// not found.
+    pub async fn delete(
+        &self,
+        id: &str,
+        principal: Option<&Principal>,
+    ) -> Result<Webhook, WebhookError> {
+        let webhook = self
+            .repo
+            .get(id)
+            .await?
+            .filter(|w| principal.is_none_or(|p| p.can_access(w.owner.as_deref())))
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        if !self.repo.remove(id).await? {
+            return Err(WebhookError::NotFound(id.to_string()));
+        }
+        tracing::info!(webhook_id = %id, "Deleted webhook");
+        Ok(webhook)
+    }
+
+    /// Fire a webhook after checking its secret. Returns what ran and how
+    /// many notifications were sent.
+    pub async fn trigger(
+        &self,
+        id: &str,
+        secret: Option<&str>,
+    ) -> Result<(String, usize), WebhookError> {
+        let webhook = self
+            .repo
+            .get(id)
+            .await?
+            .filter(|w| secret.is_some_and(|s| secret_matches(w, s)))
+            .ok_or(WebhookError::Unauthorized)?;
+        if !webhook.enabled {
+            return Err(WebhookError::Disabled);
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = self.repo.set_last_triggered(id, now).await {
+            tracing::warn!(webhook_id = %id, error = %e, "Failed to record webhook trigger");
+        }
+        tracing::info!(webhook_id = %id, name = %webhook.name, "Webhook triggered");
+
+        match parse_target(webhook.job_id, webhook.city)? {
+            HookTarget::Job(job_id) => {
+                let job = self
+                    .scheduler
+                    .get_job(&job_id)
+                    .await
+                    .ok_or(WebhookError::JobNotFound(job_id))?;
+                let sent = self
+                    .scheduler
+                    .run_job_now(&job)
+                    .await
+                    .map_err(|e| WebhookError::TriggerFailed(e.to_string()))?;
+                Ok((job.name, sent))
+            }
+            HookTarget::City(city) => {
+                let units = webhook.units.as_deref().unwrap_or(&self.default_units);
+                let sent = self
+                    .scheduler
+                    .run_now(&city, units, webhook.owner.as_deref())
+                    .await
+                    .map_err(|e| WebhookError::TriggerFailed(e.to_string()))?;
+                Ok((city, sent))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_hashing_and_targets() {
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 64);
+        let webhook = Webhook {
+            id: "hook-1".to_string(),
+            name: "Doorbell".to_string(),
+            secret_hash: hash_secret(&secret),
+            job_id: None,
+            city: Some("Chicago".to_string()),
+            units: None,
+            enabled: true,
+            owner: None,
+            created_at: 0,
+            last_triggered: None,
+        };
+        assert!(secret_matches(&webhook, &secret));
+        assert!(!secret_matches(&webhook, "wrong"));
+        assert!(!serde_json::to_string(&webhook).unwrap().contains("secret"));
+
+        assert_eq!(
+            parse_target(None, Some(" Chicago ".to_string())).unwrap(),
+            HookTarget::City("Chicago".to_string())
+        );
+        assert_eq!(
+            parse_target(Some("job-1".to_string()), Some(String::new())).unwrap(),
+            HookTarget::Job("job-1".to_string())
+        );
+        assert!(parse_target(None, None).is_err());
+        assert!(parse_target(Some("job-1".to_string()), Some("Chicago".to_string())).is_err());
+
+        assert_eq!(
+            parse_units(Some("Imperial".to_string()))
+                .unwrap()
+                .as_deref(),
+            Some("imperial")
+        );
+        assert_eq!(parse_units(None).unwrap(), None);
+        assert!(matches!(
+            parse_units(Some("farenheit".to_string())),
+            Err(WebhookError::Invalid(_))
+        ));
+    }
+}
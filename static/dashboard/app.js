@@ -0,0 +1,130 @@
+// Weathrs dashboard: polls GET /api/v1/dashboard and renders it.
+// When JWT auth is enabled, signs in through POST /api/v1/auth/login and
+// keeps the token in localStorage.
+
+const REFRESH_MS = 60 * 1000;
+const TOKEN_KEY = "weathrs.token";
+
+const $ = (id) => document.getElementById(id);
+
+function el(tag, text, className) {
+  const node = document.createElement(tag);
+  if (text !== undefined) node.textContent = text;
+  if (className) node.className = className;
+  return node;
+}
+
+function time(unix) {
+  return new Date(unix * 1000).toLocaleString();
+}
+
+function row(cells) {
+  const tr = el("tr");
+  for (const cell of cells) tr.append(el("td", cell));
+  return tr;
+}
+
+function renderWeather(cities) {
+  const root = $("weather");
+  root.replaceChildren();
+  if (cities.length === 0) {
+    root.append(el("p", "No cities configured", "muted"));
+  }
+  for (const { city, weather, error } of cities) {
+    const card = el("div", undefined, "card");
+    card.append(el("strong", weather ? `${weather.city}, ${weather.country}` : city));
+    if (weather) {
+      card.append(el("div", `${Math.round(weather.temperature)}°`, "temp"));
+      card.append(el("div", weather.description));
+      card.append(el("div", `Humidity ${weather.humidity}% · Wind ${weather.wind_speed}`, "muted"));
+    } else {
+      card.append(el("div", error || "Unavailable", "error"));
+    }
+    root.append(card);
+  }
+}
+
+function renderRuns(runs) {
+  const table = $("runs");
+  table.replaceChildren(row(["Job", "City", "Next run"]));
+  if (runs.length === 0) table.append(row(["No scheduled jobs", "", ""]));
+  for (const run of runs) table.append(row([run.name, run.city, time(run.nextRun)]));
+}
+
+function renderNotifications(records) {
+  const table = $("notifications");
+  table.replaceChildren(row(["Sent", "Title", "Status", "Device"]));
+  if (records.length === 0) table.append(row(["Nothing sent yet", "", "", ""]));
+  for (const r of records) {
+    table.append(row([time(r.created_at), r.title, r.status, r.device_id]));
+  }
+}
+
+function renderBudget(budget) {
+  const root = $("budget");
+  const percent = budget.dailyLimit ? (budget.usedToday / budget.dailyLimit) * 100 : 0;
+  const bar = el("div", undefined, "bar");
+  const fill = el("span");
+  fill.style.width = `${Math.min(percent, 100)}%`;
+  bar.append(fill);
+  root.replaceChildren(
+    el("p", `${budget.usedToday} of ${budget.dailyLimit} calls used today (resets midnight ${budget.resetTimezone})`),
+    bar,
+  );
+  const table = el("table");
+  for (const f of budget.features) {
+    table.append(row([f.feature, f.limit ? `${f.usedToday} / ${f.limit}` : `${f.usedToday}`]));
+  }
+  root.append(table);
+}
+
+function showLogin(message) {
+  $("dashboard").hidden = true;
+  $("login").hidden = false;
+  $("login-error").textContent = message || "";
+}
+
+async function refresh() {
+  const token = localStorage.getItem(TOKEN_KEY);
+  const headers = token ? { Authorization: `Bearer ${token}` } : {};
+  const response = await fetch("/api/v1/dashboard", { headers });
+
+  if (response.status === 401 || response.status === 403) {
+    localStorage.removeItem(TOKEN_KEY);
+    showLogin(response.status === 403 ? "An admin account is required." : "");
+    return;
+  }
+  if (!response.ok) {
+    $("updated").textContent = `Refresh failed (${response.status})`;
+    return;
+  }
+
+  const data = await response.json();
+  $("login").hidden = true;
+  $("dashboard").hidden = false;
+  renderWeather(data.weather);
+  renderRuns(data.upcomingRuns);
+  renderNotifications(data.recentNotifications);
+  renderBudget(data.apiBudget);
+  $("updated").textContent = `Updated ${time(data.generatedAt)}`;
+}
+
+$("login").addEventListener("submit", async (event) => {
+  event.preventDefault();
+  const form = new FormData(event.target);
+  const response = await fetch("/api/v1/auth/login", {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ username: form.get("username"), password: form.get("password") }),
+  });
+  if (!response.ok) {
+    showLogin("Sign in failed.");
+    return;
+  }
+  const { access_token } = await response.json();
+  localStorage.setItem(TOKEN_KEY, access_token);
+  refresh();
+});
+
+refresh();
+setInterval(refresh, REFRESH_MS);